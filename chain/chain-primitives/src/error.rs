@@ -4,6 +4,7 @@ use near_primitives::errors::{EpochError, StorageError};
 use near_primitives::shard_layout::ShardLayoutError;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_primitives::validator_signer::RemoteSignerError;
 use near_time::Utc;
 use std::io;
 
@@ -414,6 +415,12 @@ impl<T> EpochErrorResultToChainError<T> for Result<T, EpochError> {
     }
 }
 
+impl From<RemoteSignerError> for Error {
+    fn from(error: RemoteSignerError) -> Self {
+        Error::ValidatorError(format!("failed to sign: {error}"))
+    }
+}
+
 impl From<ShardLayoutError> for Error {
     fn from(error: ShardLayoutError) -> Self {
         match error {
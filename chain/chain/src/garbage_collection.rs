@@ -1047,6 +1047,7 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::FlatStorageStatus
             | DBCol::Misc
             | DBCol::_ReceiptIdToShardId
+            | DBCol::StateHistory
             => unreachable!(),
         }
         self.merge(store_update);
@@ -29,6 +29,7 @@ mod state_request_tracker;
 pub mod state_snapshot_actor;
 pub mod stateless_validation;
 mod store;
+pub mod store_checker_actor;
 pub mod store_validator;
 pub mod test_utils;
 pub mod types;
@@ -1,7 +1,8 @@
 use near_o11y::metrics::{
     exponential_buckets, processing_time_buckets, try_create_histogram, try_create_histogram_vec,
-    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_gauge,
-    try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_counter_vec,
+    try_create_int_gauge, try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec,
 };
 use std::sync::LazyLock;
 
@@ -239,3 +240,28 @@ pub(crate) static RESHARDING_STATUS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     )
     .unwrap()
 });
+
+pub(crate) static STORE_CHECKER_CHECKS_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_store_checker_checks_total",
+        "Number of store invariant checks done by the store checker",
+    )
+    .unwrap()
+});
+
+pub(crate) static STORE_CHECKER_FINDINGS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_store_checker_findings_total",
+        "Number of store invariant checks which failed, by the checked column",
+        &["column"],
+    )
+    .unwrap()
+});
+
+pub(crate) static STORE_CHECKER_LAST_CHECKED_HEIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    try_create_int_gauge(
+        "near_store_checker_last_checked_height",
+        "Height of the block most recently checked by the store checker",
+    )
+    .unwrap()
+});
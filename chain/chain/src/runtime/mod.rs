@@ -1,7 +1,8 @@
 use crate::types::{
     ApplyChunkBlockContext, ApplyChunkResult, ApplyChunkShardContext, ApplyResultForResharding,
-    PrepareTransactionsBlockContext, PrepareTransactionsChunkContext, PrepareTransactionsLimit,
-    PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig, StorageDataSource, Tip,
+    Block, PrepareTransactionsBlockContext, PrepareTransactionsChunkContext,
+    PrepareTransactionsLimit, PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig,
+    SimulateTransactionResult, StorageDataSource, Tip,
 };
 use crate::Error;
 use borsh::BorshDeserialize;
@@ -30,6 +31,7 @@ use near_primitives::shard_layout::{
 use near_primitives::state_part::PartId;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::trie_key::TrieKey;
+use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
     ShardId, StateChangeCause, StateChangesForResharding, StateRoot, StateRootNode,
@@ -42,6 +44,7 @@ use near_primitives::views::{
 use near_store::config::StateSnapshotType;
 use near_store::flat::FlatStorageManager;
 use near_store::metadata::DbKind;
+use near_store::state_history::StateHistory;
 use near_store::{
     ApplyStatePartResult, DBCol, ShardTries, StateSnapshotConfig, Store, Trie, TrieConfig,
     TrieUpdate, WrappedTrieChanges, COLD_HEAD_KEY,
@@ -62,6 +65,8 @@ use tracing::{debug, error, info, instrument};
 pub mod errors;
 mod metrics;
 pub mod migrations;
+mod simulation;
+mod state_history;
 #[cfg(test)]
 mod tests;
 
@@ -79,6 +84,8 @@ pub struct NightshadeRuntime {
     epoch_manager: Arc<EpochManagerHandle>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    /// Index used to answer queries about old blocks, if enabled.
+    state_history: Option<StateHistory>,
 }
 
 impl NightshadeRuntime {
@@ -93,6 +100,7 @@ impl NightshadeRuntime {
         gc_num_epochs_to_keep: u64,
         trie_config: TrieConfig,
         state_snapshot_config: StateSnapshotConfig,
+        enable_state_history: bool,
     ) -> Arc<Self> {
         let runtime_config_store = match runtime_config_store {
             Some(store) => store,
@@ -120,6 +128,7 @@ impl NightshadeRuntime {
         }
 
         let migration_data = Arc::new(migrations::load_migration_data(&genesis_config.chain_id));
+        let state_history = enable_state_history.then(|| StateHistory::new(store.clone()));
         Arc::new(NightshadeRuntime {
             genesis_config: genesis_config.clone(),
             compiled_contract_cache,
@@ -131,6 +140,7 @@ impl NightshadeRuntime {
             epoch_manager,
             migration_data,
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            state_history,
         })
     }
 
@@ -159,6 +169,7 @@ impl NightshadeRuntime {
                 hot_store_path: PathBuf::from("data"),
                 state_snapshot_subdir: PathBuf::from("state_snapshot"),
             },
+            false,
         )
     }
 
@@ -188,6 +199,7 @@ impl NightshadeRuntime {
                 hot_store_path: PathBuf::from("data"),
                 state_snapshot_subdir: PathBuf::from("state_snapshot"),
             },
+            false,
         )
     }
 
@@ -1025,6 +1037,15 @@ impl RuntimeAdapter for NightshadeRuntime {
         }
     }
 
+    fn simulate_transaction(
+        &self,
+        block: &Block,
+        chunk_extras: HashMap<ShardId, Arc<ChunkExtra>>,
+        transaction: &SignedTransaction,
+    ) -> Result<SimulateTransactionResult, Error> {
+        self.simulate_transaction_impl(block, chunk_extras, transaction)
+    }
+
     fn query(
         &self,
         shard_uid: ShardUId,
@@ -1036,6 +1057,9 @@ impl RuntimeAdapter for NightshadeRuntime {
         epoch_id: &EpochId,
         request: &QueryRequest,
     ) -> Result<QueryResponse, crate::near_chain_primitives::error::QueryError> {
+        if let Some(response) = self.query_state_history(block_height, block_hash, request)? {
+            return Ok(response);
+        }
         match request {
             QueryRequest::ViewAccount { account_id } => {
                 let account =
@@ -1133,6 +1157,36 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewStatePaged {
+                account_id,
+                prefix,
+                continuation_token,
+                limit,
+                include_proof,
+            } => {
+                let view_state_result = self
+                    .view_state_paged(
+                        &shard_uid,
+                        *state_root,
+                        account_id,
+                        prefix.as_ref(),
+                        continuation_token.as_ref().map(AsRef::as_ref),
+                        *limit,
+                        *include_proof,
+                    )
+                    .map_err(|err| {
+                        crate::near_chain_primitives::error::QueryError::from_view_state_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewState(view_state_result),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::ViewAccessKeyList { account_id } => {
                 let access_key_list =
                     self.view_access_keys(&shard_uid, *state_root, account_id).map_err(|err| {
@@ -1586,4 +1640,25 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
         self.trie_viewer.view_state(&state_update, account_id, prefix, include_proof)
     }
+
+    fn view_state_paged(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        continuation_token: Option<&[u8]>,
+        limit: Option<u32>,
+        include_proof: bool,
+    ) -> Result<ViewStateResult, node_runtime::state_viewer::errors::ViewStateError> {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        self.trie_viewer.view_state_paged(
+            &state_update,
+            account_id,
+            prefix,
+            continuation_token,
+            limit,
+            include_proof,
+        )
+    }
 }
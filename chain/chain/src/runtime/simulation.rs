@@ -0,0 +1,260 @@
+//! Execution of a transaction on top of the state of a block without
+//! persisting anything, used by the `EXPERIMENTAL_simulate_transaction` RPC.
+//!
+//! The transaction is applied to the shard of its signer as if it was included
+//! in the chunk following the block.  Receipts it produces are then applied in
+//! rounds, each round playing the role of the next chunk of the receiving
+//! shards, until no receipts are left.  Only the receipts of the simulated
+//! transaction are executed; delayed receipts and yield timeouts already in
+//! the state are ignored.
+use super::NightshadeRuntime;
+use crate::types::SimulateTransactionResult;
+use crate::Error;
+use near_primitives::apply::ApplyChunkReason;
+use near_primitives::block::Block;
+use near_primitives::congestion_info::{BlockCongestionInfo, ExtendedCongestionInfo};
+use near_primitives::errors::{RuntimeError, StorageError};
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::receipt::{Receipt, ReceiptEnum};
+use near_primitives::runtime::migration_data::MigrationFlags;
+use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardUId};
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{ShardId, StateChangeCause, StateRoot};
+use near_store::{Trie, TrieDBStorage, TrieStorage};
+use node_runtime::ApplyState;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+/// Maximum number of rounds of receipts executed by a simulation.  Receipts
+/// still pending after that are reported as unfinished.
+const MAX_SIMULATION_ROUNDS: u64 = 64;
+
+/// Trie storage which serves the nodes written by the previous rounds of the
+/// simulation from memory and everything else from the database.
+struct SimulationTrieStorage {
+    db_storage: TrieDBStorage,
+    insertions: RwLock<HashMap<CryptoHash, Arc<[u8]>>>,
+}
+
+impl TrieStorage for SimulationTrieStorage {
+    fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
+        if let Some(bytes) = self.insertions.read().expect("not poisoned").get(hash) {
+            return Ok(bytes.clone());
+        }
+        self.db_storage.retrieve_raw_bytes(hash)
+    }
+}
+
+/// State of a shard touched by the simulation.
+struct SimulatedShard {
+    state_root: StateRoot,
+    congestion_info: BlockCongestionInfo,
+    storage: Arc<SimulationTrieStorage>,
+}
+
+impl NightshadeRuntime {
+    pub(super) fn simulate_transaction_impl(
+        &self,
+        block: &Block,
+        chunk_extras: HashMap<ShardId, Arc<ChunkExtra>>,
+        transaction: &SignedTransaction,
+    ) -> Result<SimulateTransactionResult, Error> {
+        let header = block.header();
+        let prev_block_hash = *header.hash();
+        let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(&prev_block_hash)?;
+        let epoch_height = self.epoch_manager.get_epoch_height_from_prev_block(&prev_block_hash)?;
+        let shard_layout = self.epoch_manager.get_shard_layout(&epoch_id)?;
+        let current_protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
+        let config = self.runtime_config_store.get_config(current_protocol_version).clone();
+
+        let mut shards: HashMap<ShardId, SimulatedShard> = chunk_extras
+            .into_iter()
+            .map(|(shard_id, chunk_extra)| {
+                let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+                // Only the congestion of the own shard is known, receipts to other
+                // shards are never buffered.
+                let congestion_info = chunk_extra
+                    .congestion_info()
+                    .map(|congestion_info| {
+                        BlockCongestionInfo::new(
+                            [(shard_id, ExtendedCongestionInfo::new(congestion_info, 0))].into(),
+                        )
+                    })
+                    .unwrap_or_default();
+                let storage = Arc::new(SimulationTrieStorage {
+                    db_storage: TrieDBStorage::new(self.store.clone(), shard_uid),
+                    insertions: Default::default(),
+                });
+                let shard = SimulatedShard {
+                    state_root: *chunk_extra.state_root(),
+                    congestion_info,
+                    storage,
+                };
+                (shard_id, shard)
+            })
+            .collect();
+
+        let mut result = SimulateTransactionResult::default();
+        // Transaction and receipts whose outcomes and state changes belong to
+        // the simulation.
+        let mut known_ids = HashSet::from([transaction.get_hash()]);
+        // Data produced by the receipts of the simulation.
+        let mut known_data_ids = HashSet::new();
+        let mut pending_receipts: Vec<Receipt> = vec![];
+        let signer_shard_id =
+            account_id_to_shard_id(transaction.transaction.signer_id(), &shard_layout);
+
+        for round in 0..MAX_SIMULATION_ROUNDS {
+            let mut receipts_by_shard: HashMap<ShardId, Vec<Receipt>> = HashMap::new();
+            for receipt in pending_receipts.drain(..) {
+                let shard_id = account_id_to_shard_id(receipt.receiver_id(), &shard_layout);
+                receipts_by_shard.entry(shard_id).or_default().push(receipt);
+            }
+            if round == 0 {
+                receipts_by_shard.insert(signer_shard_id, vec![]);
+            }
+            if receipts_by_shard.is_empty() {
+                break;
+            }
+
+            let block_hash = hash(&borsh::to_vec(&(prev_block_hash, round)).unwrap());
+            let mut shard_ids: Vec<_> = receipts_by_shard.keys().copied().collect();
+            shard_ids.sort();
+            for shard_id in shard_ids {
+                let receipts = receipts_by_shard.remove(&shard_id).unwrap();
+                let transactions = if round == 0 && shard_id == signer_shard_id {
+                    std::slice::from_ref(transaction)
+                } else {
+                    &[]
+                };
+                // Receipts for shards the node doesn't track stay unfinished.
+                let Some(shard) = shards.get_mut(&shard_id) else {
+                    continue;
+                };
+                let apply_state = ApplyState {
+                    apply_reason: Some(ApplyChunkReason::SimulateTransaction),
+                    block_height: header.height() + 1 + round,
+                    prev_block_hash,
+                    block_hash,
+                    shard_id,
+                    epoch_id,
+                    epoch_height,
+                    gas_price: header.next_gas_price(),
+                    block_timestamp: header.raw_timestamp(),
+                    gas_limit: None,
+                    random_seed: *header.random_value(),
+                    current_protocol_version,
+                    config: config.clone(),
+                    cache: Some(self.compiled_contract_cache.handle()),
+                    is_new_chunk: true,
+                    migration_data: Arc::clone(&self.migration_data),
+                    migration_flags: MigrationFlags::default(),
+                    congestion_info: shard.congestion_info.clone(),
+                };
+                let mut trie = Trie::new(shard.storage.clone(), shard.state_root, None);
+                trie.dont_charge_gas_for_trie_node_access();
+                let apply_result = self
+                    .runtime
+                    .apply(
+                        trie,
+                        &None,
+                        &apply_state,
+                        &receipts,
+                        transactions,
+                        self.epoch_manager.as_ref(),
+                        SandboxStatePatch::default(),
+                    )
+                    .map_err(|err| match err {
+                        RuntimeError::InvalidTxError(err) => {
+                            tracing::debug!(target: "runtime", ?err, "invalid simulated transaction");
+                            Error::InvalidTransactions
+                        }
+                        RuntimeError::StorageError(err) => Error::StorageError(err),
+                        RuntimeError::ValidatorError(err) => err.into(),
+                        err => Error::Other(format!("simulation failed: {err}")),
+                    })?;
+
+                let mut insertions = shard.storage.insertions.write().expect("not poisoned");
+                for insertion in apply_result.trie_changes.insertions() {
+                    insertions.insert(*insertion.hash(), insertion.payload().into());
+                }
+                drop(insertions);
+                shard.state_root = apply_result.state_root;
+                if let Some(congestion_info) = apply_result.congestion_info {
+                    shard.congestion_info = BlockCongestionInfo::new(
+                        [(shard_id, ExtendedCongestionInfo::new(congestion_info, 0))].into(),
+                    );
+                }
+
+                for outcome in apply_result.outcomes {
+                    if known_ids.contains(&outcome.id) {
+                        known_ids.extend(outcome.outcome.receipt_ids.iter().copied());
+                        result.outcomes.push(outcome);
+                    }
+                }
+                for receipt in apply_result.outgoing_receipts {
+                    let is_known = match receipt.receipt() {
+                        ReceiptEnum::Action(action_receipt)
+                        | ReceiptEnum::PromiseYield(action_receipt) => {
+                            if !known_ids.contains(receipt.receipt_id()) {
+                                false
+                            } else {
+                                known_data_ids.extend(
+                                    action_receipt
+                                        .output_data_receivers
+                                        .iter()
+                                        .map(|receiver| receiver.data_id),
+                                );
+                                true
+                            }
+                        }
+                        ReceiptEnum::Data(data_receipt)
+                        | ReceiptEnum::PromiseResume(data_receipt) => {
+                            known_data_ids.contains(&data_receipt.data_id)
+                        }
+                    };
+                    if is_known {
+                        known_ids.insert(*receipt.receipt_id());
+                        pending_receipts.push(receipt);
+                    }
+                }
+                for mut state_changes in apply_result.state_changes {
+                    state_changes.changes.retain(|change| {
+                        state_change_cause_id(&change.cause)
+                            .is_some_and(|id| known_ids.contains(id))
+                    });
+                    if !state_changes.changes.is_empty() {
+                        result.state_changes.push(state_changes);
+                    }
+                }
+            }
+        }
+
+        // Receipts which never got executed: they are stuck waiting for data,
+        // belong to shards the node doesn't track or ran out of rounds.
+        let mut seen: HashSet<_> = result.outcomes.iter().map(|outcome| outcome.id).collect();
+        result.unfinished_receipt_ids = result
+            .outcomes
+            .iter()
+            .flat_map(|outcome| outcome.outcome.receipt_ids.iter())
+            .chain(pending_receipts.iter().map(|receipt| receipt.receipt_id()))
+            .filter(|id| seen.insert(**id))
+            .copied()
+            .collect();
+        Ok(result)
+    }
+}
+
+/// Returns the transaction or receipt that caused the state change.
+fn state_change_cause_id(cause: &StateChangeCause) -> Option<&CryptoHash> {
+    match cause {
+        StateChangeCause::TransactionProcessing { tx_hash } => Some(tx_hash),
+        StateChangeCause::ActionReceiptProcessingStarted { receipt_hash }
+        | StateChangeCause::ActionReceiptGasReward { receipt_hash }
+        | StateChangeCause::ReceiptProcessing { receipt_hash }
+        | StateChangeCause::PostponedReceipt { receipt_hash } => Some(receipt_hash),
+        _ => None,
+    }
+}
@@ -0,0 +1,185 @@
+//! Queries about old blocks answered from the state history index of archival
+//! nodes (see `near_store::state_history`) rather than from the trie.
+use super::NightshadeRuntime;
+use crate::near_chain_primitives::error::QueryError;
+use borsh::BorshDeserialize;
+use near_crypto::PublicKey;
+use near_primitives::account::{AccessKey, Account};
+use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::views::{
+    AccessKeyInfoView, ContractCodeView, QueryRequest, QueryResponse, QueryResponseKind, StateItem,
+    ViewStateResult,
+};
+use near_store::state_history::StateHistory;
+use near_vm_runner::ContractCode;
+
+/// Reads from the index at the height of the queried block.
+struct HistoryReader<'a> {
+    history: &'a StateHistory,
+    block_height: BlockHeight,
+    block_hash: &'a CryptoHash,
+}
+
+impl HistoryReader<'_> {
+    fn internal_error(&self, error_message: String) -> QueryError {
+        QueryError::InternalError {
+            error_message,
+            block_height: self.block_height,
+            block_hash: *self.block_hash,
+        }
+    }
+
+    fn get(&self, trie_key: &[u8]) -> Result<Option<Vec<u8>>, QueryError> {
+        self.history
+            .get(trie_key, self.block_height)
+            .map_err(|err| self.internal_error(err.to_string()))
+    }
+
+    fn get_ser<T: BorshDeserialize>(&self, trie_key: TrieKey) -> Result<Option<T>, QueryError> {
+        let Some(value) = self.get(&trie_key.to_vec())? else {
+            return Ok(None);
+        };
+        T::try_from_slice(&value).map(Some).map_err(|err| {
+            self.internal_error(format!("failed to deserialize value of {trie_key:?}: {err}"))
+        })
+    }
+
+    fn iter_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, QueryError> {
+        self.history
+            .iter_prefix(prefix, self.block_height)
+            .map_err(|err| self.internal_error(err.to_string()))
+    }
+
+    fn account(&self, account_id: &AccountId) -> Result<Account, QueryError> {
+        self.get_ser(TrieKey::Account { account_id: account_id.clone() })?.ok_or_else(|| {
+            QueryError::UnknownAccount {
+                requested_account_id: account_id.clone(),
+                block_height: self.block_height,
+                block_hash: *self.block_hash,
+            }
+        })
+    }
+
+    fn code(&self, account_id: &AccountId) -> Result<Option<Vec<u8>>, QueryError> {
+        self.get(&TrieKey::ContractCode { account_id: account_id.clone() }.to_vec())
+    }
+}
+
+impl NightshadeRuntime {
+    /// Answers the query from the state history index if the index is enabled
+    /// and covers the queried block.  Returns `None` if the query has to be
+    /// answered from the trie instead, which is always the case for function
+    /// calls, state proofs and paginated state views.
+    pub(super) fn query_state_history(
+        &self,
+        block_height: BlockHeight,
+        block_hash: &CryptoHash,
+        request: &QueryRequest,
+    ) -> Result<Option<QueryResponse>, QueryError> {
+        match request {
+            QueryRequest::CallFunction { .. }
+            | QueryRequest::ViewState { include_proof: true, .. }
+            | QueryRequest::ViewStatePaged { .. } => return Ok(None),
+            _ => {}
+        }
+        let Some(history) = &self.state_history else {
+            return Ok(None);
+        };
+        let reader = HistoryReader { history, block_height, block_hash };
+        let covered = reader
+            .history
+            .covers(block_height)
+            .map_err(|err| reader.internal_error(err.to_string()))?;
+        if !covered {
+            return Ok(None);
+        }
+
+        let kind = match request {
+            QueryRequest::ViewAccount { account_id } => {
+                QueryResponseKind::ViewAccount(reader.account(account_id)?.into())
+            }
+            QueryRequest::ViewCode { account_id } => {
+                let account = reader.account(account_id)?;
+                let code = reader.code(account_id)?.ok_or_else(|| QueryError::NoContractCode {
+                    contract_account_id: account_id.clone(),
+                    block_height,
+                    block_hash: *block_hash,
+                })?;
+                let contract_code = ContractCode::new(code, Some(account.code_hash()));
+                QueryResponseKind::ViewCode(ContractCodeView {
+                    hash: *contract_code.hash(),
+                    code: contract_code.into_code(),
+                })
+            }
+            QueryRequest::ViewAccessKey { account_id, public_key } => {
+                let access_key = reader
+                    .get_ser::<AccessKey>(TrieKey::AccessKey {
+                        account_id: account_id.clone(),
+                        public_key: public_key.clone(),
+                    })?
+                    .ok_or_else(|| QueryError::UnknownAccessKey {
+                        public_key: public_key.clone(),
+                        block_height,
+                        block_hash: *block_hash,
+                    })?;
+                QueryResponseKind::AccessKey(access_key.into())
+            }
+            QueryRequest::ViewAccessKeyList { account_id } => {
+                let prefix = trie_key_parsers::get_raw_prefix_for_access_keys(account_id);
+                let keys = reader
+                    .iter_prefix(&prefix)?
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let public_key = PublicKey::try_from_slice(&key[prefix.len()..]);
+                        let access_key = AccessKey::try_from_slice(&value);
+                        match (public_key, access_key) {
+                            (Ok(public_key), Ok(access_key)) => {
+                                Ok(AccessKeyInfoView { public_key, access_key: access_key.into() })
+                            }
+                            _ => Err(reader.internal_error(format!(
+                                "Unexpected invalid access key {:?} received from store",
+                                key
+                            ))),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                QueryResponseKind::AccessKeyList(keys)
+            }
+            QueryRequest::ViewState { account_id, prefix, include_proof: _ } => {
+                let account = reader.account(account_id)?;
+                if let Some(limit) = self.trie_viewer.state_size_limit() {
+                    let code_len =
+                        reader.code(account_id)?.map(|code| code.len() as u64).unwrap_or_default();
+                    if account.storage_usage().saturating_sub(code_len) > limit {
+                        return Err(QueryError::TooLargeContractState {
+                            requested_account_id: account_id.clone(),
+                            block_height,
+                            block_hash: *block_hash,
+                        });
+                    }
+                }
+                let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+                let acc_sep_len = query.len() - prefix.len();
+                let values = reader
+                    .iter_prefix(&query)?
+                    .into_iter()
+                    .map(|(key, value)| StateItem {
+                        key: key[acc_sep_len..].to_vec().into(),
+                        value: value.into(),
+                    })
+                    .collect();
+                QueryResponseKind::ViewState(ViewStateResult {
+                    values,
+                    proof: vec![],
+                    continuation_token: None,
+                })
+            }
+            QueryRequest::CallFunction { .. } | QueryRequest::ViewStatePaged { .. } => {
+                unreachable!()
+            }
+        };
+        Ok(Some(QueryResponse { kind, block_height, block_hash: *block_hash }))
+    }
+}
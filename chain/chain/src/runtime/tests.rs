@@ -137,6 +137,7 @@ impl TestEnv {
                 hot_store_path: PathBuf::from("data"),
                 state_snapshot_subdir: PathBuf::from("state_snapshot"),
             },
+            false,
         );
         let state_roots = get_genesis_state_roots(&store).unwrap().unwrap();
         let genesis_hash = hash(&[0]);
@@ -1587,7 +1588,7 @@ fn generate_transaction_pool(
     }
     transactions.shuffle(&mut rng);
 
-    let mut pool = TransactionPool::new(TEST_SEED, None, "");
+    let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
     for transaction in transactions {
         assert_eq!(pool.insert_transaction(transaction), InsertTransactionResult::Success);
     }
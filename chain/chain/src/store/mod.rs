@@ -937,6 +937,24 @@ impl ChainStore {
         Ok(StateChanges::from_changes(&mut block_changes)?)
     }
 
+    /// Same as `get_state_changes_with_cause_in_block`, but only the changes
+    /// whose trie key passes `filter` are decoded.
+    pub fn get_state_changes_with_cause_in_block_filtered(
+        &self,
+        block_hash: &CryptoHash,
+        filter: impl Fn(&TrieKey) -> bool,
+    ) -> Result<StateChanges, Error> {
+        let storage_key = KeyForStateChanges::for_block(block_hash);
+
+        let block_changes =
+            storage_key.find_iter(&self.store).filter(|raw_change| match raw_change {
+                Ok(raw_change) => filter(&raw_change.trie_key),
+                Err(_) => true,
+            });
+
+        Ok(StateChanges::from_changes(block_changes)?)
+    }
+
     /// Retrieve the key-value changes from the store and decode them appropriately.
     ///
     /// We store different types of data, so we need to take care of all the types. That is, the
@@ -0,0 +1,171 @@
+use crate::metrics;
+use crate::store_validator::StoreValidator;
+use crate::types::RuntimeAdapter;
+use crate::{ChainStore, ChainStoreAccess};
+use near_async::futures::{DelayedActionRunner, DelayedActionRunnerExt};
+use near_async::messaging::{Actor, Handler};
+use near_async::time::Clock;
+use near_chain_configs::{GenesisConfig, StoreCheckerConfig};
+use near_client_primitives::debug::{
+    GetStoreCheckerStatus, StoreCheckerFindingView, StoreCheckerStatusView,
+};
+use near_epoch_manager::shard_tracker::ShardTracker;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::types::{AccountId, BlockHeight, BlockHeightDelta};
+use near_store::Store;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Number of blocks above the tail which aren't checked, so that garbage
+/// collection doesn't remove a block while it's being checked.
+const TAIL_SAFETY_MARGIN: BlockHeightDelta = 100;
+
+/// An actor which continuously checks consistency of the store on a live
+/// node, so that corruption is noticed before the node crashes on it.
+///
+/// Every `check_period` it checks the invariants the [`StoreValidator`]
+/// checks offline for a random block between the tail and the head, and
+/// compares random flat storage keys with the trie.  Failed checks are
+/// logged, counted in metrics and shown on the `/debug/api/store_checker`
+/// page.
+///
+/// The actor is only started if the store checker is enabled in the config.
+pub struct StoreCheckerActor {
+    clock: Clock,
+    chain_store: ChainStore,
+    validator: StoreValidator,
+    config: StoreCheckerConfig,
+    rng: StdRng,
+    /// Number of checks done by `validator` which have been already counted.
+    checks_counted: u64,
+    findings_total: u64,
+    last_checked_height: Option<BlockHeight>,
+    /// The most recent findings, the latest first.
+    recent_findings: VecDeque<StoreCheckerFindingView>,
+}
+
+impl StoreCheckerActor {
+    pub fn new(
+        clock: Clock,
+        me: Option<AccountId>,
+        genesis_config: GenesisConfig,
+        epoch_manager: Arc<dyn EpochManagerAdapter>,
+        shard_tracker: ShardTracker,
+        runtime: Arc<dyn RuntimeAdapter>,
+        store: Store,
+        is_archival: bool,
+        config: StoreCheckerConfig,
+    ) -> Self {
+        let chain_store = ChainStore::new(store.clone(), genesis_config.genesis_height, true);
+        let validator = StoreValidator::new(
+            me,
+            genesis_config,
+            epoch_manager,
+            shard_tracker,
+            runtime,
+            store,
+            is_archival,
+        );
+        Self {
+            clock,
+            chain_store,
+            validator,
+            config,
+            rng: StdRng::from_entropy(),
+            checks_counted: 0,
+            findings_total: 0,
+            last_checked_height: None,
+            recent_findings: VecDeque::new(),
+        }
+    }
+
+    /// Returns a random height at least `TAIL_SAFETY_MARGIN` blocks above the
+    /// tail and not above the head.
+    fn sample_height(&mut self) -> Result<BlockHeight, crate::Error> {
+        let head = self.chain_store.head()?.height;
+        // The block at the tail itself may be already garbage collected.
+        let lowest = self.chain_store.tail()?.saturating_add(1 + TAIL_SAFETY_MARGIN).min(head);
+        Ok(self.rng.gen_range(lowest..=head))
+    }
+
+    /// Runs a single round of checks and records their findings.
+    pub fn check_once(&mut self) {
+        match self.sample_height() {
+            Ok(height) => {
+                self.validator.validate_height(height);
+                // Garbage collection may still have caught up with the block, e.g.
+                // if the node was catching up, in which case data missing from
+                // the store is expected.
+                match self.chain_store.tail() {
+                    Ok(tail) if tail < height => {}
+                    tail => {
+                        tracing::debug!(target: "store_checker", height, ?tail, "Checked block was garbage collected, dropping the findings");
+                        self.validator.errors.clear();
+                    }
+                }
+                self.record_findings(Some(height));
+                self.last_checked_height = Some(height);
+                metrics::STORE_CHECKER_LAST_CHECKED_HEIGHT.set(height as i64);
+            }
+            Err(err) => {
+                tracing::debug!(target: "store_checker", ?err, "Can't sample a height to check");
+            }
+        }
+        self.validator.validate_flat_storage(&mut self.rng, self.config.flat_storage_samples);
+        self.record_findings(None);
+    }
+
+    fn record_findings(&mut self, height: Option<BlockHeight>) {
+        let checks_done = self.validator.tests_done();
+        metrics::STORE_CHECKER_CHECKS_TOTAL.inc_by(checks_done - self.checks_counted);
+        self.checks_counted = checks_done;
+
+        for finding in std::mem::take(&mut self.validator.errors) {
+            tracing::error!(
+                target: "store_checker",
+                ?height,
+                column = %finding.col,
+                key = %finding.key,
+                err = %finding.err,
+                "Store inconsistency found",
+            );
+            metrics::STORE_CHECKER_FINDINGS_TOTAL.with_label_values(&[&finding.col]).inc();
+            self.findings_total += 1;
+            self.recent_findings.push_front(StoreCheckerFindingView {
+                height,
+                found_at: self.clock.now_utc(),
+                column: finding.col,
+                key: finding.key,
+                error: finding.err.to_string(),
+            });
+            self.recent_findings.truncate(self.config.max_recent_findings);
+        }
+    }
+
+    fn check(&mut self, ctx: &mut dyn DelayedActionRunner<Self>) {
+        self.check_once();
+        ctx.run_later("store checker", self.config.check_period, move |act, ctx| {
+            act.check(ctx);
+        });
+    }
+}
+
+impl Actor for StoreCheckerActor {
+    fn start_actor(&mut self, ctx: &mut dyn DelayedActionRunner<Self>) {
+        self.check(ctx);
+    }
+}
+
+impl Handler<GetStoreCheckerStatus> for StoreCheckerActor {
+    fn handle(&mut self, _msg: GetStoreCheckerStatus) -> StoreCheckerStatusView {
+        StoreCheckerStatusView {
+            enabled: self.config.enabled,
+            checks_done: self.checks_counted,
+            findings_total: self.findings_total,
+            last_checked_height: self.last_checked_height,
+            recent_findings: self.recent_findings.iter().cloned().collect(),
+        }
+    }
+}
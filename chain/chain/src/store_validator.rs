@@ -11,15 +11,21 @@ use near_primitives::epoch_block_info::BlockInfo;
 use near_primitives::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::AGGREGATOR_KEY;
 use near_primitives::hash::CryptoHash;
-use near_primitives::shard_layout::get_block_shard_uid_rev;
+use near_primitives::shard_layout::{get_block_shard_uid, get_block_shard_uid_rev, ShardUId};
 use near_primitives::sharding::{ChunkHash, ShardChunk, StateSyncInfo};
 use near_primitives::state_sync::{ShardStateSyncResponseHeader, StateHeaderKey, StatePartKey};
 use near_primitives::transaction::ExecutionOutcomeWithProof;
+use near_primitives::trie_key;
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{AccountId, BlockHeight, EpochId};
-use near_primitives::utils::{get_block_shard_id_rev, get_outcome_id_block_hash_rev};
+use near_primitives::utils::{
+    get_block_shard_id_rev, get_outcome_id_block_hash_rev, index_to_bytes,
+};
 use near_store::db::refcount;
+use near_store::flat::{store_helper as flat_store_helper, FlatStorageStatus};
 use near_store::{DBCol, Store, TrieChanges};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use strum::IntoEnumIterator;
@@ -365,6 +371,126 @@ impl StoreValidator {
         }
     }
 
+    /// Checks the Block on the Canonical Chain at `height`, together with its
+    /// Header, indices and ChunkExtras, without scanning whole columns so
+    /// that it can run on a live node.
+    ///
+    /// Reference counts can only be checked exactly by a full scan.  Here
+    /// it's only checked that the data the Block refers to is counted.
+    pub fn validate_height(&mut self, height: BlockHeight) {
+        if let Err(e) = validate::head_tail_validity(self) {
+            self.process_error(e, "HEAD / HEADER_HEAD / TAIL / CHUNK_TAIL", DBCol::BlockMisc);
+            return;
+        }
+        let col = DBCol::BlockHeight;
+        let hash = match self.store.get_ser::<CryptoHash>(col, &index_to_bytes(height)) {
+            Ok(Some(hash)) => hash,
+            // There is no Block on the Canonical Chain at this Height.
+            Ok(None) => return,
+            Err(e) => return self.process_error(e.into(), height, col),
+        };
+        self.check(&validate::canonical_header_validity, &height, &hash, col);
+        self.check(&validate::canonical_prev_block_validity, &height, &hash, col);
+
+        let col = DBCol::BlockHeader;
+        match self.store.get_ser::<BlockHeader>(col, hash.as_ref()) {
+            Ok(Some(header)) => {
+                self.check(&validate::block_header_hash_validity, &hash, &header, col);
+                self.check(&validate::block_header_height_validity, &hash, &header, col);
+            }
+            // Missing Header is reported by `canonical_header_validity`.
+            Ok(None) => {}
+            Err(e) => self.process_error(e.into(), hash, col),
+        }
+
+        let col = DBCol::Block;
+        let block = match self.store.get_ser::<Block>(col, hash.as_ref()) {
+            Ok(Some(block)) => block,
+            Ok(None) => {
+                let reason = format!("Block {hash:?} on the Canonical Chain is not found");
+                let err = StoreValidatorError::DBNotFound { func_name: "validate_height", reason };
+                return self.process_error(err, hash, col);
+            }
+            Err(e) => return self.process_error(e.into(), hash, col),
+        };
+        self.check(&validate::block_hash_validity, &hash, &block, col);
+        self.check(&validate::block_indexed_by_height, &hash, &block, col);
+        self.check(&validate::block_header_exists, &hash, &block, col);
+        self.check(&validate::block_chunks_exist, &hash, &block, col);
+        self.check(&validate::block_chunks_height_validity, &hash, &block, col);
+        self.check(&validate::block_info_exists, &hash, &block, col);
+        self.check(&validate::block_chunk_extras_exist, &hash, &block, DBCol::ChunkExtra);
+        self.check(&validate::block_prev_refcount_positive, &hash, &block, DBCol::BlockRefCount);
+        self.check(&validate::block_chunks_txs_refcounted, &hash, &block, DBCol::Transactions);
+    }
+
+    /// Compares values of `samples` random keys of every shard with ready
+    /// flat storage with the values in the trie at the flat storage head.
+    pub fn validate_flat_storage(&mut self, rng: &mut impl Rng, samples: usize) {
+        let flat_storage_manager = self.runtime.get_flat_storage_manager();
+        for shard_uid in flat_storage_manager.get_shard_uids() {
+            let FlatStorageStatus::Ready(status) =
+                flat_storage_manager.get_flat_storage_status(shard_uid)
+            else {
+                continue;
+            };
+            let flat_head = status.flat_head.hash;
+            let Some(chunk_view) = flat_storage_manager.chunk_view(shard_uid, flat_head) else {
+                continue;
+            };
+            let col = DBCol::ChunkExtra;
+            let state_root = match self
+                .store
+                .get_ser::<ChunkExtra>(col, &get_block_shard_uid(&flat_head, &shard_uid))
+            {
+                Ok(Some(chunk_extra)) => *chunk_extra.state_root(),
+                Ok(None) => {
+                    let reason = format!(
+                        "Can't get ChunkExtra of flat storage head {flat_head:?} of shard {shard_uid:?}"
+                    );
+                    let err = StoreValidatorError::DBNotFound {
+                        func_name: "validate_flat_storage",
+                        reason,
+                    };
+                    self.process_error(err, (flat_head, shard_uid), col);
+                    continue;
+                }
+                Err(e) => {
+                    self.process_error(e.into(), (flat_head, shard_uid), col);
+                    continue;
+                }
+            };
+            for _ in 0..samples {
+                let Some(key) = self.random_flat_state_key(rng, shard_uid) else { break };
+                // Fails if the flat storage head has moved meanwhile, in which
+                // case the sample is simply dropped.
+                let Ok(value) = chunk_view.get_value(&key) else { break };
+                let flat_value = value.map(|value| value.to_value_ref());
+                self.check(
+                    &validate::flat_state_matches_trie,
+                    &(shard_uid, state_root, key),
+                    &flat_value,
+                    DBCol::FlatState,
+                );
+            }
+        }
+    }
+
+    /// Returns the first key of the shard in flat storage following a random
+    /// key or `None` if the flat storage of the shard is empty.
+    fn random_flat_state_key(&self, rng: &mut impl Rng, shard_uid: ShardUId) -> Option<Vec<u8>> {
+        // Trie keys start with the column, so pick one of those first.
+        let (col, _) = trie_key::col::ALL_COLUMNS_WITH_NAMES.choose(rng)?;
+        let mut seek_key = vec![*col];
+        seek_key.extend(rng.gen::<[u8; 32]>());
+        let store = self.store.clone();
+        let mut entries = flat_store_helper::iter_flat_state_entries(shard_uid, &store, None, None);
+        let mut entries_from =
+            flat_store_helper::iter_flat_state_entries(shard_uid, &store, Some(&seek_key), None);
+        let (key, _) = entries_from.next().or_else(|| entries.next())?.ok()?;
+        Some(key)
+    }
+
     fn check<K: std::fmt::Debug + ?Sized, V: ?Sized>(
         &mut self,
         f: &dyn Fn(&mut StoreValidator, &K, &V) -> Result<(), StoreValidatorError>,
@@ -490,6 +616,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_height() {
+        let (chain, mut sv) = init();
+        sv.validate_height(0);
+        assert!(sv.errors.is_empty(), "{:?}", sv.errors);
+        assert!(sv.tests_done() > 0);
+
+        let mut store_update = chain.chain_store().store().store_update();
+        store_update.delete(DBCol::BlockPerHeight, &index_to_bytes(0));
+        store_update.commit().unwrap();
+        sv.validate_height(0);
+        assert_eq!(sv.errors.len(), 1, "{:?}", sv.errors);
+        assert_eq!(sv.errors[0].col, DBCol::Block.to_string());
+    }
+
     #[test]
     fn test_validation_failed() {
         let (_chain, mut sv) = init();
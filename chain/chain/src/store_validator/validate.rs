@@ -6,13 +6,15 @@ use near_primitives::epoch_info::EpochInfo;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
 use near_primitives::sharding::{ChunkHash, ShardChunk, StateSyncInfo};
+use near_primitives::state::ValueRef;
 use near_primitives::state_sync::{ShardStateSyncResponseHeader, StateHeaderKey, StatePartKey};
 use near_primitives::transaction::{ExecutionOutcomeWithProof, SignedTransaction};
 use near_primitives::types::chunk_extra::ChunkExtra;
-use near_primitives::types::{BlockHeight, EpochId};
+use near_primitives::types::{BlockHeight, EpochId, StateRoot};
 use near_primitives::utils::{get_block_shard_id, get_outcome_id_block_hash, index_to_bytes};
 use near_store::{
-    DBCol, TrieChanges, CHUNK_TAIL_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY, TAIL_KEY,
+    DBCol, KeyLookupMode, TrieChanges, CHUNK_TAIL_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    TAIL_KEY,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -866,6 +868,120 @@ pub(crate) fn state_part_header_exists(
     Ok(())
 }
 
+// Sampled checks, run on a live node for single blocks and keys
+
+pub(crate) fn block_prev_refcount_positive(
+    sv: &mut StoreValidator,
+    block_hash: &CryptoHash,
+    block: &Block,
+) -> Result<(), StoreValidatorError> {
+    let prev_hash = block.header().prev_hash();
+    // The prev Block of the Block at Tail may be already garbage collected.
+    if block.header().height() == sv.config.genesis_height
+        || !sv.store.exists(DBCol::Block, prev_hash.as_ref())?
+    {
+        return Ok(());
+    }
+    let refcount = unwrap_or_err_db!(
+        sv.store.get_ser::<u64>(DBCol::BlockRefCount, prev_hash.as_ref()),
+        "Can't get Block Refcount of prev Block {:?} of Block {:?}",
+        prev_hash,
+        block_hash
+    );
+    if refcount == 0 {
+        err!(
+            "Block Refcount of prev Block {:?} is 0 but Block {:?} refers to it",
+            prev_hash,
+            block_hash
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn block_chunks_txs_refcounted(
+    sv: &mut StoreValidator,
+    _block_hash: &CryptoHash,
+    block: &Block,
+) -> Result<(), StoreValidatorError> {
+    for chunk_header in block.chunks().iter() {
+        if chunk_header.height_included() != block.header().height() {
+            continue;
+        }
+        let Some(shard_chunk) =
+            sv.store.get_ser::<ShardChunk>(DBCol::Chunks, chunk_header.chunk_hash().as_ref())?
+        else {
+            continue;
+        };
+        // Values of reference counted columns are only visible while their
+        // refcount is positive.
+        for tx in shard_chunk.transactions() {
+            let tx_hash = tx.get_hash();
+            if !sv.store.exists(DBCol::Transactions, tx_hash.as_ref())? {
+                err!("Tx {:?} of ShardChunk {:?} is not refcounted", tx_hash, chunk_header);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn block_chunk_extras_exist(
+    sv: &mut StoreValidator,
+    block_hash: &CryptoHash,
+    block: &Block,
+) -> Result<(), StoreValidatorError> {
+    let epoch_id = block.header().epoch_id();
+    let shard_layout = unwrap_or_err!(
+        sv.epoch_manager.get_shard_layout(epoch_id),
+        "Error getting shard layout of epoch {:?}",
+        epoch_id
+    );
+    for shard_uid in shard_layout.shard_uids() {
+        let cares_about_shard = sv.shard_tracker.care_about_shard(
+            sv.me.as_ref(),
+            block.header().prev_hash(),
+            shard_uid.shard_id(),
+            true,
+        );
+        if cares_about_shard {
+            unwrap_or_err_db!(
+                sv.store.get_ser::<ChunkExtra>(
+                    DBCol::ChunkExtra,
+                    &get_block_shard_uid(block_hash, &shard_uid)
+                ),
+                "Can't get ChunkExtra of tracked shard {:?} for Block {:?}",
+                shard_uid,
+                block_hash
+            );
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn flat_state_matches_trie(
+    sv: &mut StoreValidator,
+    (shard_uid, state_root, key): &(ShardUId, StateRoot, Vec<u8>),
+    flat_value: &Option<ValueRef>,
+) -> Result<(), StoreValidatorError> {
+    let trie = sv.runtime.get_tries().get_trie_for_shard(*shard_uid, *state_root);
+    let trie_value = unwrap_or_err!(
+        trie.get_optimized_ref(key, KeyLookupMode::Trie),
+        "Can't read key {:?} from Trie of shard {:?} at root {:?}",
+        key,
+        shard_uid,
+        state_root
+    )
+    .map(|value| value.into_value_ref());
+    check_discrepancy!(
+        flat_value,
+        &trie_value,
+        "Flat State value of key {:?} in shard {:?} differs from Trie at root {:?}",
+        key,
+        shard_uid,
+        state_root
+    );
+    Ok(())
+}
+
 // Final checks
 
 pub(crate) fn block_height_cmp_tail_final(sv: &StoreValidator) -> Result<(), StoreValidatorError> {
@@ -2,9 +2,9 @@ use super::ValidatorSchedule;
 use crate::types::{
     ApplyChunkBlockContext, ApplyChunkResult, ApplyChunkShardContext, ApplyResultForResharding,
     PrepareTransactionsBlockContext, PrepareTransactionsChunkContext, PreparedTransactions,
-    RuntimeAdapter, RuntimeStorageConfig,
+    RuntimeAdapter, RuntimeStorageConfig, SimulateTransactionResult,
 };
-use crate::BlockHeader;
+use crate::{Block, BlockHeader};
 use borsh::{BorshDeserialize, BorshSerialize};
 use itertools::Itertools;
 use near_async::time::Duration;
@@ -39,6 +39,7 @@ use near_primitives::transaction::{
     Action, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
     SignedTransaction, TransferAction,
 };
+use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
     AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, Nonce, NumShards,
@@ -1288,6 +1289,15 @@ impl RuntimeAdapter for KeyValueRuntime {
         })
     }
 
+    fn simulate_transaction(
+        &self,
+        _block: &Block,
+        _chunk_extras: HashMap<ShardId, Arc<ChunkExtra>>,
+        _transaction: &SignedTransaction,
+    ) -> Result<SimulateTransactionResult, Error> {
+        Err(Error::Other("simulate_transaction should not be used in KeyValueRuntime".into()))
+    }
+
     fn query(
         &self,
         _shard_id: ShardUId,
@@ -1341,14 +1351,17 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_height,
                 block_hash: *block_hash,
             }),
-            QueryRequest::ViewState { .. } => Ok(QueryResponse {
-                kind: QueryResponseKind::ViewState(ViewStateResult {
-                    values: Default::default(),
-                    proof: vec![],
-                }),
-                block_height,
-                block_hash: *block_hash,
-            }),
+            QueryRequest::ViewState { .. } | QueryRequest::ViewStatePaged { .. } => {
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewState(ViewStateResult {
+                        values: Default::default(),
+                        proof: vec![],
+                        continuation_token: None,
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::CallFunction { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::CallResult(CallResult {
                     result: Default::default(),
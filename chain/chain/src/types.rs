@@ -23,10 +23,11 @@ use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::state_part::PartId;
 use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
+use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
-    Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks, ShardId,
-    StateChangesForResharding, StateRoot, StateRootNode,
+    Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks,
+    RawStateChangesWithTrieKey, ShardId, StateChangesForResharding, StateRoot, StateRootNode,
 };
 use near_primitives::utils::to_timestamp;
 use near_primitives::version::{
@@ -38,6 +39,7 @@ use near_store::flat::FlatStorageManager;
 use near_store::{PartialStorage, ShardTries, Store, Trie, WrappedTrieChanges};
 use num_rational::Rational32;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::instrument;
 
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -124,6 +126,20 @@ pub struct ApplyChunkResult {
     pub congestion_info: Option<CongestionInfo>,
 }
 
+/// Result of executing a transaction and all the receipts it produced without
+/// persisting anything.
+#[derive(Debug, Default)]
+pub struct SimulateTransactionResult {
+    /// Outcomes of the transaction and of the executed receipts, in the order
+    /// of execution.
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    /// State changes caused by the transaction and the executed receipts.
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    /// Receipts which were produced but not executed, e.g. because they were
+    /// sent to a shard whose state isn't available.
+    pub unfinished_receipt_ids: Vec<CryptoHash>,
+}
+
 impl ApplyChunkResult {
     /// Returns root and paths for all the outcomes in the result.
     #[instrument(target = "runtime", level = "debug", "compute_outcomes_proof", skip_all, fields(
@@ -481,6 +497,18 @@ pub trait RuntimeAdapter: Send + Sync {
         transactions: &[SignedTransaction],
     ) -> Result<ApplyChunkResult, Error>;
 
+    /// Executes the transaction and all the receipts it produces on top of the
+    /// post-state of `block`, without persisting anything.
+    /// `chunk_extras` hold the post-state of `block` for the shards available to
+    /// the simulation.  Receipts sent to other shards are not executed.
+    /// The transaction signature is not checked.
+    fn simulate_transaction(
+        &self,
+        block: &Block,
+        chunk_extras: HashMap<ShardId, Arc<ChunkExtra>>,
+        transaction: &SignedTransaction,
+    ) -> Result<SimulateTransactionResult, Error>;
+
     /// Query runtime with given `path` and `data`.
     fn query(
         &self,
@@ -599,7 +627,7 @@ mod tests {
         assert!(b1.header().verify_block_producer(&signer.public_key()));
         let other_signer = create_test_signer("other2");
         let approvals =
-            vec![Some(Box::new(Approval::new(*b1.hash(), 1, 2, &other_signer).signature))];
+            vec![Some(Box::new(Approval::new(*b1.hash(), 1, 2, &other_signer).unwrap().signature))];
         let b2 =
             TestBlockBuilder::new(Clock::real(), &b1, signer.clone()).approvals(approvals).build();
         b2.header().verify_block_producer(&signer.public_key());
@@ -3,14 +3,17 @@ use std::collections::HashMap;
 use actix::Message;
 use itertools::Itertools;
 
+use near_chain_configs::TransactionPoolEvictionConfig;
+use near_crypto::PublicKey;
 use near_pool::types::TransactionGroupIterator;
 use near_pool::{InsertTransactionResult, PoolIteratorWrapper, TransactionPool};
 use near_primitives::shard_layout::{account_id_to_shard_uid, ShardLayout, ShardUId};
 use near_primitives::{
     epoch_info::RngSeed,
+    hash::CryptoHash,
     sharding::{EncodedShardChunk, PartialEncodedChunk, ShardChunk, ShardChunkHeader},
     transaction::SignedTransaction,
-    types::{AccountId, ShardId},
+    types::{AccountId, Nonce, ShardId},
 };
 
 #[derive(Message, Debug)]
@@ -43,11 +46,27 @@ pub struct ShardedTransactionPool {
     /// If set, new transactions that bring the size of the pool over this limit will be rejected.
     /// The size is tracked and enforced separately for each shard.
     pool_size_limit: Option<u64>,
+
+    /// Which transactions get evicted from the pool of every shard.
+    eviction_config: TransactionPoolEvictionConfig,
 }
 
 impl ShardedTransactionPool {
-    pub fn new(rng_seed: RngSeed, pool_size_limit: Option<u64>) -> Self {
-        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit }
+    pub fn new(
+        rng_seed: RngSeed,
+        pool_size_limit: Option<u64>,
+        eviction_config: TransactionPoolEvictionConfig,
+    ) -> Self {
+        Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit, eviction_config }
+    }
+
+    pub fn eviction_config(&self) -> &TransactionPoolEvictionConfig {
+        &self.eviction_config
+    }
+
+    /// Returns the pools of all shards that had transactions inserted into them.
+    pub fn pools(&self) -> impl Iterator<Item = (&ShardUId, &TransactionPool)> {
+        self.tx_pools.iter()
     }
 
     pub fn get_pool_iterator(&mut self, shard_uid: ShardUId) -> Option<PoolIteratorWrapper<'_>> {
@@ -69,6 +88,33 @@ impl ShardedTransactionPool {
         }
     }
 
+    /// Returns the shard whose pool contains the transaction with the given hash, if any.
+    pub fn find_transaction(&self, tx_hash: &CryptoHash) -> Option<ShardUId> {
+        self.tx_pools
+            .iter()
+            .find(|(_, pool)| pool.contains(tx_hash))
+            .map(|(shard_uid, _)| *shard_uid)
+    }
+
+    /// Returns whether the pool of the given shard has no transactions.
+    pub fn is_empty(&self, shard_uid: ShardUId) -> bool {
+        self.tx_pools.get(&shard_uid).map_or(true, |pool| pool.len() == 0)
+    }
+
+    /// Evicts transactions of the given shard that can never be included into a chunk because
+    /// their nonce is not greater than the current nonce of their access key. At most
+    /// `invalid_nonce_checks_per_block` access keys are checked per call.
+    pub fn evict_transactions_with_invalid_nonce(
+        &mut self,
+        shard_uid: ShardUId,
+        access_key_nonce: impl FnMut(&AccountId, &PublicKey) -> Option<Nonce>,
+    ) {
+        let max_checks = self.eviction_config.invalid_nonce_checks_per_block;
+        if let Some(pool) = self.tx_pools.get_mut(&shard_uid) {
+            pool.evict_transactions_with_invalid_nonce(max_checks, access_key_nonce)
+        }
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
@@ -85,6 +131,7 @@ impl ShardedTransactionPool {
             TransactionPool::new(
                 Self::random_seed(&self.rng_seed, shard_uid.shard_id()),
                 self.pool_size_limit,
+                self.eviction_config.clone(),
                 &shard_uid.to_string(),
             )
         })
@@ -194,7 +241,7 @@ mod tests {
         let old_shard_layout = ShardLayout::get_simple_nightshade_layout();
         let new_shard_layout = ShardLayout::get_simple_nightshade_layout_v2();
 
-        let mut pool = ShardedTransactionPool::new(TEST_SEED, None);
+        let mut pool = ShardedTransactionPool::new(TEST_SEED, None, Default::default());
 
         let mut shard_id_to_accounts = HashMap::new();
         shard_id_to_accounts.insert(0, vec!["aaa", "abcd", "a-a-a-a-a"]);
@@ -193,6 +193,8 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Contents of the transaction pools and the recently evicted transactions.
+    TransactionPool,
 }
 
 impl actix::Message for DebugStatus {
@@ -214,4 +216,57 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Transaction pools of all the shards.
+    TransactionPool(Vec<TransactionPoolView>),
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct TransactionPoolView {
+    pub shard_uid: String,
+    // Number of transactions in the pool.
+    pub transactions: usize,
+    // Total size of the transactions in the pool in bytes.
+    pub size: u64,
+    // Number of transactions evicted since the node started, by reason.
+    pub evicted: HashMap<String, u64>,
+    // The most recently evicted transactions, the latest first.
+    pub recent_evictions: Vec<EvictedTransactionView>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct EvictedTransactionView {
+    pub tx_hash: CryptoHash,
+    pub signer_id: AccountId,
+    pub nonce: u64,
+    pub reason: String,
+}
+
+/// Request for the findings of the store checker, which is a separate actor
+/// and thus isn't covered by [`DebugStatus`].
+#[derive(actix::Message, Debug)]
+#[rtype(result = "StoreCheckerStatusView")]
+pub struct GetStoreCheckerStatus;
+
+#[derive(serde::Serialize, actix::MessageResponse, Debug, Default)]
+pub struct StoreCheckerStatusView {
+    // Whether the checker is enabled in the config.
+    pub enabled: bool,
+    // Number of invariant checks done since the node started.
+    pub checks_done: u64,
+    // Number of checks which failed since the node started.
+    pub findings_total: u64,
+    // Height of the most recently checked block.
+    pub last_checked_height: Option<BlockHeight>,
+    // The most recent findings, the latest first.
+    pub recent_findings: Vec<StoreCheckerFindingView>,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct StoreCheckerFindingView {
+    // Height of the sampled block, or None for flat storage checks.
+    pub height: Option<BlockHeight>,
+    pub found_at: Utc,
+    pub column: String,
+    pub key: String,
+    pub error: String,
 }
@@ -1,9 +1,11 @@
 use actix::Message;
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::{ChunkHash, ShardChunk};
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
     TransactionOrReceiptId,
@@ -12,11 +14,12 @@ use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     GasPriceView, LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView,
-    QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesRequestView, StateChangesView, SyncStatusView, TxStatusView,
+    QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView, SignedTransactionView,
+    SimulateTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView,
+    StateChangesView, SyncStatusView, TxStatusView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use time::{Duration, OffsetDateTime as Utc};
@@ -872,6 +875,20 @@ impl Message for GetStateChangesWithCauseInBlock {
 pub struct GetStateChangesWithCauseInBlockForTrackedShards {
     pub block_hash: CryptoHash,
     pub epoch_id: EpochId,
+    /// Shards to return the changes of, all of them if `None`.
+    pub shard_ids: Option<HashSet<ShardId>>,
+    /// Accounts to return the changes of, all of them if `None`.
+    pub accounts: Option<AccountFilter>,
+}
+
+/// Predicate selecting accounts, e.g. the ones an indexer is interested in.
+#[derive(Clone)]
+pub struct AccountFilter(pub Arc<dyn Fn(&AccountId) -> bool + Send + Sync>);
+
+impl std::fmt::Debug for AccountFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AccountFilter")
+    }
 }
 
 impl Message for GetStateChangesWithCauseInBlockForTrackedShards {
@@ -1114,6 +1131,91 @@ impl From<near_chain_primitives::Error> for GetClientConfigError {
     }
 }
 
+/// Lists transactions in the node's transaction pools, ordered by hash.
+#[derive(Debug)]
+pub struct GetPendingTransactions {
+    /// Only list transactions signed by this account.
+    pub signer_id: Option<AccountId>,
+    /// Only list transactions sent to this account.
+    pub receiver_id: Option<AccountId>,
+    /// Only list transactions with hashes greater than this one.
+    pub after_tx_hash: Option<CryptoHash>,
+    pub limit: usize,
+}
+
+impl Message for GetPendingTransactions {
+    type Result = Result<PendingTransactionsView, String>;
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct PendingTransactionView {
+    pub shard_id: ShardId,
+    #[serde(flatten)]
+    pub transaction: SignedTransactionView,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct PendingTransactionsView {
+    pub transactions: Vec<PendingTransactionView>,
+    /// Hash to pass as `after_tx_hash` to get the next page, if there are more transactions.
+    pub next_after_tx_hash: Option<CryptoHash>,
+}
+
+/// Checks whether the transaction is in the node's transaction pools.
+#[derive(Debug)]
+pub struct GetPendingTransactionStatus {
+    pub tx_hash: CryptoHash,
+}
+
+impl Message for GetPendingTransactionStatus {
+    // Shard whose pool contains the transaction, if any.
+    type Result = Result<Option<ShardId>, String>;
+}
+
+/// Executes a transaction and the receipts it produces on top of the state of
+/// a block without persisting anything.
+#[derive(Debug)]
+pub struct SimulateTransaction {
+    pub transaction: SignedTransaction,
+    /// Unsigned transactions are simulated with the signature check skipped.
+    pub verify_signature: bool,
+    pub block_reference: BlockReference,
+}
+
+impl Message for SimulateTransaction {
+    type Result = Result<SimulateTransactionView, SimulateTransactionError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SimulateTransactionError {
+    #[error("Transaction is invalid: {0}")]
+    InvalidTransaction(InvalidTxError),
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("Block either has never been observed on the node or has been garbage collected: {0}")]
+    UnknownBlock(String),
+    #[error("The node does not track the shard ID {0}")]
+    UnavailableShard(ShardId),
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for SimulateTransactionError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            near_chain_primitives::Error::DBNotFoundErr(s) => Self::UnknownBlock(s),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GetSplitStorageInfo {}
 
@@ -274,8 +274,11 @@ impl Client {
             chain.chain_store(),
             chain_config.background_migration_threads,
         )?;
-        let sharded_tx_pool =
-            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
+        let sharded_tx_pool = ShardedTransactionPool::new(
+            rng_seed,
+            config.transaction_pool_size_limit,
+            config.transaction_pool_eviction.clone(),
+        );
         let sync_status = SyncStatus::AwaitingPeers;
         let header_sync = HeaderSync::new(
             clock.clone(),
@@ -432,6 +435,9 @@ impl Client {
                     let chunk = self.chain.get_chunk(&chunk_header.chunk_hash()).unwrap();
                     let transactions = chunk.transactions();
                     self.sharded_tx_pool.remove_transactions(shard_uid, transactions);
+                    if self.sharded_tx_pool.eviction_config().evict_invalid_nonces {
+                        self.evict_transactions_with_invalid_nonce(block, shard_id, shard_uid);
+                    }
                 }
             }
         }
@@ -441,6 +447,44 @@ impl Client {
         Ok(())
     }
 
+    /// Evicts transactions from the pool whose nonce was already used according to the state
+    /// after the given block. Only a bounded number of access keys is looked up per block, see
+    /// `TransactionPoolEvictionConfig::invalid_nonce_checks_per_block`.
+    fn evict_transactions_with_invalid_nonce(
+        &mut self,
+        block: &Block,
+        shard_id: ShardId,
+        shard_uid: ShardUId,
+    ) {
+        if self.sharded_tx_pool.is_empty(shard_uid) {
+            return;
+        }
+        let trie = match self.chain.get_chunk_extra(block.hash(), &shard_uid).and_then(
+            |chunk_extra| {
+                self.runtime_adapter.get_view_trie_for_shard(
+                    shard_id,
+                    block.hash(),
+                    *chunk_extra.state_root(),
+                )
+            },
+        ) {
+            Ok(trie) => trie,
+            Err(err) => {
+                debug!(target: "client", ?shard_uid, ?err, "Can't evict transactions with invalid nonce");
+                return;
+            }
+        };
+        self.sharded_tx_pool.evict_transactions_with_invalid_nonce(
+            shard_uid,
+            |account_id, public_key| {
+                near_store::get_access_key(&trie, account_id, public_key)
+                    .ok()
+                    .flatten()
+                    .map(|access_key| access_key.nonce)
+            },
+        );
+    }
+
     pub fn reintroduce_transactions_for_block(
         &mut self,
         me: AccountId,
@@ -788,7 +832,7 @@ impl Client {
             block_merkle_root,
             self.clock.clone(),
             sandbox_delta_time,
-        );
+        )?;
 
         // Update latest known even before returning block out, to prevent race conditions.
         self.chain
@@ -1065,7 +1109,13 @@ impl Client {
     ) {
         if let Some(validator_signer) = &signer {
             for body in challenges {
-                let challenge = Challenge::produce(body, &**validator_signer);
+                let challenge = match Challenge::produce(body, &**validator_signer) {
+                    Ok(challenge) => challenge,
+                    Err(err) => {
+                        error!(target: "client", %err, "Failed to sign challenge");
+                        continue;
+                    }
+                };
                 self.challenges.insert(challenge.hash, challenge.clone());
                 self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
                     NetworkRequests::Challenge(challenge),
@@ -1297,26 +1347,23 @@ impl Client {
 
         // Send out challenge if the block was found to be invalid.
         if let Some(signer) = signer {
-            if let Err(e) = &result {
-                match e {
-                    near_chain::Error::InvalidChunkProofs(chunk_proofs) => {
-                        self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
-                            NetworkRequests::Challenge(Challenge::produce(
-                                ChallengeBody::ChunkProofs(*chunk_proofs.clone()),
-                                &*signer,
-                            )),
-                        ));
-                    }
-                    near_chain::Error::InvalidChunkState(chunk_state) => {
-                        self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
-                            NetworkRequests::Challenge(Challenge::produce(
-                                ChallengeBody::ChunkState(*chunk_state.clone()),
-                                &*signer,
-                            )),
-                        ));
-                    }
-                    _ => {}
+            let body = match &result {
+                Err(near_chain::Error::InvalidChunkProofs(chunk_proofs)) => {
+                    Some(ChallengeBody::ChunkProofs(*chunk_proofs.clone()))
+                }
+                Err(near_chain::Error::InvalidChunkState(chunk_state)) => {
+                    Some(ChallengeBody::ChunkState(*chunk_state.clone()))
+                }
+                _ => None,
+            };
+            match body.map(|body| Challenge::produce(body, &*signer)) {
+                Some(Ok(challenge)) => {
+                    self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                        NetworkRequests::Challenge(challenge),
+                    ));
                 }
+                Some(Err(err)) => error!(target: "client", %err, "Failed to sign challenge"),
+                None => {}
             }
         }
 
@@ -17,6 +17,7 @@ use crate::sync::state::{StateSync, StateSyncResult};
 use crate::sync_jobs_actor::{ClientSenderForSyncJobs, SyncJobsActor};
 use crate::{metrics, StatusResponse, SyncAdapter};
 use actix::Actor;
+use itertools::{Either, Itertools};
 use near_async::actix::AddrWithAutoSpanContextExt;
 use near_async::actix_wrapper::ActixWrapper;
 use near_async::futures::{
@@ -47,7 +48,8 @@ use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::get_shards_cares_about_this_or_next_epoch;
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
+    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, GetPendingTransactionStatus,
+    GetPendingTransactions, NetworkInfoResponse, PendingTransactionView, PendingTransactionsView,
     StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
@@ -66,7 +68,7 @@ use near_primitives::block::Tip;
 use near_primitives::block_header::ApprovalType;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
-use near_primitives::types::{AccountId, BlockHeight, EpochId};
+use near_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
@@ -865,6 +867,61 @@ impl Handler<GetNetworkInfo> for ClientActorInner {
     }
 }
 
+impl Handler<GetPendingTransactions> for ClientActorInner {
+    fn handle(&mut self, msg: GetPendingTransactions) -> Result<PendingTransactionsView, String> {
+        tracing::debug!(target: "client", ?msg);
+        let GetPendingTransactions { signer_id, receiver_id, after_tx_hash, limit } = msg;
+        // Every pool is listed ordered by hash starting at the cursor, and the pools are merged,
+        // so that a page is served without collecting and sorting whole pools.
+        let pools = self.client.sharded_tx_pool.pools().map(|(shard_uid, pool)| {
+            let pool_transactions = match &signer_id {
+                // Only the few transactions of the signer get sorted.
+                Some(signer_id) => Either::Left(
+                    pool.signer_transactions(signer_id)
+                        .filter(|tx| after_tx_hash.map_or(true, |after| tx.get_hash() > after))
+                        .sorted_by_key(|tx| tx.get_hash()),
+                ),
+                None => Either::Right(pool.transactions_by_hash(after_tx_hash)),
+            };
+            pool_transactions.map(move |tx| (shard_uid.shard_id(), tx))
+        });
+        let mut transactions = pools
+            .kmerge_by(|(_, a), (_, b)| a.get_hash() < b.get_hash())
+            .filter(|(_, tx)| {
+                receiver_id
+                    .as_ref()
+                    .map_or(true, |receiver_id| tx.transaction.receiver_id() == receiver_id)
+            })
+            .take(limit.saturating_add(1))
+            .collect::<Vec<_>>();
+        let has_more = transactions.len() > limit;
+        transactions.truncate(limit);
+        let next_after_tx_hash =
+            if has_more { transactions.last().map(|(_, tx)| tx.get_hash()) } else { None };
+        Ok(PendingTransactionsView {
+            transactions: transactions
+                .into_iter()
+                .map(|(shard_id, tx)| PendingTransactionView {
+                    shard_id,
+                    transaction: tx.clone().into(),
+                })
+                .collect(),
+            next_after_tx_hash,
+        })
+    }
+}
+
+impl Handler<GetPendingTransactionStatus> for ClientActorInner {
+    fn handle(&mut self, msg: GetPendingTransactionStatus) -> Result<Option<ShardId>, String> {
+        tracing::debug!(target: "client", ?msg);
+        Ok(self
+            .client
+            .sharded_tx_pool
+            .find_transaction(&msg.tx_hash)
+            .map(|shard_uid| shard_uid.shard_id()))
+    }
+}
+
 impl Handler<ApplyChunksDoneMessage> for ClientActorInner {
     fn handle(&mut self, _msg: ApplyChunksDoneMessage) {
         let validator_signer = self.client.validator_signer.get();
@@ -981,10 +1038,12 @@ impl ClientActorInner {
         // Check client is part of the futures validators
         if self.client.is_validator(&next_epoch_id, &prev_block_hash, validator_signer) {
             debug!(target: "client", "Sending announce account for {}", signer.validator_id());
+            let signature = unwrap_or_return!(signer.sign_account_announce(
+                signer.validator_id(),
+                &self.node_id,
+                &next_epoch_id
+            ));
             self.last_validator_announce_time = Some(now);
-
-            let signature =
-                signer.sign_account_announce(signer.validator_id(), &self.node_id, &next_epoch_id);
             self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
                 NetworkRequests::AnnounceAccount(AnnounceAccount {
                     account_id: signer.validator_id().clone(),
@@ -8,7 +8,8 @@ use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, Block, Chain, ChainStoreAccess};
 use near_client_primitives::debug::{
     ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    DebugStatusResponse, EvictedTransactionView, MissedHeightInfo, ProductionAtHeight,
+    TransactionPoolView, ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -178,11 +179,44 @@ impl Handler<DebugStatus> for ClientActorInner {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::TransactionPool => {
+                Ok(DebugStatusResponse::TransactionPool(self.get_transaction_pool_view()))
+            }
         }
     }
 }
 
 impl ClientActorInner {
+    fn get_transaction_pool_view(&self) -> Vec<TransactionPoolView> {
+        let mut pools = self
+            .client
+            .sharded_tx_pool
+            .pools()
+            .map(|(shard_uid, pool)| TransactionPoolView {
+                shard_uid: shard_uid.to_string(),
+                transactions: pool.len(),
+                size: pool.transaction_size(),
+                evicted: pool
+                    .eviction_counts()
+                    .iter()
+                    .map(|(reason, count)| (reason.as_str().to_string(), *count))
+                    .collect(),
+                recent_evictions: pool
+                    .recent_evictions()
+                    .rev()
+                    .map(|evicted| EvictedTransactionView {
+                        tx_hash: evicted.tx_hash,
+                        signer_id: evicted.signer_id.clone(),
+                        nonce: evicted.nonce,
+                        reason: evicted.reason.as_str().to_string(),
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        pools.sort_by(|a, b| a.shard_uid.cmp(&b.shard_uid));
+        pools
+    }
+
     // Gets a list of block producers and chunk-only producers for a given epoch.
     fn get_producers_for_epoch(
         &self,
@@ -776,6 +810,7 @@ fn new_peer_info_view(chain: &Chain, connected_peer_info: &ConnectedPeerInfo) ->
             .whole_milliseconds() as u64,
         is_outbound_peer: connected_peer_info.peer_type == PeerType::Outbound,
         nonce: connected_peer_info.nonce,
+        reputation: connected_peer_info.reputation,
     }
 }
 
@@ -547,6 +547,26 @@ pub(crate) static STATE_SYNC_EXTERNAL_PARTS_SIZE_DOWNLOADED: LazyLock<IntCounter
         .unwrap()
     });
 
+pub(crate) static STATE_SYNC_EXTERNAL_COMPRESSED_BYTES: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_state_sync_external_compressed_bytes_total",
+            "Bytes of compressed files downloaded from an external storage",
+            &["shard_id", "type"],
+        )
+        .unwrap()
+    });
+
+pub(crate) static STATE_SYNC_EXTERNAL_DECOMPRESSED_BYTES: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_state_sync_external_decompressed_bytes_total",
+            "Size of compressed files downloaded from an external storage after decompression",
+            &["shard_id", "type"],
+        )
+        .unwrap()
+    });
+
 pub(crate) static STATE_SYNC_DUMP_PUT_OBJECT_ELAPSED: LazyLock<HistogramVec> =
     LazyLock::new(|| {
         try_create_histogram_vec(
@@ -228,7 +228,14 @@ pub(crate) fn send_chunk_endorsement_to_block_producers(
     );
 
     let protocol_version = epoch_manager.get_epoch_protocol_version(&epoch_id).unwrap();
-    let endorsement = ChunkEndorsement::new(epoch_id, chunk_header, signer, protocol_version);
+    let endorsement = match ChunkEndorsement::new(epoch_id, chunk_header, signer, protocol_version)
+    {
+        Ok(endorsement) => endorsement,
+        Err(err) => {
+            tracing::error!(target: "client", ?chunk_hash, %err, "Failed to sign chunk endorsement");
+            return;
+        }
+    };
     for block_producer in block_producers {
         if signer.validator_id() == &block_producer {
             // Our own endorsements are not always valid (see issue #11750).
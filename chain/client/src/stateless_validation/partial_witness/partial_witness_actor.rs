@@ -179,7 +179,7 @@ impl PartialWitnessActor {
         let encoder = self.encoders.entry(chunk_validators.len());
         let (parts, encoded_length) = encoder.encode(&witness_bytes);
 
-        Ok(chunk_validators
+        chunk_validators
             .iter()
             .zip_eq(parts)
             .enumerate()
@@ -193,10 +193,10 @@ impl PartialWitnessActor {
                     part.unwrap().to_vec(),
                     encoded_length,
                     signer,
-                );
-                (chunk_validator.clone(), partial_witness)
+                )?;
+                Ok((chunk_validator.clone(), partial_witness))
             })
-            .collect_vec())
+            .collect()
     }
 
     // Break the state witness into parts and send each part to the corresponding chunk validator owner.
@@ -1,5 +1,6 @@
 use crate::metrics;
 use futures::TryStreamExt;
+use near_chain_configs::StateFileCompression;
 use near_primitives::types::{EpochId, ShardId};
 use std::io::{Read, Write};
 use std::path::PathBuf;
@@ -9,8 +10,14 @@ use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub enum StateFileType {
-    StatePart { part_id: u64, num_parts: u64 },
+    StatePart {
+        part_id: u64,
+        num_parts: u64,
+    },
     StateHeader,
+    /// Signed list of hashes of the header and all parts, see
+    /// [`crate::sync::manifest`].
+    Manifest,
 }
 
 impl ToString for StateFileType {
@@ -18,11 +25,20 @@ impl ToString for StateFileType {
         match self {
             StateFileType::StatePart { .. } => StateFileType::part_str(),
             StateFileType::StateHeader => StateFileType::header_str(),
+            StateFileType::Manifest => StateFileType::manifest_str(),
         }
     }
 }
 
 impl StateFileType {
+    /// Whether files of this type may be stored compressed.
+    pub fn is_compressible(&self) -> bool {
+        match self {
+            StateFileType::StatePart { .. } | StateFileType::StateHeader => true,
+            StateFileType::Manifest => false,
+        }
+    }
+
     pub fn part_str() -> String {
         String::from("part")
     }
@@ -31,12 +47,17 @@ impl StateFileType {
         String::from("header")
     }
 
+    pub fn manifest_str() -> String {
+        String::from("manifest")
+    }
+
     pub fn filename(&self) -> String {
         match self {
             StateFileType::StatePart { part_id, num_parts } => {
                 format!("state_part_{:06}_of_{:06}", part_id, num_parts)
             }
             StateFileType::StateHeader => "header".to_string(),
+            StateFileType::Manifest => "manifest.json".to_string(),
         }
     }
 }
@@ -60,15 +81,66 @@ pub enum ExternalConnection {
     },
 }
 
+/// Level of zstd compression of state headers and parts.
+const ZSTD_COMPRESSION_LEVEL: i32 = 3;
+
+/// Upper bound on the size of a decompressed header or part.  Matches the
+/// maximum size of a network message, which has to fit any part anyway.
+const MAX_DECOMPRESSED_FILE_SIZE: u64 = 512 * bytesize::MIB;
+
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
     &percent_encoding::NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
 
+/// Returned when the external storage has no file at the requested location,
+/// as opposed to failing to serve it.
+#[derive(thiserror::Error, Debug)]
+#[error("File not found: {0}")]
+pub struct FileNotFound(pub String);
+
 impl ExternalConnection {
+    /// Downloads the file at the given location.  Headers and parts may be
+    /// stored compressed, in which case the location has the extension of the
+    /// codec appended; such files are looked up first and are returned
+    /// decompressed, so that callers don't care about how the files were
+    /// dumped.  The uncompressed file is only looked up if there is no
+    /// compressed one, other errors are returned as is.
     pub async fn get_file(
         &self,
         shard_id: ShardId,
         location: &str,
         file_type: &StateFileType,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        if !file_type.is_compressible() {
+            return self.get_file_impl(shard_id, location, file_type).await;
+        }
+        let compression = StateFileCompression::Zstd;
+        let compressed_location = format!("{location}{}", compression.extension());
+        match self.get_file_impl(shard_id, &compressed_location, file_type).await {
+            Ok(data) => {
+                let decompressed = decompress(compression, &data)?;
+                metrics::STATE_SYNC_EXTERNAL_COMPRESSED_BYTES
+                    .with_label_values(&[&shard_id.to_string(), &file_type.to_string()])
+                    .inc_by(data.len() as u64);
+                metrics::STATE_SYNC_EXTERNAL_DECOMPRESSED_BYTES
+                    .with_label_values(&[&shard_id.to_string(), &file_type.to_string()])
+                    .inc_by(decompressed.len() as u64);
+                Ok(decompressed)
+            }
+            Err(err) if err.is::<FileNotFound>() => {
+                // Dumps made without compression have the files under their
+                // plain names.
+                tracing::trace!(target: "sync", %shard_id, location, ?err, "No compressed file, getting the uncompressed one");
+                self.get_file_impl(shard_id, location, file_type).await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn get_file_impl(
+        &self,
+        shard_id: ShardId,
+        location: &str,
+        file_type: &StateFileType,
     ) -> Result<Vec<u8>, anyhow::Error> {
         let _timer = metrics::STATE_SYNC_EXTERNAL_PARTS_REQUEST_DELAY
             .with_label_values(&[&shard_id.to_string(), &file_type.to_string()])
@@ -79,6 +151,8 @@ impl ExternalConnection {
                 tracing::debug!(target: "sync", %shard_id, location, response_code = response.status_code(), num_bytes = response.bytes().len(), "S3 request finished");
                 if response.status_code() == 200 {
                     Ok(response.bytes().to_vec())
+                } else if response.status_code() == 404 {
+                    Err(FileNotFound(location.to_string()).into())
                 } else {
                     Err(anyhow::anyhow!("Bad response status code: {}", response.status_code()))
                 }
@@ -86,8 +160,13 @@ impl ExternalConnection {
             ExternalConnection::Filesystem { root_dir } => {
                 let path = root_dir.join(location);
                 tracing::debug!(target: "sync", %shard_id, ?path, "Reading a file");
-                let data = std::fs::read(&path)?;
-                Ok(data)
+                match std::fs::read(&path) {
+                    Ok(data) => Ok(data),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        Err(FileNotFound(location.to_string()).into())
+                    }
+                    Err(err) => Err(err.into()),
+                }
             }
             ExternalConnection::GCS { reqwest_client, bucket, .. } => {
                 // Download should be handled anonymously, therefore we are not using cloud-storage crate.
@@ -101,7 +180,11 @@ impl ExternalConnection {
                 match response {
                     Err(e) => {
                         tracing::debug!(target: "sync", %shard_id, location, error = ?e, "GCS state_part request failed");
-                        Err(e.into())
+                        if e.status() == Some(reqwest::StatusCode::NOT_FOUND) {
+                            Err(FileNotFound(location.to_string()).into())
+                        } else {
+                            Err(e.into())
+                        }
                     }
                     Ok(r) => {
                         let bytes = r.bytes().await?.to_vec();
@@ -114,6 +197,8 @@ impl ExternalConnection {
     }
 
     /// Uploads the given state part or header to external storage.
+    /// If `compression` is set, the data is compressed and the extension of the
+    /// codec is appended to the location.
     /// Wrapper for adding is_ok to the metric labels.
     pub async fn put_file(
         &self,
@@ -121,9 +206,22 @@ impl ExternalConnection {
         data: &[u8],
         shard_id: ShardId,
         location: &str,
+        compression: StateFileCompression,
     ) -> Result<(), anyhow::Error> {
+        if compression != StateFileCompression::None && !file_type.is_compressible() {
+            anyhow::bail!("{file_type:?} can't be compressed");
+        }
         let instant = Instant::now();
-        let res = self.put_file_impl(&file_type, data, shard_id, location).await;
+        let res = match compression {
+            StateFileCompression::None => {
+                self.put_file_impl(&file_type, data, shard_id, location).await
+            }
+            StateFileCompression::Zstd => {
+                let location = format!("{location}{}", compression.extension());
+                let compressed = zstd::bulk::compress(data, ZSTD_COMPRESSION_LEVEL)?;
+                self.put_file_impl(&file_type, &compressed, shard_id, &location).await
+            }
+        };
         let is_ok = if res.is_ok() { "ok" } else { "error" };
         let elapsed = instant.elapsed();
         metrics::STATE_SYNC_DUMP_PUT_OBJECT_ELAPSED
@@ -250,18 +348,13 @@ impl ExternalConnection {
         epoch_height: u64,
     ) -> Result<bool, anyhow::Error> {
         let file_type = StateFileType::StateHeader;
-        let directory_path = external_storage_location_directory(
-            chain_id,
-            epoch_id,
-            epoch_height,
-            shard_id,
-            &file_type,
-        );
-        let file_names = self.list_objects(shard_id, &directory_path).await?;
-        let header_exits = file_names.contains(&file_type.filename());
+        let header_exits = self
+            .is_file_stored_for_epoch(shard_id, chain_id, epoch_id, epoch_height, &file_type)
+            .await?;
         tracing::debug!(
             target: "state_sync_dump",
-            ?directory_path,
+            shard_id,
+            epoch_height,
             "{}",
             match header_exits {
                 true => "Header has already been dumped.",
@@ -270,6 +363,38 @@ impl ExternalConnection {
         );
         Ok(header_exits)
     }
+
+    /// Check if the manifest of state parts exists in the external storage.
+    pub async fn is_manifest_stored_for_epoch(
+        &self,
+        shard_id: ShardId,
+        chain_id: &String,
+        epoch_id: &EpochId,
+        epoch_height: u64,
+    ) -> Result<bool, anyhow::Error> {
+        let file_type = StateFileType::Manifest;
+        self.is_file_stored_for_epoch(shard_id, chain_id, epoch_id, epoch_height, &file_type).await
+    }
+
+    async fn is_file_stored_for_epoch(
+        &self,
+        shard_id: ShardId,
+        chain_id: &String,
+        epoch_id: &EpochId,
+        epoch_height: u64,
+        file_type: &StateFileType,
+    ) -> Result<bool, anyhow::Error> {
+        let directory_path = external_storage_location_directory(
+            chain_id,
+            epoch_id,
+            epoch_height,
+            shard_id,
+            file_type,
+        );
+        let file_names = self.list_objects(shard_id, &directory_path).await?;
+        let filename = file_type.filename();
+        Ok(file_names.iter().any(|file_name| strip_compression_extension(file_name) == filename))
+    }
 }
 
 /// Construct the state file location on the external storage.
@@ -309,7 +434,7 @@ pub fn location_prefix(
             "chain_id={}/epoch_height={}/epoch_id={}/shard_id={}",
             chain_id, epoch_height, epoch_id.0, shard_id
         ),
-        StateFileType::StateHeader => format!(
+        StateFileType::StateHeader | StateFileType::Manifest => format!(
             "chain_id={}/epoch_height={}/epoch_id={}/headers/shard_id={}",
             chain_id, epoch_height, epoch_id.0, shard_id
         ),
@@ -322,7 +447,30 @@ pub fn part_filename(part_id: u64, num_parts: u64) -> String {
 
 pub fn match_filename(s: &str) -> Option<regex::Captures> {
     let re = regex::Regex::new(r"^state_part_(\d{6})_of_(\d{6})$").unwrap();
-    re.captures(s)
+    re.captures(strip_compression_extension(s))
+}
+
+/// Returns the name of the file without the extension of its codec, if it is
+/// compressed.
+pub fn strip_compression_extension(file_name: &str) -> &str {
+    file_name.strip_suffix(StateFileCompression::Zstd.extension()).unwrap_or(file_name)
+}
+
+fn decompress(compression: StateFileCompression, data: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    match compression {
+        StateFileCompression::None => Ok(data.to_vec()),
+        StateFileCompression::Zstd => {
+            let mut decompressed = vec![];
+            zstd::stream::read::Decoder::new(data)?
+                .take(MAX_DECOMPRESSED_FILE_SIZE + 1)
+                .read_to_end(&mut decompressed)?;
+            anyhow::ensure!(
+                decompressed.len() as u64 <= MAX_DECOMPRESSED_FILE_SIZE,
+                "decompressed file is larger than {MAX_DECOMPRESSED_FILE_SIZE} bytes"
+            );
+            Ok(decompressed)
+        }
+    }
 }
 
 pub fn is_part_filename(s: &str) -> bool {
@@ -407,8 +555,9 @@ fn create_bucket(
 mod test {
     use crate::sync::external::{
         get_num_parts_from_filename, get_part_id_from_filename, is_part_filename,
-        ExternalConnection, StateFileType,
+        ExternalConnection, FileNotFound, StateFileType,
     };
+    use near_chain_configs::StateFileCompression;
     use near_o11y::testonly::init_test_logger;
     use rand::distributions::{Alphanumeric, DistString};
 
@@ -420,15 +569,76 @@ mod test {
     fn test_match_filename() {
         let filename = StateFileType::StatePart { part_id: 5, num_parts: 15 }.filename();
         assert!(is_part_filename(&filename));
+        assert!(is_part_filename(&format!("{filename}.zst")));
         assert!(!is_part_filename("123123"));
+        assert!(!is_part_filename(&format!("{filename}.gz")));
 
         assert_eq!(get_num_parts_from_filename(&filename), Some(15));
         assert_eq!(get_num_parts_from_filename("123123"), None);
 
         assert_eq!(get_part_id_from_filename(&filename), Some(5));
+        assert_eq!(get_part_id_from_filename(&format!("{filename}.zst")), Some(5));
         assert_eq!(get_part_id_from_filename("123123"), None);
     }
 
+    #[test]
+    fn test_compressed_and_uncompressed_files() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let connection = ExternalConnection::Filesystem { root_dir: root_dir.path().to_path_buf() };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let data = vec![7u8; 10000];
+
+        for (part_id, compression) in
+            [StateFileCompression::None, StateFileCompression::Zstd].into_iter().enumerate()
+        {
+            let file_type = StateFileType::StatePart { part_id: part_id as u64, num_parts: 2 };
+            let location = format!("{dir}/{}", file_type.filename());
+            rt.block_on(async {
+                connection
+                    .put_file(file_type.clone(), &data, 0, &location, compression)
+                    .await
+                    .unwrap();
+                assert_eq!(connection.get_file(0, &location, &file_type).await.unwrap(), data);
+            });
+        }
+
+        let mut files = rt.block_on(async { connection.list_objects(0, dir).await.unwrap() });
+        files.sort();
+        assert_eq!(files, ["state_part_000000_of_000002", "state_part_000001_of_000002.zst"]);
+        let compressed = std::fs::metadata(root_dir.path().join(dir).join(&files[1])).unwrap();
+        assert!(compressed.len() < data.len() as u64);
+
+        // A compressed file that can't be read isn't replaced by the uncompressed one.
+        std::fs::write(root_dir.path().join(dir).join(&files[1]), b"corrupted").unwrap();
+        let file_type = StateFileType::StatePart { part_id: 1, num_parts: 2 };
+        let location = format!("{dir}/{}", file_type.filename());
+        std::fs::write(root_dir.path().join(&location), &data).unwrap();
+        assert!(rt.block_on(connection.get_file(0, &location, &file_type)).is_err());
+
+        // Nothing at all is reported as such.
+        let file_type = StateFileType::StateHeader;
+        let location = format!("{dir}/{}", file_type.filename());
+        let err = rt.block_on(connection.get_file(0, &location, &file_type)).unwrap_err();
+        assert!(err.is::<FileNotFound>(), "{err:?}");
+    }
+
+    #[test]
+    fn test_put_uncompressible_file() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let connection = ExternalConnection::Filesystem { root_dir: root_dir.path().to_path_buf() };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let result = rt.block_on(connection.put_file(
+            StateFileType::Manifest,
+            b"{}",
+            0,
+            "manifest.json",
+            StateFileCompression::Zstd,
+        ));
+        assert!(result.is_err());
+        assert!(std::fs::read_dir(root_dir.path()).unwrap().next().is_none());
+    }
+
     /// This test should be ignored by default, as it requires gcloud credentials to run.
     /// Specify the path to service account json  in `SERVICE_ACCOUNT` variable to run the test.
     #[test]
@@ -466,7 +676,10 @@ mod test {
 
         // Uploading the file.
         rt.block_on(async {
-            connection.put_file(file_type.clone(), &data, 0, &full_filename).await.unwrap()
+            connection
+                .put_file(file_type.clone(), &data, 0, &full_filename, StateFileCompression::None)
+                .await
+                .unwrap()
         });
 
         // After uploading we should see filename in the list of files.
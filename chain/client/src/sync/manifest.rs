@@ -0,0 +1,396 @@
+//! Manifests of state parts dumped to external storage.
+//!
+//! Once all parts of a shard are dumped for an epoch, the dumper publishes a
+//! manifest listing the size and hash of the header and of every part, signed
+//! with the key configured in `state_sync.dump.manifest_key_file`.  A node
+//! syncing from external storage with `trusted_manifest_signers` configured
+//! checks every downloaded file against the manifest before applying it, so
+//! incomplete or tampered dumps are detected without parsing the parts.
+//!
+//! Sizes and hashes are of the uncompressed files, so a manifest doesn't
+//! depend on the compression of the dump.
+use crate::sync::external::{external_storage_location, ExternalConnection, StateFileType};
+use borsh::BorshSerialize;
+use near_async::time::{Clock, Duration, Instant};
+use near_crypto::{InMemorySigner, PublicKey, Signature};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{EpochHeight, EpochId, ShardId, StateRoot};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+/// Number of manifests kept in [`ManifestCache`].
+const MANIFEST_CACHE_SIZE: usize = 32;
+
+/// How long a failure to get a manifest is returned to all the requests for it
+/// before the manifest is downloaded again.
+const MANIFEST_FAILURE_BACKOFF: Duration = Duration::seconds(10);
+
+#[derive(BorshSerialize, serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StatePartsManifest {
+    pub chain_id: String,
+    pub epoch_id: EpochId,
+    pub epoch_height: EpochHeight,
+    pub shard_id: ShardId,
+    pub sync_hash: CryptoHash,
+    pub state_root: StateRoot,
+    pub header: ManifestFile,
+    /// Parts in the order of their ids.
+    pub parts: Vec<ManifestPart>,
+}
+
+#[derive(BorshSerialize, serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestFile {
+    pub size: u64,
+    pub hash: CryptoHash,
+}
+
+#[derive(BorshSerialize, serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestPart {
+    pub part_id: u64,
+    pub size: u64,
+    pub hash: CryptoHash,
+}
+
+/// A manifest together with the signature of its dumper.  This is what is
+/// stored in external storage, serialized as JSON.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct SignedStatePartsManifest {
+    pub manifest: StatePartsManifest,
+    pub public_key: PublicKey,
+    /// Signature of the hash of the borsh-serialized manifest.
+    pub signature: Signature,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ManifestError {
+    #[error("manifest can't be parsed: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("manifest has invalid signature")]
+    InvalidSignature,
+    #[error("manifest is signed by untrusted key {0}")]
+    UntrustedSigner(PublicKey),
+    #[error("manifest is for another sync point: shard {shard_id} at {sync_hash}")]
+    WrongManifest { shard_id: ShardId, sync_hash: CryptoHash },
+    #[error("manifest lists {got} parts but there are {want}")]
+    WrongNumParts { got: u64, want: u64 },
+    #[error("manifest doesn't list part {0}")]
+    UnknownPart(u64),
+    #[error("{file} has {got} bytes but the manifest says {want}")]
+    SizeMismatch { file: String, got: u64, want: u64 },
+    #[error("{file} has hash {got} but the manifest says {want}")]
+    HashMismatch { file: String, got: CryptoHash, want: CryptoHash },
+}
+
+impl StatePartsManifest {
+    pub fn check_header(&self, data: &[u8]) -> Result<(), ManifestError> {
+        check_file("header", &self.header, data)
+    }
+
+    pub fn check_part(&self, part_id: u64, data: &[u8]) -> Result<(), ManifestError> {
+        let num_parts = self.parts.len() as u64;
+        let file = StateFileType::StatePart { part_id, num_parts }.filename();
+        match self.parts.get(part_id as usize) {
+            Some(part) if part.part_id == part_id => {
+                check_file(&file, &ManifestFile { size: part.size, hash: part.hash }, data)
+            }
+            _ => Err(ManifestError::UnknownPart(part_id)),
+        }
+    }
+
+    pub fn check_num_parts(&self, num_parts: u64) -> Result<(), ManifestError> {
+        let got = self.parts.len() as u64;
+        if got == num_parts {
+            Ok(())
+        } else {
+            Err(ManifestError::WrongNumParts { got, want: num_parts })
+        }
+    }
+}
+
+fn check_file(name: &str, want: &ManifestFile, data: &[u8]) -> Result<(), ManifestError> {
+    if data.len() as u64 != want.size {
+        return Err(ManifestError::SizeMismatch {
+            file: name.to_string(),
+            got: data.len() as u64,
+            want: want.size,
+        });
+    }
+    let hash = CryptoHash::hash_bytes(data);
+    if hash != want.hash {
+        return Err(ManifestError::HashMismatch {
+            file: name.to_string(),
+            got: hash,
+            want: want.hash,
+        });
+    }
+    Ok(())
+}
+
+impl ManifestFile {
+    pub fn new(data: &[u8]) -> Self {
+        Self { size: data.len() as u64, hash: CryptoHash::hash_bytes(data) }
+    }
+}
+
+impl SignedStatePartsManifest {
+    pub fn new(manifest: StatePartsManifest, signer: &InMemorySigner) -> Self {
+        let signature = signer.sign(CryptoHash::hash_borsh(&manifest).as_ref());
+        Self { manifest, public_key: signer.public_key(), signature }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).unwrap()
+    }
+
+    /// Parses the manifest and checks its signature.  If `trusted_signers` is
+    /// not empty, the manifest must also be signed by one of these keys.
+    pub fn verify(
+        data: &[u8],
+        trusted_signers: &[PublicKey],
+    ) -> Result<StatePartsManifest, ManifestError> {
+        let signed: Self = serde_json::from_slice(data)?;
+        let hash = CryptoHash::hash_borsh(&signed.manifest);
+        if !signed.signature.verify(hash.as_ref(), &signed.public_key) {
+            return Err(ManifestError::InvalidSignature);
+        }
+        if !trusted_signers.is_empty() && !trusted_signers.contains(&signed.public_key) {
+            return Err(ManifestError::UntrustedSigner(signed.public_key));
+        }
+        Ok(signed.manifest)
+    }
+}
+
+/// Downloads the manifest of the given shard and epoch and verifies its
+/// signature.  Callers are expected to check that the manifest is for the sync
+/// point they expect.
+pub async fn download_manifest(
+    external: &ExternalConnection,
+    chain_id: &str,
+    epoch_id: &EpochId,
+    epoch_height: EpochHeight,
+    shard_id: ShardId,
+    trusted_signers: &[PublicKey],
+) -> anyhow::Result<StatePartsManifest> {
+    let file_type = StateFileType::Manifest;
+    let location =
+        external_storage_location(chain_id, epoch_id, epoch_height, shard_id, &file_type);
+    let data = external.get_file(shard_id, &location, &file_type).await?;
+    Ok(SignedStatePartsManifest::verify(&data, trusted_signers)?)
+}
+
+/// Manifest of a single shard and sync point, downloaded at most once.
+#[derive(Default)]
+struct ManifestEntry {
+    manifest: tokio::sync::OnceCell<Arc<StatePartsManifest>>,
+    /// The last failure to get the manifest and when it happened.
+    last_failure: Mutex<Option<(Instant, String)>>,
+}
+
+/// Verified manifests of shards being synced from external storage.
+///
+/// Manifests are downloaded on first use so that they are available for
+/// checking parts even if the node restarted after downloading the header.
+#[derive(Clone)]
+pub struct ManifestCache {
+    clock: Clock,
+    trusted_signers: Arc<[PublicKey]>,
+    manifests: Arc<Mutex<lru::LruCache<(ShardId, CryptoHash), Arc<ManifestEntry>>>>,
+}
+
+impl ManifestCache {
+    pub fn new(clock: Clock, trusted_signers: Vec<PublicKey>) -> Self {
+        let size = NonZeroUsize::new(MANIFEST_CACHE_SIZE).unwrap();
+        Self {
+            clock,
+            trusted_signers: trusted_signers.into(),
+            manifests: Arc::new(Mutex::new(lru::LruCache::new(size))),
+        }
+    }
+
+    /// Returns the manifest to check files of the shard against or `None` if
+    /// no trusted signers are configured and thus manifests aren't used.
+    ///
+    /// Concurrent requests for the same manifest wait for a single download,
+    /// while requests for other manifests proceed independently.  A failure
+    /// is returned to all the requests for the manifest during
+    /// `MANIFEST_FAILURE_BACKOFF`, so that a missing manifest isn't downloaded
+    /// again for every part.
+    pub async fn get(
+        &self,
+        external: &ExternalConnection,
+        chain_id: &str,
+        epoch_id: &EpochId,
+        epoch_height: EpochHeight,
+        shard_id: ShardId,
+        sync_hash: &CryptoHash,
+    ) -> Result<Option<Arc<StatePartsManifest>>, String> {
+        if self.trusted_signers.is_empty() {
+            return Ok(None);
+        }
+        let entry = self
+            .manifests
+            .lock()
+            .unwrap()
+            .get_or_insert((shard_id, *sync_hash), Default::default)
+            .clone();
+        // Checked again once the download of a concurrent request failed.
+        let check_backoff = || match &*entry.last_failure.lock().unwrap() {
+            Some((failed_at, err)) if self.clock.now() < *failed_at + MANIFEST_FAILURE_BACKOFF => {
+                Err(err.clone())
+            }
+            _ => Ok(()),
+        };
+        if let Some(manifest) = entry.manifest.get() {
+            return Ok(Some(manifest.clone()));
+        }
+        check_backoff()?;
+        let manifest = entry
+            .manifest
+            .get_or_try_init(|| async {
+                check_backoff()?;
+                let result = download_manifest(
+                    external,
+                    chain_id,
+                    epoch_id,
+                    epoch_height,
+                    shard_id,
+                    &self.trusted_signers,
+                )
+                .await
+                .and_then(|manifest| {
+                    if manifest.shard_id != shard_id || &manifest.sync_hash != sync_hash {
+                        return Err(ManifestError::WrongManifest {
+                            shard_id: manifest.shard_id,
+                            sync_hash: manifest.sync_hash,
+                        }
+                        .into());
+                    }
+                    Ok(Arc::new(manifest))
+                })
+                .map_err(|err| format!("Failed to get state parts manifest: {err:#}"));
+                if let Err(err) = &result {
+                    *entry.last_failure.lock().unwrap() = Some((self.clock.now(), err.clone()));
+                }
+                result
+            })
+            .await?;
+        Ok(Some(manifest.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ManifestCache, ManifestError, ManifestFile, ManifestPart, SignedStatePartsManifest,
+        StatePartsManifest, MANIFEST_FAILURE_BACKOFF,
+    };
+    use crate::sync::external::{external_storage_location, ExternalConnection, StateFileType};
+    use futures::executor::block_on;
+    use near_async::time::FakeClock;
+    use near_chain_configs::StateFileCompression;
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::EpochId;
+
+    fn manifest(parts: &[&[u8]]) -> StatePartsManifest {
+        StatePartsManifest {
+            chain_id: "test".to_string(),
+            epoch_id: EpochId::default(),
+            epoch_height: 1,
+            shard_id: 0,
+            sync_hash: CryptoHash::hash_bytes(b"sync"),
+            state_root: CryptoHash::hash_bytes(b"root"),
+            header: ManifestFile::new(b"header"),
+            parts: parts
+                .iter()
+                .enumerate()
+                .map(|(part_id, data)| {
+                    let ManifestFile { size, hash } = ManifestFile::new(data);
+                    ManifestPart { part_id: part_id as u64, size, hash }
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_manifest_verification() {
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let other = InMemorySigner::from_seed("other".parse().unwrap(), KeyType::ED25519, "other");
+        let manifest = manifest(&[b"part0", b"part1"]);
+        let data = SignedStatePartsManifest::new(manifest.clone(), &signer).to_bytes();
+
+        assert_eq!(SignedStatePartsManifest::verify(&data, &[]).unwrap(), manifest);
+        assert_eq!(
+            SignedStatePartsManifest::verify(&data, &[signer.public_key()]).unwrap(),
+            manifest
+        );
+        assert!(matches!(
+            SignedStatePartsManifest::verify(&data, &[other.public_key()]),
+            Err(ManifestError::UntrustedSigner(_))
+        ));
+
+        let mut tampered = SignedStatePartsManifest::new(manifest.clone(), &signer);
+        tampered.manifest.parts[1].size += 1;
+        assert!(matches!(
+            SignedStatePartsManifest::verify(&tampered.to_bytes(), &[]),
+            Err(ManifestError::InvalidSignature)
+        ));
+
+        manifest.check_header(b"header").unwrap();
+        manifest.check_part(0, b"part0").unwrap();
+        manifest.check_part(1, b"part1").unwrap();
+        manifest.check_num_parts(2).unwrap();
+        assert!(matches!(
+            manifest.check_part(0, b"part1"),
+            Err(ManifestError::HashMismatch { .. })
+        ));
+        assert!(matches!(manifest.check_part(1, b"part"), Err(ManifestError::SizeMismatch { .. })));
+        assert!(matches!(manifest.check_part(2, b"part2"), Err(ManifestError::UnknownPart(2))));
+        assert!(manifest.check_num_parts(3).is_err());
+    }
+
+    #[test]
+    fn test_manifest_cache() {
+        let clock = FakeClock::default();
+        let root_dir = tempfile::tempdir().unwrap();
+        let external = ExternalConnection::Filesystem { root_dir: root_dir.path().to_path_buf() };
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let manifest = manifest(&[b"part0"]);
+        let cache = ManifestCache::new(clock.clock(), vec![signer.public_key()]);
+        let get = || {
+            block_on(cache.get(
+                &external,
+                &manifest.chain_id,
+                &manifest.epoch_id,
+                manifest.epoch_height,
+                manifest.shard_id,
+                &manifest.sync_hash,
+            ))
+        };
+        assert!(get().is_err());
+
+        let location = external_storage_location(
+            &manifest.chain_id,
+            &manifest.epoch_id,
+            manifest.epoch_height,
+            manifest.shard_id,
+            &StateFileType::Manifest,
+        );
+        block_on(external.put_file(
+            StateFileType::Manifest,
+            &SignedStatePartsManifest::new(manifest.clone(), &signer).to_bytes(),
+            manifest.shard_id,
+            &location,
+            StateFileCompression::None,
+        ))
+        .unwrap();
+        // The failure is returned until the back-off passes.
+        assert!(get().is_err());
+        clock.advance(MANIFEST_FAILURE_BACKOFF);
+        assert_eq!(*get().unwrap().unwrap(), manifest);
+
+        // Once downloaded, the manifest is not downloaded again.
+        std::fs::remove_file(root_dir.path().join(&location)).unwrap();
+        assert_eq!(*get().unwrap().unwrap(), manifest);
+    }
+}
@@ -2,5 +2,6 @@ pub mod adapter;
 pub mod block;
 pub mod external;
 pub mod header;
+pub mod manifest;
 pub mod state;
 pub mod sync_actor;
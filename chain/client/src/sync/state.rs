@@ -64,6 +64,7 @@ use tokio::sync::{Semaphore, TryAcquireError};
 use tracing::info;
 
 use super::external::StateFileType;
+use super::manifest::{ManifestCache, StatePartsManifest};
 
 /// Maximum number of state parts to request per peer on each round when node is trying to download the state.
 pub const MAX_STATE_PART_REQUEST: u64 = 16;
@@ -129,6 +130,8 @@ enum StateSyncInner {
         semaphore: Arc<tokio::sync::Semaphore>,
         /// Connection to the external storage.
         external: ExternalConnection,
+        /// Manifests to check the downloaded files against.
+        manifests: ManifestCache,
     },
 }
 
@@ -180,6 +183,7 @@ impl StateSync {
                 location,
                 num_concurrent_requests,
                 num_concurrent_requests_during_catchup,
+                trusted_manifest_signers,
             }) => {
                 let external = match location {
                     ExternalStorageLocation::S3 { bucket, region, .. } => {
@@ -211,6 +215,7 @@ impl StateSync {
                     chain_id: chain_id.to_string(),
                     semaphore: Arc::new(tokio::sync::Semaphore::new(num_permits)),
                     external,
+                    manifests: ManifestCache::new(clock.clone(), trusted_manifest_signers.clone()),
                 }
             }
         };
@@ -643,7 +648,7 @@ impl StateSync {
                         }),
                 );
             }
-            StateSyncInner::External { chain_id, external, .. } => {
+            StateSyncInner::External { chain_id, external, manifests, .. } => {
                 let sync_block_header = chain.get_block_header(&sync_hash).unwrap();
                 let epoch_id = sync_block_header.epoch_id();
                 let epoch_info = chain.epoch_manager.get_epoch_info(epoch_id).unwrap();
@@ -656,6 +661,7 @@ impl StateSync {
                     epoch_height,
                     &chain_id.clone(),
                     external.clone(),
+                    manifests.clone(),
                     state_parts_future_spawner,
                     self.state_parts_mpsc_tx.clone(),
                 );
@@ -711,7 +717,7 @@ impl StateSync {
                     );
                 }
             }
-            StateSyncInner::External { chain_id, semaphore, external } => {
+            StateSyncInner::External { chain_id, semaphore, external, manifests } => {
                 let sync_block_header = chain.get_block_header(&sync_hash).unwrap();
                 let epoch_id = sync_block_header.epoch_id();
                 let epoch_info = chain.epoch_manager.get_epoch_info(epoch_id).unwrap();
@@ -734,6 +740,7 @@ impl StateSync {
                         state_root,
                         semaphore.clone(),
                         external.clone(),
+                        manifests.clone(),
                         runtime_adapter.clone(),
                         state_parts_future_spawner,
                         self.state_parts_mpsc_tx.clone(),
@@ -1190,6 +1197,7 @@ async fn download_header_from_external_storage(
     sync_hash: CryptoHash,
     location: String,
     external: ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> Result<StateSyncFileDownloadResult, std::string::String> {
     external
     .get_file(shard_id, &location, &StateFileType::StateHeader)
@@ -1197,6 +1205,9 @@ async fn download_header_from_external_storage(
     .map_err(|err| err.to_string())
     .and_then(|data| {
         info!(target: "sync", ?shard_id, "downloaded state header");
+        if let Some(manifest) = &manifest {
+            manifest.check_header(&data).map_err(|err| err.to_string())?;
+        }
         let header_length = data.len() as u64;
         ShardStateSyncResponseHeader::try_from_slice(&data)
         .map(|header| StateSyncFileDownloadResult::StateHeader { header_length , header })
@@ -1216,6 +1227,7 @@ fn request_header_from_external_storage(
     epoch_height: EpochHeight,
     chain_id: &str,
     external: ExternalConnection,
+    manifests: ManifestCache,
     state_parts_future_spawner: &dyn FutureSpawner,
     state_parts_mpsc_tx: Sender<StateSyncGetFileResult>,
 ) {
@@ -1233,10 +1245,20 @@ fn request_header_from_external_storage(
         shard_id,
         &StateFileType::StateHeader,
     );
+    let chain_id = chain_id.to_string();
+    let epoch_id = *epoch_id;
     state_parts_future_spawner.spawn(
         "download_header_from_external_storage",
         async move {
-            let result = download_header_from_external_storage(shard_id, sync_hash, location, external).await;
+            let manifest = manifests
+                .get(&external, &chain_id, &epoch_id, epoch_height, shard_id, &sync_hash)
+                .await;
+            let result = match manifest {
+                Ok(manifest) => {
+                    download_header_from_external_storage(shard_id, sync_hash, location, external, manifest).await
+                }
+                Err(err) => Err(err),
+            };
             match state_parts_mpsc_tx.send(StateSyncGetFileResult {
                 sync_hash,
                 shard_id,
@@ -1261,6 +1283,7 @@ async fn download_and_store_part_from_external_storage(
     state_root: StateRoot,
     external: ExternalConnection,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> Result<StateSyncFileDownloadResult, String> {
     external
     .get_file(shard_id, &location, file_type)
@@ -1268,6 +1291,12 @@ async fn download_and_store_part_from_external_storage(
     .map_err(|err| err.to_string())
     .and_then(|data|  {
         info!(target: "sync", ?shard_id, ?part_id, "downloaded state part");
+        if let Some(manifest) = &manifest {
+            manifest
+                .check_num_parts(part_id.total)
+                .and_then(|()| manifest.check_part(part_id.idx, &data))
+                .map_err(|err| err.to_string())?;
+        }
         if runtime_adapter.validate_state_part(&state_root, part_id, &data) {
             let mut store_update = runtime_adapter.store().store_update();
             borsh::to_vec(&StatePartKey(sync_hash, shard_id, part_id.idx))
@@ -1296,6 +1325,7 @@ fn request_part_from_external_storage(
     state_root: StateRoot,
     semaphore: Arc<Semaphore>,
     external: ExternalConnection,
+    manifests: ManifestCache,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
     state_parts_future_spawner: &dyn FutureSpawner,
     state_parts_mpsc_tx: Sender<StateSyncGetFileResult>,
@@ -1317,21 +1347,30 @@ fn request_part_from_external_storage(
 
     match semaphore.try_acquire_owned() {
         Ok(permit) => {
+            let chain_id = chain_id.to_string();
+            let epoch_id = *epoch_id;
             state_parts_future_spawner.spawn(
                 "download_and_store_part_from_external_storage",
                 async move {
                     let file_type = StateFileType::StatePart { part_id, num_parts };
                     let part_id = PartId{ idx: part_id, total: num_parts };
-                    let result = download_and_store_part_from_external_storage(
-                        part_id,
-                        &file_type,
-                        location,
-                        shard_id,
-                        sync_hash,
-                        state_root,
-                        external,
-                        runtime_adapter)
+                    let manifest = manifests
+                        .get(&external, &chain_id, &epoch_id, epoch_height, shard_id, &sync_hash)
                         .await;
+                    let result = match manifest {
+                        Ok(manifest) => download_and_store_part_from_external_storage(
+                            part_id,
+                            &file_type,
+                            location,
+                            shard_id,
+                            sync_hash,
+                            state_root,
+                            external,
+                            runtime_adapter,
+                            manifest)
+                            .await,
+                        Err(err) => Err(err),
+                    };
 
                     match state_parts_mpsc_tx.send(StateSyncGetFileResult {
                         sync_hash,
@@ -456,6 +456,7 @@ fn process_peer_manager_message_default(
                 connection_established_time: near_async::time::Instant::now(),
                 peer_type: PeerType::Outbound,
                 nonce: 3,
+                reputation: 0,
             })
             .collect();
         let peers2 = peers.iter().filter_map(|it| it.full_peer_info.clone().into()).collect();
@@ -23,7 +23,7 @@ use near_client_primitives::types::{
     GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
     GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
     GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    SimulateTransaction, SimulateTransactionError, TxStatus, TxStatusError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -41,24 +41,27 @@ use near_primitives::epoch_info::EpochInfo;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
+use near_primitives::profile_data_v3::ProfileDataV3;
 use near_primitives::receipt::Receipt;
+use near_primitives::shard_layout::account_id_to_shard_id;
 use near_primitives::sharding::ShardChunk;
 use near_primitives::state_sync::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV3,
 };
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{ExecutionMetadata, SignedTransaction};
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId,
-    ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
+    AccountId, Balance, BlockHeight, BlockId, BlockReference, EpochReference, Finality, Gas,
+    MaybeBlockId, ShardId, StateChanges, StateChangesExt, SyncCheckpoint, TransactionOrReceiptId,
+    ValidatorInfoIdentifier,
 };
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
-    LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
-    SignedTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
-    TxExecutionStatus, TxStatusView,
+    BlockView, ChunkView, EpochValidatorInfo, ExecutionMetadataView, ExecutionOutcomeWithIdView,
+    ExecutionStatusView, FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum,
+    FinalExecutionStatus, GasPriceView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
+    QueryResponse, ReceiptView, SignedTransactionView, SimulateTransactionView,
+    SplitStorageInfoView, StateChangesKindsView, StateChangesView, TxExecutionStatus, TxStatusView,
 };
 use near_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
 use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
@@ -357,6 +360,7 @@ impl ViewClientActorInner {
         let account_id = match &msg.request {
             QueryRequest::ViewAccount { account_id, .. } => account_id,
             QueryRequest::ViewState { account_id, .. } => account_id,
+            QueryRequest::ViewStatePaged { account_id, .. } => account_id,
             QueryRequest::ViewAccessKey { account_id, .. } => account_id,
             QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
             QueryRequest::CallFunction { account_id, .. } => account_id,
@@ -975,22 +979,30 @@ impl Handler<GetStateChangesWithCauseInBlockForTrackedShards> for ViewClientActo
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["GetStateChangesWithCauseInBlockForTrackedShards"])
             .start_timer();
-        let state_changes_with_cause_in_block =
-            self.chain.chain_store().get_state_changes_with_cause_in_block(&msg.block_hash)?;
+        let shard_layout = self
+            .epoch_manager
+            .get_shard_layout(&msg.epoch_id)
+            .map_err(|err| GetStateChangesError::IOError { error_message: err.to_string() })?;
+        // The filters are applied to the raw changes, so that the changes which
+        // are filtered out are never decoded.
+        let state_changes_with_cause_in_block = self
+            .chain
+            .chain_store()
+            .get_state_changes_with_cause_in_block_filtered(&msg.block_hash, |trie_key| {
+                let Some(account_id) = trie_key.get_account_id() else { return true };
+                if msg.accounts.as_ref().is_some_and(|accounts| !(accounts.0)(&account_id)) {
+                    return false;
+                }
+                msg.shard_ids.as_ref().map_or(true, |shard_ids| {
+                    shard_ids.contains(&account_id_to_shard_id(&account_id, &shard_layout))
+                })
+            })?;
 
         let mut state_changes_with_cause_split_by_shard_id: HashMap<ShardId, StateChangesView> =
             HashMap::new();
         for state_change_with_cause in state_changes_with_cause_in_block {
             let account_id = state_change_with_cause.value.affected_account_id();
-            let shard_id = match self
-                .epoch_manager
-                .account_id_to_shard_id(account_id, &msg.epoch_id)
-            {
-                Ok(shard_id) => shard_id,
-                Err(err) => {
-                    return Err(GetStateChangesError::IOError { error_message: err.to_string() })
-                }
-            };
+            let shard_id = account_id_to_shard_id(account_id, &shard_layout);
 
             let state_changes =
                 state_changes_with_cause_split_by_shard_id.entry(shard_id).or_default();
@@ -1210,6 +1222,106 @@ impl Handler<GetProtocolConfig> for ViewClientActorInner {
     }
 }
 
+impl Handler<SimulateTransaction> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: SimulateTransaction,
+    ) -> Result<SimulateTransactionView, SimulateTransactionError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["SimulateTransaction"])
+            .start_timer();
+        let header = self
+            .get_block_header_by_reference(&msg.block_reference)?
+            .ok_or(SimulateTransactionError::NoSyncedBlocks)?;
+        let block = self.chain.get_block(header.hash())?;
+        // The transaction is simulated as if it was included in the chunk following
+        // the block, so the shards and the validation are those of the epoch of that
+        // chunk, which is the epoch the runtime simulates the transaction in.
+        let epoch_id =
+            self.epoch_manager.get_epoch_id_from_prev_block(header.hash()).into_chain_error()?;
+
+        let mut chunk_extras = HashMap::new();
+        for shard_id in self.epoch_manager.shard_ids(&epoch_id).into_chain_error()? {
+            let shard_uid =
+                self.epoch_manager.shard_id_to_uid(shard_id, &epoch_id).into_chain_error()?;
+            match self.chain.get_chunk_extra(header.hash(), &shard_uid) {
+                Ok(chunk_extra) => {
+                    chunk_extras.insert(shard_id, chunk_extra);
+                }
+                // The node doesn't track the shard.
+                Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let signer_shard_id = self
+            .epoch_manager
+            .account_id_to_shard_id(msg.transaction.transaction.signer_id(), &epoch_id)
+            .into_chain_error()?;
+        let Some(signer_chunk_extra) = chunk_extras.get(&signer_shard_id) else {
+            return Err(SimulateTransactionError::UnavailableShard(signer_shard_id));
+        };
+
+        let protocol_version =
+            self.epoch_manager.get_epoch_protocol_version(&epoch_id).into_chain_error()?;
+        if let Some(err) = self.runtime.validate_tx(
+            header.next_gas_price(),
+            Some(*signer_chunk_extra.state_root()),
+            &msg.transaction,
+            msg.verify_signature,
+            &epoch_id,
+            protocol_version,
+            None,
+        )? {
+            return Err(SimulateTransactionError::InvalidTransaction(err));
+        }
+
+        let result = self.runtime.simulate_transaction(&block, chunk_extras, &msg.transaction)?;
+
+        let mut total_gas_burnt: Gas = 0;
+        let mut total_tokens_burnt: Balance = 0;
+        let mut gas_profile = ProfileDataV3::default();
+        for outcome in &result.outcomes {
+            total_gas_burnt = total_gas_burnt.saturating_add(outcome.outcome.gas_burnt);
+            total_tokens_burnt = total_tokens_burnt.saturating_add(outcome.outcome.tokens_burnt);
+            if let ExecutionMetadata::V3(profile) = &outcome.outcome.metadata {
+                gas_profile.merge(profile);
+            }
+        }
+        let gas_profile = ExecutionMetadataView::from(ExecutionMetadata::V3(Box::new(gas_profile)))
+            .gas_profile
+            .unwrap_or_default();
+        let state_changes = StateChanges::from_changes(result.state_changes.into_iter().map(Ok))
+            .map_err(|err| SimulateTransactionError::IOError(err.to_string()))?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        // The outcomes aren't included in any block, so they carry the hash of
+        // the block the simulation was based on and no proofs.
+        let outcomes = result
+            .outcomes
+            .into_iter()
+            .map(|outcome| ExecutionOutcomeWithIdView {
+                proof: vec![],
+                block_hash: *header.hash(),
+                id: outcome.id,
+                outcome: outcome.outcome.into(),
+            })
+            .collect();
+        Ok(SimulateTransactionView {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            outcomes,
+            total_gas_burnt,
+            total_tokens_burnt,
+            gas_profile,
+            state_changes,
+            unfinished_receipt_ids: result.unfinished_receipt_ids,
+        })
+    }
+}
+
 #[cfg(feature = "test_features")]
 use crate::NetworkAdversarialMessage;
 
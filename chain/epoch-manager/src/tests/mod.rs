@@ -2932,24 +2932,27 @@ fn test_chunk_header(h: &[CryptoHash], signer: &ValidatorSigner) -> ShardChunkHe
     let congestion_info = ProtocolFeature::CongestionControl
         .enabled(PROTOCOL_VERSION)
         .then_some(CongestionInfo::default());
-    ShardChunkHeader::V3(ShardChunkHeaderV3::new(
-        PROTOCOL_VERSION,
-        h[0],
-        h[2],
-        h[2],
-        h[2],
-        0,
-        1,
-        0,
-        0,
-        0,
-        0,
-        h[2],
-        h[2],
-        vec![],
-        congestion_info,
-        signer,
-    ))
+    ShardChunkHeader::V3(
+        ShardChunkHeaderV3::new(
+            PROTOCOL_VERSION,
+            h[0],
+            h[2],
+            h[2],
+            h[2],
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            h[2],
+            h[2],
+            vec![],
+            congestion_info,
+            signer,
+        )
+        .unwrap(),
+    )
 }
 
 #[test]
@@ -2988,7 +2991,7 @@ fn test_verify_chunk_endorsements() {
     let chunk_header = test_chunk_header(&h, signer.as_ref());
 
     // check chunk endorsement validity
-    let mut chunk_endorsement = ChunkEndorsementV1::new(chunk_header.chunk_hash(), signer.as_ref());
+    let mut chunk_endorsement = ChunkEndorsementV1::new(&chunk_header, signer.as_ref()).unwrap();
     assert!(epoch_manager.verify_chunk_endorsement(&chunk_header, &chunk_endorsement).unwrap());
 
     // check invalid chunk endorsement signature
@@ -2996,7 +2999,8 @@ fn test_verify_chunk_endorsements() {
     assert!(!epoch_manager.verify_chunk_endorsement(&chunk_header, &chunk_endorsement).unwrap());
 
     // check chunk endorsement invalidity when chunk header and chunk endorsement don't match
-    let chunk_endorsement = ChunkEndorsementV1::new(h[3].into(), signer.as_ref());
+    let other_chunk_header = test_chunk_header(&h[3..], signer.as_ref());
+    let chunk_endorsement = ChunkEndorsementV1::new(&other_chunk_header, signer.as_ref()).unwrap();
     let err =
         epoch_manager.verify_chunk_endorsement(&chunk_header, &chunk_endorsement).unwrap_err();
     match err {
@@ -3006,7 +3010,7 @@ fn test_verify_chunk_endorsements() {
 
     // check chunk endorsement invalidity when signer is not chunk validator
     let bad_signer = Arc::new(create_test_signer("test2"));
-    let chunk_endorsement = ChunkEndorsementV1::new(chunk_header.chunk_hash(), bad_signer.as_ref());
+    let chunk_endorsement = ChunkEndorsementV1::new(&chunk_header, bad_signer.as_ref()).unwrap();
     let err =
         epoch_manager.verify_chunk_endorsement(&chunk_header, &chunk_endorsement).unwrap_err();
     match err {
@@ -3049,7 +3053,8 @@ fn test_verify_partial_witness_signature() {
         "witness".bytes().collect(),
         7,
         signer.as_ref(),
-    );
+    )
+    .unwrap();
     assert!(epoch_manager.verify_partial_witness_signature(&partial_witness).unwrap());
 
     // Check invalid chunk state witness signature.
@@ -3065,7 +3070,8 @@ fn test_verify_partial_witness_signature() {
         "witness".bytes().collect(),
         7,
         bad_signer.as_ref(),
-    );
+    )
+    .unwrap();
     assert!(!epoch_manager.verify_partial_witness_signature(&bad_partial_witness).unwrap());
 }
 
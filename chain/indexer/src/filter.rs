@@ -0,0 +1,249 @@
+//! Filter applied by the streamer while building `StreamerMessage`s, so the
+//! data nobody is interested in is neither fetched nor sent to the consumer.
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use near_indexer_primitives::IndexerTransactionWithOutcome;
+use near_primitives::types::{AccountId, ShardId};
+use near_primitives::views;
+
+/// Account ID to match, either exactly (`app.near`) or together with all its
+/// sub-accounts on any depth (`*.app.near`, which doesn't match `app.near`
+/// itself).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AccountIdPattern {
+    Exact(AccountId),
+    SubAccountsOf(AccountId),
+}
+
+impl AccountIdPattern {
+    pub fn matches(&self, account_id: &AccountId) -> bool {
+        match self {
+            Self::Exact(expected) => expected == account_id,
+            Self::SubAccountsOf(parent) => account_id
+                .as_str()
+                .strip_suffix(parent.as_str())
+                .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+        }
+    }
+}
+
+impl std::str::FromStr for AccountIdPattern {
+    type Err = near_primitives::account::id::ParseAccountError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        match pattern.strip_prefix("*.") {
+            Some(parent) => Ok(Self::SubAccountsOf(parent.parse()?)),
+            None => Ok(Self::Exact(pattern.parse()?)),
+        }
+    }
+}
+
+impl TryFrom<String> for AccountIdPattern {
+    type Error = near_primitives::account::id::ParseAccountError;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        pattern.parse()
+    }
+}
+
+impl From<AccountIdPattern> for String {
+    fn from(pattern: AccountIdPattern) -> Self {
+        pattern.to_string()
+    }
+}
+
+impl std::fmt::Display for AccountIdPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(account_id) => write!(f, "{}", account_id),
+            Self::SubAccountsOf(account_id) => write!(f, "*.{}", account_id),
+        }
+    }
+}
+
+/// Kind of action a transaction or a receipt must contain to be streamed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKindFilter {
+    CreateAccount,
+    DeployContract,
+    /// Function calls to any of the given methods, or to any method if the
+    /// list is empty.
+    FunctionCall {
+        #[serde(default)]
+        method_names: Vec<String>,
+    },
+    Transfer,
+    Stake,
+    AddKey,
+    DeleteKey,
+    DeleteAccount,
+    Delegate,
+}
+
+impl ActionKindFilter {
+    fn matches(&self, action: &views::ActionView) -> bool {
+        use views::ActionView;
+        match self {
+            Self::CreateAccount => matches!(action, ActionView::CreateAccount),
+            Self::DeployContract => matches!(action, ActionView::DeployContract { .. }),
+            Self::FunctionCall { method_names } => match action {
+                ActionView::FunctionCall { method_name, .. } => {
+                    method_names.is_empty() || method_names.contains(method_name)
+                }
+                _ => false,
+            },
+            Self::Transfer => matches!(action, ActionView::Transfer { .. }),
+            Self::Stake => matches!(action, ActionView::Stake { .. }),
+            Self::AddKey => matches!(action, ActionView::AddKey { .. }),
+            Self::DeleteKey => matches!(action, ActionView::DeleteKey { .. }),
+            Self::DeleteAccount => matches!(action, ActionView::DeleteAccount { .. }),
+            Self::Delegate => matches!(action, ActionView::Delegate { .. }),
+        }
+    }
+}
+
+/// Restricts what ends up in the `StreamerMessage`s.  Every empty list means
+/// "no restriction", so the default filter lets everything through.
+///
+/// Blocks are always streamed, even if nothing in them matches the filter.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct IndexerFilter {
+    /// Transactions are kept if their signer or receiver matches, receipts
+    /// and their execution outcomes if the receiver of the receipt (which is
+    /// the account the outcome is executed on) matches, and state changes if
+    /// they touch a matching account.
+    #[serde(default)]
+    pub accounts: Vec<AccountIdPattern>,
+    /// Shards to stream.  Data of the other shards is not fetched and their
+    /// `IndexerShard`s are left empty, so that `shards[shard_id]` still
+    /// refers to the shard with the given ID.
+    #[serde(default)]
+    pub shard_ids: HashSet<ShardId>,
+    /// Transactions and action receipts are kept if they contain at least one
+    /// matching action.  Data receipts are dropped when this is set.
+    #[serde(default)]
+    pub actions: Vec<ActionKindFilter>,
+}
+
+impl IndexerFilter {
+    pub(crate) fn matches_shard(&self, shard_id: ShardId) -> bool {
+        self.shard_ids.is_empty() || self.shard_ids.contains(&shard_id)
+    }
+
+    pub(crate) fn matches_account(&self, account_id: &AccountId) -> bool {
+        self.accounts.is_empty() || self.accounts.iter().any(|pattern| pattern.matches(account_id))
+    }
+
+    /// Shards whose state changes to fetch, all of them if `None`.
+    pub(crate) fn shard_ids(&self) -> Option<HashSet<ShardId>> {
+        (!self.shard_ids.is_empty()).then(|| self.shard_ids.clone())
+    }
+
+    /// Accounts whose state changes to fetch, all of them if `None`.
+    pub(crate) fn account_filter(&self) -> Option<near_client::AccountFilter> {
+        if self.accounts.is_empty() {
+            return None;
+        }
+        let accounts = self.accounts.clone();
+        Some(near_client::AccountFilter(Arc::new(move |account_id| {
+            accounts.iter().any(|pattern| pattern.matches(account_id))
+        })))
+    }
+
+    fn matches_actions(&self, actions: &[views::ActionView]) -> bool {
+        self.actions.is_empty()
+            || actions.iter().any(|action| self.actions.iter().any(|kind| kind.matches(action)))
+    }
+
+    pub(crate) fn matches_transaction(&self, transaction: &IndexerTransactionWithOutcome) -> bool {
+        let transaction = &transaction.transaction;
+        (self.matches_account(&transaction.signer_id)
+            || self.matches_account(&transaction.receiver_id))
+            && self.matches_actions(&transaction.actions)
+    }
+
+    /// Tells whether the receipts sent to `receiver_id`, and hence their
+    /// execution outcomes, may match.  It allows leaving out outcomes before
+    /// their receipts are fetched.
+    pub(crate) fn matches_receipt_receiver(&self, receiver_id: &AccountId) -> bool {
+        self.matches_account(receiver_id)
+    }
+
+    /// Receipts and receipt execution outcomes are both matched against their
+    /// receipt with this.
+    pub(crate) fn matches_receipt(&self, receipt: &views::ReceiptView) -> bool {
+        if !self.matches_receipt_receiver(&receipt.receiver_id) {
+            return false;
+        }
+        match &receipt.receipt {
+            views::ReceiptEnumView::Action { actions, .. } => self.matches_actions(actions),
+            views::ReceiptEnumView::Data { .. } => self.actions.is_empty(),
+        }
+    }
+}
+
+/// Returns the account the state change touches.
+pub(crate) fn state_change_account_id(value: &views::StateChangeValueView) -> &AccountId {
+    use views::StateChangeValueView;
+    match value {
+        StateChangeValueView::AccountUpdate { account_id, .. }
+        | StateChangeValueView::AccountDeletion { account_id }
+        | StateChangeValueView::AccessKeyUpdate { account_id, .. }
+        | StateChangeValueView::AccessKeyDeletion { account_id, .. }
+        | StateChangeValueView::DataUpdate { account_id, .. }
+        | StateChangeValueView::DataDeletion { account_id, .. }
+        | StateChangeValueView::ContractCodeUpdate { account_id, .. }
+        | StateChangeValueView::ContractCodeDeletion { account_id } => account_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_account_id_pattern() {
+        let exact: AccountIdPattern = "app.near".parse().unwrap();
+        assert!(exact.matches(&account("app.near")));
+        assert!(!exact.matches(&account("sub.app.near")));
+
+        let wildcard: AccountIdPattern = "*.app.near".parse().unwrap();
+        assert_eq!(wildcard, AccountIdPattern::SubAccountsOf(account("app.near")));
+        assert!(wildcard.matches(&account("sub.app.near")));
+        assert!(wildcard.matches(&account("deep.sub.app.near")));
+        assert!(!wildcard.matches(&account("app.near")));
+        assert!(!wildcard.matches(&account("myapp.near")));
+        assert_eq!(wildcard.to_string(), "*.app.near");
+
+        assert!("*app.near".parse::<AccountIdPattern>().is_err());
+    }
+
+    #[test]
+    fn test_filter_deserialization() {
+        let filter: IndexerFilter = serde_json::from_value(serde_json::json!({
+            "accounts": ["app.near", "*.app.near"],
+            "actions": [{"function_call": {"method_names": ["ft_transfer"]}}, "transfer"],
+        }))
+        .unwrap();
+        assert_eq!(filter.accounts.len(), 2);
+        assert!(filter.shard_ids.is_empty());
+        assert!(filter.matches_shard(3));
+        assert!(filter.matches_account(&account("token.app.near")));
+        assert!(!filter.matches_account(&account("other.near")));
+        assert!(filter.matches_actions(&[views::ActionView::Transfer { deposit: 1 }]));
+        assert!(!filter.matches_actions(&[views::ActionView::CreateAccount]));
+        assert!(!filter.matches_actions(&[views::ActionView::FunctionCall {
+            method_name: "ft_mint".to_string(),
+            args: vec![].into(),
+            gas: 0,
+            deposit: 0,
+        }]));
+    }
+}
@@ -14,8 +14,11 @@ pub use near_indexer_primitives::{
     StreamerMessage,
 };
 
-pub use streamer::build_streamer_message;
+pub use filter::{AccountIdPattern, ActionKindFilter, IndexerFilter};
+pub use streamer::{build_filtered_streamer_message, build_streamer_message, StreamerCheckpoint};
 
+mod filter;
+pub mod sinks;
 mod streamer;
 
 pub const INDEXER: &str = "indexer";
@@ -61,6 +64,10 @@ pub enum SyncModeEnum {
     FromInterruption,
     /// Specific block height to start syncing from
     BlockHeight(u64),
+    /// Starts syncing right after the last block acknowledged through
+    /// `StreamerCheckpoint`, or from the latest block if nothing has been
+    /// acknowledged yet
+    FromCheckpoint,
 }
 
 /// Enum to define whether await for node to be fully synced or stream while syncing (useful for indexing from genesis)
@@ -83,6 +90,8 @@ pub struct IndexerConfig {
     pub await_for_node_synced: AwaitForNodeSyncedEnum,
     /// Tells whether to validate the genesis file before starting
     pub validate_genesis: bool,
+    /// Restricts which shards, transactions, receipts and state changes are streamed
+    pub filter: IndexerFilter,
 }
 
 /// This is the core component, which handles `nearcore` and internal `streamer`.
@@ -131,13 +140,39 @@ impl Indexer {
             self.view_client.clone(),
             self.client.clone(),
             self.indexer_config.clone(),
-            self.near_config.config.store.clone(),
-            self.near_config.config.archive,
+            self.open_db().expect("Unable to open indexer db"),
+            None,
             sender,
         ));
         receiver
     }
 
+    /// Same as `streamer`, but the consumer is expected to acknowledge every received block through the returned `StreamerCheckpoint` once it's durably handled. Combined with `SyncModeEnum::FromCheckpoint` this gives at-least-once delivery: after a restart streaming resumes right after the last acknowledged block, and blocks whose data can't be fetched are retried rather than skipped. If they still can't be fetched after a number of retries, the stream ends.
+    pub fn checkpointed_streamer(
+        &self,
+    ) -> anyhow::Result<(mpsc::Receiver<StreamerMessage>, StreamerCheckpoint)> {
+        let (sender, receiver) = mpsc::channel(100);
+        let db = self.open_db()?;
+        let checkpoint = StreamerCheckpoint::new(db.clone()).context("StreamerCheckpoint::new")?;
+        actix::spawn(streamer::start(
+            self.view_client.clone(),
+            self.client.clone(),
+            self.indexer_config.clone(),
+            db,
+            Some(checkpoint.clone()),
+            sender,
+        ));
+        Ok((receiver, checkpoint))
+    }
+
+    fn open_db(&self) -> anyhow::Result<std::sync::Arc<rocksdb::DB>> {
+        streamer::open_indexer_db(
+            &self.indexer_config,
+            &self.near_config.config.store,
+            self.near_config.config.archive,
+        )
+    }
+
     /// Expose neard config
     pub fn near_config(&self) -> &nearcore::NearConfig {
         &self.near_config
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::types::BlockHeight;
+
+use super::StreamerMessageSink;
+
+/// Configuration of `JsonLinesSink`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct JsonLinesSinkConfig {
+    /// Directory the files are written to.  Created if it doesn't exist.
+    pub directory: PathBuf,
+    /// A new file is started once the current one holds that many blocks.
+    #[serde(default = "default_max_blocks_per_file")]
+    pub max_blocks_per_file: u64,
+    /// A new file is started once the current one grows over that many bytes.
+    #[serde(default = "default_max_file_size_bytes")]
+    pub max_file_size_bytes: u64,
+}
+
+fn default_max_blocks_per_file() -> u64 {
+    10_000
+}
+
+fn default_max_file_size_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+
+/// Writes every `StreamerMessage` as a single JSON line into files named after
+/// the height of the first block they contain (`000012345678.jsonl`).
+///
+/// Each message is flushed and synced to disk before it's reported as stored,
+/// so the written blocks survive a crash of the process or of the machine.  A crash in the middle of a
+/// write may leave a truncated last line in the file; the block is streamed
+/// again into the next file after the restart.
+pub struct JsonLinesSink {
+    config: JsonLinesSinkConfig,
+    current: Option<CurrentFile>,
+}
+
+struct CurrentFile {
+    writer: BufWriter<File>,
+    blocks: u64,
+    bytes: u64,
+}
+
+impl JsonLinesSink {
+    pub fn new(config: JsonLinesSinkConfig) -> io::Result<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+        Ok(Self { config, current: None })
+    }
+
+    fn write_line(&mut self, block_height: BlockHeight, line: &[u8]) -> io::Result<()> {
+        if self.current.is_none() {
+            let path = self.config.directory.join(format!("{:012}.jsonl", block_height));
+            let file = File::create(path)?;
+            // Sync the directory, so that the new file itself survives a crash.
+            File::open(&self.config.directory)?.sync_all()?;
+            self.current = Some(CurrentFile { writer: BufWriter::new(file), blocks: 0, bytes: 0 });
+        }
+        let current = self.current.as_mut().unwrap();
+        current.writer.write_all(line)?;
+        current.writer.write_all(b"\n")?;
+        current.writer.flush()?;
+        current.writer.get_ref().sync_data()?;
+        current.blocks += 1;
+        current.bytes += line.len() as u64 + 1;
+
+        if current.blocks >= self.config.max_blocks_per_file
+            || current.bytes >= self.config.max_file_size_bytes
+        {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(current) = self.current.take() {
+            current.writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamerMessageSink for JsonLinesSink {
+    fn write(&mut self, streamer_message: &StreamerMessage) -> io::Result<Option<BlockHeight>> {
+        let block_height = streamer_message.block.header.height;
+        let line = serde_json::to_vec(streamer_message)?;
+        self.write_line(block_height, &line)?;
+        Ok(Some(block_height))
+    }
+
+    fn flush(&mut self) -> io::Result<Option<BlockHeight>> {
+        self.rotate()?;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_files(dir: &tempfile::TempDir) -> Vec<(String, String)> {
+        let mut files = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let name = path.file_name().unwrap().to_str().unwrap().to_string();
+                (name, std::fs::read_to_string(path).unwrap())
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_rotation_by_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = JsonLinesSinkConfig {
+            directory: dir.path().to_path_buf(),
+            max_blocks_per_file: 2,
+            max_file_size_bytes: default_max_file_size_bytes(),
+        };
+        let mut sink = JsonLinesSink::new(config).unwrap();
+        for height in 7..12 {
+            sink.write_line(height, format!("{{\"height\":{}}}", height).as_bytes()).unwrap();
+        }
+        sink.flush().unwrap();
+
+        assert_eq!(
+            list_files(&dir),
+            vec![
+                ("000000000007.jsonl".to_string(), "{\"height\":7}\n{\"height\":8}\n".to_string()),
+                ("000000000009.jsonl".to_string(), "{\"height\":9}\n{\"height\":10}\n".to_string()),
+                ("000000000011.jsonl".to_string(), "{\"height\":11}\n".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = JsonLinesSinkConfig {
+            directory: dir.path().to_path_buf(),
+            max_blocks_per_file: default_max_blocks_per_file(),
+            max_file_size_bytes: 10,
+        };
+        let mut sink = JsonLinesSink::new(config).unwrap();
+        sink.write_line(1, b"short").unwrap();
+        sink.write_line(2, b"long enough").unwrap();
+        sink.write_line(3, b"next").unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(
+            list_files(&dir),
+            vec![
+                ("000000000001.jsonl".to_string(), "short\nlong enough\n".to_string()),
+                ("000000000003.jsonl".to_string(), "next\n".to_string()),
+            ]
+        );
+    }
+}
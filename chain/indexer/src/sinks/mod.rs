@@ -0,0 +1,106 @@
+//! Built-in destinations the `StreamerMessage`s can be drained into, so the
+//! consumers don't have to write the same glue over and over.
+use std::collections::VecDeque;
+use std::io;
+
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::types::BlockHeight;
+
+use crate::{StreamerCheckpoint, INDEXER};
+
+pub use self::json_lines::{JsonLinesSink, JsonLinesSinkConfig};
+#[cfg(feature = "parquet")]
+pub use self::parquet::{ParquetSink, ParquetSinkConfig};
+
+mod json_lines;
+#[cfg(feature = "parquet")]
+mod parquet;
+
+/// Destination for `StreamerMessage`s.
+///
+/// A sink may buffer the messages, so it reports back which blocks are
+/// durably written and can be acknowledged through `StreamerCheckpoint`.
+pub trait StreamerMessageSink: Send {
+    /// Writes the message.  Returns the height up to which (inclusively) all
+    /// the written blocks are durably stored, if that changed.
+    fn write(&mut self, streamer_message: &StreamerMessage) -> io::Result<Option<BlockHeight>>;
+
+    /// Writes out everything buffered so far.  Returns the height up to which
+    /// all the written blocks are durably stored, if that changed.
+    fn flush(&mut self) -> io::Result<Option<BlockHeight>>;
+}
+
+/// Configuration of one of the built-in sinks.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    JsonLines(JsonLinesSinkConfig),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetSinkConfig),
+}
+
+impl SinkConfig {
+    pub fn open(&self) -> io::Result<Box<dyn StreamerMessageSink>> {
+        Ok(match self {
+            Self::JsonLines(config) => Box::new(JsonLinesSink::new(config.clone())?),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(config) => Box::new(ParquetSink::new(config.clone())?),
+        })
+    }
+}
+
+/// Drains the stream into the sink on a dedicated thread, as writing files
+/// blocks.  When a `checkpoint` is given, blocks are acknowledged once the
+/// sink reports them durably stored.
+///
+/// The thread finishes once the stream is closed or the sink fails.
+pub fn spawn_sink(
+    stream: mpsc::Receiver<StreamerMessage>,
+    sink: Box<dyn StreamerMessageSink>,
+    checkpoint: Option<StreamerCheckpoint>,
+) -> std::thread::JoinHandle<io::Result<()>> {
+    std::thread::Builder::new()
+        .name("indexer-sink".to_string())
+        .spawn(move || {
+            let result = drain(stream, sink, checkpoint);
+            if let Err(err) = &result {
+                error!(target: INDEXER, "Indexer sink failed: {}", err);
+            }
+            result
+        })
+        .expect("failed to spawn indexer sink thread")
+}
+
+fn drain(
+    mut stream: mpsc::Receiver<StreamerMessage>,
+    mut sink: Box<dyn StreamerMessageSink>,
+    checkpoint: Option<StreamerCheckpoint>,
+) -> io::Result<()> {
+    let mut pending = VecDeque::new();
+    while let Some(streamer_message) = stream.blocking_recv() {
+        if checkpoint.is_some() {
+            pending.push_back(streamer_message.block.header.height);
+        }
+        let stored = sink.write(&streamer_message)?;
+        acknowledge(checkpoint.as_ref(), &mut pending, stored);
+    }
+    info!(target: INDEXER, "Stream is closed, flushing the indexer sink");
+    let stored = sink.flush()?;
+    acknowledge(checkpoint.as_ref(), &mut pending, stored);
+    Ok(())
+}
+
+fn acknowledge(
+    checkpoint: Option<&StreamerCheckpoint>,
+    pending: &mut VecDeque<BlockHeight>,
+    stored: Option<BlockHeight>,
+) {
+    let (Some(checkpoint), Some(stored)) = (checkpoint, stored) else { return };
+    while let Some(height) = pending.front().copied().filter(|&height| height <= stored) {
+        checkpoint.acknowledge(height);
+        pending.pop_front();
+    }
+}
@@ -0,0 +1,508 @@
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+
+use near_indexer_primitives::StreamerMessage;
+use near_primitives::types::BlockHeight;
+
+use super::StreamerMessageSink;
+use crate::filter::state_change_account_id;
+
+/// Configuration of `ParquetSink`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ParquetSinkConfig {
+    /// Directory the tables are written to, one sub-directory per table.
+    /// Created if it doesn't exist.
+    pub directory: PathBuf,
+    /// Number of blocks buffered in memory before they are written out as
+    /// one file per table.
+    #[serde(default = "default_max_blocks_per_file")]
+    pub max_blocks_per_file: u64,
+}
+
+fn default_max_blocks_per_file() -> u64 {
+    1_000
+}
+
+/// Writes `StreamerMessage`s into separate Parquet tables for blocks,
+/// transactions, receipts, execution outcomes and state changes.  Nested
+/// structures (actions, statuses, logs etc.) are stored as JSON strings.
+///
+/// Blocks are buffered in memory and written out as files named after the
+/// first and the last block height they contain
+/// (`transactions/000000000100-000000001099.parquet`).  The files of all the
+/// tables are written under a temporary name, synced to disk and renamed only
+/// once all of them are complete, so a crash never leaves a partial file
+/// behind.  The files are renamed one table at a time though, so a crash in
+/// the middle of the renames leaves the blocks in some of the tables only.
+/// Such blocks aren't reported stored, so they are written again after a
+/// restart, possibly into a file covering a different range of heights:
+/// readers have to deduplicate the rows by block height.  The blocks are
+/// reported stored only once all the renames are synced.  If writing any of
+/// the tables fails, the files written so far are removed and the blocks stay
+/// buffered, to be written along with the next ones.
+pub struct ParquetSink {
+    config: ParquetSinkConfig,
+    tables: [Table; 5],
+    /// Heights of the first and the last buffered blocks.
+    buffered: Option<(BlockHeight, BlockHeight)>,
+    buffered_blocks: u64,
+}
+
+const BLOCKS: usize = 0;
+const TRANSACTIONS: usize = 1;
+const RECEIPTS: usize = 2;
+const EXECUTION_OUTCOMES: usize = 3;
+const STATE_CHANGES: usize = 4;
+
+impl ParquetSink {
+    pub fn new(config: ParquetSinkConfig) -> io::Result<Self> {
+        use DataType::{UInt64, Utf8};
+        if config.max_blocks_per_file == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "max_blocks_per_file must be positive",
+            ));
+        }
+        let tables = [
+            Table::new(
+                "blocks",
+                &[
+                    ("block_height", UInt64),
+                    ("block_hash", Utf8),
+                    ("prev_block_hash", Utf8),
+                    ("block_timestamp", UInt64),
+                    ("author_account_id", Utf8),
+                    ("epoch_id", Utf8),
+                    ("chunks_included", UInt64),
+                    ("gas_price", Utf8),
+                    ("total_supply", Utf8),
+                ],
+            )?,
+            Table::new(
+                "transactions",
+                &[
+                    ("block_height", UInt64),
+                    ("block_hash", Utf8),
+                    ("shard_id", UInt64),
+                    ("transaction_hash", Utf8),
+                    ("signer_account_id", Utf8),
+                    ("signer_public_key", Utf8),
+                    ("nonce", UInt64),
+                    ("receiver_account_id", Utf8),
+                    ("actions", Utf8),
+                    ("status", Utf8),
+                    ("gas_burnt", UInt64),
+                    ("tokens_burnt", Utf8),
+                    ("receipt_ids", Utf8),
+                ],
+            )?,
+            Table::new(
+                "receipts",
+                &[
+                    ("block_height", UInt64),
+                    ("block_hash", Utf8),
+                    ("shard_id", UInt64),
+                    ("receipt_id", Utf8),
+                    ("predecessor_account_id", Utf8),
+                    ("receiver_account_id", Utf8),
+                    ("receipt", Utf8),
+                ],
+            )?,
+            Table::new(
+                "execution_outcomes",
+                &[
+                    ("block_height", UInt64),
+                    ("block_hash", Utf8),
+                    ("shard_id", UInt64),
+                    ("receipt_id", Utf8),
+                    ("executor_account_id", Utf8),
+                    ("status", Utf8),
+                    ("gas_burnt", UInt64),
+                    ("tokens_burnt", Utf8),
+                    ("logs", Utf8),
+                    ("receipt_ids", Utf8),
+                ],
+            )?,
+            Table::new(
+                "state_changes",
+                &[
+                    ("block_height", UInt64),
+                    ("block_hash", Utf8),
+                    ("shard_id", UInt64),
+                    ("account_id", Utf8),
+                    ("cause", Utf8),
+                    ("change", Utf8),
+                ],
+            )?,
+        ];
+        for table in &tables {
+            std::fs::create_dir_all(config.directory.join(table.name))?;
+        }
+        Ok(Self { config, tables, buffered: None, buffered_blocks: 0 })
+    }
+
+    fn append(&mut self, streamer_message: &StreamerMessage) -> io::Result<()> {
+        let header = &streamer_message.block.header;
+        let block_height = Cell::UInt64(header.height);
+        let block_hash = Cell::Utf8(header.hash.to_string());
+        // Rows are collected first, so that a failure leaves no partially
+        // appended block behind.
+        let mut rows = vec![];
+
+        rows.push((
+            BLOCKS,
+            vec![
+                block_height.clone(),
+                block_hash.clone(),
+                Cell::Utf8(header.prev_hash.to_string()),
+                Cell::UInt64(header.timestamp_nanosec),
+                Cell::Utf8(streamer_message.block.author.to_string()),
+                Cell::Utf8(header.epoch_id.to_string()),
+                Cell::UInt64(header.chunks_included),
+                Cell::Utf8(header.gas_price.to_string()),
+                Cell::Utf8(header.total_supply.to_string()),
+            ],
+        ));
+
+        for shard in &streamer_message.shards {
+            let shard_id = Cell::UInt64(shard.shard_id);
+            if let Some(chunk) = &shard.chunk {
+                for transaction in &chunk.transactions {
+                    let outcome = &transaction.outcome.execution_outcome.outcome;
+                    let transaction = &transaction.transaction;
+                    rows.push((
+                        TRANSACTIONS,
+                        vec![
+                            block_height.clone(),
+                            block_hash.clone(),
+                            shard_id.clone(),
+                            Cell::Utf8(transaction.hash.to_string()),
+                            Cell::Utf8(transaction.signer_id.to_string()),
+                            Cell::Utf8(transaction.public_key.to_string()),
+                            Cell::UInt64(transaction.nonce),
+                            Cell::Utf8(transaction.receiver_id.to_string()),
+                            Cell::json(&transaction.actions)?,
+                            Cell::json(&outcome.status)?,
+                            Cell::UInt64(outcome.gas_burnt),
+                            Cell::Utf8(outcome.tokens_burnt.to_string()),
+                            Cell::json(&outcome.receipt_ids)?,
+                        ],
+                    ));
+                }
+                for receipt in &chunk.receipts {
+                    rows.push((
+                        RECEIPTS,
+                        vec![
+                            block_height.clone(),
+                            block_hash.clone(),
+                            shard_id.clone(),
+                            Cell::Utf8(receipt.receipt_id.to_string()),
+                            Cell::Utf8(receipt.predecessor_id.to_string()),
+                            Cell::Utf8(receipt.receiver_id.to_string()),
+                            Cell::json(&receipt.receipt)?,
+                        ],
+                    ));
+                }
+            }
+            for outcome in &shard.receipt_execution_outcomes {
+                let execution_outcome = &outcome.execution_outcome;
+                rows.push((
+                    EXECUTION_OUTCOMES,
+                    vec![
+                        block_height.clone(),
+                        block_hash.clone(),
+                        shard_id.clone(),
+                        Cell::Utf8(execution_outcome.id.to_string()),
+                        Cell::Utf8(execution_outcome.outcome.executor_id.to_string()),
+                        Cell::json(&execution_outcome.outcome.status)?,
+                        Cell::UInt64(execution_outcome.outcome.gas_burnt),
+                        Cell::Utf8(execution_outcome.outcome.tokens_burnt.to_string()),
+                        Cell::json(&execution_outcome.outcome.logs)?,
+                        Cell::json(&execution_outcome.outcome.receipt_ids)?,
+                    ],
+                ));
+            }
+            for state_change in &shard.state_changes {
+                rows.push((
+                    STATE_CHANGES,
+                    vec![
+                        block_height.clone(),
+                        block_hash.clone(),
+                        shard_id.clone(),
+                        Cell::Utf8(state_change_account_id(&state_change.value).to_string()),
+                        Cell::json(&state_change.cause)?,
+                        Cell::json(&state_change.value)?,
+                    ],
+                ));
+            }
+        }
+        for (table, row) in &rows {
+            self.tables[*table].check(row)?;
+        }
+        for (table, row) in rows {
+            self.tables[table].push(row);
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered blocks out, returning the height of the last one.
+    /// On error the files written so far are removed and the blocks stay
+    /// buffered.
+    fn write_files(&mut self) -> io::Result<Option<BlockHeight>> {
+        let Some((first, last)) = self.buffered else { return Ok(None) };
+        let file_name = format!("{:012}-{:012}.parquet", first, last);
+        let mut written = vec![];
+        let result = self.write_tables(&file_name, &mut written);
+        if result.is_err() {
+            for path in written {
+                let _ = std::fs::remove_file(path);
+            }
+            return result.map(|()| None);
+        }
+        for table in &mut self.tables {
+            table.clear();
+        }
+        self.buffered = None;
+        self.buffered_blocks = 0;
+        Ok(Some(last))
+    }
+
+    /// Writes the buffered rows of every table into `file_name` in the
+    /// directory of the table.  Every file created, whether temporary or
+    /// final, is added to `written`.
+    fn write_tables(&self, file_name: &str, written: &mut Vec<PathBuf>) -> io::Result<()> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let mut files = vec![];
+        for table in &self.tables {
+            let directory = self.config.directory.join(table.name);
+            let path = directory.join(file_name);
+            let tmp_path = path.with_extension("parquet.tmp");
+            written.push(tmp_path.clone());
+
+            let batch = table.batch()?;
+            let mut writer = ArrowWriter::try_new(
+                File::create(&tmp_path)?,
+                batch.schema(),
+                Some(properties.clone()),
+            )
+            .map_err(io::Error::other)?;
+            writer.write(&batch).map_err(io::Error::other)?;
+            writer.into_inner().map_err(io::Error::other)?.sync_all()?;
+            files.push((directory, tmp_path, path));
+        }
+        for (directory, tmp_path, path) in files {
+            written.push(path.clone());
+            std::fs::rename(&tmp_path, &path)?;
+            File::open(&directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamerMessageSink for ParquetSink {
+    fn write(&mut self, streamer_message: &StreamerMessage) -> io::Result<Option<BlockHeight>> {
+        let block_height = streamer_message.block.header.height;
+        self.append(streamer_message)?;
+        let first = self.buffered.map_or(block_height, |(first, _)| first);
+        self.buffered = Some((first, block_height));
+        self.buffered_blocks += 1;
+        if self.buffered_blocks >= self.config.max_blocks_per_file {
+            return self.write_files();
+        }
+        Ok(None)
+    }
+
+    fn flush(&mut self) -> io::Result<Option<BlockHeight>> {
+        self.write_files()
+    }
+}
+
+#[derive(Clone)]
+enum Cell {
+    UInt64(u64),
+    Utf8(String),
+}
+
+impl Cell {
+    fn json(value: &impl serde::Serialize) -> io::Result<Self> {
+        Ok(Self::Utf8(serde_json::to_string(value)?))
+    }
+}
+
+enum Column {
+    UInt64(Vec<u64>),
+    Utf8(Vec<String>),
+}
+
+/// Rows of a single table buffered column by column.
+struct Table {
+    name: &'static str,
+    schema: Arc<Schema>,
+    columns: Vec<Column>,
+}
+
+impl Table {
+    fn new(name: &'static str, fields: &[(&'static str, DataType)]) -> io::Result<Self> {
+        let schema = Arc::new(Schema::new(
+            fields
+                .iter()
+                .map(|(name, data_type)| Field::new(*name, data_type.clone(), false))
+                .collect::<Vec<_>>(),
+        ));
+        let columns = fields
+            .iter()
+            .map(|(_, data_type)| match data_type {
+                DataType::UInt64 => Ok(Column::UInt64(vec![])),
+                DataType::Utf8 => Ok(Column::Utf8(vec![])),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unsupported column type {} in {} table", data_type, name),
+                )),
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { name, schema, columns })
+    }
+
+    /// Checks that the row matches the schema of the table.
+    fn check(&self, row: &[Cell]) -> io::Result<()> {
+        let matches = row.len() == self.columns.len()
+            && self.columns.iter().zip(row).all(|(column, cell)| {
+                matches!(
+                    (column, cell),
+                    (Column::UInt64(_), Cell::UInt64(_)) | (Column::Utf8(_), Cell::Utf8(_))
+                )
+            });
+        if !matches {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("row doesn't match {} table schema", self.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Appends a row which passed `check`.
+    fn push(&mut self, row: Vec<Cell>) {
+        for (column, cell) in self.columns.iter_mut().zip(row) {
+            match (column, cell) {
+                (Column::UInt64(values), Cell::UInt64(value)) => values.push(value),
+                (Column::Utf8(values), Cell::Utf8(value)) => values.push(value),
+                _ => {}
+            }
+        }
+    }
+
+    fn batch(&self) -> io::Result<RecordBatch> {
+        let arrays = self
+            .columns
+            .iter()
+            .map(|column| -> ArrayRef {
+                match column {
+                    Column::UInt64(values) => Arc::new(UInt64Array::from(values.clone())),
+                    Column::Utf8(values) => Arc::new(StringArray::from_iter_values(values)),
+                }
+            })
+            .collect();
+        RecordBatch::try_new(self.schema.clone(), arrays).map_err(io::Error::other)
+    }
+
+    fn clear(&mut self) {
+        for column in &mut self.columns {
+            match column {
+                Column::UInt64(values) => values.clear(),
+                Column::Utf8(values) => values.clear(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_files(dir: &std::path::Path) -> Vec<String> {
+        let mut files = vec![];
+        for table in std::fs::read_dir(dir).unwrap() {
+            let table = table.unwrap().path();
+            for file in std::fs::read_dir(&table).unwrap() {
+                let file = file.unwrap().path();
+                files.push(format!(
+                    "{}/{}",
+                    table.file_name().unwrap().to_str().unwrap(),
+                    file.file_name().unwrap().to_str().unwrap()
+                ));
+            }
+        }
+        files.sort();
+        files
+    }
+
+    fn buffer_block(sink: &mut ParquetSink, height: BlockHeight) {
+        let text = || Cell::Utf8(String::new());
+        let row = vec![
+            Cell::UInt64(height),
+            text(),
+            text(),
+            Cell::UInt64(0),
+            text(),
+            text(),
+            Cell::UInt64(0),
+            text(),
+            text(),
+        ];
+        sink.tables[BLOCKS].check(&row).unwrap();
+        sink.tables[BLOCKS].push(row);
+        let first = sink.buffered.map_or(height, |(first, _)| first);
+        sink.buffered = Some((first, height));
+        sink.buffered_blocks += 1;
+    }
+
+    #[test]
+    fn test_write_files_is_all_or_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let config =
+            ParquetSinkConfig { directory: dir.path().to_path_buf(), max_blocks_per_file: 10 };
+        let mut sink = ParquetSink::new(config).unwrap();
+        buffer_block(&mut sink, 5);
+        buffer_block(&mut sink, 6);
+
+        // Writing the last table fails, so none of the tables is written and
+        // the blocks stay buffered.
+        std::fs::remove_dir(dir.path().join("state_changes")).unwrap();
+        assert!(sink.flush().is_err());
+        assert_eq!(list_files(dir.path()), Vec::<String>::new());
+
+        std::fs::create_dir(dir.path().join("state_changes")).unwrap();
+        assert_eq!(sink.flush().unwrap(), Some(6));
+        assert_eq!(
+            list_files(dir.path()),
+            [
+                "blocks/000000000005-000000000006.parquet",
+                "execution_outcomes/000000000005-000000000006.parquet",
+                "receipts/000000000005-000000000006.parquet",
+                "state_changes/000000000005-000000000006.parquet",
+                "transactions/000000000005-000000000006.parquet",
+            ]
+        );
+        assert_eq!(sink.flush().unwrap(), None);
+    }
+
+    #[test]
+    fn test_row_not_matching_schema() {
+        let table = Table::new("test", &[("a", DataType::UInt64), ("b", DataType::Utf8)]).unwrap();
+        assert!(table.check(&[Cell::UInt64(1), Cell::Utf8("b".to_string())]).is_ok());
+        assert!(table.check(&[Cell::UInt64(1)]).is_err());
+        assert!(table.check(&[Cell::Utf8("a".to_string()), Cell::Utf8("b".to_string())]).is_err());
+        assert!(Table::new("test", &[("a", DataType::Boolean)]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Tracks which of the streamed blocks the consumer has acknowledged and
+//! persists the resulting checkpoint in the indexer database.
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use rocksdb::DB;
+use tracing::{error, warn};
+
+use near_primitives::types::BlockHeight;
+
+use super::metrics;
+use crate::INDEXER;
+
+pub(crate) const LAST_ACKNOWLEDGED_BLOCK_HEIGHT_KEY: &[u8] = b"last_acknowledged_block_height";
+
+/// Handle returned by `Indexer::checkpointed_streamer` which the consumer uses
+/// to acknowledge the blocks it has durably handled.
+///
+/// The persisted checkpoint is the highest block height below which every
+/// streamed block has been acknowledged.  Acknowledgements may arrive in any
+/// order; a block acknowledged ahead of an older unacknowledged one doesn't
+/// move the checkpoint until the older one is acknowledged as well.  After a
+/// restart with `SyncModeEnum::FromCheckpoint` streaming resumes right after
+/// the checkpoint, so every block is delivered at least once and blocks which
+/// were in flight during a crash are delivered again.
+///
+/// The checkpoint only moves forward on acknowledgements, and only over
+/// heights which were streamed in this run.  If streaming doesn't resume
+/// right after the persisted checkpoint (e.g. with `SyncModeEnum::LatestSynced`),
+/// the blocks in between were never delivered, so the checkpoint stays where
+/// it is.
+#[derive(Clone)]
+pub struct StreamerCheckpoint {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    db: Arc<DB>,
+    state: Mutex<CheckpointState>,
+}
+
+#[derive(Default)]
+struct CheckpointState {
+    /// Heights sent to the consumer and not acknowledged yet.
+    in_flight: BTreeSet<BlockHeight>,
+    /// Heights above the checkpoint which are done with, either because they
+    /// were acknowledged or because there is no block at that height, but
+    /// which wait for a lower height to be acknowledged.
+    done: BTreeSet<BlockHeight>,
+    /// The lowest height which hasn't been acknowledged yet.  Unknown until
+    /// the first block is streamed if nothing has been persisted.
+    next: Option<BlockHeight>,
+    /// Whether the streamer has started, i.e. whether `next` is final.
+    started: bool,
+    /// Set if streaming hasn't started right after the persisted checkpoint,
+    /// in which case the checkpoint can't move forward.
+    gap: bool,
+    /// The checkpoint as last written to the database.
+    last_acknowledged: Option<BlockHeight>,
+}
+
+impl CheckpointState {
+    /// Called for every height the streamer is done with, in increasing order.
+    fn processed(&mut self, block_height: BlockHeight) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        match self.next {
+            None => self.next = Some(block_height),
+            Some(next) if block_height > next => {
+                warn!(
+                    target: INDEXER,
+                    "Streaming started at block #{} while blocks from #{} on haven't been acknowledged, the checkpoint won't move forward",
+                    block_height,
+                    next
+                );
+                self.gap = true;
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Marks the given height as done with and moves `next` over the
+    /// contiguous heights which are done with.
+    fn done(&mut self, block_height: BlockHeight) {
+        let Some(mut next) = self.next else { return };
+        if self.gap || block_height < next {
+            return;
+        }
+        self.done.insert(block_height);
+        while self.done.remove(&next) {
+            next += 1;
+        }
+        self.next = Some(next);
+    }
+}
+
+impl StreamerCheckpoint {
+    pub(crate) fn new(db: Arc<DB>) -> anyhow::Result<Self> {
+        let last_acknowledged = read_last_acknowledged_block_height(&db)?;
+        let state = CheckpointState {
+            next: last_acknowledged.map(|height| height + 1),
+            last_acknowledged,
+            ..Default::default()
+        };
+        Ok(Self { inner: Arc::new(Inner { db, state: Mutex::new(state) }) })
+    }
+
+    /// Marks the block at the given height as handled by the consumer.
+    ///
+    /// Acknowledging a height which is not awaiting an acknowledgement (e.g.
+    /// acknowledging the same block twice) is a no-op.
+    pub fn acknowledge(&self, block_height: BlockHeight) {
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.in_flight.remove(&block_height) {
+            warn!(
+                target: INDEXER,
+                "Block #{} is not awaiting an acknowledgement, ignoring", block_height
+            );
+            return;
+        }
+        state.done(block_height);
+        metrics::NUM_UNACKNOWLEDGED_BLOCKS.set(state.in_flight.len() as i64);
+        self.persist(&mut state);
+    }
+
+    /// Returns the persisted checkpoint, i.e. the height up to which all the
+    /// streamed blocks have been acknowledged.
+    pub fn last_acknowledged_block_height(&self) -> Option<BlockHeight> {
+        self.inner.state.lock().unwrap().last_acknowledged
+    }
+
+    /// Returns the number of blocks sent to the consumer and not acknowledged yet.
+    pub fn num_unacknowledged_blocks(&self) -> usize {
+        self.inner.state.lock().unwrap().in_flight.len()
+    }
+
+    /// Records that the block at the given height is about to be sent to the
+    /// consumer.  Must be called before sending so that a fast consumer can't
+    /// acknowledge the block before the streamer knows about it.
+    pub(crate) fn block_sent(&self, block_height: BlockHeight) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.processed(block_height);
+        state.in_flight.insert(block_height);
+        metrics::NUM_UNACKNOWLEDGED_BLOCKS.set(state.in_flight.len() as i64);
+    }
+
+    /// Records that there is no block at the given height, so there is
+    /// nothing for the consumer to acknowledge.  The checkpoint moves over
+    /// such heights along with the next acknowledgement.
+    pub(crate) fn block_skipped(&self, block_height: BlockHeight) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.processed(block_height);
+        state.done(block_height);
+    }
+
+    fn persist(&self, state: &mut CheckpointState) {
+        let Some(checkpoint) = state.next.and_then(|next| next.checked_sub(1)) else { return };
+        if state.last_acknowledged.is_some_and(|last| last >= checkpoint) {
+            return;
+        }
+        if let Err(err) =
+            self.inner.db.put(LAST_ACKNOWLEDGED_BLOCK_HEIGHT_KEY, checkpoint.to_string())
+        {
+            // The checkpoint will be written along with the next acknowledgement.
+            error!(target: INDEXER, "Unable to persist indexer checkpoint: {:?}", err);
+            return;
+        }
+        state.last_acknowledged = Some(checkpoint);
+        metrics::LAST_ACKNOWLEDGED_BLOCK_HEIGHT.set(checkpoint as i64);
+    }
+}
+
+pub(crate) fn read_last_acknowledged_block_height(db: &DB) -> anyhow::Result<Option<BlockHeight>> {
+    let Some(value) =
+        db.get(LAST_ACKNOWLEDGED_BLOCK_HEIGHT_KEY).context("read indexer checkpoint")?
+    else {
+        return Ok(None);
+    };
+    let height = String::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse::<BlockHeight>().ok())
+        .context("indexer checkpoint is not a block height")?;
+    Ok(Some(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_db(dir: &tempfile::TempDir) -> Arc<DB> {
+        Arc::new(DB::open_default(dir.path()).unwrap())
+    }
+
+    #[test]
+    fn test_checkpoint_waits_for_oldest_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = StreamerCheckpoint::new(open_db(&dir)).unwrap();
+        assert_eq!(checkpoint.last_acknowledged_block_height(), None);
+
+        checkpoint.block_sent(10);
+        checkpoint.block_sent(11);
+        checkpoint.block_skipped(12);
+        checkpoint.block_sent(13);
+        // Nothing has been acknowledged yet.
+        assert_eq!(checkpoint.last_acknowledged_block_height(), None);
+
+        checkpoint.acknowledge(11);
+        checkpoint.acknowledge(13);
+        assert_eq!(checkpoint.last_acknowledged_block_height(), None);
+        assert_eq!(checkpoint.num_unacknowledged_blocks(), 1);
+
+        checkpoint.acknowledge(10);
+        assert_eq!(checkpoint.last_acknowledged_block_height(), Some(13));
+        assert_eq!(checkpoint.num_unacknowledged_blocks(), 0);
+
+        // Skipped heights are persisted along with the next acknowledgement.
+        checkpoint.block_skipped(14);
+        assert_eq!(checkpoint.last_acknowledged_block_height(), Some(13));
+        checkpoint.block_sent(15);
+        checkpoint.acknowledge(15);
+        assert_eq!(checkpoint.last_acknowledged_block_height(), Some(15));
+    }
+
+    #[test]
+    fn test_checkpoint_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let checkpoint = StreamerCheckpoint::new(open_db(&dir)).unwrap();
+            checkpoint.block_sent(5);
+            checkpoint.block_sent(6);
+            checkpoint.acknowledge(5);
+            // Acknowledging twice or acknowledging unknown heights is ignored.
+            checkpoint.acknowledge(5);
+            checkpoint.acknowledge(100);
+        }
+        let checkpoint = StreamerCheckpoint::new(open_db(&dir)).unwrap();
+        assert_eq!(checkpoint.last_acknowledged_block_height(), Some(5));
+    }
+
+    /// Streaming which doesn't resume right after the checkpoint (e.g. with
+    /// `SyncModeEnum::LatestSynced`) must not move the checkpoint over the
+    /// blocks which were never delivered.
+    #[test]
+    fn test_checkpoint_does_not_skip_undelivered_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let checkpoint = StreamerCheckpoint::new(open_db(&dir)).unwrap();
+            checkpoint.block_sent(500);
+            checkpoint.acknowledge(500);
+        }
+        let checkpoint = StreamerCheckpoint::new(open_db(&dir)).unwrap();
+        checkpoint.block_sent(1000);
+        checkpoint.block_sent(1001);
+        checkpoint.acknowledge(1000);
+        checkpoint.acknowledge(1001);
+        assert_eq!(checkpoint.last_acknowledged_block_height(), Some(500));
+        assert_eq!(checkpoint.num_unacknowledged_blocks(), 0);
+    }
+
+    #[test]
+    fn test_invalid_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open_db(&dir);
+        db.put(LAST_ACKNOWLEDGED_BLOCK_HEIGHT_KEY, "foo").unwrap();
+        assert!(StreamerCheckpoint::new(db).is_err());
+    }
+}
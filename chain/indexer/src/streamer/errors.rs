@@ -1,9 +1,11 @@
 use actix::MailboxError;
+use near_client::GetBlockError;
 
 /// Error occurs in case of failed data fetch
 #[derive(Debug)]
 pub enum FailedToFetchData {
     MailboxError(MailboxError),
+    GetBlockError(GetBlockError),
     String(String),
 }
 
@@ -13,6 +13,7 @@ use near_primitives::{types, views};
 
 use super::errors::FailedToFetchData;
 use super::INDEXER;
+use crate::IndexerFilter;
 
 pub(crate) async fn fetch_status(
     client: &Addr<near_client::ClientActor>,
@@ -38,7 +39,7 @@ pub(crate) async fn fetch_latest_block(
             .with_span_context(),
         )
         .await?
-        .map_err(|err| FailedToFetchData::String(err.to_string()))
+        .map_err(FailedToFetchData::GetBlockError)
 }
 
 /// Fetches specific block by it's height
@@ -53,7 +54,7 @@ pub(crate) async fn fetch_block_by_height(
                 .with_span_context(),
         )
         .await?
-        .map_err(|err| FailedToFetchData::String(err.to_string()))
+        .map_err(FailedToFetchData::GetBlockError)
 }
 
 /// Fetches specific block by it's hash
@@ -68,19 +69,27 @@ pub(crate) async fn fetch_block(
                 .with_span_context(),
         )
         .await?
-        .map_err(|err| FailedToFetchData::String(err.to_string()))
+        .map_err(FailedToFetchData::GetBlockError)
 }
 
+/// Fetches the state changes of the block, split by shard.  Only the changes
+/// matching the filter are fetched.
 pub(crate) async fn fetch_state_changes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
     epoch_id: near_primitives::types::EpochId,
+    filter: &IndexerFilter,
 ) -> Result<HashMap<near_primitives::types::ShardId, views::StateChangesView>, FailedToFetchData> {
     tracing::debug!(target: INDEXER, "Fetching state changes for block: {}, epoch_id: {:?}", block_hash, epoch_id);
     client
         .send(
-            near_client::GetStateChangesWithCauseInBlockForTrackedShards { block_hash, epoch_id }
-                .with_span_context(),
+            near_client::GetStateChangesWithCauseInBlockForTrackedShards {
+                block_hash,
+                epoch_id,
+                shard_ids: filter.shard_ids(),
+                accounts: filter.account_filter(),
+            }
+            .with_span_context(),
         )
         .await?
         .map_err(|err| FailedToFetchData::String(err.to_string()))
@@ -88,9 +97,14 @@ pub(crate) async fn fetch_state_changes(
 
 /// Fetch all ExecutionOutcomeWithId for current block
 /// Returns a HashMap where the key is shard id IndexerExecutionOutcomeWithOptionalReceipt
+/// Outcomes of filtered out shards are skipped, and receipts are not fetched
+/// for outcomes whose receipts can't match the filter.  These outcomes are kept
+/// without a receipt, since the outcomes of a shard are matched with its
+/// transactions by position
 pub(crate) async fn fetch_outcomes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
+    filter: &IndexerFilter,
 ) -> Result<
     HashMap<near_primitives::types::ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
     FailedToFetchData,
@@ -106,9 +120,19 @@ pub(crate) async fn fetch_outcomes(
         Vec<IndexerExecutionOutcomeWithOptionalReceipt>,
     > = HashMap::new();
     for (shard_id, shard_outcomes) in outcomes {
+        if !filter.matches_shard(shard_id) {
+            continue;
+        }
         tracing::debug!(target: INDEXER, "Fetching outcomes with receipts for shard: {}", shard_id);
         let mut outcomes_with_receipts: Vec<IndexerExecutionOutcomeWithOptionalReceipt> = vec![];
         for outcome in shard_outcomes {
+            if !filter.matches_receipt_receiver(&outcome.outcome.executor_id) {
+                outcomes_with_receipts.push(IndexerExecutionOutcomeWithOptionalReceipt {
+                    execution_outcome: outcome,
+                    receipt: None,
+                });
+                continue;
+            }
             let receipt = match fetch_receipt_by_id(&client, outcome.id).await {
                 Ok(res) => res,
                 Err(e) => {
@@ -161,12 +185,14 @@ async fn fetch_single_chunk(
 pub(crate) async fn fetch_block_chunks(
     client: &Addr<near_client::ViewClientActor>,
     block: &views::BlockView,
+    filter: &IndexerFilter,
 ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
     tracing::debug!(target: INDEXER, "Fetching chunks for block #{}", block.header.height);
     let mut futures: futures::stream::FuturesUnordered<_> = block
         .chunks
         .iter()
         .filter(|chunk| chunk.height_included == block.header.height)
+        .filter(|chunk| filter.matches_shard(chunk.shard_id))
         .map(|chunk| fetch_single_chunk(&client, chunk.chunk_hash))
         .collect();
     let mut chunks = Vec::<views::ChunkView>::with_capacity(futures.len());
@@ -52,3 +52,27 @@ pub(crate) static LOCAL_RECEIPT_LOOKUP_IN_HISTORY_BLOCKS_BACK: LazyLock<IntGauge
         )
         .unwrap()
     });
+
+pub(crate) static NUM_STREAMER_MESSAGE_RETRIES: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_indexer_num_streamer_message_retries",
+        "Number of times the checkpointed streamer had to retry a block it failed to fetch",
+    )
+    .unwrap()
+});
+
+pub(crate) static LAST_ACKNOWLEDGED_BLOCK_HEIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    try_create_int_gauge(
+        "near_indexer_last_acknowledged_block_height",
+        "Block height up to which the consumer acknowledged all the streamed blocks",
+    )
+    .unwrap()
+});
+
+pub(crate) static NUM_UNACKNOWLEDGED_BLOCKS: LazyLock<IntGauge> = LazyLock::new(|| {
+    try_create_int_gauge(
+        "near_indexer_num_unacknowledged_blocks",
+        "Number of blocks sent to the consumer and not acknowledged yet",
+    )
+    .unwrap()
+});
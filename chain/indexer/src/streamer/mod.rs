@@ -3,11 +3,13 @@ use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use actix::Addr;
+use anyhow::Context;
 use rocksdb::DB;
 use tokio::sync::mpsc;
 use tokio::time;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use near_client::GetBlockError;
 use near_indexer_primitives::{
     IndexerChunkView, IndexerExecutionOutcomeWithOptionalReceipt,
     IndexerExecutionOutcomeWithReceipt, IndexerShard, IndexerTransactionWithOutcome,
@@ -25,8 +27,11 @@ use self::fetchers::{
 use self::utils::convert_transactions_sir_into_local_receipts;
 use crate::streamer::fetchers::fetch_protocol_config;
 use crate::INDEXER;
-use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
+use crate::{AwaitForNodeSyncedEnum, IndexerConfig, IndexerFilter};
 
+pub use self::checkpoint::StreamerCheckpoint;
+
+mod checkpoint;
 mod errors;
 mod fetchers;
 mod metrics;
@@ -38,6 +43,10 @@ static DELAYED_LOCAL_RECEIPTS_CACHE: std::sync::LazyLock<
 
 const INTERVAL: Duration = Duration::from_millis(500);
 
+/// Number of times the data of a block is fetched again when streaming with a
+/// checkpoint before giving up and stopping the streamer.
+const MAX_RETRIES: u32 = 100;
+
 /// Blocks #47317863 and #47317864 with restored receipts.
 const PROBLEMATIC_BLOCKS: [CryptoHash; 2] = [
     CryptoHash(
@@ -74,9 +83,19 @@ fn test_problematic_blocks_hash() {
 pub async fn build_streamer_message(
     client: &Addr<near_client::ViewClientActor>,
     block: views::BlockView,
+) -> Result<StreamerMessage, FailedToFetchData> {
+    build_filtered_streamer_message(client, block, &IndexerFilter::default()).await
+}
+
+/// Same as `build_streamer_message`, but only the parts of the block matching
+/// the filter are fetched and included into the `StreamerMessage`
+pub async fn build_filtered_streamer_message(
+    client: &Addr<near_client::ViewClientActor>,
+    block: views::BlockView,
+    filter: &IndexerFilter,
 ) -> Result<StreamerMessage, FailedToFetchData> {
     let _timer = metrics::BUILD_STREAMER_MESSAGE_TIME.start_timer();
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let chunks = fetch_block_chunks(&client, &block, filter).await?;
 
     let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
     let num_shards = protocol_config_view.num_block_producer_seats_per_shard.len()
@@ -85,13 +104,16 @@ pub async fn build_streamer_message(
     let runtime_config_store = near_parameters::RuntimeConfigStore::new(None);
     let runtime_config = runtime_config_store.get_config(protocol_config_view.protocol_version);
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash, filter).await?;
     let mut state_changes = fetch_state_changes(
         &client,
         block.header.hash,
         near_primitives::types::EpochId(block.header.epoch_id),
+        filter,
     )
     .await?;
+    // Shards which are filtered out are left empty, so that the shards stay at
+    // the position of their ID.
     let mut indexer_shards = (0..num_shards)
         .map(|shard_id| IndexerShard {
             shard_id,
@@ -114,7 +136,7 @@ pub async fn build_streamer_message(
         // Take execution outcomes for receipts from the vec and keep only the ones for transactions
         let mut receipt_outcomes = outcomes.split_off(transactions.len());
 
-        let indexer_transactions = transactions
+        let mut indexer_transactions = transactions
             .into_iter()
             .zip(outcomes.into_iter())
             .map(|(transaction, outcome)| {
@@ -146,7 +168,9 @@ pub async fn build_streamer_message(
             {
                 debug_assert!(outcome.receipt.is_none());
                 outcome.receipt = Some(receipt.clone());
-            } else {
+            } else if filter.matches_receipt_receiver(&receipt.receiver_id) {
+                // The outcome of a filtered out receipt would never take it
+                // out of the cache, so only the matching ones are kept.
                 if let Ok(mut cache) = DELAYED_LOCAL_RECEIPTS_CACHE.write() {
                     cache.insert(receipt.receipt_id, receipt.clone());
                 } else {
@@ -159,6 +183,14 @@ pub async fn build_streamer_message(
             }
         }
 
+        indexer_transactions.retain(|transaction| filter.matches_transaction(transaction));
+        // Receipts of the outcomes executed on filtered out accounts weren't
+        // fetched, so these outcomes have to be dropped before looking for
+        // the missing receipts.
+        receipt_outcomes.retain(|outcome| {
+            filter.matches_receipt_receiver(&outcome.execution_outcome.outcome.executor_id)
+        });
+
         let mut chunk_receipts = chunk_local_receipts;
 
         let mut receipt_execution_outcomes: Vec<IndexerExecutionOutcomeWithReceipt> = vec![];
@@ -207,8 +239,10 @@ pub async fn build_streamer_message(
                     .await?
                 }
             };
-            receipt_execution_outcomes
-                .push(IndexerExecutionOutcomeWithReceipt { execution_outcome, receipt });
+            if filter.matches_receipt(&receipt) {
+                receipt_execution_outcomes
+                    .push(IndexerExecutionOutcomeWithReceipt { execution_outcome, receipt });
+            }
         }
 
         // Blocks #47317863 and #47317864
@@ -235,6 +269,7 @@ pub async fn build_streamer_message(
         }
 
         chunk_receipts.extend(chunk_non_local_receipts);
+        chunk_receipts.retain(|receipt| filter.matches_receipt(receipt));
 
         indexer_shards[shard_id].receipt_execution_outcomes = receipt_execution_outcomes;
         // Put the chunk into corresponding indexer shard
@@ -251,10 +286,16 @@ pub async fn build_streamer_message(
     // That might happen before the fix https://github.com/near/nearcore/pull/4228
     for (shard_id, outcomes) in shards_outcomes {
         indexer_shards[shard_id as usize].receipt_execution_outcomes.extend(
-            outcomes.into_iter().map(|outcome| IndexerExecutionOutcomeWithReceipt {
-                execution_outcome: outcome.execution_outcome,
-                receipt: outcome.receipt.expect("`receipt` must be present at this moment"),
-            }),
+            outcomes
+                .into_iter()
+                .filter(|outcome| {
+                    filter.matches_receipt_receiver(&outcome.execution_outcome.outcome.executor_id)
+                })
+                .map(|outcome| IndexerExecutionOutcomeWithReceipt {
+                    execution_outcome: outcome.execution_outcome,
+                    receipt: outcome.receipt.expect("`receipt` must be present at this moment"),
+                })
+                .filter(|outcome| filter.matches_receipt(&outcome.receipt)),
         )
     }
 
@@ -319,10 +360,11 @@ async fn find_local_receipt_by_id_in_block(
     block: views::BlockView,
     receipt_id: near_primitives::hash::CryptoHash,
 ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let chunks = fetch_block_chunks(&client, &block, &IndexerFilter::default()).await?;
 
     let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes =
+        fetch_outcomes(&client, block.header.hash, &IndexerFilter::default()).await?;
 
     for chunk in chunks {
         let views::ChunkView { header, transactions, .. } = chunk;
@@ -358,30 +400,43 @@ async fn find_local_receipt_by_id_in_block(
     Ok(None)
 }
 
+/// Opens the database where the streamer keeps its progress.
+pub(crate) fn open_indexer_db(
+    indexer_config: &IndexerConfig,
+    store_config: &near_store::StoreConfig,
+    archive: bool,
+) -> anyhow::Result<Arc<DB>> {
+    let indexer_db_path =
+        near_store::NodeStorage::opener(&indexer_config.home_dir, archive, store_config, None)
+            .path()
+            .join("indexer");
+
+    let db = DB::open_default(&indexer_db_path)
+        .with_context(|| format!("Unable to open indexer db at {}", indexer_db_path.display()))?;
+    Ok(Arc::new(db))
+}
+
 /// Function that starts Streamer's busy loop. Every half a seconds it fetches the status
 /// compares to already fetched block height and in case it differs fetches new block of given height.
 ///
 /// We have to pass `client: Addr<near_client::ClientActor>` and `view_client: Addr<near_client::ViewClientActor>`.
+///
+/// When `checkpoint` is given, every sent block is registered there for the consumer
+/// to acknowledge and blocks whose data can't be fetched are retried instead of being skipped.
+/// If the data of a block can't be fetched after `MAX_RETRIES` attempts, the streamer stops,
+/// which closes the stream.
 pub(crate) async fn start(
     view_client: Addr<near_client::ViewClientActor>,
     client: Addr<near_client::ClientActor>,
     indexer_config: IndexerConfig,
-    store_config: near_store::StoreConfig,
-    archive: bool,
+    db: Arc<DB>,
+    checkpoint: Option<StreamerCheckpoint>,
     blocks_sink: mpsc::Sender<StreamerMessage>,
 ) {
     info!(target: INDEXER, "Starting Streamer...");
-    let indexer_db_path =
-        near_store::NodeStorage::opener(&indexer_config.home_dir, archive, &store_config, None)
-            .path()
-            .join("indexer");
-
-    let db = match DB::open_default(indexer_db_path) {
-        Ok(db) => db,
-        Err(err) => panic!("Unable to open indexer db: {:?}", err),
-    };
-
     let mut last_synced_block_height: Option<near_primitives::types::BlockHeight> = None;
+    // The height whose data couldn't be fetched and the number of attempts so far.
+    let mut retries: Option<(near_primitives::types::BlockHeight, u32)> = None;
 
     'main: loop {
         time::sleep(INTERVAL).await;
@@ -416,6 +471,16 @@ pub(crate) async fn start(
                         None => latest_block_height,
                     }
                 }
+                crate::SyncModeEnum::FromCheckpoint => {
+                    match checkpoint::read_last_acknowledged_block_height(&db) {
+                        Ok(Some(height)) => height + 1,
+                        Ok(None) => latest_block_height,
+                        Err(err) => {
+                            error!(target: INDEXER, "Unable to read the checkpoint, terminating: {:#}", err);
+                            break 'main;
+                        }
+                    }
+                }
                 crate::SyncModeEnum::LatestSynced => latest_block_height,
                 crate::SyncModeEnum::BlockHeight(height) => height,
             }
@@ -431,12 +496,41 @@ pub(crate) async fn start(
         metrics::LATEST_BLOCK_HEIGHT.set(latest_block_height as i64);
         for block_height in start_syncing_block_height..=latest_block_height {
             metrics::CURRENT_BLOCK_HEIGHT.set(block_height as i64);
-            if let Ok(block) = fetch_block_by_height(&view_client, block_height).await {
-                let response = build_streamer_message(&view_client, block).await;
+            let block = match fetch_block_by_height(&view_client, block_height).await {
+                Ok(block) => Some(block),
+                // Only a block that doesn't exist is skipped, the node may fail to
+                // return an existing one and then it has to be fetched again.
+                Err(FailedToFetchData::GetBlockError(GetBlockError::UnknownBlock { .. })) => None,
+                Err(err) if checkpoint.is_some() => {
+                    if !should_retry(&mut retries, block_height) {
+                        error!(
+                            target: INDEXER,
+                            "Unable to fetch block #{} after {} attempts, terminating: {:?}",
+                            block_height,
+                            MAX_RETRIES,
+                            err
+                        );
+                        break 'main;
+                    }
+                    warn!(
+                        target: INDEXER,
+                        "Unable to fetch block #{}, retrying: {:?}", block_height, err
+                    );
+                    continue 'main;
+                }
+                Err(_) => None,
+            };
+            if let Some(block) = block {
+                let response =
+                    build_filtered_streamer_message(&view_client, block, &indexer_config.filter)
+                        .await;
 
                 match response {
                     Ok(streamer_message) => {
                         debug!(target: INDEXER, "Sending streamer message for block #{} to the listener", streamer_message.block.header.height);
+                        if let Some(checkpoint) = &checkpoint {
+                            checkpoint.block_sent(block_height);
+                        }
                         if blocks_sink.send(streamer_message).await.is_err() {
                             error!(
                                 target: INDEXER,
@@ -447,6 +541,25 @@ pub(crate) async fn start(
                             metrics::NUM_STREAMER_MESSAGES_SENT.inc();
                         }
                     }
+                    Err(err) if checkpoint.is_some() => {
+                        // Skipping the block would break the at-least-once
+                        // guarantee, so start over from this height.
+                        if !should_retry(&mut retries, block_height) {
+                            error!(
+                                target: INDEXER,
+                                "Missing data for block #{} after {} attempts, terminating: {:?}",
+                                block_height,
+                                MAX_RETRIES,
+                                err
+                            );
+                            break 'main;
+                        }
+                        warn!(
+                            target: INDEXER,
+                            "Missing data for block #{}, retrying: {:?}", block_height, err
+                        );
+                        continue 'main;
+                    }
                     Err(err) => {
                         debug!(
                             target: INDEXER,
@@ -455,9 +568,30 @@ pub(crate) async fn start(
                         debug!(target: INDEXER, "{:#?}", err);
                     }
                 }
+            } else if let Some(checkpoint) = &checkpoint {
+                checkpoint.block_skipped(block_height);
             }
             db.put(b"last_synced_block_height", &block_height.to_string()).unwrap();
             last_synced_block_height = Some(block_height);
+            retries = None;
         }
     }
 }
+
+/// Counts an attempt to fetch the data of the block at `block_height` again.
+/// Returns `false` once the attempts are exhausted.
+fn should_retry(
+    retries: &mut Option<(near_primitives::types::BlockHeight, u32)>,
+    block_height: near_primitives::types::BlockHeight,
+) -> bool {
+    let attempts = match *retries {
+        Some((height, attempts)) if height == block_height => attempts + 1,
+        _ => 1,
+    };
+    if attempts > MAX_RETRIES {
+        return false;
+    }
+    *retries = Some((block_height, attempts));
+    metrics::NUM_STREAMER_MESSAGE_RETRIES.inc();
+    true
+}
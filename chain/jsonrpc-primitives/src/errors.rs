@@ -35,6 +35,7 @@ pub enum RpcErrorKind {
 pub enum RpcRequestValidationErrorKind {
     MethodNotFound { method_name: String },
     ParseError { error_message: String },
+    InvalidRequest { error_message: String },
 }
 
 /// A general Server Error
@@ -98,6 +99,19 @@ impl RpcError {
         }
     }
 
+    /// Create an Invalid Request error, for valid JSON which isn't a valid
+    /// request (e.g. an entry of a batch).
+    pub fn invalid_request(e: String) -> Self {
+        RpcError {
+            code: -32_600,
+            message: "Invalid Request".to_owned(),
+            data: Some(Value::String(e.clone())),
+            error_struct: Some(RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::InvalidRequest { error_message: e },
+            )),
+        }
+    }
+
     pub fn serialization_error(e: String) -> Self {
         RpcError::new_internal_error(Some(Value::String(e.clone())), e)
     }
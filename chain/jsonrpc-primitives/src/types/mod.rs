@@ -9,10 +9,13 @@ pub mod gas_price;
 pub mod light_client;
 pub mod maintenance;
 pub mod network_info;
+pub mod pending_transactions;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod simulate_transaction;
 pub mod split_storage;
 pub mod status;
+pub mod subscriptions;
 pub mod transactions;
 pub mod validator;
@@ -0,0 +1,86 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, ShardId};
+use near_primitives::views::SignedTransactionView;
+use serde_json::Value;
+
+/// Default number of transactions returned by `EXPERIMENTAL_pending_transactions`.
+pub const DEFAULT_PENDING_TRANSACTIONS_LIMIT: u32 = 100;
+/// Maximum number of transactions returned by `EXPERIMENTAL_pending_transactions`.
+pub const MAX_PENDING_TRANSACTIONS_LIMIT: u32 = 1000;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+pub struct RpcPendingTransactionsRequest {
+    /// Only list transactions signed by this account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_id: Option<AccountId>,
+    /// Only list transactions sent to this account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver_id: Option<AccountId>,
+    /// Transactions are listed ordered by hash, starting right after this one.
+    /// Pass `next_after_tx_hash` of the previous response to get the next page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_tx_hash: Option<CryptoHash>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcPendingTransaction {
+    pub shard_id: ShardId,
+    #[serde(flatten)]
+    pub transaction: SignedTransactionView,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcPendingTransactionsResponse {
+    pub transactions: Vec<RpcPendingTransaction>,
+    /// Set if there are more transactions to list.
+    pub next_after_tx_hash: Option<CryptoHash>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct RpcPendingTransactionStatusRequest {
+    pub tx_hash: CryptoHash,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcPendingTransactionStatusResponse {
+    pub tx_hash: CryptoHash,
+    /// Whether the transaction is in the node's transaction pool, i.e. it was
+    /// accepted by the node but not yet included into a chunk.
+    pub pending: bool,
+    /// Shard whose pool contains the transaction.
+    pub shard_id: Option<ShardId>,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcPendingTransactionsError {
+    #[error("Limit must be between 1 and {max_limit}, got {limit}")]
+    InvalidLimit { limit: u32, max_limit: u32 },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcPendingTransactionsError> for crate::errors::RpcError {
+    fn from(error: RpcPendingTransactionsError) -> Self {
+        let error_data = match &error {
+            RpcPendingTransactionsError::InvalidLimit { .. }
+            | RpcPendingTransactionsError::InternalError { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcPendingTransactionsError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
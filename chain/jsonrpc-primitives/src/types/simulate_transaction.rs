@@ -0,0 +1,125 @@
+use near_crypto::{PublicKey, Signature};
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::{Action, SignedTransaction, Transaction, TransactionV0};
+use near_primitives::types::{AccountId, BlockReference, Nonce};
+use near_primitives::views::ActionView;
+use serde_json::Value;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RpcSimulateTransactionRequest {
+    #[serde(flatten)]
+    pub transaction: SimulatedTransaction,
+    /// Block on top of which the transaction is simulated, the latest one by
+    /// default.
+    #[serde(flatten)]
+    pub block_reference: Option<BlockReference>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum SimulatedTransaction {
+    /// Signed transaction, whose signature is checked like for
+    /// `send_tx`.
+    #[serde(rename = "signed_tx_base64")]
+    Signed(SignedTransaction),
+    /// Transaction simulated without checking the signature.
+    #[serde(rename = "transaction")]
+    Unsigned(RpcUnsignedTransaction),
+}
+
+impl SimulatedTransaction {
+    /// Returns the transaction to simulate and whether its signature should be
+    /// checked.
+    pub fn into_signed_transaction(
+        self,
+    ) -> Result<(SignedTransaction, bool), RpcSimulateTransactionError> {
+        match self {
+            Self::Signed(signed_transaction) => Ok((signed_transaction, true)),
+            Self::Unsigned(transaction) => {
+                let actions = transaction
+                    .actions
+                    .into_iter()
+                    .map(Action::try_from)
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| RpcSimulateTransactionError::InvalidAction {
+                        error_message: err.to_string(),
+                    })?;
+                let transaction = Transaction::V0(TransactionV0 {
+                    signer_id: transaction.signer_id,
+                    public_key: transaction.public_key,
+                    nonce: transaction.nonce,
+                    receiver_id: transaction.receiver_id,
+                    // Not checked by the runtime.
+                    block_hash: CryptoHash::default(),
+                    actions,
+                });
+                Ok((SignedTransaction::new(Signature::default(), transaction), false))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsignedTransaction {
+    pub signer_id: AccountId,
+    /// Access key used to sign the transaction.  Its permissions and nonce
+    /// are checked.
+    pub public_key: PublicKey,
+    pub nonce: Nonce,
+    pub receiver_id: AccountId,
+    pub actions: Vec<ActionView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcSimulateTransactionResponse {
+    #[serde(flatten)]
+    pub result: near_primitives::views::SimulateTransactionView,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSimulateTransactionError {
+    #[error("Transaction is invalid: {context}")]
+    InvalidTransaction { context: near_primitives::errors::InvalidTxError },
+    #[error("Action can't be simulated: {error_message}")]
+    InvalidAction { error_message: String },
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("Block either has never been observed on the node or has been garbage collected: {error_message}")]
+    UnknownBlock {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
+    #[error("The node does not track the shard ID {requested_shard_id}")]
+    UnavailableShard { requested_shard_id: near_primitives::types::ShardId },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSimulateTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcSimulateTransactionError) -> Self {
+        let error_data = match &error {
+            RpcSimulateTransactionError::UnknownBlock { error_message } => {
+                Some(Value::String(format!("Block Not Found: {}", error_message)))
+            }
+            RpcSimulateTransactionError::InvalidTransaction { .. }
+            | RpcSimulateTransactionError::InvalidAction { .. }
+            | RpcSimulateTransactionError::NoSyncedBlocks
+            | RpcSimulateTransactionError::UnavailableShard { .. }
+            | RpcSimulateTransactionError::InternalError { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSimulateTransactionError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
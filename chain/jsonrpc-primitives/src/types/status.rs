@@ -1,6 +1,7 @@
 #[cfg(feature = "debug_types")]
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    DebugBlockStatusData, EpochInfoView, StoreCheckerStatusView, TrackedShardsView,
+    TransactionPoolView, ValidatorStatus,
 };
 #[cfg(feature = "debug_types")]
 use near_primitives::views::{
@@ -36,6 +37,10 @@ pub enum DebugStatusResponse {
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
     SplitStoreStatus(SplitStorageInfoView),
+    // Transaction pools of all the shards.
+    TransactionPool(Vec<TransactionPoolView>),
+    // Findings of the store checker.
+    StoreChecker(StoreCheckerStatusView),
 }
 
 #[cfg(feature = "debug_types")]
@@ -0,0 +1,104 @@
+use serde_json::Value;
+
+/// What a WebSocket client wants to be notified about.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RpcSubscriptionKind {
+    /// Every new block reaching the given finality, in the same format as
+    /// the `block` method.
+    Blocks {
+        #[serde(default)]
+        finality: near_primitives::types::Finality,
+    },
+    /// State changes matching the request in every new block reaching the
+    /// given finality, in the same format as the `EXPERIMENTAL_changes`
+    /// method.  Blocks without matching changes are not reported.
+    StateChanges {
+        #[serde(default)]
+        finality: near_primitives::types::Finality,
+        #[serde(flatten)]
+        state_changes_request: near_primitives::views::StateChangesRequestView,
+    },
+    /// A single notification sent once the transaction reaches `wait_until`,
+    /// in the same format as the `tx` method.  The subscription ends after
+    /// that notification.
+    TxStatus {
+        #[serde(flatten)]
+        transaction_info: crate::types::transactions::TransactionInfo,
+        #[serde(default)]
+        wait_until: near_primitives::views::TxExecutionStatus,
+    },
+}
+
+impl RpcSubscriptionKind {
+    /// Short name used in metrics labels.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Blocks { .. } => "blocks",
+            Self::StateChanges { .. } => "state_changes",
+            Self::TxStatus { .. } => "tx_status",
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeRequest {
+    #[serde(flatten)]
+    pub kind: RpcSubscriptionKind,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscribeResponse {
+    pub subscription_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeRequest {
+    pub subscription_id: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcUnsubscribeResponse {
+    pub subscription_id: String,
+}
+
+/// Payload of the `subscription` notification pushed to WebSocket clients.
+///
+/// Exactly one of `result` and `error` is set.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RpcSubscriptionNotification {
+    pub subscription_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<crate::errors::RpcError>,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSubscriptionError {
+    #[error("Subscriptions are only available over a WebSocket connection")]
+    NotSupported,
+    #[error("Connection already has {limit} active subscriptions")]
+    TooManySubscriptions { limit: usize },
+    #[error("Subscription {subscription_id} does not exist")]
+    UnknownSubscription { subscription_id: String },
+}
+
+impl From<RpcSubscriptionError> for crate::errors::RpcError {
+    fn from(error: RpcSubscriptionError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSubscriptionError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
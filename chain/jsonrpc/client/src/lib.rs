@@ -258,6 +258,45 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_pending_transactions(
+        &self,
+        request: near_jsonrpc_primitives::types::pending_transactions::RpcPendingTransactionsRequest,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::pending_transactions::RpcPendingTransactionsResponse,
+    > {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_pending_transactions", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_simulate_transaction(
+        &self,
+        request: near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionRequest,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::simulate_transaction::RpcSimulateTransactionResponse,
+    > {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_simulate_transaction", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_pending_transaction_status(
+        &self,
+        tx_hash: CryptoHash,
+    ) -> RpcRequest<
+        near_jsonrpc_primitives::types::pending_transactions::RpcPendingTransactionStatusResponse,
+    > {
+        let request =
+            near_jsonrpc_primitives::types::pending_transactions::RpcPendingTransactionStatusRequest {
+                tx_hash,
+            };
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_pending_transaction_status",
+            request,
+        )
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_split_storage_info(
         &self,
@@ -9,14 +9,18 @@ use serde_json::json;
 use near_actix_test_utils::run_actix;
 use near_crypto::{KeyType, PublicKey, Signature};
 use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc_primitives::types::pending_transactions::RpcPendingTransactionsRequest;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
+use near_jsonrpc_primitives::types::simulate_transaction::{
+    RpcSimulateTransactionRequest, RpcUnsignedTransaction, SimulatedTransaction,
+};
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_network::test_utils::wait_or_timeout;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{BlockId, BlockReference, EpochId, SyncCheckpoint};
-use near_primitives::views::QueryRequest;
+use near_primitives::views::{ActionView, ExecutionStatusView, QueryRequest, StateChangeValueView};
 use near_time::Clock;
 
 use near_jsonrpc_tests::{self as test_utils, test_with_client};
@@ -315,6 +319,34 @@ fn test_query_state() {
     });
 }
 
+/// Connect to json rpc and query a page of state.
+#[test]
+fn test_query_state_paged() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let query_response = client
+            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewStatePaged {
+                    account_id: "test".parse().unwrap(),
+                    prefix: vec![].into(),
+                    continuation_token: None,
+                    limit: Some(10),
+                    include_proof: false,
+                },
+            })
+            .await
+            .unwrap();
+        assert_eq!(query_response.block_height, 0);
+        let state = if let QueryResponseKind::ViewState(state) = query_response.kind {
+            state
+        } else {
+            panic!("queried state, but received something else: {:?}", query_response.kind);
+        };
+        assert_eq!(state.values.len(), 0);
+        assert_eq!(state.continuation_token, None);
+    });
+}
+
 /// Connect to json rpc and call function
 #[test]
 fn test_query_call_function() {
@@ -506,6 +538,161 @@ fn test_gas_price() {
     });
 }
 
+/// Inspect the empty transaction pool
+#[test]
+fn test_pending_transactions_empty_pool() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let pending = client
+            .EXPERIMENTAL_pending_transactions(RpcPendingTransactionsRequest {
+                signer_id: Some("test1".parse().unwrap()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(pending.transactions.is_empty());
+        assert_eq!(pending.next_after_tx_hash, None);
+
+        let status =
+            client.EXPERIMENTAL_pending_transaction_status(CryptoHash::default()).await.unwrap();
+        assert!(!status.pending);
+        assert_eq!(status.shard_id, None);
+    });
+}
+
+#[test]
+fn test_pending_transactions_invalid_limit() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let error = client
+            .EXPERIMENTAL_pending_transactions(RpcPendingTransactionsRequest {
+                limit: Some(0),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        let s = serde_json::to_string(&error.data.unwrap()).unwrap();
+        assert!(s.starts_with("\"Limit must be between 1 and 1000"), "{}", s);
+    });
+}
+
+/// Simulate an unsigned transfer between genesis accounts
+#[test]
+fn test_simulate_transaction() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let transaction = RpcUnsignedTransaction {
+            signer_id: "test".parse().unwrap(),
+            public_key: "ed25519:23vYngy8iL7q94jby3gszBnZ9JptpMf5Hgf7KVVa2yQ2".parse().unwrap(),
+            nonce: 1,
+            receiver_id: "test1".parse().unwrap(),
+            actions: vec![ActionView::Transfer { deposit: 1 }],
+        };
+        let transaction = SimulatedTransaction::Unsigned(transaction);
+        let (signed_transaction, _) = transaction.clone().into_signed_transaction().unwrap();
+        let receiver = client
+            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewAccount { account_id: "test1".parse().unwrap() },
+            })
+            .await
+            .unwrap();
+        let QueryResponseKind::ViewAccount(receiver) = receiver.kind else {
+            panic!("queried account, but received something else: {:?}", receiver.kind);
+        };
+        let simulation = client
+            .EXPERIMENTAL_simulate_transaction(RpcSimulateTransactionRequest {
+                transaction,
+                block_reference: None,
+            })
+            .await
+            .unwrap()
+            .result;
+        assert_eq!(simulation.block_height, 0);
+        assert!(simulation.unfinished_receipt_ids.is_empty());
+
+        // The transaction is converted into a receipt which transfers the deposit to
+        // the receiver, possibly followed by a gas refund to the signer.
+        let tx_outcome = &simulation.outcomes[0];
+        assert_eq!(tx_outcome.id, signed_transaction.get_hash());
+        assert_eq!(tx_outcome.outcome.executor_id, "test");
+        assert_eq!(tx_outcome.outcome.receipt_ids.len(), 1);
+        let receipt_id = tx_outcome.outcome.receipt_ids[0];
+        assert_eq!(tx_outcome.outcome.status, ExecutionStatusView::SuccessReceiptId(receipt_id));
+        let receipt_outcome = &simulation.outcomes[1];
+        assert_eq!(receipt_outcome.id, receipt_id);
+        assert_eq!(receipt_outcome.outcome.executor_id, "test1");
+        assert_eq!(receipt_outcome.outcome.status, ExecutionStatusView::SuccessValue(vec![]));
+        for outcome in &simulation.outcomes[2..] {
+            assert_eq!(outcome.outcome.executor_id, "test");
+            assert_eq!(outcome.outcome.status, ExecutionStatusView::SuccessValue(vec![]));
+        }
+        assert_eq!(
+            simulation.total_gas_burnt,
+            simulation.outcomes.iter().map(|outcome| outcome.outcome.gas_burnt).sum::<u64>()
+        );
+        assert!(simulation.total_gas_burnt > 0);
+
+        // The nonce of the signer is bumped and the deposit reaches the receiver.
+        assert!(simulation.state_changes.iter().any(|change| matches!(
+            &change.value,
+            StateChangeValueView::AccessKeyUpdate { account_id, access_key, .. }
+                if account_id.as_str() == "test" && access_key.nonce == 1
+        )));
+        let receiver_amount = simulation
+            .state_changes
+            .iter()
+            .filter_map(|change| match &change.value {
+                StateChangeValueView::AccountUpdate { account_id, account }
+                    if account_id.as_str() == "test1" =>
+                {
+                    Some(account.amount)
+                }
+                _ => None,
+            })
+            .last()
+            .unwrap();
+        assert_eq!(receiver_amount, receiver.amount + 1);
+
+        // Nothing is persisted, the same nonce can be simulated again.
+        let access_key = client
+            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewAccessKey {
+                    account_id: "test".parse().unwrap(),
+                    public_key: "ed25519:23vYngy8iL7q94jby3gszBnZ9JptpMf5Hgf7KVVa2yQ2"
+                        .parse()
+                        .unwrap(),
+                },
+            })
+            .await
+            .unwrap();
+        let QueryResponseKind::AccessKey(access_key) = access_key.kind else {
+            panic!("queried access key, but received something else: {:?}", access_key.kind);
+        };
+        assert_eq!(access_key.nonce, 0);
+    });
+}
+
+#[test]
+fn test_simulate_transaction_unknown_signer() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let transaction = RpcUnsignedTransaction {
+            signer_id: "unknown".parse().unwrap(),
+            public_key: "ed25519:23vYngy8iL7q94jby3gszBnZ9JptpMf5Hgf7KVVa2yQ2".parse().unwrap(),
+            nonce: 1,
+            receiver_id: "test1".parse().unwrap(),
+            actions: vec![ActionView::Transfer { deposit: 1 }],
+        };
+        let error = client
+            .EXPERIMENTAL_simulate_transaction(RpcSimulateTransactionRequest {
+                transaction: SimulatedTransaction::Unsigned(transaction),
+                block_reference: None,
+            })
+            .await
+            .unwrap_err();
+        let s = serde_json::to_string(&error.data.unwrap()).unwrap();
+        assert!(s.starts_with("\"Transaction is invalid"), "{}", s);
+    });
+}
+
 #[test]
 fn test_invalid_methods() {
     test_with_client!(test_utils::NodeType::NonValidator, client, async move {
@@ -550,6 +737,115 @@ fn test_invalid_methods() {
     });
 }
 
+/// Every request of a batch gets its own response, including the failing ones.
+#[test]
+fn test_batch_request() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+            {"jsonrpc": "2.0", "id": 2, "method": "no_such_method", "params": []},
+            {"jsonrpc": "2.0", "id": 3, "method": "gas_price", "params": [null]},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response =
+            serde_json::from_value::<serde_json::Value>(response.json().await.unwrap()).unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        let response_by_id =
+            |id: u64| responses.iter().find(|response| response["id"] == id).unwrap();
+        assert_eq!(response_by_id(1)["result"]["header"]["height"], 0);
+        assert_eq!(response_by_id(2)["error"]["cause"]["name"], "METHOD_NOT_FOUND");
+        assert!(response_by_id(3)["result"]["gas_price"].is_string());
+    });
+}
+
+/// Batches larger than `max_batch_size` are rejected as a whole.
+#[test]
+fn test_batch_request_too_large() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let max_batch_size = near_jsonrpc::RpcLimitsConfig::default().max_batch_size;
+        let json = serde_json::Value::Array(
+            (0..=max_batch_size)
+                .map(|id| serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "status"}))
+                .collect(),
+        );
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    });
+}
+
+/// A batch made only of notifications gets no reply at all.
+#[test]
+fn test_batch_of_notifications() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "method": "status", "params": []},
+            {"jsonrpc": "2.0", "method": "gas_price", "params": [null]},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.body().await.unwrap().is_empty());
+    });
+}
+
+/// Entries of a batch which aren't requests get an Invalid Request error,
+/// while the other entries are processed as usual.
+#[test]
+fn test_batch_with_invalid_entries() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let json = serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "block", "params": {"block_id": 0}},
+            {"jsonrpc": "2.0", "method": "status", "params": []},
+            1,
+            {"foo": "bar"},
+        ]);
+        let response = &mut client
+            .client
+            .post(&client.server_addr)
+            .insert_header(("Content-Type", "application/json"))
+            .send_json(&json)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response =
+            serde_json::from_value::<serde_json::Value>(response.json().await.unwrap()).unwrap();
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses.iter().filter(|response| response["id"] == 1).count(), 1);
+        let errors: Vec<_> = responses.iter().filter(|response| response["id"].is_null()).collect();
+        assert_eq!(errors.len(), 2);
+        for error in errors {
+            assert_eq!(error["error"]["code"], -32600);
+            assert_eq!(error["error"]["cause"]["name"], "INVALID_REQUEST");
+        }
+    });
+}
+
 #[test]
 fn test_parse_error_status_code() {
     test_with_client!(test_utils::NodeType::NonValidator, client, async move {
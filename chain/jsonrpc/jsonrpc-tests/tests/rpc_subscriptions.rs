@@ -0,0 +1,113 @@
+use actix::System;
+use awc::ws;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+
+use near_jsonrpc::client::new_client;
+use near_o11y::testonly::init_test_logger;
+
+use near_jsonrpc_tests::{self as test_utils, test_with_client};
+
+/// Reads frames until a text frame arrives and parses it as JSON.
+async fn next_json<S, E>(connection: &mut S) -> Value
+where
+    S: futures::Stream<Item = Result<ws::Frame, E>> + Unpin,
+    E: std::fmt::Debug,
+{
+    loop {
+        match connection.next().await.unwrap().unwrap() {
+            ws::Frame::Text(text) => return serde_json::from_slice(&text).unwrap(),
+            ws::Frame::Ping(_) | ws::Frame::Pong(_) => {}
+            frame => panic!("unexpected frame: {:?}", frame),
+        }
+    }
+}
+
+/// Subscribe to optimistic blocks over WebSocket and check that consecutive
+/// notifications report increasing heights.
+#[test]
+fn test_subscribe_blocks() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let ws_addr = format!("{}/ws", client.server_addr.replacen("http", "ws", 1));
+        let (_response, mut connection) = client.client.ws(ws_addr).connect().await.unwrap();
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "method": "subscribe",
+            "params": {"kind": "blocks", "finality": "optimistic"},
+        });
+        connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+        let response = next_json(&mut connection).await;
+        let subscription_id = response["result"]["subscription_id"].as_str().unwrap().to_owned();
+
+        let mut last_height = None;
+        for _ in 0..3 {
+            let notification = next_json(&mut connection).await;
+            assert_eq!(notification["method"], "subscription");
+            assert_eq!(notification["params"]["subscription_id"], subscription_id);
+            let height = notification["params"]["result"]["header"]["height"].as_u64().unwrap();
+            if let Some(last_height) = last_height {
+                assert!(height > last_height);
+            }
+            last_height = Some(height);
+        }
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "method": "unsubscribe",
+            "params": [subscription_id],
+        });
+        connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+        // Notifications which were already queued may still arrive before the reply.
+        loop {
+            let message = next_json(&mut connection).await;
+            if message["id"] == "dontcare" {
+                assert_eq!(message["result"]["subscription_id"], subscription_id);
+                break;
+            }
+        }
+    });
+}
+
+/// Regular methods are served over the WebSocket too, while subscriptions are
+/// rejected over plain HTTP.
+#[test]
+fn test_subscriptions_require_websocket() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let ws_addr = format!("{}/ws", client.server_addr.replacen("http", "ws", 1));
+        let (_response, mut connection) = client.client.ws(ws_addr).connect().await.unwrap();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "method": "block",
+            "params": {"block_id": 0},
+        });
+        connection.send(ws::Message::Text(request.to_string().into())).await.unwrap();
+        let response = next_json(&mut connection).await;
+        assert_eq!(response["result"]["header"]["height"], 0);
+
+        for (method, params) in
+            [("subscribe", json!({"kind": "blocks"})), ("unsubscribe", json!(["dontcare"]))]
+        {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "method": method,
+                "params": params,
+            });
+            let response: Value = client
+                .client
+                .post(&client.server_addr)
+                .insert_header(("Content-Type", "application/json"))
+                .send_json(&request)
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["error"]["cause"]["name"], "NOT_SUPPORTED", "{method}");
+        }
+    });
+}
@@ -14,11 +14,14 @@ mod gas_price;
 mod light_client;
 mod maintenance;
 mod network_info;
+mod pending_transactions;
 mod query;
 mod receipts;
 mod sandbox;
+mod simulate_transaction;
 mod split_storage;
 mod status;
+mod subscriptions;
 mod transactions;
 mod validator;
 
@@ -0,0 +1,50 @@
+use near_async::messaging::AsyncSendError;
+use near_client_primitives::types::PendingTransactionsView;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::pending_transactions::{
+    RpcPendingTransaction, RpcPendingTransactionStatusRequest, RpcPendingTransactionsError,
+    RpcPendingTransactionsRequest, RpcPendingTransactionsResponse,
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcPendingTransactionsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcPendingTransactionStatusRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcPendingTransactionsError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<String> for RpcPendingTransactionsError {
+    fn rpc_from(error_message: String) -> Self {
+        Self::InternalError { error_message }
+    }
+}
+
+impl RpcFrom<PendingTransactionsView> for RpcPendingTransactionsResponse {
+    fn rpc_from(view: PendingTransactionsView) -> Self {
+        Self {
+            transactions: view
+                .transactions
+                .into_iter()
+                .map(|tx| RpcPendingTransaction {
+                    shard_id: tx.shard_id,
+                    transaction: tx.transaction,
+                })
+                .collect(),
+            next_after_tx_hash: view.next_after_tx_hash,
+        }
+    }
+}
@@ -17,16 +17,28 @@ use near_client::{
     GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
     ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
 };
-use near_client_primitives::types::GetSplitStorageInfo;
+use near_client_primitives::debug::{GetStoreCheckerStatus, StoreCheckerStatusView};
+use near_client_primitives::types::{
+    GetPendingTransactionStatus, GetPendingTransactions, GetSplitStorageInfo, SimulateTransaction,
+};
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::{RpcError, RpcErrorKind};
 use near_jsonrpc_primitives::message::{Message, Request};
 use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigResponse};
 use near_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQuery};
+use near_jsonrpc_primitives::types::pending_transactions::{
+    RpcPendingTransactionStatusRequest, RpcPendingTransactionStatusResponse,
+    RpcPendingTransactionsError, RpcPendingTransactionsRequest, RpcPendingTransactionsResponse,
+    DEFAULT_PENDING_TRANSACTIONS_LIMIT, MAX_PENDING_TRANSACTIONS_LIMIT,
+};
 use near_jsonrpc_primitives::types::query::RpcQueryRequest;
+use near_jsonrpc_primitives::types::simulate_transaction::{
+    RpcSimulateTransactionError, RpcSimulateTransactionRequest, RpcSimulateTransactionResponse,
+};
 use near_jsonrpc_primitives::types::split_storage::{
     RpcSplitStorageInfoRequest, RpcSplitStorageInfoResponse,
 };
+use near_jsonrpc_primitives::types::subscriptions::RpcSubscriptionError;
 use near_jsonrpc_primitives::types::transactions::{
     RpcSendTransactionRequest, RpcTransactionResponse,
 };
@@ -46,6 +58,7 @@ use tracing::{error, info};
 
 mod api;
 mod metrics;
+mod subscriptions;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -66,11 +79,41 @@ impl Default for RpcPollingConfig {
 pub struct RpcLimitsConfig {
     /// Maximum byte size of the json payload.
     pub json_payload_max_size: usize,
+    /// Maximum number of simultaneous subscriptions on a single WebSocket
+    /// connection.
+    #[serde(default = "default_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: usize,
+    /// Maximum number of requests processed concurrently for a single
+    /// WebSocket connection.  Further frames aren't read from the connection
+    /// until one of the requests completes.
+    #[serde(default = "default_max_concurrent_requests_per_connection")]
+    pub max_concurrent_requests_per_connection: usize,
+    /// Maximum number of requests in a single JSON RPC batch.
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: usize,
+}
+
+fn default_max_subscriptions_per_connection() -> usize {
+    100
+}
+
+fn default_max_concurrent_requests_per_connection() -> usize {
+    16
+}
+
+fn default_max_batch_size() -> usize {
+    100
 }
 
 impl Default for RpcLimitsConfig {
     fn default() -> Self {
-        Self { json_payload_max_size: 10 * 1024 * 1024 }
+        Self {
+            json_payload_max_size: 10 * 1024 * 1024,
+            max_subscriptions_per_connection: default_max_subscriptions_per_connection(),
+            max_concurrent_requests_per_connection: default_max_concurrent_requests_per_connection(
+            ),
+            max_batch_size: default_max_batch_size(),
+        }
     }
 }
 
@@ -223,6 +266,8 @@ pub struct ClientSenderForRpc(
     AsyncSender<DebugStatus, ActixResult<DebugStatus>>,
     AsyncSender<GetClientConfig, ActixResult<GetClientConfig>>,
     AsyncSender<GetNetworkInfo, ActixResult<GetNetworkInfo>>,
+    AsyncSender<GetPendingTransactionStatus, ActixResult<GetPendingTransactionStatus>>,
+    AsyncSender<GetPendingTransactions, ActixResult<GetPendingTransactions>>,
     AsyncSender<ProcessTxRequest, ActixResult<ProcessTxRequest>>,
     AsyncSender<Status, ActixResult<Status>>,
     Sender<ProcessTxRequest>,
@@ -256,6 +301,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetValidatorInfo, ActixResult<GetValidatorInfo>>,
     AsyncSender<GetValidatorOrdered, ActixResult<GetValidatorOrdered>>,
     AsyncSender<Query, ActixResult<Query>>,
+    AsyncSender<SimulateTransaction, ActixResult<SimulateTransaction>>,
     AsyncSender<TxStatus, ActixResult<TxStatus>>,
     #[cfg(feature = "test_features")] Sender<near_client::NetworkAdversarialMessage>,
 );
@@ -272,13 +318,21 @@ pub struct GCSenderForRpc(
 #[derive(Clone, near_async::MultiSend, near_async::MultiSenderFrom)]
 pub struct PeerManagerSenderForRpc(AsyncSender<GetDebugStatus, ActixResult<GetDebugStatus>>);
 
+#[derive(Clone, near_async::MultiSend, near_async::MultiSenderFrom)]
+pub struct StoreCheckerSenderForRpc(
+    AsyncSender<GetStoreCheckerStatus, ActixResult<GetStoreCheckerStatus>>,
+);
+
 struct JsonRpcHandler {
     client_sender: ClientSenderForRpc,
     view_client_sender: ViewClientSenderForRpc,
     peer_manager_sender: PeerManagerSenderForRpc,
+    /// `None` if the store checker is disabled.
+    store_checker_sender: Option<StoreCheckerSenderForRpc>,
     #[cfg(feature = "test_features")]
     gc_sender: GCSenderForRpc,
     polling_config: RpcPollingConfig,
+    limits_config: RpcLimitsConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
@@ -286,14 +340,59 @@ struct JsonRpcHandler {
 }
 
 impl JsonRpcHandler {
-    async fn process(&self, message: Message) -> Message {
+    /// Returns `None` if there is nothing to reply, i.e. for a batch made
+    /// only of notifications.
+    async fn process(&self, message: Message) -> Option<Message> {
         let id = message.id();
         match message {
-            Message::Request(request) => Message::response(id, self.process_request(request).await),
-            _ => Message::error(RpcError::parse_error(
+            Message::Request(request) => {
+                Some(Message::response(id, self.process_request(request).await))
+            }
+            Message::Batch(messages) => self.process_batch(messages).await,
+            _ => Some(Message::error(RpcError::parse_error(
                 "JSON RPC Request format was expected".to_owned(),
-            )),
+            ))),
+        }
+    }
+
+    /// Processes all requests of a batch concurrently.
+    ///
+    /// Every request gets its own response, so a failure of one of them
+    /// doesn't affect the others.  Notifications don't get a response, and
+    /// if the batch has nothing but notifications, there's no reply at all.
+    async fn process_batch(&self, messages: Vec<Message>) -> Option<Message> {
+        if messages.is_empty() {
+            return Some(Message::error(RpcError::invalid_request(
+                "JSON RPC batch must not be empty".to_owned(),
+            )));
         }
+        let max_batch_size = self.limits_config.max_batch_size;
+        if messages.len() > max_batch_size {
+            return Some(Message::error(RpcError::invalid_request(format!(
+                "JSON RPC batch of {} requests exceeds the limit of {}",
+                messages.len(),
+                max_batch_size
+            ))));
+        }
+        metrics::RPC_BATCH_SIZE.observe(messages.len() as f64);
+        let responses = futures::future::join_all(messages.into_iter().map(|message| async move {
+            let id = message.id();
+            match message {
+                Message::Request(request) => {
+                    Some(Message::response(id, self.process_request(request).await))
+                }
+                Message::Notification(_) => None,
+                _ => Some(Message::error(RpcError::invalid_request(
+                    "JSON RPC Request format was expected".to_owned(),
+                ))),
+            }
+        }))
+        .await;
+        let responses: Vec<_> = responses.into_iter().flatten().collect();
+        if responses.is_empty() {
+            return None;
+        }
+        Some(Message::Batch(responses))
     }
 
     // `process_request` increments affected metrics but the request processing is done by
@@ -350,6 +449,13 @@ impl JsonRpcHandler {
                             "query_view_state"
                         }
                     }
+                    QueryRequest::ViewStatePaged { include_proof, .. } => {
+                        if include_proof {
+                            "query_view_state_paged_with_proof"
+                        } else {
+                            "query_view_state_paged"
+                        }
+                    }
                     QueryRequest::ViewAccessKey { .. } => "query_view_access_key",
                     QueryRequest::ViewAccessKeyList { .. } => "query_view_access_key_list",
                     QueryRequest::CallFunction { .. } => "query_call_function",
@@ -425,12 +531,21 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_light_client_block_proof" => {
                 process_method_call(request, |params| self.light_client_block_proof(params)).await
             }
+            "EXPERIMENTAL_pending_transactions" => {
+                process_method_call(request, |params| self.pending_transactions(params)).await
+            }
+            "EXPERIMENTAL_pending_transaction_status" => {
+                process_method_call(request, |params| self.pending_transaction_status(params)).await
+            }
             "EXPERIMENTAL_protocol_config" => {
                 process_method_call(request, |params| self.protocol_config(params)).await
             }
             "EXPERIMENTAL_receipt" => {
                 process_method_call(request, |params| self.receipt(params)).await
             }
+            "EXPERIMENTAL_simulate_transaction" => {
+                process_method_call(request, |params| self.simulate_transaction(params)).await
+            }
             "EXPERIMENTAL_tx_status" => {
                 process_method_call(request, |params| self.tx_status_common(params, true)).await
             }
@@ -443,6 +558,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            // Subscriptions are handled by the WebSocket connection itself. The
+            // params are not parsed, so that any subscription request gets the same error.
+            "subscribe" | "unsubscribe" => Err(RpcSubscriptionError::NotSupported.into()),
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -797,6 +915,9 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/transaction_pool" => {
+                        self.client_send(DebugStatus::TransactionPool).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -819,6 +940,18 @@ impl JsonRpcHandler {
                         .peer_manager_send(near_network::debug::GetDebugStatus::SnapshotHosts)
                         .await?
                         .rpc_into(),
+                    "/debug/api/store_checker" => {
+                        let status = match &self.store_checker_sender {
+                            Some(sender) => sender
+                                .send_async(GetStoreCheckerStatus)
+                                .await
+                                .map_err(RpcFrom::rpc_from)?,
+                            None => StoreCheckerStatusView::default(),
+                        };
+                        near_jsonrpc_primitives::types::status::DebugStatusResponse::StoreChecker(
+                            status,
+                        )
+                    }
                     "/debug/api/split_store_info" => {
                         let split_storage_info: RpcSplitStorageInfoResponse = self
                             .split_storage_info(RpcSplitStorageInfoRequest {})
@@ -1170,6 +1303,53 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::client_config::RpcClientConfigResponse { client_config })
     }
 
+    async fn pending_transactions(
+        &self,
+        request: RpcPendingTransactionsRequest,
+    ) -> Result<RpcPendingTransactionsResponse, RpcPendingTransactionsError> {
+        let limit = request.limit.unwrap_or(DEFAULT_PENDING_TRANSACTIONS_LIMIT);
+        if limit == 0 || limit > MAX_PENDING_TRANSACTIONS_LIMIT {
+            return Err(RpcPendingTransactionsError::InvalidLimit {
+                limit,
+                max_limit: MAX_PENDING_TRANSACTIONS_LIMIT,
+            });
+        }
+        let pending_transactions = self
+            .client_send(GetPendingTransactions {
+                signer_id: request.signer_id,
+                receiver_id: request.receiver_id,
+                after_tx_hash: request.after_tx_hash,
+                limit: limit as usize,
+            })
+            .await?;
+        Ok(pending_transactions.rpc_into())
+    }
+
+    async fn pending_transaction_status(
+        &self,
+        request: RpcPendingTransactionStatusRequest,
+    ) -> Result<RpcPendingTransactionStatusResponse, RpcPendingTransactionsError> {
+        let tx_hash = request.tx_hash;
+        let shard_id = self.client_send(GetPendingTransactionStatus { tx_hash }).await?;
+        Ok(RpcPendingTransactionStatusResponse { tx_hash, pending: shard_id.is_some(), shard_id })
+    }
+
+    async fn simulate_transaction(
+        &self,
+        request: RpcSimulateTransactionRequest,
+    ) -> Result<RpcSimulateTransactionResponse, RpcSimulateTransactionError> {
+        let (transaction, verify_signature) = request.transaction.into_signed_transaction()?;
+        let block_reference = request.block_reference.unwrap_or_else(BlockReference::latest);
+        let result = self
+            .view_client_send(SimulateTransaction {
+                transaction,
+                verify_signature,
+                block_reference,
+            })
+            .await?;
+        Ok(result.rpc_into())
+    }
+
     pub async fn split_storage_info(
         &self,
         _request_data: near_jsonrpc_primitives::types::split_storage::RpcSplitStorageInfoRequest,
@@ -1355,8 +1535,13 @@ async fn rpc_handler(
     message: web::Json<Message>,
     handler: web::Data<JsonRpcHandler>,
 ) -> HttpResponse {
-    let message = handler.process(message.0).await;
-    let mut response = if let Message::Response(response) = &message {
+    let Some(message) = handler.process(message.0).await else {
+        return HttpResponse::NoContent().finish();
+    };
+    let mut response = if let Message::Batch(_) = &message {
+        // Errors are reported per request within a batch.
+        HttpResponse::Ok()
+    } else if let Message::Response(response) = &message {
         match &response.result {
             Ok(_) => HttpResponse::Ok(),
             Err(err) => match &err.error_struct {
@@ -1548,6 +1733,7 @@ pub fn start_http(
     client_sender: ClientSenderForRpc,
     view_client_sender: ViewClientSenderForRpc,
     peer_manager_sender: PeerManagerSenderForRpc,
+    store_checker_sender: Option<StoreCheckerSenderForRpc>,
     #[cfg(feature = "test_features")] gc_sender: GCSenderForRpc,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
 ) -> Vec<(&'static str, actix_web::dev::ServerHandle)> {
@@ -1571,7 +1757,9 @@ pub fn start_http(
                 client_sender: client_sender.clone(),
                 view_client_sender: view_client_sender.clone(),
                 peer_manager_sender: peer_manager_sender.clone(),
+                store_checker_sender: store_checker_sender.clone(),
                 polling_config,
+                limits_config: limits_config.clone(),
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
@@ -1582,6 +1770,7 @@ pub fn start_http(
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
             .service(web::resource("/").route(web::post().to(rpc_handler)))
+            .service(web::resource("/ws").route(web::get().to(subscriptions::ws_handler)))
             .service(
                 web::resource("/status")
                     .route(web::get().to(status_handler))
@@ -1,4 +1,6 @@
-use near_o11y::metrics::{exponential_buckets, HistogramVec, IntCounter, IntCounterVec};
+use near_o11y::metrics::{
+    exponential_buckets, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
 use std::sync::LazyLock;
 
 pub static RPC_PROCESSING_TIME: LazyLock<HistogramVec> = LazyLock::new(|| {
@@ -55,3 +57,26 @@ pub static RPC_UNREACHABLE_ERROR_COUNT: LazyLock<IntCounterVec> = LazyLock::new(
     )
     .unwrap()
 });
+pub static RPC_WS_ACTIVE_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    near_o11y::metrics::try_create_int_gauge(
+        "near_rpc_ws_active_connections",
+        "Number of open WebSocket connections",
+    )
+    .unwrap()
+});
+pub static RPC_WS_ACTIVE_SUBSCRIPTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    near_o11y::metrics::try_create_int_gauge_vec(
+        "near_rpc_ws_active_subscriptions",
+        "Number of active WebSocket subscriptions, by subscription kind",
+        &["kind"],
+    )
+    .unwrap()
+});
+pub static RPC_BATCH_SIZE: LazyLock<Histogram> = LazyLock::new(|| {
+    near_o11y::metrics::try_create_histogram_with_buckets(
+        "near_rpc_batch_size",
+        "Number of requests in JSON RPC batches",
+        exponential_buckets(1.0, 2.0, 12).unwrap(),
+    )
+    .unwrap()
+});
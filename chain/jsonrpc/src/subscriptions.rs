@@ -0,0 +1,463 @@
+//! WebSocket endpoint serving JSON RPC requests and push subscriptions.
+//!
+//! Every text frame received on `/ws` is handled as a JSON RPC request.  On
+//! top of the regular methods, the connection understands `subscribe` and
+//! `unsubscribe`.  Subscriptions poll the view client at the configured
+//! polling interval, the same way the HTTP handlers wait for transactions, and
+//! push `subscription` notifications whose payloads match the responses of the
+//! corresponding HTTP methods.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_http::ws;
+use actix_web::http::header;
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::{web, Error as HttpError, HttpRequest, HttpResponse};
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio_util::codec::{Decoder, Encoder};
+
+use near_client::{GetStateChanges, TxStatus};
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{self, Message};
+use near_jsonrpc_primitives::types::blocks::{RpcBlockError, RpcBlockRequest, RpcBlockResponse};
+use near_jsonrpc_primitives::types::changes::{
+    RpcStateChangesError, RpcStateChangesInBlockResponse,
+};
+use near_jsonrpc_primitives::types::subscriptions::{
+    RpcSubscribeRequest, RpcSubscribeResponse, RpcSubscriptionError, RpcSubscriptionKind,
+    RpcSubscriptionNotification, RpcUnsubscribeRequest, RpcUnsubscribeResponse,
+};
+use near_jsonrpc_primitives::types::transactions::{
+    RpcTransactionError, RpcTransactionResponse, TransactionInfo,
+};
+use near_primitives::types::{BlockHeight, BlockId, BlockReference, Finality};
+use near_primitives::views::{BlockView, StateChangesRequestView, TxExecutionStatus};
+
+use crate::{
+    metrics, process_method_call, serialize_response, tx_execution_status_meets_expectations,
+    JsonRpcHandler,
+};
+
+/// JSON RPC method of the notifications pushed for active subscriptions.
+const SUBSCRIPTION_NOTIFICATION_METHOD: &str = "subscription";
+
+/// Maximum number of blocks reported in one polling round.  If the node has
+/// advanced further than that since the previous round (e.g. it was catching
+/// up), the older blocks are skipped.
+const MAX_CATCH_UP_BLOCKS: BlockHeight = 64;
+
+/// Capacity of the queue of frames waiting to be written to the socket.
+const OUTGOING_QUEUE_CAPACITY: usize = 256;
+
+/// Upgrades the HTTP request to a WebSocket connection.
+pub(crate) async fn ws_handler(
+    req: HttpRequest,
+    payload: web::Payload,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    ws::verify_handshake(req.head())?;
+    let key = req.headers().get(header::SEC_WEBSOCKET_KEY).expect("checked by verify_handshake");
+    let accept = ws::hash_key(key.as_bytes());
+
+    let (outgoing, outgoing_receiver) = mpsc::channel(OUTGOING_QUEUE_CAPACITY);
+    let requests = Arc::new(Semaphore::new(
+        handler.limits_config.max_concurrent_requests_per_connection.max(1),
+    ));
+    let connection = Connection {
+        handler,
+        outgoing,
+        requests,
+        subscriptions: HashMap::new(),
+        next_subscription_id: 0,
+    };
+    actix_web::rt::spawn(connection.run(payload));
+
+    Ok(HttpResponse::SwitchingProtocols()
+        .upgrade("websocket")
+        .insert_header((
+            header::SEC_WEBSOCKET_ACCEPT,
+            // The hashed key is base64 and hence always a valid header value.
+            header::HeaderValue::from_bytes(&accept).unwrap(),
+        ))
+        .streaming(outgoing_receiver.map(encode_frame)))
+}
+
+fn encode_frame(message: ws::Message) -> Result<Bytes, ws::ProtocolError> {
+    let mut buffer = BytesMut::new();
+    ws::Codec::new().encode(message, &mut buffer)?;
+    Ok(buffer.freeze())
+}
+
+fn text_frame(message: Message) -> ws::Message {
+    let text: String = message.into();
+    ws::Message::Text(text.into())
+}
+
+/// State of a single WebSocket connection.
+struct Connection {
+    handler: web::Data<JsonRpcHandler>,
+    outgoing: mpsc::Sender<ws::Message>,
+    /// Limits the number of requests processed in the background at once.
+    requests: Arc<Semaphore>,
+    subscriptions: HashMap<String, JoinHandle<()>>,
+    next_subscription_id: u64,
+}
+
+impl Connection {
+    /// Reads frames until the client closes the connection, then cancels all
+    /// subscriptions which are still running.
+    async fn run(mut self, mut payload: web::Payload) {
+        metrics::RPC_WS_ACTIVE_CONNECTIONS.inc();
+        let mut codec = ws::Codec::new().max_size(self.handler.limits_config.json_payload_max_size);
+        let mut buffer = BytesMut::new();
+        'connection: while let Some(chunk) = payload.next().await {
+            match chunk {
+                Ok(chunk) => buffer.extend_from_slice(&chunk),
+                Err(err) => {
+                    tracing::debug!(target: "jsonrpc", ?err, "WebSocket connection failed");
+                    break;
+                }
+            }
+            loop {
+                let frame = match codec.decode(&mut buffer) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => break,
+                    Err(err) => {
+                        tracing::debug!(target: "jsonrpc", ?err, "Received invalid WebSocket frame");
+                        self.send(ws::Message::Close(Some(ws::CloseCode::Protocol.into()))).await;
+                        break 'connection;
+                    }
+                };
+                let reply = match frame {
+                    ws::Frame::Text(text) => self.process_text(&text).await.map(text_frame),
+                    ws::Frame::Ping(data) => Some(ws::Message::Pong(data)),
+                    ws::Frame::Pong(_) => None,
+                    ws::Frame::Close(reason) => {
+                        self.send(ws::Message::Close(reason)).await;
+                        break 'connection;
+                    }
+                    ws::Frame::Binary(_) | ws::Frame::Continuation(_) => {
+                        self.send(ws::Message::Close(Some(ws::CloseCode::Unsupported.into())))
+                            .await;
+                        break 'connection;
+                    }
+                };
+                if let Some(reply) = reply {
+                    if !self.send(reply).await {
+                        break 'connection;
+                    }
+                }
+            }
+        }
+        for (_, subscription) in self.subscriptions.drain() {
+            subscription.abort();
+        }
+        metrics::RPC_WS_ACTIVE_CONNECTIONS.dec();
+    }
+
+    /// Returns `false` if the connection has been closed.
+    async fn send(&mut self, message: ws::Message) -> bool {
+        self.outgoing.send(message).await.is_ok()
+    }
+
+    /// Handles a single JSON RPC message.
+    ///
+    /// Subscription management is done inline.  Any other method, as well as
+    /// batches, is processed in the background so that a slow request (e.g.
+    /// `send_tx` waiting for execution) doesn't hold up the rest of the
+    /// connection; its reply is sent once ready and `None` is returned.  Once
+    /// `max_concurrent_requests_per_connection` requests are in flight, this
+    /// waits for one of them to complete, so that a client can't pile up an
+    /// unbounded number of requests on the node.
+    async fn process_text(&mut self, text: &[u8]) -> Option<Message> {
+        let request = match message::from_slice(text) {
+            Ok(Message::Request(request)) => request,
+            Ok(batch @ Message::Batch(_)) => {
+                self.process_in_background(batch).await;
+                return None;
+            }
+            Ok(_) => {
+                return Some(Message::error(RpcError::parse_error(
+                    "JSON RPC Request format was expected".to_owned(),
+                )))
+            }
+            Err(broken) => return Some(broken.reply()),
+        };
+        let id = request.id.clone();
+        match request.method.as_ref() {
+            "subscribe" => {
+                let result = process_method_call(request, |params| {
+                    std::future::ready(self.subscribe(params))
+                })
+                .await;
+                Some(Message::response(id, result))
+            }
+            "unsubscribe" => {
+                let result = process_method_call(request, |params| {
+                    std::future::ready(self.unsubscribe(params))
+                })
+                .await;
+                Some(Message::response(id, result))
+            }
+            _ => {
+                self.process_in_background(Message::Request(request)).await;
+                None
+            }
+        }
+    }
+
+    async fn process_in_background(&self, message: Message) {
+        // The semaphore is never closed.
+        let permit = self.requests.clone().acquire_owned().await.unwrap();
+        let handler = self.handler.clone();
+        let mut outgoing = self.outgoing.clone();
+        actix_web::rt::spawn(async move {
+            if let Some(reply) = handler.process(message).await {
+                let _ = outgoing.send(text_frame(reply)).await;
+            }
+            drop(permit);
+        });
+    }
+
+    fn subscribe(
+        &mut self,
+        request: RpcSubscribeRequest,
+    ) -> Result<RpcSubscribeResponse, RpcSubscriptionError> {
+        self.subscriptions.retain(|_, subscription| !subscription.is_finished());
+        let limit = self.handler.limits_config.max_subscriptions_per_connection;
+        if self.subscriptions.len() >= limit {
+            return Err(RpcSubscriptionError::TooManySubscriptions { limit });
+        }
+        self.next_subscription_id += 1;
+        let subscription_id = self.next_subscription_id.to_string();
+        let subscription = Subscription {
+            handler: self.handler.clone(),
+            outgoing: self.outgoing.clone(),
+            subscription_id: subscription_id.clone(),
+        };
+        let task = actix_web::rt::spawn(subscription.run(request.kind));
+        self.subscriptions.insert(subscription_id.clone(), task);
+        Ok(RpcSubscribeResponse { subscription_id })
+    }
+
+    fn unsubscribe(
+        &mut self,
+        request: RpcUnsubscribeRequest,
+    ) -> Result<RpcUnsubscribeResponse, RpcSubscriptionError> {
+        let RpcUnsubscribeRequest { subscription_id } = request;
+        match self.subscriptions.remove(&subscription_id) {
+            Some(subscription) => {
+                subscription.abort();
+                Ok(RpcUnsubscribeResponse { subscription_id })
+            }
+            None => Err(RpcSubscriptionError::UnknownSubscription { subscription_id }),
+        }
+    }
+}
+
+/// Keeps the active subscriptions gauge up to date, including when the
+/// subscription task is aborted.
+struct ActiveSubscriptionGuard(&'static str);
+
+impl ActiveSubscriptionGuard {
+    fn new(kind: &'static str) -> Self {
+        metrics::RPC_WS_ACTIVE_SUBSCRIPTIONS.with_label_values(&[kind]).inc();
+        Self(kind)
+    }
+}
+
+impl Drop for ActiveSubscriptionGuard {
+    fn drop(&mut self) {
+        metrics::RPC_WS_ACTIVE_SUBSCRIPTIONS.with_label_values(&[self.0]).dec();
+    }
+}
+
+/// A single subscription running as a task of its own.
+struct Subscription {
+    handler: web::Data<JsonRpcHandler>,
+    outgoing: mpsc::Sender<ws::Message>,
+    subscription_id: String,
+}
+
+impl Subscription {
+    async fn run(mut self, kind: RpcSubscriptionKind) {
+        let _guard = ActiveSubscriptionGuard::new(kind.name());
+        match kind {
+            RpcSubscriptionKind::Blocks { finality } => self.follow_blocks(finality).await,
+            RpcSubscriptionKind::StateChanges { finality, state_changes_request } => {
+                self.follow_state_changes(finality, state_changes_request).await
+            }
+            RpcSubscriptionKind::TxStatus { transaction_info, wait_until } => {
+                self.wait_for_tx(transaction_info, wait_until).await
+            }
+        }
+    }
+
+    async fn follow_blocks(&mut self, finality: Finality) {
+        let mut follower = BlockFollower::new(finality);
+        loop {
+            match follower.next_blocks(&self.handler).await {
+                Ok(blocks) => {
+                    for block_view in blocks {
+                        if !self.notify(Ok(RpcBlockResponse { block_view })).await {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => {
+                    tracing::debug!(target: "jsonrpc", ?err, "Failed to fetch blocks for subscription");
+                }
+            }
+            tokio::time::sleep(self.handler.polling_config.polling_interval).await;
+        }
+    }
+
+    async fn follow_state_changes(
+        &mut self,
+        finality: Finality,
+        state_changes_request: StateChangesRequestView,
+    ) {
+        let mut follower = BlockFollower::new(finality);
+        loop {
+            let blocks = match follower.next_blocks(&self.handler).await {
+                Ok(blocks) => blocks,
+                Err(err) => {
+                    tracing::debug!(target: "jsonrpc", ?err, "Failed to fetch blocks for subscription");
+                    Vec::new()
+                }
+            };
+            for block in blocks {
+                let block_hash = block.header.hash;
+                let changes = self
+                    .handler
+                    .view_client_send::<_, _, RpcStateChangesError, _>(GetStateChanges {
+                        block_hash,
+                        state_changes_request: state_changes_request.clone(),
+                    })
+                    .await;
+                let notified = match changes {
+                    Ok(changes) if changes.is_empty() => true,
+                    Ok(changes) => {
+                        self.notify(Ok(RpcStateChangesInBlockResponse { block_hash, changes }))
+                            .await
+                    }
+                    // Let the client know it has missed the changes of this block.
+                    Err(err) => self.notify::<()>(Err(err.into())).await,
+                };
+                if !notified {
+                    return;
+                }
+            }
+            tokio::time::sleep(self.handler.polling_config.polling_interval).await;
+        }
+    }
+
+    async fn wait_for_tx(
+        &mut self,
+        transaction_info: TransactionInfo,
+        wait_until: TxExecutionStatus,
+    ) {
+        let (tx_hash, signer_account_id) = transaction_info.to_tx_hash_and_account();
+        loop {
+            let status = self
+                .handler
+                .view_client_send::<_, _, RpcTransactionError, _>(TxStatus {
+                    tx_hash,
+                    signer_account_id: signer_account_id.clone(),
+                    fetch_receipt: false,
+                })
+                .await;
+            match status {
+                Ok(status)
+                    if tx_execution_status_meets_expectations(&wait_until, &status.status) =>
+                {
+                    self.notify(Ok(RpcTransactionResponse::from(status))).await;
+                    return;
+                }
+                // Not yet known or not yet at the requested status: keep waiting.
+                Ok(_) | Err(RpcTransactionError::UnknownTransaction { .. }) => {}
+                Err(err) => {
+                    self.notify::<()>(Err(err.into())).await;
+                    return;
+                }
+            }
+            tokio::time::sleep(self.handler.polling_config.polling_interval).await;
+        }
+    }
+
+    /// Pushes a notification to the client.  Returns `false` once the
+    /// connection is gone.
+    async fn notify<T: serde::Serialize>(&mut self, result: Result<T, RpcError>) -> bool {
+        let subscription_id = self.subscription_id.clone();
+        let notification = match result.and_then(serialize_response) {
+            Ok(value) => {
+                RpcSubscriptionNotification { subscription_id, result: Some(value), error: None }
+            }
+            Err(error) => {
+                RpcSubscriptionNotification { subscription_id, result: None, error: Some(error) }
+            }
+        };
+        let params = match serialize_response(notification) {
+            Ok(params) => params,
+            Err(err) => {
+                tracing::warn!(target: "jsonrpc", ?err, "Failed to serialize subscription notification");
+                return true;
+            }
+        };
+        let message = Message::notification(SUBSCRIPTION_NOTIFICATION_METHOD.to_string(), params);
+        self.outgoing.send(text_frame(message)).await.is_ok()
+    }
+}
+
+/// Tracks the head at the given finality and returns the blocks which
+/// appeared since the previous call, so that no block is missed when several
+/// of them are produced within one polling interval.
+struct BlockFollower {
+    finality: Finality,
+    last_height: Option<BlockHeight>,
+}
+
+impl BlockFollower {
+    fn new(finality: Finality) -> Self {
+        Self { finality, last_height: None }
+    }
+
+    /// Returns the new blocks in increasing height order.  The first call
+    /// only returns the current head.
+    async fn next_blocks(
+        &mut self,
+        handler: &JsonRpcHandler,
+    ) -> Result<Vec<BlockView>, RpcBlockError> {
+        let head = handler
+            .block(RpcBlockRequest {
+                block_reference: BlockReference::Finality(self.finality.clone()),
+            })
+            .await?
+            .block_view;
+        let head_height = head.header.height;
+        let first_height = match self.last_height {
+            None => head_height,
+            Some(last_height) if head_height <= last_height => return Ok(Vec::new()),
+            Some(last_height) => {
+                (last_height + 1).max(head_height.saturating_sub(MAX_CATCH_UP_BLOCKS - 1))
+            }
+        };
+        let mut blocks = Vec::new();
+        for height in first_height..head_height {
+            let block_reference = BlockReference::BlockId(BlockId::Height(height));
+            match handler.block(RpcBlockRequest { block_reference }).await {
+                Ok(response) => blocks.push(response.block_view),
+                // Skipped heights have no block on the canonical chain.
+                Err(RpcBlockError::UnknownBlock { .. }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        blocks.push(head);
+        self.last_height = Some(head_height);
+        Ok(blocks)
+    }
+}
@@ -0,0 +1,284 @@
+//! Verification of light client blocks and proofs without running a node.
+//!
+//! A [`LightClient`] starts from a trusted block together with the block
+//! producers of its epoch.  It then accepts [`LightClientBlockView`]s, as
+//! returned by the `next_light_client_block` RPC method, checking that each of
+//! them is approved by more than two thirds of the stake of its epoch's block
+//! producers, and that the block producers of the next epoch it announces
+//! match the hash committed to in the block.  Once a block is accepted it
+//! becomes the new head, and proofs of execution outcomes and of past blocks
+//! can be checked against it.
+//!
+//! The checks follow the light client section of the NEAR protocol
+//! specification.
+use near_primitives::block_header::{Approval, ApprovalInner};
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::{combine_hash, compute_root_from_path, MerklePath};
+use near_primitives::types::{AccountId, Balance, BlockHeight};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{
+    ExecutionOutcomeWithIdView, LightClientBlockLiteView, LightClientBlockView,
+};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("block at height {height} is not above the head at height {head_height}")]
+    NotAboveHead { height: BlockHeight, head_height: BlockHeight },
+    #[error("block is in epoch {0} which is neither the epoch of the head nor the next one")]
+    UnexpectedEpoch(CryptoHash),
+    #[error("block producers of epoch {0} are not known")]
+    UnknownBlockProducers(CryptoHash),
+    #[error("block is the first one in its epoch but doesn't list the next block producers")]
+    MissingNextBlockProducers,
+    #[error("block has {got} approval slots but its epoch has {want} block producers")]
+    MissingApprovals { got: usize, want: usize },
+    #[error("approval of {0} has invalid signature")]
+    InvalidApproval(AccountId),
+    #[error("approved stake {approved} is not more than two thirds of the total stake {total}")]
+    NotEnoughApprovals { approved: Balance, total: Balance },
+    #[error("next block producers hash to {got} but the block says {want}")]
+    NextBlockProducersMismatch { got: CryptoHash, want: CryptoHash },
+    #[error("outcome proof is for block {got} but the block header is of {want}")]
+    OutcomeBlockMismatch { got: CryptoHash, want: CryptoHash },
+    #[error("outcome proof leads to outcome root {got} but the block has {want}")]
+    OutcomeRootMismatch { got: CryptoHash, want: CryptoHash },
+    #[error("block proof of {block_hash} leads to root {got} but the head has {want}")]
+    BlockRootMismatch { block_hash: CryptoHash, got: CryptoHash, want: CryptoHash },
+}
+
+/// A light client following the chain from a trusted block.
+///
+/// The state is serializable so that it can be persisted between runs, which
+/// is the same as trusting the persisted head.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct LightClient {
+    head: LightClientBlockLiteView,
+    /// Ordered block producers of the epoch of the head and, once known, of
+    /// the next epoch.  Keyed by epoch id.
+    block_producers: HashMap<CryptoHash, Vec<ValidatorStakeView>>,
+}
+
+impl LightClient {
+    /// Creates a light client whose head is the given trusted block.
+    /// `block_producers` are the ordered block producers of the epoch of the
+    /// block.  If the block lists the block producers of the next epoch,
+    /// they're checked against the block and remembered.
+    pub fn new(
+        head: &LightClientBlockView,
+        block_producers: Vec<ValidatorStakeView>,
+    ) -> Result<Self, Error> {
+        let mut this = Self {
+            head: lite_view(head),
+            block_producers: HashMap::from([(head.inner_lite.epoch_id, block_producers)]),
+        };
+        if let Some(next_bps) = &head.next_bps {
+            check_next_block_producers(head, next_bps)?;
+            this.block_producers.insert(head.inner_lite.next_epoch_id, next_bps.clone());
+        }
+        Ok(this)
+    }
+
+    pub fn head(&self) -> &LightClientBlockLiteView {
+        &self.head
+    }
+
+    pub fn head_hash(&self) -> CryptoHash {
+        self.head.hash()
+    }
+
+    /// Returns ordered block producers of the given epoch if it's the epoch of
+    /// the head or the next one and they're known.
+    pub fn block_producers(&self, epoch_id: &CryptoHash) -> Option<&[ValidatorStakeView]> {
+        self.block_producers.get(epoch_id).map(Vec::as_slice)
+    }
+
+    /// Checks that `block` can follow the current head without updating the
+    /// head.
+    pub fn validate_block(&self, block: &LightClientBlockView) -> Result<(), Error> {
+        let inner_lite = &block.inner_lite;
+        if inner_lite.height <= self.head.inner_lite.height {
+            return Err(Error::NotAboveHead {
+                height: inner_lite.height,
+                head_height: self.head.inner_lite.height,
+            });
+        }
+        if inner_lite.epoch_id != self.head.inner_lite.epoch_id
+            && inner_lite.epoch_id != self.head.inner_lite.next_epoch_id
+        {
+            return Err(Error::UnexpectedEpoch(inner_lite.epoch_id));
+        }
+        if inner_lite.epoch_id == self.head.inner_lite.next_epoch_id && block.next_bps.is_none() {
+            return Err(Error::MissingNextBlockProducers);
+        }
+        let block_producers = self
+            .block_producers
+            .get(&inner_lite.epoch_id)
+            .ok_or(Error::UnknownBlockProducers(inner_lite.epoch_id))?;
+        check_approvals(block, block_producers)?;
+        if let Some(next_bps) = &block.next_bps {
+            check_next_block_producers(block, next_bps)?;
+        }
+        Ok(())
+    }
+
+    /// Validates `block` and makes it the new head.
+    pub fn validate_and_update_head(&mut self, block: &LightClientBlockView) -> Result<(), Error> {
+        self.validate_block(block)?;
+        self.head = lite_view(block);
+        if let Some(next_bps) = &block.next_bps {
+            self.block_producers.insert(block.inner_lite.next_epoch_id, next_bps.clone());
+        }
+        let LightClientBlockLiteView { inner_lite, .. } = &self.head;
+        self.block_producers.retain(|epoch_id, _| {
+            epoch_id == &inner_lite.epoch_id || epoch_id == &inner_lite.next_epoch_id
+        });
+        Ok(())
+    }
+
+    /// Checks that `block_header_lite` is of a block preceding the head, using
+    /// `block_proof` returned for it by `EXPERIMENTAL_light_client_block_proof`
+    /// with the head as `light_client_head`.
+    pub fn verify_block(
+        &self,
+        block_header_lite: &LightClientBlockLiteView,
+        block_proof: &MerklePath,
+    ) -> Result<(), Error> {
+        verify_block_proof(&self.head, block_header_lite, block_proof)
+    }
+
+    /// Checks an execution outcome using the fields of the response of
+    /// `light_client_proof` requested with the head as `light_client_head`.
+    pub fn verify_outcome(
+        &self,
+        outcome_proof: &ExecutionOutcomeWithIdView,
+        outcome_root_proof: &MerklePath,
+        block_header_lite: &LightClientBlockLiteView,
+        block_proof: &MerklePath,
+    ) -> Result<(), Error> {
+        verify_outcome_proof(outcome_proof, outcome_root_proof, block_header_lite)?;
+        self.verify_block(block_header_lite, block_proof)
+    }
+}
+
+/// Returns the lite view, and thus the hash, of the block a light client block
+/// is for.
+pub fn lite_view(block: &LightClientBlockView) -> LightClientBlockLiteView {
+    LightClientBlockLiteView {
+        prev_block_hash: block.prev_block_hash,
+        inner_rest_hash: block.inner_rest_hash,
+        inner_lite: block.inner_lite.clone(),
+    }
+}
+
+/// Returns the message block producers sign to endorse the block after the
+/// given one, whose approvals are included in `approvals_after_next`.
+pub fn approval_message(block: &LightClientBlockView) -> Vec<u8> {
+    let current_block_hash = lite_view(block).hash();
+    let next_block_hash = combine_hash(&block.next_block_inner_hash, &current_block_hash);
+    Approval::get_data_for_sig(
+        &ApprovalInner::Endorsement(next_block_hash),
+        block.inner_lite.height + 2,
+    )
+}
+
+/// Checks that `block` is approved by more than two thirds of the stake of
+/// `block_producers`, which must be the ordered block producers of its epoch.
+/// The block must have an approval slot for each of the block producers.
+pub fn check_approvals(
+    block: &LightClientBlockView,
+    block_producers: &[ValidatorStakeView],
+) -> Result<(), Error> {
+    if block.approvals_after_next.len() < block_producers.len() {
+        return Err(Error::MissingApprovals {
+            got: block.approvals_after_next.len(),
+            want: block_producers.len(),
+        });
+    }
+    let message = approval_message(block);
+    let total: Balance = block_producers
+        .iter()
+        .map(|block_producer| {
+            let ValidatorStakeView::V1(block_producer) = block_producer;
+            block_producer.stake
+        })
+        .sum();
+    let mut approved: Balance = 0;
+    for (approval, block_producer) in block.approvals_after_next.iter().zip(block_producers) {
+        let ValidatorStakeView::V1(block_producer) = block_producer;
+        let Some(signature) = approval else {
+            continue;
+        };
+        if !signature.verify(&message, &block_producer.public_key) {
+            return Err(Error::InvalidApproval(block_producer.account_id.clone()));
+        }
+        approved += block_producer.stake;
+    }
+    if approved <= total * 2 / 3 {
+        return Err(Error::NotEnoughApprovals { approved, total });
+    }
+    Ok(())
+}
+
+/// Checks that `next_bps` hash to the `next_bp_hash` of `block`.
+pub fn check_next_block_producers(
+    block: &LightClientBlockView,
+    next_bps: &[ValidatorStakeView],
+) -> Result<(), Error> {
+    let got = CryptoHash::hash_borsh_iter(
+        next_bps.iter().cloned().map(ValidatorStakeView::into_validator_stake),
+    );
+    if got != block.inner_lite.next_bp_hash {
+        return Err(Error::NextBlockProducersMismatch { got, want: block.inner_lite.next_bp_hash });
+    }
+    Ok(())
+}
+
+/// Checks that `outcome_proof` is included in the block of
+/// `block_header_lite`.  The block itself needs to be checked separately with
+/// [`verify_block_proof`].
+pub fn verify_outcome_proof(
+    outcome_proof: &ExecutionOutcomeWithIdView,
+    outcome_root_proof: &MerklePath,
+    block_header_lite: &LightClientBlockLiteView,
+) -> Result<(), Error> {
+    let block_hash = block_header_lite.hash();
+    if outcome_proof.block_hash != block_hash {
+        return Err(Error::OutcomeBlockMismatch {
+            got: outcome_proof.block_hash,
+            want: block_hash,
+        });
+    }
+    let outcome_hash = CryptoHash::hash_borsh(outcome_proof.to_hashes());
+    let shard_outcome_root = compute_root_from_path(&outcome_proof.proof, outcome_hash);
+    let got =
+        compute_root_from_path(outcome_root_proof, CryptoHash::hash_borsh(shard_outcome_root));
+    let want = block_header_lite.inner_lite.outcome_root;
+    if got != want {
+        return Err(Error::OutcomeRootMismatch { got, want });
+    }
+    Ok(())
+}
+
+/// Checks that the block of `block_header_lite` precedes `head` using a proof
+/// of its inclusion into the block merkle tree of `head`.  The head itself is
+/// accepted with an empty proof.
+pub fn verify_block_proof(
+    head: &LightClientBlockLiteView,
+    block_header_lite: &LightClientBlockLiteView,
+    block_proof: &MerklePath,
+) -> Result<(), Error> {
+    let block_hash = block_header_lite.hash();
+    if block_proof.is_empty() && block_hash == head.hash() {
+        return Ok(());
+    }
+    let got = compute_root_from_path(block_proof, block_hash);
+    let want = head.inner_lite.block_merkle_root;
+    if got != want {
+        return Err(Error::BlockRootMismatch { block_hash, got, want });
+    }
+    Ok(())
+}
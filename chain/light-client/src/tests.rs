@@ -0,0 +1,237 @@
+use crate::{approval_message, lite_view, Error, LightClient};
+use near_crypto::{InMemorySigner, KeyType};
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::{combine_hash, merklize, Direction, MerklePath, MerklePathItem};
+use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{
+    BlockHeaderInnerLiteView, ExecutionOutcomeWithIdView, LightClientBlockView,
+    ValidatorStakeViewV1,
+};
+
+const STAKE: u128 = 1_000_000;
+
+struct Epoch {
+    id: CryptoHash,
+    signers: Vec<InMemorySigner>,
+}
+
+impl Epoch {
+    fn new(name: &str, num_block_producers: usize) -> Self {
+        let signers = (0..num_block_producers)
+            .map(|i| {
+                let account_id = format!("{name}{i}").parse().unwrap();
+                InMemorySigner::from_seed(account_id, KeyType::ED25519, &format!("{name}{i}"))
+            })
+            .collect();
+        Self { id: CryptoHash::hash_bytes(name.as_bytes()), signers }
+    }
+
+    fn block_producers(&self) -> Vec<ValidatorStakeView> {
+        self.signers
+            .iter()
+            .map(|signer| {
+                ValidatorStakeView::V1(ValidatorStakeViewV1 {
+                    account_id: signer.account_id.clone(),
+                    public_key: signer.public_key(),
+                    stake: STAKE,
+                })
+            })
+            .collect()
+    }
+
+    fn bp_hash(&self) -> CryptoHash {
+        CryptoHash::hash_borsh_iter(
+            self.block_producers().into_iter().map(ValidatorStakeView::into_validator_stake),
+        )
+    }
+}
+
+/// Returns a block at `height` in `epoch` approved by block producers of the
+/// epoch for which `approvers` is true.  If `with_next_bps` is set, the block
+/// lists the block producers of `next_epoch`.
+fn make_block(
+    height: u64,
+    epoch: &Epoch,
+    next_epoch: &Epoch,
+    with_next_bps: bool,
+    approvers: &[bool],
+) -> LightClientBlockView {
+    let mut block = LightClientBlockView {
+        prev_block_hash: CryptoHash::hash_borsh(height - 1),
+        next_block_inner_hash: CryptoHash::hash_borsh(height + 1),
+        inner_lite: BlockHeaderInnerLiteView {
+            height,
+            epoch_id: epoch.id,
+            next_epoch_id: next_epoch.id,
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp: height,
+            timestamp_nanosec: height,
+            next_bp_hash: next_epoch.bp_hash(),
+            block_merkle_root: CryptoHash::default(),
+        },
+        inner_rest_hash: CryptoHash::hash_borsh(height),
+        next_bps: with_next_bps.then(|| next_epoch.block_producers()),
+        approvals_after_next: vec![],
+    };
+    let message = approval_message(&block);
+    block.approvals_after_next = epoch
+        .signers
+        .iter()
+        .zip(approvers)
+        .map(|(signer, approves)| approves.then(|| Box::new(signer.sign(&message))))
+        .collect();
+    block
+}
+
+#[test]
+fn test_follow_epochs() {
+    let epochs = [Epoch::new("a", 4), Epoch::new("b", 3), Epoch::new("c", 4)];
+    let trusted = make_block(10, &epochs[0], &epochs[1], true, &[true; 4]);
+    let mut client = LightClient::new(&trusted, epochs[0].block_producers()).unwrap();
+    assert_eq!(client.head_hash(), lite_view(&trusted).hash());
+
+    // Three out of four block producers are enough.
+    let block = make_block(12, &epochs[0], &epochs[1], false, &[true, false, true, true]);
+    client.validate_and_update_head(&block).unwrap();
+    assert_eq!(client.head().inner_lite.height, 12);
+
+    // The first block of the next epoch is approved by its block producers
+    // and announces the ones of the epoch after it.
+    let block = make_block(20, &epochs[1], &epochs[2], true, &[true; 3]);
+    client.validate_and_update_head(&block).unwrap();
+    assert_eq!(client.head_hash(), lite_view(&block).hash());
+    assert!(client.block_producers(&epochs[0].id).is_none());
+    assert_eq!(client.block_producers(&epochs[2].id).unwrap(), epochs[2].block_producers());
+
+    // The state can be persisted and restored.
+    let restored: LightClient =
+        serde_json::from_str(&serde_json::to_string(&client).unwrap()).unwrap();
+    let block = make_block(30, &epochs[2], &epochs[0], true, &[true; 4]);
+    restored.validate_block(&block).unwrap();
+    client.validate_and_update_head(&block).unwrap();
+}
+
+#[test]
+fn test_invalid_blocks() {
+    let epochs = [Epoch::new("a", 3), Epoch::new("b", 3), Epoch::new("c", 3)];
+    let trusted = make_block(10, &epochs[0], &epochs[1], true, &[true; 3]);
+    let client = LightClient::new(&trusted, epochs[0].block_producers()).unwrap();
+
+    let block = make_block(10, &epochs[0], &epochs[1], false, &[true; 3]);
+    assert_eq!(
+        client.validate_block(&block),
+        Err(Error::NotAboveHead { height: 10, head_height: 10 })
+    );
+
+    let block = make_block(20, &epochs[2], &epochs[0], true, &[true; 3]);
+    assert_eq!(client.validate_block(&block), Err(Error::UnexpectedEpoch(epochs[2].id)));
+
+    let block = make_block(20, &epochs[1], &epochs[2], false, &[true; 3]);
+    assert_eq!(client.validate_block(&block), Err(Error::MissingNextBlockProducers));
+
+    // Exactly two thirds of the stake is not enough.
+    let block = make_block(11, &epochs[0], &epochs[1], false, &[true, true, false]);
+    assert_eq!(
+        client.validate_block(&block),
+        Err(Error::NotEnoughApprovals { approved: 2 * STAKE, total: 3 * STAKE })
+    );
+
+    // Dropping the slots of the missing approvals doesn't lower the total stake.
+    let mut block = make_block(11, &epochs[0], &epochs[1], false, &[true, true, false]);
+    block.approvals_after_next.truncate(2);
+    assert_eq!(client.validate_block(&block), Err(Error::MissingApprovals { got: 2, want: 3 }));
+
+    // Approvals signed by block producers of another epoch.
+    let mut block = make_block(11, &epochs[0], &epochs[1], false, &[true; 3]);
+    block.approvals_after_next =
+        make_block(11, &epochs[1], &epochs[1], false, &[true; 3]).approvals_after_next;
+    assert_eq!(
+        client.validate_block(&block),
+        Err(Error::InvalidApproval(epochs[0].signers[0].account_id.clone()))
+    );
+
+    // Approvals of a different block.
+    let mut block = make_block(11, &epochs[0], &epochs[1], false, &[true; 3]);
+    block.inner_lite.outcome_root = CryptoHash::hash_bytes(b"outcome");
+    assert!(matches!(client.validate_block(&block), Err(Error::InvalidApproval(_))));
+
+    let mut block = make_block(20, &epochs[1], &epochs[2], true, &[true; 3]);
+    block.next_bps = Some(epochs[0].block_producers());
+    assert!(matches!(client.validate_block(&block), Err(Error::NextBlockProducersMismatch { .. })));
+
+    let mut trusted = trusted;
+    trusted.next_bps = Some(epochs[2].block_producers());
+    assert!(matches!(
+        LightClient::new(&trusted, epochs[0].block_producers()),
+        Err(Error::NextBlockProducersMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_proofs() {
+    let epochs = [Epoch::new("a", 1), Epoch::new("b", 1)];
+    let outcomes: Vec<ExecutionOutcomeWithIdView> = (0..3u64)
+        .map(|i| {
+            let mut outcome =
+                ExecutionOutcomeWithIdView::from(ExecutionOutcomeWithIdAndProof::default());
+            outcome.id = CryptoHash::hash_borsh(i);
+            outcome.outcome.logs = vec![format!("log {i}")];
+            outcome
+        })
+        .collect();
+    let (chunk_outcome_root, outcome_paths) =
+        merklize(&outcomes.iter().map(|outcome| outcome.to_hashes()).collect::<Vec<_>>());
+    let chunk_outcome_roots = [CryptoHash::hash_bytes(b"other shard"), chunk_outcome_root];
+    let (outcome_root, outcome_root_paths) = merklize(&chunk_outcome_roots);
+
+    let mut block = make_block(10, &epochs[0], &epochs[1], false, &[true]);
+    block.inner_lite.outcome_root = outcome_root;
+    let block_header_lite = lite_view(&block);
+    let block_hash = block_header_lite.hash();
+    let prev_block_hash = CryptoHash::hash_bytes(b"prev");
+
+    let mut head = make_block(12, &epochs[0], &epochs[1], true, &[true]);
+    head.inner_lite.block_merkle_root = combine_hash(&prev_block_hash, &block_hash);
+    let client = LightClient::new(&head, epochs[0].block_producers()).unwrap();
+    let block_proof = vec![MerklePathItem { hash: prev_block_hash, direction: Direction::Left }];
+    client.verify_block(&block_header_lite, &block_proof).unwrap();
+    client.verify_block(&lite_view(&head), &MerklePath::new()).unwrap();
+    assert!(matches!(
+        client.verify_block(&block_header_lite, &MerklePath::new()),
+        Err(Error::BlockRootMismatch { .. })
+    ));
+
+    for (mut outcome, path) in outcomes.into_iter().zip(outcome_paths) {
+        outcome.proof = path;
+        outcome.block_hash = block_hash;
+        let outcome_root_proof = &outcome_root_paths[1];
+        client
+            .verify_outcome(&outcome, outcome_root_proof, &block_header_lite, &block_proof)
+            .unwrap();
+
+        assert!(matches!(
+            client.verify_outcome(
+                &outcome,
+                &outcome_root_paths[0],
+                &block_header_lite,
+                &block_proof
+            ),
+            Err(Error::OutcomeRootMismatch { .. })
+        ));
+
+        let mut tampered = outcome.clone();
+        tampered.outcome.logs.push("forged".to_string());
+        assert!(matches!(
+            client.verify_outcome(&tampered, outcome_root_proof, &block_header_lite, &block_proof),
+            Err(Error::OutcomeRootMismatch { .. })
+        ));
+
+        outcome.block_hash = prev_block_hash;
+        assert!(matches!(
+            client.verify_outcome(&outcome, outcome_root_proof, &block_header_lite, &block_proof),
+            Err(Error::OutcomeBlockMismatch { .. })
+        ));
+    }
+}
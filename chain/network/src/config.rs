@@ -5,6 +5,7 @@ use crate::network_protocol::PeerInfo;
 use crate::peer_manager::peer_store;
 use crate::rate_limits::messages_limits;
 use crate::snapshot_hosts;
+use crate::state_part_server;
 use crate::stun;
 use crate::tcp;
 use crate::types::ROUTED_MESSAGE_TTL;
@@ -101,6 +102,9 @@ pub struct Tier1 {
     /// - a node will try to start outbound TIER1 connections iff `enable_outbound` is true.
     pub enable_inbound: bool,
     pub enable_outbound: bool,
+    /// Whether to use QUIC for TIER1 connections: accept QUIC connections on the UDP port of
+    /// `node_addr` and try QUIC first when connecting to TIER1 proxies, falling back to TCP.
+    pub quic: bool,
 }
 
 #[derive(Clone)]
@@ -124,6 +128,8 @@ pub struct NetworkConfig {
 
     pub peer_store: peer_store::Config,
     pub snapshot_hosts: snapshot_hosts::Config,
+    /// Bandwidth limits of serving state parts to peers.
+    pub state_part_server: state_part_server::Config,
     pub whitelist_nodes: Vec<PeerInfo>,
     pub handshake_timeout: time::Duration,
 
@@ -196,6 +202,16 @@ pub struct NetworkConfig {
     /// Configuration of rate limits for incoming messages.
     pub received_messages_rate_limits: messages_limits::Config,
 
+    /// Directory to record the messages exchanged with peers to, if any. Every run
+    /// of the node creates a new recording in it.
+    /// See near_network::recorder for details.
+    pub record_traffic: Option<std::path::PathBuf>,
+
+    /// Whether to refuse TCP connections which are not encrypted, i.e. with
+    /// peers at protocol versions without `ProtocolFeature::EncryptedPeerTransport`.
+    /// See near_network::peer::noise for details.
+    pub require_encryption: bool,
+
     #[cfg(test)]
     pub(crate) event_sink:
         near_async::messaging::Sender<crate::peer_manager::peer_manager_actor::Event>,
@@ -324,6 +340,7 @@ impl NetworkConfig {
                 snapshot_hosts_cache_size: cfg.snapshot_hosts_cache_size,
                 part_selection_cache_batch_size: 10,
             },
+            state_part_server: cfg.state_part_server,
             whitelist_nodes: if cfg.whitelist_nodes.is_empty() {
                 vec![]
             } else {
@@ -371,6 +388,7 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::minutes(15),
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
+                quic: cfg.experimental.tier1_quic,
             }),
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
@@ -380,6 +398,8 @@ impl NetworkConfig {
             },
             // Use a preset to configure rate limits and override entries with user defined values later.
             received_messages_rate_limits: messages_limits::Config::standard_preset(),
+            record_traffic: cfg.experimental.record_traffic,
+            require_encryption: cfg.experimental.require_encryption,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -422,6 +442,7 @@ impl NetworkConfig {
                 snapshot_hosts_cache_size: 1000,
                 part_selection_cache_batch_size: 10,
             },
+            state_part_server: state_part_server::Config::default(),
             whitelist_nodes: vec![],
             handshake_timeout: time::Duration::seconds(5),
             connect_to_reliable_peers_on_startup: true,
@@ -455,9 +476,12 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::hours(1000),
                 enable_inbound: true,
                 enable_outbound: true,
+                quic: false,
             }),
             skip_tombstones: None,
             received_messages_rate_limits: messages_limits::Config::default(),
+            record_traffic: None,
+            require_encryption: false,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -515,6 +539,8 @@ impl NetworkConfig {
             anyhow::bail!("One or more invalid rate limits: {err:?}");
         }
 
+        self.state_part_server.validate().context("state_part_server")?;
+
         Ok(VerifiedConfig { node_id: self.node_id(), inner: self })
     }
 }
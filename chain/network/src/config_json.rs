@@ -1,5 +1,6 @@
 use crate::network_protocol::PeerAddr;
 use crate::rate_limits::messages_limits;
+use crate::state_part_server;
 use crate::stun;
 use near_async::time::Duration;
 
@@ -159,6 +160,9 @@ pub struct Config {
     /// Maximum number of snapshot hosts to keep in memory.
     #[serde(default = "default_snapshot_hosts_cache_size")]
     pub snapshot_hosts_cache_size: u32,
+    /// Bandwidth limits of serving state parts to peers syncing state.
+    #[serde(default)]
+    pub state_part_server: state_part_server::Config,
     // Remove peers that were not active for this amount of time.
     #[serde(default = "default_peer_expiration_duration")]
     #[serde(with = "near_async::time::serde_duration_as_std")]
@@ -256,6 +260,10 @@ pub struct ExperimentalConfig {
     #[serde(default = "default_tier1_enable_outbound")]
     pub tier1_enable_outbound: bool,
 
+    /// See `near_network::config::Tier1::quic`.
+    #[serde(default)]
+    pub tier1_quic: bool,
+
     /// See `near_network::config::Tier1::connect_interval`.
     #[serde(default = "default_tier1_connect_interval")]
     #[serde(with = "near_async::time::serde_duration_as_std")]
@@ -265,6 +273,15 @@ pub struct ExperimentalConfig {
     #[serde(default = "default_tier1_new_connections_per_attempt")]
     pub tier1_new_connections_per_attempt: u64,
 
+    /// See `near_network::config::NetworkConfig::record_traffic`.
+    /// Relative paths are resolved against the working directory of the node.
+    #[serde(default)]
+    pub record_traffic: Option<std::path::PathBuf>,
+
+    /// See `near_network::config::NetworkConfig::require_encryption`.
+    #[serde(default)]
+    pub require_encryption: bool,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     #[serde(default)]
@@ -297,8 +314,11 @@ impl Default for ExperimentalConfig {
             skip_sending_tombstones_seconds: 0,
             tier1_enable_inbound: default_tier1_enable_inbound(),
             tier1_enable_outbound: default_tier1_enable_outbound(),
+            tier1_quic: false,
             tier1_connect_interval: default_tier1_connect_interval(),
             tier1_new_connections_per_attempt: default_tier1_new_connections_per_attempt(),
+            record_traffic: None,
+            require_encryption: false,
             network_config_overrides: Default::default(),
         }
     }
@@ -323,6 +343,7 @@ impl Default for Config {
             skip_sync_wait: false,
             peer_states_cache_size: default_peer_states_cache_size(),
             snapshot_hosts_cache_size: default_snapshot_hosts_cache_size(),
+            state_part_server: Default::default(),
             ban_window: Duration::seconds(3 * 60 * 60),
             blacklist: vec![],
             ttl_account_id_router: default_ttl_account_id_router(),
@@ -7,8 +7,10 @@ mod network_protocol;
 mod peer;
 mod peer_manager;
 mod private_actix;
+mod quic;
 mod rate_limits;
 mod snapshot_hosts;
+mod state_part_server;
 mod stats;
 mod store;
 mod stun;
@@ -21,6 +23,7 @@ pub mod config;
 pub mod config_json;
 pub mod debug;
 pub mod raw;
+pub mod recorder;
 pub mod routing;
 pub mod shards_manager;
 pub mod state_sync;
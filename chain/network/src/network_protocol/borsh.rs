@@ -119,7 +119,9 @@ impl std::error::Error for HandshakeFailureReason {}
 /// Warning, position of each message type in this enum defines the protocol due to serialization.
 /// DO NOT MOVE, REORDER, DELETE items from the list. Only add new items to the end.
 /// If need to remove old items - replace with `None`.
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug, strum::AsRefStr)]
+#[derive(
+    BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug, strum::AsRefStr, strum::EnumCount,
+)]
 // TODO(#1313): Use Box
 pub(super) enum PeerMessage {
     Handshake(Handshake),
@@ -163,3 +165,8 @@ pub(super) enum PeerMessage {
 }
 #[cfg(target_arch = "x86_64")] // Non-x86_64 doesn't match this requirement yet but it's not bad as it's not production-ready
 const _: () = assert!(std::mem::size_of::<PeerMessage>() <= 1500, "PeerMessage > 1500 bytes");
+
+impl PeerMessage {
+    /// Borsh tag of the `Routed` variant.
+    pub(super) const ROUTED_TAG: u8 = 13;
+}
@@ -14,6 +14,8 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
             owned_account: None,
+            session_key: None,
+            accepted_compression: vec![],
         }
     }
 }
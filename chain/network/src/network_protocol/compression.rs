@@ -0,0 +1,112 @@
+//! Compression of large `PeerMessage`s.
+//!
+//! Peers announce the compression algorithms they accept in their `Handshake`.
+//! Messages of the types dominating the traffic (blocks, chunks, state witnesses
+//! and state parts) larger than `COMPRESSION_THRESHOLD_BYTES` are then sent to
+//! them compressed, wrapped in a proto `CompressedPeerMessage`. Compression is
+//! therefore supported only with the proto encoding.
+use super::{proto, PeerMessage, RoutedMessageBody};
+use bytesize::MIB;
+use protobuf::Message as _;
+use std::io::Read as _;
+
+/// Messages smaller than that are sent uncompressed, as the savings wouldn't be
+/// worth the CPU time.
+pub(crate) const COMPRESSION_THRESHOLD_BYTES: usize = 4 * 1024;
+/// Favours speed over ratio, as compression is on the path of every large message.
+const ZSTD_LEVEL: i32 = 1;
+/// Same as the maximum size of a message on the wire.
+const MAX_DECOMPRESSED_SIZE_BYTES: u64 = 512 * MIB;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    Zstd,
+}
+
+impl Compression {
+    /// Compression algorithms this node accepts, in the order of preference.
+    pub(crate) const ACCEPTED: &'static [Compression] = &[Compression::Zstd];
+
+    /// Chooses the algorithm to compress messages sent to a peer with.
+    pub(crate) fn negotiate(accepted_by_peer: &[Compression]) -> Option<Compression> {
+        Self::ACCEPTED.iter().find(|c| accepted_by_peer.contains(c)).copied()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DecompressError {
+    #[error("unknown algorithm")]
+    UnknownAlgorithm,
+    #[error("zstd: {0}")]
+    Zstd(#[source] std::io::Error),
+    #[error("decompressed message is larger than {MAX_DECOMPRESSED_SIZE_BYTES}B")]
+    TooLarge,
+    #[error("decode: {0}")]
+    Decode(#[source] protobuf::Error),
+}
+
+impl PeerMessage {
+    /// Whether messages of this type are compressed when large enough.
+    pub(crate) fn is_compressible(&self) -> bool {
+        match self {
+            PeerMessage::Block(_)
+            | PeerMessage::BlockHeaders(_)
+            | PeerMessage::VersionedStateResponse(_) => true,
+            PeerMessage::Routed(msg) => matches!(
+                msg.body,
+                RoutedMessageBody::PartialEncodedChunkResponse(_)
+                    | RoutedMessageBody::VersionedPartialEncodedChunk(_)
+                    | RoutedMessageBody::PartialEncodedChunkForward(_)
+                    | RoutedMessageBody::PartialEncodedStateWitness(_)
+                    | RoutedMessageBody::PartialEncodedStateWitnessForward(_)
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// Compresses a message serialized with `Encoding::Proto` into a serialized
+/// `CompressedPeerMessage`. Returns `None` if that doesn't make it smaller.
+pub(crate) fn compress(bytes: &[u8], compression: Compression) -> Option<Vec<u8>> {
+    let data = match compression {
+        Compression::Zstd => zstd::bulk::compress(bytes, ZSTD_LEVEL).ok()?,
+    };
+    let msg = proto::PeerMessage {
+        message_type: Some(proto::peer_message::Message_type::Compressed(
+            proto::CompressedPeerMessage {
+                algorithm: proto::compressed_peer_message::Algorithm::from(compression).into(),
+                data,
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    };
+    let compressed = msg.write_to_bytes().unwrap();
+    (compressed.len() < bytes.len()).then_some(compressed)
+}
+
+/// Decompresses the wrapped message.
+pub(crate) fn decompress(
+    msg: &proto::CompressedPeerMessage,
+) -> Result<proto::PeerMessage, DecompressError> {
+    let compression = msg
+        .algorithm
+        .enum_value()
+        .ok()
+        .and_then(|algorithm| Compression::try_from(algorithm).ok())
+        .ok_or(DecompressError::UnknownAlgorithm)?;
+    let mut bytes = vec![];
+    match compression {
+        Compression::Zstd => {
+            zstd::stream::read::Decoder::new(&msg.data[..])
+                .map_err(DecompressError::Zstd)?
+                .take(MAX_DECOMPRESSED_SIZE_BYTES + 1)
+                .read_to_end(&mut bytes)
+                .map_err(DecompressError::Zstd)?;
+        }
+    }
+    if bytes.len() as u64 > MAX_DECOMPRESSED_SIZE_BYTES {
+        return Err(DecompressError::TooLarge);
+    }
+    proto::PeerMessage::parse_from_bytes(&bytes).map_err(DecompressError::Decode)
+}
@@ -2,10 +2,12 @@
 #[path = "borsh.rs"]
 mod borsh_;
 mod borsh_conv;
+mod compression;
 mod edge;
 mod peer;
 mod proto_conv;
 mod state_sync;
+pub use compression::*;
 pub use edge::*;
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsementV1;
@@ -36,7 +38,7 @@ use near_o11y::OpenTelemetrySpanExt;
 use near_primitives::block::{Approval, Block, BlockHeader, GenesisId};
 use near_primitives::challenge::Challenge;
 use near_primitives::hash::CryptoHash;
-use near_primitives::merkle::combine_hash;
+use near_primitives::merkle::{combine_hash, verify_path};
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::{
     ChunkHash, PartialEncodedChunk, PartialEncodedChunkPart, ReceiptProof, ShardChunkHeader,
@@ -45,7 +47,7 @@ use near_primitives::state_sync::{ShardStateSyncResponse, ShardStateSyncResponse
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::AccountId;
 use near_primitives::types::{BlockHeight, ShardId};
-use near_primitives::validator_signer::ValidatorSigner;
+use near_primitives::validator_signer::{RemoteSignerError, ValidatorSigner};
 use near_primitives::views::FinalExecutionOutcomeView;
 use protobuf::Message as _;
 use std::collections::HashSet;
@@ -185,7 +187,7 @@ impl VersionedAccountData {
                 MAX_ACCOUNT_DATA_SIZE_BYTES
             );
         }
-        let signature = signer.sign_account_key_payload(&payload);
+        let signature = signer.sign_account_key_payload(&payload)?;
         Ok(SignedAccountData {
             account_data: self,
             payload: AccountKeySignedPayload { payload, signature },
@@ -257,18 +259,19 @@ impl OwnedAccount {
     /// Serializes OwnedAccount to proto and signs it using `signer`.
     /// Panics if OwnedAccount.account_key doesn't match signer.public_key(),
     /// as this would likely be a bug.
-    pub fn sign(self, signer: &ValidatorSigner) -> SignedOwnedAccount {
+    /// Returns an error if the remote signer fails to sign it.
+    pub fn sign(self, signer: &ValidatorSigner) -> Result<SignedOwnedAccount, RemoteSignerError> {
         assert_eq!(
             self.account_key,
             signer.public_key(),
             "OwnedAccount.account_key doesn't match the signer's account_key"
         );
         let payload = proto::AccountKeyPayload::from(&self).write_to_bytes().unwrap();
-        let signature = signer.sign_account_key_payload(&payload);
-        SignedOwnedAccount {
+        let signature = signer.sign_account_key_payload(&payload)?;
+        Ok(SignedOwnedAccount {
             owned_account: self,
             payload: AccountKeySignedPayload { payload, signature },
-        }
+        })
     }
 }
 
@@ -292,6 +295,14 @@ impl SignedOwnedAccount {
     }
 }
 
+/// Ephemeral X25519 public key of the sender of a `Handshake`, signed with its
+/// node key. See `peer::noise` for how the connection is encrypted with it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SessionKey {
+    pub(crate) public_key: [u8; 32],
+    pub(crate) signature: Signature,
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, Default)]
 pub struct RoutingTableUpdate {
     pub edges: Vec<Edge>,
@@ -356,6 +367,11 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Key used to encrypt the connection, required iff the protocol version
+    /// supports `ProtocolFeature::EncryptedPeerTransport`.
+    pub(crate) session_key: Option<SessionKey>,
+    /// Compression algorithms the sender accepts in messages sent to it.
+    pub(crate) accepted_compression: Vec<Compression>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -459,6 +475,66 @@ pub enum ParsePeerMessageError {
     ProtoDecode(#[source] protobuf::Error),
     #[error("ProtoConv")]
     ProtoConv(#[source] proto_conv::ParsePeerMessageError),
+    #[error("Decompress")]
+    Decompress(#[source] DecompressError),
+    #[error("UnknownType")]
+    UnknownType,
+}
+
+impl ParsePeerMessageError {
+    /// Whether the message is of a type this node doesn't know, e.g. one added
+    /// in a newer version of the protocol, as opposed to a malformed one.
+    pub(crate) fn is_unknown_type(&self) -> bool {
+        match self {
+            Self::UnknownType => true,
+            // Unknown fields of the oneof are skipped by the decoder.
+            Self::ProtoConv(proto_conv::ParsePeerMessageError::Empty) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Whether `data`, which failed to parse, is a borsh encoded `PeerMessage` of
+/// a type this node doesn't know.  Variants are only ever appended to the
+/// enums, so a tag past the last known variant comes from a newer version of
+/// the protocol.
+fn is_unknown_borsh_type(data: &[u8]) -> bool {
+    match data.split_first() {
+        Some((&borsh_::PeerMessage::ROUTED_TAG, routed)) => is_unknown_routed_body(routed),
+        Some((&tag, _)) => usize::from(tag) >= <borsh_::PeerMessage as strum::EnumCount>::COUNT,
+        None => false,
+    }
+}
+
+/// Whether `data`, which failed to parse, is a borsh encoded `RoutedMessage`
+/// whose body is of a type this node doesn't know.
+fn is_unknown_routed_body(mut data: &[u8]) -> bool {
+    let parse_header = |data: &mut &[u8]| -> std::io::Result<()> {
+        PeerIdOrHash::deserialize_reader(data)?;
+        PeerId::deserialize_reader(data)?;
+        Signature::deserialize_reader(data)?;
+        u8::deserialize_reader(data)?;
+        Ok(())
+    };
+    parse_header(&mut data).is_ok()
+        && data
+            .first()
+            .is_some_and(|&tag| usize::from(tag) >= <RoutedMessageBody as strum::EnumCount>::COUNT)
+}
+
+/// Wraps error of converting `msg`, telling messages with a routed body of
+/// an unknown type apart from malformed ones.
+fn proto_conv_error(
+    msg: &proto::PeerMessage,
+    err: proto_conv::ParsePeerMessageError,
+) -> ParsePeerMessageError {
+    match (&err, &msg.message_type) {
+        (
+            proto_conv::ParsePeerMessageError::Routed(_),
+            Some(proto::peer_message::Message_type::Routed(routed)),
+        ) if is_unknown_routed_body(&routed.borsh) => ParsePeerMessageError::UnknownType,
+        _ => ParsePeerMessageError::ProtoConv(err),
+    }
 }
 
 impl PeerMessage {
@@ -482,17 +558,28 @@ impl PeerMessage {
     ) -> Result<PeerMessage, ParsePeerMessageError> {
         let span = tracing::trace_span!(target: "network", "deserialize").entered();
         Ok(match enc {
-            Encoding::Borsh => (&borsh_::PeerMessage::try_from_slice(data)
-                .map_err(ParsePeerMessageError::BorshDecode)?)
+            Encoding::Borsh => (&borsh_::PeerMessage::try_from_slice(data).map_err(|err| {
+                if is_unknown_borsh_type(data) {
+                    ParsePeerMessageError::UnknownType
+                } else {
+                    ParsePeerMessageError::BorshDecode(err)
+                }
+            })?)
                 .try_into()
                 .map_err(ParsePeerMessageError::BorshConv)?,
             Encoding::Proto => {
-                let proto_msg: proto::PeerMessage = proto::PeerMessage::parse_from_bytes(data)
+                let mut proto_msg: proto::PeerMessage = proto::PeerMessage::parse_from_bytes(data)
                     .map_err(ParsePeerMessageError::ProtoDecode)?;
+                if let Some(proto::peer_message::Message_type::Compressed(compressed)) =
+                    &proto_msg.message_type
+                {
+                    proto_msg =
+                        decompress(compressed).map_err(ParsePeerMessageError::Decompress)?;
+                }
                 if let Ok(extracted_span_context) = extract_span_context(&proto_msg.trace_context) {
                     span.clone().or_current().add_link(extracted_span_context);
                 }
-                (&proto_msg).try_into().map_err(|err| ParsePeerMessageError::ProtoConv(err))?
+                (&proto_msg).try_into().map_err(|err| proto_conv_error(&proto_msg, err))?
             }
         })
     }
@@ -507,7 +594,13 @@ impl PeerMessage {
 
 // TODO(#1313): Use Box
 #[derive(
-    borsh::BorshSerialize, borsh::BorshDeserialize, PartialEq, Eq, Clone, strum::IntoStaticStr,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    strum::IntoStaticStr,
+    strum::EnumCount,
 )]
 pub enum RoutedMessageBody {
     BlockApproval(Approval),
@@ -766,6 +859,26 @@ impl PartialEncodedChunkForwardMsg {
         let correct_hash = combine_hash(&self.inner_header_hash, &self.merkle_root);
         ChunkHash(correct_hash) == self.chunk_hash
     }
+
+    /// Checks the hash and the merkle proofs of the parts, which unlike the
+    /// signature can be done without knowing the chunk producer.
+    pub(crate) fn is_well_formed(&self) -> bool {
+        self.is_valid_hash() && are_parts_valid(self.merkle_root, &self.parts)
+    }
+}
+
+/// Checks the merkle proofs of the parts of `chunk`, which unlike the signature
+/// can be done without knowing the chunk producer.
+pub(crate) fn is_well_formed_chunk(chunk: &PartialEncodedChunk) -> bool {
+    let merkle_root = match chunk {
+        PartialEncodedChunk::V1(chunk) => chunk.header.inner.encoded_merkle_root,
+        PartialEncodedChunk::V2(chunk) => chunk.header.encoded_merkle_root(),
+    };
+    are_parts_valid(merkle_root, chunk.parts())
+}
+
+fn are_parts_valid(merkle_root: CryptoHash, parts: &[PartialEncodedChunkPart]) -> bool {
+    parts.iter().all(|part| verify_path(merkle_root, &part.merkle_proof, &part.part))
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
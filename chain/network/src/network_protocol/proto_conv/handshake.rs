@@ -2,7 +2,7 @@
 use super::*;
 
 use crate::network_protocol::proto;
-use crate::network_protocol::{Handshake, HandshakeFailureReason};
+use crate::network_protocol::{Compression, Handshake, HandshakeFailureReason, SessionKey};
 use crate::network_protocol::{PeerChainInfoV2, PeerInfo};
 use near_primitives::block::GenesisId;
 use protobuf::MessageField as MF;
@@ -63,6 +63,60 @@ impl TryFrom<&proto::PeerChainInfo> for PeerChainInfoV2 {
 
 //////////////////////////////////////////
 
+#[derive(thiserror::Error, Debug)]
+pub enum ParseSessionKeyError {
+    #[error("public_key: got {0} bytes, want 32")]
+    PublicKey(usize),
+    #[error("signature {0}")]
+    Signature(ParseRequiredError<ParseSignatureError>),
+}
+
+impl From<&SessionKey> for proto::SessionKey {
+    fn from(x: &SessionKey) -> Self {
+        Self {
+            public_key: x.public_key.to_vec(),
+            signature: MF::some((&x.signature).into()),
+            ..Self::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::SessionKey> for SessionKey {
+    type Error = ParseSessionKeyError;
+    fn try_from(p: &proto::SessionKey) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: p
+                .public_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| Self::Error::PublicKey(p.public_key.len()))?,
+            signature: try_from_required(&p.signature).map_err(Self::Error::Signature)?,
+        })
+    }
+}
+
+//////////////////////////////////////////
+
+impl From<Compression> for proto::compressed_peer_message::Algorithm {
+    fn from(x: Compression) -> Self {
+        match x {
+            Compression::Zstd => Self::ZSTD,
+        }
+    }
+}
+
+impl TryFrom<proto::compressed_peer_message::Algorithm> for Compression {
+    type Error = ();
+    fn try_from(p: proto::compressed_peer_message::Algorithm) -> Result<Self, Self::Error> {
+        match p {
+            proto::compressed_peer_message::Algorithm::UNKNOWN => Err(()),
+            proto::compressed_peer_message::Algorithm::ZSTD => Ok(Self::Zstd),
+        }
+    }
+}
+
+//////////////////////////////////////////
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseHandshakeError {
     #[error("sender_peer_id {0}")]
@@ -77,6 +131,8 @@ pub enum ParseHandshakeError {
     PartialEdgeInfo(ParseRequiredError<ParsePartialEdgeInfoError>),
     #[error("owned_account {0}")]
     OwnedAccount(ParseSignedOwnedAccountError),
+    #[error("session_key {0}")]
+    SessionKey(ParseSessionKeyError),
 }
 
 impl From<&Handshake> for proto::Handshake {
@@ -90,6 +146,12 @@ impl From<&Handshake> for proto::Handshake {
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
             owned_account: x.owned_account.as_ref().map(Into::into).into(),
+            session_key: x.session_key.as_ref().map(Into::into).into(),
+            accepted_compression: x
+                .accepted_compression
+                .iter()
+                .map(|c| proto::compressed_peer_message::Algorithm::from(*c).into())
+                .collect(),
             ..Self::default()
         }
     }
@@ -120,6 +182,13 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::PartialEdgeInfo)?,
             owned_account: try_from_optional(&p.owned_account)
                 .map_err(Self::Error::OwnedAccount)?,
+            session_key: try_from_optional(&p.session_key).map_err(Self::Error::SessionKey)?,
+            // Algorithms unknown to us are ignored.
+            accepted_compression: p
+                .accepted_compression
+                .iter()
+                .filter_map(|c| Compression::try_from(c.enum_value().ok()?).ok())
+                .collect(),
         })
     }
 }
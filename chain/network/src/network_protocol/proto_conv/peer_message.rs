@@ -386,6 +386,8 @@ pub enum ParsePeerMessageError {
     StateResponse(ParseRequiredError<ParseStateInfoError>),
     #[error("sync_snapshot_hosts: {0}")]
     SyncSnapshotHosts(ParseSyncSnapshotHostsError),
+    #[error("compressed message inside of a compressed message")]
+    NestedCompressed,
 }
 
 impl TryFrom<&proto::PeerMessage> for PeerMessage {
@@ -487,6 +489,9 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::SyncSnapshotHosts(srh) => PeerMessage::SyncSnapshotHosts(
                 srh.try_into().map_err(Self::Error::SyncSnapshotHosts)?,
             ),
+            // Compressed messages are unwrapped by PeerMessage::deserialize, so this is
+            // a compressed message which was compressed once again.
+            ProtoMT::Compressed(_) => return Err(Self::Error::NestedCompressed),
         })
     }
 }
@@ -2,7 +2,7 @@ use super::*;
 
 use crate::config;
 use crate::network_protocol::{
-    Edge, PartialEdgeInfo, PeerInfo, RawRoutedMessage, RoutedMessageBody,
+    Edge, PartialEdgeInfo, PeerInfo, RawRoutedMessage, RoutedMessageBody, SessionKey,
 };
 use crate::tcp;
 use crate::types::{AccountKeys, ChainInfo, Handshake, RoutingTableUpdate};
@@ -70,6 +70,7 @@ pub fn make_block(
         clock,
         None,
     )
+    .unwrap()
 }
 
 pub fn make_account_id<R: Rng>(rng: &mut R) -> AccountId {
@@ -175,6 +176,7 @@ pub fn make_challenge<R: Rng>(rng: &mut R) -> Challenge {
         }),
         &make_validator_signer(rng).into(),
     )
+    .unwrap()
 }
 
 // Based on ShardsManager::prepare_partial_encoded_chunk_response_from_chunk.
@@ -348,9 +350,19 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         sender_chain_info: chain.get_peer_chain_info(),
         partial_edge_info: make_partial_edge(rng),
         owned_account: None,
+        session_key: None,
+        accepted_compression: vec![],
     }
 }
 
+pub fn make_session_key<R: Rng>(
+    rng: &mut R,
+    node_key: &SecretKey,
+    protocol_version: version::ProtocolVersion,
+) -> SessionKey {
+    SessionKey::new(rng.gen(), protocol_version, node_key)
+}
+
 pub fn make_routed_message<R: Rng>(rng: &mut R, body: RoutedMessageBody) -> RoutedMessageV2 {
     let signer = make_signer(rng);
     let peer_id = PeerId::new(signer.public_key);
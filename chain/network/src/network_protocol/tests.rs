@@ -7,6 +7,8 @@ use crate::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg
 use anyhow::{bail, Context as _};
 use itertools::Itertools as _;
 use near_async::time;
+use near_primitives::merkle::merklize;
+use near_primitives::version::PROTOCOL_VERSION;
 use rand::Rng as _;
 
 #[test]
@@ -61,8 +63,13 @@ fn serialize_deserialize_protobuf_only() {
     let mut rng = make_rng(39521947542);
     let mut clock = time::FakeClock::default();
     let chain = data::Chain::make(&mut clock, &mut rng, 12);
+    let node_key = data::make_secret_key(&mut rng);
     let msgs = [
-        PeerMessage::Tier1Handshake(data::make_handshake(&mut rng, &chain)),
+        PeerMessage::Tier1Handshake(Handshake {
+            session_key: Some(data::make_session_key(&mut rng, &node_key, PROTOCOL_VERSION)),
+            accepted_compression: vec![Compression::Zstd],
+            ..data::make_handshake(&mut rng, &chain)
+        }),
         PeerMessage::SyncAccountsData(SyncAccountsData {
             accounts_data: (0..4)
                 .map(|_| Arc::new(data::make_signed_account_data(&mut rng, &clock.clock())))
@@ -79,6 +86,96 @@ fn serialize_deserialize_protobuf_only() {
     }
 }
 
+#[test]
+fn compression() {
+    let mut rng = make_rng(5829147329);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 12);
+    let header = chain.blocks[5].header().clone();
+    let msg = PeerMessage::BlockHeaders(vec![header; 64]);
+    assert!(msg.is_compressible());
+    assert!(!PeerMessage::BlockRequest(*chain.blocks[5].hash()).is_compressible());
+
+    let bytes = msg.serialize(Encoding::Proto);
+    assert!(bytes.len() >= COMPRESSION_THRESHOLD_BYTES);
+    let compressed = compress(&bytes, Compression::Zstd).unwrap();
+    assert!(compressed.len() < bytes.len());
+    assert_eq!(msg, PeerMessage::deserialize(Encoding::Proto, &compressed).unwrap());
+
+    // Incompressible payloads are left alone.
+    let noise: Vec<u8> = (0..COMPRESSION_THRESHOLD_BYTES).map(|_| rng.gen()).collect();
+    assert_eq!(None, compress(&noise, Compression::Zstd));
+
+    // A compressed message cannot wrap another one.
+    let nested = compress(&compressed.repeat(4), Compression::Zstd).unwrap();
+    assert!(matches!(
+        PeerMessage::deserialize(Encoding::Proto, &nested),
+        Err(ParsePeerMessageError::ProtoConv(_))
+    ));
+}
+
+#[test]
+fn unknown_message_type() {
+    let mut rng = make_rng(89028037454);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 12);
+
+    // Field 1000 of the oneof, unknown to this version of the protocol.
+    let err = PeerMessage::deserialize(Encoding::Proto, &[0xc0, 0x3e, 0x01]).unwrap_err();
+    assert!(err.is_unknown_type(), "{err:?}");
+    let err = PeerMessage::deserialize(Encoding::Borsh, &[0xff]).unwrap_err();
+    assert!(err.is_unknown_type(), "{err:?}");
+
+    // Messages of known types which fail to parse are malformed.
+    let msg = PeerMessage::Block(chain.blocks[5].clone());
+    for enc in [Encoding::Proto, Encoding::Borsh] {
+        let bytes = msg.serialize(enc);
+        let err = PeerMessage::deserialize(enc, &bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(!err.is_unknown_type(), "{err:?}");
+    }
+
+    // Routed messages with a body of an unknown type.
+    let routed = data::make_routed_message(
+        &mut rng,
+        RoutedMessageBody::Ping(Ping { nonce: 1, source: data::make_peer_id(&mut rng) }),
+    );
+    let routed_borsh = borsh::to_vec(&routed.msg).unwrap();
+    let body_len = borsh::to_vec(&routed.msg.body).unwrap().len();
+    let msg = PeerMessage::Routed(Box::new(routed));
+    assert_eq!(borsh_::PeerMessage::ROUTED_TAG, msg.serialize(Encoding::Borsh)[0]);
+    for enc in [Encoding::Proto, Encoding::Borsh] {
+        let mut bytes = msg.serialize(enc);
+        let start = bytes.windows(routed_borsh.len()).position(|w| w == routed_borsh).unwrap();
+        bytes[start + routed_borsh.len() - body_len] = 0xff;
+        let err = PeerMessage::deserialize(enc, &bytes).unwrap_err();
+        assert!(err.is_unknown_type(), "{err:?}");
+    }
+}
+
+#[test]
+fn well_formed_chunk_forward() {
+    let mut rng = make_rng(89028037455);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 12);
+
+    let parts = data::make_chunk_parts(chain.chunks.values().next().unwrap().clone());
+    let (merkle_root, _) = merklize(&parts.iter().map(|part| &part.part[..]).collect::<Vec<_>>());
+    let inner_header_hash = CryptoHash::hash_bytes(b"inner");
+    let mut forward = PartialEncodedChunkForwardMsg {
+        chunk_hash: ChunkHash(combine_hash(&inner_header_hash, &merkle_root)),
+        inner_header_hash,
+        merkle_root,
+        signature: Signature::default(),
+        prev_block_hash: CryptoHash::default(),
+        height_created: 1,
+        shard_id: 0,
+        parts,
+    };
+    assert!(forward.is_well_formed());
+    forward.parts[0].part[0] ^= 1;
+    assert!(!forward.is_well_formed());
+}
+
 #[test]
 fn serialize_deserialize() -> anyhow::Result<()> {
     let mut rng = make_rng(89028037453);
@@ -1,3 +1,4 @@
+mod noise;
 pub(crate) mod peer_actor;
 mod stream;
 mod tracker;
@@ -0,0 +1,183 @@
+//! Key agreement and authenticated encryption of peer connections.
+//!
+//! The scheme follows the Noise framework: each side of a connection generates
+//! an ephemeral X25519 key pair per connection and sends the public key in its
+//! `Handshake`, as a `SessionKey` signed with its node key together with the
+//! protocol version of the handshake. Since node keys are signing keys, they
+//! authenticate the ephemeral keys with signatures rather than by taking part
+//! in Diffie-Hellman. Once both handshakes are exchanged, each side computes
+//! the shared X25519 secret and expands it with HKDF-SHA256, salted with a hash
+//! of the ephemeral keys and peer ids of both sides, into one ChaCha20-Poly1305
+//! key per direction. Frames are sealed with a counter
+//! nonce, so that reordered, replayed, dropped or modified frames fail to open.
+//!
+//! Encryption is used iff `ProtocolFeature::EncryptedPeerTransport` is enabled
+//! in the protocol version negotiated in the handshake, so that encrypted and
+//! legacy peers can talk to each other during the rollout. A node can refuse
+//! unencrypted connections altogether with `NetworkConfig::require_encryption`,
+//! as nothing stops a man in the middle from negotiating a legacy version.
+use crate::network_protocol::SessionKey;
+use crate::types::PeerType;
+use chacha20poly1305::aead::{Aead as _, KeyInit as _};
+use chacha20poly1305::ChaCha20Poly1305;
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use near_crypto::SecretKey;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::version::ProtocolVersion;
+use rand::RngCore as _;
+
+/// Identifies the scheme in signatures of session keys and in key derivation,
+/// so that neither can be confused with anything else signed by a node key.
+const PROTOCOL_NAME: &str = "near-peer-transport-x25519-chacha20poly1305-v1";
+
+/// Number of bytes an encrypted frame is longer than its plaintext.
+pub(crate) const TAG_SIZE: usize = 16;
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("session key is not signed by the node key of the peer")]
+    InvalidSignature,
+    #[error("session key of the peer is a low order point")]
+    LowOrderPoint,
+    #[error("all nonces have been used")]
+    NonceExhausted,
+    #[error("frame failed authentication")]
+    Authentication,
+}
+
+impl SessionKey {
+    fn payload(public_key: &[u8; 32], protocol_version: ProtocolVersion) -> CryptoHash {
+        CryptoHash::hash_borsh((PROTOCOL_NAME, protocol_version, public_key))
+    }
+
+    /// Signs the ephemeral `public_key` with `node_key`, for a handshake at
+    /// `protocol_version`.
+    pub(crate) fn new(
+        public_key: [u8; 32],
+        protocol_version: ProtocolVersion,
+        node_key: &SecretKey,
+    ) -> Self {
+        let signature = node_key.sign(Self::payload(&public_key, protocol_version).as_ref());
+        Self { public_key, signature }
+    }
+
+    /// Checks that the session key was signed by `peer_id` for a handshake at
+    /// `protocol_version`.
+    pub(crate) fn verify(
+        &self,
+        peer_id: &PeerId,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), Error> {
+        let payload = Self::payload(&self.public_key, protocol_version);
+        if !self.signature.verify(payload.as_ref(), peer_id.public_key()) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// Ephemeral X25519 secret generated for a single connection.
+pub(crate) struct EphemeralSecret([u8; 32]);
+
+impl EphemeralSecret {
+    pub fn new() -> Self {
+        let mut secret = [0; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self(secret)
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        MontgomeryPoint::mul_base_clamped(self.0).to_bytes()
+    }
+
+    /// Returns the public key signed with `node_key`, to be sent in the
+    /// `Handshake` at `protocol_version`.
+    pub fn session_key(
+        &self,
+        protocol_version: ProtocolVersion,
+        node_key: &SecretKey,
+    ) -> SessionKey {
+        SessionKey::new(self.public_key(), protocol_version, node_key)
+    }
+
+    /// Derives the ciphers of the connection from the peer's session key.
+    /// `peer_type` is the direction of the connection from our side.
+    /// The peer's session key should be verified with `SessionKey::verify`
+    /// beforehand.
+    pub fn agree(
+        &self,
+        peer_type: PeerType,
+        my_id: &PeerId,
+        peer_key: &SessionKey,
+        peer_id: &PeerId,
+    ) -> Result<SessionCiphers, Error> {
+        let shared = MontgomeryPoint(peer_key.public_key).mul_clamped(self.0);
+        if shared.to_bytes() == [0; 32] {
+            return Err(Error::LowOrderPoint);
+        }
+        let me = (self.public_key(), my_id);
+        let peer = (peer_key.public_key, peer_id);
+        let (outbound, inbound) = match peer_type {
+            PeerType::Outbound => (me, peer),
+            PeerType::Inbound => (peer, me),
+        };
+        let salt = CryptoHash::hash_borsh((PROTOCOL_NAME, outbound, inbound));
+        let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(salt.as_ref()), shared.as_bytes());
+        let derive = |info: &[u8]| {
+            let mut key = [0; 32];
+            hkdf.expand(info, &mut key).expect("32 bytes is a valid HKDF-SHA256 output length");
+            Cipher::new(key)
+        };
+        let outbound_to_inbound = derive(b"outbound to inbound");
+        let inbound_to_outbound = derive(b"inbound to outbound");
+        Ok(match peer_type {
+            PeerType::Outbound => {
+                SessionCiphers { send: outbound_to_inbound, recv: inbound_to_outbound }
+            }
+            PeerType::Inbound => {
+                SessionCiphers { send: inbound_to_outbound, recv: outbound_to_inbound }
+            }
+        })
+    }
+}
+
+/// Ciphers for both directions of a connection.
+pub(crate) struct SessionCiphers {
+    pub send: Cipher,
+    pub recv: Cipher,
+}
+
+/// ChaCha20-Poly1305 cipher for a single direction of a connection.
+/// The nonce of the n-th frame is n, so frames have to be opened in the
+/// order they were sealed in.
+pub(crate) struct Cipher {
+    aead: ChaCha20Poly1305,
+    counter: u64,
+}
+
+impl Cipher {
+    fn new(key: [u8; 32]) -> Self {
+        Self { aead: ChaCha20Poly1305::new(&key.into()), counter: 0 }
+    }
+
+    fn next_nonce(&mut self) -> Result<chacha20poly1305::Nonce, Error> {
+        let mut nonce = [0; 12];
+        nonce[4..].copy_from_slice(&self.counter.to_le_bytes());
+        self.counter = self.counter.checked_add(1).ok_or(Error::NonceExhausted)?;
+        Ok(nonce.into())
+    }
+
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = self.next_nonce()?;
+        Ok(self
+            .aead
+            .encrypt(&nonce, plaintext)
+            .expect("frames are below the size limit of the cipher"))
+    }
+
+    pub fn open(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let nonce = self.next_nonce()?;
+        self.aead.decrypt(&nonce, ciphertext).map_err(|_| Error::Authentication)
+    }
+}
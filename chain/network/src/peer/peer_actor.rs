@@ -9,10 +9,12 @@ use crate::concurrency::demux;
 use crate::config::PEERS_RESPONSE_MAX_PEERS;
 use crate::network_protocol::SnapshotHostInfoVerificationError;
 use crate::network_protocol::{
-    DistanceVector, Edge, EdgeState, Encoding, OwnedAccount, ParsePeerMessageError,
-    PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo, PeersRequest, PeersResponse,
-    RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, SyncAccountsData, SyncSnapshotHosts,
+    compress, is_well_formed_chunk, Compression, DistanceVector, Edge, EdgeState, Encoding,
+    OwnedAccount, ParsePeerMessageError, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo,
+    PeersRequest, PeersResponse, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
+    SessionKey, SyncAccountsData, SyncSnapshotHosts, COMPRESSION_THRESHOLD_BYTES,
 };
+use crate::peer::noise;
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
 use crate::peer_manager::connection;
@@ -20,8 +22,11 @@ use crate::peer_manager::network_state::{NetworkState, PRUNE_EDGES_AFTER};
 #[cfg(test)]
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_manager_actor::MAX_TIER2_PEERS;
+use crate::peer_manager::peer_store::reputation;
 use crate::private_actix::{RegisterPeerError, SendMessage};
+use crate::quic;
 use crate::rate_limits::messages_limits;
+use crate::recorder;
 use crate::routing::edge::verify_nonce;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
@@ -49,7 +54,7 @@ use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 use near_primitives::types::EpochId;
 use near_primitives::utils::DisplayOption;
 use near_primitives::version::{
-    ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    ProtocolFeature, ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 use parking_lot::Mutex;
 use rand::seq::IteratorRandom;
@@ -127,6 +132,8 @@ pub(crate) enum ClosingReason {
     TooLargeClockSkew,
     #[error("owned_account.peer_id doesn't match handshake.sender_peer_id")]
     OwnedAccountMismatch,
+    #[error("connection is not encrypted, which is required by the config")]
+    EncryptionRequired,
     #[error("PeerActor stopped NOT via PeerActor::stop()")]
     Unknown,
 }
@@ -148,6 +155,7 @@ impl ClosingReason {
             ClosingReason::DisconnectMessage => false, // graceful disconnect
             ClosingReason::TooLargeClockSkew => true, // reconnect will fail for the same reason
             ClosingReason::OwnedAccountMismatch => true, // misbehaving peer
+            ClosingReason::EncryptionRequired => true, // reconnect will fail for the same reason
             ClosingReason::Unknown => false,        // only happens in tests
         }
     }
@@ -170,6 +178,10 @@ pub(crate) struct PeerActor {
 
     /// Framed wrapper to send messages through the TCP connection.
     framed: stream::FramedStream<PeerActor>,
+    /// Secret of the session key sent in our handshake.
+    session_secret: noise::EphemeralSecret,
+    /// Channel binding of the QUIC connection, `None` for TCP connections.
+    quic_binding: Option<[u8; 32]>,
 
     /// Tracker for requests and responses.
     tracker: Arc<Mutex<Tracker>>,
@@ -182,6 +194,9 @@ pub(crate) struct PeerActor {
     /// Whether the PeerActor should skip protobuf support detection and use
     /// a given encoding right away.
     force_encoding: Option<Encoding>,
+    /// Compression of large messages sent to the peer, as negotiated during
+    /// handshake.
+    compression: Option<Compression>,
 
     /// Peer status.
     peer_status: PeerStatus,
@@ -329,6 +344,10 @@ impl PeerActor {
                 let peer_addr = stream.peer_addr;
                 let stream_type = stream.type_.clone();
                 let stats = Arc::new(connection::Stats::default());
+                let quic_binding = match &stream.transport {
+                    tcp::Transport::Tcp(_) => None,
+                    tcp::Transport::Quic(conn) => Some(quic::channel_binding(conn)),
+                };
                 let framed = stream::FramedStream::spawn(ctx, stream, stats.clone());
                 Self {
                     closing_reason: None,
@@ -342,6 +361,8 @@ impl PeerActor {
                     },
                     peer_status: PeerStatus::Connecting(send, connecting_status),
                     framed,
+                    session_secret: noise::EphemeralSecret::new(),
+                    quic_binding,
                     tracker: Default::default(),
                     stats,
                     routed_message_cache: LruCache::new(
@@ -349,6 +370,7 @@ impl PeerActor {
                     ),
                     protocol_buffers_supported: false,
                     force_encoding,
+                    compression: None,
                     peer_info: match &stream_type {
                         tcp::StreamType::Inbound => None,
                         tcp::StreamType::Outbound { peer_id, .. } => Some(PeerInfo {
@@ -402,6 +424,11 @@ impl PeerActor {
         if let (PeerStatus::Ready(conn), PeerMessage::PeersRequest(_)) = (&self.peer_status, msg) {
             conn.last_time_peer_requested.store(Some(self.clock.now()));
         }
+        if let (Some(recorder), Some(peer_id)) =
+            (&self.network_state.recorder, self.other_peer_id())
+        {
+            recorder.record(&self.clock, peer_id, recorder::Direction::Outbound, msg);
+        }
         if let Some(enc) = self.encoding() {
             return self.send_message_with_encoding(msg, enc);
         }
@@ -424,7 +451,7 @@ impl PeerActor {
             // peers to update its height at the peer. In the future we will introduce a new
             // peer message type for that and then we can enable this check again.
             //PeerMessage::Block(b) if self.tracker.lock().has_received(b.hash()) => return,
-            PeerMessage::BlockRequest(h) => self.tracker.lock().push_request(*h),
+            PeerMessage::BlockRequest(h) => self.tracker.lock().push_request(&self.clock, *h),
             PeerMessage::SyncAccountsData(d) => metrics::SYNC_ACCOUNTS_DATA
                 .with_label_values(&[
                     "sent",
@@ -441,11 +468,26 @@ impl PeerActor {
             _ => (),
         };
 
-        let bytes = msg.serialize(enc);
+        let mut bytes = msg.serialize(enc);
+        if let (Encoding::Proto, Some(compression)) = (enc, self.compression) {
+            if bytes.len() >= COMPRESSION_THRESHOLD_BYTES && msg.is_compressible() {
+                let raw_len = bytes.len();
+                if let Some(compressed) = compress(&bytes, compression) {
+                    bytes = compressed;
+                }
+                let msg_type = msg.msg_variant();
+                metrics::PEER_MESSAGE_COMPRESSION_RAW_BYTES
+                    .with_label_values(&[msg_type])
+                    .inc_by(raw_len as u64);
+                metrics::PEER_MESSAGE_COMPRESSION_COMPRESSED_BYTES
+                    .with_label_values(&[msg_type])
+                    .inc_by(bytes.len() as u64);
+            }
+        }
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.framed.send_on(msg.stream_class(), stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         let msg_type = msg.msg_variant();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
@@ -475,14 +517,30 @@ impl PeerActor {
                 archival: self.network_state.config.archive,
             },
             partial_edge_info: spec.partial_edge_info,
-            owned_account: self.network_state.config.validator.signer.get().map(|signer| {
+            owned_account: self.network_state.config.validator.signer.get().and_then(|signer| {
                 OwnedAccount {
                     account_key: signer.public_key(),
                     peer_id: self.network_state.config.node_id(),
                     timestamp: self.clock.now_utc(),
                 }
                 .sign(&signer)
+                .inspect_err(|err| {
+                    tracing::error!(target: "network", %err, "Failed to sign the owned account")
+                })
+                .ok()
             }),
+            accepted_compression: Compression::ACCEPTED.to_vec(),
+            session_key: match self.quic_binding {
+                Some(binding) => {
+                    Some(SessionKey::new_quic(binding, &self.network_state.config.node_key))
+                }
+                None => ProtocolFeature::EncryptedPeerTransport
+                    .enabled(spec.protocol_version)
+                    .then(|| {
+                        self.session_secret
+                            .session_key(spec.protocol_version, &self.network_state.config.node_key)
+                    }),
+            },
         };
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
@@ -622,6 +680,73 @@ impl PeerActor {
             }
         }
 
+        // Agree on the keys encrypting the connection. Frames received after this
+        // handshake are decrypted right away, while encryption of sent frames starts
+        // once our handshake (if any) has been sent.
+        // QUIC connections are encrypted by TLS already, so the session key only
+        // binds the peer to the TLS session.
+        let send_cipher = if let Some(binding) = &self.quic_binding {
+            if tier != tcp::Tier::T1 {
+                tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, ?tier, "Non-TIER1 handshake over QUIC, disconnecting");
+                self.stop(ctx, ClosingReason::HandshakeFailed);
+                return;
+            }
+            let Some(session_key) = &handshake.session_key else {
+                tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, "Handshake without session key, disconnecting");
+                self.stop(ctx, ClosingReason::HandshakeFailed);
+                return;
+            };
+            match session_key.verify_quic(binding, &handshake.sender_peer_id) {
+                Ok(()) => {}
+                Err(quic::AuthError::ChannelBindingMismatch) => {
+                    tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, "Session key bound to another QUIC connection, disconnecting");
+                    self.stop(ctx, ClosingReason::HandshakeFailed);
+                    return;
+                }
+                Err(quic::AuthError::InvalidSignature) => {
+                    self.stop(ctx, ClosingReason::Ban(ReasonForBan::InvalidSignature));
+                    return;
+                }
+            }
+            None
+        } else if ProtocolFeature::EncryptedPeerTransport.enabled(handshake.protocol_version) {
+            let Some(session_key) = &handshake.session_key else {
+                tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, "Handshake without session key, disconnecting");
+                self.stop(ctx, ClosingReason::HandshakeFailed);
+                return;
+            };
+            // The session key is signed together with the protocol version, so that
+            // the version can't be changed by a man in the middle.
+            if let Err(_) =
+                session_key.verify(&handshake.sender_peer_id, handshake.protocol_version)
+            {
+                self.stop(ctx, ClosingReason::Ban(ReasonForBan::InvalidSignature));
+                return;
+            }
+            match self.session_secret.agree(
+                self.peer_type,
+                self.my_node_id(),
+                session_key,
+                &handshake.sender_peer_id,
+            ) {
+                Ok(ciphers) => {
+                    self.framed.enable_recv_encryption(ciphers.recv);
+                    Some(ciphers.send)
+                }
+                Err(err) => {
+                    tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, "Invalid session key, disconnecting: {err}");
+                    self.stop(ctx, ClosingReason::HandshakeFailed);
+                    return;
+                }
+            }
+        } else if self.network_state.config.require_encryption {
+            tracing::debug!(target: "network", peer_id=?handshake.sender_peer_id, version=handshake.protocol_version, "Handshake at a protocol version without encryption, disconnecting");
+            self.stop(ctx, ClosingReason::EncryptionRequired);
+            return;
+        } else {
+            None
+        };
+
         // Merge partial edges.
         let nonce = handshake.partial_edge_info.nonce;
         let partial_edge_info = match cs {
@@ -656,6 +781,7 @@ impl PeerActor {
         let now = self.clock.now();
         let conn = Arc::new(connection::Connection {
             tier,
+            quic: self.quic_binding.is_some(),
             addr: ctx.address(),
             peer_info: peer_info.clone(),
             owned_account: handshake.owned_account.clone(),
@@ -722,6 +848,7 @@ impl PeerActor {
                     Ok(()) => {
                         act.peer_info = Some(peer_info).into();
                         act.peer_status = PeerStatus::Ready(conn.clone());
+                        act.compression = Compression::negotiate(&handshake.accepted_compression);
                         // Respond to handshake if it's inbound and connection was consolidated.
                         if act.peer_type == PeerType::Inbound {
                             act.send_handshake(HandshakeSpec{
@@ -731,6 +858,9 @@ impl PeerActor {
                                 partial_edge_info: partial_edge_info,
                             });
                         }
+                        if let Some(cipher) = send_cipher {
+                            act.framed.enable_send_encryption(cipher);
+                        }
                         // TIER1 is strictly reserved for BFT consensensus messages,
                         // so all kinds of periodical syncs happen only on TIER2 connections.
                         if tier==tcp::Tier::T2 {
@@ -887,6 +1017,13 @@ impl PeerActor {
                             self.stop(ctx, ClosingReason::HandshakeFailed);
                             return;
                         }
+                        if self.network_state.config.require_encryption
+                            && !ProtocolFeature::EncryptedPeerTransport.enabled(common_version)
+                        {
+                            tracing::warn!(target: "network", "Unable to connect to a node ({}) as it doesn't support encryption of the connection. Its version: {:?}", peer_info, version);
+                            self.stop(ctx, ClosingReason::EncryptionRequired);
+                            return;
+                        }
                         handshake_spec.protocol_version = common_version;
                         let spec = handshake_spec.clone();
                         ctx.wait(actix::fut::ready(()).then(move |_, act: &mut Self, _| {
@@ -963,6 +1100,7 @@ impl PeerActor {
         clock: &time::Clock,
         network_state: &NetworkState,
         peer_id: PeerId,
+        author: &PeerId,
         msg_hash: CryptoHash,
         body: RoutedMessageBody,
     ) -> Result<Option<RoutedMessageBody>, ReasonForBan> {
@@ -1012,13 +1150,31 @@ impl PeerActor {
                 );
                 None
             }
+            // Routed messages are signed by their author, so it is the author
+            // who is blamed for chunks which are malformed.  The message is
+            // still delivered: ShardsManager validates it on its own and may
+            // make use of its header.
             RoutedMessageBody::VersionedPartialEncodedChunk(chunk) => {
+                if !is_well_formed_chunk(&chunk) {
+                    network_state.peer_store.peer_reputation_signal(
+                        clock,
+                        author,
+                        reputation::Signal::InvalidChunk,
+                    );
+                }
                 network_state
                     .shards_manager_adapter
                     .send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunk(chunk));
                 None
             }
             RoutedMessageBody::PartialEncodedChunkForward(msg) => {
+                if !msg.is_well_formed() {
+                    network_state.peer_store.peer_reputation_signal(
+                        clock,
+                        author,
+                        reputation::Signal::InvalidChunk,
+                    );
+                }
                 network_state
                     .shards_manager_adapter
                     .send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkForward(msg));
@@ -1086,6 +1242,13 @@ impl PeerActor {
                 });
                 let mut tracker = self.tracker.lock();
                 tracker.push_received(hash);
+                if let Some(latency) = tracker.pop_request_latency(&self.clock, &hash) {
+                    self.network_state.peer_store.peer_reputation_signal(
+                        &self.clock,
+                        &conn.peer_info.id,
+                        reputation::Signal::Response { latency },
+                    );
+                }
                 tracker.has_request(&hash)
             }
             _ => false,
@@ -1097,10 +1260,12 @@ impl PeerActor {
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
                     let msg_hash = msg.hash();
+                    let author = msg.msg.author.clone();
                     Self::receive_routed_message(
                         &clock,
                         &network_state,
                         peer_id,
+                        &author,
                         msg_hash,
                         msg.msg.body,
                     )
@@ -1167,13 +1332,27 @@ impl PeerActor {
                     .ok()
                     .flatten()
                     .map(|response| PeerMessage::VersionedStateResponse(*response.0)),
-                PeerMessage::StateRequestPart(shard_id, sync_hash, part_id) => network_state
-                    .client
-                    .send_async(StateRequestPart { shard_id, sync_hash, part_id })
-                    .await
-                    .ok()
-                    .flatten()
-                    .map(|response| PeerMessage::VersionedStateResponse(*response.0)),
+                PeerMessage::StateRequestPart(shard_id, sync_hash, part_id) => {
+                    match network_state.state_part_server.acquire(&clock, &peer_id).await {
+                        Ok(permit) => {
+                            let response = network_state
+                                .client
+                                .send_async(StateRequestPart { shard_id, sync_hash, part_id })
+                                .await
+                                .ok()
+                                .flatten();
+                            permit.finish(response.as_ref().map(|response| {
+                                borsh::object_length(&response.0).unwrap_or_default() as u64
+                            }));
+                            response
+                                .map(|response| PeerMessage::VersionedStateResponse(*response.0))
+                        }
+                        Err(reason) => {
+                            tracing::debug!(target: "network", %peer_id, shard_id, part_id, ?reason, "Dropped state part request");
+                            None
+                        }
+                    }
+                }
                 PeerMessage::VersionedStateResponse(info) => {
                     //TODO: Route to state sync actor.
                     network_state.client.send_async(StateResponse(info.into())).await.ok();
@@ -1680,12 +1859,20 @@ impl actix::Handler<stream::Error> for PeerActor {
             }
             // It is expected in a sense that the peer might be just slow.
             stream::Error::Send(stream::SendError::QueueOverflow { .. }) => true,
+            // Frames can be corrupted or injected by anyone on the path, so don't
+            // blame the peer.
+            stream::Error::Recv(stream::RecvError::Decrypt(_))
+            | stream::Error::Recv(stream::RecvError::UnexpectedEncryptedFrame) => true,
+            // The peer might be running a newer version.
+            stream::Error::Recv(stream::RecvError::UnknownStreamClass(_)) => true,
             stream::Error::Recv(stream::RecvError::IO(err))
             | stream::Error::Send(stream::SendError::IO(err)) => match err.kind() {
                 // Connection has been closed.
                 io::ErrorKind::UnexpectedEof
                 | io::ErrorKind::ConnectionReset
                 | io::ErrorKind::BrokenPipe
+                // QUIC connection has been closed.
+                | io::ErrorKind::NotConnected
                 // libc::ETIIMEDOUT = 110, translates to io::ErrorKind::TimedOut.
                 | io::ErrorKind::TimedOut => true,
                 // When stopping tokio runtime, an "IO driver has terminated" is sometimes
@@ -1730,13 +1917,30 @@ impl actix::Handler<stream::Frame> for PeerActor {
 
         let mut peer_msg = match self.parse_message(&msg) {
             Ok(msg) => msg,
+            // Peers at newer protocol versions may send messages we don't know yet.
+            Err(err) if err.is_unknown_type() => {
+                tracing::debug!(target: "network", "Received message of unknown type {} from {}: {}", near_fmt::AbbrBytes(&msg), self.peer_info, err);
+                return;
+            }
             Err(err) => {
                 tracing::debug!(target: "network", "Received invalid data {} from {}: {}", near_fmt::AbbrBytes(&msg), self.peer_info, err);
+                if let Some(peer_id) = self.other_peer_id() {
+                    self.network_state.peer_store.peer_reputation_signal(
+                        &self.clock,
+                        peer_id,
+                        reputation::Signal::InvalidMessage,
+                    );
+                }
                 return;
             }
         };
 
         tracing::trace!(target: "network", "Received message: {}", peer_msg);
+        if let (Some(recorder), Some(peer_id)) =
+            (&self.network_state.recorder, self.other_peer_id())
+        {
+            recorder.record(&self.clock, peer_id, recorder::Direction::Inbound, &peer_msg);
+        }
 
         let now = self.clock.now();
         {
@@ -1748,6 +1952,13 @@ impl actix::Handler<stream::Frame> for PeerActor {
             if !self.received_messages_rate_limits.is_allowed(&peer_msg, now) {
                 metrics::PEER_MESSAGE_RATE_LIMITED_BY_TYPE_TOTAL.with_label_values(&labels).inc();
                 tracing::debug!(target: "network", "Peer {} is being rate limited for message {}", self.peer_info, peer_msg.msg_variant());
+                if let Some(peer_id) = self.other_peer_id() {
+                    self.network_state.peer_store.peer_reputation_signal(
+                        &self.clock,
+                        peer_id,
+                        reputation::Signal::RateLimited,
+                    );
+                }
                 return;
             }
         }
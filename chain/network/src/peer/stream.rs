@@ -1,14 +1,19 @@
+use crate::peer::noise;
 use crate::peer_manager::connection;
+use crate::quic;
 use crate::stats::metrics;
 use crate::tcp;
 use actix::fut::future::wrap_future;
 use actix::AsyncContext as _;
 use bytesize::{GIB, MIB};
+use enum_map::EnumMap;
+use futures_util::StreamExt as _;
+use parking_lot::Mutex;
 use std::io;
 use std::net::SocketAddr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use tokio::io::AsyncReadExt as _;
+use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt as _;
 
 /// Maximum size of network message in encoded format.
@@ -16,6 +21,9 @@ use tokio::io::AsyncWriteExt as _;
 const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
 /// Maximum capacity of write buffer in bytes.
 const MAX_WRITE_BUFFER_CAPACITY_BYTES: usize = GIB as usize;
+/// Bit of the length prefix marking encrypted frames. Lengths of frames are below
+/// `NETWORK_MESSAGE_MAX_SIZE_BYTES`, so the bit is never set for plaintext frames.
+const ENCRYPTED_FRAME_FLAG: u32 = 1 << 31;
 
 type ReadHalf = tokio::io::ReadHalf<tokio::net::TcpStream>;
 type WriteHalf = tokio::io::WriteHalf<tokio::net::TcpStream>;
@@ -34,12 +42,25 @@ pub(crate) enum RecvError {
     IO(#[source] io::Error),
     #[error("message too large: got {got_bytes}B, want <={want_max_bytes}B")]
     MessageTooLarge { got_bytes: usize, want_max_bytes: usize },
+    #[error("encrypted frame received before encryption was enabled")]
+    UnexpectedEncryptedFrame,
+    #[error("decrypt: {0}")]
+    Decrypt(#[source] noise::Error),
+    #[error("unknown QUIC stream class {0}")]
+    UnknownStreamClass(u8),
 }
 
 #[derive(actix::Message, PartialEq, Eq, Clone, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct Frame(pub Vec<u8>);
 
+/// Item of the send queue. Encryption is enabled through the queue, so that
+/// frames queued before are still sent in plaintext.
+enum Outgoing {
+    Frame(quic::StreamClass, Frame),
+    StartEncryption(noise::Cipher),
+}
+
 /// Stream critical error.
 /// Actor is responsible for calling ctx.stop() after receiving stream::Error.
 /// Actor might receive more than 1 stream::Error, but should call ctx.stop() just after the
@@ -57,7 +78,8 @@ pub(crate) enum Error {
 }
 
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    queue_send: tokio::sync::mpsc::UnboundedSender<Outgoing>,
+    recv_cipher: Arc<Mutex<Option<noise::Cipher>>>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -74,34 +96,87 @@ where
         stream: tcp::Stream,
         stats: Arc<connection::Stats>,
     ) -> Self {
-        let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
         let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
             vec![stream.peer_addr.to_string()],
         ));
-        ctx.spawn(wrap_future({
-            let addr = ctx.address();
-            let stats = stats.clone();
-            let m = send_buf_size_metric.clone();
-            async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
-                    addr.do_send(Error::Send(SendError::IO(err)));
-                }
+        let recv_cipher = Arc::new(Mutex::new(None));
+        match stream.transport {
+            tcp::Transport::Tcp(tcp_stream) => {
+                let (tcp_recv, tcp_send) = tokio::io::split(tcp_stream);
+                ctx.spawn(wrap_future({
+                    let addr = ctx.address();
+                    let stats = stats.clone();
+                    let m = send_buf_size_metric.clone();
+                    async move {
+                        if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await
+                        {
+                            addr.do_send(Error::Send(SendError::IO(err)));
+                        }
+                    }
+                }));
+                ctx.spawn(wrap_future({
+                    let addr = ctx.address();
+                    let stats = stats.clone();
+                    let recv_cipher = recv_cipher.clone();
+                    async move {
+                        if let Err(err) = Self::run_recv_loop(
+                            stream.peer_addr,
+                            tcp_recv,
+                            addr.clone(),
+                            stats,
+                            recv_cipher,
+                        )
+                        .await
+                        {
+                            addr.do_send(Error::Recv(err));
+                        }
+                    }
+                }));
             }
-        }));
-        ctx.spawn(wrap_future({
-            let addr = ctx.address();
-            let stats = stats.clone();
-            async move {
-                if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
-                {
-                    addr.do_send(Error::Recv(err));
-                }
+            tcp::Transport::Quic(conn) => {
+                ctx.spawn(wrap_future({
+                    let addr = ctx.address();
+                    let conn = conn.clone();
+                    let stats = stats.clone();
+                    let m = send_buf_size_metric.clone();
+                    async move {
+                        if let Err(err) = Self::run_quic_send_loop(conn, queue_recv, stats, m).await
+                        {
+                            addr.do_send(Error::Send(SendError::IO(err)));
+                        }
+                    }
+                }));
+                ctx.spawn(wrap_future({
+                    let addr = ctx.address();
+                    let stats = stats.clone();
+                    async move {
+                        if let Err(err) =
+                            Self::run_quic_recv_loop(stream.peer_addr, conn, addr.clone(), stats)
+                                .await
+                        {
+                            addr.do_send(Error::Recv(err));
+                        }
+                    }
+                }));
             }
-        }));
-        Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
+        }
+        Self { queue_send, recv_cipher, stats, send_buf_size_metric, addr: ctx.address() }
+    }
+
+    /// Encrypts all frames sent after this call with `cipher`.
+    /// QUIC connections are encrypted by TLS already, so it is a no-op for them.
+    pub fn enable_send_encryption(&self, cipher: noise::Cipher) {
+        let _ = self.queue_send.send(Outgoing::StartEncryption(cipher));
+    }
+
+    /// Decrypts all frames received after the current one with `cipher`.
+    /// Since the recv loop waits for each frame to be processed, calling it from
+    /// the handler of a frame switches exactly at the next frame. Plaintext
+    /// frames received afterwards are dropped.
+    pub fn enable_recv_encryption(&self, cipher: noise::Cipher) {
+        *self.recv_cipher.lock() = Some(cipher);
     }
 
     /// Pushes `msg` to the send queue.
@@ -109,6 +184,11 @@ where
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
     pub fn send(&self, frame: Frame) {
+        self.send_on(quic::StreamClass::Control, frame)
+    }
+
+    /// Same as `send`, but over QUIC sends the frame on the stream of `class`.
+    pub fn send_on(&self, class: quic::StreamClass, frame: Frame) {
         let msg = &frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
@@ -125,7 +205,7 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send.send(Outgoing::Frame(class, frame));
     }
 
     /// Event loop receiving and processing messages.
@@ -141,6 +221,7 @@ where
         read: ReadHalf,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
+        cipher: Arc<Mutex<Option<noise::Cipher>>>,
     ) -> Result<(), RecvError> {
         const READ_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut read = tokio::io::BufReader::with_capacity(READ_BUFFER_CAPACITY, read);
@@ -152,12 +233,13 @@ where
             vec![peer_addr.to_string()],
         );
         loop {
-            let n = read.read_u32_le().await.map_err(RecvError::IO)? as usize;
-            if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                return Err(RecvError::MessageTooLarge {
-                    got_bytes: n,
-                    want_max_bytes: NETWORK_MESSAGE_MAX_SIZE_BYTES,
-                });
+            let len = read.read_u32_le().await.map_err(RecvError::IO)?;
+            let encrypted = len & ENCRYPTED_FRAME_FLAG != 0;
+            let n = (len & !ENCRYPTED_FRAME_FLAG) as usize;
+            let max_size =
+                NETWORK_MESSAGE_MAX_SIZE_BYTES + if encrypted { noise::TAG_SIZE } else { 0 };
+            if n > max_size {
+                return Err(RecvError::MessageTooLarge { got_bytes: n, want_max_bytes: max_size });
             }
             msg_size_metric.observe(n as f64);
             buf_size_metric.set(n as i64);
@@ -168,6 +250,16 @@ where
             buf_size_metric.set(0);
             stats.received_messages.fetch_add(1, Ordering::Relaxed);
             stats.received_bytes.fetch_add(n as u64, Ordering::Relaxed);
+            let buf = match (cipher.lock().as_mut(), encrypted) {
+                (None, false) => buf,
+                (Some(cipher), true) => cipher.open(&buf).map_err(RecvError::Decrypt)?,
+                (None, true) => return Err(RecvError::UnexpectedEncryptedFrame),
+                // Plaintext could have been injected by anyone on the path.
+                (Some(_), false) => {
+                    metrics::PEER_PLAINTEXT_FRAMES_DROPPED.inc();
+                    continue;
+                }
+            };
             if let Err(_) = addr.send(Frame(buf)).await {
                 // We got mailbox error, which means that Actor has stopped,
                 // so we should just close the stream.
@@ -177,28 +269,43 @@ where
     }
     async fn run_send_loop(
         tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Outgoing>,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
     ) -> io::Result<()> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
+        let mut cipher: Option<noise::Cipher> = None;
+        while let Some(mut item) = queue_recv.recv().await {
             // Try writing a batch of messages and flush once at the end.
             loop {
-                // TODO(gprusak): sending a too large message should probably be treated as a bug,
-                // since dropping messages may lead to hard-to-debug high-level issues.
-                if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                    metrics::MessageDropped::InputTooLong.inc_unknown_msg();
-                } else {
-                    writer.write_u32_le(msg.len() as u32).await?;
-                    writer.write_all(&msg[..]).await?;
+                match item {
+                    Outgoing::StartEncryption(c) => cipher = Some(c),
+                    Outgoing::Frame(_, Frame(msg)) => {
+                        let n = msg.len();
+                        // TODO(gprusak): sending a too large message should probably be treated
+                        // as a bug, since dropping messages may lead to hard-to-debug high-level
+                        // issues.
+                        if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                            metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+                        } else {
+                            let (flag, msg) = match &mut cipher {
+                                Some(cipher) => (
+                                    ENCRYPTED_FRAME_FLAG,
+                                    cipher.seal(&msg).map_err(io::Error::other)?,
+                                ),
+                                None => (0, msg),
+                            };
+                            writer.write_u32_le(msg.len() as u32 | flag).await?;
+                            writer.write_all(&msg[..]).await?;
+                        }
+                        stats.messages_to_send.fetch_sub(1, Ordering::Release);
+                        stats.bytes_to_send.fetch_sub(n as u64, Ordering::Release);
+                        buf_size_metric.sub(n as i64);
+                    }
                 }
-                stats.messages_to_send.fetch_sub(1, Ordering::Release);
-                stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
-                buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
+                item = match queue_recv.try_recv() {
+                    Ok(it) => it,
                     Err(_) => break,
                 };
             }
@@ -212,4 +319,155 @@ where
         }
         Ok(())
     }
+
+    /// Dispatches the frames from the send queue to a separate QUIC stream per
+    /// `quic::StreamClass`, so that a large frame delays only the frames of its
+    /// own class. Streams are opened once the first frame of their class is sent.
+    async fn run_quic_send_loop(
+        conn: quinn::Connection,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Outgoing>,
+        stats: Arc<connection::Stats>,
+        buf_size_metric: Arc<metrics::IntGaugeGuard>,
+    ) -> io::Result<()> {
+        let mut queues = EnumMap::<
+            quic::StreamClass,
+            Option<tokio::sync::mpsc::UnboundedSender<Frame>>,
+        >::default();
+        let mut streams = futures_util::stream::FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                item = queue_recv.recv() => match item {
+                    Some(Outgoing::Frame(class, frame)) => {
+                        let queue = queues[class].get_or_insert_with(|| {
+                            let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+                            streams.push(Self::run_quic_stream_send_loop(
+                                &conn,
+                                class,
+                                queue_recv,
+                                &stats,
+                                &buf_size_metric,
+                            ));
+                            queue_send
+                        });
+                        let _ = queue.send(frame);
+                    }
+                    // QUIC connections are encrypted by TLS.
+                    Some(Outgoing::StartEncryption(_)) => {}
+                    None => {
+                        // Send the remaining frames before closing the streams.
+                        drop(queues);
+                        while let Some(res) = streams.next().await {
+                            res?;
+                        }
+                        return Ok(());
+                    }
+                },
+                Some(res) = streams.next() => res?,
+            }
+        }
+    }
+
+    async fn run_quic_stream_send_loop(
+        conn: &quinn::Connection,
+        class: quic::StreamClass,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        stats: &connection::Stats,
+        buf_size_metric: &metrics::IntGaugeGuard,
+    ) -> io::Result<()> {
+        let mut send = conn.open_uni().await.map_err(quic::io_error)?;
+        send.set_priority(class.priority()).map_err(io::Error::other)?;
+        send.write_u8(class as u8).await?;
+        while let Some(Frame(msg)) = queue_recv.recv().await {
+            let n = msg.len();
+            if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+            } else {
+                send.write_u32_le(n as u32).await?;
+                send.write_all(&msg[..]).await?;
+            }
+            stats.messages_to_send.fetch_sub(1, Ordering::Release);
+            stats.bytes_to_send.fetch_sub(n as u64, Ordering::Release);
+            buf_size_metric.sub(n as i64);
+        }
+        Ok(())
+    }
+
+    /// Receives frames from the QUIC streams opened by the peer.
+    /// Since streams are independent, frames of classes other than `Control`
+    /// are passed to the Actor only once the first `Control` frame (the
+    /// handshake) has been processed.
+    async fn run_quic_recv_loop(
+        peer_addr: SocketAddr,
+        conn: quinn::Connection,
+        addr: actix::Addr<Actor>,
+        stats: Arc<connection::Stats>,
+    ) -> Result<(), RecvError> {
+        let msg_size_metric =
+            metrics::MetricGuard::new(&metrics::PEER_MSG_SIZE_BYTES, vec![peer_addr.to_string()]);
+        let (handshake_done_send, handshake_done) = tokio::sync::watch::channel(false);
+        let mut streams = futures_util::stream::FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                recv = conn.accept_uni() => {
+                    let recv = recv.map_err(|err| RecvError::IO(quic::io_error(err)))?;
+                    streams.push(Self::run_quic_stream_recv_loop(
+                        recv,
+                        &addr,
+                        &stats,
+                        &msg_size_metric,
+                        &handshake_done_send,
+                        handshake_done.clone(),
+                    ));
+                }
+                Some(res) = streams.next() => {
+                    // Streams end only with an error or once the Actor has stopped.
+                    res?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn run_quic_stream_recv_loop(
+        mut recv: quinn::RecvStream,
+        addr: &actix::Addr<Actor>,
+        stats: &connection::Stats,
+        msg_size_metric: &metrics::HistogramGuard,
+        handshake_done_send: &tokio::sync::watch::Sender<bool>,
+        mut handshake_done: tokio::sync::watch::Receiver<bool>,
+    ) -> Result<(), RecvError> {
+        let class = recv.read_u8().await.map_err(RecvError::IO)?;
+        let class =
+            quic::StreamClass::from_repr(class).ok_or(RecvError::UnknownStreamClass(class))?;
+        loop {
+            let n = recv.read_u32_le().await.map_err(RecvError::IO)? as usize;
+            if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                return Err(RecvError::MessageTooLarge {
+                    got_bytes: n,
+                    want_max_bytes: NETWORK_MESSAGE_MAX_SIZE_BYTES,
+                });
+            }
+            msg_size_metric.observe(n as f64);
+            let mut buf = vec![0; n];
+            let t = metrics::PEER_MSG_READ_LATENCY.start_timer();
+            // Call the tokio trait method rather than the inherent one, which
+            // doesn't return an io::Error.
+            AsyncReadExt::read_exact(&mut recv, &mut buf[..]).await.map_err(RecvError::IO)?;
+            t.observe_duration();
+            stats.received_messages.fetch_add(1, Ordering::Relaxed);
+            stats.received_bytes.fetch_add(n as u64, Ordering::Relaxed);
+            if class != quic::StreamClass::Control
+                && handshake_done.wait_for(|done| *done).await.is_err()
+            {
+                return Ok(());
+            }
+            if let Err(_) = addr.send(Frame(buf)).await {
+                // Actor has stopped.
+                return Ok(());
+            }
+            if class == quic::StreamClass::Control {
+                handshake_done_send.send_replace(true);
+            }
+        }
+    }
 }
@@ -13,7 +13,9 @@ use anyhow::Context as _;
 use assert_matches::assert_matches;
 use near_async::time;
 use near_o11y::testonly::init_test_logger;
-use near_primitives::version::{PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use near_primitives::version::{
+    ProtocolFeature, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 use std::sync::Arc;
 
 async fn test_peer_communication(
@@ -159,11 +161,21 @@ async fn test_peer_communication(
     Ok(())
 }
 
+/// Encodings to test with. Borsh doesn't support session keys, so it can't be used
+/// for handshakes at protocol versions requiring encryption of the connection.
+fn supported_encodings() -> Vec<Option<Encoding>> {
+    if ProtocolFeature::EncryptedPeerTransport.enabled(PROTOCOL_VERSION) {
+        vec![None, Some(Encoding::Proto)]
+    } else {
+        vec![None, Some(Encoding::Proto), Some(Encoding::Borsh)]
+    }
+}
+
 #[tokio::test]
 // Verifies that peers are able to establish a common encoding protocol.
 async fn peer_communication() -> anyhow::Result<()> {
     init_test_logger();
-    let encodings = [None, Some(Encoding::Proto), Some(Encoding::Borsh)];
+    let encodings = supported_encodings();
     for outbound in &encodings {
         for inbound in &encodings {
             if let (Some(a), Some(b)) = (outbound, inbound) {
@@ -211,6 +223,12 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         partial_edge_info: outbound_cfg
             .partial_edge_info(&inbound.cfg.id(), Edge::create_fresh_nonce(&clock.clock())),
         owned_account: None,
+        session_key: Some(data::make_session_key(
+            &mut rng,
+            &outbound_cfg.network.node_key,
+            PROTOCOL_VERSION,
+        )),
+        accepted_compression: vec![],
     };
     // We will also introduce chain_id mismatch, but ProtocolVersionMismatch is expected to take priority.
     handshake.sender_chain_info.genesis_id.chain_id = "unknown_chain".to_string();
@@ -253,7 +271,7 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
 // Verifies that HandshakeFailures are served correctly.
 async fn handshake() -> anyhow::Result<()> {
     init_test_logger();
-    let encodings = [None, Some(Encoding::Proto), Some(Encoding::Borsh)];
+    let encodings = supported_encodings();
     for outbound in &encodings {
         for inbound in &encodings {
             println!("oubound = {:?}, inbound = {:?}", outbound, inbound);
@@ -0,0 +1,91 @@
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::SessionKey;
+use crate::peer::noise;
+use crate::testonly::make_rng;
+use crate::types::PeerType;
+use near_primitives::network::PeerId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+/// Returns ciphers of the outbound and inbound side of a connection.
+pub(super) fn make_ciphers(
+    rng: &mut impl rand::Rng,
+) -> (noise::SessionCiphers, noise::SessionCiphers) {
+    let outbound_key = data::make_secret_key(rng);
+    let inbound_key = data::make_secret_key(rng);
+    let outbound_id = PeerId::new(outbound_key.public_key());
+    let inbound_id = PeerId::new(inbound_key.public_key());
+    let outbound = noise::EphemeralSecret::new();
+    let inbound = noise::EphemeralSecret::new();
+    let outbound_session_key = outbound.session_key(PROTOCOL_VERSION, &outbound_key);
+    let inbound_session_key = inbound.session_key(PROTOCOL_VERSION, &inbound_key);
+    outbound_session_key.verify(&outbound_id, PROTOCOL_VERSION).unwrap();
+    inbound_session_key.verify(&inbound_id, PROTOCOL_VERSION).unwrap();
+    (
+        outbound
+            .agree(PeerType::Outbound, &outbound_id, &inbound_session_key, &inbound_id)
+            .unwrap(),
+        inbound.agree(PeerType::Inbound, &inbound_id, &outbound_session_key, &outbound_id).unwrap(),
+    )
+}
+
+#[test]
+fn agree() {
+    let mut rng = make_rng(4381201);
+    let (mut o, mut i) = make_ciphers(&mut rng);
+    for n in 0..3 {
+        let msg = vec![n; 100];
+        let sealed = o.send.seal(&msg).unwrap();
+        assert_eq!(sealed.len(), msg.len() + noise::TAG_SIZE);
+        assert_eq!(i.recv.open(&sealed).unwrap(), msg);
+        assert_eq!(o.recv.open(&i.send.seal(&msg).unwrap()).unwrap(), msg);
+    }
+    // Each direction uses a different key.
+    i.recv.open(&i.send.seal(b"frame").unwrap()).unwrap_err();
+
+    // Frames can't be reordered or replayed.
+    let (mut o, mut i) = make_ciphers(&mut rng);
+    let first = o.send.seal(b"first").unwrap();
+    let second = o.send.seal(b"second").unwrap();
+    i.recv.open(&second).unwrap_err();
+    let (mut o, mut i) = make_ciphers(&mut rng);
+    let first_again = o.send.seal(b"first").unwrap();
+    assert_ne!(first, first_again);
+    i.recv.open(&first_again).unwrap();
+    i.recv.open(&first_again).unwrap_err();
+
+    // Nor modified.
+    let (mut o, mut i) = make_ciphers(&mut rng);
+    let mut sealed = o.send.seal(b"frame").unwrap();
+    sealed[0] ^= 1;
+    i.recv.open(&sealed).unwrap_err();
+}
+
+#[test]
+fn invalid_session_key() {
+    let mut rng = make_rng(4381202);
+    let key = data::make_secret_key(&mut rng);
+    let id = PeerId::new(key.public_key());
+    let other_id = data::make_peer_id(&mut rng);
+    let secret = noise::EphemeralSecret::new();
+
+    let session_key = secret.session_key(PROTOCOL_VERSION, &key);
+    assert!(matches!(
+        session_key.verify(&other_id, PROTOCOL_VERSION),
+        Err(noise::Error::InvalidSignature)
+    ));
+    // The protocol version of the handshake can't be changed.
+    assert!(matches!(
+        session_key.verify(&id, PROTOCOL_VERSION - 1),
+        Err(noise::Error::InvalidSignature)
+    ));
+    let tampered = SessionKey { public_key: [7; 32], ..session_key };
+    assert!(matches!(tampered.verify(&id, PROTOCOL_VERSION), Err(noise::Error::InvalidSignature)));
+
+    // Low order points would make the shared secret predictable.
+    let low_order = SessionKey::new([0; 32], PROTOCOL_VERSION, &key);
+    low_order.verify(&id, PROTOCOL_VERSION).unwrap();
+    assert!(matches!(
+        secret.agree(PeerType::Inbound, &other_id, &low_order, &id),
+        Err(noise::Error::LowOrderPoint)
+    ));
+}
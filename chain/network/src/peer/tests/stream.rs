@@ -1,10 +1,14 @@
 use crate::actix::ActixSystem;
 use crate::network_protocol::testonly as data;
+use crate::peer::noise;
 use crate::peer::stream;
+use crate::peer::tests::noise::make_ciphers;
+use crate::quic;
 use crate::tcp;
 use crate::testonly::make_rng;
 use actix::Actor as _;
 use actix::ActorContext as _;
+use enum_map::EnumMap;
 use rand::Rng as _;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -12,6 +16,9 @@ use tokio::sync::mpsc;
 struct Actor {
     stream: stream::FramedStream<Actor>,
     queue_send: mpsc::UnboundedSender<stream::Frame>,
+    /// Cipher to decrypt frames after the next received one with, the same way
+    /// PeerActor enables decryption when processing the handshake.
+    recv_cipher_after_frame: Option<noise::Cipher>,
 }
 
 impl actix::Actor for Actor {
@@ -29,10 +36,43 @@ impl actix::Handler<SendFrame> for Actor {
     }
 }
 
+#[derive(actix::Message)]
+#[rtype("()")]
+struct SendFrameOn(quic::StreamClass, stream::Frame);
+
+impl actix::Handler<SendFrameOn> for Actor {
+    type Result = ();
+    fn handle(&mut self, SendFrameOn(class, frame): SendFrameOn, _ctx: &mut Self::Context) {
+        self.stream.send_on(class, frame);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype("()")]
+enum EnableEncryption {
+    Send(noise::Cipher),
+    Recv(noise::Cipher),
+    RecvAfterFrame(noise::Cipher),
+}
+
+impl actix::Handler<EnableEncryption> for Actor {
+    type Result = ();
+    fn handle(&mut self, msg: EnableEncryption, _ctx: &mut Self::Context) {
+        match msg {
+            EnableEncryption::Send(cipher) => self.stream.enable_send_encryption(cipher),
+            EnableEncryption::Recv(cipher) => self.stream.enable_recv_encryption(cipher),
+            EnableEncryption::RecvAfterFrame(cipher) => self.recv_cipher_after_frame = Some(cipher),
+        }
+    }
+}
+
 impl actix::Handler<stream::Frame> for Actor {
     type Result = ();
     fn handle(&mut self, frame: stream::Frame, _ctx: &mut Self::Context) {
         self.queue_send.send(frame).ok().unwrap();
+        if let Some(cipher) = self.recv_cipher_after_frame.take() {
+            self.stream.enable_recv_encryption(cipher);
+        }
     }
 }
 
@@ -56,7 +96,7 @@ impl Actor {
             system: ActixSystem::spawn(|| {
                 Actor::create(|ctx| {
                     let stream = stream::FramedStream::spawn(ctx, s, Arc::default());
-                    Self { stream, queue_send }
+                    Self { stream, queue_send, recv_cipher_after_frame: None }
                 })
             })
             .await,
@@ -90,3 +130,111 @@ async fn send_recv() {
         }
     }
 }
+
+fn make_frames(rng: &mut impl rand::Rng) -> Vec<stream::Frame> {
+    (0..rng.gen_range(1..10))
+        .map(|_| {
+            let mut msg = vec![0; rng.gen_range(0..10000)];
+            rng.fill(&mut msg[..]);
+            stream::Frame(msg)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn send_recv_encrypted() {
+    let mut rng = make_rng(98324533);
+    let (s1, s2) = tcp::Stream::loopback(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+    let (c1, c2) = make_ciphers(&mut rng);
+
+    // Like a handshake, the first frame is sent in plaintext and both sides
+    // switch to encryption right after it, without waiting for each other.
+    a2.system.addr.send(EnableEncryption::RecvAfterFrame(c2.recv)).await.unwrap();
+    let mut want = vec![stream::Frame(b"handshake".to_vec())];
+    want.extend(make_frames(&mut rng));
+    a1.system.addr.send(SendFrame(want[0].clone())).await.unwrap();
+    a1.system.addr.send(EnableEncryption::Send(c1.send)).await.unwrap();
+    for msg in &want[1..] {
+        a1.system.addr.send(SendFrame(msg.clone())).await.unwrap();
+    }
+    for want in &want {
+        assert_eq!(&a2.queue_recv.recv().await.unwrap(), want);
+    }
+}
+
+#[tokio::test]
+async fn plaintext_dropped_after_encryption() {
+    let mut rng = make_rng(98324534);
+    let (s1, s2) = tcp::Stream::loopback(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+    let (c1, c2) = make_ciphers(&mut rng);
+
+    a2.system.addr.send(EnableEncryption::Recv(c2.recv)).await.unwrap();
+    for msg in make_frames(&mut rng) {
+        a1.system.addr.send(SendFrame(msg)).await.unwrap();
+    }
+    a1.system.addr.send(EnableEncryption::Send(c1.send)).await.unwrap();
+    let want = make_frames(&mut rng);
+    for msg in &want {
+        a1.system.addr.send(SendFrame(msg.clone())).await.unwrap();
+    }
+    for want in &want {
+        assert_eq!(&a2.queue_recv.recv().await.unwrap(), want);
+    }
+}
+
+#[tokio::test]
+async fn wrong_key_closes_stream() {
+    let mut rng = make_rng(98324535);
+    let (s1, s2) = tcp::Stream::loopback(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+    let (c1, _) = make_ciphers(&mut rng);
+    let (_, c2) = make_ciphers(&mut rng);
+
+    a2.system.addr.send(EnableEncryption::Recv(c2.recv)).await.unwrap();
+    a1.system.addr.send(EnableEncryption::Send(c1.send)).await.unwrap();
+    for msg in make_frames(&mut rng) {
+        a1.system.addr.send(SendFrame(msg)).await.unwrap();
+    }
+    // Actor stops on the decryption error, closing its queue.
+    assert_eq!(a2.queue_recv.recv().await, None);
+}
+
+#[tokio::test]
+async fn send_recv_quic() {
+    let mut rng = make_rng(98324536);
+    let (s1, s2) = quic::testonly::loopback(data::make_peer_id(&mut rng)).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+
+    // Frames are prefixed with their class, so that the receiver can tell them apart.
+    let classes = [
+        quic::StreamClass::Control,
+        quic::StreamClass::Consensus,
+        quic::StreamClass::Chunks,
+        quic::StreamClass::StateWitness,
+    ];
+    let handshake = stream::Frame(vec![quic::StreamClass::Control as u8]);
+    a1.system.addr.send(SendFrameOn(quic::StreamClass::Control, handshake.clone())).await.unwrap();
+    let mut want = EnumMap::<quic::StreamClass, Vec<stream::Frame>>::default();
+    for stream::Frame(msg) in make_frames(&mut rng) {
+        let class = classes[rng.gen_range(0..classes.len())];
+        let frame = stream::Frame([&[class as u8][..], &msg[..]].concat());
+        a1.system.addr.send(SendFrameOn(class, frame.clone())).await.unwrap();
+        want[class].push(frame);
+    }
+
+    // The first frame (the handshake) is received before frames of any other
+    // class, and frames of each class are received in order.
+    assert_eq!(a2.queue_recv.recv().await.unwrap(), handshake);
+    let mut got = EnumMap::<quic::StreamClass, Vec<stream::Frame>>::default();
+    for _ in 0..want.values().map(Vec::len).sum() {
+        let frame = a2.queue_recv.recv().await.unwrap();
+        got[quic::StreamClass::from_repr(frame.0[0]).unwrap()].push(frame);
+    }
+    assert_eq!(got, want);
+}
@@ -1,6 +1,8 @@
 use crate::peer::transfer_stats::TransferStats;
+use lru::LruCache;
 use near_async::time;
 use near_primitives::hash::CryptoHash;
+use std::num::NonZeroUsize;
 
 /// Maximum number of requests and responses to track.
 const MAX_TRACK_SIZE: usize = 30;
@@ -47,6 +49,8 @@ pub(crate) struct Tracker {
     pub(crate) received_bytes: TransferStats,
     /// Sent requests.
     requested: CircularUniqueQueue,
+    /// Times the sent requests which haven't been responded to yet were sent at.
+    pending_requests: LruCache<CryptoHash, time::Instant>,
     /// Received elements.
     received: CircularUniqueQueue,
 }
@@ -57,6 +61,7 @@ impl Default for Tracker {
             sent_bytes: TransferStats::default(),
             received_bytes: TransferStats::default(),
             requested: CircularUniqueQueue::new(MAX_TRACK_SIZE),
+            pending_requests: LruCache::new(NonZeroUsize::new(MAX_TRACK_SIZE).unwrap()),
             received: CircularUniqueQueue::new(MAX_TRACK_SIZE),
         }
     }
@@ -86,8 +91,21 @@ impl Tracker {
         self.requested.contains(hash)
     }
 
-    pub(crate) fn push_request(&mut self, hash: CryptoHash) {
+    pub(crate) fn push_request(&mut self, clock: &time::Clock, hash: CryptoHash) {
         self.requested.push(hash);
+        if !self.pending_requests.contains(&hash) {
+            self.pending_requests.push(hash, clock.now());
+        }
+    }
+
+    /// Returns the time elapsed since the request for `hash` was sent, unless it has
+    /// been responded to already.
+    pub(crate) fn pop_request_latency(
+        &mut self,
+        clock: &time::Clock,
+        hash: &CryptoHash,
+    ) -> Option<time::Duration> {
+        self.pending_requests.pop(hash).map(|sent| clock.now() - sent)
     }
 }
 
@@ -165,4 +183,20 @@ mod tests {
         }
         assert!(q.contains(&hash(&[5])));
     }
+
+    #[test]
+    fn test_request_latency() {
+        let clock = time::FakeClock::default();
+        let mut tracker = Tracker::default();
+        tracker.push_request(&clock.clock(), hash(&[1]));
+        clock.advance(time::Duration::seconds(3));
+        // Repeating a request doesn't reset the time it was first sent at.
+        tracker.push_request(&clock.clock(), hash(&[1]));
+        assert_eq!(
+            tracker.pop_request_latency(&clock.clock(), &hash(&[1])),
+            Some(time::Duration::seconds(3))
+        );
+        assert_eq!(tracker.pop_request_latency(&clock.clock(), &hash(&[1])), None);
+        assert_eq!(tracker.pop_request_latency(&clock.clock(), &hash(&[2])), None);
+    }
 }
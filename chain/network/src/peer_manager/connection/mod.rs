@@ -76,6 +76,8 @@ pub(crate) struct Stats {
 pub(crate) struct Connection {
     // TODO(gprusak): add rate limiting on TIER1 connections for defence in-depth.
     pub tier: tcp::Tier,
+    /// Whether the connection uses QUIC rather than TCP.
+    pub quic: bool,
     // TODO(gprusak): addr should be internal, so that Connection will become an API of the
     // PeerActor.
     pub addr: actix::Addr<PeerActor>,
@@ -14,10 +14,13 @@ use crate::peer_manager::connection;
 use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_store;
 use crate::private_actix::RegisterPeerError;
+use crate::quic;
+use crate::recorder;
 use crate::routing::route_back_cache::RouteBackCache;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::snapshot_hosts::{SnapshotHostInfoError, SnapshotHostsCache};
+use crate::state_part_server::StatePartServer;
 use crate::state_witness::PartialWitnessSenderForNetwork;
 use crate::stats::metrics;
 use crate::store;
@@ -108,12 +111,18 @@ pub(crate) struct NetworkState {
     /// Connected peers (inbound and outbound) with their full peer information.
     pub tier2: connection::Pool,
     pub tier1: connection::Pool,
+    /// QUIC endpoint of TIER1 connections, set once it is bound iff `Tier1::quic` is enabled.
+    pub quic: std::sync::OnceLock<quic::Endpoint>,
+    /// Recorder of the messages exchanged with peers, iff `NetworkConfig::record_traffic` is set.
+    pub recorder: Option<recorder::Recorder>,
     /// Semaphore limiting inflight inbound handshakes.
     pub inbound_handshake_permits: Arc<tokio::sync::Semaphore>,
     /// Peer store that provides read/write access to peers.
     pub peer_store: peer_store::PeerStore,
     /// Information about state snapshots hosted by network peers.
     pub snapshot_hosts: Arc<SnapshotHostsCache>,
+    /// Bandwidth limits of state parts served to peers.
+    pub state_part_server: StatePartServer,
     /// Connection store that provides read/write access to stored connections.
     pub connection_store: connection_store::ConnectionStore,
     /// List of peers to which we should re-establish a connection
@@ -168,6 +177,7 @@ impl NetworkState {
         shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
         partial_witness_adapter: PartialWitnessSenderForNetwork,
         whitelist_nodes: Vec<WhitelistNode>,
+        recorder: Option<recorder::Recorder>,
     ) -> Self {
         Self {
             runtime: Runtime::new(),
@@ -187,9 +197,12 @@ impl NetworkState {
             chain_info: Default::default(),
             tier2: connection::Pool::new(config.node_id()),
             tier1: connection::Pool::new(config.node_id()),
+            quic: std::sync::OnceLock::new(),
+            recorder,
             inbound_handshake_permits: Arc::new(tokio::sync::Semaphore::new(LIMIT_PENDING_PEERS)),
             peer_store,
             snapshot_hosts: Arc::new(SnapshotHostsCache::new(config.snapshot_hosts.clone())),
+            state_part_server: StatePartServer::new(config.state_part_server.clone(), clock.now()),
             connection_store: connection_store::ConnectionStore::new(store.clone()).unwrap(),
             pending_reconnect: Mutex::new(Vec::<PeerInfo>::new()),
             accounts_data: Arc::new(AccountDataCache::new()),
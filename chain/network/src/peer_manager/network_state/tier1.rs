@@ -37,6 +37,20 @@ impl super::NetworkState {
         Some(FrozenValidatorConfig { signer, proxies: &self.config.validator.proxies })
     }
 
+    /// Establishes a TIER1 connection to `peer_info`, over QUIC if enabled.
+    /// Falls back to TCP, since the peer might not accept QUIC connections.
+    async fn tier1_stream_connect(&self, peer_info: &PeerInfo) -> anyhow::Result<tcp::Stream> {
+        if let Some(endpoint) = self.quic.get() {
+            match endpoint.connect(peer_info).await {
+                Ok(stream) => return Ok(stream),
+                Err(err) => {
+                    tracing::debug!(target:"network", ?err, "failed to establish a QUIC connection to {}, falling back to TCP", peer_info)
+                }
+            }
+        }
+        tcp::Stream::connect(peer_info, tcp::Tier::T1, &self.config.socket_options).await
+    }
+
     async fn tier1_connect_to_my_proxies(
         self: &Arc<Self>,
         clock: &time::Clock,
@@ -52,16 +66,13 @@ impl super::NetworkState {
             }
             handles.push(async move {
                 let res = async {
-                    let stream = tcp::Stream::connect(
-                        &PeerInfo {
+                    let stream = self
+                        .tier1_stream_connect(&PeerInfo {
                             id: proxy.peer_id.clone(),
                             addr: Some(proxy.addr),
                             account_id: None,
-                        },
-                        tcp::Tier::T1,
-                        &self.config.socket_options,
-                    )
-                    .await?;
+                        })
+                        .await?;
                     anyhow::Ok(PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await?)
                 }.await;
                 if let Err(err) = res {
@@ -330,16 +341,13 @@ impl super::NetworkState {
                 if let Some(proxy) = proxy {
                     let proxy = (*proxy).clone();
                     handles.push(async move {
-                        let stream = tcp::Stream::connect(
-                            &PeerInfo {
+                        let stream = self
+                            .tier1_stream_connect(&PeerInfo {
                                 id: proxy.peer_id,
                                 addr: Some(proxy.addr),
                                 account_id: None,
-                            },
-                            tcp::Tier::T1,
-                            &self.config.socket_options,
-                        )
-                        .await?;
+                            })
+                            .await?;
                         PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
                             .await
                     });
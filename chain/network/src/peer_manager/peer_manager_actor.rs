@@ -10,6 +10,8 @@ use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
 use crate::peer_manager::peer_store;
+use crate::quic;
+use crate::recorder;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::state_witness::PartialWitnessSenderForNetwork;
 use crate::stats::metrics;
@@ -230,6 +232,14 @@ impl PeerManagerActor {
             }
             v
         };
+        let recorder = match &config.record_traffic {
+            Some(dir) => {
+                let recorder = recorder::Recorder::new(&clock, dir).context("Recorder::new")?;
+                tracing::info!(target: "network", path = ?recorder.path(), "Recording network traffic");
+                Some(recorder)
+            }
+            None => None,
+        };
         let my_peer_id = config.node_id();
         let arbiter = actix::Arbiter::new().handle();
         let clock = clock;
@@ -243,6 +253,7 @@ impl PeerManagerActor {
             shards_manager_adapter,
             partial_witness_adapter,
             whitelist_nodes,
+            recorder,
         ));
         arbiter.spawn({
             let arbiter = arbiter.clone();
@@ -258,6 +269,45 @@ impl PeerManagerActor {
                             panic!("failed to start listening on server_addr={server_addr:?} e={e:?}")
                         }
                     };
+                    if state.config.tier1.as_ref().map_or(false, |cfg| cfg.quic) {
+                        match quic::Endpoint::new(**server_addr) {
+                            Ok(endpoint) => {
+                                let endpoint = state.quic.get_or_init(|| endpoint);
+                                tracing::debug!(target: "network", at = ?endpoint.local_addr(), "accepting TIER1 QUIC connections");
+                                arbiter.spawn({
+                                    let clock = clock.clone();
+                                    let state = state.clone();
+                                    async move {
+                                        let endpoint = state.quic.get().unwrap();
+                                        while let Some(incoming) = endpoint.accept().await {
+                                            // Complete the QUIC handshake in the background, so
+                                            // that a slow peer doesn't block the accept loop.
+                                            let clock = clock.clone();
+                                            let state = state.clone();
+                                            tokio::spawn(async move {
+                                                let stream = match incoming.accept().await {
+                                                    Ok(stream) => stream,
+                                                    Err(err) => {
+                                                        tracing::debug!(target: "network", ?err, "failed to accept a QUIC connection");
+                                                        return;
+                                                    }
+                                                };
+                                                tracing::debug!(target: "network", from = ?stream.peer_addr, "got new QUIC connection");
+                                                if let Err(err) =
+                                                    PeerActor::spawn(clock, stream, None, state)
+                                                {
+                                                    tracing::info!(target:"network", ?err, "PeerActor::spawn()");
+                                                }
+                                            });
+                                        }
+                                    }
+                                });
+                            }
+                            Err(err) => {
+                                tracing::warn!(target: "network", ?err, "failed to bind the QUIC endpoint, TIER1 connections will use TCP only")
+                            }
+                        }
+                    }
                     #[cfg(test)]
                     state.config.event_sink.send(Event::ServerStarted);
                     arbiter.spawn({
@@ -477,6 +527,9 @@ impl PeerManagerActor {
     /// 3. Find all peers who sent us a message within the last peer_recent_time_window,
     ///    and add them one by one to the safe_set (starting from earliest connection time)
     ///    until safe set has safe_set_size elements.
+    ///
+    /// Among the peers outside of the safe set, the one with the lowest reputation is selected
+    /// (with ties broken randomly).
     fn maybe_stop_active_connection(&self) {
         let tier2 = self.state.tier2.load();
         let filter_peers = |predicate: &dyn Fn(&connection::Connection) -> bool| -> Vec<_> {
@@ -542,9 +595,18 @@ impl PeerManagerActor {
         }
 
         // Build valid candidate list to choose the peer to be removed. All peers outside the safe set.
-        let candidates = tier2.ready.values().filter(|p| !safe_set.contains(&p.peer_info.id));
-        if let Some(p) = candidates.choose(&mut rand::thread_rng()) {
+        let mut candidates: Vec<_> = tier2
+            .ready
+            .values()
+            .filter(|p| !safe_set.contains(&p.peer_info.id))
+            .map(|p| (self.state.peer_store.reputation_score(&self.clock, &p.peer_info.id), p))
+            .collect();
+        candidates.shuffle(&mut rand::thread_rng());
+        if let Some((reputation, p)) =
+            candidates.into_iter().min_by(|(a, _), (b, _)| a.total_cmp(b))
+        {
             tracing::debug!(target: "network", id = ?p.peer_info.id,
+                reputation,
                 tier2_len = tier2.ready.len(),
                 ideal_connections_hi = self.state.config.ideal_connections_hi,
                 "Stop active connection"
@@ -583,6 +645,7 @@ impl PeerManagerActor {
             let prefer_previously_connected_peer =
                 thread_rng().gen_bool(PREFER_PREVIOUSLY_CONNECTED_PEER);
             if let Some(peer_info) = self.state.peer_store.unconnected_peer(
+                &self.clock,
                 |peer_state| {
                     // Ignore connecting to ourself
                     self.my_peer_id == peer_state.peer_info.id
@@ -590,6 +653,7 @@ impl PeerManagerActor {
                     // Or to peers we are currently trying to connect to
                     || tier2.outbound_handshakes.contains(&peer_state.peer_info.id)
                 },
+                |peer_state| self.state.is_peer_whitelisted(&peer_state.peer_info),
                 prefer_previously_connected_peer,
             ) {
                 // Start monitor_peers_attempts from start after we discover the first healthy peer
@@ -674,6 +738,8 @@ impl PeerManagerActor {
                 Some(e) => e.nonce(),
                 None => 0,
             },
+            reputation: self.state.peer_store.reputation_score(&self.clock, &cp.peer_info.id)
+                as i64,
         };
         NetworkInfo {
             connected_peers: tier2.ready.values().map(connected_peer).collect(),
@@ -1129,6 +1195,8 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                                 (attempt_time.unix_timestamp(), foo)
                             },
                         ),
+                        reputation: known_peer_state.reputation.score_at(self.clock.now_utc())
+                            as i64,
                     })
                     .collect::<Vec<_>>();
 
@@ -8,12 +8,14 @@ use lru::LruCache;
 use near_async::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 use rand::thread_rng;
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::ops::Not;
 
+pub(crate) mod reputation;
+
 #[cfg(test)]
 mod testonly;
 #[cfg(test)]
@@ -28,6 +30,12 @@ mod tests;
 /// Contents of the PeerStore are not persisted to the database. Upon starting a node,
 /// the PeerStore is initialized from the boot nodes in its config.
 
+/// Number of random candidates the peer with the best reputation is chosen from,
+/// when selecting a peer to connect to. Choosing from a small sample rather than
+/// all the candidates prefers reputable peers, while keeping the selection random
+/// enough that nodes don't all end up connecting to the same peers.
+const CONNECT_CANDIDATES: usize = 3;
+
 /// Level of trust we have about a new (PeerId, Addr) pair.
 #[derive(Eq, PartialEq, Debug, Clone, Copy)]
 enum TrustLevel {
@@ -181,6 +189,22 @@ impl Inner {
             .collect()
     }
 
+    /// Find the peer with the best reputation among a few random peers matching the filter.
+    fn find_best_peer<F>(&self, now: time::Utc, filter: F) -> Option<PeerInfo>
+    where
+        F: FnMut(&&KnownPeerState) -> bool,
+    {
+        let mut candidates = (self.peer_states.iter().map(|(_, v)| v))
+            .filter(filter)
+            .choose_multiple(&mut thread_rng(), CONNECT_CANDIDATES);
+        // Break ties randomly.
+        candidates.shuffle(&mut thread_rng());
+        candidates
+            .into_iter()
+            .max_by(|a, b| a.reputation.score_at(now).total_cmp(&b.reputation.score_at(now)))
+            .map(|kps| kps.peer_info.clone())
+    }
+
     /// Create new pair between peer_info.id and peer_addr removing
     /// old pairs if necessary.
     fn update_peer_info(
@@ -421,6 +445,7 @@ impl PeerStore {
         if let Some(peer_state) = inner.peer_states.get_mut(peer_id) {
             let now = clock.now_utc();
             peer_state.last_seen = now;
+            peer_state.reputation.record(now, &reputation::Signal::Banned(ban_reason));
             peer_state.status = KnownPeerStatus::Banned(ban_reason, now);
         } else {
             bail!("Peer {} is missing in the peer store", peer_id);
@@ -428,45 +453,69 @@ impl PeerStore {
         Ok(())
     }
 
+    /// Updates the reputation of a peer with a signal of its behavior.
+    /// Signals about peers missing in the peer store are ignored.
+    pub fn peer_reputation_signal(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        signal: reputation::Signal,
+    ) {
+        if let Some(peer_state) = self.0.lock().peer_states.peek_mut(peer_id) {
+            peer_state.reputation.record(clock.now_utc(), &signal);
+        }
+    }
+
+    /// Returns the current reputation score of a peer, 0 for peers missing in the peer store.
+    pub fn reputation_score(&self, clock: &time::Clock, peer_id: &PeerId) -> f64 {
+        self.0
+            .lock()
+            .peer_states
+            .peek(peer_id)
+            .map_or(0., |s| s.reputation.score_at(clock.now_utc()))
+    }
+
     /// Return unconnected or peers with unknown status that we can try to connect to.
-    /// Peers with unknown addresses are filtered out.
+    /// Peers with unknown addresses or too low reputation are filtered out, and peers
+    /// with better reputation are preferred. Boot nodes and peers for which `trusted_fn`
+    /// returns true are not filtered out by reputation.
     pub fn unconnected_peer(
         &self,
+        clock: &time::Clock,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
+        trusted_fn: impl Fn(&KnownPeerState) -> bool,
         prefer_previously_connected_peer: bool,
     ) -> Option<PeerInfo> {
         let inner = self.0.lock();
+        let now = clock.now_utc();
+        let reputable = |p: &KnownPeerState| {
+            p.reputation.score_at(now) >= reputation::MIN_OUTBOUND_SCORE
+                || inner.boot_nodes.contains(&p.peer_info.id)
+                || trusted_fn(p)
+        };
         if prefer_previously_connected_peer {
-            let preferred_peer = inner.find_peers(
-                |p| {
-                    (p.status == KnownPeerStatus::NotConnected)
-                        && !ignore_fn(p)
-                        && p.peer_info.addr.is_some()
-                        // if we're connecting only to the boot nodes - filter out the nodes that are not bootnodes.
-                        && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-                },
-                1,
-            )
-            .get(0)
-            .cloned();
+            let preferred_peer = inner.find_best_peer(now, |p| {
+                (p.status == KnownPeerStatus::NotConnected)
+                    && !ignore_fn(p)
+                    && p.peer_info.addr.is_some()
+                    && reputable(p)
+                    // if we're connecting only to the boot nodes - filter out the nodes that are not bootnodes.
+                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
+            });
             // If we found a preferred peer - return it.
             if preferred_peer.is_some() {
                 return preferred_peer;
             };
             // otherwise, pick a peer from the wider pool below.
         }
-        inner.find_peers(
-            |p| {
-                (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
-                    && !ignore_fn(p)
-                    && p.peer_info.addr.is_some()
-                    // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-            },
-            1,
-        )
-        .get(0)
-        .cloned()
+        inner.find_best_peer(now, |p| {
+            (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
+                && !ignore_fn(p)
+                && p.peer_info.addr.is_some()
+                && reputable(p)
+                // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
+                && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
+        })
     }
 
     /// Return healthy known peers up to given amount.
@@ -0,0 +1,70 @@
+//! Reputation of known peers.
+//!
+//! Every peer in the PeerStore has a score, which starts at 0, grows with timely
+//! responses to our requests and drops with misbehavior: slow responses, invalid
+//! chunks, malformed and rate limited messages, and most of all getting banned. Scores decay
+//! exponentially towards 0, so that peers recover from past misbehavior once their
+//! ban is over, and can't live off their past merit either.
+//!
+//! The score is used to:
+//! - prefer peers with higher scores and skip peers with very low ones, other
+//!   than boot nodes and whitelisted peers, when selecting a peer to establish
+//!   an outbound connection to,
+//! - evict the connection with the lowest score first when there are too many.
+use crate::types::{ReasonForBan, Reputation};
+use near_async::time;
+
+/// Time after which a score decays to half of its value.
+const HALF_LIFE: time::Duration = time::Duration::hours(1);
+const MIN_SCORE: f64 = -1000.;
+const MAX_SCORE: f64 = 100.;
+/// Peers with lower scores are not selected for outbound connections.
+/// A banned peer recovers from the ban penalty to that level after ~2h.
+pub(crate) const MIN_OUTBOUND_SCORE: f64 = -100.;
+/// Responses arriving later than that are considered slow.
+const SLOW_RESPONSE_THRESHOLD: time::Duration = time::Duration::seconds(2);
+
+/// Signals of the behavior of a peer affecting its reputation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Signal {
+    /// Peer responded to our request after `latency`.
+    Response { latency: time::Duration },
+    /// Peer sent a message of a known type we failed to parse.
+    InvalidMessage,
+    /// Peer authored a chunk whose parts don't match its merkle root.
+    InvalidChunk,
+    /// Peer sent a message exceeding the rate limit of its type.
+    RateLimited,
+    /// Peer has been banned.
+    Banned(ReasonForBan),
+}
+
+impl Signal {
+    fn delta(&self) -> f64 {
+        match self {
+            Signal::Response { latency } if *latency <= SLOW_RESPONSE_THRESHOLD => 1.,
+            Signal::Response { .. } => -2.,
+            Signal::InvalidMessage => -10.,
+            Signal::InvalidChunk => -50.,
+            Signal::RateLimited => -1.,
+            Signal::Banned(_) => -500.,
+        }
+    }
+}
+
+impl Reputation {
+    pub fn new(now: time::Utc) -> Self {
+        Self { score: 0., updated: now }
+    }
+
+    /// Returns the score decayed until `now`.
+    pub fn score_at(&self, now: time::Utc) -> f64 {
+        let elapsed = (now - self.updated).as_seconds_f64().max(0.);
+        self.score * 0.5f64.powf(elapsed / HALF_LIFE.as_seconds_f64())
+    }
+
+    pub(crate) fn record(&mut self, now: time::Utc, signal: &Signal) {
+        self.score = (self.score_at(now) + signal.delta()).clamp(MIN_SCORE, MAX_SCORE);
+        self.updated = now;
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
 use crate::blacklist::Blacklist;
+use crate::types::Reputation;
 use near_async::time;
 use near_crypto::{KeyType, SecretKey};
 use std::collections::HashSet;
@@ -66,8 +67,8 @@ fn test_unconnected_peer() {
         PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
             .unwrap();
 
-    assert!(peer_store.unconnected_peer(|_| false, false).is_some());
-    assert!(peer_store.unconnected_peer(|_| true, false).is_none());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false).is_some());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| true, |_| false, false).is_none());
 }
 
 #[test]
@@ -120,7 +121,10 @@ fn test_unknown_vs_not_connected() {
     // if we prefer 'previously connected' peers - we should keep picking 'b'.
     assert_eq!(
         (0..10)
-            .map(|_| peer_store.unconnected_peer(|_| false, true).unwrap().id)
+            .map(|_| peer_store
+                .unconnected_peer(&clock.clock(), |_| false, |_| false, true)
+                .unwrap()
+                .id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone()].into_iter().collect::<HashSet<_>>()
     );
@@ -128,7 +132,10 @@ fn test_unknown_vs_not_connected() {
     // if we don't care, we should pick either 'b' or 'boot'.
     assert_eq!(
         (0..100)
-            .map(|_| peer_store.unconnected_peer(|_| false, false).unwrap().id)
+            .map(|_| peer_store
+                .unconnected_peer(&clock.clock(), |_| false, |_| false, false)
+                .unwrap()
+                .id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone(), peer_info_boot_node.id.clone()]
             .into_iter()
@@ -164,7 +171,10 @@ fn test_unconnected_peer_only_boot_nodes() {
                 .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store.clone());
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_in_store.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false),
+            Some(peer_in_store.clone())
+        );
     }
 
     // 1 boot node (peer_info_a) that we're already connected to.
@@ -176,7 +186,7 @@ fn test_unconnected_peer_only_boot_nodes() {
                 .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store);
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+        assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false), None);
     }
 
     // 1 boot node (peer_info_a) is in the store.
@@ -188,10 +198,126 @@ fn test_unconnected_peer_only_boot_nodes() {
         )
         .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_info_a.clone());
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_a.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false),
+            Some(peer_info_a.clone())
+        );
     }
 }
 
+#[test]
+fn reputation_decays() {
+    let clock = time::FakeClock::default();
+    let mut reputation = Reputation::new(clock.now_utc());
+    reputation.record(clock.now_utc(), &reputation::Signal::Banned(ReasonForBan::Abusive));
+    assert_eq!(reputation.score_at(clock.now_utc()), -500.);
+    clock.advance(time::Duration::hours(1));
+    assert_eq!(reputation.score_at(clock.now_utc()), -250.);
+    // Scores are bounded, so that a long history of good behavior doesn't
+    // outweigh a ban.
+    for _ in 0..1000 {
+        let latency = time::Duration::milliseconds(100);
+        reputation.record(clock.now_utc(), &reputation::Signal::Response { latency });
+    }
+    assert_eq!(reputation.score_at(clock.now_utc()), 100.);
+}
+
+#[test]
+fn unconnected_peer_by_reputation() {
+    let clock = time::FakeClock::default();
+    let good = gen_peer_info(0);
+    let slow = gen_peer_info(1);
+    let banned = gen_peer_info(2);
+    let peer_store =
+        PeerStore::new(&clock.clock(), make_config(&[], Blacklist::default(), false)).unwrap();
+    for peer_info in [&good, &slow, &banned] {
+        peer_store.add_direct_peer(&clock.clock(), peer_info.clone());
+    }
+
+    let fast = reputation::Signal::Response { latency: time::Duration::milliseconds(100) };
+    peer_store.peer_reputation_signal(&clock.clock(), &good.id, fast);
+    let slow_response = reputation::Signal::Response { latency: time::Duration::seconds(10) };
+    peer_store.peer_reputation_signal(&clock.clock(), &slow.id, slow_response);
+    peer_store.peer_ban(&clock.clock(), &banned.id, ReasonForBan::Abusive).unwrap();
+    // Once the ban is over, the peer still has too low reputation to connect to.
+    clock.advance(time::Duration::seconds(2));
+    peer_store.update(&clock.clock());
+    assert_eq!(
+        peer_store.get_peer_state(&banned.id).unwrap().status,
+        KnownPeerStatus::NotConnected
+    );
+    assert!(peer_store.reputation_score(&clock.clock(), &banned.id) < -100.);
+
+    // With fewer candidates than the sample size, the best one is always selected.
+    for _ in 0..10 {
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false),
+            Some(good.clone())
+        );
+    }
+    assert_eq!(
+        peer_store.unconnected_peer(
+            &clock.clock(),
+            |p| p.peer_info.id == good.id,
+            |_| false,
+            false
+        ),
+        Some(slow.clone())
+    );
+    assert_eq!(
+        peer_store.unconnected_peer(
+            &clock.clock(),
+            |p| p.peer_info.id != banned.id,
+            |_| false,
+            false
+        ),
+        None
+    );
+    // Unless the peer is trusted.
+    assert_eq!(
+        peer_store.unconnected_peer(
+            &clock.clock(),
+            |p| p.peer_info.id != banned.id,
+            |p| p.peer_info.id == banned.id,
+            false
+        ),
+        Some(banned.clone())
+    );
+
+    // The penalty decays over time.
+    clock.advance(time::Duration::hours(3));
+    assert_eq!(
+        peer_store.unconnected_peer(
+            &clock.clock(),
+            |p| p.peer_info.id != banned.id,
+            |_| false,
+            false
+        ),
+        Some(banned)
+    );
+}
+
+#[test]
+fn unconnected_boot_node_by_reputation() {
+    let clock = time::FakeClock::default();
+    let boot_node = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[boot_node.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    peer_store.peer_ban(&clock.clock(), &boot_node.id, ReasonForBan::Abusive).unwrap();
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false), None);
+    // Boot nodes are connected to once their ban is over, regardless of their reputation.
+    clock.advance(time::Duration::seconds(2));
+    peer_store.update(&clock.clock());
+    assert!(peer_store.reputation_score(&clock.clock(), &boot_node.id) < -100.);
+    assert_eq!(
+        peer_store.unconnected_peer(&clock.clock(), |_| false, |_| false, false),
+        Some(boot_node)
+    );
+}
+
 fn check_exist(
     peer_store: &PeerStore,
     peer_id: &PeerId,
@@ -15,7 +15,7 @@ use crate::testonly::stream::Stream;
 use crate::types::Edge;
 use near_async::time;
 use near_o11y::testonly::init_test_logger;
-use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -107,6 +107,8 @@ async fn loop_connection() {
                 &pm.cfg.node_key,
             ),
             owned_account: None,
+            session_key: Some(data::make_session_key(rng, &pm.cfg.node_key, PROTOCOL_VERSION)),
+            accepted_compression: vec![],
         }))
         .await;
     let reason = events
@@ -128,6 +130,70 @@ async fn loop_connection() {
     );
 }
 
+#[tokio::test]
+async fn encryption_required() {
+    init_test_logger();
+    let mut rng = make_rng(921853234);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut cfg = chain.make_config(rng);
+    cfg.require_encryption = true;
+    let pm = peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg,
+        chain.clone(),
+    )
+    .await;
+
+    // A handshake at a protocol version without encryption should be rejected.
+    let version = std::cmp::min(
+        PROTOCOL_VERSION,
+        ProtocolFeature::EncryptedPeerTransport.protocol_version() - 1,
+    );
+    let cfg = chain.make_config(rng);
+    let stream = tcp::Stream::connect(&pm.peer_info(), tcp::Tier::T2, &SocketOptions::default())
+        .await
+        .unwrap();
+    let stream_id = stream.id();
+    let port = stream.local_addr.port();
+    let mut events = pm.events.from_now();
+    let mut stream = Stream::new(Some(Encoding::Proto), stream);
+    stream
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version: version,
+            oldest_supported_version: version,
+            sender_peer_id: cfg.node_id(),
+            target_peer_id: pm.cfg.node_id(),
+            sender_listen_port: Some(port),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: PartialEdgeInfo::new(
+                &cfg.node_id(),
+                &pm.cfg.node_id(),
+                Edge::create_fresh_nonce(&clock.clock()),
+                &cfg.node_key,
+            ),
+            owned_account: None,
+            session_key: None,
+            accepted_compression: vec![],
+        }))
+        .await;
+    let reason = events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::ConnectionClosed(ev)) if ev.stream_id == stream_id => {
+                Some(ev.reason)
+            }
+            Event::PeerManager(PME::HandshakeCompleted(ev)) if ev.stream_id == stream_id => {
+                panic!("PeerManager accepted the handshake")
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(ClosingReason::EncryptionRequired, reason);
+}
+
 #[tokio::test]
 async fn owned_account_mismatch() {
     init_test_logger();
@@ -175,8 +241,11 @@ async fn owned_account_mismatch() {
                     peer_id: data::make_peer_id(rng),
                     timestamp: clock.now_utc(),
                 }
-                .sign(&signer),
+                .sign(&signer)
+                .unwrap(),
             ),
+            session_key: Some(data::make_session_key(rng, &cfg.node_key, PROTOCOL_VERSION)),
+            accepted_compression: vec![],
         }))
         .await;
     let reason = events
@@ -297,8 +366,11 @@ async fn invalid_edge() {
                         peer_id: cfg.node_id(),
                         timestamp: clock.now_utc(),
                     }
-                    .sign(&signer),
+                    .sign(&signer)
+                    .unwrap(),
                 ),
+                session_key: Some(data::make_session_key(rng, &cfg.node_key, PROTOCOL_VERSION)),
+                accepted_compression: vec![],
             };
             let handshake = match tier {
                 tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
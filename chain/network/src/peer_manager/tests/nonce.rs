@@ -75,6 +75,8 @@ async fn test_nonces() {
             sender_chain_info: chain.get_peer_chain_info(),
             partial_edge_info: PartialEdgeInfo::new(&peer_id, &pm.cfg.node_id(), test.0, &peer_key),
             owned_account: None,
+            session_key: Some(data::make_session_key(rng, &peer_key, version::PROTOCOL_VERSION)),
+            accepted_compression: vec![],
         });
         stream.write(&handshake).await;
         if test.1 {
@@ -22,7 +22,7 @@ fn make_block_approval(rng: &mut Rng, signer: &ValidatorSigner) -> Approval {
     let inner = ApprovalInner::Endorsement(data::make_hash(rng));
     let target_height = rng.gen_range(0..100000);
     Approval {
-        signature: signer.sign_approval(&inner, target_height),
+        signature: signer.sign_approval(&inner, target_height).unwrap(),
         account_id: signer.validator_id().clone(),
         target_height,
         inner,
@@ -95,6 +95,26 @@ async fn send_and_recv_tier1_message(
     assert_eq!(want, got.body);
 }
 
+/// Constructs a config of a node using QUIC for TIER1 connections iff `quic` is set.
+fn make_config(rng: &mut Rng, chain: &data::Chain, quic: bool) -> config::NetworkConfig {
+    let mut cfg = chain.make_config(rng);
+    cfg.tier1.as_mut().unwrap().quic = quic;
+    cfg
+}
+
+/// Checks that all the TIER1 connections of `pms` use QUIC iff `quic` is set,
+/// i.e. that connecting over QUIC didn't fall back to TCP.
+async fn check_tier1_transport(pms: &[&peer_manager::testonly::ActorHandler], quic: bool) {
+    for pm in pms {
+        let ok = pm
+            .with_state(move |s| async move {
+                s.tier1.load().ready.values().all(|conn| conn.quic == quic)
+            })
+            .await;
+        assert!(ok, "{}: TIER1 connection with quic != {quic}", pm.cfg.node_id());
+    }
+}
+
 /// Send a message over each connection.
 async fn test_clique(
     rng: &mut Rng,
@@ -140,8 +160,7 @@ async fn first_proxy_advertisement() {
     );
 }
 
-#[tokio::test]
-async fn direct_connections() {
+async fn test_direct_connections(quic: bool) {
     init_test_logger();
     let mut rng = make_rng(921853233);
     let rng = &mut rng;
@@ -154,7 +173,7 @@ async fn direct_connections() {
             start_pm(
                 clock.clock(),
                 near_store::db::TestDB::new(),
-                chain.make_config(rng),
+                make_config(rng, &chain, quic),
                 chain.clone(),
             )
             .await,
@@ -177,15 +196,25 @@ async fn direct_connections() {
     }
     tracing::info!(target:"test", "Establish connections.");
     establish_connections(&clock.clock(), &pms[..]).await;
+    check_tier1_transport(&pms[..], quic).await;
     tracing::info!(target:"test", "Test clique.");
     test_clique(rng, &clock.clock(), &pms[..]).await;
 }
 
+#[tokio::test]
+async fn direct_connections() {
+    test_direct_connections(false).await;
+}
+
+#[tokio::test]
+async fn direct_connections_over_quic() {
+    test_direct_connections(true).await;
+}
+
 /// Test which spawns N validators, each with 1 proxy.
 /// All the nodes are connected in TIER2 star topology.
 /// Then all validators connect to the proxy of each other validator.
-#[tokio::test]
-async fn proxy_connections() {
+async fn test_proxy_connections(quic: bool) {
     init_test_logger();
     let mut rng = make_rng(921853233);
     let rng = &mut rng;
@@ -200,7 +229,7 @@ async fn proxy_connections() {
             start_pm(
                 clock.clock(),
                 near_store::db::TestDB::new(),
-                chain.make_config(rng),
+                make_config(rng, &chain, quic),
                 chain.clone(),
             )
             .await,
@@ -210,7 +239,7 @@ async fn proxy_connections() {
 
     let mut validators = vec![];
     for i in 0..N {
-        let mut cfg = chain.make_config(rng);
+        let mut cfg = make_config(rng, &chain, quic);
         cfg.validator.proxies = config::ValidatorProxies::Static(vec![PeerAddr {
             peer_id: proxies[i].cfg.node_id(),
             addr: **proxies[i].cfg.node_addr.as_ref().unwrap(),
@@ -224,7 +253,7 @@ async fn proxy_connections() {
     let hub = start_pm(
         clock.clock(),
         near_store::db::TestDB::new(),
-        chain.make_config(rng),
+        make_config(rng, &chain, quic),
         chain.clone(),
     )
     .await;
@@ -248,21 +277,35 @@ async fn proxy_connections() {
         pm.set_chain_info(chain_info.clone()).await;
     }
     establish_connections(&clock.clock(), &all[..]).await;
+    check_tier1_transport(&all[..], quic).await;
     test_clique(rng, &clock.clock(), &validators[..]).await;
 }
 
 #[tokio::test]
-async fn account_keys_change() {
+async fn proxy_connections() {
+    test_proxy_connections(false).await;
+}
+
+#[tokio::test]
+async fn proxy_connections_over_quic() {
+    test_proxy_connections(true).await;
+}
+
+async fn test_account_keys_change(quic: bool) {
     init_test_logger();
     let mut rng = make_rng(921853233);
     let rng = &mut rng;
     let mut clock = time::FakeClock::default();
     let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
 
-    let v0 = start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
-    let v1 = start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
-    let v2 = start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
-    let hub = start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
+    let v0 =
+        start_pm(clock.clock(), TestDB::new(), make_config(rng, &chain, quic), chain.clone()).await;
+    let v1 =
+        start_pm(clock.clock(), TestDB::new(), make_config(rng, &chain, quic), chain.clone()).await;
+    let v2 =
+        start_pm(clock.clock(), TestDB::new(), make_config(rng, &chain, quic), chain.clone()).await;
+    let hub =
+        start_pm(clock.clock(), TestDB::new(), make_config(rng, &chain, quic), chain.clone()).await;
     hub.connect_to(&v0.peer_info(), tcp::Tier::T2).await;
     hub.connect_to(&v1.peer_info(), tcp::Tier::T2).await;
     hub.connect_to(&v2.peer_info(), tcp::Tier::T2).await;
@@ -273,6 +316,7 @@ async fn account_keys_change() {
         pm.set_chain_info(chain_info.clone()).await;
     }
     establish_connections(&clock.clock(), &[&v0, &v1, &v2, &hub]).await;
+    check_tier1_transport(&[&v0, &v1, &v2, &hub], quic).await;
     test_clique(rng, &clock.clock(), &[&v0, &v1]).await;
 
     // TIER1 nodes in 2nd epoch are {v0,v2}.
@@ -281,6 +325,7 @@ async fn account_keys_change() {
         pm.set_chain_info(chain_info.clone()).await;
     }
     establish_connections(&clock.clock(), &[&v0, &v1, &v2, &hub]).await;
+    check_tier1_transport(&[&v0, &v1, &v2, &hub], quic).await;
     test_clique(rng, &clock.clock(), &[&v0, &v2]).await;
 
     drop(v0);
@@ -289,12 +334,21 @@ async fn account_keys_change() {
     drop(hub);
 }
 
+#[tokio::test]
+async fn account_keys_change() {
+    test_account_keys_change(false).await;
+}
+
+#[tokio::test]
+async fn account_keys_change_over_quic() {
+    test_account_keys_change(true).await;
+}
+
 // Let's say that a validator has 2 proxies configured. At first proxy0 is available and proxy1 is not,
 // then proxy1 is available and proxy0 is not. In both situations validator should be reachable,
 // as long as it manages to advertise the currently available proxy and the TIER1 nodes connect to
 // that proxy.
-#[tokio::test]
-async fn proxy_change() {
+async fn test_proxy_change(quic: bool) {
     init_test_logger();
     let mut rng = make_rng(921853233);
     let rng = &mut rng;
@@ -303,14 +357,14 @@ async fn proxy_change() {
 
     // v0 has proxies {p0,p1}
     // v1 has no proxies.
-    let p0cfg = chain.make_config(rng);
-    let p1cfg = chain.make_config(rng);
-    let mut v0cfg = chain.make_config(rng);
+    let p0cfg = make_config(rng, &chain, quic);
+    let p1cfg = make_config(rng, &chain, quic);
+    let mut v0cfg = make_config(rng, &chain, quic);
     v0cfg.validator.proxies = config::ValidatorProxies::Static(vec![
         PeerAddr { peer_id: p0cfg.node_id(), addr: **p0cfg.node_addr.as_ref().unwrap() },
         PeerAddr { peer_id: p1cfg.node_id(), addr: **p1cfg.node_addr.as_ref().unwrap() },
     ]);
-    let mut v1cfg = chain.make_config(rng);
+    let mut v1cfg = make_config(rng, &chain, quic);
     v1cfg.validator.proxies = config::ValidatorProxies::Static(vec![]);
 
     tracing::info!(target:"test", "Start all nodes.");
@@ -318,7 +372,8 @@ async fn proxy_change() {
     let p1 = start_pm(clock.clock(), TestDB::new(), p1cfg.clone(), chain.clone()).await;
     let v0 = start_pm(clock.clock(), TestDB::new(), v0cfg.clone(), chain.clone()).await;
     let v1 = start_pm(clock.clock(), TestDB::new(), v1cfg.clone(), chain.clone()).await;
-    let hub = start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
+    let hub =
+        start_pm(clock.clock(), TestDB::new(), make_config(rng, &chain, quic), chain.clone()).await;
     hub.connect_to(&p0.peer_info(), tcp::Tier::T2).await;
     hub.connect_to(&p1.peer_info(), tcp::Tier::T2).await;
     hub.connect_to(&v0.peer_info(), tcp::Tier::T2).await;
@@ -333,6 +388,7 @@ async fn proxy_change() {
     }
     tracing::info!(target:"test", "TIER1 connections get established: v0 -> p1 <- v1.");
     establish_connections(&clock.clock(), &[&v0, &v1, &p1, &hub]).await;
+    check_tier1_transport(&[&v0, &v1, &p1, &hub], quic).await;
     tracing::info!(target:"test", "Send message v1 -> v0 over TIER1.");
     send_and_recv_tier1_message(rng, &clock.clock(), &v1, &v0, tcp::Tier::T1).await;
 
@@ -347,6 +403,7 @@ async fn proxy_change() {
     hub.connect_to(&p0.peer_info(), tcp::Tier::T2).await;
     tracing::info!(target:"test", "TIER1 connections get established: v0 -> p0 <- v1.");
     establish_connections(&clock.clock(), &[&v0, &v1, &p0, &hub]).await;
+    check_tier1_transport(&[&v0, &v1, &p0, &hub], quic).await;
     tracing::info!(target:"test", "Send message v1 -> v0 over TIER1.");
     send_and_recv_tier1_message(rng, &clock.clock(), &v1, &v0, tcp::Tier::T1).await;
 
@@ -356,6 +413,16 @@ async fn proxy_change() {
     drop(p0);
 }
 
+#[tokio::test]
+async fn proxy_change() {
+    test_proxy_change(false).await;
+}
+
+#[tokio::test]
+async fn proxy_change_over_quic() {
+    test_proxy_change(true).await;
+}
+
 #[tokio::test]
 async fn tier2_routing_using_accounts_data() {
     init_test_logger();
@@ -0,0 +1,295 @@
+//! QUIC transport of TIER1 connections.
+//!
+//! Over TCP, latency-critical TIER1 messages like approvals and chunk parts
+//! queue up behind a large message (e.g. a state witness part) already in
+//! flight. Over QUIC, messages of every `StreamClass` are sent over a separate
+//! unidirectional stream, so that a large message delays only the messages of
+//! its own class.
+//!
+//! Nodes with `Tier1::quic` enabled accept QUIC connections on the UDP port of
+//! their `node_addr` and try QUIC first when connecting to TIER1 proxies,
+//! falling back to TCP. The handshake is exchanged over the connection the
+//! same way as over TCP.
+//!
+//! TLS certificates are self-signed and not verified. Instead, each side signs
+//! keying material exported from the TLS session with its node key and sends
+//! it as the `SessionKey` of its `Handshake`. A man in the middle would have to
+//! terminate two different TLS sessions, so the signatures it relays wouldn't
+//! match, and the peer is authenticated by its `PeerId` just like over TCP.
+use crate::network_protocol::{PeerInfo, PeerMessage, RoutedMessageBody, SessionKey};
+use crate::tcp;
+use anyhow::Context as _;
+use near_crypto::SecretKey;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+pub(crate) mod testonly;
+
+/// Identifies the scheme in the exported keying material and in the
+/// signatures of it.
+const PROTOCOL_NAME: &str = "near-tier1-quic-v1";
+/// Application protocol negotiated with ALPN.
+const ALPN: &[u8] = b"near-tier1";
+/// Name of the server in the (unverified) certificates.
+const SERVER_NAME: &str = "near-node";
+/// Same as for TCP connections, see `tcp::Stream::connect`.
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+/// Keeps idle connections from timing out.
+const KEEP_ALIVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Classes of messages sent over independent QUIC streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, enum_map::Enum, strum::FromRepr)]
+#[repr(u8)]
+pub(crate) enum StreamClass {
+    /// The handshake and all the messages not belonging to any other class.
+    Control,
+    /// Approvals and chunk endorsements.
+    Consensus,
+    /// Partial encoded chunks.
+    Chunks,
+    /// Parts of state witnesses, the largest TIER1 messages.
+    StateWitness,
+}
+
+impl StreamClass {
+    /// Priority of the stream of the class. When the congestion window is
+    /// full, data of streams with higher priority is sent first.
+    pub(crate) fn priority(self) -> i32 {
+        match self {
+            StreamClass::Consensus => 2,
+            StreamClass::Control | StreamClass::Chunks => 1,
+            StreamClass::StateWitness => 0,
+        }
+    }
+}
+
+impl PeerMessage {
+    /// Class of the QUIC stream the message is sent over.
+    pub(crate) fn stream_class(&self) -> StreamClass {
+        let PeerMessage::Routed(msg) = self else {
+            return StreamClass::Control;
+        };
+        match &msg.body {
+            RoutedMessageBody::BlockApproval(_)
+            | RoutedMessageBody::ChunkEndorsement(_)
+            | RoutedMessageBody::VersionedChunkEndorsement(_)
+            | RoutedMessageBody::ChunkStateWitnessAck(_) => StreamClass::Consensus,
+            RoutedMessageBody::PartialEncodedChunkRequest(_)
+            | RoutedMessageBody::PartialEncodedChunkResponse(_)
+            | RoutedMessageBody::VersionedPartialEncodedChunk(_)
+            | RoutedMessageBody::PartialEncodedChunkForward(_) => StreamClass::Chunks,
+            RoutedMessageBody::PartialEncodedStateWitness(_)
+            | RoutedMessageBody::PartialEncodedStateWitnessForward(_) => StreamClass::StateWitness,
+            _ => StreamClass::Control,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum AuthError {
+    #[error("session key is bound to a different TLS session")]
+    ChannelBindingMismatch,
+    #[error("session key is not signed by the node key of the peer")]
+    InvalidSignature,
+}
+
+/// Returns keying material exported from the TLS session of `conn`, which is
+/// the same on both ends of the connection.
+pub(crate) fn channel_binding(conn: &quinn::Connection) -> [u8; 32] {
+    let mut binding = [0; 32];
+    conn.export_keying_material(&mut binding, PROTOCOL_NAME.as_bytes(), &[])
+        .expect("32 bytes is a valid length of exported keying material");
+    binding
+}
+
+impl SessionKey {
+    fn quic_payload(binding: &[u8; 32]) -> CryptoHash {
+        CryptoHash::hash_borsh((PROTOCOL_NAME, binding))
+    }
+
+    /// Signs the channel binding of a QUIC connection with `node_key`.
+    pub(crate) fn new_quic(binding: [u8; 32], node_key: &SecretKey) -> Self {
+        let signature = node_key.sign(Self::quic_payload(&binding).as_ref());
+        Self { public_key: binding, signature }
+    }
+
+    /// Checks that the session key binds `peer_id` to the QUIC connection with
+    /// the channel binding `binding`.
+    pub(crate) fn verify_quic(
+        &self,
+        binding: &[u8; 32],
+        peer_id: &PeerId,
+    ) -> Result<(), AuthError> {
+        if &self.public_key != binding {
+            return Err(AuthError::ChannelBindingMismatch);
+        }
+        if !self.signature.verify(Self::quic_payload(binding).as_ref(), peer_id.public_key()) {
+            return Err(AuthError::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// Converts errors of QUIC connections to `io::Error`s of the same kinds as
+/// errors of closed TCP connections.
+pub(crate) fn io_error(err: quinn::ConnectionError) -> io::Error {
+    let kind = match err {
+        quinn::ConnectionError::TimedOut => io::ErrorKind::TimedOut,
+        _ => io::ErrorKind::ConnectionReset,
+    };
+    io::Error::new(kind, err)
+}
+
+/// Accepts any certificate of the server: peers are authenticated by the
+/// session keys in their handshakes instead.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn transport_config() -> Arc<quinn::TransportConfig> {
+    let mut config = quinn::TransportConfig::default();
+    // Each side opens a single stream per StreamClass.
+    config.max_concurrent_uni_streams((<StreamClass as enum_map::Enum>::LENGTH as u32).into());
+    config.max_concurrent_bidi_streams(0u32.into());
+    config.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    Arc::new(config)
+}
+
+fn stream(conn: quinn::Connection, type_: tcp::StreamType, local_addr: SocketAddr) -> tcp::Stream {
+    tcp::Stream {
+        peer_addr: conn.remote_address(),
+        local_addr,
+        transport: tcp::Transport::Quic(conn),
+        type_,
+    }
+}
+
+/// QUIC endpoint accepting and establishing TIER1 connections.
+pub(crate) struct Endpoint(quinn::Endpoint);
+
+impl Endpoint {
+    /// Binds the endpoint to the UDP port `addr`.
+    /// It has to be called within a tokio runtime.
+    pub fn new(addr: SocketAddr) -> anyhow::Result<Self> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])?;
+        let key = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+
+        let mut server_crypto = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .with_no_client_auth()
+            .with_single_cert(vec![CertificateDer::from(cert.cert)], key.into())?;
+        server_crypto.alpn_protocols = vec![ALPN.to_vec()];
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto)?,
+        ));
+        server_config.transport_config(transport_config());
+
+        let mut client_crypto = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+            .with_no_client_auth();
+        client_crypto.alpn_protocols = vec![ALPN.to_vec()];
+        let mut client_config = quinn::ClientConfig::new(Arc::new(
+            quinn::crypto::rustls::QuicClientConfig::try_from(client_crypto)?,
+        ));
+        client_config.transport_config(transport_config());
+
+        let mut endpoint =
+            quinn::Endpoint::server(server_config, addr).context("quinn::Endpoint::server()")?;
+        endpoint.set_default_client_config(client_config);
+        Ok(Self(endpoint))
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Establishes a TIER1 connection to `peer_info`.
+    pub async fn connect(&self, peer_info: &PeerInfo) -> anyhow::Result<tcp::Stream> {
+        let addr = peer_info.addr.context("Trying to connect to peer with no public address")?;
+        let conn = tokio::time::timeout(CONNECT_TIMEOUT, self.0.connect(addr, SERVER_NAME)?)
+            .await?
+            .context("quinn::Connecting")?;
+        Ok(stream(
+            conn,
+            tcp::StreamType::Outbound { peer_id: peer_info.id.clone(), tier: tcp::Tier::T1 },
+            self.local_addr()?,
+        ))
+    }
+
+    /// Waits for an incoming connection.
+    /// Returns `None` once the endpoint has been closed.
+    pub async fn accept(&self) -> Option<Incoming> {
+        let incoming = self.0.accept().await?;
+        Some(Incoming { incoming, local_addr: self.local_addr().ok()? })
+    }
+}
+
+/// Incoming connection, which hasn't completed the QUIC handshake yet.
+pub(crate) struct Incoming {
+    incoming: quinn::Incoming,
+    local_addr: SocketAddr,
+}
+
+impl Incoming {
+    /// Completes the QUIC handshake. It is separate from `Endpoint::accept`, so
+    /// that slow peers don't delay accepting other connections.
+    pub async fn accept(self) -> anyhow::Result<tcp::Stream> {
+        let conn = self.incoming.await.context("quinn::Incoming")?;
+        Ok(stream(conn, tcp::StreamType::Inbound, self.local_addr))
+    }
+}
@@ -0,0 +1,17 @@
+use crate::network_protocol::PeerInfo;
+use crate::quic::Endpoint;
+use crate::tcp;
+use near_primitives::network::PeerId;
+
+/// Establishes a loopback QUIC connection between two endpoints on localhost
+/// with random ports. Returns a pair of streams: (outbound,inbound).
+pub(crate) async fn loopback(peer_id: PeerId) -> (tcp::Stream, tcp::Stream) {
+    let server = Endpoint::new("[::1]:0".parse().unwrap()).unwrap();
+    let client = Endpoint::new("[::1]:0".parse().unwrap()).unwrap();
+    let peer_info =
+        PeerInfo { id: peer_id, addr: Some(server.local_addr().unwrap()), account_id: None };
+    let (outbound, inbound) = tokio::join!(client.connect(&peer_info), async {
+        server.accept().await.unwrap().accept().await
+    });
+    (outbound.unwrap(), inbound.unwrap())
+}
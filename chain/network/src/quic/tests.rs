@@ -0,0 +1,71 @@
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{PeerMessage, RoutedMessageBody, SessionKey};
+use crate::quic::{self, AuthError, StreamClass};
+use crate::tcp;
+use crate::testonly::make_rng;
+use crate::types::PartialEncodedChunkRequestMsg;
+use assert_matches::assert_matches;
+use near_primitives::block_header::{Approval, ApprovalInner};
+use near_primitives::network::PeerId;
+use near_primitives::validator_signer::ValidatorSigner;
+
+fn channel_binding(stream: &tcp::Stream) -> [u8; 32] {
+    match &stream.transport {
+        tcp::Transport::Quic(conn) => quic::channel_binding(conn),
+        tcp::Transport::Tcp(_) => panic!("not a QUIC stream"),
+    }
+}
+
+#[tokio::test]
+async fn session_keys() {
+    let mut rng = make_rng(4829572);
+    let outbound_key = data::make_secret_key(&mut rng);
+    let outbound_id = PeerId::new(outbound_key.public_key());
+    let (outbound, inbound) = quic::testonly::loopback(data::make_peer_id(&mut rng)).await;
+    let binding = channel_binding(&outbound);
+    assert_eq!(binding, channel_binding(&inbound));
+
+    let session_key = SessionKey::new_quic(binding, &outbound_key);
+    session_key.verify_quic(&channel_binding(&inbound), &outbound_id).unwrap();
+    assert_matches!(
+        session_key.verify_quic(&binding, &data::make_peer_id(&mut rng)),
+        Err(AuthError::InvalidSignature)
+    );
+
+    // A session key relayed from another connection doesn't verify.
+    let (other, _) = quic::testonly::loopback(data::make_peer_id(&mut rng)).await;
+    assert_ne!(binding, channel_binding(&other));
+    assert_matches!(
+        session_key.verify_quic(&channel_binding(&other), &outbound_id),
+        Err(AuthError::ChannelBindingMismatch)
+    );
+}
+
+#[test]
+fn stream_classes() {
+    let mut rng = make_rng(7234987);
+    let mut clock = near_async::time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 3);
+    let signer = ValidatorSigner::InMemory(data::make_validator_signer(&mut rng));
+    let inner = ApprovalInner::Endorsement(data::make_hash(&mut rng));
+    let approval = Approval {
+        signature: signer.sign_approval(&inner, 1).unwrap(),
+        account_id: signer.validator_id().clone(),
+        target_height: 1,
+        inner,
+    };
+    let mut routed = |body| {
+        PeerMessage::Routed(Box::new(data::make_routed_message(&mut rng, body))).stream_class()
+    };
+    assert_eq!(StreamClass::Consensus, routed(RoutedMessageBody::BlockApproval(approval)));
+    let chunk_hash = chain.blocks[1].chunks()[0].chunk_hash();
+    assert_eq!(
+        StreamClass::Chunks,
+        routed(RoutedMessageBody::PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg {
+            chunk_hash,
+            part_ords: vec![],
+            tracking_shards: Default::default(),
+        }))
+    );
+    assert_eq!(StreamClass::Control, PeerMessage::Block(chain.blocks[1].clone()).stream_class());
+}
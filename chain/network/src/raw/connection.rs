@@ -16,7 +16,7 @@ use near_primitives::block::{Block, BlockHeader, GenesisId};
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::{BlockHeight, ShardId};
-use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
+use near_primitives::version::{ProtocolFeature, ProtocolVersion, PROTOCOL_VERSION};
 use std::fmt;
 use std::io;
 use std::net::SocketAddr;
@@ -24,6 +24,17 @@ use std::num::NonZeroUsize;
 use time::ext::InstantExt as _;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// Newest protocol version used by default. Raw connections don't implement
+/// encryption of the transport, so they stay below the versions requiring it.
+const DEFAULT_PROTOCOL_VERSION: ProtocolVersion = {
+    let unencrypted = ProtocolFeature::EncryptedPeerTransport.protocol_version() - 1;
+    if PROTOCOL_VERSION < unencrypted {
+        PROTOCOL_VERSION
+    } else {
+        unencrypted
+    }
+};
+
 /// Represents a connection to a peer, and provides only minimal functionality.
 /// Almost none of the usual NEAR network logic is implemented, and the user
 /// will receive messages via the recv() function and send messages via
@@ -218,6 +229,8 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        session_key: None,
+        accepted_compression: vec![],
     })
 }
 
@@ -260,7 +273,7 @@ impl Connection {
         };
         peer.do_handshake(
             &clock,
-            my_protocol_version.unwrap_or(PROTOCOL_VERSION),
+            my_protocol_version.unwrap_or(DEFAULT_PROTOCOL_VERSION),
             chain_id,
             genesis_hash,
             head_height,
@@ -309,7 +322,7 @@ impl Connection {
             &peer_id,
             stream.stream.local_addr.port(),
             nonce,
-            PROTOCOL_VERSION,
+            DEFAULT_PROTOCOL_VERSION,
             chain_id,
             genesis_hash,
             head_height,
@@ -554,13 +567,13 @@ impl PeerStream {
         let mut msg = msg.serialize(Encoding::Proto);
         let mut buf = (msg.len() as u32).to_le_bytes().to_vec();
         buf.append(&mut msg);
-        self.stream.stream.write_all(&buf).await
+        self.stream.tcp().write_all(&buf).await
     }
 
     async fn do_read(&mut self) -> io::Result<()> {
         let n = tokio::time::timeout(
             self.recv_timeout.try_into().unwrap(),
-            self.stream.stream.read_buf(&mut self.buf),
+            self.stream.tcp().read_buf(&mut self.buf),
         )
         .await??;
         tracing::trace!(target: "network", "Read {} bytes from {:?}", n, self.stream.peer_addr);
@@ -0,0 +1,197 @@
+//! Recording of the network traffic of a node.
+//!
+//! When `NetworkConfig::record_traffic` is set, every `PeerMessage` exchanged with
+//! a peer is appended to a recording file in that directory, together with the time
+//! it was sent or received and the id of the peer. Messages exchanged before the
+//! peer has been identified in the handshake are not recorded.
+//!
+//! Every run of the node writes a new recording, named after the time the node
+//! started, so that restarting the node never overwrites an earlier recording.
+//! Each recording can be replayed on top of the database the node had when it
+//! started.
+//!
+//! The recording starts with `MAGIC`, followed by borsh-serialized records. Each
+//! record carries its message serialized with `Encoding::Proto`, so that records
+//! stay readable as long as the proto schema remains backward compatible.
+//! Recordings can be fed back into a fresh node with `tools/network-replay`.
+//!
+//! Records are written by a dedicated thread, so that recording never blocks the
+//! PeerActors on disk IO. If the writer falls behind by more than `QUEUE_CAPACITY`
+//! records, new records are dropped and counted in the
+//! `near_network_recorder_dropped_messages` metric.
+use crate::network_protocol::{Encoding, PeerMessage};
+use crate::stats::metrics;
+use anyhow::Context as _;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_async::time;
+use near_primitives::network::PeerId;
+use std::io::{BufRead, Read as _, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+#[cfg(test)]
+mod tests;
+
+/// Identifies the format of the recording.
+const MAGIC: &[u8] = b"near-network-recording-v1\n";
+/// Maximal number of records waiting to be written.
+const QUEUE_CAPACITY: usize = 10000;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Received from the peer.
+    Inbound,
+    /// Sent to the peer.
+    Outbound,
+}
+
+/// Record as stored in the recording.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct RawRecord {
+    timestamp_nanos: i128,
+    peer_id: PeerId,
+    direction: Direction,
+    message: Vec<u8>,
+}
+
+/// Message exchanged with a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub timestamp: time::Utc,
+    pub peer_id: PeerId,
+    pub direction: Direction,
+    pub message: PeerMessage,
+}
+
+/// Appends the messages exchanged with peers to a recording file.
+pub struct Recorder {
+    path: PathBuf,
+    sender: Option<mpsc::SyncSender<RawRecord>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Recorder {
+    /// Creates a new recording in `dir`, named after the current time.
+    /// Fails rather than overwriting an existing recording.
+    pub fn new(clock: &time::Clock, dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("create {dir:?}"))?;
+        let timestamp_millis = clock.now_utc().unix_timestamp_nanos() / 1_000_000;
+        let path = dir.join(format!("{timestamp_millis}.rec"));
+        let file = std::fs::File::create_new(&path).with_context(|| format!("create {path:?}"))?;
+        let mut file = std::io::BufWriter::new(file);
+        file.write_all(MAGIC).context("write header")?;
+        let (sender, receiver) = mpsc::sync_channel::<RawRecord>(QUEUE_CAPACITY);
+        let writer = std::thread::Builder::new()
+            .name("network-recorder".to_string())
+            .spawn(move || {
+                let result = (|| -> std::io::Result<()> {
+                    while let Ok(record) = receiver.recv() {
+                        borsh::to_writer(&mut file, &record)?;
+                        // Flush whenever the queue is drained, so that the recording
+                        // is complete up to the last message in case the node crashes.
+                        while let Ok(record) = receiver.try_recv() {
+                            borsh::to_writer(&mut file, &record)?;
+                        }
+                        file.flush()?;
+                    }
+                    Ok(())
+                })();
+                if let Err(err) = result {
+                    tracing::error!(target: "network", ?err, "Failed to write the traffic recording, recording stopped");
+                }
+            })
+            .context("spawn writer thread")?;
+        Ok(Self { path, sender: Some(sender), writer: Some(writer) })
+    }
+
+    /// Path of the recording.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records `msg` exchanged with `peer_id` at the current time.
+    pub fn record(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        direction: Direction,
+        msg: &PeerMessage,
+    ) {
+        let Some(sender) = &self.sender else { return };
+        let record = RawRecord {
+            timestamp_nanos: clock.now_utc().unix_timestamp_nanos(),
+            peer_id: peer_id.clone(),
+            direction,
+            message: msg.serialize(Encoding::Proto),
+        };
+        match sender.try_send(record) {
+            Ok(()) => {}
+            // The writer has stopped after an error, which has been logged already.
+            Err(mpsc::TrySendError::Disconnected(_)) => {}
+            Err(mpsc::TrySendError::Full(_)) => metrics::RECORDER_DROPPED_MESSAGES.inc(),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    /// Waits for the queued records to be written.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Reads the records of a recording in the order they were recorded in.
+pub struct Reader<R> {
+    reader: R,
+    failed: bool,
+}
+
+impl Reader<std::io::BufReader<std::fs::File>> {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("open {path:?}"))?;
+        Self::new(std::io::BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = vec![0; MAGIC.len()];
+        reader.read_exact(&mut magic).context("read header")?;
+        anyhow::ensure!(magic == MAGIC, "not a network traffic recording");
+        Ok(Self { reader, failed: false })
+    }
+
+    fn read_record(&mut self) -> anyhow::Result<Record> {
+        let raw = RawRecord::deserialize_reader(&mut self.reader).context("read record")?;
+        Ok(Record {
+            timestamp: time::Utc::from_unix_timestamp_nanos(raw.timestamp_nanos)
+                .context("timestamp")?,
+            peer_id: raw.peer_id,
+            direction: raw.direction,
+            message: PeerMessage::deserialize(Encoding::Proto, &raw.message)
+                .context("PeerMessage::deserialize()")?,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = anyhow::Result<Record>;
+
+    /// Returns `None` at the end of the recording. A record cut short, e.g. by
+    /// a crash of the node, is returned as an error, after which the iteration ends.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.reader.fill_buf() {
+            Ok(buf) if buf.is_empty() => return None,
+            Ok(_) => self.read_record(),
+            Err(err) => Err(err.into()),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
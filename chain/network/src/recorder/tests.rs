@@ -0,0 +1,93 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use rand::Rng as _;
+
+#[test]
+fn record_and_read() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let chain = data::Chain::make(&clock, rng, 3);
+    let peer_a = data::make_peer_id(rng);
+    let peer_b = data::make_peer_id(rng);
+    let dir = tempfile::tempdir().unwrap();
+
+    let messages = [
+        (peer_a.clone(), Direction::Outbound, PeerMessage::BlockRequest(*chain.blocks[2].hash())),
+        (peer_a, Direction::Inbound, PeerMessage::Block(chain.blocks[2].clone())),
+        (peer_b, Direction::Inbound, PeerMessage::Transaction(data::make_signed_transaction(rng))),
+    ];
+    let mut want = vec![];
+    let recorder = Recorder::new(&clock.clock(), dir.path()).unwrap();
+    let path = recorder.path().to_owned();
+    for (peer_id, direction, message) in messages {
+        clock.advance(time::Duration::milliseconds(rng.gen_range(1..1000)));
+        recorder.record(&clock.clock(), &peer_id, direction, &message);
+        want.push(Record { timestamp: clock.now_utc(), peer_id, direction, message });
+    }
+    drop(recorder);
+
+    let got: Vec<_> = Reader::open(&path).unwrap().collect::<anyhow::Result<_>>().unwrap();
+    assert_eq!(want, got);
+}
+
+#[test]
+fn read_truncated() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let peer_id = data::make_peer_id(rng);
+    let dir = tempfile::tempdir().unwrap();
+
+    let recorder = Recorder::new(&clock.clock(), dir.path()).unwrap();
+    let path = recorder.path().to_owned();
+    for _ in 0..2 {
+        let message = PeerMessage::Transaction(data::make_signed_transaction(rng));
+        recorder.record(&clock.clock(), &peer_id, Direction::Inbound, &message);
+    }
+    drop(recorder);
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 1).unwrap();
+
+    let mut reader = Reader::open(&path).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn recording_per_run() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let peer_id = data::make_peer_id(rng);
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut runs = vec![];
+    for _ in 0..2 {
+        let recorder = Recorder::new(&clock.clock(), dir.path()).unwrap();
+        let message = PeerMessage::Transaction(data::make_signed_transaction(rng));
+        recorder.record(&clock.clock(), &peer_id, Direction::Inbound, &message);
+        runs.push((recorder.path().to_owned(), message));
+        // Another recorder started at the same time must not overwrite the recording.
+        assert!(Recorder::new(&clock.clock(), dir.path()).is_err());
+        drop(recorder);
+        clock.advance(time::Duration::seconds(1));
+    }
+
+    assert_ne!(runs[0].0, runs[1].0);
+    for (path, message) in runs {
+        let got: Vec<_> = Reader::open(&path).unwrap().collect::<anyhow::Result<_>>().unwrap();
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].message, message);
+    }
+}
+
+#[test]
+fn not_a_recording() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("recording");
+    std::fs::write(&path, b"definitely not a network recording").unwrap();
+    assert!(Reader::open(&path).is_err());
+}
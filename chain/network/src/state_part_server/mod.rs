@@ -0,0 +1,390 @@
+//! Bandwidth shaping of state parts served to peers.
+//!
+//! Computing and sending state parts is expensive, and a node hosting a state
+//! snapshot may be asked for parts by many syncing peers at once. To keep them
+//! from starving the node's own block processing, every request for a part
+//! waits here until both the global byte budget and the byte budget of the
+//! requester allow serving it.
+//!
+//! Waiting requests form a priority queue by requester: requests of peers
+//! which have been served fewer bytes go first, so that a single greedy peer
+//! can't monopolize the server. Requests which don't fit into the queue or
+//! wait for too long are dropped. The requester will ask another host
+//! eventually, just like when a host doesn't respond at all.
+
+use crate::stats::metrics;
+use lru::LruCache;
+use near_async::time;
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::num::NonZeroUsize;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of peers for which budgets and served bytes are remembered.
+const PEERS_CACHE_SIZE: usize = 1024;
+
+/// Size of a response assumed before any part has been served.
+const INITIAL_RESPONSE_SIZE_ESTIMATE: i64 = bytesize::MIB as i64;
+
+/// How often waiting requests check whether budgets have been refilled.
+const DISPATCH_PERIOD: time::Duration = time::Duration::milliseconds(50);
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Bytes of state parts served per second to all peers together.
+    pub global_bytes_per_second: u64,
+    /// Bytes of state parts which may be served to all peers together at once
+    /// after a period of inactivity.
+    pub global_burst_bytes: u64,
+    /// Bytes of state parts served per second to a single peer.
+    pub per_peer_bytes_per_second: u64,
+    /// Bytes of state parts which may be served to a single peer at once after
+    /// a period of inactivity.
+    pub per_peer_burst_bytes: u64,
+    /// Maximum number of requests waiting for budget.  Further requests are
+    /// dropped.
+    pub max_queued_requests: usize,
+    /// Maximum number of requests of a single peer waiting for budget.
+    pub max_queued_requests_per_peer: usize,
+    /// Requests waiting for longer than this are dropped, as the requester
+    /// has likely given up on them already.
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub max_queue_time: time::Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            global_bytes_per_second: 50 * bytesize::MIB,
+            global_burst_bytes: 100 * bytesize::MIB,
+            per_peer_bytes_per_second: 10 * bytesize::MIB,
+            per_peer_burst_bytes: 20 * bytesize::MIB,
+            max_queued_requests: 200,
+            max_queued_requests_per_peer: 20,
+            max_queue_time: time::Duration::seconds(10),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.global_bytes_per_second == 0 || self.per_peer_bytes_per_second == 0 {
+            anyhow::bail!("bytes_per_second has to be positive");
+        }
+        if self.global_burst_bytes == 0 || self.per_peer_burst_bytes == 0 {
+            anyhow::bail!("burst_bytes has to be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Why a request for a state part wasn't served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::IntoStaticStr)]
+pub(crate) enum DropReason {
+    /// Too many requests are waiting for budget.
+    QueueFull,
+    /// Too many requests of the same peer are waiting for budget.
+    PeerQueueFull,
+    /// The request waited for budget for too long.
+    Expired,
+}
+
+/// Byte budget refilled at a constant rate.
+///
+/// Unlike with [`crate::rate_limits::token_bucket::TokenBucket`], the cost of
+/// a response is known only once it has been computed.  Requests are let
+/// through while there is some budget left and are charged afterwards, so the
+/// budget may go into debt, which delays the following requests.
+struct ByteBudget {
+    available: i64,
+    burst: i64,
+    bytes_per_second: u64,
+    last_refill: time::Instant,
+}
+
+impl ByteBudget {
+    fn new(burst: u64, bytes_per_second: u64, now: time::Instant) -> Self {
+        let burst = burst as i64;
+        Self { available: burst, burst, bytes_per_second, last_refill: now }
+    }
+
+    fn refill(&mut self, now: time::Instant) {
+        if now <= self.last_refill {
+            return;
+        }
+        let elapsed = (now - self.last_refill).as_seconds_f64();
+        let bytes = (elapsed * self.bytes_per_second as f64) as i64;
+        // Don't move `last_refill` if nothing was added, so that frequent
+        // refills don't lose fractions of bytes.
+        if bytes > 0 {
+            self.available = self.available.saturating_add(bytes).min(self.burst);
+            self.last_refill = now;
+        }
+    }
+
+    fn has_budget(&mut self, now: time::Instant) -> bool {
+        self.refill(now);
+        self.available > 0
+    }
+
+    fn charge(&mut self, bytes: i64) {
+        self.available -= bytes;
+    }
+}
+
+struct PeerState {
+    budget: ByteBudget,
+    served_bytes: u64,
+    queued: usize,
+}
+
+struct QueuedRequest {
+    /// Bytes served to the requester when the request was queued.  Requests of
+    /// peers which have been served less go first.
+    served_bytes: u64,
+    /// Order of arrival, to serve requests of the same priority in order.
+    seq: u64,
+    peer_id: PeerId,
+    queued_at: time::Instant,
+    /// Receives the number of bytes reserved for the request once it may be
+    /// served.
+    sender: tokio::sync::oneshot::Sender<i64>,
+}
+
+impl QueuedRequest {
+    fn key(&self) -> (Reverse<u64>, Reverse<u64>) {
+        (Reverse(self.served_bytes), Reverse(self.seq))
+    }
+}
+
+impl PartialEq for QueuedRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for QueuedRequest {}
+
+impl PartialOrd for QueuedRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedRequest {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+struct Inner {
+    config: Config,
+    global: ByteBudget,
+    peers: LruCache<PeerId, PeerState>,
+    queue: BinaryHeap<QueuedRequest>,
+    next_seq: u64,
+    /// Moving average of the size of served responses, reserved from the
+    /// budgets while a response is being computed.
+    response_size_estimate: i64,
+}
+
+impl Inner {
+    fn peer(&mut self, peer_id: &PeerId, now: time::Instant) -> &mut PeerState {
+        let config = &self.config;
+        self.peers.get_or_insert_mut(peer_id.clone(), || PeerState {
+            budget: ByteBudget::new(
+                config.per_peer_burst_bytes,
+                config.per_peer_bytes_per_second,
+                now,
+            ),
+            served_bytes: 0,
+            queued: 0,
+        })
+    }
+
+    fn enqueue(
+        &mut self,
+        peer_id: &PeerId,
+        now: time::Instant,
+    ) -> Result<tokio::sync::oneshot::Receiver<i64>, DropReason> {
+        if self.queue.len() >= self.config.max_queued_requests {
+            return Err(DropReason::QueueFull);
+        }
+        let max_queued_requests_per_peer = self.config.max_queued_requests_per_peer;
+        let peer = self.peer(peer_id, now);
+        if peer.queued >= max_queued_requests_per_peer {
+            return Err(DropReason::PeerQueueFull);
+        }
+        peer.queued += 1;
+        let served_bytes = peer.served_bytes;
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.queue.push(QueuedRequest {
+            served_bytes,
+            seq: self.next_seq,
+            peer_id: peer_id.clone(),
+            queued_at: now,
+            sender,
+        });
+        self.next_seq += 1;
+        Ok(receiver)
+    }
+
+    fn dequeued(&mut self, peer_id: &PeerId) {
+        if let Some(peer) = self.peers.peek_mut(peer_id) {
+            peer.queued = peer.queued.saturating_sub(1);
+        }
+    }
+
+    /// Lets through waiting requests in the order of priority, for as long as
+    /// there is budget for them, and drops requests which waited for too long.
+    fn dispatch(&mut self, now: time::Instant) {
+        let mut expired = vec![];
+        let max_queue_time = self.config.max_queue_time;
+        self.queue.retain(|request| {
+            let keep = now - request.queued_at <= max_queue_time && !request.sender.is_closed();
+            if !keep {
+                expired.push((request.peer_id.clone(), request.sender.is_closed()));
+            }
+            keep
+        });
+        for (peer_id, cancelled) in expired {
+            self.dequeued(&peer_id);
+            if !cancelled {
+                metrics::STATE_PART_SERVER_DROPPED_REQUESTS
+                    .with_label_values(&[DropReason::Expired.into()])
+                    .inc();
+            }
+        }
+
+        // Requests of peers which are out of budget are put back into the
+        // queue once the dispatching is done.
+        let mut skipped = vec![];
+        while self.global.has_budget(now) {
+            let Some(request) = self.queue.pop() else { break };
+            let reserved = self.response_size_estimate;
+            let peer = self.peer(&request.peer_id, now);
+            if !peer.budget.has_budget(now) {
+                skipped.push(request);
+                continue;
+            }
+            peer.queued = peer.queued.saturating_sub(1);
+            peer.budget.charge(reserved);
+            self.global.charge(reserved);
+            metrics::STATE_PART_SERVER_QUEUE_TIME
+                .observe((now - request.queued_at).as_seconds_f64());
+            if request.sender.send(reserved).is_err() {
+                // The requester is gone.
+                self.finish(&request.peer_id, reserved, None);
+            }
+        }
+        self.queue.extend(skipped);
+        metrics::STATE_PART_SERVER_QUEUED_REQUESTS.set(self.queue.len() as i64);
+    }
+
+    /// Replaces the reservation of a request with the actual size of its
+    /// response.  `response_bytes` is `None` if nothing was sent.
+    fn finish(&mut self, peer_id: &PeerId, reserved: i64, response_bytes: Option<u64>) {
+        let bytes = response_bytes.unwrap_or(0) as i64;
+        self.global.charge(bytes - reserved);
+        if let Some(peer) = self.peers.peek_mut(peer_id) {
+            peer.budget.charge(bytes - reserved);
+            peer.served_bytes += bytes as u64;
+        }
+        if let Some(bytes) = response_bytes {
+            self.response_size_estimate = (self.response_size_estimate * 9 + bytes as i64) / 10;
+            metrics::STATE_PART_SERVER_SERVED_REQUESTS.inc();
+            metrics::STATE_PART_SERVER_SERVED_BYTES.inc_by(bytes);
+        }
+    }
+}
+
+/// Admits requests for state parts according to the byte budgets, see the
+/// module documentation.
+pub(crate) struct StatePartServer {
+    inner: Mutex<Inner>,
+}
+
+impl StatePartServer {
+    pub fn new(config: Config, now: time::Instant) -> Self {
+        let global =
+            ByteBudget::new(config.global_burst_bytes, config.global_bytes_per_second, now);
+        Self {
+            inner: Mutex::new(Inner {
+                config,
+                global,
+                peers: LruCache::new(NonZeroUsize::new(PEERS_CACHE_SIZE).unwrap()),
+                queue: BinaryHeap::new(),
+                next_seq: 0,
+                response_size_estimate: INITIAL_RESPONSE_SIZE_ESTIMATE,
+            }),
+        }
+    }
+
+    /// Waits until a request of `peer_id` for a state part may be served.
+    /// The returned permit has to be finished with the size of the response.
+    pub async fn acquire(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+    ) -> Result<StatePartPermit<'_>, DropReason> {
+        let mut receiver = {
+            let mut inner = self.inner.lock();
+            let now = clock.now();
+            let receiver = inner.enqueue(peer_id, now);
+            if let Err(reason) = receiver {
+                metrics::STATE_PART_SERVER_DROPPED_REQUESTS
+                    .with_label_values(&[reason.into()])
+                    .inc();
+            }
+            inner.dispatch(now);
+            receiver?
+        };
+        loop {
+            tokio::select! {
+                reserved = &mut receiver => {
+                    return match reserved {
+                        Ok(reserved) => Ok(StatePartPermit {
+                            server: self,
+                            peer_id: peer_id.clone(),
+                            reserved,
+                            finished: false,
+                        }),
+                        Err(_) => Err(DropReason::Expired),
+                    };
+                }
+                _ = clock.sleep(DISPATCH_PERIOD) => self.inner.lock().dispatch(clock.now()),
+            }
+        }
+    }
+}
+
+/// Permission to serve a single state part.  Bytes reserved for it are
+/// returned to the budgets if it is dropped without being finished.
+pub(crate) struct StatePartPermit<'a> {
+    server: &'a StatePartServer,
+    peer_id: PeerId,
+    reserved: i64,
+    finished: bool,
+}
+
+impl StatePartPermit<'_> {
+    /// Charges the budgets with the size of the response sent to the peer, or
+    /// just releases the reservation if there is no response.
+    pub fn finish(mut self, response_bytes: Option<u64>) {
+        self.finished = true;
+        self.server.inner.lock().finish(&self.peer_id, self.reserved, response_bytes);
+    }
+}
+
+impl Drop for StatePartPermit<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.server.inner.lock().finish(&self.peer_id, self.reserved, None);
+        }
+    }
+}
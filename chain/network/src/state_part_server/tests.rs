@@ -0,0 +1,147 @@
+use crate::network_protocol::testonly as data;
+use crate::state_part_server::{Config, DropReason, StatePartServer};
+use crate::testonly::make_rng;
+use near_async::time;
+use tokio::sync::oneshot::error::TryRecvError;
+
+fn make_server(clock: &time::FakeClock, config: Config) -> StatePartServer {
+    let server = StatePartServer::new(config, clock.now());
+    // Reserve exactly the size of the responses served in the tests.
+    server.inner.lock().response_size_estimate = 1000;
+    server
+}
+
+#[test]
+fn test_global_budget_prioritizes_less_served_peers() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let server = make_server(
+        &clock,
+        Config {
+            global_bytes_per_second: 1000,
+            global_burst_bytes: 1000,
+            per_peer_bytes_per_second: 1_000_000,
+            per_peer_burst_bytes: 1_000_000,
+            ..Config::default()
+        },
+    );
+    let mut inner = server.inner.lock();
+    let a = data::make_peer_id(&mut rng);
+    let b = data::make_peer_id(&mut rng);
+
+    let mut a1 = inner.enqueue(&a, clock.now()).unwrap();
+    inner.dispatch(clock.now());
+    let reserved = a1.try_recv().unwrap();
+    inner.finish(&a, reserved, Some(1000));
+
+    // The global budget is used up by the first response.
+    let mut a2 = inner.enqueue(&a, clock.now()).unwrap();
+    let mut b1 = inner.enqueue(&b, clock.now()).unwrap();
+    inner.dispatch(clock.now());
+    assert_eq!(a2.try_recv(), Err(TryRecvError::Empty));
+    assert_eq!(b1.try_recv(), Err(TryRecvError::Empty));
+
+    // B hasn't been served anything yet, so it goes first.
+    clock.advance(time::Duration::seconds(1));
+    inner.dispatch(clock.now());
+    assert_eq!(a2.try_recv(), Err(TryRecvError::Empty));
+    let reserved = b1.try_recv().unwrap();
+    inner.finish(&b, reserved, Some(1000));
+
+    clock.advance(time::Duration::seconds(1));
+    inner.dispatch(clock.now());
+    a2.try_recv().unwrap();
+}
+
+#[test]
+fn test_per_peer_budget() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let server = make_server(
+        &clock,
+        Config { per_peer_bytes_per_second: 1000, per_peer_burst_bytes: 1000, ..Config::default() },
+    );
+    let mut inner = server.inner.lock();
+    let a = data::make_peer_id(&mut rng);
+    let b = data::make_peer_id(&mut rng);
+
+    let mut a1 = inner.enqueue(&a, clock.now()).unwrap();
+    inner.dispatch(clock.now());
+    let reserved = a1.try_recv().unwrap();
+    inner.finish(&a, reserved, Some(1000));
+
+    // A is out of budget, but it doesn't hold up B.
+    let mut a2 = inner.enqueue(&a, clock.now()).unwrap();
+    let mut b1 = inner.enqueue(&b, clock.now()).unwrap();
+    inner.dispatch(clock.now());
+    assert_eq!(a2.try_recv(), Err(TryRecvError::Empty));
+    b1.try_recv().unwrap();
+
+    clock.advance(time::Duration::milliseconds(500));
+    inner.dispatch(clock.now());
+    a2.try_recv().unwrap();
+}
+
+#[test]
+fn test_dropped_requests() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let server = make_server(
+        &clock,
+        Config {
+            global_bytes_per_second: 1000,
+            global_burst_bytes: 1,
+            max_queued_requests: 2,
+            max_queued_requests_per_peer: 1,
+            max_queue_time: time::Duration::seconds(5),
+            ..Config::default()
+        },
+    );
+    let mut inner = server.inner.lock();
+    let a = data::make_peer_id(&mut rng);
+    let b = data::make_peer_id(&mut rng);
+    let c = data::make_peer_id(&mut rng);
+
+    // Use up the global budget.
+    let mut a1 = inner.enqueue(&a, clock.now()).unwrap();
+    inner.dispatch(clock.now());
+    let reserved = a1.try_recv().unwrap();
+    inner.finish(&a, reserved, Some(10000));
+
+    let mut a2 = inner.enqueue(&a, clock.now()).unwrap();
+    assert_eq!(inner.enqueue(&a, clock.now()).unwrap_err(), DropReason::PeerQueueFull);
+    let b1 = inner.enqueue(&b, clock.now()).unwrap();
+    assert_eq!(inner.enqueue(&c, clock.now()).unwrap_err(), DropReason::QueueFull);
+
+    // Dropping the request frees its place in the queue.
+    drop(b1);
+    inner.dispatch(clock.now());
+    let mut c1 = inner.enqueue(&c, clock.now()).unwrap();
+
+    clock.advance(time::Duration::seconds(6));
+    inner.dispatch(clock.now());
+    assert_eq!(a2.try_recv(), Err(TryRecvError::Closed));
+    assert_eq!(c1.try_recv(), Err(TryRecvError::Closed));
+    assert!(inner.queue.is_empty());
+    inner.enqueue(&a, clock.now()).unwrap();
+}
+
+#[tokio::test]
+async fn test_unfinished_permit_returns_reservation() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let server = make_server(
+        &clock,
+        Config { global_bytes_per_second: 1000, global_burst_bytes: 1000, ..Config::default() },
+    );
+    let a = data::make_peer_id(&mut rng);
+
+    let permit = server.acquire(&clock.clock(), &a).await.unwrap();
+    assert_eq!(server.inner.lock().global.available, 0);
+    drop(permit);
+    assert_eq!(server.inner.lock().global.available, 1000);
+
+    let permit = server.acquire(&clock.clock(), &a).await.unwrap();
+    permit.finish(Some(400));
+    assert_eq!(server.inner.lock().global.available, 600);
+}
@@ -106,6 +106,7 @@ impl<M: prometheus::core::Metric> std::ops::Deref for MetricGuard<M> {
 }
 
 pub(crate) type IntGaugeGuard = MetricGuard<prometheus::IntGauge>;
+pub(crate) type HistogramGuard = MetricGuard<prometheus::Histogram>;
 
 pub static PEER_CONNECTIONS: LazyLock<Gauge<Connection>> =
     LazyLock::new(|| Gauge::new("near_peer_connections", "Number of connected peers").unwrap());
@@ -144,6 +145,22 @@ pub(crate) static PEER_DATA_SENT_BYTES: LazyLock<IntCounter> = LazyLock::new(||
     try_create_int_counter("near_peer_data_sent_bytes", "Total data sent to peers").unwrap()
 });
 
+pub(crate) static PEER_PLAINTEXT_FRAMES_DROPPED: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_peer_plaintext_frames_dropped",
+        "Number of plaintext frames dropped because they were received over an encrypted connection",
+    )
+    .unwrap()
+});
+
+pub(crate) static RECORDER_DROPPED_MESSAGES: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_network_recorder_dropped_messages",
+        "Number of messages missing from the traffic recording because its writer fell behind",
+    )
+    .unwrap()
+});
+
 pub(crate) static PEER_DATA_READ_BUFFER_SIZE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     try_create_int_gauge_vec(
         "near_peer_read_buffer_size",
@@ -186,6 +203,25 @@ pub(crate) static PEER_MESSAGE_SENT_BY_TYPE_BYTES: LazyLock<IntCounterVec> = Laz
     )
     .unwrap()
 });
+pub(crate) static PEER_MESSAGE_COMPRESSION_RAW_BYTES: LazyLock<IntCounterVec> = LazyLock::new(
+    || {
+        try_create_int_counter_vec(
+            "near_peer_message_compression_raw_bytes",
+            "Size of messages eligible for compression sent to peers, before compression, by message types",
+            &["type"],
+        )
+        .unwrap()
+    },
+);
+pub(crate) static PEER_MESSAGE_COMPRESSION_COMPRESSED_BYTES: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_peer_message_compression_compressed_bytes",
+            "Size of messages eligible for compression sent to peers, as sent, by message types",
+            &["type"],
+        )
+        .unwrap()
+    });
 pub(crate) static PEER_MESSAGE_SENT_BY_TYPE_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_peer_message_sent_by_type_total",
@@ -392,6 +428,49 @@ pub(crate) static NETWORK_ROUTED_MSG_DISTANCES: LazyLock<IntCounterVec> = LazyLo
     .unwrap()
 });
 
+pub(crate) static STATE_PART_SERVER_SERVED_REQUESTS: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_state_part_server_served_requests_total",
+        "Number of requests for state parts let through by the state part server",
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_PART_SERVER_SERVED_BYTES: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_state_part_server_served_bytes_total",
+        "Size of responses to requests for state parts",
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_PART_SERVER_DROPPED_REQUESTS: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_state_part_server_dropped_requests_total",
+            "Number of requests for state parts dropped because of bandwidth limits, by reason",
+            &["reason"],
+        )
+        .unwrap()
+    });
+
+pub(crate) static STATE_PART_SERVER_QUEUED_REQUESTS: LazyLock<IntGauge> = LazyLock::new(|| {
+    try_create_int_gauge(
+        "near_state_part_server_queued_requests",
+        "Number of requests for state parts waiting for bandwidth budget",
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_PART_SERVER_QUEUE_TIME: LazyLock<Histogram> = LazyLock::new(|| {
+    try_create_histogram_with_buckets(
+        "near_state_part_server_queue_time_seconds",
+        "Time requests for state parts wait for bandwidth budget",
+        exponential_buckets(0.001, 2.0, 15).unwrap(),
+    )
+    .unwrap()
+});
+
 /// Updated the prometheus metrics about the received routed message `msg`.
 /// `tier` indicates the network over which the message was transmitted.
 /// `fastest` indicates whether this message is the first copy of `msg` received -
@@ -34,9 +34,16 @@ pub(crate) enum StreamType {
     Outbound { peer_id: PeerId, tier: Tier },
 }
 
+#[derive(Debug)]
+pub(crate) enum Transport {
+    Tcp(tokio::net::TcpStream),
+    /// TIER1 connection over QUIC, see `crate::quic`.
+    Quic(quinn::Connection),
+}
+
 #[derive(Debug)]
 pub struct Stream {
-    pub(crate) stream: tokio::net::TcpStream,
+    pub(crate) transport: Transport,
     pub(crate) type_: StreamType,
     /// cached stream.local_addr()
     pub(crate) local_addr: std::net::SocketAddr,
@@ -80,7 +87,21 @@ impl Socket {
 
 impl Stream {
     fn new(stream: tokio::net::TcpStream, type_: StreamType) -> std::io::Result<Self> {
-        Ok(Self { peer_addr: stream.peer_addr()?, local_addr: stream.local_addr()?, stream, type_ })
+        Ok(Self {
+            peer_addr: stream.peer_addr()?,
+            local_addr: stream.local_addr()?,
+            transport: Transport::Tcp(stream),
+            type_,
+        })
+    }
+
+    /// Returns the underlying TCP stream.
+    /// Panics for QUIC connections, which are established only by `quic::Endpoint`.
+    pub(crate) fn tcp(&mut self) -> &mut tokio::net::TcpStream {
+        match &mut self.transport {
+            Transport::Tcp(stream) => stream,
+            Transport::Quic(_) => panic!("not a TCP stream"),
+        }
     }
 
     pub async fn connect(
@@ -6,8 +6,8 @@ pub use crate::network_protocol::{
 /// Exported types, which are part of network protocol.
 pub use crate::network_protocol::{
     Edge, PartialEdgeInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerInfo, SnapshotHostInfo, StateResponseInfo,
-    StateResponseInfoV1, StateResponseInfoV2,
+    PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerIdOrHash, PeerInfo, RoutedMessageBody,
+    SnapshotHostInfo, StateResponseInfo, StateResponseInfoV1, StateResponseInfoV2,
 };
 use crate::routing::routing_table_view::RoutingTableInfo;
 pub use crate::state_sync::StateSyncResponse;
@@ -106,6 +106,18 @@ pub struct KnownPeerState {
     // Last time we tried to connect to this peer.
     // This data is not persisted in storage.
     pub last_outbound_attempt: Option<(time::Utc, Result<(), String>)>,
+    /// Reputation based on the behavior of the peer.
+    /// This data is not persisted in storage.
+    pub reputation: Reputation,
+}
+
+/// Score of a peer, growing with timely responses and dropping with misbehavior.
+/// See `peer_manager::peer_store::reputation` for how it is computed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reputation {
+    /// Score as of `updated`. It decays towards 0 over time.
+    pub score: f64,
+    pub updated: time::Utc,
 }
 
 impl KnownPeerState {
@@ -116,6 +128,7 @@ impl KnownPeerState {
             first_seen: now,
             last_seen: now,
             last_outbound_attempt: None,
+            reputation: Reputation::new(now),
         }
     }
 }
@@ -353,6 +366,8 @@ pub struct ConnectedPeerInfo {
     pub peer_type: PeerType,
     /// Nonce used for the connection with the peer.
     pub nonce: u64,
+    /// Reputation score of the peer.
+    pub reputation: i64,
 }
 
 #[derive(Debug, Clone, actix::MessageResponse, PartialEq, Eq)]
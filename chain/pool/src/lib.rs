@@ -1,16 +1,22 @@
 use std::collections::btree_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
-use crate::types::{PoolKey, TransactionGroup, TransactionGroupIterator};
+use crate::types::{
+    EvictedTransaction, EvictionReason, PoolKey, TransactionGroup, TransactionGroupIterator,
+};
 
+use near_chain_configs::TransactionPoolEvictionConfig;
 use near_crypto::PublicKey;
 use near_o11y::metrics::prometheus::core::{AtomicI64, GenericGauge};
 use near_primitives::epoch_info::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Nonce};
 use std::ops::Bound;
 
+/// Number of the most recently evicted transactions kept for introspection.
+const MAX_RECENT_EVICTIONS: usize = 100;
+
 mod metrics;
 pub mod types;
 
@@ -30,25 +36,72 @@ pub struct TransactionPool {
     /// NOTE: It's more efficient on average to keep transactions unsorted and with potentially
     /// conflicting nonce than to create a BTreeMap for every transaction.
     transactions: BTreeMap<PoolKey, Vec<SignedTransaction>>,
-    /// Set of all hashes to quickly check if the given transaction is in the pool.
-    unique_transactions: HashSet<CryptoHash>,
+    /// All hashes to quickly check if the given transaction is in the pool, along with the
+    /// bookkeeping needed to evict the transaction. Ordered, so that the pool can be listed
+    /// page by page.
+    unique_transactions: BTreeMap<CryptoHash, TransactionInfo>,
+    /// Hashes of the transactions in the order they were inserted into the pool, keyed by
+    /// `TransactionInfo::insertion_index`.
+    insertion_order: BTreeMap<u64, CryptoHash>,
+    /// (priority fee, insertion index) of every transaction in the pool, in the order in which
+    /// they get evicted when the pool is full.
+    eviction_order: BTreeSet<(u64, u64)>,
+    /// Priority of every group in `transactions`, see `group_priority`.
+    group_priorities: HashMap<PoolKey, u64>,
+    /// (priority, key) of every group in `transactions`. The pool iterator takes the groups with
+    /// the highest priority first.
+    groups_by_priority: BTreeSet<(u64, PoolKey)>,
+    /// Insertion indices of the transactions of every signer in the pool.
+    signer_transactions: HashMap<AccountId, BTreeSet<u64>>,
+    /// Insertion index of the next transaction.
+    next_insertion_index: u64,
     /// A uniquely generated key seed to randomize PoolKey order.
     key_seed: RngSeed,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
     last_used_key: PoolKey,
+    /// The key after which the next check of invalid nonces starts.
+    nonce_check_cursor: PoolKey,
     /// If set, new transactions that bring the size of the pool over this limit will be rejected.
     total_transaction_size_limit: Option<u64>,
     /// Total size of transactions in the pool measured in bytes.
     total_transaction_size: u64,
+    /// Which transactions get evicted to make room for the new ones.
+    eviction_config: TransactionPoolEvictionConfig,
+    /// The most recently evicted transactions, the latest at the back.
+    recent_evictions: VecDeque<EvictedTransaction>,
+    /// Number of transactions evicted since the pool was created, by reason.
+    eviction_counts: HashMap<EvictionReason, u64>,
     /// Metrics tracked for transaction pool.
+    metrics_label: String,
     transaction_pool_count_metric: GenericGauge<AtomicI64>,
     transaction_pool_size_metric: GenericGauge<AtomicI64>,
 }
 
+struct TransactionInfo {
+    key: PoolKey,
+    signer_id: AccountId,
+    nonce: Nonce,
+    priority: u64,
+    size: u64,
+    insertion_index: u64,
+}
+
+/// Priority fee of the transaction. Transactions without one have the lowest priority.
+fn priority(tx: &SignedTransaction) -> u64 {
+    tx.transaction.priority_fee().unwrap_or(0)
+}
+
+/// Priority of a group of transactions: the priority of the transaction with the smallest nonce,
+/// as it has to be included into a chunk before the others.
+fn group_priority(transactions: &[SignedTransaction]) -> Option<u64> {
+    transactions.iter().min_by_key(|tx| tx.transaction.nonce()).map(priority)
+}
+
 impl TransactionPool {
     pub fn new(
         key_seed: RngSeed,
         total_transaction_size_limit: Option<u64>,
+        eviction_config: TransactionPoolEvictionConfig,
         metrics_label: &str,
     ) -> Self {
         let transaction_pool_count_metric =
@@ -62,10 +115,21 @@ impl TransactionPool {
         Self {
             key_seed,
             transactions: BTreeMap::new(),
-            unique_transactions: HashSet::new(),
+            unique_transactions: BTreeMap::new(),
+            insertion_order: BTreeMap::new(),
+            eviction_order: BTreeSet::new(),
+            group_priorities: HashMap::new(),
+            groups_by_priority: BTreeSet::new(),
+            signer_transactions: HashMap::new(),
+            next_insertion_index: 0,
             last_used_key: CryptoHash::default(),
+            nonce_check_cursor: CryptoHash::default(),
             total_transaction_size_limit,
             total_transaction_size: 0,
+            eviction_config,
+            recent_evictions: VecDeque::new(),
+            eviction_counts: HashMap::new(),
+            metrics_label: metrics_label.to_string(),
             transaction_pool_count_metric,
             transaction_pool_size_metric,
         }
@@ -79,36 +143,94 @@ impl TransactionPool {
     }
 
     /// Inserts a signed transaction that passed validation into the pool.
+    ///
+    /// Depending on the eviction config, transactions with a lower or equal priority fee may be
+    /// evicted to make room for it.
     #[must_use]
     pub fn insert_transaction(
         &mut self,
         signed_transaction: SignedTransaction,
     ) -> InsertTransactionResult {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
+        let tx_hash = signed_transaction.get_hash();
+        if self.unique_transactions.contains_key(&tx_hash) {
             // The hash of this transaction was already seen, skip it.
             return InsertTransactionResult::Duplicate;
         }
-        // We never expect the total size to go over `u64` during real operation as that would
-        // be more than 10^9 GiB of RAM consumed for transaction pool, so panicing here is intended
-        // to catch a logic error in estimation of transaction size.
-        let new_total_transaction_size = self
-            .total_transaction_size
-            .checked_add(signed_transaction.get_size())
-            .expect("Total transaction size is too large");
+        let tx_size = signed_transaction.get_size();
+        let tx_priority = priority(&signed_transaction);
+        // Transactions to evict if the pool is full, lowest priority and oldest first.
+        let mut pool_full_evictions = vec![];
         if let Some(limit) = self.total_transaction_size_limit {
+            // We never expect the total size to go over `u64` during real operation as that would
+            // be more than 10^9 GiB of RAM consumed for transaction pool, so panicing here is
+            // intended to catch a logic error in estimation of transaction size.
+            let new_total_transaction_size = self
+                .total_transaction_size
+                .checked_add(tx_size)
+                .expect("Total transaction size is too large");
             if new_total_transaction_size > limit {
-                return InsertTransactionResult::NoSpaceLeft;
+                if !self.eviction_config.evict_oldest {
+                    return InsertTransactionResult::NoSpaceLeft;
+                }
+                // Only transactions with a lower or equal priority fee make room for the new one.
+                let mut freed_size = 0;
+                for &(priority, insertion_index) in &self.eviction_order {
+                    if new_total_transaction_size - freed_size <= limit || priority > tx_priority {
+                        break;
+                    }
+                    let tx_hash = self.insertion_order[&insertion_index];
+                    freed_size += self.unique_transactions[&tx_hash].size;
+                    pool_full_evictions.push(tx_hash);
+                }
+                if new_total_transaction_size - freed_size > limit {
+                    return InsertTransactionResult::NoSpaceLeft;
+                }
             }
         }
 
         // At this point transaction is accepted to the pool.
-        self.total_transaction_size = new_total_transaction_size;
-        let signer_id = signed_transaction.transaction.signer_id();
-        let signer_public_key = signed_transaction.transaction.public_key();
-        self.transactions
-            .entry(self.key(signer_id, signer_public_key))
-            .or_insert_with(Vec::new)
-            .push(signed_transaction);
+        let signer_id = signed_transaction.transaction.signer_id().clone();
+        if let Some(max_transactions) = self.eviction_config.max_transactions_per_signer {
+            while let Some(oldest) = self
+                .signer_transactions
+                .get(&signer_id)
+                .filter(|indices| indices.len() >= max_transactions.max(1))
+                .and_then(|indices| indices.first().copied())
+            {
+                let oldest_hash = self.insertion_order[&oldest];
+                self.evict_transaction(&oldest_hash, EvictionReason::SignerLimit);
+            }
+        }
+        if let Some(limit) = self.total_transaction_size_limit {
+            // Transactions evicted over the signer limit may have already made enough room.
+            for tx_hash in pool_full_evictions {
+                if self.total_transaction_size + tx_size <= limit {
+                    break;
+                }
+                self.evict_transaction(&tx_hash, EvictionReason::PoolFull);
+            }
+        }
+
+        let key = self.key(&signer_id, signed_transaction.transaction.public_key());
+        let insertion_index = self.next_insertion_index;
+        self.next_insertion_index += 1;
+        self.insertion_order.insert(insertion_index, tx_hash);
+        self.eviction_order.insert((tx_priority, insertion_index));
+        self.signer_transactions.entry(signer_id.clone()).or_default().insert(insertion_index);
+        self.unique_transactions.insert(
+            tx_hash,
+            TransactionInfo {
+                key,
+                signer_id,
+                nonce: signed_transaction.transaction.nonce(),
+                priority: tx_priority,
+                size: tx_size,
+                insertion_index,
+            },
+        );
+        self.total_transaction_size += tx_size;
+        self.transactions.entry(key).or_insert_with(Vec::new).push(signed_transaction);
+        self.update_group_priority(key);
 
         self.transaction_pool_count_metric.inc();
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
@@ -130,16 +252,10 @@ impl TransactionPool {
         let mut grouped_transactions = HashMap::new();
         for tx in transactions {
             // If transaction is not present in the pool, skip it.
-            if !self.unique_transactions.remove(&tx.get_hash()) {
+            let Some(info) = self.forget_transaction(&tx.get_hash()) else {
                 continue;
-            }
-
-            let signer_id = tx.transaction.signer_id();
-            let signer_public_key = tx.transaction.public_key();
-            grouped_transactions
-                .entry(self.key(signer_id, signer_public_key))
-                .or_insert_with(HashSet::new)
-                .insert(tx.get_hash());
+            };
+            grouped_transactions.entry(info.key).or_insert_with(HashSet::new).insert(tx.get_hash());
         }
         for (key, hashes) in grouped_transactions {
             if let Entry::Occupied(mut entry) = self.transactions.entry(key) {
@@ -159,6 +275,7 @@ impl TransactionPool {
                     entry.remove_entry();
                 }
             }
+            self.update_group_priority(key);
         }
 
         // We can update metrics only once for the whole batch of transactions.
@@ -166,6 +283,172 @@ impl TransactionPool {
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
     }
 
+    /// Evicts transactions whose nonce is not greater than the current nonce of their access key,
+    /// as they can never be included into a chunk.
+    ///
+    /// `access_key_nonce` returns the current nonce of the given access key, if it exists. It is
+    /// called for at most `max_checks` (signer, public key) pairs present in the pool, starting
+    /// after the last pair checked by the previous call, so that consecutive calls go through
+    /// the whole pool.
+    pub fn evict_transactions_with_invalid_nonce(
+        &mut self,
+        max_checks: usize,
+        mut access_key_nonce: impl FnMut(&AccountId, &PublicKey) -> Option<Nonce>,
+    ) {
+        let mut to_evict = vec![];
+        let groups = self
+            .transactions
+            .range((Bound::Excluded(self.nonce_check_cursor), Bound::Unbounded))
+            .chain(self.transactions.range(..=self.nonce_check_cursor))
+            .take(max_checks);
+        let mut cursor = self.nonce_check_cursor;
+        for (key, transactions) in groups {
+            cursor = *key;
+            let Some(first) = transactions.first() else { continue };
+            let Some(nonce) =
+                access_key_nonce(first.transaction.signer_id(), first.transaction.public_key())
+            else {
+                continue;
+            };
+            to_evict.extend(
+                transactions
+                    .iter()
+                    .filter(|tx| tx.transaction.nonce() <= nonce)
+                    .map(|tx| tx.get_hash()),
+            );
+        }
+        self.nonce_check_cursor = cursor;
+        for tx_hash in to_evict {
+            self.evict_transaction(&tx_hash, EvictionReason::InvalidNonce);
+        }
+    }
+
+    /// Removes the transaction from the pool, recording why it happened.
+    fn evict_transaction(&mut self, tx_hash: &CryptoHash, reason: EvictionReason) {
+        let Some(info) = self.forget_transaction(tx_hash) else { return };
+        if let Entry::Occupied(mut entry) = self.transactions.entry(info.key) {
+            entry.get_mut().retain(|tx| &tx.get_hash() != tx_hash);
+            if entry.get().is_empty() {
+                entry.remove_entry();
+            }
+        }
+        self.update_group_priority(info.key);
+        // See the comment in `insert_transaction` where we increase the size for reasoning why
+        // panicing here catches a logic error.
+        self.total_transaction_size = self
+            .total_transaction_size
+            .checked_sub(info.size)
+            .expect("Total transaction size dropped below zero");
+
+        tracing::debug!(
+            target: "pool",
+            ?tx_hash,
+            signer_id = %info.signer_id,
+            nonce = info.nonce,
+            ?reason,
+            "Evicted transaction"
+        );
+        metrics::TRANSACTION_POOL_EVICTED
+            .with_label_values(&[&self.metrics_label, reason.as_str()])
+            .inc();
+        *self.eviction_counts.entry(reason).or_default() += 1;
+        if self.recent_evictions.len() == MAX_RECENT_EVICTIONS {
+            self.recent_evictions.pop_front();
+        }
+        self.recent_evictions.push_back(EvictedTransaction {
+            tx_hash: *tx_hash,
+            signer_id: info.signer_id,
+            nonce: info.nonce,
+            reason,
+        });
+        self.transaction_pool_count_metric.set(self.unique_transactions.len() as i64);
+        self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
+    }
+
+    /// Drops the bookkeeping of the transaction, but not the transaction itself.
+    fn forget_transaction(&mut self, tx_hash: &CryptoHash) -> Option<TransactionInfo> {
+        let info = self.unique_transactions.remove(tx_hash)?;
+        self.insertion_order.remove(&info.insertion_index);
+        self.eviction_order.remove(&(info.priority, info.insertion_index));
+        if let Some(indices) = self.signer_transactions.get_mut(&info.signer_id) {
+            indices.remove(&info.insertion_index);
+            if indices.is_empty() {
+                self.signer_transactions.remove(&info.signer_id);
+            }
+        }
+        Some(info)
+    }
+
+    /// Brings the priority of the group up to date with its transactions in `transactions`.
+    fn update_group_priority(&mut self, key: PoolKey) {
+        if let Some(old) = self.group_priorities.remove(&key) {
+            self.groups_by_priority.remove(&(old, key));
+        }
+        if let Some(new) = self.transactions.get(&key).and_then(|txs| group_priority(txs)) {
+            self.group_priorities.insert(key, new);
+            self.groups_by_priority.insert((new, key));
+        }
+    }
+
+    /// Returns the key of the group the pool iterator takes next: among the groups with the
+    /// highest priority, the first one after `last_used_key`, wrapping around.
+    fn next_group_key(&self) -> Option<PoolKey> {
+        let &(priority, _) = self.groups_by_priority.last()?;
+        let (_, key) = self
+            .groups_by_priority
+            .range((Bound::Excluded((priority, self.last_used_key)), Bound::Unbounded))
+            .next()
+            .or_else(|| self.groups_by_priority.range((priority, CryptoHash::default())..).next())
+            .expect("the group with the highest priority is in the range");
+        Some(*key)
+    }
+
+    /// Returns whether the transaction with the given hash is in the pool.
+    pub fn contains(&self, tx_hash: &CryptoHash) -> bool {
+        self.unique_transactions.contains_key(tx_hash)
+    }
+
+    /// Returns the transactions in the pool ordered by hash, starting right after
+    /// `after_tx_hash` if it is set.
+    pub fn transactions_by_hash(
+        &self,
+        after_tx_hash: Option<CryptoHash>,
+    ) -> impl Iterator<Item = &SignedTransaction> {
+        let start = after_tx_hash.map_or(Bound::Unbounded, Bound::Excluded);
+        self.unique_transactions
+            .range((start, Bound::Unbounded))
+            .filter_map(|(tx_hash, info)| self.get_transaction(tx_hash, info))
+    }
+
+    /// Returns the transactions of the given signer in the order they were inserted into the pool.
+    pub fn signer_transactions<'a>(
+        &'a self,
+        signer_id: &AccountId,
+    ) -> impl Iterator<Item = &'a SignedTransaction> + 'a {
+        self.signer_transactions.get(signer_id).into_iter().flatten().filter_map(|index| {
+            let tx_hash = self.insertion_order.get(index)?;
+            self.get_transaction(tx_hash, self.unique_transactions.get(tx_hash)?)
+        })
+    }
+
+    fn get_transaction(
+        &self,
+        tx_hash: &CryptoHash,
+        info: &TransactionInfo,
+    ) -> Option<&SignedTransaction> {
+        self.transactions.get(&info.key)?.iter().find(|tx| &tx.get_hash() == tx_hash)
+    }
+
+    /// Returns the most recently evicted transactions, the latest last.
+    pub fn recent_evictions(&self) -> impl Iterator<Item = &EvictedTransaction> {
+        self.recent_evictions.iter()
+    }
+
+    /// Returns the number of transactions evicted since the pool was created, by reason.
+    pub fn eviction_counts(&self) -> &HashMap<EvictionReason, u64> {
+        &self.eviction_counts
+    }
+
     /// Returns the number of unique transactions in the pool.
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
@@ -195,9 +478,10 @@ impl<'a> PoolIteratorWrapper<'a> {
 }
 
 /// The iterator works with the following algorithm:
-/// On next(), the iterator tries to get a transaction group from the pool, sorts transactions in
-/// it, and add it to the back of the sorted groups queue.
-/// Remembers the last used key, so it can continue from the next key.
+/// On next(), the iterator tries to get a transaction group with the highest priority from the
+/// pool, sorts transactions in it, and add it to the back of the sorted groups queue.
+/// Remembers the last used key, so it can continue from the next key among the groups with the
+/// same priority.
 ///
 /// If the pool is empty, the iterator gets the group from the front of the sorted groups queue.
 ///
@@ -213,23 +497,11 @@ impl<'a> PoolIteratorWrapper<'a> {
 /// And all non-empty group from the sorted groups queue are inserted back into the pool.
 impl<'a> TransactionGroupIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
-        if !self.pool.transactions.is_empty() {
-            let key = *self
-                .pool
-                .transactions
-                .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
-                .next()
-                .map(|(k, _v)| k)
-                .unwrap_or_else(|| {
-                    self.pool
-                        .transactions
-                        .keys()
-                        .next()
-                        .expect("we've just checked that the map is not empty")
-                });
+        if let Some(key) = self.pool.next_group_key() {
             self.pool.last_used_key = key;
             let mut transactions =
-                self.pool.transactions.remove(&key).expect("just checked existence");
+                self.pool.transactions.remove(&key).expect("groups are indexed by priority");
+            self.pool.update_group_priority(key);
             transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce()));
             self.sorted_groups.push_back(TransactionGroup {
                 key,
@@ -242,7 +514,7 @@ impl<'a> TransactionGroupIterator for PoolIteratorWrapper<'a> {
             while let Some(sorted_group) = self.sorted_groups.pop_front() {
                 if sorted_group.transactions.is_empty() {
                     for hash in sorted_group.removed_transaction_hashes {
-                        self.pool.unique_transactions.remove(&hash);
+                        self.pool.forget_transaction(&hash);
                     }
                     // See the comment in `insert_transaction` where we increase the size for reasoning
                     // why panicing here catches a logic error.
@@ -273,7 +545,7 @@ impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
         for group in self.sorted_groups.drain(..) {
             for hash in group.removed_transaction_hashes {
-                self.pool.unique_transactions.remove(&hash);
+                self.pool.forget_transaction(&hash);
             }
             // See the comment in `insert_transaction` where we increase the size for reasoning
             // why panicing here catches a logic error.
@@ -285,6 +557,7 @@ impl<'a> Drop for PoolIteratorWrapper<'a> {
 
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
+                self.pool.update_group_priority(group.key);
             }
         }
         // We can update metrics only once for the whole batch of transactions.
@@ -337,7 +610,7 @@ mod tests {
     use rand::seq::SliceRandom;
     use rand::thread_rng;
 
-    use near_crypto::{InMemorySigner, KeyType};
+    use near_crypto::{InMemorySigner, KeyType, Signer};
 
     use near_primitives::hash::CryptoHash;
     use near_primitives::types::Balance;
@@ -372,7 +645,7 @@ mod tests {
         mut transactions: Vec<SignedTransaction>,
         expected_weight: u32,
     ) -> (Vec<u64>, TransactionPool) {
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
         let mut rng = thread_rng();
         transactions.shuffle(&mut rng);
         for tx in transactions {
@@ -482,7 +755,7 @@ mod tests {
             })
             .collect::<Vec<_>>();
 
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
         let mut rng = thread_rng();
         transactions.shuffle(&mut rng);
         for tx in transactions.clone() {
@@ -596,7 +869,7 @@ mod tests {
 
     #[test]
     fn test_transaction_pool_size() {
-        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
         let transactions = generate_transactions("alice.near", "alice.near", 1, 100);
         let mut total_transaction_size = 0;
         // Adding transactions increases the size.
@@ -620,7 +893,8 @@ mod tests {
         // Each transaction is at least 1 byte in size, so the last transaction will not fit.
         let pool_size_limit =
             transactions.iter().map(|tx| tx.get_size()).sum::<u64>().checked_sub(1).unwrap();
-        let mut pool = TransactionPool::new(TEST_SEED, Some(pool_size_limit), "");
+        let mut pool =
+            TransactionPool::new(TEST_SEED, Some(pool_size_limit), Default::default(), "");
         for (i, tx) in transactions.iter().cloned().enumerate() {
             if i + 1 < transactions.len() {
                 assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
@@ -629,4 +903,210 @@ mod tests {
             }
         }
     }
+
+    fn pool_nonces(pool: &mut TransactionPool) -> Vec<u64> {
+        let mut nonces = prepare_transactions(pool, 1000)
+            .iter()
+            .map(|tx| tx.transaction.nonce())
+            .collect::<Vec<_>>();
+        nonces.sort();
+        nonces
+    }
+
+    #[test]
+    fn test_lookup_transactions() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
+        let alice_transactions = generate_transactions("alice.near", "alice.near", 1, 3);
+        let bob_transactions = generate_transactions("bob.near", "bob.near", 1, 2);
+        for tx in alice_transactions.iter().chain(&bob_transactions).cloned() {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        assert!(pool.contains(&alice_transactions[0].get_hash()));
+        let mut hashes = alice_transactions
+            .iter()
+            .chain(&bob_transactions)
+            .map(|tx| tx.get_hash())
+            .collect::<Vec<_>>();
+        hashes.sort();
+        assert_eq!(
+            pool.transactions_by_hash(None).map(|tx| tx.get_hash()).collect::<Vec<_>>(),
+            hashes
+        );
+        assert_eq!(
+            pool.transactions_by_hash(Some(hashes[1])).map(|tx| tx.get_hash()).collect::<Vec<_>>(),
+            hashes[2..]
+        );
+        assert_eq!(
+            pool.signer_transactions(&"alice.near".parse().unwrap())
+                .map(|tx| tx.get_hash())
+                .collect::<Vec<_>>(),
+            alice_transactions.iter().map(|tx| tx.get_hash()).collect::<Vec<_>>()
+        );
+        assert_eq!(pool.signer_transactions(&"carol.near".parse().unwrap()).count(), 0);
+
+        pool.remove_transactions(&alice_transactions[..1]);
+        assert!(!pool.contains(&alice_transactions[0].get_hash()));
+        assert_eq!(pool.signer_transactions(&"alice.near".parse().unwrap()).count(), 2);
+    }
+
+    #[test]
+    fn test_evict_oldest() {
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+        let tx_size = transactions[0].get_size();
+        let eviction_config =
+            TransactionPoolEvictionConfig { evict_oldest: true, ..Default::default() };
+        let mut pool = TransactionPool::new(TEST_SEED, Some(3 * tx_size), eviction_config, "");
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.transaction_size(), 3 * tx_size);
+        assert_eq!(pool.eviction_counts()[&EvictionReason::PoolFull], 7);
+        assert_eq!(
+            pool.recent_evictions().map(|tx| tx.nonce).collect::<Vec<_>>(),
+            (1..=7).collect::<Vec<_>>()
+        );
+        assert_eq!(pool_nonces(&mut pool), vec![8, 9, 10]);
+
+        // A transaction that would not fit even into the empty pool is rejected.
+        let mut pool = TransactionPool::new(
+            TEST_SEED,
+            Some(tx_size - 1),
+            TransactionPoolEvictionConfig { evict_oldest: true, ..Default::default() },
+            "",
+        );
+        let tx = generate_transactions("alice.near", "alice.near", 1, 1).pop().unwrap();
+        assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::NoSpaceLeft);
+    }
+
+    fn generate_transaction_with_priority(
+        signer_id: &str,
+        nonce: u64,
+        priority_fee: u64,
+    ) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer: Signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_str())
+                .into();
+        SignedTransaction::from_actions_v1(
+            nonce,
+            signer_id,
+            "bob.near".parse().unwrap(),
+            &signer,
+            vec![],
+            CryptoHash::default(),
+            priority_fee,
+        )
+    }
+
+    #[test]
+    fn test_pool_iterator_priority() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
+        let transactions = [
+            generate_transaction_with_priority("alice.near", 1, 10),
+            generate_transaction_with_priority("alice.near", 2, 30),
+            generate_transaction_with_priority("bob.near", 1, 20),
+            generate_transaction_with_priority("carol.near", 1, 0),
+        ];
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // The priority of a group is the priority of its transaction with the smallest nonce.
+        let order = prepare_transactions(&mut pool, 10)
+            .iter()
+            .map(|tx| (tx.transaction.signer_id().to_string(), tx.transaction.nonce()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![
+                ("bob.near".to_string(), 1),
+                ("alice.near".to_string(), 1),
+                ("carol.near".to_string(), 1),
+                ("alice.near".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evict_lowest_priority() {
+        let tx_size = generate_transaction_with_priority("alice.near", 1, 0).get_size();
+        let eviction_config =
+            TransactionPoolEvictionConfig { evict_oldest: true, ..Default::default() };
+        let mut pool = TransactionPool::new(TEST_SEED, Some(3 * tx_size), eviction_config, "");
+        let transactions = [
+            generate_transaction_with_priority("alice.near", 1, 20),
+            generate_transaction_with_priority("brian.near", 1, 10),
+            generate_transaction_with_priority("carol.near", 1, 10),
+        ];
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        // A transaction with a lower priority doesn't evict anything.
+        let low = generate_transaction_with_priority("david.near", 1, 5);
+        assert_eq!(pool.insert_transaction(low), InsertTransactionResult::NoSpaceLeft);
+        // The oldest of the transactions with the lowest priority is evicted first.
+        let high = generate_transaction_with_priority("david.near", 1, 15);
+        assert_eq!(pool.insert_transaction(high), InsertTransactionResult::Success);
+        let equal = generate_transaction_with_priority("erika.near", 1, 10);
+        assert_eq!(pool.insert_transaction(equal), InsertTransactionResult::Success);
+        assert_eq!(
+            pool.recent_evictions().map(|tx| tx.signer_id.as_str()).collect::<Vec<_>>(),
+            vec!["brian.near", "carol.near"]
+        );
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn test_evict_over_signer_limit() {
+        let eviction_config = TransactionPoolEvictionConfig {
+            max_transactions_per_signer: Some(2),
+            ..Default::default()
+        };
+        let mut pool = TransactionPool::new(TEST_SEED, None, eviction_config, "");
+        let transactions = [
+            generate_transactions("alice.near", "alice.near", 1, 3),
+            generate_transactions("bob.near", "bob.near", 11, 12),
+            // Same signer with a different access key.
+            generate_transactions("alice.near", "alice2.near", 21, 21),
+        ]
+        .concat();
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        assert_eq!(pool.len(), 4);
+        assert_eq!(pool.eviction_counts()[&EvictionReason::SignerLimit], 2);
+        assert_eq!(pool_nonces(&mut pool), vec![3, 11, 12, 21]);
+    }
+
+    #[test]
+    fn test_evict_invalid_nonces() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, Default::default(), "");
+        let transactions = [
+            generate_transactions("alice.near", "alice.near", 1, 5),
+            generate_transactions("bob.near", "bob.near", 1, 5),
+            generate_transactions("carol.near", "carol.near", 1, 5),
+        ]
+        .concat();
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        let mut checked = vec![];
+        let mut access_key_nonce = |account_id: &AccountId, _: &PublicKey| {
+            checked.push(account_id.clone());
+            match account_id.as_str() {
+                "alice.near" => Some(3),
+                "bob.near" => Some(10),
+                _ => None,
+            }
+        };
+        // Every call checks at most two access keys and continues where the previous one stopped.
+        pool.evict_transactions_with_invalid_nonce(2, &mut access_key_nonce);
+        pool.evict_transactions_with_invalid_nonce(2, &mut access_key_nonce);
+        assert_eq!(checked.len(), 4);
+        assert_eq!(checked.iter().collect::<HashSet<_>>().len(), 3);
+        assert_eq!(checked[0], checked[3]);
+        assert_eq!(pool.len(), 7);
+        assert_eq!(pool.eviction_counts()[&EvictionReason::InvalidNonce], 8);
+        assert_eq!(pool_nonces(&mut pool), vec![1, 2, 3, 4, 4, 5, 5]);
+    }
 }
@@ -1,4 +1,4 @@
-use near_o11y::metrics::IntGaugeVec;
+use near_o11y::metrics::{IntCounterVec, IntGaugeVec};
 use std::sync::LazyLock;
 
 pub static TRANSACTION_POOL_COUNT: LazyLock<IntGaugeVec> = LazyLock::new(|| {
@@ -18,3 +18,12 @@ pub static TRANSACTION_POOL_SIZE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     )
     .unwrap()
 });
+
+pub static TRANSACTION_POOL_EVICTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_transaction_pool_evicted_transactions_total",
+        "Number of transactions evicted from a given shard pool before being included into a chunk",
+        &["shard_id", "reason"],
+    )
+    .unwrap()
+});
@@ -1,9 +1,11 @@
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, Nonce};
 
 /// Trait acts like an iterator. It iterates over transactions groups by returning mutable
 /// references to them. Each transaction group implements a draining iterator to pull transactions.
-/// The order of the transaction groups is round robin scheduling.
+/// The order of the transaction groups is round robin scheduling, the groups whose next
+/// transaction has a higher priority fee going first.
 pub trait TransactionGroupIterator {
     fn next(&mut self) -> Option<&mut TransactionGroup>;
 }
@@ -12,6 +14,36 @@ pub trait TransactionGroupIterator {
 /// Used to randomize the order of the keys.
 pub(crate) type PoolKey = CryptoHash;
 
+/// Why a transaction was evicted from the pool before being included into a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionReason {
+    /// The pool ran out of space for a new transaction and this one was the oldest.
+    PoolFull,
+    /// The nonce of the transaction is not greater than the nonce of its access key.
+    InvalidNonce,
+    /// The signer had too many transactions in the pool and this one was the oldest.
+    SignerLimit,
+}
+
+impl EvictionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PoolFull => "pool_full",
+            Self::InvalidNonce => "invalid_nonce",
+            Self::SignerLimit => "signer_limit",
+        }
+    }
+}
+
+/// A transaction evicted from the pool.
+#[derive(Debug, Clone)]
+pub struct EvictedTransaction {
+    pub tx_hash: CryptoHash,
+    pub signer_id: AccountId,
+    pub nonce: Nonce,
+    pub reason: EvictionReason,
+}
+
 /// Represents a group of transactions with the same key.
 pub struct TransactionGroup {
     /// The key of the group.
@@ -510,18 +510,27 @@ async fn construction_derive(
     let public_key: near_crypto::PublicKey = (&public_key)
         .try_into()
         .map_err(|_| errors::ErrorKind::InvalidInput("Invalid PublicKey".to_string()))?;
-    let address = if let near_crypto::KeyType::ED25519 = public_key.key_type() {
-        hex::encode(public_key.key_data())
-    } else {
-        return Err(errors::ErrorKind::InvalidInput(
-            "Only Ed25519 keys are allowed for implicit accounts".to_string(),
-        )
-        .into());
+    let address = match &public_key {
+        near_crypto::PublicKey::ED25519(public_key) => {
+            near_primitives::utils::derive_near_implicit_account_id(public_key)
+        }
+        near_crypto::PublicKey::SECP256K1(public_key) => {
+            near_primitives::utils::derive_eth_implicit_account_id(public_key)
+        }
+        near_crypto::PublicKey::ETH(address) => {
+            near_primitives::utils::derive_eth_implicit_account_id_from_address(address)
+        }
+        near_crypto::PublicKey::WEBAUTHN(_) => {
+            return Err(errors::ErrorKind::InvalidInput(
+                "Only Ed25519 and Secp256k1 keys are allowed for implicit accounts".to_string(),
+            )
+            .into());
+        }
     };
 
     Ok(Json(models::ConstructionDeriveResponse {
         account_identifier: models::AccountIdentifier {
-            address: address.parse().unwrap(),
+            address: address.into(),
             sub_account: None,
             metadata: None,
         },
@@ -672,7 +681,7 @@ async fn construction_payloads(
         unsigned_transaction: unsigned_transaction.into(),
         payloads: vec![models::SigningPayload {
             account_identifier: signer_account_id.into(),
-            signature_type: Some(signer_public_access_key.key_type().into()),
+            signature_type: Some(signer_public_access_key.key_type().try_into()?),
             hex_bytes: transaction_hash.as_ref().to_owned().into(),
         }],
     }))
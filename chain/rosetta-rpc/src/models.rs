@@ -1234,6 +1234,12 @@ impl TryFrom<&PublicKey> for near_crypto::PublicKey {
             CurveType::Secp256k1 => {
                 near_crypto::PublicKey::SECP256K1((hex_bytes.as_ref() as &[u8]).try_into()?)
             }
+            CurveType::Secp256r1 => {
+                near_crypto::PublicKey::WEBAUTHN((hex_bytes.as_ref() as &[u8]).try_into()?)
+            }
+            CurveType::EthAddress => {
+                near_crypto::PublicKey::ETH((hex_bytes.as_ref() as &[u8]).try_into()?)
+            }
         })
     }
 }
@@ -1246,6 +1252,14 @@ pub(crate) enum CurveType {
     Edwards25519,
     /// SEC compressed - 33 bytes (<https://secg.org/sec1-v2.pdf#subsubsection.2.3.3>)
     Secp256k1,
+    /// SEC compressed - 33 bytes (<https://secg.org/sec1-v2.pdf#subsubsection.2.3.3>)
+    Secp256r1,
+    /// Last 20 bytes of the Keccak-256 hash of the uncompressed secp256k1
+    /// public key - 20 bytes. Not a curve defined by Rosetta: ETH keys are
+    /// Ethereum addresses rather than public keys and are signed with
+    /// `SignatureType::EcdsaRecovery`.
+    #[serde(rename = "eth_address")]
+    EthAddress,
 }
 
 impl From<near_crypto::KeyType> for CurveType {
@@ -1253,6 +1267,8 @@ impl From<near_crypto::KeyType> for CurveType {
         match key_type {
             near_crypto::KeyType::ED25519 => Self::Edwards25519,
             near_crypto::KeyType::SECP256K1 => Self::Secp256k1,
+            near_crypto::KeyType::WEBAUTHN => Self::Secp256r1,
+            near_crypto::KeyType::ETH => Self::EthAddress,
         }
     }
 }
@@ -1297,13 +1313,13 @@ impl TryFrom<&Signature> for near_crypto::Signature {
 pub(crate) enum SignatureType {
     /// `R (32-byte) || s (32-bytes)` - `64 bytes`
     Ed25519,
+    /// `r (32-bytes) || s (32-bytes) || v (1-byte)` - `65 bytes`
+    #[serde(rename = "ecdsa_recovery")]
+    EcdsaRecovery,
     /* Rosetta Spec also provides:
      *
      * /// `r (32-bytes) || s (32-bytes)` - `64 bytes`
      * ECDSA,
-     * /// `r (32-bytes) || s (32-bytes) || v (1-byte)` - `65 bytes`
-     * #[serde(rename = "ecdsa_recovery")]
-     * ECDSARecovery,
      * #[serde(rename = "schnorr_1")]
      * /// `r (32-bytes) || s (32-bytes)` - `64 bytes` (schnorr signature
      * /// implemented by Zilliqa where both `r` and `s` are scalars encoded as
@@ -1311,12 +1327,17 @@ pub(crate) enum SignatureType {
      * Schnorr1, */
 }
 
-impl From<near_crypto::KeyType> for SignatureType {
-    fn from(key_type: near_crypto::KeyType) -> Self {
+impl TryFrom<near_crypto::KeyType> for SignatureType {
+    type Error = crate::errors::ErrorKind;
+
+    fn try_from(key_type: near_crypto::KeyType) -> Result<Self, Self::Error> {
         match key_type {
-            near_crypto::KeyType::ED25519 => Self::Ed25519,
-            near_crypto::KeyType::SECP256K1 => {
-                unimplemented!("SECP256K1 keys are not implemented in Rosetta yet")
+            near_crypto::KeyType::ED25519 => Ok(Self::Ed25519),
+            near_crypto::KeyType::ETH => Ok(Self::EcdsaRecovery),
+            near_crypto::KeyType::SECP256K1 | near_crypto::KeyType::WEBAUTHN => {
+                Err(crate::errors::ErrorKind::InvalidInput(format!(
+                    "{key_type} signatures are not supported by Rosetta yet"
+                )))
             }
         }
     }
@@ -1326,6 +1347,7 @@ impl From<SignatureType> for near_crypto::KeyType {
     fn from(signature_type: SignatureType) -> Self {
         match signature_type {
             SignatureType::Ed25519 => Self::ED25519,
+            SignatureType::EcdsaRecovery => Self::ETH,
         }
     }
 }
@@ -1417,3 +1439,42 @@ pub(crate) struct FTMetadataResponse {
 pub(crate) struct FTAccountBalanceResponse {
     pub amount: u128,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eth_key_construction_round_trip() {
+        let secret_key = near_crypto::SecretKey::from_seed(near_crypto::KeyType::ETH, "test");
+        let public_key = secret_key.public_key();
+
+        // The key the client passes to `construction_payloads`.
+        let rosetta_public_key = PublicKey::from(&public_key);
+        assert_eq!(rosetta_public_key.curve_type, CurveType::EthAddress);
+        let signer_public_access_key =
+            near_crypto::PublicKey::try_from(&rosetta_public_key).unwrap();
+        assert_eq!(signer_public_access_key, public_key);
+        let signature_type = SignatureType::try_from(signer_public_access_key.key_type()).unwrap();
+        assert_eq!(signature_type, SignatureType::EcdsaRecovery);
+
+        // The signature the client passes to `construction_combine`.
+        let payload = near_primitives::hash::hash(b"transaction");
+        let near_crypto::Signature::ETH(signature) = secret_key.sign(payload.as_ref()) else {
+            panic!("expected an ETH signature");
+        };
+        let signature_bytes: [u8; 65] = signature.into();
+        let signature = Signature {
+            signing_payload: SigningPayload {
+                account_identifier: "test".parse().unwrap(),
+                hex_bytes: payload.as_ref().to_owned().into(),
+                signature_type: Some(signature_type),
+            },
+            public_key: rosetta_public_key,
+            signature_type,
+            hex_bytes: signature_bytes.to_vec().into(),
+        };
+        let signature = near_crypto::Signature::try_from(&signature).unwrap();
+        assert!(signature.verify(payload.as_ref(), &signer_public_access_key));
+    }
+}
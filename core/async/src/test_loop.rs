@@ -66,7 +66,7 @@ pub mod sender;
 
 use data::TestLoopData;
 use futures::{TestLoopAsyncComputationSpawner, TestLoopFututeSpawner};
-use near_time::{Clock, Duration, FakeClock};
+use near_time::{Clock, Duration, FakeClock, Utc};
 use pending_events_sender::{CallbackEvent, PendingEventsSender};
 use sender::TestLoopSender;
 use serde::Serialize;
@@ -186,6 +186,16 @@ struct EventEndLogOutput {
 
 impl TestLoopV2 {
     pub fn new() -> Self {
+        Self::new_with_clock(FakeClock::default())
+    }
+
+    /// Creates a test loop whose virtual time starts at `start`, e.g. to replay
+    /// events that happened at a known time.
+    pub fn new_with_start_time(start: Utc) -> Self {
+        Self::new_with_clock(FakeClock::new(start))
+    }
+
+    fn new_with_clock(clock: FakeClock) -> Self {
         let pending_events = Arc::new(Mutex::new(InFlightEvents {
             events: Vec::new(),
             event_loop_thread_id: std::thread::current().id(),
@@ -206,7 +216,7 @@ impl TestLoopV2 {
             pending_events_sender,
             next_event_index: 0,
             current_time: Duration::ZERO,
-            clock: FakeClock::default(),
+            clock,
             shutting_down,
         }
     }
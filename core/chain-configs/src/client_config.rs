@@ -2,6 +2,7 @@
 use crate::ExternalStorageLocation::GCS;
 use crate::MutableConfigValue;
 use bytesize::ByteSize;
+use near_crypto::PublicKey;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId,
 };
@@ -69,6 +70,38 @@ impl GCConfig {
     }
 }
 
+/// Configuration for the store checker, which continuously verifies
+/// consistency of the database on a running node.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StoreCheckerConfig {
+    /// Whether the store checker runs.  Disabled by default.
+    pub enabled: bool,
+
+    /// How often the store checker checks a random block and random flat
+    /// storage keys.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub check_period: Duration,
+
+    /// Number of random flat storage keys of every shard compared with the
+    /// trie at every check.
+    pub flat_storage_samples: usize,
+
+    /// Number of the most recent findings shown on the debug page.
+    pub max_recent_findings: usize,
+}
+
+impl Default for StoreCheckerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_period: Duration::seconds(10),
+            flat_storage_samples: 10,
+            max_recent_findings: 100,
+        }
+    }
+}
+
 fn default_num_concurrent_requests() -> u32 {
     DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL
 }
@@ -89,6 +122,11 @@ pub struct ExternalStorageConfig {
     /// to reduce the performance impact of state sync.
     #[serde(default = "default_num_concurrent_requests_during_catchup")]
     pub num_concurrent_requests_during_catchup: u32,
+    /// Public keys of the dumpers whose manifests of state parts are trusted.
+    /// If not empty, the manifest is required and every downloaded header and
+    /// part is checked against it before being applied.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_manifest_signers: Vec<PublicKey>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -125,6 +163,34 @@ pub struct DumpConfig {
     /// Location of a json file with credentials allowing write access to the bucket.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub credentials_file: Option<PathBuf>,
+    /// Location of a key file, in the same format as `node_key.json`, used to
+    /// sign manifests of the dumped state parts.  If not set, manifests are not
+    /// published.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_key_file: Option<PathBuf>,
+    /// Compression of the dumped headers and parts.  Nodes syncing from
+    /// external storage handle both compressed and uncompressed files.
+    #[serde(default)]
+    pub compression: StateFileCompression,
+}
+
+/// Compression of state headers and parts in external storage.  The codec is
+/// recorded in the file name, see [`StateFileCompression::extension`].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StateFileCompression {
+    #[default]
+    None,
+    Zstd,
+}
+
+impl StateFileCompression {
+    /// Suffix appended to names of files compressed with this codec.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StateFileCompression::None => "",
+            StateFileCompression::Zstd => ".zst",
+        }
+    }
 }
 
 /// Configures how to fetch state parts during state sync.
@@ -273,6 +339,7 @@ pub fn default_state_sync() -> Option<StateSyncConfig> {
             num_concurrent_requests: DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL,
             num_concurrent_requests_during_catchup:
                 DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL,
+            trusted_manifest_signers: vec![],
         }),
     })
 }
@@ -326,6 +393,37 @@ pub fn default_orphan_state_witness_max_size() -> ByteSize {
     ByteSize::mb(40)
 }
 
+/// Which transactions get evicted from the per-shard transaction pool before they are included
+/// into a chunk. All policies are disabled by default.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TransactionPoolEvictionConfig {
+    /// When the pool is full, evict the transactions with the lowest priority fee, the oldest
+    /// first, to make room for a new one instead of rejecting it. Transactions with a higher
+    /// priority fee than the new one are never evicted.
+    pub evict_oldest: bool,
+    /// After every block, evict transactions whose nonce is not greater than the nonce of their
+    /// access key, as they can never be included into a chunk.
+    pub evict_invalid_nonces: bool,
+    /// Maximum number of access keys looked up per shard after every block when evicting
+    /// transactions with invalid nonces. The next block continues where the previous one stopped.
+    pub invalid_nonce_checks_per_block: usize,
+    /// Maximum number of transactions of a single signer in the pool. When exceeded, the oldest
+    /// transaction of the signer is evicted.
+    pub max_transactions_per_signer: Option<usize>,
+}
+
+impl Default for TransactionPoolEvictionConfig {
+    fn default() -> Self {
+        Self {
+            evict_oldest: false,
+            evict_invalid_nonces: false,
+            invalid_nonce_checks_per_block: 100,
+            max_transactions_per_signer: None,
+        }
+    }
+}
+
 /// Config for the Chunk Distribution Network feature.
 /// This allows nodes to push and pull chunks from a central stream.
 /// The two benefits of this approach are: (1) less request/response traffic
@@ -410,6 +508,8 @@ pub struct ClientConfig {
     pub block_header_fetch_horizon: BlockHeightDelta,
     /// Garbage collection configuration.
     pub gc: GCConfig,
+    /// Store checker configuration.
+    pub store_checker: StoreCheckerConfig,
     /// Accounts that this client tracks.
     pub tracked_accounts: Vec<AccountId>,
     /// Track shards that should be tracked by given validator.
@@ -453,6 +553,8 @@ pub struct ClientConfig {
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
     /// will be unbounded.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Which transactions get evicted from the transaction pool.
+    pub transaction_pool_eviction: TransactionPoolEvictionConfig,
     // Allows more detailed logging, for example a list of orphaned blocks.
     pub enable_multiline_logging: bool,
     // Configuration for resharding.
@@ -540,6 +642,7 @@ impl ClientConfig {
             doosmslug_step_period: Duration::milliseconds(100),
             block_header_fetch_horizon: 50,
             gc: GCConfig { gc_blocks_limit: 100, ..GCConfig::default() },
+            store_checker: StoreCheckerConfig::default(),
             tracked_accounts: vec![],
             tracked_shadow_validator: None,
             tracked_shards: vec![],
@@ -558,6 +661,7 @@ impl ClientConfig {
             state_sync_enabled,
             state_sync: StateSyncConfig::default(),
             transaction_pool_size_limit: None,
+            transaction_pool_eviction: TransactionPoolEvictionConfig::default(),
             enable_multiline_logging: false,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
@@ -4,10 +4,11 @@
 pub use errors::{ParseKeyError, ParseKeyTypeError, ParseSignatureError};
 pub use key_file::KeyFile;
 pub use signature::{
-    ED25519PublicKey, ED25519SecretKey, KeyType, PublicKey, Secp256K1PublicKey, Secp256K1Signature,
-    SecretKey, Signature,
+    ED25519PublicKey, ED25519SecretKey, EthPublicKey, KeyType, PublicKey, Secp256K1PublicKey,
+    Secp256K1Signature, SecretKey, Signature,
 };
 pub use signer::{EmptySigner, InMemorySigner, Signer};
+pub use webauthn::{WebAuthnPublicKey, WebAuthnSecretKey, WebAuthnSignature};
 
 #[macro_use]
 mod hash;
@@ -23,3 +24,4 @@ mod signature;
 mod signer;
 mod test_utils;
 pub mod vrf;
+mod webauthn;
@@ -11,6 +11,8 @@ use std::io::{Error, ErrorKind, Read, Write};
 use std::str::FromStr;
 use std::sync::LazyLock;
 
+use crate::webauthn::{self, WebAuthnPublicKey, WebAuthnSecretKey, WebAuthnSignature};
+
 pub static SECP256K1: LazyLock<secp256k1::Secp256k1<secp256k1::All>> =
     LazyLock::new(secp256k1::Secp256k1::new);
 
@@ -19,6 +21,8 @@ pub static SECP256K1: LazyLock<secp256k1::Secp256k1<secp256k1::All>> =
 pub enum KeyType {
     ED25519 = 0,
     SECP256K1 = 1,
+    ETH = 2,
+    WEBAUTHN = 3,
 }
 
 impl Display for KeyType {
@@ -26,6 +30,8 @@ impl Display for KeyType {
         f.write_str(match self {
             KeyType::ED25519 => "ed25519",
             KeyType::SECP256K1 => "secp256k1",
+            KeyType::ETH => "eth",
+            KeyType::WEBAUTHN => "webauthn",
         })
     }
 }
@@ -38,6 +44,8 @@ impl FromStr for KeyType {
         match lowercase_key_type.as_str() {
             "ed25519" => Ok(KeyType::ED25519),
             "secp256k1" => Ok(KeyType::SECP256K1),
+            "eth" => Ok(KeyType::ETH),
+            "webauthn" => Ok(KeyType::WEBAUTHN),
             _ => Err(Self::Err::UnknownKeyType { unknown_key_type: lowercase_key_type }),
         }
     }
@@ -50,6 +58,8 @@ impl TryFrom<u8> for KeyType {
         match value {
             0 => Ok(KeyType::ED25519),
             1 => Ok(KeyType::SECP256K1),
+            2 => Ok(KeyType::ETH),
+            3 => Ok(KeyType::WEBAUTHN),
             unknown_key_type => {
                 Err(Self::Error::UnknownKeyType { unknown_key_type: unknown_key_type.to_string() })
             }
@@ -114,6 +124,45 @@ impl std::fmt::Debug for ED25519PublicKey {
     }
 }
 
+const ETH_ADDRESS_LENGTH: usize = 20;
+
+/// Ethereum address, i.e. the last 20 bytes of the Keccak-256 hash of an
+/// uncompressed secp256k1 public key.
+///
+/// The public key itself is not known, so signatures are verified by
+/// recovering the public key from the signature and comparing its address.
+#[derive(
+    Clone, Eq, Ord, PartialEq, PartialOrd, derive_more::AsRef, derive_more::From, ProtocolSchema,
+)]
+#[cfg_attr(test, derive(bolero::TypeGenerator))]
+#[as_ref(forward)]
+pub struct EthPublicKey(pub [u8; ETH_ADDRESS_LENGTH]);
+
+impl TryFrom<&[u8]> for EthPublicKey {
+    type Error = crate::errors::ParseKeyError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        data.try_into().map(Self).map_err(|_| Self::Error::InvalidLength {
+            expected_length: ETH_ADDRESS_LENGTH,
+            received_length: data.len(),
+        })
+    }
+}
+
+impl From<&Secp256K1PublicKey> for EthPublicKey {
+    fn from(public_key: &Secp256K1PublicKey) -> Self {
+        use sha3::Digest;
+        let hash = sha3::Keccak256::digest(&public_key.0);
+        Self(hash[32 - ETH_ADDRESS_LENGTH..].try_into().unwrap())
+    }
+}
+
+impl std::fmt::Debug for EthPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        Display::fmt(&PrefixedHex(&self.0), f)
+    }
+}
+
 /// Public key container supporting different curves.
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq, ProtocolSchema)]
 #[cfg_attr(test, derive(bolero::TypeGenerator))]
@@ -122,6 +171,10 @@ pub enum PublicKey {
     ED25519(ED25519PublicKey),
     /// 512 bit elliptic curve based public-key used in Bitcoin's public-key cryptography.
     SECP256K1(Secp256K1PublicKey),
+    /// Ethereum address derived from a secp256k1 public key.
+    ETH(EthPublicKey),
+    /// P-256 public key of a WebAuthn credential (passkey).
+    WEBAUTHN(WebAuthnPublicKey),
 }
 
 impl PublicKey {
@@ -132,6 +185,8 @@ impl PublicKey {
         match self {
             Self::ED25519(_) => ED25519_LEN,
             Self::SECP256K1(_) => 65,
+            Self::ETH(_) => ETH_ADDRESS_LENGTH + 1,
+            Self::WEBAUTHN(_) => webauthn::PUBLIC_KEY_LENGTH + 1,
         }
     }
 
@@ -141,6 +196,10 @@ impl PublicKey {
                 PublicKey::ED25519(ED25519PublicKey([0u8; ed25519_dalek::PUBLIC_KEY_LENGTH]))
             }
             KeyType::SECP256K1 => PublicKey::SECP256K1(Secp256K1PublicKey([0u8; 64])),
+            KeyType::ETH => PublicKey::ETH(EthPublicKey([0u8; ETH_ADDRESS_LENGTH])),
+            KeyType::WEBAUTHN => {
+                PublicKey::WEBAUTHN(WebAuthnPublicKey([0u8; webauthn::PUBLIC_KEY_LENGTH]))
+            }
         }
     }
 
@@ -148,6 +207,8 @@ impl PublicKey {
         match self {
             Self::ED25519(_) => KeyType::ED25519,
             Self::SECP256K1(_) => KeyType::SECP256K1,
+            Self::ETH(_) => KeyType::ETH,
+            Self::WEBAUTHN(_) => KeyType::WEBAUTHN,
         }
     }
 
@@ -155,20 +216,29 @@ impl PublicKey {
         match self {
             Self::ED25519(key) => key.as_ref(),
             Self::SECP256K1(key) => key.as_ref(),
+            Self::ETH(key) => key.as_ref(),
+            Self::WEBAUTHN(key) => key.as_ref(),
         }
     }
 
     pub fn unwrap_as_ed25519(&self) -> &ED25519PublicKey {
         match self {
             Self::ED25519(key) => key,
-            Self::SECP256K1(_) => panic!(),
+            _ => panic!(),
         }
     }
 
     pub fn unwrap_as_secp256k1(&self) -> &Secp256K1PublicKey {
         match self {
             Self::SECP256K1(key) => key,
-            Self::ED25519(_) => panic!(),
+            _ => panic!(),
+        }
+    }
+
+    pub fn unwrap_as_eth(&self) -> &EthPublicKey {
+        match self {
+            Self::ETH(key) => key,
+            _ => panic!(),
         }
     }
 }
@@ -186,6 +256,14 @@ impl Hash for PublicKey {
                 state.write_u8(1u8);
                 state.write(&public_key.0);
             }
+            PublicKey::ETH(public_key) => {
+                state.write_u8(2u8);
+                state.write(&public_key.0);
+            }
+            PublicKey::WEBAUTHN(public_key) => {
+                state.write_u8(3u8);
+                state.write(&public_key.0);
+            }
         }
     }
 }
@@ -195,6 +273,10 @@ impl Display for PublicKey {
         let (key_type, key_data) = match self {
             PublicKey::ED25519(public_key) => (KeyType::ED25519, &public_key.0[..]),
             PublicKey::SECP256K1(public_key) => (KeyType::SECP256K1, &public_key.0[..]),
+            PublicKey::ETH(public_key) => {
+                return write!(fmt, "{}:{}", KeyType::ETH, PrefixedHex(&public_key.0));
+            }
+            PublicKey::WEBAUTHN(public_key) => (KeyType::WEBAUTHN, &public_key.0[..]),
         };
         write!(fmt, "{}:{}", key_type, Bs58(key_data))
     }
@@ -217,6 +299,14 @@ impl BorshSerialize for PublicKey {
                 BorshSerialize::serialize(&1u8, writer)?;
                 writer.write_all(&public_key.0)?;
             }
+            PublicKey::ETH(public_key) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                writer.write_all(&public_key.0)?;
+            }
+            PublicKey::WEBAUTHN(public_key) => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                writer.write_all(&public_key.0)?;
+            }
         }
         Ok(())
     }
@@ -233,6 +323,12 @@ impl BorshDeserialize for PublicKey {
             KeyType::SECP256K1 => Ok(PublicKey::SECP256K1(Secp256K1PublicKey(
                 BorshDeserialize::deserialize_reader(rd)?,
             ))),
+            KeyType::ETH => {
+                Ok(PublicKey::ETH(EthPublicKey(BorshDeserialize::deserialize_reader(rd)?)))
+            }
+            KeyType::WEBAUTHN => Ok(PublicKey::WEBAUTHN(WebAuthnPublicKey(
+                BorshDeserialize::deserialize_reader(rd)?,
+            ))),
         }
     }
 }
@@ -268,6 +364,8 @@ impl FromStr for PublicKey {
         Ok(match key_type {
             KeyType::ED25519 => Self::ED25519(ED25519PublicKey(decode_bs58(key_data)?)),
             KeyType::SECP256K1 => Self::SECP256K1(Secp256K1PublicKey(decode_bs58(key_data)?)),
+            KeyType::ETH => Self::ETH(EthPublicKey(decode_prefixed_hex(key_data)?)),
+            KeyType::WEBAUTHN => Self::WEBAUTHN(WebAuthnPublicKey(decode_bs58(key_data)?)),
         })
     }
 }
@@ -284,6 +382,18 @@ impl From<Secp256K1PublicKey> for PublicKey {
     }
 }
 
+impl From<EthPublicKey> for PublicKey {
+    fn from(eth: EthPublicKey) -> Self {
+        Self::ETH(eth)
+    }
+}
+
+impl From<WebAuthnPublicKey> for PublicKey {
+    fn from(webauthn: WebAuthnPublicKey) -> Self {
+        Self::WEBAUTHN(webauthn)
+    }
+}
+
 #[derive(Clone, Eq)]
 // This is actually a keypair, because ed25519_dalek api only has keypair.sign
 // From ed25519_dalek doc: The first SECRET_KEY_LENGTH of bytes is the SecretKey
@@ -307,6 +417,10 @@ impl std::fmt::Debug for ED25519SecretKey {
 pub enum SecretKey {
     ED25519(ED25519SecretKey),
     SECP256K1(secp256k1::SecretKey),
+    /// secp256k1 secret key identified by the Ethereum address of its
+    /// public key.
+    ETH(secp256k1::SecretKey),
+    WEBAUTHN(WebAuthnSecretKey),
 }
 
 impl SecretKey {
@@ -314,6 +428,8 @@ impl SecretKey {
         match self {
             SecretKey::ED25519(_) => KeyType::ED25519,
             SecretKey::SECP256K1(_) => KeyType::SECP256K1,
+            SecretKey::ETH(_) => KeyType::ETH,
+            SecretKey::WEBAUTHN(_) => KeyType::WEBAUTHN,
         }
     }
 
@@ -327,6 +443,8 @@ impl SecretKey {
                 SecretKey::ED25519(ED25519SecretKey(keypair.to_keypair_bytes()))
             }
             KeyType::SECP256K1 => SecretKey::SECP256K1(secp256k1::SecretKey::new(&mut OsRng)),
+            KeyType::ETH => SecretKey::ETH(secp256k1::SecretKey::new(&mut OsRng)),
+            KeyType::WEBAUTHN => SecretKey::WEBAUTHN(WebAuthnSecretKey::from_random()),
         }
     }
 
@@ -338,16 +456,13 @@ impl SecretKey {
             }
 
             SecretKey::SECP256K1(secret_key) => {
-                let signature = SECP256K1.sign_ecdsa_recoverable(
-                    &secp256k1::Message::from_slice(data).expect("32 bytes"),
-                    secret_key,
-                );
-                let (rec_id, data) = signature.serialize_compact();
-                let mut buf = [0; 65];
-                buf[0..64].copy_from_slice(&data[0..64]);
-                buf[64] = rec_id.to_i32() as u8;
-                Signature::SECP256K1(Secp256K1Signature(buf))
+                Signature::SECP256K1(sign_secp256k1(secret_key, data, 0))
+            }
+            // Ethereum tooling expects the recovery id to be 27 or 28.
+            SecretKey::ETH(secret_key) => {
+                Signature::ETH(sign_secp256k1(secret_key, data, ETH_RECOVERY_ID_OFFSET))
             }
+            SecretKey::WEBAUTHN(secret_key) => Signature::WEBAUTHN(Box::new(secret_key.sign(data))),
         }
     }
 
@@ -357,28 +472,56 @@ impl SecretKey {
                 secret_key.0[ed25519_dalek::SECRET_KEY_LENGTH..].try_into().unwrap(),
             )),
             SecretKey::SECP256K1(secret_key) => {
-                let pk = secp256k1::PublicKey::from_secret_key(&SECP256K1, secret_key);
-                let serialized = pk.serialize_uncompressed();
-                let mut public_key = Secp256K1PublicKey([0; 64]);
-                public_key.0.copy_from_slice(&serialized[1..65]);
-                PublicKey::SECP256K1(public_key)
+                PublicKey::SECP256K1(secp256k1_public_key(secret_key))
             }
+            SecretKey::ETH(secret_key) => {
+                PublicKey::ETH(EthPublicKey::from(&secp256k1_public_key(secret_key)))
+            }
+            SecretKey::WEBAUTHN(secret_key) => PublicKey::WEBAUTHN(secret_key.public_key()),
         }
     }
 
     pub fn unwrap_as_ed25519(&self) -> &ED25519SecretKey {
         match self {
             SecretKey::ED25519(key) => key,
-            SecretKey::SECP256K1(_) => panic!(),
+            _ => panic!(),
         }
     }
 }
 
+fn sign_secp256k1(
+    secret_key: &secp256k1::SecretKey,
+    data: &[u8],
+    recovery_id_offset: u8,
+) -> Secp256K1Signature {
+    let signature = SECP256K1.sign_ecdsa_recoverable(
+        &secp256k1::Message::from_slice(data).expect("32 bytes"),
+        secret_key,
+    );
+    let (rec_id, data) = signature.serialize_compact();
+    let mut buf = [0; 65];
+    buf[0..64].copy_from_slice(&data[0..64]);
+    buf[64] = (rec_id.to_i32() as u8).wrapping_add(recovery_id_offset);
+    Secp256K1Signature(buf)
+}
+
+fn secp256k1_public_key(secret_key: &secp256k1::SecretKey) -> Secp256K1PublicKey {
+    let pk = secp256k1::PublicKey::from_secret_key(&SECP256K1, secret_key);
+    let serialized = pk.serialize_uncompressed();
+    let mut public_key = Secp256K1PublicKey([0; 64]);
+    public_key.0.copy_from_slice(&serialized[1..65]);
+    public_key
+}
+
 impl std::fmt::Display for SecretKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         let (key_type, key_data) = match self {
             SecretKey::ED25519(secret_key) => (KeyType::ED25519, &secret_key.0[..]),
             SecretKey::SECP256K1(secret_key) => (KeyType::SECP256K1, &secret_key[..]),
+            SecretKey::ETH(secret_key) => {
+                return write!(f, "{}:{}", KeyType::ETH, PrefixedHex(&secret_key[..]));
+            }
+            SecretKey::WEBAUTHN(secret_key) => (KeyType::WEBAUTHN, &secret_key.as_bytes()[..]),
         };
         write!(f, "{}:{}", key_type, Bs58(key_data))
     }
@@ -397,6 +540,17 @@ impl FromStr for SecretKey {
                     .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
                 Self::SECP256K1(sk)
             }
+            KeyType::ETH => {
+                let data =
+                    decode_prefixed_hex::<{ secp256k1::constants::SECRET_KEY_SIZE }>(key_data)?;
+                let sk = secp256k1::SecretKey::from_slice(&data)
+                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                Self::ETH(sk)
+            }
+            KeyType::WEBAUTHN => {
+                let data = decode_bs58::<{ webauthn::SECRET_KEY_LENGTH }>(key_data)?;
+                Self::WEBAUTHN(WebAuthnSecretKey::try_from(&data[..])?)
+            }
         })
     }
 }
@@ -432,6 +586,9 @@ const SECP256K1_N_HALF_ONE: U256 =
 
 const SECP256K1_SIGNATURE_LENGTH: usize = 65;
 
+/// Offset added to the recovery id (0 or 1) in Ethereum signatures.
+const ETH_RECOVERY_ID_OFFSET: u8 = 27;
+
 #[derive(Clone, Eq, PartialEq, Hash, derive_more::From, derive_more::Into, ProtocolSchema)]
 pub struct Secp256K1Signature([u8; SECP256K1_SIGNATURE_LENGTH]);
 
@@ -480,6 +637,30 @@ impl Secp256K1Signature {
 
         Ok(pk)
     }
+
+    /// Recovers the Ethereum address of the key which signed the given
+    /// 32-byte message.
+    ///
+    /// Only accepts Ethereum (27 or 28) recovery ids, so that every signature
+    /// has a single encoding, and rejects signatures with the upper range `s`
+    /// values to prevent malleability.
+    pub fn recover_eth_address(&self, msg: &[u8]) -> Option<EthPublicKey> {
+        if !self.check_signature_values(true) {
+            return None;
+        }
+        let rec_id = match self.0[64] {
+            rec_id @ (27 | 28) => rec_id - ETH_RECOVERY_ID_OFFSET,
+            _ => return None,
+        };
+        let rec_id = secp256k1::ecdsa::RecoveryId::from_i32(i32::from(rec_id)).ok()?;
+        let recoverable_sig =
+            secp256k1::ecdsa::RecoverableSignature::from_compact(&self.0[0..64], rec_id).ok()?;
+        let msg = Message::from_slice(msg).ok()?;
+        let public_key = SECP256K1.recover_ecdsa(&msg, &recoverable_sig).ok()?;
+        let public_key = Secp256K1PublicKey::try_from(&public_key.serialize_uncompressed()[1..65])
+            .expect("uncompressed public key is 65 bytes");
+        Some(EthPublicKey::from(&public_key))
+    }
 }
 
 impl TryFrom<&[u8]> for Secp256K1Signature {
@@ -504,6 +685,10 @@ impl Debug for Secp256K1Signature {
 pub enum Signature {
     ED25519(ed25519_dalek::Signature),
     SECP256K1(Secp256K1Signature),
+    /// secp256k1 signature in the Ethereum `r || s || v` format.
+    ETH(Secp256K1Signature),
+    /// WebAuthn assertion.
+    WEBAUTHN(Box<WebAuthnSignature>),
 }
 
 // This `Hash` implementation is safe since it retains the property
@@ -513,6 +698,8 @@ impl Hash for Signature {
         match self {
             Signature::ED25519(sig) => sig.to_bytes().hash(state),
             Signature::SECP256K1(sig) => sig.hash(state),
+            Signature::ETH(sig) => sig.hash(state),
+            Signature::WEBAUTHN(sig) => sig.hash(state),
         };
     }
 }
@@ -538,6 +725,21 @@ impl Signature {
                     },
                 )?))
             }
+            KeyType::ETH => {
+                Ok(Signature::ETH(Secp256K1Signature::try_from(signature_data).map_err(|_| {
+                    crate::errors::ParseSignatureError::InvalidData {
+                        error_message: "invalid Ethereum signature length".to_string(),
+                    }
+                })?))
+            }
+            // WebAuthn assertions have variable length and are borsh-encoded.
+            KeyType::WEBAUTHN => {
+                Ok(Signature::WEBAUTHN(Box::new(borsh::from_slice(signature_data).map_err(
+                    |err| crate::errors::ParseSignatureError::InvalidData {
+                        error_message: err.to_string(),
+                    },
+                )?)))
+            }
         }
     }
 
@@ -581,6 +783,12 @@ impl Signature {
                 };
                 SECP256K1.verify_ecdsa(&message, &sig, &pub_key).is_ok()
             }
+            (Signature::ETH(signature), PublicKey::ETH(public_key)) => {
+                signature.recover_eth_address(data).as_ref() == Some(public_key)
+            }
+            (Signature::WEBAUTHN(signature), PublicKey::WEBAUTHN(public_key)) => {
+                signature.verify(data, public_key)
+            }
             _ => false,
         }
     }
@@ -589,6 +797,8 @@ impl Signature {
         match self {
             Signature::ED25519(_) => KeyType::ED25519,
             Signature::SECP256K1(_) => KeyType::SECP256K1,
+            Signature::ETH(_) => KeyType::ETH,
+            Signature::WEBAUTHN(_) => KeyType::WEBAUTHN,
         }
     }
 }
@@ -610,6 +820,14 @@ impl BorshSerialize for Signature {
                 BorshSerialize::serialize(&1u8, writer)?;
                 writer.write_all(&signature.0)?;
             }
+            Signature::ETH(signature) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                writer.write_all(&signature.0)?;
+            }
+            Signature::WEBAUTHN(signature) => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                BorshSerialize::serialize(signature, writer)?;
+            }
         }
         Ok(())
     }
@@ -636,6 +854,13 @@ impl BorshDeserialize for Signature {
                 let array: [u8; 65] = BorshDeserialize::deserialize_reader(rd)?;
                 Ok(Signature::SECP256K1(Secp256K1Signature(array)))
             }
+            KeyType::ETH => {
+                let array: [u8; 65] = BorshDeserialize::deserialize_reader(rd)?;
+                Ok(Signature::ETH(Secp256K1Signature(array)))
+            }
+            KeyType::WEBAUTHN => {
+                Ok(Signature::WEBAUTHN(Box::new(BorshDeserialize::deserialize_reader(rd)?)))
+            }
         }
     }
 }
@@ -649,6 +874,13 @@ impl Display for Signature {
                 (KeyType::ED25519, &buf[..])
             }
             Signature::SECP256K1(signature) => (KeyType::SECP256K1, &signature.0[..]),
+            Signature::ETH(signature) => {
+                return write!(f, "{}:{}", KeyType::ETH, PrefixedHex(&signature.0));
+            }
+            Signature::WEBAUTHN(signature) => {
+                let data = borsh::to_vec(signature).map_err(|_| std::fmt::Error)?;
+                return write!(f, "{}:{}", KeyType::WEBAUTHN, bs58::encode(data).into_string());
+            }
         };
         write!(f, "{}:{}", key_type, Bs58(&key_data))
     }
@@ -684,6 +916,13 @@ impl FromStr for Signature {
                 Signature::ED25519(sig)
             }
             KeyType::SECP256K1 => Signature::SECP256K1(Secp256K1Signature(decode_bs58(sig_data)?)),
+            KeyType::ETH => Signature::ETH(Secp256K1Signature(decode_prefixed_hex(sig_data)?)),
+            KeyType::WEBAUTHN => {
+                let data = bs58::decode(sig_data)
+                    .into_vec()
+                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                Signature::from_parts(KeyType::WEBAUTHN, &data)?
+            }
         })
     }
 }
@@ -721,6 +960,16 @@ impl<'a> core::fmt::Display for Bs58<'a> {
     }
 }
 
+/// Helper struct which provides Display implementation for bytes slice
+/// encoding them using `0x`-prefixed lowercase hex, as used by Ethereum.
+struct PrefixedHex<'a>(&'a [u8]);
+
+impl<'a> core::fmt::Display for PrefixedHex<'a> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(fmt, "0x{}", hex::encode(self.0))
+    }
+}
+
 /// Helper which decodes fixed-length base58-encoded data.
 ///
 /// If the encoded string decodes into a buffer of different length than `N`,
@@ -743,6 +992,24 @@ fn decode_bs58_impl(dst: &mut [u8], encoded: &str) -> Result<(), DecodeBs58Error
     }
 }
 
+/// Helper which decodes fixed-length `0x`-prefixed hex-encoded data.
+///
+/// Both lower and upper case digits are accepted; EIP-55 checksums of mixed
+/// case addresses are not verified.
+fn decode_prefixed_hex<const N: usize>(encoded: &str) -> Result<[u8; N], DecodeBs58Error> {
+    let Some(encoded) = encoded.strip_prefix("0x") else {
+        return Err(DecodeBs58Error::BadData("missing 0x prefix".to_string()));
+    };
+    let mut buffer = [0u8; N];
+    match hex::decode_to_slice(encoded, &mut buffer) {
+        Ok(()) => Ok(buffer),
+        Err(hex::FromHexError::InvalidStringLength) => {
+            Err(DecodeBs58Error::BadLength { expected: N, received: encoded.len() / 2 })
+        }
+        Err(err) => Err(DecodeBs58Error::BadData(err.to_string())),
+    }
+}
+
 enum DecodeBs58Error {
     BadLength { expected: usize, received: usize },
     BadData(String),
@@ -779,7 +1046,7 @@ mod tests {
 
     #[test]
     fn test_sign_verify() {
-        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1, KeyType::ETH, KeyType::WEBAUTHN] {
             let secret_key = SecretKey::from_random(key_type);
             let public_key = secret_key.public_key();
             use sha2::Digest;
@@ -800,6 +1067,11 @@ mod tests {
                     KeyType::SECP256K1 => {
                         Signature::from_parts(KeyType::SECP256K1, &sign[..65]).unwrap()
                     }
+                    KeyType::ETH => Signature::from_parts(KeyType::ETH, &sign[..65]).unwrap(),
+                    KeyType::WEBAUTHN => match Signature::from_parts(KeyType::WEBAUTHN, sign) {
+                        Ok(signature) => signature,
+                        Err(_) => return,
+                    },
                 };
                 let _ = signature.verify(&data, &public_key);
             },
@@ -865,11 +1137,93 @@ mod tests {
         assert_eq!(signature, signature2);
     }
 
+    #[test]
+    fn test_json_serialize_eth() {
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123").to_vec();
+
+        let sk = SecretKey::from_seed(KeyType::ETH, "test");
+        let pk = sk.public_key();
+        let expected = "\"eth:0x96791e923f8cf697ad9c3290f2c9059f0231b24c\"";
+        assert_eq!(serde_json::to_string(&pk).unwrap(), expected);
+        assert_eq!(pk, serde_json::from_str(expected).unwrap());
+        assert_eq!(
+            pk,
+            serde_json::from_str("\"eth:0x96791E923F8CF697AD9C3290F2C9059F0231B24C\"").unwrap()
+        );
+        let secp256k1_pk = SecretKey::from_seed(KeyType::SECP256K1, "test").public_key();
+        assert_eq!(pk, EthPublicKey::from(secp256k1_pk.unwrap_as_secp256k1()).into());
+
+        let expected = "\"eth:0x07b31421ab1f49393ada270d2e55c85c1451f416d5c38519c8507dda77253df1\"";
+        assert_eq!(serde_json::to_string(&sk).unwrap(), expected);
+        assert_eq!(sk, serde_json::from_str(expected).unwrap());
+
+        let signature = sk.sign(&data);
+        let expected = "\"eth:0x3171ea8b79f2e74ae7e3973f0443e5b55e79025d3f696c7d84699775efbb76ca7ae7de364388d3be939c0cb3679af59442f3341ebe724c0d55ef4f2570e6cb721c\"";
+        assert_eq!(serde_json::to_string(&signature).unwrap(), expected);
+        assert_eq!(signature, serde_json::from_str(expected).unwrap());
+        let signature_str: String = signature.to_string();
+        let signature2: Signature = signature_str.parse().unwrap();
+        assert_eq!(signature, signature2);
+    }
+
+    #[test]
+    fn test_json_serialize_webauthn() {
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123").to_vec();
+
+        let sk = SecretKey::from_seed(KeyType::WEBAUTHN, "test");
+        let pk = sk.public_key();
+        let pk_str = serde_json::to_string(&pk).unwrap();
+        assert!(pk_str.starts_with("\"webauthn:"));
+        assert_eq!(pk, serde_json::from_str(&pk_str).unwrap());
+        assert_eq!(sk, serde_json::from_str(&serde_json::to_string(&sk).unwrap()).unwrap());
+
+        let signature = sk.sign(&data);
+        let signature2: Signature = signature.to_string().parse().unwrap();
+        assert_eq!(signature, signature2);
+        assert!(signature2.verify(&data, &pk));
+        assert!(!signature2.verify(&data, &PublicKey::from_seed(KeyType::WEBAUTHN, "other")));
+    }
+
+    #[test]
+    fn test_eth_signature_verification() {
+        use sha2::Digest;
+        let data = sha2::Sha256::digest(b"123").to_vec();
+        let sk = SecretKey::from_seed(KeyType::ETH, "test");
+        let pk = sk.public_key();
+        let Signature::ETH(signature) = sk.sign(&data) else { panic!() };
+        assert!(Signature::ETH(signature.clone()).verify(&data, &pk));
+
+        // Raw recovery ids are rejected, so that the signature has a single encoding.
+        let mut raw = signature.0;
+        raw[64] -= ETH_RECOVERY_ID_OFFSET;
+        assert!(!Signature::ETH(Secp256K1Signature(raw)).verify(&data, &pk));
+
+        // Invalid recovery id.
+        let mut invalid = signature.0;
+        invalid[64] = 2;
+        assert!(!Signature::ETH(Secp256K1Signature(invalid)).verify(&data, &pk));
+
+        // The same signature with the upper range `s`.
+        let mut malleable = signature.0;
+        let (s, _) = SECP256K1_N.overflowing_sub(U256::from_big_endian(&signature.0[32..64]));
+        s.to_big_endian(&mut malleable[32..64]);
+        malleable[64] = 2 * ETH_RECOVERY_ID_OFFSET + 1 - malleable[64];
+        assert!(!Signature::ETH(Secp256K1Signature(malleable)).verify(&data, &pk));
+
+        // Other data or other key.
+        assert!(!Signature::ETH(signature.clone()).verify(&[0; 32], &pk));
+        let other_pk = PublicKey::from_seed(KeyType::ETH, "other");
+        assert!(!Signature::ETH(signature.clone()).verify(&data, &other_pk));
+        assert!(!Signature::SECP256K1(signature).verify(&data, &pk));
+    }
+
     #[test]
     fn test_borsh_serialization() {
         use sha2::Digest;
         let data = sha2::Sha256::digest(b"123").to_vec();
-        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1, KeyType::ETH, KeyType::WEBAUTHN] {
             let sk = SecretKey::from_seed(key_type, "test");
             let pk = sk.public_key();
             let bytes = borsh::to_vec(&pk).unwrap();
@@ -890,5 +1244,14 @@ mod tests {
         assert!(serde_json::from_str::<PublicKey>(invalid).is_err());
         assert!(serde_json::from_str::<SecretKey>(invalid).is_err());
         assert!(serde_json::from_str::<Signature>(invalid).is_err());
+
+        for invalid in [
+            "\"eth:96791e923f8cf697ad9c3290f2c9059f0231b24c\"",
+            "\"eth:0x96791e923f8cf697ad9c3290f2c9059f0231b2\"",
+            "\"eth:0x96791e923f8cf697ad9c3290f2c9059f0231b24c00\"",
+            "\"eth:0x96791e923f8cf697ad9c3290f2c9059f0231b24g\"",
+        ] {
+            assert!(serde_json::from_str::<PublicKey>(invalid).is_err());
+        }
     }
 }
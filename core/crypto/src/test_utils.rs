@@ -22,6 +22,15 @@ fn secp256k1_secret_key_from_seed(seed: &str) -> secp256k1::SecretKey {
     secp256k1::SecretKey::new(&mut rng)
 }
 
+#[cfg(feature = "rand")]
+fn webauthn_secret_key_from_seed(seed: &str) -> crate::WebAuthnSecretKey {
+    let seed_bytes = seed.as_bytes();
+    let len = std::cmp::min(32, seed_bytes.len());
+    let mut seed: [u8; 32] = [b' '; 32];
+    seed[..len].copy_from_slice(&seed_bytes[..len]);
+    crate::WebAuthnSecretKey::try_from(&seed[..]).unwrap()
+}
+
 impl PublicKey {
     #[cfg(feature = "rand")]
     pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
@@ -36,6 +45,13 @@ impl PublicKey {
                 let secret_key = SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed));
                 PublicKey::SECP256K1(secret_key.public_key().unwrap_as_secp256k1().clone())
             }
+            KeyType::ETH => {
+                let secret_key = SecretKey::ETH(secp256k1_secret_key_from_seed(seed));
+                PublicKey::ETH(secret_key.public_key().unwrap_as_eth().clone())
+            }
+            KeyType::WEBAUTHN => {
+                PublicKey::WEBAUTHN(webauthn_secret_key_from_seed(seed).public_key())
+            }
         }
     }
 }
@@ -49,6 +65,8 @@ impl SecretKey {
                 SecretKey::ED25519(crate::signature::ED25519SecretKey(keypair.to_keypair_bytes()))
             }
             KeyType::SECP256K1 => SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed)),
+            KeyType::ETH => SecretKey::ETH(secp256k1_secret_key_from_seed(seed)),
+            KeyType::WEBAUTHN => SecretKey::WEBAUTHN(webauthn_secret_key_from_seed(seed)),
         }
     }
 }
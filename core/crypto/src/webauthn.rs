@@ -0,0 +1,258 @@
+//! Keys and signatures of WebAuthn authenticators (passkeys).
+//!
+//! Authenticators don't sign the data directly.  They sign their
+//! `authenticatorData` followed by the SHA-256 hash of `clientDataJSON`, a JSON
+//! document which carries the data as a base64url-encoded `challenge`, using
+//! ECDSA over the P-256 (secp256r1) curve.  See
+//! <https://www.w3.org/TR/webauthn-2/#sctn-verifying-assertion>.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_schema_checker_lib::ProtocolSchema;
+use p256::ecdsa::signature::{Signer, Verifier};
+use sha2::Digest;
+
+/// Length of a SEC1 compressed P-256 public key.
+pub(crate) const PUBLIC_KEY_LENGTH: usize = 33;
+pub(crate) const SECRET_KEY_LENGTH: usize = 32;
+const SIGNATURE_LENGTH: usize = 64;
+
+/// `authenticatorData` consists of 32-byte RP ID hash, 1-byte flags and 4-byte
+/// signature counter, optionally followed by extensions.
+const MIN_AUTHENTICATOR_DATA_LENGTH: usize = 37;
+const FLAGS_OFFSET: usize = 32;
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+
+const CLIENT_DATA_TYPE_GET: &str = "webauthn.get";
+
+/// RP ID used by `WebAuthnSecretKey` when it emulates an authenticator.
+const EMULATED_RP_ID: &str = "near.org";
+
+/// P-256 public key of a WebAuthn credential in the SEC1 compressed form.
+#[derive(
+    Clone, Eq, Ord, PartialEq, PartialOrd, derive_more::AsRef, derive_more::From, ProtocolSchema,
+)]
+#[cfg_attr(test, derive(bolero::TypeGenerator))]
+#[as_ref(forward)]
+pub struct WebAuthnPublicKey(pub [u8; PUBLIC_KEY_LENGTH]);
+
+impl TryFrom<&[u8]> for WebAuthnPublicKey {
+    type Error = crate::errors::ParseKeyError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        data.try_into().map(Self).map_err(|_| Self::Error::InvalidLength {
+            expected_length: PUBLIC_KEY_LENGTH,
+            received_length: data.len(),
+        })
+    }
+}
+
+impl std::fmt::Debug for WebAuthnPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&bs58::encode(&self.0).into_string())
+    }
+}
+
+/// P-256 secret key.
+///
+/// Passkeys never leave the device, so signing with this key emulates an
+/// authenticator.  It's meant for tests and tools.
+#[derive(Clone, Eq, PartialEq)]
+pub struct WebAuthnSecretKey([u8; SECRET_KEY_LENGTH]);
+
+impl WebAuthnSecretKey {
+    #[cfg(feature = "rand")]
+    pub(crate) fn from_random() -> Self {
+        let signing_key = p256::ecdsa::SigningKey::random(&mut secp256k1::rand::rngs::OsRng);
+        Self(signing_key.to_bytes().as_slice().try_into().unwrap())
+    }
+
+    pub fn public_key(&self) -> WebAuthnPublicKey {
+        let encoded = self.signing_key().verifying_key().to_encoded_point(true);
+        WebAuthnPublicKey(encoded.as_bytes().try_into().expect("compressed point is 33 bytes"))
+    }
+
+    /// Signs the data the way a WebAuthn authenticator would sign an assertion
+    /// with the data as the challenge.
+    pub fn sign(&self, data: &[u8]) -> WebAuthnSignature {
+        let client_data_json = serde_json::json!({
+            "type": CLIENT_DATA_TYPE_GET,
+            "challenge": URL_SAFE_NO_PAD.encode(data),
+            "origin": format!("https://{}", EMULATED_RP_ID),
+        })
+        .to_string()
+        .into_bytes();
+
+        let mut authenticator_data = Vec::with_capacity(MIN_AUTHENTICATOR_DATA_LENGTH);
+        authenticator_data.extend_from_slice(&sha2::Sha256::digest(EMULATED_RP_ID));
+        authenticator_data.push(FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+        authenticator_data.extend_from_slice(&0u32.to_be_bytes());
+
+        let signature: p256::ecdsa::Signature =
+            self.signing_key().sign(&signed_message(&authenticator_data, &client_data_json));
+        let signature = signature.normalize_s().unwrap_or(signature);
+        WebAuthnSignature {
+            authenticator_data,
+            client_data_json,
+            signature: signature.to_bytes().as_slice().try_into().unwrap(),
+        }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; SECRET_KEY_LENGTH] {
+        &self.0
+    }
+
+    fn signing_key(&self) -> p256::ecdsa::SigningKey {
+        p256::ecdsa::SigningKey::from_slice(&self.0).expect("secret key is validated")
+    }
+}
+
+impl TryFrom<&[u8]> for WebAuthnSecretKey {
+    type Error = crate::errors::ParseKeyError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        let data: [u8; SECRET_KEY_LENGTH] =
+            data.try_into().map_err(|_| Self::Error::InvalidLength {
+                expected_length: SECRET_KEY_LENGTH,
+                received_length: data.len(),
+            })?;
+        p256::ecdsa::SigningKey::from_slice(&data)
+            .map_err(|err| Self::Error::InvalidData { error_message: err.to_string() })?;
+        Ok(Self(data))
+    }
+}
+
+impl std::fmt::Debug for WebAuthnSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&bs58::encode(&self.0).into_string())
+    }
+}
+
+/// WebAuthn assertion: the data signed by the authenticator together with the
+/// P-256 signature in the `r || s` form.
+#[derive(Clone, Eq, PartialEq, Hash, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct WebAuthnSignature {
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
+    pub signature: [u8; SIGNATURE_LENGTH],
+}
+
+impl WebAuthnSignature {
+    /// Verifies that the assertion has the data as its challenge and is signed
+    /// by the given key.
+    ///
+    /// The RP ID and the origin are not checked, since the chain doesn't know
+    /// which website the credential was created for, and neither is the
+    /// signature counter.  The user presence flag must be set.  Signatures with
+    /// the upper range `s` values are rejected to prevent malleability, so
+    /// clients must normalize them.
+    pub fn verify(&self, data: &[u8], public_key: &WebAuthnPublicKey) -> bool {
+        if self.authenticator_data.len() < MIN_AUTHENTICATOR_DATA_LENGTH
+            || self.authenticator_data[FLAGS_OFFSET] & FLAG_USER_PRESENT == 0
+        {
+            return false;
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ClientData {
+            #[serde(rename = "type")]
+            type_: String,
+            challenge: String,
+        }
+        let Ok(client_data) = serde_json::from_slice::<ClientData>(&self.client_data_json) else {
+            return false;
+        };
+        if client_data.type_ != CLIENT_DATA_TYPE_GET
+            || client_data.challenge != URL_SAFE_NO_PAD.encode(data)
+        {
+            return false;
+        }
+
+        let Ok(verifying_key) = p256::ecdsa::VerifyingKey::from_sec1_bytes(&public_key.0) else {
+            return false;
+        };
+        let Ok(signature) = p256::ecdsa::Signature::from_slice(&self.signature) else {
+            return false;
+        };
+        if signature.normalize_s().is_some() {
+            return false;
+        }
+        let message = signed_message(&self.authenticator_data, &self.client_data_json);
+        verifying_key.verify(&message, &signature).is_ok()
+    }
+}
+
+impl std::fmt::Debug for WebAuthnSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.debug_struct("WebAuthnSignature")
+            .field("authenticator_data", &hex::encode(&self.authenticator_data))
+            .field("client_data_json", &String::from_utf8_lossy(&self.client_data_json))
+            .field("signature", &bs58::encode(&self.signature).into_string())
+            .finish()
+    }
+}
+
+/// Returns the message the authenticator signs: `authenticatorData` followed
+/// by SHA-256 of `clientDataJSON`.  ECDSA hashes it once more with SHA-256.
+fn signed_message(authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+    let mut message = authenticator_data.to_vec();
+    message.extend_from_slice(&sha2::Sha256::digest(client_data_json));
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret_key() -> WebAuthnSecretKey {
+        WebAuthnSecretKey::try_from(&[7u8; SECRET_KEY_LENGTH][..]).unwrap()
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let secret_key = secret_key();
+        let public_key = secret_key.public_key();
+        let data = sha2::Sha256::digest(b"123");
+        let signature = secret_key.sign(&data);
+        assert!(signature.verify(&data, &public_key));
+        assert!(!signature.verify(&[0; 32], &public_key));
+
+        let other_key = WebAuthnSecretKey::try_from(&[8u8; SECRET_KEY_LENGTH][..]).unwrap();
+        assert!(!signature.verify(&data, &other_key.public_key()));
+    }
+
+    #[test]
+    fn test_invalid_assertion() {
+        let secret_key = secret_key();
+        let public_key = secret_key.public_key();
+        let data = sha2::Sha256::digest(b"123");
+        let signature = secret_key.sign(&data);
+
+        // The user wasn't present.
+        let mut invalid = signature.clone();
+        invalid.authenticator_data[FLAGS_OFFSET] &= !FLAG_USER_PRESENT;
+        assert!(!invalid.verify(&data, &public_key));
+
+        // Truncated authenticator data.
+        let mut invalid = signature.clone();
+        invalid.authenticator_data.truncate(MIN_AUTHENTICATOR_DATA_LENGTH - 1);
+        assert!(!invalid.verify(&data, &public_key));
+
+        // Assertion made during registration rather than authentication.
+        let mut invalid = signature.clone();
+        invalid.client_data_json = String::from_utf8(invalid.client_data_json)
+            .unwrap()
+            .replace("webauthn.get", "webauthn.create")
+            .into();
+        assert!(!invalid.verify(&data, &public_key));
+
+        // The same signature with the upper range `s`.
+        let mut invalid = signature;
+        let normalized = p256::ecdsa::Signature::from_slice(&invalid.signature).unwrap();
+        let (r, s) = normalized.split_scalars();
+        let high_s = p256::ecdsa::Signature::from_scalars(r, std::ops::Neg::neg(s)).unwrap();
+        invalid.signature = high_s.to_bytes().as_slice().try_into().unwrap();
+        assert!(!invalid.verify(&data, &public_key));
+    }
+}
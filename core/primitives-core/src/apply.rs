@@ -11,6 +11,9 @@ pub enum ApplyChunkReason {
     UpdateTrackedShard,
     /// Apply-chunk is invoked to validate the state witness for a shard in the context of stateless validation.
     ValidateChunkStateWitness,
+    /// Apply-chunk is invoked to simulate a transaction on top of the state of a block
+    /// without committing the results, e.g. for the `EXPERIMENTAL_simulate_transaction` RPC.
+    SimulateTransaction,
 }
 
 impl ApplyChunkReason {
@@ -19,6 +22,7 @@ impl ApplyChunkReason {
         match self {
             ApplyChunkReason::UpdateTrackedShard => "update_shard",
             ApplyChunkReason::ValidateChunkStateWitness => "validate_chunk",
+            ApplyChunkReason::SimulateTransaction => "simulate_transaction",
         }
     }
 }
@@ -167,6 +167,15 @@ pub enum ProtocolFeature {
     // Include a bitmap of endorsements from chunk validator in the block header
     // in order to calculate the rewards and kickouts for the chunk validators.
     ChunkEndorsementsInBlockHeader,
+    /// Allows adding access keys identified by an Ethereum address, which
+    /// verify secp256k1 signatures by recovering the signer's address.
+    EthAddressKeys,
+    /// Allows signing transactions with WebAuthn credentials (passkeys), which
+    /// produce P-256 signatures wrapped in WebAuthn assertions.
+    WebAuthnKeys,
+    /// Encrypts and authenticates connections between peers with keys agreed
+    /// on during the handshake and bound to the peers' node keys.
+    EncryptedPeerTransport,
 }
 
 impl ProtocolFeature {
@@ -241,6 +250,9 @@ impl ProtocolFeature {
             ProtocolFeature::ShuffleShardAssignments => 143,
             ProtocolFeature::ChunkEndorsementV2 => 144,
             ProtocolFeature::ChunkEndorsementsInBlockHeader => 145,
+            ProtocolFeature::EthAddressKeys => 146,
+            ProtocolFeature::WebAuthnKeys => 147,
+            ProtocolFeature::EncryptedPeerTransport => 148,
         }
     }
 
@@ -253,7 +265,7 @@ impl ProtocolFeature {
 const STABLE_PROTOCOL_VERSION: ProtocolVersion = 71;
 
 // On nightly, pick big enough version to support all features.
-const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 145;
+const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 148;
 
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
@@ -299,7 +299,7 @@ impl Block {
         block_merkle_root: CryptoHash,
         clock: near_time::Clock,
         sandbox_delta_time: Option<near_time::Duration>,
-    ) -> Self {
+    ) -> Result<Self, crate::validator_signer::RemoteSignerError> {
         use crate::hash::hash;
         // Collect aggregate of validators and gas usage/limits from chunks.
         let mut prev_validator_proposals = vec![];
@@ -336,7 +336,7 @@ impl Block {
         debug_assert!(sandbox_delta_time.is_none());
         let time = if now <= prev.raw_timestamp() { prev.raw_timestamp() + 1 } else { now };
 
-        let (vrf_value, vrf_proof) = signer.compute_vrf_with_proof(prev.random_value().as_ref());
+        let (vrf_value, vrf_proof) = signer.compute_vrf_with_proof(prev.random_value().as_ref())?;
         let random_value = hash(vrf_value.0.as_ref());
 
         let last_ds_final_block =
@@ -399,14 +399,14 @@ impl Block {
             block_merkle_root,
             prev.height(),
             clock,
-        );
+        )?;
 
-        Self::block_from_protocol_version(
+        Ok(Self::block_from_protocol_version(
             this_epoch_protocol_version,
             next_epoch_protocol_version,
             header,
             body,
-        )
+        ))
     }
 
     pub fn verify_total_supply(
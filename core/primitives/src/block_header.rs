@@ -5,7 +5,7 @@ use crate::network::PeerId;
 use crate::stateless_validation::chunk_endorsements_bitmap::ChunkEndorsementsBitmap;
 use crate::types::validator_stake::{ValidatorStake, ValidatorStakeIter, ValidatorStakeV1};
 use crate::types::{AccountId, Balance, BlockHeight, EpochId, MerkleHash, NumBlocks};
-use crate::validator_signer::ValidatorSigner;
+use crate::validator_signer::{RemoteSignerError, ValidatorSigner};
 use crate::version::ProtocolVersion;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::{KeyType, PublicKey, Signature};
@@ -287,10 +287,10 @@ impl Approval {
         parent_height: BlockHeight,
         target_height: BlockHeight,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = ApprovalInner::new(&parent_hash, parent_height, target_height);
-        let signature = signer.sign_approval(&inner, target_height);
-        Approval { inner, target_height, signature, account_id: signer.validator_id().clone() }
+        let signature = signer.sign_approval(&inner, target_height)?;
+        Ok(Approval { inner, target_height, signature, account_id: signer.validator_id().clone() })
     }
 
     pub fn get_data_for_sig(inner: &ApprovalInner, target_height: BlockHeight) -> Vec<u8> {
@@ -493,7 +493,7 @@ impl BlockHeader {
         block_merkle_root: CryptoHash,
         prev_height: BlockHeight,
         clock: near_time::Clock,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner_lite = BlockHeaderInnerLite {
             height,
             epoch_id,
@@ -537,14 +537,14 @@ impl BlockHeader {
                 prev_hash,
                 &borsh::to_vec(&inner_lite).expect("Failed to serialize"),
                 &borsh::to_vec(&inner_rest).expect("Failed to serialize"),
-            );
-            Self::BlockHeaderV1(Arc::new(BlockHeaderV1 {
+            )?;
+            Ok(Self::BlockHeaderV1(Arc::new(BlockHeaderV1 {
                 prev_hash,
                 inner_lite,
                 inner_rest,
                 signature,
                 hash,
-            }))
+            })))
         } else if this_epoch_protocol_version <= last_header_v2_version {
             let inner_rest = BlockHeaderInnerRestV2 {
                 prev_chunk_outgoing_receipts_root,
@@ -569,14 +569,14 @@ impl BlockHeader {
                 prev_hash,
                 &borsh::to_vec(&inner_lite).expect("Failed to serialize"),
                 &borsh::to_vec(&inner_rest).expect("Failed to serialize"),
-            );
-            Self::BlockHeaderV2(Arc::new(BlockHeaderV2 {
+            )?;
+            Ok(Self::BlockHeaderV2(Arc::new(BlockHeaderV2 {
                 prev_hash,
                 inner_lite,
                 inner_rest,
                 signature,
                 hash,
-            }))
+            })))
         } else if !crate::checked_feature!("stable", BlockHeaderV4, this_epoch_protocol_version) {
             let inner_rest = BlockHeaderInnerRestV3 {
                 prev_chunk_outgoing_receipts_root,
@@ -604,14 +604,14 @@ impl BlockHeader {
                 prev_hash,
                 &borsh::to_vec(&inner_lite).expect("Failed to serialize"),
                 &borsh::to_vec(&inner_rest).expect("Failed to serialize"),
-            );
-            Self::BlockHeaderV3(Arc::new(BlockHeaderV3 {
+            )?;
+            Ok(Self::BlockHeaderV3(Arc::new(BlockHeaderV3 {
                 prev_hash,
                 inner_lite,
                 inner_rest,
                 signature,
                 hash,
-            }))
+            })))
         } else {
             let inner_rest = BlockHeaderInnerRestV4 {
                 block_body_hash,
@@ -640,14 +640,14 @@ impl BlockHeader {
                 prev_hash,
                 &borsh::to_vec(&inner_lite).expect("Failed to serialize"),
                 &borsh::to_vec(&inner_rest).expect("Failed to serialize"),
-            );
-            Self::BlockHeaderV4(Arc::new(BlockHeaderV4 {
+            )?;
+            Ok(Self::BlockHeaderV4(Arc::new(BlockHeaderV4 {
                 prev_hash,
                 inner_lite,
                 inner_rest,
                 signature,
                 hash,
-            }))
+            })))
         }
     }
 
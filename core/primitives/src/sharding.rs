@@ -5,7 +5,7 @@ use crate::receipt::Receipt;
 use crate::transaction::SignedTransaction;
 use crate::types::validator_stake::{ValidatorStake, ValidatorStakeIter, ValidatorStakeV1};
 use crate::types::{Balance, BlockHeight, Gas, MerkleHash, ShardId, StateRoot};
-use crate::validator_signer::ValidatorSigner;
+use crate::validator_signer::{RemoteSignerError, ValidatorSigner};
 use crate::version::{ProtocolFeature, ProtocolVersion, SHARD_CHUNK_HEADER_UPGRADE_VERSION};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::Signature;
@@ -129,7 +129,7 @@ impl ShardChunkHeaderV2 {
         tx_root: CryptoHash,
         prev_validator_proposals: Vec<ValidatorStakeV1>,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = ShardChunkHeaderInnerV1 {
             prev_block_hash,
             prev_state_root,
@@ -146,8 +146,8 @@ impl ShardChunkHeaderV2 {
             prev_validator_proposals,
         };
         let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_chunk_hash(&hash);
-        Self { inner, height_included: 0, signature, hash }
+        let signature = signer.sign_chunk_hash(&hash)?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -195,7 +195,7 @@ impl ShardChunkHeaderV3 {
         prev_validator_proposals: Vec<ValidatorStake>,
         congestion_info: Option<CongestionInfo>,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = if let Some(congestion_info) = congestion_info {
             assert!(ProtocolFeature::CongestionControl.enabled(protocol_version));
             ShardChunkHeaderInner::V3(ShardChunkHeaderInnerV3 {
@@ -234,10 +234,12 @@ impl ShardChunkHeaderV3 {
         Self::from_inner(inner, signer)
     }
 
-    pub fn from_inner(inner: ShardChunkHeaderInner, signer: &ValidatorSigner) -> Self {
-        let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_chunk_hash(&hash);
-        Self { inner, height_included: 0, signature, hash }
+    pub fn from_inner(
+        inner: ShardChunkHeaderInner,
+        signer: &ValidatorSigner,
+    ) -> Result<Self, RemoteSignerError> {
+        let (hash, signature) = signer.sign_chunk_header(&inner)?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -509,7 +511,7 @@ impl ShardChunkHeaderV1 {
         tx_root: CryptoHash,
         prev_validator_proposals: Vec<ValidatorStakeV1>,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = ShardChunkHeaderInnerV1 {
             prev_block_hash,
             prev_state_root,
@@ -526,8 +528,8 @@ impl ShardChunkHeaderV1 {
             prev_validator_proposals,
         };
         let hash = Self::compute_hash(&inner);
-        let signature = signer.sign_chunk_hash(&hash);
-        Self { inner, height_included: 0, signature, hash }
+        let signature = signer.sign_chunk_hash(&hash)?;
+        Ok(Self { inner, height_included: 0, signature, hash })
     }
 }
 
@@ -1090,7 +1092,8 @@ impl EncodedShardChunk {
                 tx_root,
                 prev_validator_proposals,
                 signer,
-            );
+            )
+            .map_err(std::io::Error::other)?;
             let chunk = EncodedShardChunkV1 { header, content };
             Ok((Self::V1(chunk), merkle_paths))
         } else if block_header_v3_version.is_none()
@@ -1113,7 +1116,8 @@ impl EncodedShardChunk {
                 tx_root,
                 validator_proposals,
                 signer,
-            );
+            )
+            .map_err(std::io::Error::other)?;
             let chunk = EncodedShardChunkV2 { header: ShardChunkHeader::V2(header), content };
             Ok((Self::V2(chunk), merkle_paths))
         } else {
@@ -1134,7 +1138,8 @@ impl EncodedShardChunk {
                 prev_validator_proposals,
                 congestion_info,
                 signer,
-            );
+            )
+            .map_err(std::io::Error::other)?;
             let chunk = EncodedShardChunkV2 { header: ShardChunkHeader::V3(header), content };
             Ok((Self::V2(chunk), merkle_paths))
         }
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use crate::sharding::{ChunkHash, ShardChunkHeader};
 use crate::types::EpochId;
-use crate::validator_signer::ValidatorSigner;
+use crate::validator_signer::{RemoteSignerError, ValidatorSigner};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::{PublicKey, Signature};
 use near_primitives_core::types::{AccountId, BlockHeight, ProtocolVersion, ShardId};
@@ -25,12 +25,13 @@ impl ChunkEndorsement {
         chunk_header: &ShardChunkHeader,
         signer: &ValidatorSigner,
         protocol_version: ProtocolVersion,
-    ) -> ChunkEndorsement {
-        if ProtocolFeature::ChunkEndorsementV2.enabled(protocol_version) {
-            ChunkEndorsement::V2(ChunkEndorsementV2::new(epoch_id, chunk_header, signer))
+    ) -> Result<ChunkEndorsement, RemoteSignerError> {
+        let endorsement = if ProtocolFeature::ChunkEndorsementV2.enabled(protocol_version) {
+            ChunkEndorsement::V2(ChunkEndorsementV2::new(epoch_id, chunk_header, signer)?)
         } else {
-            ChunkEndorsement::V1(ChunkEndorsementV1::new(chunk_header.chunk_hash(), signer))
-        }
+            ChunkEndorsement::V1(ChunkEndorsementV1::new(chunk_header, signer)?)
+        };
+        Ok(endorsement)
     }
 
     pub fn chunk_hash(&self) -> &ChunkHash {
@@ -66,11 +67,14 @@ pub struct ChunkEndorsementV1 {
 }
 
 impl ChunkEndorsementV1 {
-    pub fn new(chunk_hash: ChunkHash, signer: &ValidatorSigner) -> Self {
-        let inner = ChunkEndorsementInner::new(chunk_hash);
+    pub fn new(
+        chunk_header: &ShardChunkHeader,
+        signer: &ValidatorSigner,
+    ) -> Result<Self, RemoteSignerError> {
+        let inner = ChunkEndorsementInner::new(chunk_header.chunk_hash());
         let account_id = signer.validator_id().clone();
-        let signature = signer.sign_chunk_endorsement(&inner);
-        Self { inner, account_id, signature }
+        let signature = signer.sign_chunk_endorsement(chunk_header)?;
+        Ok(Self { inner, account_id, signature })
     }
 
     pub fn chunk_hash(&self) -> &ChunkHash {
@@ -100,7 +104,7 @@ impl ChunkEndorsementV2 {
         epoch_id: EpochId,
         chunk_header: &ShardChunkHeader,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = ChunkEndorsementInner::new(chunk_header.chunk_hash());
         let metadata = ChunkEndorsementMetadata {
             account_id: signer.validator_id().clone(),
@@ -108,9 +112,9 @@ impl ChunkEndorsementV2 {
             epoch_id,
             height_created: chunk_header.height_created(),
         };
-        let signature = signer.sign_chunk_endorsement(&inner);
-        let metadata_signature = signer.sign_chunk_endorsement_metadata(&metadata);
-        Self { inner, signature, metadata, metadata_signature }
+        let signature = signer.sign_chunk_endorsement(chunk_header)?;
+        let metadata_signature = signer.sign_chunk_endorsement_metadata(&metadata)?;
+        Ok(Self { inner, signature, metadata, metadata_signature })
     }
 
     // TODO(ChunkEndorsementV2): Remove this once we implement tracker_v2
@@ -139,7 +143,7 @@ pub struct ChunkEndorsementInner {
 }
 
 impl ChunkEndorsementInner {
-    fn new(chunk_hash: ChunkHash) -> Self {
+    pub fn new(chunk_hash: ChunkHash) -> Self {
         Self { chunk_hash, signature_differentiator: "ChunkEndorsement".to_owned() }
     }
 }
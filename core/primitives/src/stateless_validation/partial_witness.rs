@@ -3,7 +3,7 @@ use std::fmt::{Debug, Formatter};
 use super::{ChunkProductionKey, SignatureDifferentiator};
 use crate::sharding::ShardChunkHeader;
 use crate::types::EpochId;
-use crate::validator_signer::ValidatorSigner;
+use crate::validator_signer::{RemoteSignerError, ValidatorSigner};
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytesize::ByteSize;
 use near_crypto::{PublicKey, Signature};
@@ -47,7 +47,7 @@ impl PartialEncodedStateWitness {
         part: Vec<u8>,
         encoded_length: usize,
         signer: &ValidatorSigner,
-    ) -> Self {
+    ) -> Result<Self, RemoteSignerError> {
         let inner = PartialEncodedStateWitnessInner::new(
             epoch_id,
             chunk_header,
@@ -55,8 +55,8 @@ impl PartialEncodedStateWitness {
             part,
             encoded_length,
         );
-        let signature = signer.sign_partial_encoded_state_witness(&inner);
-        Self { inner, signature }
+        let signature = signer.sign_partial_encoded_state_witness(&inner)?;
+        Ok(Self { inner, signature })
     }
 
     pub fn chunk_production_key(&self) -> ChunkProductionKey {
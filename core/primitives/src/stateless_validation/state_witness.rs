@@ -241,24 +241,27 @@ impl ChunkStateWitness {
             .enabled(PROTOCOL_VERSION)
             .then_some(CongestionInfo::default());
 
-        let header = ShardChunkHeader::V3(ShardChunkHeaderV3::new(
-            PROTOCOL_VERSION,
-            prev_block_hash,
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            height,
-            shard_id,
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            Default::default(),
-            congestion_info,
-            &EmptyValidatorSigner::default().into(),
-        ));
+        let header = ShardChunkHeader::V3(
+            ShardChunkHeaderV3::new(
+                PROTOCOL_VERSION,
+                prev_block_hash,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                height,
+                shard_id,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                congestion_info,
+                &EmptyValidatorSigner::default().into(),
+            )
+            .unwrap(),
+        );
         Self::new(
             "alice.near".parse().unwrap(),
             EpochId::default(),
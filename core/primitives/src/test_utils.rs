@@ -386,11 +386,13 @@ impl BlockHeader {
     }
 
     pub fn resign(&mut self, signer: &ValidatorSigner) {
-        let (hash, signature) = signer.sign_block_header_parts(
-            *self.prev_hash(),
-            &self.inner_lite_bytes(),
-            &self.inner_rest_bytes(),
-        );
+        let (hash, signature) = signer
+            .sign_block_header_parts(
+                *self.prev_hash(),
+                &self.inner_lite_bytes(),
+                &self.inner_rest_bytes(),
+            )
+            .unwrap();
         match self {
             BlockHeader::BlockHeaderV1(header) => {
                 let header = Arc::make_mut(header);
@@ -562,6 +564,7 @@ impl TestBlockBuilder {
             self.clock,
             None,
         )
+        .unwrap()
     }
 }
 
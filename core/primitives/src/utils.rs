@@ -15,7 +15,7 @@ use crate::version::{
     CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION,
 };
 
-use near_crypto::{ED25519PublicKey, Secp256K1PublicKey};
+use near_crypto::{ED25519PublicKey, EthPublicKey, Secp256K1PublicKey};
 use near_primitives_core::account::id::{AccountId, AccountType};
 
 use std::mem::size_of;
@@ -497,6 +497,12 @@ pub fn derive_eth_implicit_account_id(public_key: &Secp256K1PublicKey) -> Accoun
     format!("0x{}", hex::encode(&pk_hash[12..32])).parse().unwrap()
 }
 
+/// Returns '0x' + address.hex().
+/// This is an ETH-implicit account ID which can be controlled by the ETH key with the given address.
+pub fn derive_eth_implicit_account_id_from_address(address: &EthPublicKey) -> AccountId {
+    format!("0x{}", hex::encode(address)).parse().unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -517,6 +523,10 @@ mod tests {
         let expected: AccountId = "0x96791e923f8cf697ad9c3290f2c9059f0231b24c".parse().unwrap();
         let account_id = derive_eth_implicit_account_id(public_key.unwrap_as_secp256k1());
         assert_eq!(account_id, expected);
+
+        let public_key = PublicKey::from_seed(KeyType::ETH, "test");
+        let account_id = derive_eth_implicit_account_id_from_address(public_key.unwrap_as_eth());
+        assert_eq!(account_id, expected);
     }
 
     #[test]
@@ -8,7 +8,7 @@ use crate::block::{Approval, ApprovalInner, BlockHeader};
 use crate::challenge::ChallengeBody;
 use crate::hash::CryptoHash;
 use crate::network::{AnnounceAccount, PeerId};
-use crate::sharding::ChunkHash;
+use crate::sharding::{ChunkHash, ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderV3};
 use crate::stateless_validation::chunk_endorsement::{
     ChunkEndorsementInner, ChunkEndorsementMetadata,
 };
@@ -17,6 +17,11 @@ use crate::stateless_validation::state_witness::EncodedChunkStateWitness;
 use crate::telemetry::TelemetryInfo;
 use crate::types::{AccountId, BlockHeight, EpochId};
 
+pub mod remote;
+
+pub use remote::{RemoteSignerError, RemoteValidatorSigner};
+use remote::{RemoteSignerRequest, SignKind};
+
 /// Enum for validator signer, that holds validator id and key used for signing data.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ValidatorSigner {
@@ -24,15 +29,22 @@ pub enum ValidatorSigner {
     Empty(EmptyValidatorSigner),
     /// Default validator signer that holds data in memory.
     InMemory(InMemoryValidatorSigner),
+    /// Validator signer that keeps the key in a separate process.
+    Remote(RemoteValidatorSigner),
 }
 
 /// Validator signer that is used to sign blocks and approvals.
+///
+/// Signing may fail only with the remote signer, e.g. when the signer process
+/// is unreachable or refuses to sign a message conflicting with one it has
+/// already signed.
 impl ValidatorSigner {
     /// Account id of the given validator.
     pub fn validator_id(&self) -> &AccountId {
         match self {
             ValidatorSigner::Empty(signer) => signer.validator_id(),
             ValidatorSigner::InMemory(signer) => signer.validator_id(),
+            ValidatorSigner::Remote(signer) => signer.validator_id(),
         }
     }
 
@@ -41,6 +53,7 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(signer) => signer.public_key(),
             ValidatorSigner::InMemory(signer) => signer.public_key(),
+            ValidatorSigner::Remote(signer) => signer.public_key(),
         }
     }
 
@@ -49,6 +62,7 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(signer) => signer.sign_telemetry(info),
             ValidatorSigner::InMemory(signer) => signer.sign_telemetry(info),
+            ValidatorSigner::Remote(signer) => signer.sign_telemetry(info),
         }
     }
 
@@ -58,54 +72,93 @@ impl ValidatorSigner {
         prev_hash: CryptoHash,
         inner_lite: &[u8],
         inner_rest: &[u8],
-    ) -> (CryptoHash, Signature) {
+    ) -> Result<(CryptoHash, Signature), RemoteSignerError> {
         match self {
             ValidatorSigner::Empty(signer) => {
-                signer.sign_block_header_parts(prev_hash, inner_lite, inner_rest)
+                Ok(signer.sign_block_header_parts(prev_hash, inner_lite, inner_rest))
             }
             ValidatorSigner::InMemory(signer) => {
+                Ok(signer.sign_block_header_parts(prev_hash, inner_lite, inner_rest))
+            }
+            ValidatorSigner::Remote(signer) => {
                 signer.sign_block_header_parts(prev_hash, inner_lite, inner_rest)
             }
         }
     }
 
-    /// Signs given inner of the chunk header.
-    pub fn sign_chunk_hash(&self, chunk_hash: &ChunkHash) -> Signature {
+    /// Signs hash of the V1 or V2 chunk header.  The remote signer only signs
+    /// V3 chunk headers, see `sign_chunk_header`.
+    pub fn sign_chunk_hash(&self, chunk_hash: &ChunkHash) -> Result<Signature, RemoteSignerError> {
+        match self {
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_chunk_hash(chunk_hash)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_chunk_hash(chunk_hash)),
+            ValidatorSigner::Remote(_) => {
+                Err(RemoteSignerError::Unsupported("chunk headers older than V3"))
+            }
+        }
+    }
+
+    /// Signs given inner of the V3 chunk header, returning the hash of the
+    /// chunk with the signature.
+    pub fn sign_chunk_header(
+        &self,
+        inner: &ShardChunkHeaderInner,
+    ) -> Result<(ChunkHash, Signature), RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_chunk_hash(chunk_hash),
-            ValidatorSigner::InMemory(signer) => signer.sign_chunk_hash(chunk_hash),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_chunk_header(inner)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_chunk_header(inner)),
+            ValidatorSigner::Remote(signer) => signer.sign_chunk_header(inner),
         }
     }
 
     /// Signs approval of given parent hash and reference hash.
-    pub fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature {
+    pub fn sign_approval(
+        &self,
+        inner: &ApprovalInner,
+        target_height: BlockHeight,
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_approval(inner, target_height),
-            ValidatorSigner::InMemory(signer) => signer.sign_approval(inner, target_height),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_approval(inner, target_height)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_approval(inner, target_height)),
+            ValidatorSigner::Remote(signer) => signer.sign_approval(inner, target_height),
         }
     }
 
-    /// Signs chunk endorsement to be sent to block producer.
-    pub fn sign_chunk_endorsement(&self, inner: &ChunkEndorsementInner) -> Signature {
+    /// Signs endorsement of the chunk to be sent to block producer.
+    pub fn sign_chunk_endorsement(
+        &self,
+        chunk_header: &ShardChunkHeader,
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_chunk_endorsement(inner),
-            ValidatorSigner::InMemory(signer) => signer.sign_chunk_endorsement(inner),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_chunk_endorsement(chunk_header)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_chunk_endorsement(chunk_header)),
+            ValidatorSigner::Remote(signer) => signer.sign_chunk_endorsement(chunk_header),
         }
     }
 
     /// Signs chunk endorsement metadata.
-    pub fn sign_chunk_endorsement_metadata(&self, inner: &ChunkEndorsementMetadata) -> Signature {
+    pub fn sign_chunk_endorsement_metadata(
+        &self,
+        inner: &ChunkEndorsementMetadata,
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_chunk_endorsement_metadata(inner),
-            ValidatorSigner::InMemory(signer) => signer.sign_chunk_endorsement_metadata(inner),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_chunk_endorsement_metadata(inner)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_chunk_endorsement_metadata(inner)),
+            ValidatorSigner::Remote(signer) => signer.sign_chunk_endorsement_metadata(inner),
         }
     }
 
     /// Signs chunk state witness to be sent to all validators.
-    pub fn sign_chunk_state_witness(&self, witness_bytes: &EncodedChunkStateWitness) -> Signature {
+    pub fn sign_chunk_state_witness(
+        &self,
+        witness_bytes: &EncodedChunkStateWitness,
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_chunk_state_witness(witness_bytes),
-            ValidatorSigner::InMemory(signer) => signer.sign_chunk_state_witness(witness_bytes),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_chunk_state_witness(witness_bytes)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_chunk_state_witness(witness_bytes)),
+            ValidatorSigner::Remote(_) => {
+                Err(RemoteSignerError::Unsupported("chunk state witnesses"))
+            }
         }
     }
 
@@ -113,18 +166,25 @@ impl ValidatorSigner {
     pub fn sign_partial_encoded_state_witness(
         &self,
         part: &PartialEncodedStateWitnessInner,
-    ) -> Signature {
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_partial_encoded_state_witness(part),
-            ValidatorSigner::InMemory(signer) => signer.sign_partial_encoded_state_witness(part),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_partial_encoded_state_witness(part)),
+            ValidatorSigner::InMemory(signer) => {
+                Ok(signer.sign_partial_encoded_state_witness(part))
+            }
+            ValidatorSigner::Remote(signer) => signer.sign_partial_encoded_state_witness(part),
         }
     }
 
     /// Signs challenge body.
-    pub fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
+    pub fn sign_challenge(
+        &self,
+        challenge_body: &ChallengeBody,
+    ) -> Result<(CryptoHash, Signature), RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_challenge(challenge_body),
-            ValidatorSigner::InMemory(signer) => signer.sign_challenge(challenge_body),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_challenge(challenge_body)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_challenge(challenge_body)),
+            ValidatorSigner::Remote(signer) => signer.sign_challenge(challenge_body),
         }
     }
 
@@ -134,12 +194,15 @@ impl ValidatorSigner {
         account_id: &AccountId,
         peer_id: &PeerId,
         epoch_id: &EpochId,
-    ) -> Signature {
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
             ValidatorSigner::Empty(signer) => {
-                signer.sign_account_announce(account_id, peer_id, epoch_id)
+                Ok(signer.sign_account_announce(account_id, peer_id, epoch_id))
             }
             ValidatorSigner::InMemory(signer) => {
+                Ok(signer.sign_account_announce(account_id, peer_id, epoch_id))
+            }
+            ValidatorSigner::Remote(signer) => {
                 signer.sign_account_announce(account_id, peer_id, epoch_id)
             }
         }
@@ -156,20 +219,25 @@ impl ValidatorSigner {
     /// used only for networking purposes and are not persisted on chain.
     /// Moving to proto serialization for stuff stored on chain would be way
     /// harder.
-    pub fn sign_account_key_payload(&self, proto_bytes: &[u8]) -> Signature {
+    pub fn sign_account_key_payload(
+        &self,
+        proto_bytes: &[u8],
+    ) -> Result<Signature, RemoteSignerError> {
         match self {
-            ValidatorSigner::Empty(signer) => signer.sign_account_key_payload(proto_bytes),
-            ValidatorSigner::InMemory(signer) => signer.sign_account_key_payload(proto_bytes),
+            ValidatorSigner::Empty(signer) => Ok(signer.sign_account_key_payload(proto_bytes)),
+            ValidatorSigner::InMemory(signer) => Ok(signer.sign_account_key_payload(proto_bytes)),
+            ValidatorSigner::Remote(signer) => signer.sign_account_key_payload(proto_bytes),
         }
     }
 
     pub fn compute_vrf_with_proof(
         &self,
         data: &[u8],
-    ) -> (near_crypto::vrf::Value, near_crypto::vrf::Proof) {
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), RemoteSignerError> {
         match self {
             ValidatorSigner::Empty(_) => unimplemented!(),
-            ValidatorSigner::InMemory(signer) => signer.compute_vrf_with_proof(data),
+            ValidatorSigner::InMemory(signer) => Ok(signer.compute_vrf_with_proof(data)),
+            ValidatorSigner::Remote(signer) => signer.compute_vrf_with_proof(data),
         }
     }
 
@@ -178,6 +246,10 @@ impl ValidatorSigner {
         match self {
             ValidatorSigner::Empty(_) => unimplemented!(),
             ValidatorSigner::InMemory(signer) => signer.write_to_file(path),
+            ValidatorSigner::Remote(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "remote validator signer doesn't hold the key",
+            )),
         }
     }
 }
@@ -194,6 +266,12 @@ impl From<InMemoryValidatorSigner> for ValidatorSigner {
     }
 }
 
+impl From<RemoteValidatorSigner> for ValidatorSigner {
+    fn from(signer: RemoteValidatorSigner) -> Self {
+        ValidatorSigner::Remote(signer)
+    }
+}
+
 /// Test-only signer that "signs" everything with 0s.
 /// Don't use in any production or code that requires signature verification.
 #[derive(smart_default::SmartDefault, Clone, Debug, PartialEq)]
@@ -233,11 +311,15 @@ impl EmptyValidatorSigner {
         Signature::default()
     }
 
+    fn sign_chunk_header(&self, inner: &ShardChunkHeaderInner) -> (ChunkHash, Signature) {
+        (ShardChunkHeaderV3::compute_hash(inner), Signature::default())
+    }
+
     fn sign_approval(&self, _inner: &ApprovalInner, _target_height: BlockHeight) -> Signature {
         Signature::default()
     }
 
-    fn sign_chunk_endorsement(&self, _inner: &ChunkEndorsementInner) -> Signature {
+    fn sign_chunk_endorsement(&self, _chunk_header: &ShardChunkHeader) -> Signature {
         Signature::default()
     }
 
@@ -334,12 +416,19 @@ impl InMemoryValidatorSigner {
         self.signer.sign(chunk_hash.as_ref())
     }
 
+    fn sign_chunk_header(&self, inner: &ShardChunkHeaderInner) -> (ChunkHash, Signature) {
+        let hash = ShardChunkHeaderV3::compute_hash(inner);
+        let signature = self.signer.sign(hash.as_ref());
+        (hash, signature)
+    }
+
     fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature {
         self.signer.sign(&Approval::get_data_for_sig(inner, target_height))
     }
 
-    fn sign_chunk_endorsement(&self, inner: &ChunkEndorsementInner) -> Signature {
-        self.signer.sign(&borsh::to_vec(inner).unwrap())
+    fn sign_chunk_endorsement(&self, chunk_header: &ShardChunkHeader) -> Signature {
+        let inner = ChunkEndorsementInner::new(chunk_header.chunk_hash());
+        self.signer.sign(&borsh::to_vec(&inner).unwrap())
     }
 
     fn sign_chunk_endorsement_metadata(&self, inner: &ChunkEndorsementMetadata) -> Signature {
@@ -388,3 +477,125 @@ impl InMemoryValidatorSigner {
         self.signer.write_to_file(path)
     }
 }
+
+impl RemoteValidatorSigner {
+    fn sign_telemetry(&self, info: &TelemetryInfo) -> serde_json::Value {
+        let mut value = serde_json::to_value(info).expect("Telemetry must serialize to JSON");
+        let content = serde_json::to_string(&value).expect("Telemetry must serialize to JSON");
+        let data = content.into_bytes();
+        let request = RemoteSignerRequest::Sign { kind: SignKind::Telemetry, data: data.clone() };
+        // Telemetry isn't worth reporting an error for.
+        match self.sign(&request, &data) {
+            Ok(signature) => value["signature"] = signature.to_string().into(),
+            Err(err) => tracing::warn!(target: "remote_signer", %err, "failed to sign telemetry"),
+        }
+        value
+    }
+
+    fn sign_block_header_parts(
+        &self,
+        prev_hash: CryptoHash,
+        inner_lite: &[u8],
+        inner_rest: &[u8],
+    ) -> Result<(CryptoHash, Signature), RemoteSignerError> {
+        let hash = BlockHeader::compute_hash(prev_hash, inner_lite, inner_rest);
+        let request = RemoteSignerRequest::SignBlockHeader {
+            prev_hash,
+            inner_lite: inner_lite.to_vec(),
+            inner_rest: inner_rest.to_vec(),
+        };
+        Ok((hash, self.sign(&request, hash.as_ref())?))
+    }
+
+    fn sign_chunk_header(
+        &self,
+        inner: &ShardChunkHeaderInner,
+    ) -> Result<(ChunkHash, Signature), RemoteSignerError> {
+        let hash = ShardChunkHeaderV3::compute_hash(inner);
+        let request = RemoteSignerRequest::SignChunkHeader { inner: borsh::to_vec(inner).unwrap() };
+        Ok((hash.clone(), self.sign(&request, hash.as_ref())?))
+    }
+
+    fn sign_approval(
+        &self,
+        inner: &ApprovalInner,
+        target_height: BlockHeight,
+    ) -> Result<Signature, RemoteSignerError> {
+        let request = RemoteSignerRequest::SignApproval {
+            inner: borsh::to_vec(inner).unwrap(),
+            target_height,
+        };
+        self.sign(&request, &Approval::get_data_for_sig(inner, target_height))
+    }
+
+    fn sign_chunk_endorsement(
+        &self,
+        chunk_header: &ShardChunkHeader,
+    ) -> Result<Signature, RemoteSignerError> {
+        let ShardChunkHeader::V3(header) = chunk_header else {
+            return Err(RemoteSignerError::Unsupported("endorsements of chunks older than V3"));
+        };
+        let request = RemoteSignerRequest::SignChunkEndorsement {
+            chunk_header_inner: borsh::to_vec(&header.inner).unwrap(),
+        };
+        let inner = ChunkEndorsementInner::new(chunk_header.chunk_hash());
+        self.sign(&request, &borsh::to_vec(&inner).unwrap())
+    }
+
+    fn sign_chunk_endorsement_metadata(
+        &self,
+        inner: &ChunkEndorsementMetadata,
+    ) -> Result<Signature, RemoteSignerError> {
+        let data = borsh::to_vec(inner).unwrap();
+        let request = RemoteSignerRequest::Sign {
+            kind: SignKind::ChunkEndorsementMetadata,
+            data: data.clone(),
+        };
+        self.sign(&request, &data)
+    }
+
+    fn sign_partial_encoded_state_witness(
+        &self,
+        part: &PartialEncodedStateWitnessInner,
+    ) -> Result<Signature, RemoteSignerError> {
+        let data = borsh::to_vec(part).unwrap();
+        let request = RemoteSignerRequest::Sign {
+            kind: SignKind::PartialEncodedStateWitness,
+            data: data.clone(),
+        };
+        self.sign(&request, &data)
+    }
+
+    fn sign_challenge(
+        &self,
+        challenge_body: &ChallengeBody,
+    ) -> Result<(CryptoHash, Signature), RemoteSignerError> {
+        let hash = CryptoHash::hash_borsh(challenge_body);
+        let request =
+            RemoteSignerRequest::SignChallenge { body: borsh::to_vec(challenge_body).unwrap() };
+        Ok((hash, self.sign(&request, hash.as_ref())?))
+    }
+
+    fn sign_account_announce(
+        &self,
+        account_id: &AccountId,
+        peer_id: &PeerId,
+        epoch_id: &EpochId,
+    ) -> Result<Signature, RemoteSignerError> {
+        let hash = AnnounceAccount::build_header_hash(account_id, peer_id, epoch_id);
+        let request = RemoteSignerRequest::SignAccountAnnounce {
+            account_id: account_id.clone(),
+            peer_id: peer_id.clone(),
+            epoch_id: *epoch_id,
+        };
+        self.sign(&request, hash.as_ref())
+    }
+
+    fn sign_account_key_payload(&self, proto_bytes: &[u8]) -> Result<Signature, RemoteSignerError> {
+        let request = RemoteSignerRequest::Sign {
+            kind: SignKind::AccountKeyPayload,
+            data: proto_bytes.to_vec(),
+        };
+        self.sign(&request, proto_bytes)
+    }
+}
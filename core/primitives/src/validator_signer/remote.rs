@@ -0,0 +1,326 @@
+//! Validator signer which keeps the validator key in a separate process.
+//!
+//! The node connects to the signer over a Unix socket and exchanges
+//! newline-delimited JSON messages with it.  Each request is answered with
+//! exactly one response, in order:
+//!
+//! ```text
+//! -> {"type":"public_key"}
+//! <- {"type":"public_key","public_key":"ed25519:..."}
+//! -> {"type":"sign_approval","inner":"<base64>","target_height":42}
+//! <- {"type":"signature","signature":"ed25519:..."}
+//! -> {"type":"compute_vrf","data":"<base64>"}
+//! <- {"type":"vrf","value":"...","proof":"..."}
+//! <- {"type":"error","message":"..."}
+//! ```
+//!
+//! Block headers, chunk headers, approvals and chunk endorsements, which a
+//! validator must never sign twice at the same height, are sent as their
+//! borsh-serialized parts rather than as the bytes to sign.  The signer
+//! computes the signed message and its height from them, so that it can
+//! refuse to sign two different messages of the same kind at the same height
+//! (see `near-remote-signer` for the reference implementation).  The rest of
+//! the messages are sent with `sign` and signed as they are.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use near_crypto::{PublicKey, Signature};
+use serde_with::base64::Base64;
+use serde_with::serde_as;
+
+use crate::hash::CryptoHash;
+use crate::network::PeerId;
+use crate::types::{AccountId, BlockHeight, EpochId};
+
+/// Kind of the message the node asks the remote signer to sign as it is.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SignKind {
+    /// JSON-serialized `TelemetryInfo`.
+    Telemetry,
+    /// Borsh-serialized `ChunkEndorsementMetadata`.
+    ChunkEndorsementMetadata,
+    /// Borsh-serialized `PartialEncodedStateWitnessInner`.
+    PartialEncodedStateWitness,
+    /// Proto-serialized `AccountKeyPayload`.
+    AccountKeyPayload,
+}
+
+#[serde_as]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteSignerRequest {
+    PublicKey,
+    /// Signs `BlockHeader::compute_hash` of the parts of the header.  The
+    /// height is read from `inner_lite`.
+    SignBlockHeader {
+        prev_hash: CryptoHash,
+        #[serde_as(as = "Base64")]
+        inner_lite: Vec<u8>,
+        #[serde_as(as = "Base64")]
+        inner_rest: Vec<u8>,
+    },
+    /// Signs `ShardChunkHeaderV3::compute_hash` of the borsh-serialized
+    /// `ShardChunkHeaderInner`.
+    SignChunkHeader {
+        #[serde_as(as = "Base64")]
+        inner: Vec<u8>,
+    },
+    /// Signs `Approval::get_data_for_sig` of the borsh-serialized
+    /// `ApprovalInner`.
+    SignApproval {
+        #[serde_as(as = "Base64")]
+        inner: Vec<u8>,
+        target_height: BlockHeight,
+    },
+    /// Signs the `ChunkEndorsementInner` of the chunk with the given
+    /// borsh-serialized `ShardChunkHeaderInner`.
+    SignChunkEndorsement {
+        #[serde_as(as = "Base64")]
+        chunk_header_inner: Vec<u8>,
+    },
+    /// Signs the hash of the borsh-serialized `ChallengeBody`.
+    SignChallenge {
+        #[serde_as(as = "Base64")]
+        body: Vec<u8>,
+    },
+    /// Signs `AnnounceAccount::build_header_hash` of the announcement.
+    SignAccountAnnounce {
+        account_id: AccountId,
+        peer_id: PeerId,
+        epoch_id: EpochId,
+    },
+    Sign {
+        kind: SignKind,
+        #[serde_as(as = "Base64")]
+        data: Vec<u8>,
+    },
+    ComputeVrf {
+        #[serde_as(as = "Base64")]
+        data: Vec<u8>,
+    },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteSignerResponse {
+    PublicKey { public_key: PublicKey },
+    Signature { signature: Signature },
+    Vrf { value: near_crypto::vrf::Value, proof: near_crypto::vrf::Proof },
+    Error { message: String },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RemoteSignerError {
+    #[error("failed to talk to the remote signer: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed message from the remote signer: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("remote signer refused the request: {0}")]
+    Refused(String),
+    #[error("unexpected response from the remote signer: {0:?}")]
+    UnexpectedResponse(RemoteSignerResponse),
+    #[error("remote signer doesn't support signing {0}")]
+    Unsupported(&'static str),
+}
+
+/// Signer that forwards all signing requests to a signer process listening on
+/// a Unix socket.
+///
+/// Each request is sent over a connection taken from a pool, so that the
+/// requests of different threads don't wait for each other.  Broken
+/// connections are dropped and new ones are opened as needed, so the signer
+/// process may be restarted while the node is running.
+#[derive(Clone)]
+pub struct RemoteValidatorSigner {
+    account_id: AccountId,
+    public_key: PublicKey,
+    socket_path: PathBuf,
+    timeout: Duration,
+    /// Idle connections.  The lock is only held to take or return one.
+    connections: Arc<Mutex<Vec<Connection>>>,
+}
+
+struct Connection {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Connection {
+    #[cfg(unix)]
+    fn open(socket_path: &Path, timeout: Duration) -> std::io::Result<Self> {
+        let stream = std::os::unix::net::UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        let reader = Box::new(stream.try_clone()?);
+        Ok(Self { reader: BufReader::new(reader), writer: Box::new(stream) })
+    }
+
+    #[cfg(not(unix))]
+    fn open(_socket_path: &Path, _timeout: Duration) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "remote validator signer requires Unix sockets",
+        ))
+    }
+
+    fn call(
+        &mut self,
+        request: &RemoteSignerRequest,
+    ) -> Result<RemoteSignerResponse, RemoteSignerError> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+impl RemoteValidatorSigner {
+    /// Connects to the signer and fetches the public key of the validator.
+    pub fn connect(
+        account_id: AccountId,
+        socket_path: PathBuf,
+        timeout: Duration,
+    ) -> Result<Self, RemoteSignerError> {
+        let mut connection = Connection::open(&socket_path, timeout)?;
+        let public_key = match connection.call(&RemoteSignerRequest::PublicKey)? {
+            RemoteSignerResponse::PublicKey { public_key } => public_key,
+            RemoteSignerResponse::Error { message } => {
+                return Err(RemoteSignerError::Refused(message))
+            }
+            response => return Err(RemoteSignerError::UnexpectedResponse(response)),
+        };
+        Ok(Self {
+            account_id,
+            public_key,
+            socket_path,
+            timeout,
+            connections: Arc::new(Mutex::new(vec![connection])),
+        })
+    }
+
+    pub fn validator_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Sends a signing request to the signer.  `data` is the message the
+    /// node expects the signer to sign for the request; the returned
+    /// signature is checked against it and the validator key, so a
+    /// misconfigured signer can't make the node broadcast invalid signatures.
+    pub fn sign(
+        &self,
+        request: &RemoteSignerRequest,
+        data: &[u8],
+    ) -> Result<Signature, RemoteSignerError> {
+        match self.call(request)? {
+            RemoteSignerResponse::Signature { signature } => {
+                if !signature.verify(data, &self.public_key) {
+                    return Err(RemoteSignerError::Refused(format!(
+                        "signature doesn't match public key {}",
+                        self.public_key
+                    )));
+                }
+                Ok(signature)
+            }
+            RemoteSignerResponse::Error { message } => Err(RemoteSignerError::Refused(message)),
+            response => Err(RemoteSignerError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn compute_vrf_with_proof(
+        &self,
+        data: &[u8],
+    ) -> Result<(near_crypto::vrf::Value, near_crypto::vrf::Proof), RemoteSignerError> {
+        match self.call(&RemoteSignerRequest::ComputeVrf { data: data.to_vec() })? {
+            RemoteSignerResponse::Vrf { value, proof } => Ok((value, proof)),
+            RemoteSignerResponse::Error { message } => Err(RemoteSignerError::Refused(message)),
+            response => Err(RemoteSignerError::UnexpectedResponse(response)),
+        }
+    }
+
+    /// Sends the request over an idle connection, or a new one if there is
+    /// none.  A request which fails with an I/O error on an idle connection
+    /// is retried once on a new connection, since the signer may have been
+    /// restarted in the meantime.
+    fn call(
+        &self,
+        request: &RemoteSignerRequest,
+    ) -> Result<RemoteSignerResponse, RemoteSignerError> {
+        let idle = self.connections.lock().unwrap().pop();
+        let (mut connection, reused) = match idle {
+            Some(connection) => (connection, true),
+            None => (Connection::open(&self.socket_path, self.timeout)?, false),
+        };
+        let result = match connection.call(request) {
+            Err(RemoteSignerError::Io(err)) if reused => {
+                tracing::warn!(target: "remote_signer", ?err, "remote signer connection failed, reconnecting");
+                connection = Connection::open(&self.socket_path, self.timeout)?;
+                connection.call(request)
+            }
+            result => result,
+        };
+        // After an error the stream may be left in the middle of a message,
+        // so the connection is only reused after a successful call.
+        if result.is_ok() {
+            self.connections.lock().unwrap().push(connection);
+        }
+        result
+    }
+}
+
+impl std::fmt::Debug for RemoteValidatorSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteValidatorSigner")
+            .field("account_id", &self.account_id)
+            .field("public_key", &self.public_key)
+            .field("socket_path", &self.socket_path)
+            .finish()
+    }
+}
+
+impl PartialEq for RemoteValidatorSigner {
+    fn eq(&self, other: &Self) -> bool {
+        self.account_id == other.account_id
+            && self.public_key == other.public_key
+            && self.socket_path == other.socket_path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_format() {
+        let request = RemoteSignerRequest::SignApproval { inner: vec![1, 2, 3], target_height: 42 };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"type":"sign_approval","inner":"AQID","target_height":42}"#);
+        assert_eq!(serde_json::from_str::<RemoteSignerRequest>(&json).unwrap(), request);
+
+        let request = RemoteSignerRequest::Sign { kind: SignKind::Telemetry, data: vec![1, 2, 3] };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"type":"sign","kind":"telemetry","data":"AQID"}"#);
+
+        let json = serde_json::to_string(&RemoteSignerRequest::PublicKey).unwrap();
+        assert_eq!(json, r#"{"type":"public_key"}"#);
+
+        let response: RemoteSignerResponse =
+            serde_json::from_str(r#"{"type":"error","message":"double sign"}"#).unwrap();
+        assert_eq!(response, RemoteSignerResponse::Error { message: "double sign".to_string() });
+    }
+}
@@ -216,6 +216,10 @@ pub struct ViewStateResult {
     #[serde_as(as = "Vec<Base64>")]
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub proof: Vec<Arc<[u8]>>,
+    /// Set by [`QueryRequest::ViewStatePaged`] if there are more entries
+    /// after `values`; pass it in the next request to fetch them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<StoreKey>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Default)]
@@ -256,6 +260,8 @@ pub struct KnownPeerStateView {
     pub first_seen: i64,
     pub last_seen: i64,
     pub last_attempt: Option<(i64, String)>,
+    /// Reputation score of the peer.
+    pub reputation: i64,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -303,6 +309,23 @@ pub enum QueryRequest {
         #[serde(default, skip_serializing_if = "is_false")]
         include_proof: bool,
     },
+    /// Like [`Self::ViewState`], but returns at most `limit` entries starting
+    /// after `continuation_token`, so that contracts with state too large for
+    /// a single response can still be viewed.
+    ViewStatePaged {
+        account_id: AccountId,
+        #[serde(rename = "prefix_base64")]
+        prefix: StoreKey,
+        /// Token returned with the previous page, or `None` for the first one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        continuation_token: Option<StoreKey>,
+        /// Maximum number of entries in the page.  The node caps it, and may
+        /// return fewer entries to keep the response size within its limits.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        include_proof: bool,
+    },
     ViewAccessKey {
         account_id: AccountId,
         public_key: PublicKey,
@@ -370,6 +393,8 @@ pub struct PeerInfoView {
     pub is_outbound_peer: bool,
     /// Connection nonce.
     pub nonce: u64,
+    /// Reputation score of the peer.
+    pub reputation: i64,
 }
 
 /// Information about a Producer: its account name, peer_id and a list of connected peers that
@@ -2229,7 +2254,7 @@ pub struct GasPriceView {
 ///
 /// [serializable view]: ./index.html
 /// [`StateChangesRequest`]: ../types/struct.StateChangesRequest.html
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "changes_type", rename_all = "snake_case")]
 pub enum StateChangesRequestView {
     AccountChanges {
@@ -2439,6 +2464,26 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
 
+/// Result of the simulation of a transaction.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct SimulateTransactionView {
+    /// Block on top of which the transaction was simulated.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Outcomes of the transaction and of the receipts it produced, in the
+    /// order of execution.
+    pub outcomes: Vec<ExecutionOutcomeWithIdView>,
+    pub total_gas_burnt: Gas,
+    #[serde(with = "dec_format")]
+    pub total_tokens_burnt: Balance,
+    /// Gas used by all the receipts broken down by cost.
+    pub gas_profile: Vec<CostGasUsed>,
+    pub state_changes: StateChangesView,
+    /// Receipts which weren't executed, because they are waiting for data or
+    /// were sent to shards the node doesn't track.
+    pub unfinished_receipt_ids: Vec<CryptoHash>,
+}
+
 /// Maintenance windows view are a vector of maintenance window.
 pub type MaintenanceWindowsView = Vec<Range<BlockHeight>>;
 
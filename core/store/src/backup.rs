@@ -0,0 +1,570 @@
+//! Incremental, content-addressed backups of a database.
+//!
+//! A backup directory has the following layout:
+//!
+//! ```text
+//! <backup dir>/
+//!   manifests/<id>.json  -- files making up the database in backup <id>
+//!   objects/<sha256>     -- contents of the files, named after their hash
+//! ```
+//!
+//! A backup is made from a checkpoint of the database, so it can be made
+//! while the node is running.  Contents of files are stored only once no
+//! matter in how many backups they appear.  Since RocksDB never modifies an
+//! SST file once written, SST files which have the same name and size as in
+//! the previous backup of the same database, as identified by its `IDENTITY`
+//! file, aren’t even read again.  This makes consecutive backups of a large
+//! database cheap: only SST files written since the previous backup are
+//! copied.
+//!
+//! Nothing in the layout requires a local disk, so the backup directory may
+//! be a mount point of an object store.  However, a backup directory must
+//! hold backups of a single database only since manifests of different
+//! databases would overwrite each other.  Config validation rejects hot and
+//! cold databases backed up into the same directory.
+use crate::{metrics, Store, Temperature};
+use actix_rt::ArbiterHandle;
+use near_time::{Clock, Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the manifest format.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Name of the directory inside of the database directory where the
+/// checkpoint the backup is made from is created.  Checkpoint needs to be on
+/// the same file system as the database so that files can be hard linked.
+const CHECKPOINT_DIR: &str = "backup-checkpoint";
+
+const MANIFESTS_DIR: &str = "manifests";
+const OBJECTS_DIR: &str = "objects";
+
+/// File holding the unique id RocksDB assigns to a database when creating it.
+/// File numbers, and thus names of SST files, are only unique within a
+/// database.
+const IDENTITY_FILE: &str = "IDENTITY";
+
+/// Files making up the database at the time a backup was made.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub id: u64,
+    #[serde(with = "near_time::serde_utc_as_iso")]
+    pub created_at: Utc,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupFile {
+    /// Path to the file relative to the database directory.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the file which is also the name of the object
+    /// holding its contents.
+    pub hash: String,
+}
+
+impl BackupManifest {
+    /// Returns total size of the database in the backup.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// Summary of a newly made backup.
+#[derive(Debug)]
+pub struct BackupStats {
+    pub manifest: BackupManifest,
+    /// Number of files whose contents weren’t in the backup directory yet.
+    pub copied_files: usize,
+    pub copied_bytes: u64,
+}
+
+/// A problem with an object found when verifying or restoring a backup.
+#[derive(Debug, thiserror::Error)]
+pub enum BackupFileError {
+    #[error("{}: object {hash} is missing", path.display())]
+    Missing { path: PathBuf, hash: String },
+    #[error("{}: object {hash} has {got} bytes but {want} were expected", path.display())]
+    SizeMismatch { path: PathBuf, hash: String, got: u64, want: u64 },
+    #[error("{}: object {hash} has hash {got}", path.display())]
+    HashMismatch { path: PathBuf, hash: String, got: String },
+}
+
+/// A directory holding backups of a database.
+pub struct BackupDir {
+    path: PathBuf,
+}
+
+impl BackupDir {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn manifest_path(&self, id: u64) -> PathBuf {
+        self.path.join(MANIFESTS_DIR).join(format!("{id:010}.json"))
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.path.join(OBJECTS_DIR).join(hash)
+    }
+
+    /// Returns ids of all backups in the directory in ascending order.
+    pub fn list(&self) -> io::Result<Vec<u64>> {
+        let dir = match std::fs::read_dir(self.path.join(MANIFESTS_DIR)) {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut ids = Vec::new();
+        for entry in dir {
+            let name = entry?.file_name();
+            let id = name.to_str().and_then(|name| name.strip_suffix(".json"));
+            if let Some(id) = id.and_then(|id| id.parse().ok()) {
+                ids.push(id);
+            }
+        }
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// Returns id of the most recent backup or `None` if there are no backups.
+    pub fn latest(&self) -> io::Result<Option<u64>> {
+        Ok(self.list()?.last().copied())
+    }
+
+    pub fn read_manifest(&self, id: u64) -> anyhow::Result<BackupManifest> {
+        let path = self.manifest_path(id);
+        let data = std::fs::read(&path)
+            .map_err(|err| anyhow::anyhow!("failed to read {}: {err}", path.display()))?;
+        let manifest: BackupManifest = serde_json::from_slice(&data)
+            .map_err(|err| anyhow::anyhow!("failed to parse {}: {err}", path.display()))?;
+        anyhow::ensure!(
+            manifest.version == MANIFEST_VERSION,
+            "{}: unsupported manifest version {}",
+            path.display(),
+            manifest.version
+        );
+        Ok(manifest)
+    }
+
+    /// Makes a new backup of the database at `db_path` which is open as
+    /// `store`.
+    ///
+    /// Creates a checkpoint of the database inside of `db_path`, backs it up
+    /// with [`Self::backup_directory`] and removes the checkpoint.
+    pub fn create(
+        &self,
+        clock: &Clock,
+        store: &Store,
+        db_path: &Path,
+    ) -> anyhow::Result<BackupStats> {
+        let checkpoint_path = db_path.join(CHECKPOINT_DIR);
+        if checkpoint_path.exists() {
+            // Left over from an interrupted backup.
+            std::fs::remove_dir_all(&checkpoint_path)?;
+        }
+        store.storage.create_checkpoint(&checkpoint_path, None)?;
+        let result = self.backup_directory(clock, &checkpoint_path);
+        std::fs::remove_dir_all(&checkpoint_path)?;
+        result
+    }
+
+    /// Makes a new backup of database files in `src`.  The database must not
+    /// be modified while this runs.
+    pub fn backup_directory(&self, clock: &Clock, src: &Path) -> anyhow::Result<BackupStats> {
+        std::fs::create_dir_all(self.path.join(MANIFESTS_DIR))?;
+        std::fs::create_dir_all(self.path.join(OBJECTS_DIR))?;
+        let previous = match self.latest()? {
+            Some(id) => Some(self.read_manifest(id)?),
+            None => None,
+        };
+        // Files of the previous backup can only be reused if it's a backup of the
+        // same database, e.g. not of a database restored from an older backup
+        // or recreated by state sync.
+        let identity_hash = match std::fs::File::open(src.join(IDENTITY_FILE)) {
+            Ok(mut identity) => Some(copy_hashed(&mut identity, &mut io::sink())?.0),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        let same_database = identity_hash.is_some_and(|identity_hash| {
+            previous
+                .iter()
+                .flat_map(|previous| &previous.files)
+                .any(|file| file.path == Path::new(IDENTITY_FILE) && file.hash == identity_hash)
+        });
+        let previous_files: HashMap<&Path, &BackupFile> = previous
+            .iter()
+            .filter(|_| same_database)
+            .flat_map(|previous| &previous.files)
+            .filter(|file| is_immutable(&file.path))
+            .map(|file| (file.path.as_path(), file))
+            .collect();
+
+        let mut paths = Vec::new();
+        list_files(src, Path::new(""), &mut paths)?;
+        paths.sort();
+
+        let mut files = Vec::with_capacity(paths.len());
+        let mut copied_files = 0;
+        let mut copied_bytes = 0;
+        for path in paths {
+            let full_path = src.join(&path);
+            let size = std::fs::metadata(&full_path)?.len();
+            if let Some(&file) = previous_files.get(path.as_path()) {
+                if file.size == size && self.object_path(&file.hash).exists() {
+                    files.push(file.clone());
+                    continue;
+                }
+            }
+            let (hash, copied) = self.store_object(&full_path)?;
+            if copied {
+                copied_files += 1;
+                copied_bytes += size;
+            }
+            files.push(BackupFile { path, size, hash });
+        }
+
+        let id = previous.as_ref().map_or(0, |previous| previous.id + 1);
+        let manifest =
+            BackupManifest { version: MANIFEST_VERSION, id, created_at: clock.now_utc(), files };
+        let manifest_path = self.manifest_path(id);
+        let tmp_path = manifest_path.with_extension("json.tmp");
+        let mut file = std::fs::File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(&mut file, &manifest)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &manifest_path)?;
+        Ok(BackupStats { manifest, copied_files, copied_bytes })
+    }
+
+    /// Copies file at `path` into the objects directory unless an object with
+    /// the same contents already exists.  Returns hash of the file and whether
+    /// it has been copied.
+    ///
+    /// The file is copied under a temporary name and renamed once complete,
+    /// so an object is never half-written even if the copy is interrupted.
+    fn store_object(&self, path: &Path) -> anyhow::Result<(String, bool)> {
+        let (hash, _) = copy_hashed(&mut std::fs::File::open(path)?, &mut io::sink())?;
+        let object_path = self.object_path(&hash);
+        if object_path.exists() {
+            return Ok((hash, false));
+        }
+        let suffix: u64 = rand::random();
+        let tmp_path = self.path.join(OBJECTS_DIR).join(format!("{hash}.{suffix:016x}.tmp"));
+        let result = (|| {
+            let mut tmp = std::fs::File::create(&tmp_path)?;
+            let (copied_hash, _) = copy_hashed(&mut std::fs::File::open(path)?, &mut tmp)?;
+            anyhow::ensure!(
+                copied_hash == hash,
+                "{} has been modified while being backed up",
+                path.display()
+            );
+            tmp.sync_all()?;
+            std::fs::rename(&tmp_path, &object_path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result.map(|()| (hash, true))
+    }
+
+    /// Checks that every object referenced by backup `id` exists and has the
+    /// size and hash recorded in the manifest.  Returns all problems found.
+    pub fn verify(&self, id: u64) -> anyhow::Result<Vec<BackupFileError>> {
+        let manifest = self.read_manifest(id)?;
+        let mut errors = Vec::new();
+        for file in &manifest.files {
+            if let Err(err) = self.copy_object(file, &mut io::sink())? {
+                errors.push(err);
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Restores database from backup `id` into `destination` which must not
+    /// exist or be an empty directory.  Fails if any object doesn’t match the
+    /// manifest, in which case `destination` is left as it was.
+    pub fn restore(&self, id: u64, destination: &Path) -> anyhow::Result<BackupManifest> {
+        let manifest = self.read_manifest(id)?;
+        let existed = destination.exists();
+        if existed {
+            anyhow::ensure!(
+                std::fs::read_dir(destination)?.next().is_none(),
+                "{} is not empty",
+                destination.display()
+            );
+        }
+        let result = manifest.files.iter().try_for_each(|file| -> anyhow::Result<()> {
+            let path = destination.join(&file.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut out = std::fs::File::create(&path)?;
+            self.copy_object(file, &mut out)??;
+            out.sync_all()?;
+            Ok(())
+        });
+        if let Err(err) = result {
+            // Don't leave a partially restored database behind.
+            if let Err(cleanup_err) = remove_restored(destination, existed) {
+                tracing::warn!(target: "backup", ?cleanup_err, destination = %destination.display(), "Failed to clean up after a failed restore");
+            }
+            return Err(err);
+        }
+        Ok(manifest)
+    }
+
+    /// Copies object holding contents of `file` into `out` verifying its size
+    /// and hash on the way.
+    fn copy_object(
+        &self,
+        file: &BackupFile,
+        out: &mut impl Write,
+    ) -> io::Result<Result<(), BackupFileError>> {
+        let mut object = match std::fs::File::open(self.object_path(&file.hash)) {
+            Ok(object) => object,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Err(BackupFileError::Missing {
+                    path: file.path.clone(),
+                    hash: file.hash.clone(),
+                }));
+            }
+            Err(err) => return Err(err),
+        };
+        let (hash, size) = copy_hashed(&mut object, out)?;
+        Ok(if size != file.size {
+            Err(BackupFileError::SizeMismatch {
+                path: file.path.clone(),
+                hash: file.hash.clone(),
+                got: size,
+                want: file.size,
+            })
+        } else if hash != file.hash {
+            Err(BackupFileError::HashMismatch {
+                path: file.path.clone(),
+                hash: file.hash.clone(),
+                got: hash,
+            })
+        } else {
+            Ok(())
+        })
+    }
+}
+
+/// Removes the files written by a failed restore into `destination`, and
+/// `destination` itself unless it `existed` before.
+fn remove_restored(destination: &Path, existed: bool) -> io::Result<()> {
+    if !existed {
+        return std::fs::remove_dir_all(destination);
+    }
+    for entry in std::fs::read_dir(destination)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            std::fs::remove_dir_all(entry.path())?;
+        } else {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file is never modified once written, in which case file with
+/// the same name and size of the same database is known to have the same
+/// contents.
+fn is_immutable(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "sst" || ext == "blob")
+}
+
+/// Adds paths of all files in `root.join(dir)` relative to `root` to `out`.
+fn list_files(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(root.join(dir))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            list_files(root, &path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Copies `src` into `dst`.  Returns hex-encoded SHA-256 of the data and its
+/// length.
+fn copy_hashed(src: &mut impl Read, dst: &mut impl Write) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 20];
+    let mut size = 0;
+    loop {
+        let n = match src.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        hasher.update(&buf[..n]);
+        dst.write_all(&buf[..n])?;
+        size += n as u64;
+    }
+    dst.flush()?;
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// Spawns a loop which makes a backup of the database at `db_path` into
+/// `backup_dir` every `period`.
+pub fn spawn_backup_loop(
+    clock: Clock,
+    store: Store,
+    temperature: Temperature,
+    db_path: PathBuf,
+    backup_dir: PathBuf,
+    period: Duration,
+) -> ArbiterHandle {
+    tracing::info!(
+        target: "backup",
+        ?temperature,
+        backup_dir = %backup_dir.display(),
+        "Spawning the backup loop."
+    );
+    let arbiter = actix_rt::Arbiter::new();
+    let mut interval = actix_rt::time::interval(period.unsigned_abs());
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let label: &str = temperature.into();
+    let backup_dir = BackupDir::new(backup_dir);
+
+    arbiter.spawn(async move {
+        loop {
+            interval.tick().await;
+            match backup_dir.create(&clock, &store, &db_path) {
+                Ok(stats) => {
+                    tracing::info!(
+                        target: "backup",
+                        ?temperature,
+                        id = stats.manifest.id,
+                        files = stats.manifest.files.len(),
+                        copied_files = stats.copied_files,
+                        copied_bytes = stats.copied_bytes,
+                        "Backup created",
+                    );
+                    metrics::BACKUP_LAST_ID
+                        .with_label_values(&[label])
+                        .set(stats.manifest.id as i64);
+                    metrics::BACKUP_COPIED_BYTES
+                        .with_label_values(&[label])
+                        .inc_by(stats.copied_bytes);
+                }
+                Err(err) => {
+                    tracing::error!(
+                        target: "backup",
+                        ?temperature,
+                        ?err,
+                        "Failed to create a backup"
+                    );
+                    metrics::BACKUP_FAILURES.with_label_values(&[label]).inc();
+                }
+            }
+        }
+    });
+
+    arbiter.handle()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackupDir, BackupFileError};
+    use crate::{DBCol, NodeStorage, StoreConfig};
+    use near_time::Clock;
+
+    fn write(storage: &NodeStorage, key: u8) {
+        let store = storage.get_hot_store();
+        let mut update = store.store_update();
+        update.insert(DBCol::Block, vec![key], vec![key; 1024]);
+        update.commit().unwrap();
+    }
+
+    #[test]
+    fn test_incremental_backup_and_restore() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let backup_path = home_dir.path().join("backup");
+        let store_config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+        let storage = opener.open().unwrap();
+        let backup_dir = BackupDir::new(&backup_path);
+        let clock = Clock::real();
+
+        write(&storage, 0);
+        let first = backup_dir.create(&clock, &storage.get_hot_store(), opener.path()).unwrap();
+        assert_eq!(first.manifest.id, 0);
+        assert_eq!(first.copied_files, first.manifest.files.len());
+
+        write(&storage, 1);
+        let second = backup_dir.create(&clock, &storage.get_hot_store(), opener.path()).unwrap();
+        assert_eq!(second.manifest.id, 1);
+        // SST file holding the first key is reused.
+        assert!(second.copied_files < second.manifest.files.len());
+        assert!(second.manifest.files.iter().any(|file| {
+            file.path.extension().is_some_and(|ext| ext == "sst")
+                && first.manifest.files.contains(file)
+        }));
+        assert!(!home_dir.path().join("data").join(super::CHECKPOINT_DIR).exists());
+        assert_eq!(backup_dir.list().unwrap(), vec![0, 1]);
+        assert!(backup_dir.verify(1).unwrap().is_empty());
+
+        let restored_home = tempfile::tempdir().unwrap();
+        backup_dir.restore(1, &restored_home.path().join("data")).unwrap();
+        let restored = NodeStorage::opener(restored_home.path(), false, &store_config, None)
+            .open_in_mode(crate::Mode::ReadOnly)
+            .unwrap();
+        for key in [0, 1] {
+            assert!(restored.get_hot_store().exists(DBCol::Block, &[key]).unwrap());
+        }
+
+        // Corrupt one of the objects.
+        let file = &second.manifest.files[0];
+        std::fs::write(backup_dir.object_path(&file.hash), b"corrupted").unwrap();
+        let errors = backup_dir.verify(1).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], BackupFileError::SizeMismatch { .. }));
+        let destination = tempfile::tempdir().unwrap();
+        assert!(backup_dir.restore(1, destination.path()).is_err());
+        // Nothing is left behind.
+        assert!(std::fs::read_dir(destination.path()).unwrap().next().is_none());
+        let missing_destination = destination.path().join("data");
+        assert!(backup_dir.restore(1, &missing_destination).is_err());
+        assert!(!missing_destination.exists());
+    }
+
+    #[test]
+    fn test_backup_of_another_database() {
+        let backup_path = tempfile::tempdir().unwrap();
+        let backup_dir = BackupDir::new(backup_path.path());
+        let store_config = StoreConfig::test_config();
+        let clock = Clock::real();
+
+        // Both databases have SST files with the same names and sizes, which
+        // must not be reused across the databases.
+        for key in [0, 1] {
+            let home_dir = tempfile::tempdir().unwrap();
+            let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+            let storage = opener.open().unwrap();
+            write(&storage, key);
+            backup_dir.create(&clock, &storage.get_hot_store(), opener.path()).unwrap();
+        }
+        assert!(backup_dir.verify(1).unwrap().is_empty());
+
+        let restored_home = tempfile::tempdir().unwrap();
+        backup_dir.restore(1, &restored_home.path().join("data")).unwrap();
+        let restored = NodeStorage::opener(restored_home.path(), false, &store_config, None)
+            .open_in_mode(crate::Mode::ReadOnly)
+            .unwrap();
+        assert!(!restored.get_hot_store().exists(DBCol::Block, &[0]).unwrap());
+        assert!(restored.get_hot_store().exists(DBCol::Block, &[1]).unwrap());
+    }
+}
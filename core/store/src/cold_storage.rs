@@ -1,5 +1,6 @@
 use crate::columns::DBKeyType;
 use crate::db::{ColdDB, COLD_HEAD_KEY, HEAD_KEY};
+use crate::state_history::{self, STATE_HISTORY_HEAD_KEY};
 use crate::{metrics, DBCol, DBTransaction, Database, Store, TrieChanges};
 
 use borsh::BorshDeserialize;
@@ -7,7 +8,7 @@ use near_primitives::block::{Block, BlockHeader, Tip};
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::sharding::ShardChunk;
-use near_primitives::types::BlockHeight;
+use near_primitives::types::{BlockHeight, RawStateChangesWithTrieKey};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::collections::HashMap;
 use std::io;
@@ -127,9 +128,69 @@ pub fn update_cold_db(
                 )
         })?;
 
+    update_state_history(cold_db, height)?;
+
     Ok(true)
 }
 
+/// Maximum number of blocks added to the state history index by a single call
+/// to `update_state_history`, so that an index lagging far behind the cold head
+/// catches up over several iterations of the cold store loop.
+const MAX_STATE_HISTORY_CATCH_UP_BLOCKS: BlockHeight = 1000;
+
+/// Adds the state changes of the blocks above the head of the state history
+/// index, up to the block at `height`, to the index if the index is
+/// maintained.  Must be called once the block at `height` has been copied to
+/// the cold database.
+///
+/// The index falls behind the cold head when blocks are copied to cold storage
+/// by a node which doesn't maintain it, e.g. during the initial migration.  It
+/// then catches up by at most `MAX_STATE_HISTORY_CATCH_UP_BLOCKS` blocks at a
+/// time.
+fn update_state_history(cold_db: &ColdDB, height: &BlockHeight) -> io::Result<()> {
+    let Some(index_head) =
+        get_ser_from_cold_db::<BlockHeight>(cold_db, DBCol::StateHistory, STATE_HISTORY_HEAD_KEY)?
+    else {
+        return Ok(());
+    };
+    if index_head >= *height {
+        return Ok(());
+    }
+    let new_head = (*height).min(index_head + MAX_STATE_HISTORY_CATCH_UP_BLOCKS);
+    if new_head < *height {
+        tracing::debug!(target: "cold_store", index_head, height, new_head, "state history index is behind the cold head, catching up");
+    }
+
+    let _span =
+        tracing::debug_span!(target: "cold_store", "update_state_history", index_head, new_head);
+    let mut transaction = DBTransaction::new();
+    for index_height in index_head + 1..=new_head {
+        let Some(block_hash) =
+            cold_db.get_raw_bytes(DBCol::BlockHeight, &index_height.to_le_bytes())?
+        else {
+            continue;
+        };
+        let state_changes = cold_db
+            .iter_prefix(DBCol::StateChanges, &*block_hash)
+            .map(|item| {
+                let (_, value) = item?;
+                RawStateChangesWithTrieKey::try_from_slice(&value)
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        state_history::record_state_changes(&mut transaction, index_height, state_changes);
+    }
+    state_history::set_head(&mut transaction, new_head);
+    cold_db.write(transaction)
+}
+
+fn get_ser_from_cold_db<T: BorshDeserialize>(
+    cold_db: &ColdDB,
+    col: DBCol,
+    key: &[u8],
+) -> io::Result<Option<T>> {
+    cold_db.get_raw_bytes(col, key)?.as_deref().map(T::try_from_slice).transpose()
+}
+
 // Correctly set the key and value on DBTransaction, taking reference counting
 // into account. For non-rc columns it just sets the value. For rc columns it
 // appends rc = 1 to the value and sets it.
@@ -293,6 +293,16 @@ pub enum DBCol {
     /// Witnesses with the lowest index are garbage collected first.
     /// u64 -> LatestWitnessesKey
     LatestWitnessesByIndex,
+    /// History of account-scoped state (accounts, contract code, access keys
+    /// and contract data) used to answer historical queries on archival nodes
+    /// without walking old tries.  Only present in the cold database, see
+    /// `crate::state_history`.
+    /// - *Rows*: TrieKey with zero bytes escaped and terminated by two zero
+    ///   bytes || (u64::MAX - BlockHeight) big-endian, plus the `TAIL` and
+    ///   `HEAD` rows with the range of heights covered
+    /// - *Column type*: `Option<Vec<u8>>`, the value after the block at that
+    ///   height or `None` if it was deleted
+    StateHistory,
 }
 
 /// Defines different logical parts of a db key.
@@ -465,6 +475,8 @@ impl DBCol {
             // LatestChunkStateWitnesses stores the last N observed witnesses, used only for debugging.
             DBCol::LatestChunkStateWitnesses => false,
             DBCol::LatestWitnessesByIndex => false,
+            // StateHistory is written to the cold storage directly.
+            DBCol::StateHistory => false,
             // Deprecated.
             DBCol::_ReceiptIdToShardId => false,
 
@@ -498,9 +510,20 @@ impl DBCol {
         }
     }
 
+    /// Whether this column only exists in cold storage.
+    ///
+    /// Such columns are not copied from the hot database but written to the
+    /// cold one directly, and reads through the split storage go straight to
+    /// the cold database.
+    pub const fn is_cold_only(&self) -> bool {
+        matches!(*self, DBCol::StateHistory)
+    }
+
     /// Whether this column exists in cold storage.
     pub(crate) const fn is_in_colddb(&self) -> bool {
-        matches!(*self, DBCol::DbVersion | DBCol::BlockMisc) || self.is_cold()
+        matches!(*self, DBCol::DbVersion | DBCol::BlockMisc)
+            || self.is_cold()
+            || self.is_cold_only()
     }
 
     /// Vector of DBKeyType s concatenation of which results in key for the column.
@@ -566,6 +589,7 @@ impl DBCol {
             DBCol::StateTransitionData => &[DBKeyType::BlockHash, DBKeyType::ShardId],
             DBCol::LatestChunkStateWitnesses => &[DBKeyType::LatestWitnessesKey],
             DBCol::LatestWitnessesByIndex => &[DBKeyType::LatestWitnessIndex],
+            DBCol::StateHistory => &[DBKeyType::TrieKey, DBKeyType::BlockHeight],
         }
     }
 }
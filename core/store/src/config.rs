@@ -13,6 +13,20 @@ pub struct StoreConfig {
     /// database.
     pub path: Option<std::path::PathBuf>,
 
+    /// Storage engine used when creating a new database.
+    ///
+    /// An existing database is always opened with the engine it has been
+    /// created with, so changing this option has no effect on it.  Use the
+    /// `neard database convert-db` command to move the data into a database
+    /// using a different engine.
+    ///
+    /// `redb` requires neard built with the `redb` feature and is only
+    /// supported on RPC nodes, i.e. not in archival or split storage setups.
+    /// Unlike RocksDB, a redb database can be opened by a single process at
+    /// a time, even when opening it for reading.
+    #[serde(skip_serializing_if = "DbBackend::is_default")]
+    pub backend: DbBackend,
+
     /// Collect internal storage layer statistics.
     /// Minor performance impact is expected.
     pub enable_statistics: bool,
@@ -99,6 +113,68 @@ pub struct StoreConfig {
 
     // TODO (#9989): To be phased out in favor of state_snapshot_config
     pub state_snapshot_enabled: bool,
+
+    /// If set, the node periodically makes incremental backups of the
+    /// database while running.  See [`crate::backup`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupConfig>,
+}
+
+/// Configuration of periodic backups of a database.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BackupConfig {
+    /// Directory to store the backups in.  If relative, resolved relative to
+    /// neard home directory.  It may be a mount point of an object store.
+    pub path: std::path::PathBuf,
+
+    /// How often to make a backup.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub period: near_time::Duration,
+}
+
+/// Storage engine of a database.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::Display,
+    strum::EnumString,
+)]
+pub enum DbBackend {
+    /// RocksDB, a log-structured merge-tree.  This is the default.
+    #[default]
+    #[serde(rename = "rocksdb")]
+    #[strum(serialize = "rocksdb")]
+    RocksDb,
+    /// redb, an embedded copy-on-write B-tree.  Reads never wait for
+    /// compactions, which makes latencies more predictable.
+    #[serde(rename = "redb")]
+    #[strum(serialize = "redb")]
+    Redb,
+}
+
+impl DbBackend {
+    /// Returns backend of the database at `path` or `None` if there’s no
+    /// database there.
+    pub fn detect(path: &std::path::Path) -> Option<Self> {
+        if path.join("CURRENT").is_file() {
+            Some(Self::RocksDb)
+        } else if path.join(crate::db::REDB_FILE_NAME).is_file() {
+            Some(Self::Redb)
+        } else {
+            None
+        }
+    }
+
+    /// Checks whether the object equals its default value.
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// Config used to control state snapshot creation. This is used for state sync and resharding.
@@ -189,6 +265,7 @@ impl Default for StoreConfig {
     fn default() -> Self {
         Self {
             path: None,
+            backend: DbBackend::default(),
             enable_statistics: false,
             enable_statistics_export: true,
 
@@ -280,6 +357,8 @@ impl Default for StoreConfig {
 
             // TODO: To be phased out in favor of state_snapshot_config
             state_snapshot_enabled: false,
+
+            backup: None,
         }
     }
 }
@@ -8,6 +8,8 @@ pub(crate) mod rocksdb;
 mod colddb;
 mod mixeddb;
 mod recoverydb;
+#[cfg(feature = "redb")]
+mod redb;
 mod splitdb;
 
 pub mod refcount;
@@ -19,6 +21,8 @@ mod database_tests;
 pub use self::colddb::ColdDB;
 pub use self::mixeddb::{MixedDB, ReadOrder};
 pub use self::recoverydb::RecoveryDB;
+#[cfg(feature = "redb")]
+pub use self::redb::RedbDB;
 pub use self::rocksdb::RocksDB;
 pub use self::splitdb::SplitDB;
 
@@ -48,6 +52,10 @@ pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
 pub const STATE_TRANSITION_START_HEIGHTS: &[u8] = b"STATE_TRANSITION_START_HEIGHTS";
 pub const LATEST_WITNESSES_INFO: &[u8] = b"LATEST_WITNESSES_INFO";
 
+/// Name of the file holding a database using the redb backend inside of the
+/// database directory.
+pub const REDB_FILE_NAME: &str = "data.redb";
+
 #[derive(Default, Debug)]
 pub struct DBTransaction {
     pub(crate) ops: Vec<DBOp>,
@@ -0,0 +1,453 @@
+use crate::config::Mode;
+use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, REDB_FILE_NAME};
+use crate::{metadata, metrics, DBCol, StoreConfig, StoreStatistics};
+use ::redb::{Durability, ReadOnlyTable, ReadableTable, TableDefinition, TableError};
+use std::io;
+use std::ops::Bound;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// Number of entries iterators read from the database at once.
+const ITER_BATCH_SIZE: usize = 1024;
+
+/// Number of entries copied in a single write transaction when creating
+/// a checkpoint.
+const CHECKPOINT_BATCH_SIZE: usize = 100_000;
+
+/// Database backed by [redb](https://www.redb.org), an embedded B-tree key
+/// value store.
+///
+/// Every column is stored in its own table of a single database file.
+/// Reference counted columns keep the values in the same format as
+/// [`super::RocksDB`] but, since redb has no merge operator, the reference
+/// counts are merged when writing and values whose count drops to zero are
+/// deleted right away.
+pub struct RedbDB {
+    db: ::redb::Database,
+
+    /// Whether the database has been opened in read-only mode.  redb has no
+    /// such mode so it’s enforced by [`Database::write`].
+    read_only: bool,
+}
+
+/// Returns definition of the table holding given column.
+fn table(col: DBCol) -> TableDefinition<'static, &'static [u8], &'static [u8]> {
+    TableDefinition::new(col.into())
+}
+
+impl RedbDB {
+    /// Opens the database.
+    ///
+    /// `path` specifies location of the directory holding the database file.
+    /// Like with [`super::RocksDB::open`] it’s assumed that it has been
+    /// resolved based on configuration in `store_config` and thus path
+    /// configuration in `store_config` is ignored.
+    ///
+    /// `mode` specifies whether to open the database in read/write or
+    /// read-only mode and whether the database may or must be created.
+    pub fn open(path: &Path, store_config: &StoreConfig, mode: Mode) -> io::Result<Self> {
+        let file = path.join(REDB_FILE_NAME);
+        let exists = file.is_file();
+        if exists && mode.must_create() {
+            let msg = format!("{}: database already exists", file.display());
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+        }
+        if !exists && !mode.can_create() {
+            let msg = format!("{}: database does not exist", file.display());
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+        std::fs::create_dir_all(path)?;
+        // Unlike RocksDB, redb has a single cache shared by all the tables.
+        // Size it to hold what the caches of the hottest columns would.
+        let cache_size = store_config.col_state_cache_size + store_config.col_flat_state_cache_size;
+        let mut builder = ::redb::Builder::new();
+        builder.set_cache_size(cache_size.as_u64().try_into().unwrap_or(usize::MAX));
+        // redb locks the file exclusively in every mode, so a database can’t
+        // be opened while a node has it open, not even in read-only mode.
+        let db = if exists { builder.open(&file) } else { builder.create(&file) };
+        let db = db.map_err(io::Error::other)?;
+        Ok(Self { db, read_only: mode.read_only() })
+    }
+
+    /// Returns metadata of the database or `None` if the db doesn’t exist.
+    pub(crate) fn get_metadata(
+        path: &Path,
+        store_config: &StoreConfig,
+    ) -> io::Result<Option<metadata::DbMetadata>> {
+        if !path.join(REDB_FILE_NAME).is_file() {
+            return Ok(None);
+        }
+        let db = Self::open(path, store_config, Mode::ReadOnly)?;
+        Some(metadata::DbMetadata::read(&db)).transpose()
+    }
+
+    /// Opens the table of the column in a new read transaction.  Returns
+    /// `None` if nothing has been written to the column yet.
+    fn open_read_table(
+        &self,
+        col: DBCol,
+    ) -> io::Result<Option<ReadOnlyTable<&'static [u8], &'static [u8]>>> {
+        let txn = self.db.begin_read().map_err(io::Error::other)?;
+        match txn.open_table(table(col)) {
+            Ok(table) => Ok(Some(table)),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    /// Returns an iterator over raw entries of the column within the range.
+    fn iter_raw_bytes_internal(
+        &self,
+        col: DBCol,
+        lower: Bound<Vec<u8>>,
+        upper: Bound<Vec<u8>>,
+    ) -> RedbIterator {
+        let (table, error) = match self.open_read_table(col) {
+            Ok(table) => (table, None),
+            Err(err) => (None, Some(err)),
+        };
+        RedbIterator { table, lower, upper, batch: Vec::new().into_iter(), error }
+    }
+
+    /// Copies columns of the database into a new database at `path`.
+    ///
+    /// All the data is read in a single read transaction so the copy is
+    /// consistent.  If `columns` is given, only those columns (and always
+    /// [`DBCol::DbVersion`]) are copied.
+    fn copy_to(&self, path: &Path, columns: Option<&[DBCol]>) -> io::Result<()> {
+        let config = StoreConfig::default();
+        let target = Self::open(path, &config, Mode::Create)?;
+        let txn = self.db.begin_read().map_err(io::Error::other)?;
+        for col in DBCol::iter() {
+            if columns.is_some_and(|columns| !columns.contains(&col)) && col != DBCol::DbVersion {
+                continue;
+            }
+            let table = match txn.open_table(table(col)) {
+                Ok(table) => table,
+                Err(TableError::TableDoesNotExist(_)) => continue,
+                Err(err) => return Err(io::Error::other(err)),
+            };
+            let mut batch = DBTransaction::new();
+            for item in table.iter().map_err(io::Error::other)? {
+                let (key, value) = item.map_err(io::Error::other)?;
+                batch.set(col, key.value().to_vec(), value.value().to_vec());
+                if batch.ops.len() >= CHECKPOINT_BATCH_SIZE {
+                    target.write(std::mem::take(&mut batch))?;
+                }
+            }
+            target.write(batch)?;
+        }
+        target.flush()
+    }
+}
+
+/// Iterator over a range of a column reading it in batches of
+/// [`ITER_BATCH_SIZE`] entries.
+///
+/// Like RocksDB iterators, all the batches are read in the read transaction
+/// opened when the iterator was created, so the iteration doesn’t observe
+/// writes which happened after that.  redb cannot reuse pages freed by later
+/// writes while a read transaction which could see them is open, so
+/// iterators shouldn’t be kept around longer than needed.
+struct RedbIterator {
+    /// Table of the column in the read transaction of the iterator, `None`
+    /// once the last batch has been read.
+    table: Option<ReadOnlyTable<&'static [u8], &'static [u8]>>,
+    /// Lower bound of the next batch.
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    batch: std::vec::IntoIter<(Box<[u8]>, Box<[u8]>)>,
+    /// Error opening the read transaction, returned by the first call to
+    /// `next`.
+    error: Option<io::Error>,
+}
+
+impl RedbIterator {
+    /// Reads up to [`ITER_BATCH_SIZE`] entries following the previous batch.
+    fn read_batch(
+        table: &ReadOnlyTable<&'static [u8], &'static [u8]>,
+        lower: Bound<&[u8]>,
+        upper: Bound<&[u8]>,
+    ) -> io::Result<Vec<(Box<[u8]>, Box<[u8]>)>> {
+        let range = table.range::<&[u8]>((lower, upper)).map_err(io::Error::other)?;
+        range
+            .take(ITER_BATCH_SIZE)
+            .map(|item| {
+                let (key, value) = item.map_err(io::Error::other)?;
+                Ok((key.value().into(), value.value().into()))
+            })
+            .collect()
+    }
+}
+
+impl Iterator for RedbIterator {
+    type Item = io::Result<(Box<[u8]>, Box<[u8]>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        loop {
+            if let Some(item) = self.batch.next() {
+                return Some(Ok(item));
+            }
+            let table = self.table.as_ref()?;
+            let lower = self.lower.as_ref().map(Vec::as_slice);
+            let upper = self.upper.as_ref().map(Vec::as_slice);
+            match Self::read_batch(table, lower, upper) {
+                Ok(batch) => {
+                    if batch.len() < ITER_BATCH_SIZE {
+                        // Release the read transaction as soon as possible.
+                        self.table = None;
+                    }
+                    if let Some((key, _)) = batch.last() {
+                        self.lower = Bound::Excluded(key.to_vec());
+                    }
+                    self.batch = batch.into_iter();
+                }
+                Err(err) => {
+                    self.table = None;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for RedbIterator {}
+
+/// Returns the smallest key greater than all the keys starting with `prefix`
+/// or `None` if there’s no such key.
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let end = prefix.iter().rposition(|&byte| byte != u8::MAX)?;
+    let mut upper = prefix[..=end].to_vec();
+    upper[end] += 1;
+    Some(upper)
+}
+
+impl Database for RedbDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        let timer =
+            metrics::DATABASE_OP_LATENCY_HIST.with_label_values(&["get", col.into()]).start_timer();
+        let Some(table) = self.open_read_table(col)? else {
+            return Ok(None);
+        };
+        let result = table.get(key).map_err(io::Error::other)?;
+        let result = result.map(|value| DBSlice::from_vec(value.value().to_vec()));
+        timer.observe_duration();
+        Ok(result)
+    }
+
+    fn iter_raw_bytes(&self, col: DBCol) -> DBIterator {
+        Box::new(self.iter_raw_bytes_internal(col, Bound::Unbounded, Bound::Unbounded))
+    }
+
+    fn iter(&self, col: DBCol) -> DBIterator {
+        let iter = self.iter_raw_bytes_internal(col, Bound::Unbounded, Bound::Unbounded);
+        refcount::iter_with_rc_logic(col, iter)
+    }
+
+    fn iter_prefix(&self, col: DBCol, key_prefix: &[u8]) -> DBIterator {
+        let lower = Bound::Included(key_prefix.to_vec());
+        let upper = prefix_upper_bound(key_prefix).map_or(Bound::Unbounded, Bound::Excluded);
+        refcount::iter_with_rc_logic(col, self.iter_raw_bytes_internal(col, lower, upper))
+    }
+
+    fn iter_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        let lower = lower_bound.map_or(Bound::Unbounded, |key| Bound::Included(key.to_vec()));
+        let upper = upper_bound.map_or(Bound::Unbounded, |key| Bound::Excluded(key.to_vec()));
+        refcount::iter_with_rc_logic(col, self.iter_raw_bytes_internal(col, lower, upper))
+    }
+
+    #[tracing::instrument(target = "store::db::redb", level = "trace", "RedbDB::write", skip_all)]
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "database has been opened in read-only mode",
+            ));
+        }
+        let mut txn = self.db.begin_write().map_err(io::Error::other)?;
+        // redb has no write-ahead log: a transaction which isn’t synced to
+        // disk is rolled back after a crash, so sync every write.  This makes
+        // writes slower than with RocksDB, whose default write options don’t
+        // sync the log but which recovers the latest transactions from it
+        // after the process crashes.
+        txn.set_durability(Durability::Immediate);
+        {
+            let mut tables = enum_map::EnumMap::<DBCol, Option<::redb::Table<_, _>>>::default();
+            for op in transaction.ops {
+                let col = op.col();
+                if tables[col].is_none() {
+                    tables[col] = Some(txn.open_table(table(col)).map_err(io::Error::other)?);
+                }
+                let table = tables[col].as_mut().unwrap();
+                match op {
+                    DBOp::Set { key, value, .. } => {
+                        table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::Insert { col, key, value } => {
+                        if cfg!(debug_assertions) {
+                            if let Some(old_value) =
+                                table.get(key.as_slice()).map_err(io::Error::other)?
+                            {
+                                super::assert_no_overwrite(col, &key, &value, old_value.value())
+                            }
+                        }
+                        table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::UpdateRefcount { key, value, .. } => {
+                        let existing = table
+                            .get(key.as_slice())
+                            .map_err(io::Error::other)?
+                            .map(|existing| existing.value().to_vec());
+                        let merged = refcount::refcount_merge(existing.as_deref(), [&value[..]]);
+                        if merged.is_empty() {
+                            table.remove(key.as_slice()).map_err(io::Error::other)?;
+                        } else {
+                            table
+                                .insert(key.as_slice(), merged.as_slice())
+                                .map_err(io::Error::other)?;
+                        }
+                    }
+                    DBOp::Delete { key, .. } => {
+                        table.remove(key.as_slice()).map_err(io::Error::other)?;
+                    }
+                    DBOp::DeleteAll { .. } => {
+                        table.retain(|_, _| false).map_err(io::Error::other)?;
+                    }
+                    DBOp::DeleteRange { from, to, .. } => {
+                        table
+                            .retain_in::<&[u8], _>(from.as_slice()..to.as_slice(), |_, _| false)
+                            .map_err(io::Error::other)?;
+                    }
+                }
+            }
+        }
+        txn.commit().map_err(io::Error::other)
+    }
+
+    /// redb doesn’t need compaction.  Pages freed by writes are reused by
+    /// later ones.
+    fn compact(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Every write is synced to disk already, see [`Database::write`].
+    fn flush(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        None
+    }
+
+    #[tracing::instrument(
+        target = "store::db::redb",
+        level = "debug",
+        "RedbDB::create_checkpoint",
+        skip_all,
+        fields(path = %path.display()),
+    )]
+    fn create_checkpoint(
+        &self,
+        path: &std::path::Path,
+        columns_to_keep: Option<&[DBCol]>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+        self.copy_to(path, columns_to_keep)
+            .with_context(|| format!("failed to create checkpoint at {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound(b""), None);
+        assert_eq!(prefix_upper_bound(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_upper_bound(b"a\xff\xff"), Some(b"b".to_vec()));
+        assert_eq!(prefix_upper_bound(b"\xff"), None);
+    }
+
+    #[test]
+    fn test_refcount_and_delete_range() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let db = RedbDB::open(tmpdir.path(), &StoreConfig::test_config(), Mode::Create).unwrap();
+        let one = std::num::NonZeroU32::new(1).unwrap();
+
+        let mut transaction = DBTransaction::new();
+        transaction.update_refcount(
+            DBCol::State,
+            b"key".to_vec(),
+            refcount::add_positive_refcount(b"value", one),
+        );
+        transaction.update_refcount(
+            DBCol::State,
+            b"key".to_vec(),
+            refcount::add_positive_refcount(b"value", one),
+        );
+        db.write(transaction).unwrap();
+        let value = db.get_with_rc_stripped(DBCol::State, b"key").unwrap();
+        assert_eq!(value.as_deref(), Some(&b"value"[..]));
+
+        let mut transaction = DBTransaction::new();
+        transaction.update_refcount(
+            DBCol::State,
+            b"key".to_vec(),
+            refcount::encode_negative_refcount(one).to_vec(),
+        );
+        db.write(transaction).unwrap();
+        assert!(db.get_with_rc_stripped(DBCol::State, b"key").unwrap().is_some());
+
+        let mut transaction = DBTransaction::new();
+        transaction.update_refcount(
+            DBCol::State,
+            b"key".to_vec(),
+            refcount::encode_negative_refcount(one).to_vec(),
+        );
+        db.write(transaction).unwrap();
+        assert!(db.get_raw_bytes(DBCol::State, b"key").unwrap().is_none());
+
+        let mut transaction = DBTransaction::new();
+        for key in [b"a", b"b", b"c", b"d"] {
+            transaction.set(DBCol::Block, key.to_vec(), key.to_vec());
+        }
+        transaction.delete_range(DBCol::Block, b"b".to_vec(), b"d".to_vec());
+        db.write(transaction).unwrap();
+        let keys: Vec<_> = db.iter(DBCol::Block).map(|item| item.unwrap().0.to_vec()).collect();
+        assert_eq!(keys, vec![b"a".to_vec(), b"d".to_vec()]);
+    }
+
+    #[test]
+    fn test_iterator_reads_snapshot() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let db = RedbDB::open(tmpdir.path(), &StoreConfig::test_config(), Mode::Create).unwrap();
+        let key = |i: usize| format!("{i:08}").into_bytes();
+
+        let mut transaction = DBTransaction::new();
+        for i in 0..ITER_BATCH_SIZE * 2 {
+            transaction.set(DBCol::Block, key(i), key(i));
+        }
+        db.write(transaction).unwrap();
+
+        let mut iter = db.iter(DBCol::Block);
+        assert_eq!(iter.next().unwrap().unwrap().0.to_vec(), key(0));
+        // Changes made while iterating, including in the following batches,
+        // aren't observed.
+        let mut transaction = DBTransaction::new();
+        transaction.delete(DBCol::Block, key(ITER_BATCH_SIZE + 1));
+        transaction.set(DBCol::Block, key(ITER_BATCH_SIZE * 3), key(0));
+        db.write(transaction).unwrap();
+        let keys: Vec<_> = iter.map(|item| item.unwrap().0.to_vec()).collect();
+        assert_eq!(keys, (1..ITER_BATCH_SIZE * 2).map(key).collect::<Vec<_>>());
+        assert_eq!(db.iter(DBCol::Block).count(), ITER_BATCH_SIZE * 2);
+    }
+}
@@ -81,6 +81,38 @@ impl Snapshot {
         db_path: &std::path::Path,
         config: &crate::StoreConfig,
         temp: Temperature,
+    ) -> Result<Self, SnapshotError> {
+        Self::create_with(db_path, config, |snapshot_path| {
+            let db = super::RocksDB::open(db_path, config, crate::Mode::ReadWriteExisting, temp)?;
+            let cp = Checkpoint::new(&db.db).map_err(io::Error::other)?;
+            cp.create_checkpoint(snapshot_path)?;
+            Ok(())
+        })
+    }
+
+    /// Possibly creates a new snapshot for given redb database.
+    ///
+    /// Works like [`Self::new`] except that the snapshot is a copy of the redb
+    /// database file.
+    #[cfg(feature = "redb")]
+    pub fn new_redb(
+        db_path: &std::path::Path,
+        config: &crate::StoreConfig,
+    ) -> Result<Self, SnapshotError> {
+        use crate::db::Database;
+        Self::create_with(db_path, config, |snapshot_path| {
+            let db = crate::db::RedbDB::open(db_path, config, crate::Mode::ReadWriteExisting)?;
+            db.create_checkpoint(snapshot_path, None).map_err(io::Error::other)?;
+            Ok(())
+        })
+    }
+
+    /// Determines path of the snapshot and, if snapshots are enabled, creates
+    /// it there with `create`.
+    fn create_with(
+        db_path: &std::path::Path,
+        config: &crate::StoreConfig,
+        create: impl FnOnce(&std::path::Path) -> Result<(), SnapshotError>,
     ) -> Result<Self, SnapshotError> {
         let snapshot_path = match config.migration_snapshot.get_path(db_path) {
             Some(snapshot_path) => snapshot_path,
@@ -93,10 +125,7 @@ impl Snapshot {
             return Err(SnapshotError::AlreadyExists(snapshot_path));
         }
 
-        let db = super::RocksDB::open(db_path, config, crate::Mode::ReadWriteExisting, temp)?;
-        let cp = Checkpoint::new(&db.db).map_err(io::Error::other)?;
-        cp.create_checkpoint(&snapshot_path)?;
-
+        create(&snapshot_path)?;
         Ok(Self(Some(snapshot_path)))
     }
 
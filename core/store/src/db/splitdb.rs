@@ -12,7 +12,8 @@ use crate::DBCol;
 ///
 /// For hot-only columns it always reads from the hot database only. For cold
 /// columns it reads from hot first and if the value is present it returns it.
-/// If the value is not present it reads from the cold database.
+/// If the value is not present it reads from the cold database. For cold-only
+/// columns it reads from the cold database only.
 ///
 /// The iter* methods return a merge iterator of hot and cold iterators.
 ///
@@ -77,6 +78,9 @@ impl Database for SplitDB {
     /// First tries to read the data from the hot db and returns it if found.
     /// Then it tries to read the data from the cold db and returns the result.
     fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        if col.is_cold_only() {
+            return self.cold.get_raw_bytes(col, key);
+        }
         if let Some(hot_result) = self.hot.get_raw_bytes(col, key)? {
             return Ok(Some(hot_result));
         }
@@ -110,6 +114,9 @@ impl Database for SplitDB {
     /// The returned iterator will iterate through items in both the cold store
     /// and the hot store. The items will be deduplicated and sorted.
     fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        if col.is_cold_only() {
+            return self.cold.iter(col);
+        }
         if !col.is_cold() {
             return self.hot.iter(col);
         }
@@ -123,6 +130,9 @@ impl Database for SplitDB {
     /// The returned iterator will iterate through items in both the cold store
     /// and the hot store. The items will be unique and sorted.
     fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        if col.is_cold_only() {
+            return self.cold.iter_prefix(col, key_prefix);
+        }
         if !col.is_cold() {
             return self.hot.iter_prefix(col, key_prefix);
         }
@@ -147,6 +157,9 @@ impl Database for SplitDB {
         lower_bound: Option<&[u8]>,
         upper_bound: Option<&[u8]>,
     ) -> DBIterator<'a> {
+        if col.is_cold_only() {
+            return self.cold.iter_range(col, lower_bound, upper_bound);
+        }
         if !col.is_cold() {
             return self.hot.iter_range(col, lower_bound, upper_bound);
         }
@@ -163,6 +176,9 @@ impl Database for SplitDB {
     /// The returned iterator will iterate through items in both the cold store
     /// and the hot store. The items will be unique and sorted.
     fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        if col.is_cold_only() {
+            return self.cold.iter_raw_bytes(col);
+        }
         if !col.is_cold() {
             return self.hot.iter_raw_bytes(col);
         }
@@ -43,6 +43,7 @@ use std::sync::LazyLock;
 use std::{fmt, io};
 use strum;
 
+pub mod backup;
 pub mod cold_storage;
 mod columns;
 pub mod config;
@@ -54,11 +55,12 @@ pub mod metrics;
 pub mod migrations;
 mod opener;
 mod rocksdb_metrics;
+pub mod state_history;
 mod sync_utils;
 pub mod test_utils;
 pub mod trie;
 
-pub use crate::config::{Mode, StoreConfig};
+pub use crate::config::{BackupConfig, DbBackend, Mode, StoreConfig};
 pub use crate::opener::{
     checkpoint_hot_storage_and_cleanup_columns, StoreMigrator, StoreOpener, StoreOpenerError,
 };
@@ -123,14 +125,11 @@ impl NodeStorage {
         StoreOpener::new(home_dir, archive, config, cold_config)
     }
 
-    /// Constructs new object backed by given database.
-    fn from_rocksdb(
-        hot_storage: crate::db::RocksDB,
-        cold_storage: Option<crate::db::RocksDB>,
+    /// Constructs new object backed by given databases.
+    fn from_databases(
+        hot_storage: Arc<dyn Database>,
+        cold_storage: Option<Arc<dyn Database>>,
     ) -> Self {
-        let hot_storage = Arc::new(hot_storage);
-        let cold_storage = cold_storage.map(|storage| Arc::new(storage));
-
         let cold_db = if let Some(cold_storage) = cold_storage {
             Some(Arc::new(crate::db::ColdDB::new(cold_storage)))
         } else {
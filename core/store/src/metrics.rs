@@ -560,6 +560,33 @@ fn export_store_stats(store: &Store, temperature: Temperature) {
     }
 }
 
+pub(crate) static BACKUP_LAST_ID: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_backup_last_id",
+        "Id of the most recent backup of the database",
+        &["temperature"],
+    )
+    .unwrap()
+});
+
+pub(crate) static BACKUP_COPIED_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_backup_copied_bytes",
+        "Number of bytes of new files copied into the backup directory",
+        &["temperature"],
+    )
+    .unwrap()
+});
+
+pub(crate) static BACKUP_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_backup_failures_total",
+        "Number of failed attempts to back up the database",
+        &["temperature"],
+    )
+    .unwrap()
+});
+
 pub fn spawn_db_metrics_loop(
     storage: &NodeStorage,
     period: Duration,
@@ -1,7 +1,8 @@
 use crate::db::rocksdb::snapshot::{Snapshot, SnapshotError, SnapshotRemoveError};
 use crate::db::rocksdb::RocksDB;
+use crate::db::Database;
 use crate::metadata::{DbKind, DbMetadata, DbVersion, DB_VERSION};
-use crate::{DBCol, DBTransaction, Mode, NodeStorage, Store, StoreConfig, Temperature};
+use crate::{DBCol, DBTransaction, DbBackend, Mode, NodeStorage, Store, StoreConfig, Temperature};
 use std::sync::Arc;
 
 #[derive(Debug, thiserror::Error)]
@@ -41,6 +42,16 @@ pub enum StoreOpenerError {
     #[error("{which} database kind should be {want} but got {got:?}. Did you forget to set archive on your store opener?")]
     DbKindMismatch { which: &'static str, got: Option<DbKind>, want: DbKind },
 
+    /// Database uses a backend which cannot be used in the node’s setup.
+    ///
+    /// Currently backends other than RocksDB are only supported for RPC
+    /// databases.
+    #[error(
+        "{which} database uses the {backend} backend \
+         which is only supported for RPC databases"
+    )]
+    UnsupportedBackend { which: &'static str, backend: DbBackend },
+
     /// Unable to create a migration snapshot because one already exists.
     #[error(
         "Migration snapshot already exists at {0}; \
@@ -228,6 +239,13 @@ impl<'a> StoreOpener<'a> {
         &self.hot.path
     }
 
+    /// Returns path to the cold database if one is configured.
+    ///
+    /// Does not check whether the database actually exists.
+    pub fn cold_path(&self) -> Option<&std::path::Path> {
+        self.cold.as_ref().map(|cold| cold.path.as_path())
+    }
+
     #[cfg(test)]
     pub(crate) fn config(&self) -> &StoreConfig {
         self.hot.config
@@ -256,6 +274,7 @@ impl<'a> StoreOpener<'a> {
             };
             tracing::info!(target: "db_opener", path=hot_path, cold_path=cold_path, "Opening NodeStorage");
         }
+        self.ensure_backend()?;
 
         let hot_snapshot = {
             Self::ensure_created(mode, &self.hot)?;
@@ -279,7 +298,7 @@ impl<'a> StoreOpener<'a> {
             .transpose()?
             .map(|(db, _)| db);
 
-        let storage = NodeStorage::from_rocksdb(hot_db, cold_db);
+        let storage = NodeStorage::from_databases(hot_db, cold_db);
 
         hot_snapshot.remove()?;
         cold_snapshot.remove()?;
@@ -296,6 +315,7 @@ impl<'a> StoreOpener<'a> {
             };
             tracing::info!(target: "db_opener", path=hot_path, cold_path=cold_path, "Creating NodeStorage snapshots");
         }
+        self.ensure_backend()?;
 
         let hot_snapshot = {
             Self::ensure_created(mode, &self.hot)?;
@@ -324,6 +344,37 @@ impl<'a> StoreOpener<'a> {
         Ok((hot_snapshot, cold_snapshot))
     }
 
+    /// Ensures that the databases use backends supported in the node’s setup.
+    fn ensure_backend(&self) -> Result<(), StoreOpenerError> {
+        let hot_backend = self.hot.backend();
+        if hot_backend != DbBackend::RocksDb && (self.archive || self.cold.is_some()) {
+            return Err(StoreOpenerError::UnsupportedBackend {
+                which: "Hot",
+                backend: hot_backend,
+            });
+        }
+        if let Some(cold) = &self.cold {
+            let cold_backend = cold.backend();
+            if cold_backend != DbBackend::RocksDb {
+                return Err(StoreOpenerError::UnsupportedBackend {
+                    which: "Cold",
+                    backend: cold_backend,
+                });
+            }
+        }
+        if hot_backend != self.hot.config.backend {
+            tracing::warn!(
+                target: "db_opener",
+                path = %self.hot.path.display(),
+                configured = %self.hot.config.backend,
+                existing = %hot_backend,
+                "The database uses a different backend than configured; \
+                 use `neard database convert-db` to convert it"
+            );
+        }
+        Ok(())
+    }
+
     // Creates the DB if it doesn't exist.
     fn ensure_created(mode: Mode, opener: &DBOpener) -> Result<(), StoreOpenerError> {
         let meta = opener.get_metadata()?;
@@ -339,7 +390,7 @@ impl<'a> StoreOpener<'a> {
                 tracing::info!(target: "db_opener", path=%opener.path.display(), "The database doesn't exist, creating it.");
 
                 let db = opener.create()?;
-                let store = Store { storage: Arc::new(db) };
+                let store = Store { storage: db };
                 store.set_db_version(DB_VERSION)?;
                 return Ok(());
             }
@@ -467,13 +518,13 @@ impl<'a> StoreOpener<'a> {
         version: DbVersion,
     ) -> Result<Store, StoreOpenerError> {
         let (db, _) = opener.open(mode, version)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 
     fn open_store_unsafe(mode: Mode, opener: &DBOpener) -> Result<Store, StoreOpenerError> {
         let db = opener.open_unsafe(mode)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 }
@@ -490,6 +541,29 @@ impl<'a> DBOpener<'a> {
         Self { path, config, temp }
     }
 
+    /// Returns backend of the database.
+    ///
+    /// For an existing database, this is the backend it has been created
+    /// with.  Otherwise, it’s the one set in the configuration.
+    fn backend(&self) -> DbBackend {
+        DbBackend::detect(&self.path).unwrap_or(self.config.backend)
+    }
+
+    /// Opens the database with its backend in given mode.
+    fn open_db(&self, mode: Mode) -> std::io::Result<Arc<dyn Database>> {
+        match self.backend() {
+            DbBackend::RocksDb => {
+                Ok(Arc::new(RocksDB::open(&self.path, &self.config, mode, self.temp)?))
+            }
+            #[cfg(feature = "redb")]
+            DbBackend::Redb => {
+                Ok(Arc::new(crate::db::RedbDB::open(&self.path, self.config, mode)?))
+            }
+            #[cfg(not(feature = "redb"))]
+            DbBackend::Redb => Err(redb_not_supported()),
+        }
+    }
+
     /// Returns version and kind of the database or `None` if it doesn’t exist.
     ///
     /// If the database exists but doesn’t have version set, returns an error.
@@ -499,7 +573,13 @@ impl<'a> DBOpener<'a> {
     /// introduced, the kind is returned as `None`.  Otherwise, it’s also
     /// fetched and if it’s not there error is returned.
     fn get_metadata(&self) -> std::io::Result<Option<DbMetadata>> {
-        RocksDB::get_metadata(&self.path, self.config)
+        match self.backend() {
+            DbBackend::RocksDb => RocksDB::get_metadata(&self.path, self.config),
+            #[cfg(feature = "redb")]
+            DbBackend::Redb => crate::db::RedbDB::get_metadata(&self.path, self.config),
+            #[cfg(not(feature = "redb"))]
+            DbBackend::Redb => Err(redb_not_supported()),
+        }
     }
 
     /// Opens the database in given mode checking expected version and kind.
@@ -518,9 +598,13 @@ impl<'a> DBOpener<'a> {
     /// new version.
     ///
     /// Use [`Self::create`] to create a new database.
-    fn open(&self, mode: Mode, want_version: DbVersion) -> std::io::Result<(RocksDB, DbMetadata)> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        let metadata = DbMetadata::read(&db)?;
+    fn open(
+        &self,
+        mode: Mode,
+        want_version: DbVersion,
+    ) -> std::io::Result<(Arc<dyn Database>, DbMetadata)> {
+        let db = self.open_db(mode)?;
+        let metadata = DbMetadata::read(db.as_ref())?;
         if want_version != metadata.version {
             let msg = format!("unexpected DbVersion {}; expected {want_version}", metadata.version);
             Err(std::io::Error::other(msg))
@@ -533,22 +617,35 @@ impl<'a> DBOpener<'a> {
     ///
     /// This is only suitable when creating the database or setting the version
     /// and kind for the first time.
-    fn open_unsafe(&self, mode: Mode) -> std::io::Result<RocksDB> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        Ok(db)
+    fn open_unsafe(&self, mode: Mode) -> std::io::Result<Arc<dyn Database>> {
+        self.open_db(mode)
     }
 
     /// Creates a new database.
-    fn create(&self) -> std::io::Result<RocksDB> {
-        RocksDB::open(&self.path, &self.config, Mode::Create, self.temp)
+    fn create(&self) -> std::io::Result<Arc<dyn Database>> {
+        self.open_db(Mode::Create)
     }
 
     /// Creates a new snapshot for the database.
     fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
-        Snapshot::new(&self.path, &self.config, self.temp)
+        match self.backend() {
+            DbBackend::RocksDb => Snapshot::new(&self.path, &self.config, self.temp),
+            #[cfg(feature = "redb")]
+            DbBackend::Redb => Snapshot::new_redb(&self.path, &self.config),
+            #[cfg(not(feature = "redb"))]
+            DbBackend::Redb => Err(redb_not_supported().into()),
+        }
     }
 }
 
+#[cfg(not(feature = "redb"))]
+fn redb_not_supported() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the database uses the redb backend but neard was built without the redb feature",
+    )
+}
+
 pub trait StoreMigrator {
     /// Checks whether migrator supports database versions starting at given.
     ///
@@ -694,4 +791,29 @@ mod tests {
         check_keys_existence(&store.get_hot_store(), &DBCol::Chunks, &keys, false);
         check_keys_existence(&store.get_hot_store(), &DBCol::BlockHeader, &keys, false);
     }
+
+    #[cfg(feature = "redb")]
+    #[test]
+    fn test_redb_backend_detection() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let mut config = StoreConfig::test_config();
+        config.backend = DbBackend::Redb;
+        let storage = NodeStorage::opener(home_dir.path(), false, &config, None).open().unwrap();
+        let mut store_update = storage.get_hot_store().store_update();
+        store_update.insert(DBCol::Block, vec![1], vec![42]);
+        store_update.commit().unwrap();
+        drop(storage);
+
+        // The existing database is opened with its backend regardless of the
+        // configured one.
+        let config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &config, None);
+        assert_eq!(DbBackend::detect(opener.path()), Some(DbBackend::Redb));
+        let store = opener.open().unwrap().get_hot_store();
+        assert_eq!(store.get(DBCol::Block, &[1]).unwrap().as_deref(), Some(&[42][..]));
+        drop(store);
+
+        let err = NodeStorage::opener(home_dir.path(), true, &config, None).open().unwrap_err();
+        assert!(matches!(err, StoreOpenerError::UnsupportedBackend { which: "Hot", .. }), "{err}");
+    }
 }
@@ -0,0 +1,320 @@
+//! Index of the history of account-scoped state kept by archival nodes.
+//!
+//! Answering a query about an old block normally requires walking the trie of
+//! that block, which on archival nodes with split storage means many random
+//! reads from the cold database.  The state history index instead keeps, for
+//! every account, contract code, access key and contract data key, the list
+//! of values it had after each block which changed it.  The value of a key at
+//! a given height is then the first entry at or below that height.
+//!
+//! The index lives in [`DBCol::StateHistory`] of the cold database.  It is
+//! built by the `cold-store backfill-state-history` command, which writes the
+//! whole state at some height and all the changes since then, and then kept up
+//! to date by the cold store loop.  The `TAIL` and `HEAD` rows hold the range
+//! of heights for which the index is complete.
+use crate::db::DBTransaction;
+use crate::{DBCol, Store};
+use borsh::BorshDeserialize;
+use near_primitives::trie_key::col;
+use near_primitives::types::{BlockHeight, RawStateChangesWithTrieKey};
+use std::io;
+use std::ops::RangeInclusive;
+
+/// Lowest height for which the index is complete.
+pub const STATE_HISTORY_TAIL_KEY: &[u8] = b"TAIL";
+/// Highest height for which the index is complete.
+pub const STATE_HISTORY_HEAD_KEY: &[u8] = b"HEAD";
+
+/// Length of the height suffix of the keys.
+const HEIGHT_SUFFIX_LEN: usize = std::mem::size_of::<BlockHeight>();
+
+/// Whether the raw trie key is tracked by the index.
+pub fn is_indexed_trie_key(trie_key: &[u8]) -> bool {
+    matches!(
+        trie_key.first(),
+        Some(&col::ACCOUNT | &col::CONTRACT_CODE | &col::ACCESS_KEY | &col::CONTRACT_DATA)
+    )
+}
+
+/// Appends `trie_key` to `out` with every zero byte escaped as `0x00 0xff`
+/// and, if `terminate` is set, followed by `0x00 0x00`.
+///
+/// The encoding preserves the order of trie keys, and since no encoded trie
+/// key is a prefix of another one the entries of a trie key are contiguous in
+/// the column, rather than interleaved with the entries of the longer trie
+/// keys sharing its prefix.  An unterminated encoding is a prefix of the
+/// encodings of exactly the trie keys starting with `trie_key`.
+fn encode_trie_key(out: &mut Vec<u8>, trie_key: &[u8], terminate: bool) {
+    for &byte in trie_key {
+        out.push(byte);
+        if byte == 0 {
+            out.push(u8::MAX);
+        }
+    }
+    if terminate {
+        out.extend_from_slice(&[0, 0]);
+    }
+}
+
+/// Returns the key of the entry of `trie_key` at `height`.  Heights are
+/// inverted so that iterating forward from a height yields the most recent
+/// entry first.
+fn history_key(trie_key: &[u8], height: BlockHeight) -> Vec<u8> {
+    let mut key = Vec::with_capacity(trie_key.len() + 2 + HEIGHT_SUFFIX_LEN);
+    encode_trie_key(&mut key, trie_key, true);
+    key.extend_from_slice(&(BlockHeight::MAX - height).to_be_bytes());
+    key
+}
+
+/// Returns the lowest key above all the entries of `trie_key`.
+fn history_key_upper_bound(trie_key: &[u8]) -> Vec<u8> {
+    let mut key = history_key(trie_key, 0);
+    key.push(0);
+    key
+}
+
+/// Splits a key of the column into the trie key and the height.
+fn split_history_key(key: &[u8]) -> Option<(Vec<u8>, BlockHeight)> {
+    let mut trie_key = Vec::with_capacity(key.len());
+    let mut bytes = key.iter();
+    loop {
+        match (*bytes.next()?, bytes.as_slice().first()) {
+            (0, Some(&u8::MAX)) => {
+                bytes.next();
+                trie_key.push(0);
+            }
+            (0, Some(&0)) => {
+                bytes.next();
+                break;
+            }
+            (0, _) => return None,
+            (byte, _) => trie_key.push(byte),
+        }
+    }
+    let inverted_height = BlockHeight::from_be_bytes(bytes.as_slice().try_into().ok()?);
+    Some((trie_key, BlockHeight::MAX - inverted_height))
+}
+
+/// Adds to the transaction the value `trie_key` has after the block at
+/// `height`, `None` meaning that it was deleted.
+pub fn record_value(
+    transaction: &mut DBTransaction,
+    trie_key: &[u8],
+    height: BlockHeight,
+    value: Option<&[u8]>,
+) -> usize {
+    debug_assert!(is_indexed_trie_key(trie_key));
+    let key = history_key(trie_key, height);
+    let value = borsh::to_vec(&value).expect("Borsh serialize cannot fail");
+    let size = key.len() + value.len();
+    transaction.set(DBCol::StateHistory, key, value);
+    size
+}
+
+/// Adds to the transaction the final values of the indexed keys changed by
+/// the block at `height`.  Returns the size of the added entries in bytes.
+pub fn record_state_changes(
+    transaction: &mut DBTransaction,
+    height: BlockHeight,
+    state_changes: impl IntoIterator<Item = RawStateChangesWithTrieKey>,
+) -> usize {
+    let mut size = 0;
+    for state_changes in state_changes {
+        let trie_key = state_changes.trie_key.to_vec();
+        if !is_indexed_trie_key(&trie_key) {
+            continue;
+        }
+        let Some(last_change) = state_changes.changes.last() else { continue };
+        size += record_value(transaction, &trie_key, height, last_change.data.as_deref());
+    }
+    size
+}
+
+/// Adds to the transaction the range of heights covered by the index.
+pub fn set_covered_heights(transaction: &mut DBTransaction, heights: RangeInclusive<BlockHeight>) {
+    transaction.set(
+        DBCol::StateHistory,
+        STATE_HISTORY_TAIL_KEY.to_vec(),
+        borsh::to_vec(heights.start()).expect("Borsh serialize cannot fail"),
+    );
+    set_head(transaction, *heights.end());
+}
+
+/// Adds to the transaction the new highest height covered by the index.
+pub fn set_head(transaction: &mut DBTransaction, height: BlockHeight) {
+    transaction.set(
+        DBCol::StateHistory,
+        STATE_HISTORY_HEAD_KEY.to_vec(),
+        borsh::to_vec(&height).expect("Borsh serialize cannot fail"),
+    );
+}
+
+/// Read access to the state history index.
+///
+/// The store must be either the cold store or the split store of an archival
+/// node; on other nodes the index is simply empty.
+pub struct StateHistory {
+    store: Store,
+}
+
+impl StateHistory {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    /// Returns the heights for which the index is complete or `None` if it
+    /// hasn't been built.
+    pub fn covered_heights(&self) -> io::Result<Option<RangeInclusive<BlockHeight>>> {
+        let tail =
+            self.store.get_ser::<BlockHeight>(DBCol::StateHistory, STATE_HISTORY_TAIL_KEY)?;
+        let head =
+            self.store.get_ser::<BlockHeight>(DBCol::StateHistory, STATE_HISTORY_HEAD_KEY)?;
+        Ok(tail.zip(head).map(|(tail, head)| tail..=head))
+    }
+
+    /// Whether queries about the block at `height` can be answered from the
+    /// index.
+    pub fn covers(&self, height: BlockHeight) -> io::Result<bool> {
+        Ok(self.covered_heights()?.is_some_and(|heights| heights.contains(&height)))
+    }
+
+    /// Returns the value of `trie_key` after the block at `height`.
+    pub fn get(&self, trie_key: &[u8], height: BlockHeight) -> io::Result<Option<Vec<u8>>> {
+        let lower_bound = history_key(trie_key, height);
+        let upper_bound = history_key_upper_bound(trie_key);
+        let Some(item) = self
+            .store
+            .iter_range(DBCol::StateHistory, Some(&lower_bound), Some(&upper_bound))
+            .next()
+        else {
+            return Ok(None);
+        };
+        let (_, value) = item?;
+        Ok(Option::<Vec<u8>>::try_from_slice(&value)?)
+    }
+
+    /// Returns the keys starting with `prefix` and their values after the
+    /// block at `height`, sorted by key.
+    ///
+    /// Seeks to the entry at `height` of every trie key rather than reading
+    /// the whole history of the keys.
+    pub fn iter_prefix(
+        &self,
+        prefix: &[u8],
+        height: BlockHeight,
+    ) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut encoded_prefix = Vec::with_capacity(prefix.len());
+        encode_trie_key(&mut encoded_prefix, prefix, false);
+        let mut values = vec![];
+        let mut lower_bound = encoded_prefix.clone();
+        loop {
+            let Some(item) =
+                self.store.iter_range(DBCol::StateHistory, Some(&lower_bound), None).next()
+            else {
+                break;
+            };
+            let (key, _) = item?;
+            if !key.starts_with(&encoded_prefix) {
+                break;
+            }
+            let Some((trie_key, _)) = split_history_key(&key) else {
+                lower_bound = [&key[..], &[0]].concat();
+                continue;
+            };
+            if let Some(value) = self.get(&trie_key, height)? {
+                values.push((trie_key.clone(), value));
+            }
+            lower_bound = history_key_upper_bound(&trie_key);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_store;
+    use near_primitives::trie_key::trie_key_parsers;
+
+    fn write(store: &Store, transaction: DBTransaction) {
+        store.storage.write(transaction).unwrap();
+    }
+
+    #[test]
+    fn test_get_value_at_height() {
+        let store = create_test_store();
+        let history = StateHistory::new(store.clone());
+        let alice =
+            trie_key_parsers::get_raw_prefix_for_contract_data(&"alice".parse().unwrap(), b"k");
+        let longer_key =
+            trie_key_parsers::get_raw_prefix_for_contract_data(&"alice".parse().unwrap(), b"k\xff");
+        let zero_key =
+            trie_key_parsers::get_raw_prefix_for_contract_data(&"alice".parse().unwrap(), b"k\0");
+
+        let mut transaction = DBTransaction::new();
+        record_value(&mut transaction, &alice, 10, Some(b"a"));
+        record_value(&mut transaction, &alice, 20, None);
+        record_value(&mut transaction, &alice, 30, Some(b"b"));
+        record_value(&mut transaction, &longer_key, 15, Some(b"c"));
+        record_value(&mut transaction, &zero_key, 12, Some(b"d"));
+        record_value(&mut transaction, &zero_key, 18, Some(b"e"));
+        write(&store, transaction);
+
+        assert_eq!(history.get(&alice, 5).unwrap(), None);
+        assert_eq!(history.get(&alice, 10).unwrap().as_deref(), Some(&b"a"[..]));
+        assert_eq!(history.get(&alice, 19).unwrap().as_deref(), Some(&b"a"[..]));
+        assert_eq!(history.get(&alice, 25).unwrap(), None);
+        assert_eq!(history.get(&alice, 100).unwrap().as_deref(), Some(&b"b"[..]));
+        assert_eq!(history.get(&longer_key, 100).unwrap().as_deref(), Some(&b"c"[..]));
+        assert_eq!(history.get(&zero_key, 11).unwrap(), None);
+        assert_eq!(history.get(&zero_key, 17).unwrap().as_deref(), Some(&b"d"[..]));
+
+        assert_eq!(
+            history.iter_prefix(&alice, 17).unwrap(),
+            vec![
+                (alice.clone(), b"a".to_vec()),
+                (zero_key.clone(), b"d".to_vec()),
+                (longer_key.clone(), b"c".to_vec())
+            ]
+        );
+        assert_eq!(
+            history.iter_prefix(&alice, 25).unwrap(),
+            vec![(zero_key.clone(), b"e".to_vec()), (longer_key, b"c".to_vec())]
+        );
+        assert_eq!(history.iter_prefix(&zero_key, 25).unwrap(), vec![(zero_key, b"e".to_vec())]);
+    }
+
+    #[test]
+    fn test_history_key() {
+        let trie_keys: [&[u8]; 5] = [b"a", b"a\0", b"a\0\0", b"a\x01", b"a\xff\0"];
+        for (i, trie_key) in trie_keys.iter().enumerate() {
+            let key = history_key(trie_key, 42);
+            assert_eq!(split_history_key(&key), Some((trie_key.to_vec(), 42)));
+            // Keys are ordered by trie key first, and all the entries of a
+            // trie key are below the entries of the next one.
+            for next in &trie_keys[i + 1..] {
+                assert!(history_key_upper_bound(trie_key) <= history_key(next, u64::MAX));
+            }
+        }
+        assert_eq!(split_history_key(STATE_HISTORY_HEAD_KEY), None);
+    }
+
+    #[test]
+    fn test_covered_heights() {
+        let store = create_test_store();
+        let history = StateHistory::new(store.clone());
+        assert_eq!(history.covered_heights().unwrap(), None);
+        assert!(!history.covers(10).unwrap());
+
+        let mut transaction = DBTransaction::new();
+        set_covered_heights(&mut transaction, 10..=20);
+        write(&store, transaction);
+        assert!(history.covers(10).unwrap());
+        assert!(!history.covers(21).unwrap());
+
+        let mut transaction = DBTransaction::new();
+        set_head(&mut transaction, 21);
+        write(&store, transaction);
+        assert_eq!(history.covered_heights().unwrap(), Some(10..=21));
+    }
+}
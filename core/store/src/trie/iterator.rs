@@ -107,6 +107,14 @@ impl<'a> DiskTrieIterator<'a> {
         self.seek_nibble_slice(NibbleSlice::new(key.as_ref()), true).map(drop)
     }
 
+    /// Position the iterator on the first element with key >= `key`.
+    ///
+    /// Unlike [`Self::seek_prefix`], the iteration doesn’t stop after the
+    /// keys starting with `key` but continues until the end of the trie.
+    pub fn seek<K: AsRef<[u8]>>(&mut self, key: K) -> Result<(), StorageError> {
+        self.seek_nibble_slice(NibbleSlice::new(key.as_ref()), false).map(drop)
+    }
+
     /// Configures whether the iterator should remember all the nodes its
     /// visiting.
     ///
@@ -705,6 +713,27 @@ mod tests {
         assert_eq!(got, want);
     }
 
+    #[test]
+    fn test_disk_iterator_seek() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let (trie_changes, map, trie) = gen_random_trie(&mut rng, false);
+            let seek_keys = trie_changes
+                .iter()
+                .map(|(key, _)| key.clone())
+                .chain(trie_changes.iter().map(|(key, _)| [&key[..], &[0]].concat()))
+                .chain(std::iter::once(vec![]));
+            for seek_key in seek_keys {
+                let mut iterator = trie.disk_iter().unwrap();
+                iterator.seek(&seek_key).unwrap();
+                let got: Vec<_> = iterator.map(Result::unwrap).collect();
+                let want: Vec<_> =
+                    map.range(seek_key..).map(|(k, v)| (k.clone(), v.clone())).collect();
+                assert_eq!(got, want);
+            }
+        }
+    }
+
     #[test]
     fn test_has_value() {
         let mut rng = rand::thread_rng();
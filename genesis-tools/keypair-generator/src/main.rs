@@ -36,12 +36,22 @@ fn main() {
                 .action(clap::ArgAction::SetTrue),
         )
         .subcommand(
-            Command::new("signer-keys").about("Generate signer keys.").arg(
-                Arg::new("num-keys")
-                    .long("num-keys")
-                    .action(clap::ArgAction::Set)
-                    .help("Number of signer keys to generate. (default 3)"),
-            ),
+            Command::new("signer-keys")
+                .about("Generate signer keys.")
+                .arg(
+                    Arg::new("num-keys")
+                        .long("num-keys")
+                        .action(clap::ArgAction::Set)
+                        .help("Number of signer keys to generate. (default 3)"),
+                )
+                .arg(
+                    Arg::new("key-type")
+                        .long("key-type")
+                        .default_value("ed25519")
+                        .value_parser(clap::value_parser!(KeyType))
+                        .help("Type of signer keys to generate: ed25519, secp256k1, eth or webauthn.")
+                        .action(clap::ArgAction::Set),
+                ),
         )
         .subcommand(
             Command::new("node-key").about("Generate key for the node communication."),
@@ -60,8 +70,9 @@ fn main() {
                 .get_one::<String>("num-keys")
                 .map(|x| x.parse().expect("Failed to parse number keys."))
                 .unwrap_or(3usize);
+            let key_type = *args.get_one::<KeyType>("key-type").unwrap();
             let keys: Vec<SecretKey> =
-                (0..num_keys).map(|_| SecretKey::from_random(KeyType::ED25519)).collect();
+                (0..num_keys).map(|_| SecretKey::from_random(key_type)).collect();
             let mut pks = vec![];
             for (i, key) in keys.into_iter().enumerate() {
                 println!("Key#{}", i);
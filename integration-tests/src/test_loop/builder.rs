@@ -230,12 +230,15 @@ impl TestLoopBuilder {
                 iteration_delay: Some(Duration::seconds(1)),
                 location: external_storage_location.clone(),
                 credentials_file: None,
+                manifest_key_file: None,
+                compression: Default::default(),
                 restart_dump_for_shards: None,
             }),
             sync: SyncConfig::ExternalStorage(ExternalStorageConfig {
                 location: external_storage_location,
                 num_concurrent_requests: 1,
                 num_concurrent_requests_during_catchup: 1,
+                trusted_manifest_signers: vec![],
             }),
         };
 
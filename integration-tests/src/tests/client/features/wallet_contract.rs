@@ -418,6 +418,9 @@ fn abi_encode(target: String, action: Action) -> Vec<u8> {
             let (public_key_kind, public_key) = match add_key.public_key {
                 PublicKey::ED25519(key) => (0, key.as_ref().to_vec()),
                 PublicKey::SECP256K1(key) => (1, key.as_ref().to_vec()),
+                PublicKey::ETH(_) | PublicKey::WEBAUTHN(_) => {
+                    panic!("Wallet contract supports only ED25519 and SECP256K1 keys")
+                }
             };
             let nonce = add_key.access_key.nonce;
             let (is_full_access, is_limited_allowance, allowance, receiver_id, method_names) =
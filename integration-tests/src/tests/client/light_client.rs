@@ -0,0 +1,161 @@
+use near_chain::{get_epoch_block_producers_view, Chain, ChainStoreAccess};
+use near_chain_configs::Genesis;
+use near_client::test_utils::TestEnv;
+use near_client::Client;
+use near_crypto::{InMemorySigner, KeyType};
+use near_light_client::LightClient;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::merklize;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, EpochId};
+use near_primitives::views::{ExecutionOutcomeWithIdView, LightClientBlockView};
+use nearcore::test_utils::TestEnvNightshadeSetupExt;
+
+const EPOCH_LENGTH: u64 = 5;
+
+/// Returns the light client block following `last_block_hash` the same way
+/// the `next_light_client_block` RPC method does.
+fn next_light_client_block(
+    client: &Client,
+    last_block_hash: &CryptoHash,
+) -> Option<LightClientBlockView> {
+    let last_block_header = client.chain.get_block_header(last_block_hash).unwrap();
+    let head = client.chain.head().unwrap();
+    if last_block_header.epoch_id() == &head.epoch_id
+        || last_block_header.next_epoch_id() == &head.epoch_id
+    {
+        let head_header = client.chain.get_block_header(&head.last_block_hash).unwrap();
+        let block = Chain::create_light_client_block(
+            &head_header,
+            client.epoch_manager.as_ref(),
+            client.chain.chain_store(),
+        )
+        .unwrap();
+        (block.inner_lite.height > last_block_header.height()).then_some(block)
+    } else {
+        client
+            .chain
+            .chain_store()
+            .get_epoch_light_client_block(&last_block_header.next_epoch_id().0)
+            .ok()
+            .map(|block| LightClientBlockView::clone(&block))
+    }
+}
+
+/// Moves the head of the light client as far as the chain allows.
+fn sync_light_client(client: &Client, light_client: &mut LightClient) {
+    while let Some(block) = next_light_client_block(client, &light_client.head_hash()) {
+        light_client.validate_and_update_head(&block).unwrap();
+    }
+}
+
+/// Returns the proof of an outcome the same way the `light_client_proof` RPC
+/// method does and checks it with the light client.
+fn verify_outcome(
+    client: &Client,
+    light_client: &LightClient,
+    id: &CryptoHash,
+    account_id: &AccountId,
+) {
+    let mut outcome = client.chain.get_execution_outcome(id).unwrap();
+    let epoch_id = *client.chain.get_block_header(&outcome.block_hash).unwrap().epoch_id();
+    let shard_id = client.epoch_manager.account_id_to_shard_id(account_id, &epoch_id).unwrap();
+    let (block_hash, shard_id) = client
+        .chain
+        .get_next_block_hash_with_new_chunk(&outcome.block_hash, shard_id)
+        .unwrap()
+        .unwrap();
+    outcome.block_hash = block_hash;
+    let outcome_roots = client
+        .chain
+        .get_block(&block_hash)
+        .unwrap()
+        .chunks()
+        .iter()
+        .map(|chunk| chunk.prev_outcome_root())
+        .collect::<Vec<_>>();
+    let outcome_root_proof = merklize(&outcome_roots).1[shard_id as usize].clone();
+    let block_header_lite = client.chain.get_block_header(&block_hash).unwrap().into();
+    let block_proof = client.chain.get_block_proof(&block_hash, &light_client.head_hash()).unwrap();
+
+    let outcome = ExecutionOutcomeWithIdView::from(outcome);
+    light_client
+        .verify_outcome(&outcome, &outcome_root_proof, &block_header_lite, &block_proof)
+        .unwrap();
+
+    let mut forged = outcome;
+    forged.outcome.gas_burnt += 1;
+    light_client
+        .verify_outcome(&forged, &outcome_root_proof, &block_header_lite, &block_proof)
+        .unwrap_err();
+}
+
+/// Follows a real chain across epochs with the light client, including
+/// catching up on several epochs at once, and checks proofs of outcomes of a
+/// transaction against it.
+#[test]
+fn test_light_client_follows_chain() {
+    init_test_logger();
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = EPOCH_LENGTH;
+    let mut env = TestEnv::builder(&genesis.config).nightshade_runtimes(&genesis).build();
+
+    let mut height = 1;
+    for _ in 0..EPOCH_LENGTH {
+        env.produce_block(0, height);
+        height += 1;
+    }
+    let client = &env.clients[0];
+    let head = client.chain.head().unwrap();
+    let head_header = client.chain.get_block_header(&head.last_block_hash).unwrap();
+    let trusted = Chain::create_light_client_block(
+        &head_header,
+        client.epoch_manager.as_ref(),
+        client.chain.chain_store(),
+    )
+    .unwrap();
+    let block_producers = get_epoch_block_producers_view(
+        &EpochId(trusted.inner_lite.epoch_id),
+        &head.last_block_hash,
+        client.epoch_manager.as_ref(),
+    )
+    .unwrap();
+    let mut light_client = LightClient::new(&trusted, block_producers).unwrap();
+
+    // Follow the chain block by block.
+    for _ in 0..2 * EPOCH_LENGTH {
+        env.produce_block(0, height);
+        height += 1;
+        sync_light_client(&env.clients[0], &mut light_client);
+    }
+    // Catch up on several epochs at once.
+    for _ in 0..3 * EPOCH_LENGTH {
+        env.produce_block(0, height);
+        height += 1;
+    }
+    let before = light_client.head().inner_lite.height;
+    sync_light_client(&env.clients[0], &mut light_client);
+    assert!(light_client.head().inner_lite.height >= before + 2 * EPOCH_LENGTH);
+
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer.into(),
+        100,
+        env.clients[0].chain.head().unwrap().last_block_hash,
+    );
+    let outcome = env.execute_tx(tx).unwrap();
+    for _ in 0..EPOCH_LENGTH {
+        let height = env.clients[0].chain.head().unwrap().height + 1;
+        env.produce_block(0, height);
+    }
+    let client = &env.clients[0];
+    sync_light_client(client, &mut light_client);
+
+    for outcome in std::iter::once(&outcome.transaction_outcome).chain(&outcome.receipts_outcome) {
+        verify_outcome(client, &light_client, &outcome.id, &outcome.outcome.executor_id);
+    }
+}
@@ -5,6 +5,7 @@ mod chunks_management;
 mod cold_storage;
 mod features;
 mod flat_storage;
+mod light_client;
 mod process_blocks;
 mod resharding;
 mod runtimes;
@@ -351,7 +351,8 @@ fn receive_network_block() {
                 block_merkle_tree.root(),
                 Clock::real(),
                 None,
-            );
+            )
+            .unwrap();
             actor_handles.client_actor.do_send(
                 BlockResponse { block, peer_id: PeerInfo::random().id, was_requested: false }
                     .with_span_context(),
@@ -438,7 +439,8 @@ fn produce_block_with_approvals() {
                 block_merkle_tree.root(),
                 Clock::real(),
                 None,
-            );
+            )
+            .unwrap();
             actor_handles.client_actor.do_send(
                 BlockResponse {
                     block: block.clone(),
@@ -461,7 +463,8 @@ fn produce_block_with_approvals() {
                     block.header().height(),
                     10, // the height at which "test1" is producing
                     &signer,
-                );
+                )
+                .unwrap();
                 actor_handles
                     .client_actor
                     .do_send(BlockApproval(approval, PeerInfo::random().id).with_span_context());
@@ -653,7 +656,8 @@ fn invalid_blocks_common(is_requested: bool) {
                 block_merkle_tree.root(),
                 Clock::real(),
                 None,
-            );
+            )
+            .unwrap();
             // Send block with invalid chunk mask
             let mut block = valid_block.clone();
             block.mut_header().get_mut().inner_rest.chunk_mask = vec![];
@@ -995,6 +999,7 @@ fn client_sync_headers() {
                     connection_established_time: near_async::time::Instant::now(),
                     peer_type: PeerType::Outbound,
                     nonce: 1,
+                    reputation: 0,
                 }],
                 num_connected_peers: 1,
                 peer_max_count: 1,
@@ -2271,7 +2276,7 @@ fn test_validate_chunk_extra() {
         block.mut_header().get_mut().inner_rest.chunk_mask = vec![true];
         block.mut_header().get_mut().inner_lite.prev_outcome_root =
             Block::compute_outcome_root(block.chunks().iter());
-        let endorsement = ChunkEndorsementV1::new(chunk_header.chunk_hash(), &validator_signer);
+        let endorsement = ChunkEndorsementV1::new(&chunk_header, &validator_signer).unwrap();
         block.set_chunk_endorsements(vec![vec![Some(Box::new(endorsement.signature))]]);
         block.mut_header().get_mut().inner_rest.block_body_hash =
             block.compute_block_body_hash().unwrap();
@@ -3323,11 +3328,13 @@ fn test_header_version_downgrade() {
             BlockHeader::BlockHeaderV1(header) => {
                 let header = Arc::make_mut(header);
                 header.inner_rest.latest_protocol_version = PROTOCOL_VERSION;
-                let (hash, signature) = validator_signer.sign_block_header_parts(
-                    header.prev_hash,
-                    &borsh::to_vec(&header.inner_lite).expect("Failed to serialize"),
-                    &borsh::to_vec(&header.inner_rest).expect("Failed to serialize"),
-                );
+                let (hash, signature) = validator_signer
+                    .sign_block_header_parts(
+                        header.prev_hash,
+                        &borsh::to_vec(&header.inner_lite).expect("Failed to serialize"),
+                        &borsh::to_vec(&header.inner_rest).expect("Failed to serialize"),
+                    )
+                    .unwrap();
                 header.hash = hash;
                 header.signature = signature;
             }
@@ -56,6 +56,8 @@ fn test_state_dump() {
             restart_dump_for_shards: None,
             iteration_delay: Some(Duration::ZERO),
             credentials_file: None,
+            manifest_key_file: None,
+            compression: Default::default(),
         });
 
         let validator = MutableConfigValue::new(
@@ -169,6 +171,8 @@ fn run_state_sync_with_dumped_parts(
             restart_dump_for_shards: None,
             iteration_delay: Some(Duration::ZERO),
             credentials_file: None,
+            manifest_key_file: None,
+            compression: Default::default(),
         });
         let mut state_sync_dumper = StateSyncDumper {
             clock: Clock::real(),
@@ -445,6 +445,8 @@ fn sync_state_dump() {
                 restart_dump_for_shards: None,
                 iteration_delay: Some(Duration::milliseconds(500)),
                 credentials_file: None,
+                manifest_key_file: None,
+                compression: Default::default(),
             });
             near1.config.store.state_snapshot_enabled = true;
 
@@ -494,6 +496,7 @@ fn sync_state_dump() {
                                         },
                                         num_concurrent_requests: 1,
                                         num_concurrent_requests_during_catchup: 1,
+                                        trusted_manifest_signers: vec![],
                                     });
 
                                 let nearcore::NearNode {
@@ -17,12 +17,13 @@ use std::collections::HashSet;
 use std::process::Command;
 use std::str;
 
-const LIBS_THRESHOLDS: [(&str, usize); 9] = [
+const LIBS_THRESHOLDS: [(&str, usize); 10] = [
     ("near-primitives", 120),
     ("near-jsonrpc-primitives", 130),
     ("near-chain-configs", 130),
     ("near-chain-primitives", 130),
     ("near-client-primitives", 150),
+    ("near-light-client", 120),
     ("near-parameters", 65),
     ("near-crypto", 75),
     ("near-primitives-core", 60),
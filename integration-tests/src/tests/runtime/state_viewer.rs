@@ -385,6 +385,72 @@ fn test_view_state_with_large_contract() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_view_state_paged() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let shard_uid = TEST_SHARD_UID;
+    let mut state_update = tries.new_trie_update(shard_uid, root);
+    let all_keys: [&[u8]; 5] = [b"a", b"test", b"test1", b"test12", b"test2"];
+    for key in all_keys {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.to_vec() },
+            b"value".to_vec(),
+        );
+    }
+    state_update.set(
+        TrieKey::ContractData { account_id: "alina".parse().unwrap(), key: b"test3".to_vec() },
+        b"value".to_vec(),
+    );
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().1;
+    let mut db_changes = tries.store_update();
+    let new_root = tries.apply_all(&trie_changes, shard_uid, &mut db_changes);
+    db_changes.commit().unwrap();
+    let state_update = tries.new_trie_update(shard_uid, new_root);
+
+    // Walks all the pages and returns the keys of each one.
+    let walk = |trie_viewer: &TrieViewer, prefix: &[u8], limit| {
+        let mut pages = vec![];
+        let mut token = None;
+        loop {
+            let result = trie_viewer
+                .view_state_paged(
+                    &state_update,
+                    &alice_account(),
+                    prefix,
+                    token.as_deref(),
+                    limit,
+                    false,
+                )
+                .unwrap();
+            let page: Vec<Vec<u8>> =
+                result.values.iter().map(|item| item.key.as_ref().to_vec()).collect();
+            pages.push(page);
+            match result.continuation_token {
+                Some(next) => token = Some(next.as_ref().to_vec()),
+                None => return pages,
+            }
+        }
+    };
+    let keys = |keys: &[&[u8]]| keys.iter().map(|key| key.to_vec()).collect::<Vec<_>>();
+
+    let trie_viewer = TrieViewer::default();
+    assert_eq!(walk(&trie_viewer, b"", None), vec![keys(&all_keys)]);
+    assert_eq!(
+        walk(&trie_viewer, b"test", Some(2)),
+        vec![keys(&[b"test", b"test1"]), keys(&[b"test12", b"test2"])]
+    );
+    assert_eq!(walk(&trie_viewer, b"test1", Some(0)), vec![keys(&[b"test1"]), keys(&[b"test12"])]);
+    assert_eq!(walk(&trie_viewer, b"xyz", None), vec![keys(&[])]);
+
+    // The state size limit cuts pages short rather than failing the query.
+    let trie_viewer = TrieViewer::new(Some(10), None);
+    assert_eq!(
+        walk(&trie_viewer, b"test", None),
+        vec![keys(&[b"test", b"test1"]), keys(&[b"test12"]), keys(&[b"test2"])]
+    );
+}
+
 #[test]
 fn test_log_when_panic() {
     let (viewer, root) = get_test_trie_viewer();
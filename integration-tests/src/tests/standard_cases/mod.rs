@@ -16,7 +16,10 @@ use near_primitives::errors::{
 };
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::types::{AccountId, Balance};
-use near_primitives::utils::{derive_eth_implicit_account_id, derive_near_implicit_account_id};
+use near_primitives::utils::{
+    derive_eth_implicit_account_id, derive_eth_implicit_account_id_from_address,
+    derive_near_implicit_account_id,
+};
 use near_primitives::views::{
     AccessKeyView, AccountView, ExecutionMetadataView, FinalExecutionOutcomeView,
     FinalExecutionStatus,
@@ -349,6 +352,8 @@ pub fn transfer_tokens_to_implicit_account(node: impl Node, public_key: PublicKe
     let receiver_id = match public_key.key_type() {
         KeyType::ED25519 => derive_near_implicit_account_id(public_key.unwrap_as_ed25519()),
         KeyType::SECP256K1 => derive_eth_implicit_account_id(public_key.unwrap_as_secp256k1()),
+        KeyType::ETH => derive_eth_implicit_account_id_from_address(public_key.unwrap_as_eth()),
+        KeyType::WEBAUTHN => panic!("WebAuthn keys don't have implicit accounts"),
     };
 
     let transfer_cost = match receiver_id.get_account_type() {
@@ -421,6 +426,8 @@ pub fn trying_to_create_implicit_account(node: impl Node, public_key: PublicKey)
     let receiver_id = match public_key.key_type() {
         KeyType::ED25519 => derive_near_implicit_account_id(public_key.unwrap_as_ed25519()),
         KeyType::SECP256K1 => derive_eth_implicit_account_id(public_key.unwrap_as_secp256k1()),
+        KeyType::ETH => derive_eth_implicit_account_id_from_address(public_key.unwrap_as_eth()),
+        KeyType::WEBAUTHN => panic!("WebAuthn keys don't have implicit accounts"),
     };
 
     let transaction_result = node_user
@@ -21,12 +21,12 @@ use near_chain_configs::{
     default_view_client_throttle_period, get_initial_supply, ChunkDistributionNetworkConfig,
     ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode, LogSummaryStyle,
     MutableConfigValue, MutableValidatorSigner, ReshardingConfig, StateSyncConfig,
-    BLOCK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_PRODUCER_KICKOUT_THRESHOLD,
-    CHUNK_VALIDATOR_ONLY_KICKOUT_THRESHOLD, EXPECTED_EPOCH_LENGTH, FISHERMEN_THRESHOLD,
-    GAS_PRICE_ADJUSTMENT_RATE, GENESIS_CONFIG_FILENAME, INITIAL_GAS_LIMIT, MAX_INFLATION_RATE,
-    MIN_BLOCK_PRODUCTION_DELAY, MIN_GAS_PRICE, NEAR_BASE, NUM_BLOCKS_PER_YEAR,
-    NUM_BLOCK_PRODUCER_SEATS, PROTOCOL_REWARD_RATE, PROTOCOL_UPGRADE_STAKE_THRESHOLD,
-    TRANSACTION_VALIDITY_PERIOD,
+    StoreCheckerConfig, TransactionPoolEvictionConfig, BLOCK_PRODUCER_KICKOUT_THRESHOLD,
+    CHUNK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_VALIDATOR_ONLY_KICKOUT_THRESHOLD,
+    EXPECTED_EPOCH_LENGTH, FISHERMEN_THRESHOLD, GAS_PRICE_ADJUSTMENT_RATE, GENESIS_CONFIG_FILENAME,
+    INITIAL_GAS_LIMIT, MAX_INFLATION_RATE, MIN_BLOCK_PRODUCTION_DELAY, MIN_GAS_PRICE, NEAR_BASE,
+    NUM_BLOCKS_PER_YEAR, NUM_BLOCK_PRODUCER_SEATS, PROTOCOL_REWARD_RATE,
+    PROTOCOL_UPGRADE_STAKE_THRESHOLD, TRANSACTION_VALIDITY_PERIOD,
 };
 use near_config_utils::{ValidationError, ValidationErrors};
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey};
@@ -44,7 +44,9 @@ use near_primitives::types::{
     ShardId,
 };
 use near_primitives::utils::{from_timestamp, get_num_seats_per_shard};
-use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use near_primitives::validator_signer::{
+    InMemoryValidatorSigner, RemoteValidatorSigner, ValidatorSigner,
+};
 use near_primitives::version::PROTOCOL_VERSION;
 #[cfg(feature = "rosetta_rpc")]
 use near_rosetta_rpc::RosettaRpcConfig;
@@ -215,6 +217,10 @@ pub struct Config {
     pub genesis_file: String,
     pub genesis_records_file: Option<String>,
     pub validator_key_file: String,
+    /// If set, the validator key is held by a separate signer process and
+    /// `validator_key_file` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_signer: Option<RemoteSignerConfig>,
     pub node_key_file: String,
     #[cfg(feature = "json_rpc")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -248,6 +254,8 @@ pub struct Config {
     /// Garbage collection configuration.
     #[serde(flatten)]
     pub gc: GCConfig,
+    /// Continuous consistency checks of the database on the running node.
+    pub store_checker: StoreCheckerConfig,
     pub view_client_threads: usize,
     #[serde(with = "near_async::time::serde_duration_as_std")]
     pub view_client_throttle_period: Duration,
@@ -281,6 +289,9 @@ pub struct Config {
     /// Setting this value too low (<1MB) on the validator might lead to production of smaller
     /// chunks and underutilizing the capacity of the network.
     pub transaction_pool_size_limit: Option<u64>,
+    /// Which transactions get evicted from the transaction pool before they are included into a
+    /// chunk, e.g. to make room for new transactions when the pool is full.
+    pub transaction_pool_eviction: TransactionPoolEvictionConfig,
     // Configuration for resharding.
     pub resharding_config: ReshardingConfig,
     /// If the node is not a chunk producer within that many blocks, then route
@@ -332,6 +343,7 @@ impl Default for Config {
             genesis_file: GENESIS_CONFIG_FILENAME.to_string(),
             genesis_records_file: None,
             validator_key_file: VALIDATOR_KEY_FILE.to_string(),
+            remote_signer: None,
             node_key_file: NODE_KEY_FILE.to_string(),
             #[cfg(feature = "json_rpc")]
             rpc: Some(RpcConfig::default()),
@@ -349,6 +361,7 @@ impl Default for Config {
             log_summary_style: LogSummaryStyle::Colored,
             log_summary_period: default_log_summary_period(),
             gc: GCConfig::default(),
+            store_checker: StoreCheckerConfig::default(),
             view_client_threads: default_view_client_threads(),
             view_client_throttle_period: default_view_client_throttle_period(),
             trie_viewer_state_size_limit: default_trie_viewer_state_size_limit(),
@@ -360,6 +373,7 @@ impl Default for Config {
             state_sync: default_state_sync(),
             state_sync_enabled: default_state_sync_enabled(),
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
+            transaction_pool_eviction: TransactionPoolEvictionConfig::default(),
             enable_multiline_logging: default_enable_multiline_logging(),
             resharding_config: ReshardingConfig::default(),
             tx_routing_height_horizon: default_tx_routing_height_horizon(),
@@ -411,6 +425,12 @@ pub struct SplitStorageConfig {
 
     #[serde(default = "default_num_cold_store_read_threads")]
     pub num_cold_store_read_threads: usize,
+
+    /// Answer queries about old blocks from the state history index of the cold
+    /// database, see `cold-store backfill-state-history`.  Without it the index
+    /// is still kept up to date once built, but queries walk the tries.
+    #[serde(default)]
+    pub enable_state_history: bool,
 }
 
 impl Default for SplitStorageConfig {
@@ -423,10 +443,29 @@ impl Default for SplitStorageConfig {
                 default_cold_store_initial_migration_loop_sleep_duration(),
             cold_store_loop_sleep_duration: default_cold_store_loop_sleep_duration(),
             num_cold_store_read_threads: default_num_cold_store_read_threads(),
+            enable_state_history: false,
         }
     }
 }
 
+fn default_remote_signer_timeout() -> Duration {
+    Duration::seconds(1)
+}
+
+/// Configuration of the signer process holding the validator key, see
+/// `near_primitives::validator_signer::remote` for the protocol.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct RemoteSignerConfig {
+    /// Unix socket the signer listens on, relative to the home directory.
+    pub socket_path: PathBuf,
+    /// Account of the validator whose key the signer holds.
+    pub account_id: AccountId,
+    /// How long to wait for the signer to answer a request.
+    #[serde(default = "default_remote_signer_timeout")]
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub timeout: Duration,
+}
+
 impl Config {
     /// load Config from config.json without panic. Do semantic validation on field values.
     /// If config file issues occur, a ValidationError::ConfigFileError will be returned;
@@ -568,6 +607,7 @@ impl NearConfig {
                 save_trie_changes: config.save_trie_changes.unwrap_or(!config.archive),
                 log_summary_style: config.log_summary_style,
                 gc: config.gc,
+                store_checker: config.store_checker.clone(),
                 view_client_threads: config.view_client_threads,
                 view_client_throttle_period: config.view_client_throttle_period,
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
@@ -579,6 +619,7 @@ impl NearConfig {
                 state_sync_enabled: config.state_sync_enabled,
                 state_sync: config.state_sync.unwrap_or_default(),
                 transaction_pool_size_limit: config.transaction_pool_size_limit,
+                transaction_pool_eviction: config.transaction_pool_eviction.clone(),
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
                 resharding_config: MutableConfigValue::new(
                     config.resharding_config,
@@ -689,6 +730,7 @@ impl NightshadeRuntime {
             config.config.gc.gc_num_epochs_to_keep(),
             TrieConfig::from_store_config(&config.config.store),
             state_snapshot_config,
+            config.config.split_storage.as_ref().is_some_and(|c| c.enable_state_history),
         ))
     }
 }
@@ -1407,6 +1449,32 @@ pub fn load_validator_key(validator_file: &Path) -> anyhow::Result<Option<Arc<Va
     }
 }
 
+pub fn load_remote_signer(
+    dir: &Path,
+    config: &RemoteSignerConfig,
+) -> anyhow::Result<Arc<ValidatorSigner>> {
+    let socket_path = dir.join(&config.socket_path);
+    let signer = RemoteValidatorSigner::connect(
+        config.account_id.clone(),
+        socket_path.clone(),
+        config.timeout.unsigned_abs(),
+    )
+    .with_context(|| format!("Failed connecting to remote signer at {}", socket_path.display()))?;
+    Ok(Arc::new(signer.into()))
+}
+
+/// Loads the validator signer from the key file or, if configured, connects to
+/// the remote signer.
+pub fn load_validator_signer(
+    dir: &Path,
+    config: &Config,
+) -> anyhow::Result<Option<Arc<ValidatorSigner>>> {
+    match &config.remote_signer {
+        Some(remote_signer) => load_remote_signer(dir, remote_signer).map(Some),
+        None => load_validator_key(&dir.join(&config.validator_key_file)),
+    }
+}
+
 pub fn load_config(
     dir: &Path,
     genesis_validation: GenesisValidationMode,
@@ -1420,8 +1488,7 @@ pub fn load_config(
         validation_errors.push_errors(e)
     };
 
-    let validator_file: PathBuf = dir.join(&config.validator_key_file);
-    let validator_signer = match load_validator_key(&validator_file) {
+    let validator_signer = match load_validator_signer(dir, &config) {
         Ok(validator_signer) => validator_signer,
         Err(e) => {
             validation_errors.push_validator_key_file_error(e.to_string());
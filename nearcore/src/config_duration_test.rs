@@ -5,7 +5,7 @@ use near_jsonrpc::RpcConfig;
 use near_network::config_json::{ExperimentalConfig, NetworkConfigOverrides};
 use near_o11y::testonly::init_test_logger;
 use near_primitives::types::AccountId;
-use near_store::StoreConfig;
+use near_store::{BackupConfig, StoreConfig};
 use serde::ser::{
     SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
     SerializeTupleStruct, SerializeTupleVariant,
@@ -28,7 +28,11 @@ fn test_config_duration_all_std() {
     // because there are Vec's. So it's best-effort.
     let config = Config {
         chunk_distribution_network: Some(Default::default()),
-        store: StoreConfig { path: Some(Default::default()), ..Default::default() },
+        store: StoreConfig {
+            path: Some(Default::default()),
+            backup: Some(BackupConfig { path: Default::default(), period: Default::default() }),
+            ..Default::default()
+        },
         cold_store: Some(StoreConfig { path: Some(Default::default()), ..Default::default() }),
         enable_multiline_logging: Some(Default::default()),
         expected_shutdown: Some(Default::default()),
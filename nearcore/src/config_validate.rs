@@ -43,6 +43,19 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        // A backup directory may hold backups of a single database only, since
+        // manifests of the hot and cold databases would overwrite each other.
+        let cold_backup = self.config.cold_store.as_ref().and_then(|store| store.backup.as_ref());
+        if let (Some(hot_backup), Some(cold_backup)) = (&self.config.store.backup, cold_backup) {
+            let normalize = |path: &Path| -> std::path::PathBuf {
+                path.components().filter(|c| *c != std::path::Component::CurDir).collect()
+            };
+            if normalize(&hot_backup.path) == normalize(&cold_backup.path) {
+                let error_message = format!("'config.store.backup.path' and 'config.cold_store.backup.path' are both {:?}. Hot and cold databases need to be backed up into different directories.", hot_backup.path);
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+
         if self.config.consensus.min_block_production_delay
             > self.config.consensus.max_block_production_delay
         {
@@ -231,6 +244,23 @@ mod tests {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.store.backup.path' and 'config.cold_store.backup.path' are both \\\"backup\\\". Hot and cold databases need to be backed up into different directories."
+    )]
+    fn test_same_hot_and_cold_backup_path() {
+        let mut config = Config::default();
+        config.save_trie_changes = Some(true);
+        config.store.backup = Some(near_store::BackupConfig {
+            path: "backup".into(),
+            period: near_async::time::Duration::hours(1),
+        });
+        let mut cold_store = config.store.clone();
+        cold_store.backup.as_mut().unwrap().path = "./backup".into();
+        config.cold_store = Some(cold_store);
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "\\nconfig.json semantic issue: 'config.tx_routing_height_horizon' needs to be at least 2, got 1."
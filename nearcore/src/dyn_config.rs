@@ -106,6 +106,14 @@ fn read_validator_key(
     home_dir: &Path,
     config: &Config,
 ) -> Result<Option<Arc<ValidatorSigner>>, UpdateableConfigLoaderError> {
+    if let Some(remote_signer) = &config.remote_signer {
+        // Key rotation is up to the signer process, reconnecting only picks up
+        // changes of the signer configuration.
+        let socket_path = home_dir.join(&remote_signer.socket_path);
+        return crate::config::load_remote_signer(home_dir, remote_signer).map(Some).map_err(
+            |err| UpdateableConfigLoaderError::ValidatorKeyFileError { file: socket_path, err },
+        );
+    }
     let validator_file: PathBuf = home_dir.join(&config.validator_key_file);
     match crate::config::load_validator_key(&validator_file) {
         Ok(Some(validator_signer)) => {
@@ -17,6 +17,7 @@ pub use near_chain::runtime::NightshadeRuntime;
 use near_chain::state_snapshot_actor::{
     get_delete_snapshot_callback, get_make_snapshot_callback, SnapshotCallbacks, StateSnapshotActor,
 };
+use near_chain::store_checker_actor::StoreCheckerActor;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis};
 use near_chain_configs::ReshardingHandle;
@@ -35,11 +36,12 @@ use near_epoch_manager::EpochManagerAdapter;
 use near_network::PeerManagerActor;
 use near_primitives::block::GenesisId;
 use near_primitives::types::EpochId;
+use near_store::backup::spawn_backup_loop;
 use near_store::flat::FlatStateValuesInliningMigrationHandle;
 use near_store::genesis::initialize_sharded_genesis_state;
 use near_store::metadata::DbKind;
 use near_store::metrics::spawn_db_metrics_loop;
-use near_store::{NodeStorage, Store, StoreOpenerError};
+use near_store::{NodeStorage, Store, StoreOpenerError, Temperature};
 use near_telemetry::TelemetryActor;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
@@ -232,6 +234,46 @@ pub fn start_with_config(home_dir: &Path, config: NearConfig) -> anyhow::Result<
     start_with_config_and_synchronization(home_dir, config, None, None)
 }
 
+/// Spawns loops making periodic backups of hot and cold databases for which
+/// backups are configured.
+fn spawn_backup_loops(
+    home_dir: &Path,
+    config: &NearConfig,
+    storage: &NodeStorage,
+) -> Vec<ArbiterHandle> {
+    let opener = NodeStorage::opener(
+        home_dir,
+        config.client_config.archive,
+        &config.config.store,
+        config.config.cold_store.as_ref(),
+    );
+    let mut arbiters = Vec::new();
+    if let Some(backup) = &config.config.store.backup {
+        arbiters.push(spawn_backup_loop(
+            Clock::real(),
+            storage.get_hot_store(),
+            Temperature::Hot,
+            opener.path().to_path_buf(),
+            home_dir.join(&backup.path),
+            backup.period,
+        ));
+    }
+    let cold_backup = config.config.cold_store.as_ref().and_then(|config| config.backup.as_ref());
+    if let (Some(backup), Some(cold_store), Some(cold_path)) =
+        (cold_backup, storage.get_cold_store(), opener.cold_path())
+    {
+        arbiters.push(spawn_backup_loop(
+            Clock::real(),
+            cold_store,
+            Temperature::Cold,
+            cold_path.to_path_buf(),
+            home_dir.join(&backup.path),
+            backup.period,
+        ));
+    }
+    arbiters
+}
+
 pub fn start_with_config_and_synchronization(
     home_dir: &Path,
     mut config: NearConfig,
@@ -249,6 +291,8 @@ pub fn start_with_config_and_synchronization(
         None
     };
 
+    let backup_arbiters = spawn_backup_loops(home_dir, &config, &storage);
+
     let trie_metrics_arbiter = spawn_trie_metrics_loop(
         config.clone(),
         storage.get_hot_store(),
@@ -384,6 +428,22 @@ pub fn start_with_config_and_synchronization(
         config.client_config.archive,
     ));
 
+    let store_checker = if config.client_config.store_checker.enabled {
+        Some(spawn_actix_actor(StoreCheckerActor::new(
+            Clock::real(),
+            config.validator_signer.get().map(|signer| signer.validator_id().clone()),
+            config.genesis.config.clone(),
+            epoch_manager.clone(),
+            shard_tracker.clone(),
+            runtime.clone(),
+            runtime.store().clone(),
+            config.client_config.archive,
+            config.client_config.store_checker.clone(),
+        )))
+    } else {
+        None
+    };
+
     let StartClientResult { client_actor, client_arbiter_handle, resharding_handle } = start_client(
         Clock::real(),
         config.client_config.clone(),
@@ -471,6 +531,9 @@ pub fn start_with_config_and_synchronization(
             client_actor.clone().with_auto_span_context().into_multi_sender(),
             view_client_addr.clone().with_auto_span_context().into_multi_sender(),
             network_actor.into_multi_sender(),
+            store_checker
+                .as_ref()
+                .map(|(actor, _)| actor.clone().with_auto_span_context().into_multi_sender()),
             #[cfg(feature = "test_features")]
             _gc_actor.with_auto_span_context().into_multi_sender(),
             Arc::new(entity_debug_handler),
@@ -506,6 +569,10 @@ pub fn start_with_config_and_synchronization(
     if let Some(db_metrics_arbiter) = db_metrics_arbiter {
         arbiters.push(db_metrics_arbiter);
     }
+    if let Some((_, store_checker_arbiter)) = store_checker {
+        arbiters.push(store_checker_arbiter);
+    }
+    arbiters.extend(backup_arbiters);
 
     Ok(NearNode {
         client: client_actor,
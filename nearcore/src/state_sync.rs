@@ -1,13 +1,16 @@
 use crate::metrics;
 
 use actix_rt::Arbiter;
+use anyhow::Context;
 use borsh::BorshSerialize;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use near_async::time::{Clock, Duration, Instant};
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode, Error};
-use near_chain_configs::{ClientConfig, ExternalStorageLocation, MutableValidatorSigner};
+use near_chain_configs::{
+    ClientConfig, ExternalStorageLocation, MutableValidatorSigner, StateFileCompression,
+};
 use near_client::sync::external::{
     create_bucket_readwrite, external_storage_location, StateFileType,
 };
@@ -15,7 +18,11 @@ use near_client::sync::external::{
     external_storage_location_directory, get_part_id_from_filename, is_part_filename,
     ExternalConnection,
 };
+use near_client::sync::manifest::{
+    ManifestFile, ManifestPart, SignedStatePartsManifest, StatePartsManifest,
+};
 use near_client::sync::state::{StateSync, STATE_DUMP_ITERATION_TIME_LIMIT_SECS};
+use near_crypto::InMemorySigner;
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::hash::CryptoHash;
@@ -80,6 +87,12 @@ impl StateSyncDumper {
                 }
             },
         };
+        let manifest_signer = match &dump_config.manifest_key_file {
+            Some(path) => Some(Arc::new(InMemorySigner::from_file(path).with_context(|| {
+                format!("failed to read manifest key file {}", path.display())
+            })?)),
+            None => None,
+        };
 
         // Determine how many threads to start.
         // TODO(resharding): Handle the case of changing the shard layout.
@@ -127,6 +140,8 @@ impl StateSyncDumper {
                         chain_id.clone(),
                         dump_config.restart_dump_for_shards.clone().unwrap_or_default(),
                         external.clone(),
+                        manifest_signer.clone(),
+                        dump_config.compression,
                         dump_config.iteration_delay.unwrap_or(Duration::seconds(10)),
                         self.validator.clone(),
                         keep_running.clone(),
@@ -301,6 +316,7 @@ async fn upload_state_header(
     shard_id: ShardId,
     state_sync_header: anyhow::Result<Vec<u8>>,
     external: &ExternalConnection,
+    compression: StateFileCompression,
 ) -> bool {
     match state_sync_header {
         Err(err) => {
@@ -311,7 +327,7 @@ async fn upload_state_header(
             let file_type = StateFileType::StateHeader;
             let location =
                 external_storage_location(&chain_id, &epoch_id, epoch_height, shard_id, &file_type);
-            match external.put_file(file_type, &header, shard_id, &location).await {
+            match external.put_file(file_type, &header, shard_id, &location, compression).await {
                 Err(err) => {
                     tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to put header into external storage. Will retry next iteration.");
                     false
@@ -325,6 +341,117 @@ async fn upload_state_header(
     }
 }
 
+/// Uploads the signed manifest of the dumped header and parts unless it's
+/// already in external storage.
+/// Returns true if the manifest is in external storage.
+async fn upload_manifest(
+    chain_id: &String,
+    epoch_id: &EpochId,
+    epoch_height: u64,
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+    state_root: StateRoot,
+    num_parts: u64,
+    chain: &Chain,
+    external: &ExternalConnection,
+    signer: &InMemorySigner,
+) -> bool {
+    match external.is_manifest_stored_for_epoch(shard_id, chain_id, epoch_id, epoch_height).await {
+        Ok(true) => return true,
+        Ok(false) => {}
+        Err(err) => {
+            tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to determine manifest presence in external storage.");
+            return false;
+        }
+    }
+    let manifest = build_manifest(
+        chain_id,
+        epoch_id,
+        epoch_height,
+        shard_id,
+        sync_hash,
+        state_root,
+        num_parts,
+        chain,
+        external,
+    )
+    .await;
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to build the manifest. Will retry next iteration.");
+            return false;
+        }
+    };
+    let data = SignedStatePartsManifest::new(manifest, signer).to_bytes();
+    let file_type = StateFileType::Manifest;
+    let location =
+        external_storage_location(chain_id, epoch_id, epoch_height, shard_id, &file_type);
+    match external.put_file(file_type, &data, shard_id, &location, StateFileCompression::None).await
+    {
+        Err(err) => {
+            tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to put manifest into external storage. Will retry next iteration.");
+            false
+        }
+        Ok(_) => {
+            tracing::debug!(target: "state_sync_dump", shard_id, epoch_height, "Manifest saved to external storage.");
+            true
+        }
+    }
+}
+
+/// Lists hashes of the header and of all parts of the shard.  Parts are read
+/// from the local store if they were dumped by this node and from external
+/// storage otherwise.
+async fn build_manifest(
+    chain_id: &String,
+    epoch_id: &EpochId,
+    epoch_height: u64,
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+    state_root: StateRoot,
+    num_parts: u64,
+    chain: &Chain,
+    external: &ExternalConnection,
+) -> anyhow::Result<StatePartsManifest> {
+    let file_type = StateFileType::StateHeader;
+    let location =
+        external_storage_location(chain_id, epoch_id, epoch_height, shard_id, &file_type);
+    let header = external.get_file(shard_id, &location, &file_type).await?;
+
+    let store = chain.chain_store().store();
+    let mut parts = Vec::with_capacity(num_parts as usize);
+    for part_id in 0..num_parts {
+        let key = borsh::to_vec(&StatePartKey(sync_hash, shard_id, part_id))?;
+        let local_part = store.get(DBCol::StateParts, &key)?.map(|part| ManifestFile::new(&part));
+        let ManifestFile { size, hash } = match local_part {
+            Some(file) => file,
+            None => {
+                let file_type = StateFileType::StatePart { part_id, num_parts };
+                let location = external_storage_location(
+                    chain_id,
+                    epoch_id,
+                    epoch_height,
+                    shard_id,
+                    &file_type,
+                );
+                ManifestFile::new(&external.get_file(shard_id, &location, &file_type).await?)
+            }
+        };
+        parts.push(ManifestPart { part_id, size, hash });
+    }
+    Ok(StatePartsManifest {
+        chain_id: chain_id.clone(),
+        epoch_id: *epoch_id,
+        epoch_height,
+        shard_id,
+        sync_hash,
+        state_root,
+        header: ManifestFile::new(&header),
+        parts,
+    })
+}
+
 const FAILURES_ALLOWED_PER_ITERATION: u32 = 10;
 
 async fn state_sync_dump(
@@ -337,6 +464,8 @@ async fn state_sync_dump(
     chain_id: String,
     restart_dump_for_shards: Vec<ShardId>,
     external: ExternalConnection,
+    manifest_signer: Option<Arc<InMemorySigner>>,
+    compression: StateFileCompression,
     iteration_delay: Duration,
     validator: MutableValidatorSigner,
     keep_running: Arc<AtomicBool>,
@@ -399,6 +528,7 @@ async fn state_sync_dump(
                                     shard_id,
                                     get_serialized_header(shard_id, sync_hash, &chain),
                                     &external,
+                                    compression,
                                 )
                                 .await
                             }
@@ -487,7 +617,13 @@ async fn state_sync_dump(
                                         &file_type,
                                     );
                                     if let Err(err) = external
-                                        .put_file(file_type, &state_part, shard_id, &location)
+                                        .put_file(
+                                            file_type,
+                                            &state_part,
+                                            shard_id,
+                                            &location,
+                                            compression,
+                                        )
                                         .await
                                     {
                                         // no need to break if there's an error, we should keep dumping other parts.
@@ -525,6 +661,30 @@ async fn state_sync_dump(
                                 }
                             }
                         };
+                        // Publish the manifest once all parts are dumped.
+                        let parts_upload_status = match (parts_upload_status, &manifest_signer) {
+                            (
+                                Some(status @ StateSyncDumpProgress::AllDumped { .. }),
+                                Some(signer),
+                            ) => {
+                                let manifest_uploaded = upload_manifest(
+                                    &chain_id,
+                                    &epoch_id,
+                                    epoch_height,
+                                    shard_id,
+                                    sync_hash,
+                                    state_root,
+                                    num_parts,
+                                    &chain,
+                                    &external,
+                                    signer,
+                                )
+                                .await;
+                                // If the upload failed, wait and retry.
+                                manifest_uploaded.then_some(status)
+                            }
+                            (status, _) => status,
+                        };
                         match (&parts_upload_status, &header_upload_status) {
                             (
                                 Some(StateSyncDumpProgress::AllDumped { .. }),
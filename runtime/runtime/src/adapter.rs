@@ -66,4 +66,15 @@ pub trait ViewRuntimeAdapter {
         prefix: &[u8],
         include_proof: bool,
     ) -> Result<ViewStateResult, crate::state_viewer::errors::ViewStateError>;
+
+    fn view_state_paged(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        prefix: &[u8],
+        continuation_token: Option<&[u8]>,
+        limit: Option<u32>,
+        include_proof: bool,
+    ) -> Result<ViewStateResult, crate::state_viewer::errors::ViewStateError>;
 }
@@ -295,12 +295,16 @@ impl Runtime {
         let span = tracing::Span::current();
         metrics::TRANSACTION_PROCESSED_TOTAL.inc();
 
+        // Simulated transactions may be unsigned.  Signatures of the signed ones are
+        // checked by the caller before the simulation.
+        let verify_signature =
+            !matches!(apply_state.apply_reason, Some(ApplyChunkReason::SimulateTransaction));
         match verify_and_charge_transaction(
             &apply_state.config,
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            verify_signature,
             Some(apply_state.block_height),
             apply_state.current_protocol_version,
         ) {
@@ -1812,6 +1816,14 @@ impl Runtime {
         // TODO(#8859): Introduce a dedicated `compute_limit` for the chunk.
         // For now compute limit always matches the gas limit.
         let compute_limit = apply_state.gas_limit.unwrap_or(Gas::max_value());
+        // Simulations only execute the receipts of the simulated transaction, so the
+        // backlog of delayed receipts and yield timeouts already in the state is left alone.
+        let backlog_compute_limit =
+            if matches!(apply_state.apply_reason, Some(ApplyChunkReason::SimulateTransaction)) {
+                0
+            } else {
+                compute_limit
+            };
         let proof_size_limit = if ProtocolFeature::StatelessValidation.enabled(protocol_version) {
             Some(apply_state.config.witness_config.main_storage_proof_size_soft_limit)
         } else {
@@ -1831,7 +1843,7 @@ impl Runtime {
         let processed_delayed_receipts = self.process_delayed_receipts(
             processing_state,
             receipt_sink,
-            compute_limit,
+            backlog_compute_limit,
             proof_size_limit,
             &mut validator_proposals,
         )?;
@@ -1849,7 +1861,7 @@ impl Runtime {
         let promise_yield_result = resolve_promise_yield_timeouts(
             processing_state,
             receipt_sink,
-            compute_limit,
+            backlog_compute_limit,
             proof_size_limit,
         )?;
 
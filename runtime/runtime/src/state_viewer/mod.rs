@@ -25,6 +25,9 @@ use tracing::debug;
 
 pub mod errors;
 
+/// Maximum number of entries returned by [`TrieViewer::view_state_paged`].
+pub const MAX_VIEW_STATE_PAGE_LIMIT: u32 = 1000;
+
 /// State for the view call.
 #[derive(Debug)]
 pub struct ViewApplyState {
@@ -71,6 +74,11 @@ impl TrieViewer {
         Self { state_size_limit, max_gas_burnt_view }
     }
 
+    /// Upper bound of the byte size of contract state that is still viewable.
+    pub fn state_size_limit(&self) -> Option<u64> {
+        self.state_size_limit
+    }
+
     pub fn view_account(
         &self,
         state_update: &TrieUpdate,
@@ -175,7 +183,75 @@ impl TrieViewer {
             values.push(StateItem { key: key[acc_sep_len..].to_vec().into(), value: value.into() });
         }
         let proof = iter.into_visited_nodes();
-        Ok(ViewStateResult { values, proof })
+        Ok(ViewStateResult { values, proof, continuation_token: None })
+    }
+
+    /// Returns a page of the contract data of the account with keys starting
+    /// with `prefix`.
+    ///
+    /// The page begins after the key given as `continuation_token` and holds
+    /// at most `limit` entries (capped at [`MAX_VIEW_STATE_PAGE_LIMIT`]).
+    /// Unlike [`Self::view_state`] this works for accounts of any size: once
+    /// the entries exceed the state size limit the page is cut short.  If
+    /// there are more entries, the key of the last one is returned as the
+    /// continuation token for the next page.
+    pub fn view_state_paged(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        prefix: &[u8],
+        continuation_token: Option<&[u8]>,
+        limit: Option<u32>,
+        include_proof: bool,
+    ) -> Result<ViewStateResult, errors::ViewStateError> {
+        if get_account(state_update, account_id)?.is_none() {
+            return Err(errors::ViewStateError::AccountDoesNotExist {
+                requested_account_id: account_id.clone(),
+            });
+        }
+        let limit = limit.unwrap_or(MAX_VIEW_STATE_PAGE_LIMIT).clamp(1, MAX_VIEW_STATE_PAGE_LIMIT);
+
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+        let acc_sep_len = query.len() - prefix.len();
+        // Smallest key greater than the token, i.e. the token followed by
+        // a zero byte.
+        let start = match continuation_token {
+            Some(token) => {
+                let mut start =
+                    trie_key_parsers::get_raw_prefix_for_contract_data(account_id, token);
+                start.push(0);
+                start.max(query.clone())
+            }
+            None => query.clone(),
+        };
+        let mut iter = state_update.trie().disk_iter()?;
+        iter.remember_visited_nodes(include_proof);
+        iter.seek(&start)?;
+
+        let mut values: Vec<StateItem> = vec![];
+        let mut size = 0u64;
+        let mut continuation_token = None;
+        for item in &mut iter {
+            let (key, value) = item?;
+            if !key.starts_with(&query) {
+                break;
+            }
+            // Every page holds at least one entry so that the iteration
+            // always makes progress.
+            let page_full = !values.is_empty()
+                && (values.len() == limit as usize
+                    || self
+                        .state_size_limit
+                        .is_some_and(|state_size_limit| size >= state_size_limit));
+            if page_full {
+                continuation_token = values.last().map(|item| item.key.clone());
+                break;
+            }
+            size += (key.len() - acc_sep_len + value.len()) as u64;
+            values.push(StateItem { key: key[acc_sep_len..].to_vec().into(), value: value.into() });
+        }
+        let proof = iter.into_visited_nodes();
+        Ok(ViewStateResult { values, proof, continuation_token })
     }
 
     pub fn call_function(
@@ -2,6 +2,7 @@ use crate::config::{total_prepaid_gas, tx_cost, TransactionCost};
 use crate::near_primitives::account::Account;
 use crate::VerificationResult;
 use near_crypto::key_conversion::is_valid_staking_key;
+use near_crypto::PublicKey;
 use near_parameters::RuntimeConfig;
 use near_primitives::account::AccessKeyPermission;
 use near_primitives::action::delegate::SignedDelegateAction;
@@ -104,6 +105,12 @@ pub fn validate_transaction(
     let transaction = &signed_transaction.transaction;
     let signer_id = transaction.signer_id();
 
+    if let PublicKey::WEBAUTHN(_) = transaction.public_key() {
+        if !checked_feature!("stable", WebAuthnKeys, current_protocol_version) {
+            return Err(InvalidTxError::InvalidSignature);
+        }
+    }
+
     if verify_signature
         && !signed_transaction
             .signature
@@ -427,7 +434,7 @@ pub fn validate_action(
             check_feature_enabled(ProtocolFeature::NonrefundableStorage, current_protocol_version)
         }
         Action::Stake(a) => validate_stake_action(a),
-        Action::AddKey(a) => validate_add_key_action(limit_config, a),
+        Action::AddKey(a) => validate_add_key_action(limit_config, a, current_protocol_version),
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(a) => validate_delete_action(a),
         Action::Delegate(a) => validate_delegate_action(limit_config, a, current_protocol_version),
@@ -503,7 +510,17 @@ fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationEr
 fn validate_add_key_action(
     limit_config: &LimitConfig,
     action: &AddKeyAction,
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
+    match action.public_key {
+        PublicKey::ETH(_) => {
+            check_key_type_enabled(ProtocolFeature::EthAddressKeys, current_protocol_version)?
+        }
+        PublicKey::WEBAUTHN(_) => {
+            check_key_type_enabled(ProtocolFeature::WebAuthnKeys, current_protocol_version)?
+        }
+        PublicKey::ED25519(_) | PublicKey::SECP256K1(_) => {}
+    }
     if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
         // Check whether `receiver_id` is a valid account_id. Historically, we
         // allowed arbitrary strings there!
@@ -570,6 +587,21 @@ fn check_feature_enabled(
     }
 }
 
+/// Checks that keys gated by `feature` may be added to accounts.
+fn check_key_type_enabled(
+    feature: ProtocolFeature,
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), ActionsValidationError> {
+    if feature.enabled(current_protocol_version) {
+        Ok(())
+    } else {
+        Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: format!("{feature:?}"),
+            version: feature.protocol_version(),
+        })
+    }
+}
+
 fn truncate_string(s: &str, limit: usize) -> String {
     for i in (0..=limit).rev() {
         if let Some(s) = s.get(..i) {
@@ -918,6 +950,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_transaction_webauthn_signature() {
+        let config = RuntimeConfig::test();
+        let signer: Signer =
+            InMemorySigner::from_seed(alice_account(), KeyType::WEBAUTHN, "alice").into();
+        let tx = SignedTransaction::send_money(
+            1,
+            alice_account(),
+            bob_account(),
+            &signer,
+            100,
+            CryptoHash::default(),
+        );
+
+        let protocol_version = ProtocolFeature::WebAuthnKeys.protocol_version();
+        assert_eq!(
+            validate_transaction(&config, 100, &tx, true, protocol_version - 1)
+                .expect_err("expected an error"),
+            InvalidTxError::InvalidSignature,
+        );
+        validate_transaction(&config, 100, &tx, true, protocol_version).expect("valid transaction");
+    }
+
     #[test]
     fn test_validate_transaction_invalid_access_key_not_found() {
         let config = RuntimeConfig::test();
@@ -1868,6 +1923,24 @@ mod tests {
         .expect("valid action");
     }
 
+    #[test]
+    fn test_validate_action_add_eth_key() {
+        let action = Action::AddKey(Box::new(AddKeyAction {
+            public_key: PublicKey::from_seed(KeyType::ETH, "test"),
+            access_key: AccessKey::full_access(),
+        }));
+        let protocol_version = ProtocolFeature::EthAddressKeys.protocol_version();
+        assert_eq!(
+            validate_action(&test_limit_config(), &action, protocol_version - 1)
+                .expect_err("Expected an error"),
+            ActionsValidationError::UnsupportedProtocolFeature {
+                protocol_feature: "EthAddressKeys".to_string(),
+                version: protocol_version,
+            },
+        );
+        validate_action(&test_limit_config(), &action, protocol_version).expect("valid action");
+    }
+
     #[test]
     fn test_validate_action_valid_delete_key() {
         validate_action(
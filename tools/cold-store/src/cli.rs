@@ -4,17 +4,24 @@ use anyhow::Context;
 use borsh::BorshDeserialize;
 use clap;
 use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
-use near_primitives::block::Tip;
+use near_primitives::block::{Block, Tip};
 use near_primitives::epoch_block_info::BlockInfo;
 use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::get_block_shard_uid;
+use near_primitives::trie_key::col;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{BlockHeight, RawStateChangesWithTrieKey};
 use near_store::cold_storage::{copy_all_data_to_cold, update_cold_db, update_cold_head};
+use near_store::db::{ColdDB, DBTransaction, Database};
 use near_store::metadata::DbKind;
-use near_store::{DBCol, NodeStorage, Store, StoreOpener};
+use near_store::state_history;
+use near_store::{DBCol, NodeStorage, Store, StoreOpener, Trie, TrieDBStorage};
 use near_store::{COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY, TAIL_KEY};
 use nearcore::NearConfig;
 use rand::seq::SliceRandom;
 use std::io::Result;
 use std::path::Path;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
 
 #[derive(clap::Parser)]
@@ -56,6 +63,12 @@ enum SubCommand {
     /// Modifies cold db from config to be considered not initialised.
     /// Doesn't actually delete any data, except for HEAD and COLD_HEAD in BlockMisc.
     ResetCold(ResetColdCmd),
+    /// Builds the state history index used to answer queries about old blocks.
+    /// Writes the state at the given height and the state changes of every
+    /// block up to the cold head.  Once built, the index is kept up to date by
+    /// the node when it copies new blocks to cold storage.
+    /// Requires the `--readwrite` flag and the node to be stopped.
+    BackfillStateHistory(BackfillStateHistoryCmd),
 }
 
 impl ColdStoreCommand {
@@ -91,6 +104,7 @@ impl ColdStoreCommand {
             SubCommand::PrepareHot(cmd) => cmd.run(&storage, &home_dir, &near_config),
             SubCommand::CheckStateRoot(cmd) => cmd.run(&storage),
             SubCommand::ResetCold(cmd) => cmd.run(&storage),
+            SubCommand::BackfillStateHistory(cmd) => cmd.run(&storage, epoch_manager.as_ref()),
         }
     }
 
@@ -670,3 +684,145 @@ impl ResetColdCmd {
         Ok(())
     }
 }
+
+#[derive(clap::Args)]
+struct BackfillStateHistoryCmd {
+    /// Height of the first block covered by the index.  The whole state at
+    /// that height is written, so picking a recent height keeps the backfill
+    /// short.
+    #[clap(long)]
+    from_height: BlockHeight,
+    /// Threshold size of the write transaction.
+    #[clap(short = 'b', long, default_value_t = 500_000_000)]
+    batch_size: usize,
+}
+
+impl BackfillStateHistoryCmd {
+    pub fn run(
+        self,
+        storage: &NodeStorage,
+        epoch_manager: &EpochManagerHandle,
+    ) -> anyhow::Result<()> {
+        let cold_db =
+            storage.cold_db().ok_or_else(|| anyhow::anyhow!("Cold storage is not configured"))?;
+        let cold_store = storage.get_cold_store().unwrap();
+        let hot_store = storage.get_hot_store();
+        let cold_head = cold_store
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+            .ok_or_else(|| anyhow::anyhow!("The cold head is missing"))?;
+        if self.from_height > cold_head.height {
+            return Err(anyhow::anyhow!(
+                "The cold head {} is below the requested height {}",
+                cold_head.height,
+                self.from_height
+            ));
+        }
+
+        let mut writer = StateHistoryWriter::new(cold_db, self.batch_size);
+        self.write_state(&hot_store, &cold_store, epoch_manager, &mut writer)?;
+        for height in self.from_height + 1..=cold_head.height {
+            // BlockHeight is never garbage collected from the hot store.
+            let Some(block_hash) = get_ser_from_store::<CryptoHash>(
+                &hot_store,
+                DBCol::BlockHeight,
+                &height.to_le_bytes(),
+            ) else {
+                continue;
+            };
+            for item in cold_store.iter_prefix_ser::<RawStateChangesWithTrieKey>(
+                DBCol::StateChanges,
+                block_hash.as_ref(),
+            ) {
+                let (_, state_changes) = item?;
+                writer.add(|transaction| {
+                    state_history::record_state_changes(transaction, height, [state_changes])
+                })?;
+            }
+            if height % 10_000 == 0 {
+                tracing::info!(target: "cold_store", height, cold_head = cold_head.height, "Backfilling state history");
+            }
+        }
+        writer.add(|transaction| {
+            state_history::set_covered_heights(transaction, self.from_height..=cold_head.height);
+            0
+        })?;
+        writer.write()?;
+        println!("State history covers heights {}..={}", self.from_height, cold_head.height);
+        Ok(())
+    }
+
+    /// Writes the indexed part of the state after the block at `from_height`.
+    fn write_state(
+        &self,
+        hot_store: &Store,
+        cold_store: &Store,
+        epoch_manager: &EpochManagerHandle,
+        writer: &mut StateHistoryWriter,
+    ) -> anyhow::Result<()> {
+        let block_hash = get_ser_from_store::<CryptoHash>(
+            hot_store,
+            DBCol::BlockHeight,
+            &self.from_height.to_le_bytes(),
+        )
+        .ok_or_else(|| anyhow::anyhow!("No block at height {}", self.from_height))?;
+        let block = cold_store
+            .get_ser::<Block>(DBCol::Block, block_hash.as_ref())?
+            .ok_or_else(|| anyhow::anyhow!("Failed to find Block: {:?}", block_hash))?;
+        let shard_layout = epoch_manager.get_shard_layout(block.header().epoch_id())?;
+        for shard_uid in shard_layout.shard_uids() {
+            let chunk_extra = cold_store
+                .get_ser::<ChunkExtra>(
+                    DBCol::ChunkExtra,
+                    &get_block_shard_uid(&block_hash, &shard_uid),
+                )?
+                .ok_or_else(|| anyhow::anyhow!("Failed to find ChunkExtra of {shard_uid}"))?;
+            tracing::info!(target: "cold_store", %shard_uid, state_root = ?chunk_extra.state_root(), "Writing state to state history");
+            let storage = Arc::new(TrieDBStorage::new(cold_store.clone(), shard_uid));
+            let trie = Trie::new(storage, *chunk_extra.state_root(), None);
+            for col in [col::ACCOUNT, col::CONTRACT_CODE, col::ACCESS_KEY, col::CONTRACT_DATA] {
+                let mut iter = trie.disk_iter()?;
+                iter.seek_prefix([col])?;
+                for item in iter {
+                    let (key, value) = item?;
+                    writer.add(|transaction| {
+                        state_history::record_value(
+                            transaction,
+                            &key,
+                            self.from_height,
+                            Some(&value),
+                        )
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the state history to the cold db in batches of ~`batch_size` bytes.
+struct StateHistoryWriter<'a> {
+    cold_db: &'a ColdDB,
+    transaction: DBTransaction,
+    transaction_size: usize,
+    batch_size: usize,
+}
+
+impl<'a> StateHistoryWriter<'a> {
+    fn new(cold_db: &'a ColdDB, batch_size: usize) -> Self {
+        Self { cold_db, transaction: DBTransaction::new(), transaction_size: 0, batch_size }
+    }
+
+    fn add(&mut self, f: impl FnOnce(&mut DBTransaction) -> usize) -> std::io::Result<()> {
+        self.transaction_size += f(&mut self.transaction);
+        if self.transaction_size > self.batch_size {
+            self.write()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self) -> std::io::Result<()> {
+        self.cold_db.write(std::mem::take(&mut self.transaction))?;
+        self.transaction_size = 0;
+        Ok(())
+    }
+}
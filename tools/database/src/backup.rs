@@ -0,0 +1,119 @@
+use near_async::time::Clock;
+use near_store::backup::BackupDir;
+use near_store::{Mode, NodeStorage};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args)]
+pub(crate) struct BackupCommand {
+    /// Directory holding the backups.
+    #[clap(long)]
+    backup_dir: PathBuf,
+
+    #[clap(subcommand)]
+    subcmd: BackupSubCommand,
+}
+
+#[derive(clap::Subcommand)]
+enum BackupSubCommand {
+    /// Make a new backup of the database.  The node must not be running; to
+    /// back up a running node set `store.backup` in config.json instead.
+    Create {
+        /// Back up the cold database rather than the hot one.
+        #[clap(long)]
+        cold: bool,
+    },
+    /// List backups in the backup directory.
+    List,
+    /// Check that files of a backup are present and match their hashes.
+    Verify {
+        /// Backup to verify.  The latest one by default.
+        #[clap(long)]
+        id: Option<u64>,
+    },
+    /// Restore the database from a backup.
+    Restore {
+        /// Backup to restore.  The latest one by default.
+        #[clap(long)]
+        id: Option<u64>,
+        /// Directory to restore the database into.  Must not exist or be
+        /// empty.
+        #[clap(long)]
+        destination: PathBuf,
+    },
+}
+
+impl BackupCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        let backup_dir = BackupDir::new(&self.backup_dir);
+        match &self.subcmd {
+            BackupSubCommand::Create { cold } => self.create(home, &backup_dir, *cold),
+            BackupSubCommand::List => {
+                for id in backup_dir.list()? {
+                    let manifest = backup_dir.read_manifest(id)?;
+                    println!(
+                        "{id}\t{}\t{} files\t{}",
+                        manifest.created_at,
+                        manifest.files.len(),
+                        bytesize::ByteSize::b(manifest.total_size())
+                    );
+                }
+                Ok(())
+            }
+            BackupSubCommand::Verify { id } => {
+                let id = resolve_id(&backup_dir, *id)?;
+                let errors = backup_dir.verify(id)?;
+                for err in &errors {
+                    println!("{err}");
+                }
+                anyhow::ensure!(errors.is_empty(), "backup {id} is corrupted");
+                println!("Backup {id} is intact.");
+                Ok(())
+            }
+            BackupSubCommand::Restore { id, destination } => {
+                let id = resolve_id(&backup_dir, *id)?;
+                let manifest = backup_dir.restore(id, destination)?;
+                println!(
+                    "Restored backup {id} made at {} into {}.",
+                    manifest.created_at,
+                    destination.display()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn create(&self, home: &Path, backup_dir: &BackupDir, cold: bool) -> anyhow::Result<()> {
+        let config = nearcore::config::Config::from_file_skip_validation(
+            &home.join(nearcore::config::CONFIG_FILENAME),
+        )?;
+        let opener =
+            NodeStorage::opener(home, config.archive, &config.store, config.cold_store.as_ref());
+        let storage = opener.open_in_mode(Mode::ReadWriteExisting)?;
+        let (store, db_path) = if cold {
+            let store = storage.get_cold_store();
+            let db_path = opener.cold_path();
+            store.zip(db_path).ok_or_else(|| anyhow::anyhow!("cold store is not configured"))?
+        } else {
+            (storage.get_hot_store(), opener.path())
+        };
+        let stats = backup_dir.create(&Clock::real(), &store, db_path)?;
+        println!(
+            "Created backup {} of {} files ({}), copied {} new files ({}).",
+            stats.manifest.id,
+            stats.manifest.files.len(),
+            bytesize::ByteSize::b(stats.manifest.total_size()),
+            stats.copied_files,
+            bytesize::ByteSize::b(stats.copied_bytes)
+        );
+        Ok(())
+    }
+}
+
+fn resolve_id(backup_dir: &BackupDir, id: Option<u64>) -> anyhow::Result<u64> {
+    match id {
+        Some(id) => Ok(id),
+        None => backup_dir
+            .latest()?
+            .ok_or_else(|| anyhow::anyhow!("no backups in {}", backup_dir.path().display())),
+    }
+}
@@ -4,7 +4,10 @@ use crate::analyse_gas_usage::AnalyseGasUsageCommand;
 use crate::analyse_high_load::HighLoadStatsCommand;
 use crate::analyze_contract_sizes::AnalyzeContractSizesCommand;
 use crate::analyze_delayed_receipt::AnalyzeDelayedReceiptCommand;
+use crate::backup::BackupCommand;
 use crate::compact::RunCompactionCommand;
+#[cfg(feature = "redb")]
+use crate::convert_db::ConvertDbCommand;
 use crate::corrupt::CorruptStateSnapshotCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::LoadMemTrieCommand;
@@ -30,12 +33,19 @@ enum SubCommand {
     /// Analyse gas usage in a chosen sequnce of blocks
     AnalyseGasUsage(AnalyseGasUsageCommand),
 
+    /// Create, verify and restore incremental backups of the database
+    Backup(BackupCommand),
+
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
     /// Run SST file compaction on database
     CompactDatabase(RunCompactionCommand),
 
+    /// Copy the RocksDB database into a new database using another backend
+    #[cfg(feature = "redb")]
+    ConvertDb(ConvertDbCommand),
+
     /// Corrupt the state snapshot.
     CorruptStateSnapshot(CorruptStateSnapshotCommand),
 
@@ -46,7 +56,7 @@ enum SubCommand {
     RunMigrations(RunMigrationsCommand),
 
     /// Run performance test for State column reads.
-    /// Uses RocksDB data specified via --home argument.
+    /// Uses the database specified via --home argument or --db-path.
     StatePerf(StatePerfCommand),
 
     /// Loads an in-memory trie for research purposes.
@@ -70,8 +80,11 @@ impl DatabaseCommand {
         match &self.subcmd {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
             SubCommand::AnalyseGasUsage(cmd) => cmd.run(home),
+            SubCommand::Backup(cmd) => cmd.run(home),
             SubCommand::ChangeDbKind(cmd) => cmd.run(home),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
+            #[cfg(feature = "redb")]
+            SubCommand::ConvertDb(cmd) => cmd.run(home),
             SubCommand::CorruptStateSnapshot(cmd) => cmd.run(home),
             SubCommand::MakeSnapshot(cmd) => {
                 let near_config = load_config(home);
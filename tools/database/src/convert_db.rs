@@ -0,0 +1,73 @@
+use crate::utils::open_rocksdb;
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use near_store::db::{DBTransaction, Database, RedbDB};
+use near_store::{DBCol, DbBackend};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+
+/// Approximate size of a single write transaction to the new database.
+const BATCH_SIZE_BYTES: usize = 64 * bytesize::MIB as usize;
+
+#[derive(Parser)]
+pub(crate) struct ConvertDbCommand {
+    /// Directory to create the new database in.  Must not contain a database
+    /// yet.
+    #[arg(long)]
+    destination: PathBuf,
+
+    /// Backend of the new database.
+    #[arg(long, default_value_t = DbBackend::Redb)]
+    backend: DbBackend,
+}
+
+impl ConvertDbCommand {
+    pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.backend == DbBackend::Redb,
+            "converting into {} database is not supported",
+            self.backend
+        );
+        let source = open_rocksdb(home, near_store::Mode::ReadOnly)?;
+        let config = nearcore::config::Config::from_file_skip_validation(
+            &home.join(nearcore::config::CONFIG_FILENAME),
+        )?;
+        let target = RedbDB::open(&self.destination, &config.store, near_store::Mode::Create)?;
+
+        for col in DBCol::iter() {
+            let progress = ProgressBar::new_spinner();
+            progress.set_style(
+                ProgressStyle::default_spinner().template("{msg}: {pos} entries [{elapsed}]"),
+            );
+            progress.set_message(<&str>::from(col));
+            let mut batch = DBTransaction::new();
+            let mut batch_size = 0;
+            for item in source.iter_raw_bytes(col) {
+                let (key, value) = item?;
+                // Reference counted values whose count dropped to zero wait
+                // for compaction to be removed from RocksDB.  Skip them.
+                if col.is_rc() && value.is_empty() {
+                    continue;
+                }
+                batch_size += key.len() + value.len();
+                batch.set(col, key.into_vec(), value.into_vec());
+                progress.inc(1);
+                if batch_size >= BATCH_SIZE_BYTES {
+                    target.write(std::mem::take(&mut batch))?;
+                    batch_size = 0;
+                }
+            }
+            target.write(batch)?;
+            progress.finish();
+        }
+        target.flush()?;
+
+        println!("Converted the database into {}.", self.destination.display());
+        println!("To use it, set the following in config.json of the node:");
+        println!(
+            "  \"store\": {{ \"path\": {:?}, \"backend\": \"{}\" }}",
+            self.destination, self.backend
+        );
+        Ok(())
+    }
+}
@@ -4,9 +4,12 @@ mod analyse_gas_usage;
 mod analyse_high_load;
 mod analyze_contract_sizes;
 mod analyze_delayed_receipt;
+mod backup;
 mod block_iterators;
 pub mod commands;
 mod compact;
+#[cfg(feature = "redb")]
+mod convert_db;
 mod corrupt;
 mod make_snapshot;
 mod memtrie;
@@ -2,8 +2,7 @@ use clap::Parser;
 use indicatif::{ProgressBar, ProgressIterator};
 use std::collections::BTreeMap;
 use std::fmt::{Display, Write};
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use near_primitives::shard_layout::{ShardLayout, ShardUId};
@@ -13,9 +12,7 @@ use rand::seq::SliceRandom;
 use rand::SeedableRng;
 
 use near_store::flat::store_helper::iter_flat_state_entries;
-use near_store::{Store, TrieStorage};
-
-use crate::utils::open_rocksdb;
+use near_store::{DbBackend, NodeStorage, Store, TrieStorage};
 
 #[derive(Parser)]
 pub(crate) struct StatePerfCommand {
@@ -28,14 +25,29 @@ pub(crate) struct StatePerfCommand {
     /// Those requests will be excluded from the measurements.
     #[arg(short, long, default_value_t = 1000)]
     warmup_samples: usize,
+
+    /// Path of the database to test instead of the one configured in
+    /// `store.path`, e.g. one created with `convert-db` to compare it with
+    /// the original.  Backend of the database is detected automatically.
+    #[arg(long)]
+    db_path: Option<PathBuf>,
 }
 
 impl StatePerfCommand {
     pub(crate) fn run(&self, home: &Path) -> anyhow::Result<()> {
-        let rocksdb = Arc::new(open_rocksdb(home, near_store::Mode::ReadOnly)?);
-        let store = near_store::NodeStorage::new(rocksdb).get_hot_store();
-        eprintln!("Start State perf test");
-        let mut perf_context = PerfContext::new();
+        let config = nearcore::config::Config::from_file_skip_validation(
+            &home.join(nearcore::config::CONFIG_FILENAME),
+        )?;
+        let mut store_config = config.store;
+        if let Some(db_path) = &self.db_path {
+            store_config.path = Some(db_path.clone());
+        }
+        let opener =
+            NodeStorage::opener(home, config.archive, &store_config, config.cold_store.as_ref());
+        let backend = DbBackend::detect(opener.path()).unwrap_or(store_config.backend);
+        let store = opener.open_in_mode(near_store::Mode::ReadOnly)?.get_hot_store();
+        eprintln!("Start State perf test of {backend} database at {}", opener.path().display());
+        let mut perf_context = PerfContext::new(backend);
         let total_samples = self.warmup_samples + self.samples;
         for (sample_i, (shard_uid, value_ref)) in
             generate_state_requests(store.clone(), total_samples).into_iter().enumerate().progress()
@@ -57,7 +69,9 @@ impl StatePerfCommand {
 }
 
 struct PerfContext {
-    rocksdb_context: rocksdb::perf::PerfContext,
+    /// RocksDB perf context used to measure block reads.  `None` for other
+    /// backends which are only measured by the observed latency.
+    rocksdb_context: Option<rocksdb::perf::PerfContext>,
     start: Instant,
     measurements_per_block_reads: BTreeMap<usize, Measurements>,
     measurements_overall: Measurements,
@@ -108,10 +122,13 @@ impl Display for Measurements {
 }
 
 impl PerfContext {
-    fn new() -> Self {
-        rocksdb::perf::set_perf_stats(rocksdb::perf::PerfStatsLevel::EnableTime);
+    fn new(backend: DbBackend) -> Self {
+        let rocksdb_context = (backend == DbBackend::RocksDb).then(|| {
+            rocksdb::perf::set_perf_stats(rocksdb::perf::PerfStatsLevel::EnableTime);
+            rocksdb::perf::PerfContext::default()
+        });
         Self {
-            rocksdb_context: rocksdb::perf::PerfContext::default(),
+            rocksdb_context,
             start: Instant::now(),
             measurements_per_block_reads: BTreeMap::new(),
             measurements_overall: Measurements::default(),
@@ -119,22 +136,31 @@ impl PerfContext {
     }
 
     fn reset(&mut self) {
-        self.rocksdb_context.reset();
+        if let Some(rocksdb_context) = &mut self.rocksdb_context {
+            rocksdb_context.reset();
+        }
         self.start = Instant::now();
     }
 
     fn record(&mut self) {
         let observed_latency = self.start.elapsed();
-        let block_read_cnt =
-            self.rocksdb_context.metric(rocksdb::PerfMetric::BlockReadCount) as usize;
-        let read_block_latency =
-            Duration::from_nanos(self.rocksdb_context.metric(rocksdb::PerfMetric::BlockReadTime));
-        assert!(observed_latency > read_block_latency);
-        // This is a hack to check if at least one merge operator was executed during this request,
-        // will be replaced by a proper metric after `internal_merge_point_lookup_count` is added to
-        // rust-rocksdb
-        let has_merge =
-            self.rocksdb_context.metric(rocksdb::PerfMetric::MergeOperatorTimeNanos) > 0;
+        let (block_read_cnt, read_block_latency, has_merge) = match &self.rocksdb_context {
+            Some(rocksdb_context) => {
+                let block_read_cnt =
+                    rocksdb_context.metric(rocksdb::PerfMetric::BlockReadCount) as usize;
+                let read_block_latency = Duration::from_nanos(
+                    rocksdb_context.metric(rocksdb::PerfMetric::BlockReadTime),
+                );
+                // This is a hack to check if at least one merge operator was executed during this request,
+                // will be replaced by a proper metric after `internal_merge_point_lookup_count` is added to
+                // rust-rocksdb
+                let has_merge =
+                    rocksdb_context.metric(rocksdb::PerfMetric::MergeOperatorTimeNanos) > 0;
+                (block_read_cnt, read_block_latency, has_merge)
+            }
+            None => (0, Duration::ZERO, false),
+        };
+        assert!(observed_latency >= read_block_latency);
         self.measurements_per_block_reads.entry(block_read_cnt).or_default().record(
             observed_latency,
             read_block_latency,
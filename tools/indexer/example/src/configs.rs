@@ -1,3 +1,4 @@
+use anyhow::Context;
 use near_indexer::near_primitives::types::Gas;
 
 /// NEAR Indexer Example
@@ -17,11 +18,32 @@ pub(crate) struct Opts {
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum SubCommand {
     /// Run NEAR Indexer Example. Start observe the network
-    Run,
+    Run(RunArgs),
     /// Initialize necessary configs
     Init(InitConfigArgs),
 }
 
+#[derive(clap::Parser, Debug)]
+pub(crate) struct RunArgs {
+    /// Path to a JSON file with `near_indexer::sinks::SinkConfig` to write the blocks to,
+    /// e.g. `{"type": "json_lines", "directory": "blocks"}` or
+    /// `{"type": "parquet", "directory": "tables"}` (requires the `parquet` feature).
+    /// Blocks are only logged if it's not set.
+    #[clap(long)]
+    pub sink_config: Option<std::path::PathBuf>,
+}
+
+impl RunArgs {
+    pub(crate) fn sink_config(&self) -> anyhow::Result<Option<near_indexer::sinks::SinkConfig>> {
+        let Some(path) = &self.sink_config else { return Ok(None) };
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let config = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        Ok(Some(config))
+    }
+}
+
 #[derive(clap::Parser, Debug)]
 pub(crate) struct InitConfigArgs {
     /// chain/network id (localnet, testnet, devnet, betanet)
@@ -270,18 +270,34 @@ fn main() -> Result<()> {
     let home_dir = opts.home_dir.unwrap_or_else(near_indexer::get_default_home);
 
     match opts.subcmd {
-        SubCommand::Run => {
+        SubCommand::Run(args) => {
+            let sink_config = args.sink_config()?;
             let indexer_config = near_indexer::IndexerConfig {
                 home_dir,
-                sync_mode: near_indexer::SyncModeEnum::FromInterruption,
+                sync_mode: if sink_config.is_some() {
+                    near_indexer::SyncModeEnum::FromCheckpoint
+                } else {
+                    near_indexer::SyncModeEnum::FromInterruption
+                },
                 await_for_node_synced: near_indexer::AwaitForNodeSyncedEnum::WaitForFullSync,
                 validate_genesis: true,
+                filter: near_indexer::IndexerFilter::default(),
             };
             let system = actix::System::new();
             system.block_on(async move {
                 let indexer = near_indexer::Indexer::new(indexer_config).expect("Indexer::new()");
-                let stream = indexer.streamer();
-                actix::spawn(listen_blocks(stream));
+                match sink_config {
+                    Some(sink_config) => {
+                        let sink = sink_config.open().expect("failed to open the sink");
+                        let (stream, checkpoint) =
+                            indexer.checkpointed_streamer().expect("checkpointed_streamer()");
+                        near_indexer::sinks::spawn_sink(stream, sink, Some(checkpoint));
+                    }
+                    None => {
+                        let stream = indexer.streamer();
+                        actix::spawn(listen_blocks(stream));
+                    }
+                }
             });
             system.run()?;
         }
@@ -1,5 +1,8 @@
 use hkdf::Hkdf;
-use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, Secp256K1PublicKey, SecretKey};
+use near_crypto::{
+    ED25519PublicKey, ED25519SecretKey, EthPublicKey, PublicKey, Secp256K1PublicKey, SecretKey,
+    WebAuthnPublicKey, WebAuthnSecretKey,
+};
 use near_primitives::types::AccountId;
 use near_primitives::utils::derive_near_implicit_account_id;
 use near_primitives_core::account::id::AccountType;
@@ -59,7 +62,7 @@ fn map_ed25519(
     ED25519SecretKey(buf)
 }
 
-fn secp256k1_from_slice(buf: &mut [u8], public: &Secp256K1PublicKey) -> secp256k1::SecretKey {
+fn secp256k1_from_slice(buf: &mut [u8], public: &impl std::fmt::Debug) -> secp256k1::SecretKey {
     match secp256k1::SecretKey::from_slice(buf) {
         Ok(s) => s,
         Err(_) => {
@@ -92,6 +95,54 @@ fn map_secp256k1(
     secp256k1_from_slice(&mut buf, public)
 }
 
+fn map_eth(
+    public: &EthPublicKey,
+    secret: Option<&[u8; crate::secret::SECRET_LEN]>,
+) -> secp256k1::SecretKey {
+    let mut buf = [0; secp256k1::constants::SECRET_KEY_SIZE];
+
+    match secret {
+        Some(secret) => {
+            let hk = Hkdf::<Sha256>::new(None, secret);
+            hk.expand(public.as_ref(), &mut buf).unwrap();
+        }
+        None => {
+            // The address is shorter than a secret key, so the rest is left zeroed.
+            buf[..public.as_ref().len()].copy_from_slice(public.as_ref());
+        }
+    };
+
+    secp256k1_from_slice(&mut buf, public)
+}
+
+fn map_webauthn(
+    public: &WebAuthnPublicKey,
+    secret: Option<&[u8; crate::secret::SECRET_LEN]>,
+) -> WebAuthnSecretKey {
+    let mut buf = [0; 32];
+
+    match secret {
+        Some(secret) => {
+            let hk = Hkdf::<Sha256>::new(None, secret);
+            hk.expand(public.as_ref(), &mut buf).unwrap();
+        }
+        None => {
+            // Skip the byte telling the parity of the y coordinate.
+            buf.copy_from_slice(&public.as_ref()[1..]);
+        }
+    };
+
+    match WebAuthnSecretKey::try_from(&buf[..]) {
+        Ok(s) => s,
+        Err(_) => {
+            tracing::warn!(target: "mirror", "Something super unlikely occurred! WebAuthn key mapped from {:?} is too large. Flipping most significant bit.", public);
+            // Same as for SECP256K1 keys, the order of the P-256 curve starts with 0xFF.
+            buf[0] ^= 0x80;
+            WebAuthnSecretKey::try_from(&buf[..]).unwrap()
+        }
+    }
+}
+
 // This maps the public key to a secret key so that we can sign
 // transactions on the target chain.  If secret is None, then we just
 // use the bytes of the public key directly, otherwise we feed the
@@ -100,6 +151,8 @@ pub fn map_key(key: &PublicKey, secret: Option<&[u8; crate::secret::SECRET_LEN]>
     match key {
         PublicKey::ED25519(k) => SecretKey::ED25519(map_ed25519(k, secret)),
         PublicKey::SECP256K1(k) => SecretKey::SECP256K1(map_secp256k1(k, secret)),
+        PublicKey::ETH(k) => SecretKey::ETH(map_eth(k, secret)),
+        PublicKey::WEBAUTHN(k) => SecretKey::WEBAUTHN(map_webauthn(k, secret)),
     }
 }
 
@@ -0,0 +1,297 @@
+//! Replays the network traffic recorded by a node (see `near_network::recorder`)
+//! into a fresh node, to reproduce the decisions the node made given the
+//! messages it received.
+//!
+//! The actors of the node run in a TestLoop, so the replay is deterministic:
+//! inbound messages are delivered to the ClientActor, ShardsManagerActor and
+//! PartialWitnessActor the same way the PeerActor would, at the time they were
+//! received according to the clock of the TestLoop, and every message and timer
+//! of the actors is handled at its due time, in a reproducible order. Outbound
+//! messages are not sent anywhere, but the decisions they carry are compared
+//! against the network requests of the replayed node.
+
+use crate::setup::ReplayNode;
+use near_async::messaging::CanSend;
+use near_async::test_loop::TestLoopV2;
+use near_async::time;
+use near_network::client::{
+    BlockApproval, BlockHeadersResponse, BlockResponse, ChunkEndorsementMessage, ProcessTxRequest,
+    RecvChallenge, StateResponse, TxStatusResponse,
+};
+use near_network::recorder::{Direction, Record};
+use near_network::shards_manager::ShardsManagerRequestFromNetwork;
+use near_network::state_witness::{
+    ChunkStateWitnessAckMessage, PartialEncodedStateWitnessForwardMessage,
+    PartialEncodedStateWitnessMessage,
+};
+use near_network::types::{NetworkRequests, PeerIdOrHash, PeerMessage, RoutedMessageBody};
+use near_primitives::block::{Approval, Block};
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
+use near_primitives::types::BlockHeight;
+use std::collections::{BTreeSet, HashSet};
+
+pub mod setup;
+#[cfg(test)]
+mod tests;
+
+/// Maximal time given to the actors to handle their pending events once the
+/// replay is finished.
+const SHUTDOWN_TIMEOUT: time::Duration = time::Duration::seconds(20);
+
+/// Decision of a node observable in the messages it sends.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Decision {
+    /// Broadcasting a block produced by the node.
+    Block { height: BlockHeight, hash: CryptoHash },
+    /// Sending an approval. `hash` is the hash of the whole approval.
+    Approval { target_height: BlockHeight, hash: CryptoHash },
+    /// Sending an endorsement of a chunk.
+    ChunkEndorsement(ChunkHash),
+}
+
+impl Decision {
+    fn block(block: &Block) -> Self {
+        Decision::Block { height: block.header().height(), hash: *block.hash() }
+    }
+
+    fn approval(approval: &Approval) -> Self {
+        Decision::Approval {
+            target_height: approval.target_height,
+            hash: CryptoHash::hash_borsh(approval),
+        }
+    }
+}
+
+/// Decisions of the recorded node compared with the decisions of the replayed node.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Decisions made by both nodes.
+    pub matched: BTreeSet<Decision>,
+    /// Decisions made only by the recorded node.
+    pub missing: BTreeSet<Decision>,
+    /// Decisions made only by the replayed node.
+    pub unexpected: BTreeSet<Decision>,
+}
+
+/// Replays records into a `ReplayNode`.
+pub struct Replay {
+    test_loop: TestLoopV2,
+    node: ReplayNode,
+    /// Hashes of the routed messages authored by the node, responses to which are
+    /// routed back to it.
+    route_back: HashSet<CryptoHash>,
+    /// Blocks requested by the replayed node.
+    requested_blocks: HashSet<CryptoHash>,
+    /// Blocks received from peers, as opposed to produced by the node.
+    received_blocks: HashSet<CryptoHash>,
+    /// Decisions of the recorded node.
+    recorded: BTreeSet<Decision>,
+    /// Decisions of the replayed node.
+    replayed: BTreeSet<Decision>,
+}
+
+impl Replay {
+    /// `node` has to be set up in `test_loop`.
+    pub fn new(test_loop: TestLoopV2, node: ReplayNode) -> Self {
+        Self {
+            test_loop,
+            node,
+            route_back: HashSet::new(),
+            requested_blocks: HashSet::new(),
+            received_blocks: HashSet::new(),
+            recorded: BTreeSet::new(),
+            replayed: BTreeSet::new(),
+        }
+    }
+
+    /// Runs the node until the time of `record` and replays it. Returns once the
+    /// actors have handled all the events due at that time, including the record.
+    pub fn process(&mut self, record: Record) {
+        let now = self.test_loop.clock().now_utc();
+        if record.timestamp > now {
+            self.test_loop.run_for(record.timestamp - now);
+        }
+        self.collect_network_requests();
+        match record.direction {
+            Direction::Inbound => self.process_inbound(record.peer_id, record.message),
+            Direction::Outbound => self.process_outbound(record.message),
+        }
+        self.test_loop.run_instant();
+    }
+
+    /// Runs the node for `settle_time` more, to let it handle the last records,
+    /// then stops it and compares the decisions of the nodes.
+    pub fn finish(mut self, settle_time: time::Duration) -> Report {
+        self.test_loop.run_for(settle_time);
+        self.collect_network_requests();
+        self.test_loop.shutdown_and_drain_remaining_events(SHUTDOWN_TIMEOUT);
+        let mut report = Report::default();
+        for decision in self.recorded {
+            if self.replayed.remove(&decision) {
+                report.matched.insert(decision);
+            } else {
+                report.missing.insert(decision);
+            }
+        }
+        report.unexpected = self.replayed;
+        report
+    }
+
+    fn collect_network_requests(&mut self) {
+        let network = self.test_loop.data.get_mut(&self.node.network.actor_handle());
+        for request in std::mem::take(&mut network.requests) {
+            match request {
+                NetworkRequests::Block { block } => {
+                    self.replayed.insert(Decision::block(&block));
+                }
+                NetworkRequests::Approval { approval_message } => {
+                    self.replayed.insert(Decision::approval(&approval_message.approval));
+                }
+                NetworkRequests::ChunkEndorsement(_, endorsement) => {
+                    self.replayed
+                        .insert(Decision::ChunkEndorsement(endorsement.chunk_hash().clone()));
+                }
+                NetworkRequests::BlockRequest { hash, .. } => {
+                    self.requested_blocks.insert(hash);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn process_outbound(&mut self, msg: PeerMessage) {
+        match msg {
+            PeerMessage::Block(block) if !self.received_blocks.contains(block.hash()) => {
+                self.recorded.insert(Decision::block(&block));
+            }
+            PeerMessage::Routed(msg) if msg.author == self.node.node_id => {
+                self.route_back.insert(msg.hash());
+                match &msg.body {
+                    RoutedMessageBody::BlockApproval(approval) => {
+                        self.recorded.insert(Decision::approval(approval));
+                    }
+                    RoutedMessageBody::ChunkEndorsement(endorsement) => {
+                        self.recorded
+                            .insert(Decision::ChunkEndorsement(endorsement.chunk_hash().clone()));
+                    }
+                    RoutedMessageBody::VersionedChunkEndorsement(endorsement) => {
+                        self.recorded
+                            .insert(Decision::ChunkEndorsement(endorsement.chunk_hash().clone()));
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Delivers `msg` the same way `PeerActor::receive_message` does.
+    fn process_inbound(&mut self, peer_id: PeerId, msg: PeerMessage) {
+        let client = &self.node.client;
+        match msg {
+            PeerMessage::Block(block) => {
+                let hash = *block.hash();
+                self.received_blocks.insert(hash);
+                let was_requested = self.requested_blocks.remove(&hash);
+                client.send(BlockResponse { block, peer_id, was_requested });
+            }
+            PeerMessage::BlockHeaders(headers) => {
+                client.send(BlockHeadersResponse(headers, peer_id));
+            }
+            PeerMessage::Transaction(transaction) => {
+                client.send(ProcessTxRequest {
+                    transaction,
+                    is_forwarded: false,
+                    check_only: false,
+                });
+            }
+            PeerMessage::Challenge(challenge) => {
+                client.send(RecvChallenge(challenge));
+            }
+            PeerMessage::VersionedStateResponse(info) => {
+                client.send(StateResponse(info.into()));
+            }
+            PeerMessage::Routed(msg) => {
+                let for_me = match &msg.target {
+                    PeerIdOrHash::PeerId(target) => target == &self.node.node_id,
+                    PeerIdOrHash::Hash(hash) => self.route_back.contains(hash),
+                };
+                if for_me {
+                    let msg_hash = msg.hash();
+                    self.process_routed(peer_id, msg_hash, msg.msg.body);
+                }
+            }
+            // Requests are served by the ViewClientActor and the rest of the messages
+            // are handled by the PeerManagerActor, neither of which makes decisions
+            // on behalf of the node.
+            _ => {}
+        }
+    }
+
+    /// Delivers `body` the same way `PeerActor::receive_routed_message` does.
+    fn process_routed(&self, peer_id: PeerId, msg_hash: CryptoHash, body: RoutedMessageBody) {
+        let client = &self.node.client;
+        match body {
+            RoutedMessageBody::TxStatusResponse(tx_result) => {
+                self.node.view_client.send(TxStatusResponse(tx_result.into()));
+            }
+            RoutedMessageBody::BlockApproval(approval) => {
+                client.send(BlockApproval(approval, peer_id));
+            }
+            RoutedMessageBody::ForwardTx(transaction) => {
+                client.send(ProcessTxRequest {
+                    transaction,
+                    is_forwarded: true,
+                    check_only: false,
+                });
+            }
+            RoutedMessageBody::PartialEncodedChunkRequest(request) => {
+                self.node.shards_manager.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkRequest {
+                        partial_encoded_chunk_request: request,
+                        route_back: msg_hash,
+                    },
+                );
+            }
+            RoutedMessageBody::PartialEncodedChunkResponse(response) => {
+                self.node.shards_manager.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
+                        partial_encoded_chunk_response: response,
+                        received_time: self.test_loop.clock().now(),
+                    },
+                );
+            }
+            RoutedMessageBody::VersionedPartialEncodedChunk(chunk) => {
+                self.node
+                    .shards_manager
+                    .send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunk(chunk));
+            }
+            RoutedMessageBody::PartialEncodedChunkForward(msg) => {
+                self.node
+                    .shards_manager
+                    .send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkForward(msg));
+            }
+            RoutedMessageBody::ChunkStateWitnessAck(ack) => {
+                self.node.partial_witness.send(ChunkStateWitnessAckMessage(ack));
+            }
+            RoutedMessageBody::ChunkEndorsement(endorsement) => {
+                let endorsement = ChunkEndorsement::V1(endorsement);
+                client.send(ChunkEndorsementMessage(endorsement));
+            }
+            RoutedMessageBody::VersionedChunkEndorsement(endorsement) => {
+                client.send(ChunkEndorsementMessage(endorsement));
+            }
+            RoutedMessageBody::PartialEncodedStateWitness(witness) => {
+                self.node.partial_witness.send(PartialEncodedStateWitnessMessage(witness));
+            }
+            RoutedMessageBody::PartialEncodedStateWitnessForward(witness) => {
+                self.node.partial_witness.send(PartialEncodedStateWitnessForwardMessage(witness));
+            }
+            // Requests served by the ViewClientActor and network-level messages.
+            _ => {}
+        }
+    }
+}
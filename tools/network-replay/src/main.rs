@@ -0,0 +1,92 @@
+//! A binary replaying the network traffic recorded by a node into a fresh
+//! ClientActor, to reproduce the decisions the node made given the messages it
+//! received, e.g. to debug forks or stalled sync.
+
+use anyhow::Context;
+use near_async::test_loop::TestLoopV2;
+use near_async::time;
+use near_chain_configs::GenesisValidationMode;
+use near_network::recorder::Reader;
+use near_o11y::testonly::init_integration_logger;
+use network_replay::setup::setup_replay_node;
+use network_replay::{Replay, Report};
+use std::path::PathBuf;
+
+/// Program to replay a network traffic recording into a fresh node.
+///
+/// The node is started from the home dir of the recorded node with the network
+/// replaced by the recording: the messages the recorded node received are
+/// delivered to the node at the times they were received, according to the
+/// virtual clock of a TestLoop, which makes the replay deterministic. Once the
+/// recording ends, the decisions the recorded node made
+/// (produced blocks, approvals and chunk endorsements) are compared with the
+/// decisions of the replayed node.
+///
+/// To record the traffic of a node, set `network.experimental.record_traffic`
+/// in its config.json to the directory of the recordings, and make a copy of
+/// its database before starting it. Every run of the node creates a new
+/// recording in the directory.
+///
+/// Example
+///
+/// ```console
+/// $ network-replay ~/.near/replay ~/.near/recordings/1718000000000.rec
+/// ```
+#[derive(clap::Parser)]
+struct Cli {
+    /// Home dir of the recorded node, with its database replaced by the copy made
+    /// before the recording started. The replay writes to the database.
+    home_dir: PathBuf,
+    /// Recording of the network traffic of the node.
+    recording: PathBuf,
+    /// Time (in ms, on the virtual clock) the replayed node is given to handle the
+    /// last records before its decisions are compared.
+    #[clap(long, default_value = "5000")]
+    settle_time: u64,
+}
+
+fn print_report(report: &Report) {
+    println!("matched decisions: {}", report.matched.len());
+    println!("decisions made only by the recorded node: {}", report.missing.len());
+    for decision in &report.missing {
+        println!("  {decision:?}");
+    }
+    println!("decisions made only by the replayed node: {}", report.unexpected.len());
+    for decision in &report.unexpected {
+        println!("  {decision:?}");
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    init_integration_logger();
+    let args: Cli = clap::Parser::parse();
+    let near_config =
+        nearcore::config::load_config(&args.home_dir, GenesisValidationMode::UnsafeFast)
+            .context("Error loading config")?;
+    let mut records = Reader::open(&args.recording).context("Error opening the recording")?;
+    let Some(first) = records.next().transpose().context("Error reading the recording")? else {
+        anyhow::bail!("the recording is empty");
+    };
+    // Start the clock at the time of the first record, so that the node sees the
+    // records at the times they were recorded.
+    let mut test_loop = TestLoopV2::new_with_start_time(first.timestamp);
+    let node = setup_replay_node(&mut test_loop, &args.home_dir, &near_config)
+        .context("Error setting up the node")?;
+    let mut replay = Replay::new(test_loop, node);
+    let mut count = 1;
+    replay.process(first);
+    for record in records {
+        match record {
+            Ok(record) => replay.process(record),
+            // The last record may be cut short if the recorded node crashed.
+            Err(err) => {
+                tracing::warn!(target: "network_replay", ?err, "Error reading the recording, stopping the replay");
+                break;
+            }
+        }
+        count += 1;
+    }
+    tracing::info!(target: "network_replay", count, "Replayed the recording");
+    print_report(&replay.finish(time::Duration::milliseconds(args.settle_time as i64)));
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! Provides functions for setting up the node to replay a recording into.
+
+use anyhow::Context;
+use near_async::messaging::{noop, Actor, Handler, IntoMultiSender, IntoSender, LateBoundSender};
+use near_async::test_loop::sender::TestLoopSender;
+use near_async::test_loop::TestLoopV2;
+use near_async::time::Duration;
+use near_chain::chunks_store::ReadOnlyChunksStore;
+use near_chain::ChainGenesis;
+use near_chunks::shards_manager_actor::ShardsManagerActor;
+use near_client::client_actor::ClientActorInner;
+use near_client::sync::adapter::SyncAdapter;
+use near_client::sync_jobs_actor::SyncJobsActor;
+use near_client::test_utils::test_loop::test_loop_sync_actor_maker;
+use near_client::{Client, PartialWitnessActor, ViewClientActorInner};
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_network::types::{
+    NetworkRequests, NetworkResponses, PeerManagerMessageRequest, PeerManagerMessageResponse,
+    SetChainInfo,
+};
+use near_primitives::network::PeerId;
+use near_primitives::types::EpochId;
+use near_store::genesis::initialize_sharded_genesis_state;
+use nearcore::{NearConfig, NightshadeRuntime, NightshadeRuntimeExt};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Seed of the randomness of the Client, fixed so that replays are reproducible.
+const RNG_SEED: [u8; 32] = [0; 32];
+
+/// Replaces the PeerManagerActor of the replayed node: collects the network
+/// requests of the node instead of sending them.
+#[derive(Default)]
+pub struct ReplayPeerManagerActor {
+    pub requests: Vec<NetworkRequests>,
+}
+
+impl Actor for ReplayPeerManagerActor {}
+
+impl Handler<SetChainInfo> for ReplayPeerManagerActor {
+    fn handle(&mut self, _msg: SetChainInfo) {}
+}
+
+impl Handler<PeerManagerMessageRequest> for ReplayPeerManagerActor {
+    fn handle(&mut self, msg: PeerManagerMessageRequest) -> PeerManagerMessageResponse {
+        if let PeerManagerMessageRequest::NetworkRequests(request) = msg {
+            self.requests.push(request);
+        }
+        PeerManagerMessageResponse::NetworkResponses(NetworkResponses::NoResponse)
+    }
+}
+
+/// Node replaying a recording, with the network replaced by the recording.
+pub struct ReplayNode {
+    pub node_id: PeerId,
+    /// Senders of the messages the PeerManagerActor delivers to the node.
+    pub client: TestLoopSender<ClientActorInner>,
+    pub view_client: TestLoopSender<ViewClientActorInner>,
+    pub shards_manager: TestLoopSender<ShardsManagerActor>,
+    pub partial_witness: TestLoopSender<PartialWitnessActor>,
+    /// Collects the network requests of the node.
+    pub network: TestLoopSender<ReplayPeerManagerActor>,
+}
+
+/// Registers a ClientActor, ShardsManagerActor, PartialWitnessActor and
+/// ViewClientActor of the node at `home_dir` in `test_loop`, with all their
+/// network requests collected by `ReplayNode::network`.
+/// All the actors run on the clock of `test_loop`, so that the replay controls
+/// their time, and the order in which they handle messages.
+/// WARNING: the actors write to the database of the node.
+pub fn setup_replay_node(
+    test_loop: &mut TestLoopV2,
+    home_dir: &Path,
+    config: &NearConfig,
+) -> anyhow::Result<ReplayNode> {
+    let store = near_store::NodeStorage::opener(
+        home_dir,
+        config.config.archive,
+        &config.config.store,
+        None,
+    )
+    .open()
+    .context("open storage")?
+    .get_hot_store();
+    let epoch_manager = EpochManager::new_arc_handle(store.clone(), &config.genesis.config);
+    let genesis_epoch_config = epoch_manager.get_epoch_config(&EpochId::default())?;
+    initialize_sharded_genesis_state(
+        store.clone(),
+        &config.genesis,
+        &genesis_epoch_config,
+        Some(home_dir),
+    );
+    let shard_tracker =
+        ShardTracker::new(TrackedConfig::from_config(&config.client_config), epoch_manager.clone());
+    let runtime =
+        NightshadeRuntime::from_config(home_dir, store.clone(), config, epoch_manager.clone())
+            .context("could not create transaction runtime")?;
+    let chain_genesis = ChainGenesis::new(&config.genesis.config);
+    let node_id = config.network_config.node_id();
+    let adv = near_client::adversarial::Controls::new(config.client_config.archive);
+
+    let client_adapter = LateBoundSender::new();
+    let network_adapter = LateBoundSender::new();
+    let shards_manager_adapter = LateBoundSender::new();
+    let partial_witness_adapter = LateBoundSender::new();
+    let sync_jobs_adapter = LateBoundSender::new();
+
+    let state_sync_adapter = Arc::new(RwLock::new(SyncAdapter::new(
+        client_adapter.as_sender(),
+        network_adapter.as_sender(),
+        test_loop_sync_actor_maker(0, test_loop.sender()),
+    )));
+
+    let client = Client::new(
+        test_loop.clock(),
+        config.client_config.clone(),
+        chain_genesis.clone(),
+        epoch_manager.clone(),
+        shard_tracker.clone(),
+        state_sync_adapter,
+        runtime.clone(),
+        network_adapter.as_multi_sender(),
+        shards_manager_adapter.as_sender(),
+        config.validator_signer.clone(),
+        true,
+        RNG_SEED,
+        None,
+        Arc::new(test_loop.async_computation_spawner(|_| Duration::ZERO)),
+        partial_witness_adapter.as_multi_sender(),
+    )
+    .context("Client::new")?;
+
+    let shards_manager = ShardsManagerActor::new(
+        test_loop.clock(),
+        config.validator_signer.clone(),
+        epoch_manager.clone(),
+        shard_tracker.clone(),
+        network_adapter.as_sender(),
+        client_adapter.as_sender(),
+        ReadOnlyChunksStore::new(store.clone()),
+        client.chain.head().context("head")?,
+        client.chain.header_head().context("header_head")?,
+        config.client_config.chunk_request_retry_period,
+    );
+
+    let client_actor = ClientActorInner::new(
+        test_loop.clock(),
+        client,
+        client_adapter.as_multi_sender(),
+        node_id.clone(),
+        network_adapter.as_multi_sender(),
+        noop().into_sender(),
+        None,
+        adv.clone(),
+        None,
+        sync_jobs_adapter.as_multi_sender(),
+        Box::new(test_loop.future_spawner()),
+    )
+    .context("ClientActorInner::new")?;
+
+    let view_client_actor = ViewClientActorInner::new(
+        test_loop.clock(),
+        config.validator_signer.clone(),
+        chain_genesis,
+        epoch_manager.clone(),
+        shard_tracker,
+        runtime,
+        network_adapter.as_multi_sender(),
+        config.client_config.clone(),
+        adv,
+    )
+    .context("ViewClientActorInner::new")?;
+
+    let partial_witness_actor = PartialWitnessActor::new(
+        test_loop.clock(),
+        network_adapter.as_multi_sender(),
+        client_adapter.as_multi_sender(),
+        config.validator_signer.clone(),
+        epoch_manager,
+        store,
+    );
+
+    let sync_jobs_actor = SyncJobsActor::new(client_adapter.as_multi_sender());
+    test_loop.register_actor(sync_jobs_actor, Some(sync_jobs_adapter));
+
+    Ok(ReplayNode {
+        node_id,
+        client: test_loop.register_actor(client_actor, Some(client_adapter)),
+        view_client: test_loop.register_actor(view_client_actor, None),
+        shards_manager: test_loop.register_actor(shards_manager, Some(shards_manager_adapter)),
+        partial_witness: test_loop
+            .register_actor(partial_witness_actor, Some(partial_witness_adapter)),
+        network: test_loop.register_actor(ReplayPeerManagerActor::default(), Some(network_adapter)),
+    })
+}
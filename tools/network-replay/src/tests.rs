@@ -0,0 +1,79 @@
+use crate::setup::setup_replay_node;
+use crate::Replay;
+use near_async::test_loop::TestLoopV2;
+use near_async::time;
+use near_chain_configs::Genesis;
+use near_network::recorder::{Direction, Reader, Recorder};
+use near_network::tcp;
+use near_network::types::{NetworkRequests, PeerMessage};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::network::PeerId;
+use near_primitives::shard_layout::ShardLayout;
+use nearcore::NearConfig;
+use std::path::{Path, PathBuf};
+
+/// Time the recorded node runs for.
+const RECORDING_DURATION: time::Duration = time::Duration::seconds(5);
+/// Interval at which the messages sent by the recorded node are recorded.
+const RECORDING_STEP: time::Duration = time::Duration::milliseconds(10);
+
+fn make_config() -> NearConfig {
+    // Genesis starts at the time the TestLoop starts at.
+    let genesis = Genesis::test_with_seeds(
+        time::FakeClock::default().clock(),
+        vec!["test0".parse().unwrap()],
+        1,
+        vec![1],
+        ShardLayout::v0_single_shard(),
+    );
+    let mut config =
+        nearcore::config::load_test_config("test0", tcp::ListenerAddr::reserve_for_test(), genesis);
+    config.client_config.skip_sync_wait = true;
+    config
+}
+
+/// Runs the node at `home_dir` on its own and records the messages it sends to
+/// a peer, the same way the PeerActor does. Returns the path of the recording.
+fn record(home_dir: &Path, config: &NearConfig, recordings_dir: &Path) -> PathBuf {
+    let mut test_loop = TestLoopV2::new();
+    let node = setup_replay_node(&mut test_loop, home_dir, config).unwrap();
+    let recorder = Recorder::new(&test_loop.clock(), recordings_dir).unwrap();
+    let peer_id = PeerId::random();
+    let mut elapsed = time::Duration::ZERO;
+    while elapsed < RECORDING_DURATION {
+        test_loop.run_for(RECORDING_STEP);
+        elapsed += RECORDING_STEP;
+        let network = test_loop.data.get_mut(&node.network.actor_handle());
+        for request in std::mem::take(&mut network.requests) {
+            if let NetworkRequests::Block { block } = request {
+                let msg = PeerMessage::Block(block);
+                recorder.record(&test_loop.clock(), &peer_id, Direction::Outbound, &msg);
+            }
+        }
+    }
+    test_loop.shutdown_and_drain_remaining_events(time::Duration::seconds(20));
+    let path = recorder.path().to_owned();
+    drop(recorder);
+    path
+}
+
+#[test]
+fn replay_recording() {
+    init_test_logger();
+    let config = make_config();
+    let recorded_home = tempfile::tempdir().unwrap();
+    let replayed_home = tempfile::tempdir().unwrap();
+    let recordings_dir = tempfile::tempdir().unwrap();
+    let recording = record(recorded_home.path(), &config, recordings_dir.path());
+
+    let mut test_loop = TestLoopV2::new();
+    let node = setup_replay_node(&mut test_loop, replayed_home.path(), &config).unwrap();
+    let mut replay = Replay::new(test_loop, node);
+    for record in Reader::open(&recording).unwrap() {
+        replay.process(record.unwrap());
+    }
+    let report = replay.finish(time::Duration::ZERO);
+    assert!(!report.matched.is_empty());
+    assert!(report.missing.is_empty(), "{:?}", report.missing);
+    assert!(report.unexpected.is_empty(), "{:?}", report.unexpected);
+}
@@ -0,0 +1,649 @@
+//! Reference implementation of the signer process used by
+//! `near_primitives::validator_signer::RemoteValidatorSigner`.
+//!
+//! The signer holds the validator key and answers the requests of a node
+//! connected over a Unix socket.  Block headers, chunk headers, approvals and
+//! chunk endorsements are received as their parts: the signer computes the
+//! message to sign and its height from them, records the height in the
+//! slashing protection file, and refuses to sign a different message of the
+//! same kind at a height it has already signed.
+//! The rest of the messages are signed as they are, once they are decoded as
+//! what they claim to be.
+use borsh::BorshDeserialize;
+use near_crypto::{InMemorySigner, PublicKey};
+use near_primitives::block::{Approval, ApprovalInner};
+use near_primitives::block_header::{BlockHeader, BlockHeaderInnerLite};
+use near_primitives::challenge::ChallengeBody;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::AnnounceAccount;
+use near_primitives::sharding::{ShardChunkHeaderInner, ShardChunkHeaderV3};
+use near_primitives::stateless_validation::chunk_endorsement::{
+    ChunkEndorsementInner, ChunkEndorsementMetadata,
+};
+use near_primitives::stateless_validation::partial_witness::PartialEncodedStateWitnessInner;
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::validator_signer::remote::{
+    RemoteSignerRequest, RemoteSignerResponse, SignKind,
+};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Number of heights remembered per kind of message (and shard, for chunks).
+/// Requests for heights below the remembered ones are refused.
+const SLASHING_PROTECTION_HISTORY: usize = 10_000;
+
+/// Messages which a validator must never sign twice at the same height.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slashable {
+    BlockHeader,
+    Approval,
+    ChunkHeader(ShardId),
+    ChunkEndorsement(ShardId),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SlashingProtectionError {
+    #[error("{kind:?} at height {height} is below the lowest remembered height {min_height}")]
+    HeightTooLow { kind: Slashable, height: BlockHeight, min_height: BlockHeight },
+    #[error("refusing to sign another {kind:?} at height {height}")]
+    DoubleSign { kind: Slashable, height: BlockHeight },
+    #[error("failed to persist slashing protection state: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+struct SignedHeights {
+    /// Requests for heights below this one are refused, since the history
+    /// before it has been pruned.
+    min_height: BlockHeight,
+    /// Hashes of the messages signed at each height.
+    signed: BTreeMap<BlockHeight, CryptoHash>,
+}
+
+impl SignedHeights {
+    fn check_and_insert(
+        &mut self,
+        kind: Slashable,
+        height: BlockHeight,
+        hash: CryptoHash,
+    ) -> Result<bool, SlashingProtectionError> {
+        if height < self.min_height {
+            return Err(SlashingProtectionError::HeightTooLow {
+                kind,
+                height,
+                min_height: self.min_height,
+            });
+        }
+        match self.signed.get(&height) {
+            // Signing the same message again is harmless, e.g. when the node
+            // retries after a timeout.
+            Some(signed) if *signed == hash => return Ok(false),
+            Some(_) => return Err(SlashingProtectionError::DoubleSign { kind, height }),
+            None => {}
+        }
+        self.signed.insert(height, hash);
+        while self.signed.len() > SLASHING_PROTECTION_HISTORY {
+            let (pruned, _) = self.signed.pop_first().unwrap();
+            self.min_height = pruned + 1;
+        }
+        Ok(true)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+struct SlashingProtectionState {
+    block_headers: SignedHeights,
+    approvals: SignedHeights,
+    #[serde(default)]
+    chunk_headers: BTreeMap<ShardId, SignedHeights>,
+    #[serde(default)]
+    chunk_endorsements: BTreeMap<ShardId, SignedHeights>,
+}
+
+/// Record of the heights at which slashable messages have been signed,
+/// persisted in a JSON file.
+///
+/// The file is updated before the signature is released, so a crash between
+/// the two can only make the signer refuse to sign, never double-sign.
+pub struct SlashingProtection {
+    path: PathBuf,
+    state: SlashingProtectionState,
+}
+
+impl SlashingProtection {
+    /// Loads the state from the file, or starts from scratch if it doesn't
+    /// exist.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let state = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path: path.to_path_buf(), state })
+    }
+
+    /// Checks whether signing `data` doesn't conflict with anything signed
+    /// before and records it.
+    pub fn check_and_record(
+        &mut self,
+        kind: Slashable,
+        height: BlockHeight,
+        data: &[u8],
+    ) -> Result<(), SlashingProtectionError> {
+        let hash = CryptoHash::hash_bytes(data);
+        if self.signed_heights(kind).check_and_insert(kind, height, hash)? {
+            if let Err(err) = self.persist() {
+                // Don't let a retry sign what isn't on disk.
+                self.signed_heights(kind).signed.remove(&height);
+                return Err(err.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn signed_heights(&mut self, kind: Slashable) -> &mut SignedHeights {
+        match kind {
+            Slashable::BlockHeader => &mut self.state.block_headers,
+            Slashable::Approval => &mut self.state.approvals,
+            Slashable::ChunkHeader(shard_id) => {
+                self.state.chunk_headers.entry(shard_id).or_default()
+            }
+            Slashable::ChunkEndorsement(shard_id) => {
+                self.state.chunk_endorsements.entry(shard_id).or_default()
+            }
+        }
+    }
+
+    fn persist(&self) -> std::io::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(&self.state)?)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)
+    }
+}
+
+/// Message to sign computed from a request, with the height it is signed at
+/// if it must not be signed twice.
+struct Message {
+    data: Vec<u8>,
+    slashable: Option<(Slashable, BlockHeight)>,
+}
+
+impl Message {
+    /// Decodes the parts of the message sent by the node and computes the
+    /// message to sign from them, so that the node can't make the signer
+    /// sign a slashable message under another kind or height.
+    fn from_request(
+        request: &RemoteSignerRequest,
+        public_key: &PublicKey,
+    ) -> std::io::Result<Option<Self>> {
+        let message = match request {
+            RemoteSignerRequest::PublicKey => return Ok(None),
+            // Blocks only need the VRF of the random value of the previous block.
+            RemoteSignerRequest::ComputeVrf { data } => {
+                CryptoHash::try_from(data.as_slice()).map_err(|_| {
+                    invalid_data(format!("VRF input is {} bytes, not a hash", data.len()))
+                })?;
+                return Ok(None);
+            }
+            RemoteSignerRequest::SignBlockHeader { prev_hash, inner_lite, inner_rest } => {
+                let height = BlockHeaderInnerLite::try_from_slice(inner_lite)?.height;
+                let hash = BlockHeader::compute_hash(*prev_hash, inner_lite, inner_rest);
+                Self {
+                    data: hash.as_ref().to_vec(),
+                    slashable: Some((Slashable::BlockHeader, height)),
+                }
+            }
+            RemoteSignerRequest::SignChunkHeader { inner } => {
+                let inner = ShardChunkHeaderInner::try_from_slice(inner)?;
+                let hash = ShardChunkHeaderV3::compute_hash(&inner);
+                Self {
+                    data: hash.as_ref().to_vec(),
+                    slashable: Some((
+                        Slashable::ChunkHeader(inner.shard_id()),
+                        inner.height_created(),
+                    )),
+                }
+            }
+            RemoteSignerRequest::SignApproval { inner, target_height } => {
+                let inner = ApprovalInner::try_from_slice(inner)?;
+                Self {
+                    data: Approval::get_data_for_sig(&inner, *target_height),
+                    slashable: Some((Slashable::Approval, *target_height)),
+                }
+            }
+            RemoteSignerRequest::SignChunkEndorsement { chunk_header_inner } => {
+                let inner = ShardChunkHeaderInner::try_from_slice(chunk_header_inner)?;
+                let endorsement =
+                    ChunkEndorsementInner::new(ShardChunkHeaderV3::compute_hash(&inner));
+                Self {
+                    data: borsh::to_vec(&endorsement)?,
+                    slashable: Some((
+                        Slashable::ChunkEndorsement(inner.shard_id()),
+                        inner.height_created(),
+                    )),
+                }
+            }
+            RemoteSignerRequest::SignChallenge { body } => {
+                let body = ChallengeBody::try_from_slice(body)?;
+                Self { data: CryptoHash::hash_borsh(&body).as_ref().to_vec(), slashable: None }
+            }
+            RemoteSignerRequest::SignAccountAnnounce { account_id, peer_id, epoch_id } => {
+                let hash = AnnounceAccount::build_header_hash(account_id, peer_id, epoch_id);
+                Self { data: hash.as_ref().to_vec(), slashable: None }
+            }
+            RemoteSignerRequest::Sign { kind, data } => {
+                // The rest of the messages are signed as they are, but only if
+                // they look like what they claim to be.
+                match kind {
+                    SignKind::Telemetry => {
+                        serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(data)?;
+                    }
+                    SignKind::ChunkEndorsementMetadata => {
+                        ChunkEndorsementMetadata::try_from_slice(data)?;
+                    }
+                    SignKind::PartialEncodedStateWitness => {
+                        PartialEncodedStateWitnessInner::try_from_slice(data)?;
+                    }
+                    SignKind::AccountKeyPayload => {
+                        account_key_payload::check(data, public_key)?;
+                    }
+                }
+                Self { data: data.clone(), slashable: None }
+            }
+        };
+        Ok(Some(message))
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+/// Checks of the proto-serialized `AccountKeyPayload`s of near-network,
+/// decoding just the fields they need from the protobuf wire format:
+///
+/// ```proto
+/// message AccountKeyPayload {
+///   oneof payload_type {
+///     AccountData account_data = 2;    // PublicKey account_key = 6;
+///     OwnedAccount owned_account = 3;  // PublicKey account_key = 1;
+///   }
+/// }
+/// message PublicKey { bytes borsh = 1; }
+/// ```
+mod account_key_payload {
+    use super::invalid_data;
+    use borsh::BorshDeserialize;
+    use near_crypto::PublicKey;
+
+    /// Checks that `data` is an `AccountKeyPayload` for `account_key`, so that
+    /// the network key can't be used to sign anything else.
+    pub(super) fn check(data: &[u8], account_key: &PublicKey) -> std::io::Result<()> {
+        let (key_number, payload) = match single_field(data)? {
+            (2, account_data) => (6, account_data),
+            (3, owned_account) => (1, owned_account),
+            (number, _) => return Err(invalid_data(format!("unexpected payload type {number}"))),
+        };
+        let key = find_field(payload, key_number)?
+            .and_then(|key| find_field(key, 1).transpose())
+            .transpose()?
+            .ok_or_else(|| invalid_data("missing account key"))?;
+        if PublicKey::try_from_slice(key)? != *account_key {
+            return Err(invalid_data("payload is for another account key"));
+        }
+        Ok(())
+    }
+
+    /// Returns the number and the contents of the only field of the message,
+    /// which must be length-delimited.
+    fn single_field(data: &[u8]) -> std::io::Result<(u64, &[u8])> {
+        let fields = fields(data)?;
+        match fields.as_slice() {
+            [(number, Some(value))] => Ok((*number, value)),
+            _ => Err(invalid_data("expected a single message field")),
+        }
+    }
+
+    /// Returns the contents of the last occurrence of the length-delimited
+    /// field `number`, the way protobuf decoders do.
+    fn find_field(data: &[u8], number: u64) -> std::io::Result<Option<&[u8]>> {
+        let mut found = None;
+        for (field_number, value) in fields(data)? {
+            if field_number == number {
+                found = Some(value.ok_or_else(|| invalid_data("expected a message field"))?);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Splits a message into its fields, with the contents of the
+    /// length-delimited ones.
+    fn fields(mut data: &[u8]) -> std::io::Result<Vec<(u64, Option<&[u8]>)>> {
+        let mut fields = vec![];
+        while !data.is_empty() {
+            let tag = read_varint(&mut data)?;
+            let value = match tag & 7 {
+                0 => {
+                    read_varint(&mut data)?;
+                    None
+                }
+                1 => {
+                    take(&mut data, 8)?;
+                    None
+                }
+                2 => {
+                    let len = read_varint(&mut data)?;
+                    let len = usize::try_from(len).map_err(|_| invalid_data("field too long"))?;
+                    Some(take(&mut data, len)?)
+                }
+                5 => {
+                    take(&mut data, 4)?;
+                    None
+                }
+                wire_type => return Err(invalid_data(format!("unexpected wire type {wire_type}"))),
+            };
+            fields.push((tag >> 3, value));
+        }
+        Ok(fields)
+    }
+
+    fn read_varint(data: &mut &[u8]) -> std::io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = take(data, 1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data("varint too long"))
+    }
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> std::io::Result<&'a [u8]> {
+        if data.len() < len {
+            return Err(invalid_data("truncated message"));
+        }
+        let (taken, rest) = data.split_at(len);
+        *data = rest;
+        Ok(taken)
+    }
+}
+
+pub struct RemoteSigner {
+    signer: InMemorySigner,
+    slashing_protection: Mutex<SlashingProtection>,
+}
+
+impl RemoteSigner {
+    pub fn new(signer: InMemorySigner, slashing_protection: SlashingProtection) -> Self {
+        Self { signer, slashing_protection: Mutex::new(slashing_protection) }
+    }
+
+    pub fn handle(&self, request: RemoteSignerRequest) -> RemoteSignerResponse {
+        let message = match Message::from_request(&request, &self.signer.public_key()) {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(target: "remote_signer", %err, "malformed request");
+                return RemoteSignerResponse::Error {
+                    message: format!("malformed request: {err}"),
+                };
+            }
+        };
+        match (request, message) {
+            (RemoteSignerRequest::PublicKey, _) => {
+                RemoteSignerResponse::PublicKey { public_key: self.signer.public_key() }
+            }
+            (RemoteSignerRequest::ComputeVrf { data }, _) => {
+                let (value, proof) = self.signer.compute_vrf_with_proof(&data);
+                RemoteSignerResponse::Vrf { value, proof }
+            }
+            (_, Some(Message { data, slashable: Some((kind, height)) })) => {
+                // Keep the lock until the signature is produced, so that two
+                // connections can't race on the same height.
+                let mut slashing_protection = self.slashing_protection.lock().unwrap();
+                if let Err(err) = slashing_protection.check_and_record(kind, height, &data) {
+                    tracing::warn!(target: "remote_signer", %err, "refusing to sign");
+                    return RemoteSignerResponse::Error { message: err.to_string() };
+                }
+                RemoteSignerResponse::Signature { signature: self.signer.sign(&data) }
+            }
+            (_, Some(Message { data, slashable: None })) => {
+                RemoteSignerResponse::Signature { signature: self.signer.sign(&data) }
+            }
+            (_, None) => unreachable!("only public key and VRF requests have no message"),
+        }
+    }
+
+    /// Accepts connections until the listener fails, serving each of them on
+    /// a separate thread.
+    #[cfg(unix)]
+    pub fn serve(
+        self: Arc<Self>,
+        listener: std::os::unix::net::UnixListener,
+    ) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let this = self.clone();
+            std::thread::spawn(move || {
+                if let Err(err) = this.serve_connection(stream) {
+                    tracing::warn!(target: "remote_signer", %err, "connection failed");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn serve_connection(&self, stream: std::os::unix::net::UnixStream) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => self.handle(request),
+                Err(err) => RemoteSignerResponse::Error { message: err.to_string() },
+            };
+            let mut line = serde_json::to_vec(&response)?;
+            line.push(b'\n');
+            writer.write_all(&line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+    use near_primitives::hash::hash;
+    use near_primitives::sharding::{ShardChunkHeader, ShardChunkHeaderInnerV2};
+    use near_primitives::validator_signer::{
+        EmptyValidatorSigner, RemoteSignerError, RemoteValidatorSigner, ValidatorSigner,
+    };
+    use std::time::Duration;
+
+    fn start_signer(dir: &Path, name: &str) -> (InMemorySigner, PathBuf) {
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let slashing_protection = SlashingProtection::open(&dir.join("signed.json")).unwrap();
+        let remote_signer = Arc::new(RemoteSigner::new(signer.clone(), slashing_protection));
+        let socket_path = dir.join(name);
+        let listener = std::os::unix::net::UnixListener::bind(&socket_path).unwrap();
+        std::thread::spawn(move || remote_signer.serve(listener));
+        (signer, socket_path)
+    }
+
+    fn connect(socket_path: PathBuf) -> ValidatorSigner {
+        RemoteValidatorSigner::connect("test".parse().unwrap(), socket_path, Duration::from_secs(5))
+            .unwrap()
+            .into()
+    }
+
+    fn chunk_header_inner(
+        height: BlockHeight,
+        shard_id: ShardId,
+        seed: &[u8],
+    ) -> ShardChunkHeaderInner {
+        ShardChunkHeaderInner::V2(ShardChunkHeaderInnerV2 {
+            prev_block_hash: hash(seed),
+            prev_state_root: Default::default(),
+            prev_outcome_root: Default::default(),
+            encoded_merkle_root: Default::default(),
+            encoded_length: 0,
+            height_created: height,
+            shard_id,
+            prev_gas_used: 0,
+            gas_limit: 0,
+            prev_balance_burnt: 0,
+            prev_outgoing_receipts_root: Default::default(),
+            tx_root: Default::default(),
+            prev_validator_proposals: vec![],
+        })
+    }
+
+    fn assert_refused(err: RemoteSignerError) {
+        assert!(matches!(err, RemoteSignerError::Refused(_)), "{err}");
+    }
+
+    #[test]
+    fn test_sign_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let (signer, socket_path) = start_signer(dir.path(), "signer.sock");
+        let validator_signer = connect(socket_path);
+        assert_eq!(validator_signer.public_key(), signer.public_key());
+
+        let inner = ApprovalInner::Endorsement(hash(b"block"));
+        let signature = validator_signer.sign_approval(&inner, 10).unwrap();
+        assert!(signature.verify(&Approval::get_data_for_sig(&inner, 10), &signer.public_key()));
+        // Retrying the same approval is fine.
+        assert_eq!(validator_signer.sign_approval(&inner, 10).unwrap(), signature);
+
+        let inner = chunk_header_inner(10, 0, b"chunk");
+        let (chunk_hash, signature) = validator_signer.sign_chunk_header(&inner).unwrap();
+        assert_eq!(chunk_hash, ShardChunkHeaderV3::compute_hash(&inner));
+        assert!(signature.verify(chunk_hash.as_ref(), &signer.public_key()));
+
+        let random_value = hash(b"random value");
+        let (value, proof) =
+            validator_signer.compute_vrf_with_proof(random_value.as_ref()).unwrap();
+        assert_eq!((value, proof), signer.compute_vrf_with_proof(random_value.as_ref()));
+        assert_refused(validator_signer.compute_vrf_with_proof(b"vrf").unwrap_err());
+
+        // Legacy chunk headers are signed over their hash only, which the
+        // signer can't check.
+        assert!(matches!(
+            validator_signer.sign_chunk_hash(&hash(b"chunk").into()),
+            Err(RemoteSignerError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_refuse_double_sign() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, socket_path) = start_signer(dir.path(), "signer.sock");
+        let validator_signer = connect(socket_path);
+
+        let endorsement = ApprovalInner::Endorsement(hash(b"block"));
+        let skip = ApprovalInner::Skip(8);
+        validator_signer.sign_approval(&endorsement, 10).unwrap();
+        assert_refused(validator_signer.sign_approval(&skip, 10).unwrap_err());
+
+        // Chunk headers and endorsements are tracked per shard.
+        validator_signer.sign_chunk_header(&chunk_header_inner(10, 0, b"a")).unwrap();
+        validator_signer.sign_chunk_header(&chunk_header_inner(10, 1, b"b")).unwrap();
+        let err = validator_signer.sign_chunk_header(&chunk_header_inner(10, 0, b"c")).unwrap_err();
+        assert_refused(err);
+
+        let chunk_producer: ValidatorSigner = EmptyValidatorSigner::default().into();
+        let endorse = |inner: ShardChunkHeaderInner| {
+            let header = ShardChunkHeaderV3::from_inner(inner, &chunk_producer).unwrap();
+            validator_signer.sign_chunk_endorsement(&ShardChunkHeader::V3(header))
+        };
+        endorse(chunk_header_inner(10, 0, b"a")).unwrap();
+        endorse(chunk_header_inner(10, 0, b"a")).unwrap();
+        assert_refused(endorse(chunk_header_inner(10, 0, b"c")).unwrap_err());
+        endorse(chunk_header_inner(10, 1, b"c")).unwrap();
+
+        // The signed heights survive restarts of the signer.
+        let (_, socket_path) = start_signer(dir.path(), "restarted.sock");
+        let validator_signer = connect(socket_path);
+        assert_refused(validator_signer.sign_approval(&skip, 10).unwrap_err());
+        validator_signer.sign_approval(&skip, 11).unwrap();
+        let err = validator_signer.sign_chunk_header(&chunk_header_inner(10, 0, b"c")).unwrap_err();
+        assert_refused(err);
+    }
+
+    #[test]
+    fn test_refuse_mislabelled_messages() {
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let dir = tempfile::tempdir().unwrap();
+        let slashing_protection =
+            SlashingProtection::open(&dir.path().join("signed.json")).unwrap();
+        let remote_signer = RemoteSigner::new(signer, slashing_protection);
+
+        // A block hash can't be signed as something else.
+        let data = hash(b"block").as_ref().to_vec();
+        for kind in
+            [SignKind::Telemetry, SignKind::ChunkEndorsementMetadata, SignKind::AccountKeyPayload]
+        {
+            let request = RemoteSignerRequest::Sign { kind, data: data.clone() };
+            assert!(matches!(remote_signer.handle(request), RemoteSignerResponse::Error { .. }));
+        }
+        let request = RemoteSignerRequest::SignApproval { inner: vec![7; 40], target_height: 1 };
+        assert!(matches!(remote_signer.handle(request), RemoteSignerResponse::Error { .. }));
+    }
+
+    /// Encodes a length-delimited protobuf field.
+    fn proto_field(number: u8, value: &[u8]) -> Vec<u8> {
+        let mut field = vec![(number << 3) | 2, value.len().try_into().unwrap()];
+        field.extend_from_slice(value);
+        field
+    }
+
+    #[test]
+    fn test_sign_account_key_payload() {
+        let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let dir = tempfile::tempdir().unwrap();
+        let slashing_protection =
+            SlashingProtection::open(&dir.path().join("signed.json")).unwrap();
+        let remote_signer = RemoteSigner::new(signer.clone(), slashing_protection);
+        let sign = |data: Vec<u8>| {
+            remote_signer
+                .handle(RemoteSignerRequest::Sign { kind: SignKind::AccountKeyPayload, data })
+        };
+        let owned_account = |account_key: &PublicKey| {
+            let account_key = proto_field(1, &borsh::to_vec(account_key).unwrap());
+            let peer_id = proto_field(2, &borsh::to_vec(&signer.public_key()).unwrap());
+            proto_field(3, &[account_key, peer_id].concat())
+        };
+
+        let data = owned_account(&signer.public_key());
+        let RemoteSignerResponse::Signature { signature } = sign(data.clone()) else {
+            panic!("AccountKeyPayload not signed");
+        };
+        assert!(signature.verify(&data, &signer.public_key()));
+
+        let other_key = PublicKey::from_seed(KeyType::ED25519, "other");
+        assert!(matches!(sign(owned_account(&other_key)), RemoteSignerResponse::Error { .. }));
+        let mut data = owned_account(&signer.public_key());
+        data.extend(proto_field(2, &[]));
+        assert!(matches!(sign(data), RemoteSignerResponse::Error { .. }));
+    }
+
+    #[test]
+    fn test_prune_history() {
+        let mut heights = SignedHeights::default();
+        let total = SLASHING_PROTECTION_HISTORY as BlockHeight + 5;
+        for height in 0..total {
+            heights
+                .check_and_insert(Slashable::Approval, height, hash(&height.to_le_bytes()))
+                .unwrap();
+        }
+        assert_eq!(heights.min_height, 5);
+        assert_eq!(heights.signed.len(), SLASHING_PROTECTION_HISTORY);
+        let err =
+            heights.check_and_insert(Slashable::Approval, 4, CryptoHash::default()).unwrap_err();
+        assert!(matches!(err, SlashingProtectionError::HeightTooLow { .. }), "{err}");
+    }
+}
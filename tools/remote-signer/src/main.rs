@@ -0,0 +1,50 @@
+use anyhow::Context;
+use clap::Parser;
+use near_crypto::InMemorySigner;
+use near_remote_signer::{RemoteSigner, SlashingProtection};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Holds a validator key and signs messages for the node connected over a
+/// Unix socket, refusing to sign two different block headers, chunk headers,
+/// approvals or chunk endorsements at the same height.
+#[derive(clap::Parser)]
+struct Cli {
+    /// Validator key file in the format of `validator_key.json`.
+    #[clap(long)]
+    key_file: PathBuf,
+    /// Unix socket to listen on.
+    #[clap(long)]
+    socket: PathBuf,
+    /// File recording the heights at which slashable messages were signed.
+    /// It must be preserved across restarts and never shared between keys.
+    #[clap(long)]
+    slashing_protection_file: PathBuf,
+}
+
+#[cfg(unix)]
+fn main() -> anyhow::Result<()> {
+    let env_filter = near_o11y::EnvFilterBuilder::from_env().finish().unwrap();
+    let _subscriber = near_o11y::default_subscriber(env_filter, &Default::default()).global();
+
+    let cli = Cli::parse();
+    let signer = InMemorySigner::from_file(&cli.key_file)
+        .with_context(|| format!("failed to read key from {}", cli.key_file.display()))?;
+    let slashing_protection = SlashingProtection::open(&cli.slashing_protection_file)?;
+    let listener = std::os::unix::net::UnixListener::bind(&cli.socket)
+        .with_context(|| format!("failed to listen on {}", cli.socket.display()))?;
+    tracing::info!(
+        target: "remote_signer",
+        account_id = %signer.account_id,
+        public_key = %signer.public_key(),
+        socket = %cli.socket.display(),
+        "serving signing requests"
+    );
+    Arc::new(RemoteSigner::new(signer, slashing_protection)).serve(listener)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn main() -> anyhow::Result<()> {
+    anyhow::bail!("remote signer requires Unix sockets")
+}
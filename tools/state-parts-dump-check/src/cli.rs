@@ -5,6 +5,8 @@ use near_client::sync::external::{
     create_bucket_readonly, external_storage_location, external_storage_location_directory,
     get_num_parts_from_filename, ExternalConnection, StateFileType,
 };
+use near_client::sync::manifest::{download_manifest, StatePartsManifest};
+use near_crypto::PublicKey;
 use near_jsonrpc::client::{new_client, JsonRpcClient};
 use near_primitives::hash::CryptoHash;
 use near_primitives::state_part::PartId;
@@ -41,6 +43,11 @@ pub struct StatePartsDumpCheckCommand {
     // the gcs bucket to use when retrieving state parts from GCP
     #[clap(long)]
     gcs_bucket: Option<String>,
+    // public key trusted to sign manifests of the dumped parts, can be repeated.
+    // If set, a manifest signed by one of the keys is required and parts and headers are checked against it.
+    // Otherwise parts and headers are checked against the manifest only if one is dumped.
+    #[clap(long = "manifest-signer")]
+    manifest_signers: Vec<PublicKey>,
     // this can be either loop-check or single-check
     #[clap(subcommand)]
     subcmd: StatePartsDumpCheckSubCommand,
@@ -87,6 +94,7 @@ impl StatePartsDumpCheckCommand {
             self.s3_bucket.clone(),
             self.s3_region.clone(),
             self.gcs_bucket.clone(),
+            self.manifest_signers.clone(),
         )
     }
 }
@@ -99,13 +107,14 @@ impl StatePartsDumpCheckSubCommand {
         s3_bucket: Option<String>,
         s3_region: Option<String>,
         gcs_bucket: Option<String>,
+        manifest_signers: Vec<PublicKey>,
     ) -> anyhow::Result<()> {
         match self {
             StatePartsDumpCheckSubCommand::SingleCheck(cmd) => {
-                cmd.run(chain_id, root_dir, s3_bucket, s3_region, gcs_bucket)
+                cmd.run(chain_id, root_dir, s3_bucket, s3_region, gcs_bucket, manifest_signers)
             }
             StatePartsDumpCheckSubCommand::LoopCheck(cmd) => {
-                cmd.run(chain_id, root_dir, s3_bucket, s3_region, gcs_bucket)
+                cmd.run(chain_id, root_dir, s3_bucket, s3_region, gcs_bucket, manifest_signers)
             }
         }
     }
@@ -119,6 +128,7 @@ impl SingleCheckCommand {
         s3_bucket: Option<String>,
         s3_region: Option<String>,
         gcs_bucket: Option<String>,
+        manifest_signers: Vec<PublicKey>,
     ) -> anyhow::Result<()> {
         let sys = actix::System::new();
         sys.block_on(async move {
@@ -133,6 +143,7 @@ impl SingleCheckCommand {
                 s3_bucket,
                 s3_region,
                 gcs_bucket,
+                manifest_signers,
             )
             .await;
         });
@@ -152,6 +163,7 @@ impl LoopCheckCommand {
         s3_bucket: Option<String>,
         s3_region: Option<String>,
         gcs_bucket: Option<String>,
+        manifest_signers: Vec<PublicKey>,
     ) -> anyhow::Result<()> {
         let rpc_server_addr = match &self.rpc_server_addr {
             None => {
@@ -174,6 +186,7 @@ impl LoopCheckCommand {
             s3_bucket,
             s3_region,
             gcs_bucket,
+            manifest_signers,
             &rpc_client,
             &self.prometheus_addr,
             self.interval,
@@ -276,6 +289,7 @@ fn run_loop_all_shards(
     s3_bucket: Option<String>,
     s3_region: Option<String>,
     gcs_bucket: Option<String>,
+    manifest_signers: Vec<PublicKey>,
     rpc_client: &JsonRpcClient,
     prometheus_addr: &str,
     loop_interval: u64,
@@ -367,6 +381,7 @@ fn run_loop_all_shards(
             let s3_bucket = s3_bucket.clone();
             let s3_region = s3_region.clone();
             let gcs_bucket = gcs_bucket.clone();
+            let manifest_signers = manifest_signers.clone();
             let old_status = status.as_ref().ok().cloned();
             let new_status = sys.block_on(async move {
                 if !is_prometheus_server_up {
@@ -395,6 +410,7 @@ fn run_loop_all_shards(
                     s3_bucket,
                     s3_region,
                     gcs_bucket,
+                    manifest_signers,
                 )
                 .await
             });
@@ -418,6 +434,9 @@ fn reset_num_parts_metrics(chain_id: &str, shard_id: ShardId) -> () {
     crate::metrics::STATE_SYNC_DUMP_CHECK_NUM_HEADERS_INVALID
         .with_label_values(&[&shard_id.to_string(), chain_id])
         .set(0);
+    crate::metrics::STATE_SYNC_DUMP_CHECK_MANIFEST_VALID
+        .with_label_values(&[&shard_id.to_string(), chain_id])
+        .set(0);
 }
 
 async fn run_single_check_with_3_retries(
@@ -431,6 +450,7 @@ async fn run_single_check_with_3_retries(
     s3_bucket: Option<String>,
     s3_region: Option<String>,
     gcs_bucket: Option<String>,
+    manifest_signers: Vec<PublicKey>,
 ) -> anyhow::Result<StatePartsDumpCheckStatus> {
     let mut retries = 0;
     let mut res;
@@ -451,6 +471,7 @@ async fn run_single_check_with_3_retries(
             s3_bucket,
             s3_region,
             gcs_bucket,
+            &manifest_signers,
         )
         .await;
         match res {
@@ -484,6 +505,7 @@ async fn check_parts(
     shard_id: ShardId,
     state_root: StateRoot,
     external: &ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<bool> {
     let directory_path = external_storage_location_directory(
         &chain_id,
@@ -555,6 +577,7 @@ async fn check_parts(
         let chain_id = chain_id.clone();
         let external = external.clone();
         let epoch_id = *epoch_id;
+        let manifest = manifest.clone();
         let handle = tokio::spawn(async move {
             process_part_with_3_retries(
                 part_id,
@@ -565,6 +588,7 @@ async fn check_parts(
                 state_root,
                 num_parts,
                 external,
+                manifest,
             )
             .await
         });
@@ -587,6 +611,7 @@ async fn check_headers(
     epoch_height: u64,
     shard_id: ShardId,
     external: &ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<bool> {
     let directory_path = external_storage_location_directory(
         &chain_id,
@@ -614,13 +639,51 @@ async fn check_headers(
     let chain_id = chain_id.clone();
     let external = external.clone();
 
-    process_header_with_3_retries(chain_id, *epoch_id, epoch_height, shard_id, external).await?;
+    process_header_with_3_retries(chain_id, *epoch_id, epoch_height, shard_id, external, manifest)
+        .await?;
 
     let duration = start.elapsed();
     tracing::info!("Time elapsed in downloading and validating the header is: {:?}", duration);
     Ok(true)
 }
 
+// download and verify the manifest of state parts for a single epoch and shard.
+// Returns None if no manifest is dumped and none is required.
+async fn check_manifest(
+    chain_id: &String,
+    epoch_id: &EpochId,
+    epoch_height: u64,
+    shard_id: ShardId,
+    state_root: StateRoot,
+    external: &ExternalConnection,
+    manifest_signers: &[PublicKey],
+) -> anyhow::Result<Option<StatePartsManifest>> {
+    if !external.is_manifest_stored_for_epoch(shard_id, chain_id, epoch_id, epoch_height).await? {
+        if manifest_signers.is_empty() {
+            return Ok(None);
+        }
+        anyhow::bail!("manifest is not dumped yet");
+    }
+    let result =
+        download_manifest(external, chain_id, epoch_id, epoch_height, shard_id, manifest_signers)
+            .await
+            .and_then(|manifest| {
+                anyhow::ensure!(
+                    &manifest.chain_id == chain_id
+                        && &manifest.epoch_id == epoch_id
+                        && manifest.epoch_height == epoch_height
+                        && manifest.shard_id == shard_id
+                        && manifest.state_root == state_root,
+                    "manifest is for another epoch, shard or state root"
+                );
+                Ok(manifest)
+            });
+    crate::metrics::STATE_SYNC_DUMP_CHECK_MANIFEST_VALID
+        .with_label_values(&[&shard_id.to_string(), &chain_id.to_string()])
+        .set(result.is_ok() as i64);
+    result.map(Some)
+}
+
 async fn run_single_check(
     status: Option<StatePartsDumpCheckStatus>,
     chain_id: String,
@@ -632,6 +695,7 @@ async fn run_single_check(
     s3_bucket: Option<String>,
     s3_region: Option<String>,
     gcs_bucket: Option<String>,
+    manifest_signers: &[PublicKey],
 ) -> anyhow::Result<StatePartsDumpCheckStatus> {
     tracing::info!(
         current_epoch_height,
@@ -671,14 +735,53 @@ async fn run_single_check(
         None => (false, false),
     };
 
-    parts_done = parts_done
-        || check_parts(&chain_id, &epoch_id, current_epoch_height, shard_id, state_root, &external)
+    if !parts_done || !headers_done {
+        let manifest = match check_manifest(
+            &chain_id,
+            &epoch_id,
+            current_epoch_height,
+            shard_id,
+            state_root,
+            &external,
+            manifest_signers,
+        )
+        .await
+        {
+            Ok(manifest) => manifest.map(Arc::new),
+            Err(err) => {
+                // Parts and headers are not checked until there is a valid manifest to check them against.
+                tracing::info!(shard_id, current_epoch_height, ?err, "Manifest check failed.");
+                return Ok(StatePartsDumpCheckStatus::Waiting {
+                    epoch_height: current_epoch_height,
+                    parts_done,
+                    headers_done,
+                });
+            }
+        };
+        parts_done = parts_done
+            || check_parts(
+                &chain_id,
+                &epoch_id,
+                current_epoch_height,
+                shard_id,
+                state_root,
+                &external,
+                manifest.clone(),
+            )
             .await
             .unwrap_or(false);
-    headers_done = headers_done
-        || check_headers(&chain_id, &epoch_id, current_epoch_height, shard_id, &external)
+        headers_done = headers_done
+            || check_headers(
+                &chain_id,
+                &epoch_id,
+                current_epoch_height,
+                shard_id,
+                &external,
+                manifest,
+            )
             .await
             .unwrap_or(false);
+    }
     if !parts_done || !headers_done {
         Ok(StatePartsDumpCheckStatus::Waiting {
             epoch_height: current_epoch_height,
@@ -699,6 +802,7 @@ async fn process_part_with_3_retries(
     state_root: StateRoot,
     num_parts: u64,
     external: ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<()> {
     let mut retries = 0;
     let mut res;
@@ -721,6 +825,7 @@ async fn process_part_with_3_retries(
                 state_root,
                 num_parts,
                 external,
+                manifest.clone(),
             ),
         )
         .await;
@@ -754,6 +859,7 @@ async fn process_header_with_3_retries(
     epoch_height: u64,
     shard_id: ShardId,
     external: ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<()> {
     let mut retries = 0;
     let mut res: Result<Result<(), anyhow::Error>, tokio::time::error::Elapsed>;
@@ -763,7 +869,7 @@ async fn process_header_with_3_retries(
         let timeout_duration = tokio::time::Duration::from_secs(60);
         res = timeout(
             timeout_duration,
-            process_header(chain_id, epoch_id, epoch_height, shard_id, external),
+            process_header(chain_id, epoch_id, epoch_height, shard_id, external, manifest.clone()),
         )
         .await;
         match res {
@@ -799,13 +905,22 @@ async fn process_part(
     state_root: StateRoot,
     num_parts: u64,
     external: ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<()> {
     tracing::info!(part_id, "process_part started.");
     let file_type = StateFileType::StatePart { part_id, num_parts };
     let location =
         external_storage_location(&chain_id, &epoch_id, epoch_height, shard_id, &file_type);
     let part = external.get_file(shard_id, &location, &file_type).await?;
-    let is_part_valid = validate_state_part(&state_root, PartId::new(part_id, num_parts), &part);
+    let matches_manifest = match manifest.map(|manifest| manifest.check_part(part_id, &part)) {
+        Some(Err(err)) => {
+            tracing::error!(target: "state-parts", ?err, "State part doesn't match the manifest");
+            false
+        }
+        _ => true,
+    };
+    let is_part_valid = matches_manifest
+        && validate_state_part(&state_root, PartId::new(part_id, num_parts), &part);
     if is_part_valid {
         crate::metrics::STATE_SYNC_DUMP_CHECK_NUM_PARTS_VALID
             .with_label_values(&[&shard_id.to_string(), &chain_id.to_string()])
@@ -826,6 +941,7 @@ async fn process_header(
     epoch_height: u64,
     shard_id: ShardId,
     external: ExternalConnection,
+    manifest: Option<Arc<StatePartsManifest>>,
 ) -> anyhow::Result<()> {
     tracing::info!("process_header started.");
     let file_type = StateFileType::StateHeader;
@@ -833,7 +949,14 @@ async fn process_header(
         external_storage_location(&chain_id, &epoch_id, epoch_height, shard_id, &file_type);
     let header = external.get_file(shard_id, &location, &file_type).await?;
 
-    if validate_state_header(&header) {
+    let matches_manifest = match manifest.map(|manifest| manifest.check_header(&header)) {
+        Some(Err(err)) => {
+            tracing::error!(target: "state-parts", ?err, "Header doesn't match the manifest");
+            false
+        }
+        _ => true,
+    };
+    if matches_manifest && validate_state_header(&header) {
         crate::metrics::STATE_SYNC_DUMP_CHECK_NUM_HEADERS_VALID
             .with_label_values(&[&shard_id.to_string(), &chain_id.to_string()])
             .inc();
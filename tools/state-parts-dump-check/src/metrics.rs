@@ -31,6 +31,16 @@ pub(crate) static STATE_SYNC_DUMP_CHECK_NUM_HEADERS_DUMPED: LazyLock<IntGaugeVec
         .unwrap()
     });
 
+pub(crate) static STATE_SYNC_DUMP_CHECK_MANIFEST_VALID: LazyLock<IntGaugeVec> =
+    LazyLock::new(|| {
+        try_create_int_gauge_vec(
+            "near_state_sync_dump_check_manifest_valid",
+            "Whether the manifest of state parts dumped for the epoch is present and valid",
+            &["shard_id", "chain_id"],
+        )
+        .unwrap()
+    });
+
 pub(crate) static STATE_SYNC_DUMP_CHECK_NUM_PARTS_VALID: LazyLock<IntGaugeVec> =
     LazyLock::new(|| {
         try_create_int_gauge_vec(
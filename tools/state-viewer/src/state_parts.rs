@@ -1,6 +1,7 @@
 use crate::epoch_info::iterate_and_filter;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode};
+use near_chain_configs::StateFileCompression;
 use near_client::sync::external::{
     create_bucket_readonly, create_bucket_readwrite, external_storage_location,
     external_storage_location_directory, get_num_parts_from_filename, ExternalConnection,
@@ -474,7 +475,13 @@ async fn dump_state_parts(
         let location =
             external_storage_location(&chain_id, &epoch_id, epoch_height, shard_id, &file_type);
         external
-            .put_file(file_type, &state_sync_header_buf, shard_id, &location)
+            .put_file(
+                file_type,
+                &state_sync_header_buf,
+                shard_id,
+                &location,
+                StateFileCompression::None,
+            )
             .await
             .expect("Failed to put header into external storage.");
         tracing::info!(target: "state-parts", elapsed_sec = timer.elapsed().as_secs_f64(), "Header saved to external storage.");
@@ -502,7 +509,10 @@ async fn dump_state_parts(
             shard_id,
             &file_type,
         );
-        external.put_file(file_type, &state_part, shard_id, &location).await.unwrap();
+        external
+            .put_file(file_type, &state_part, shard_id, &location, StateFileCompression::None)
+            .await
+            .unwrap();
         // part_storage.write(&state_part, part_id, num_parts);
         let elapsed_sec = timer.elapsed().as_secs_f64();
         let first_state_record = get_first_state_record(&state_root, &state_part);